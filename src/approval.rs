@@ -0,0 +1,165 @@
+//! Approval sheets: a month's entries for one client, sent for sign-off before
+//! they are invoiced. The PDF ends with a block for the client to sign; the CSV
+//! is for clients who would rather check the hours in a spreadsheet. Which
+//! entries are on a sheet and whether it was approved is kept in the database
+//! (see `Db::create_approval_sheet`).
+
+use chrono::{Local, TimeZone};
+use genpdf::elements::{Break, Paragraph, TableLayout};
+use genpdf::style::Style;
+use genpdf::{Document, Element, Margins, Mm, SimplePageDecorator};
+use std::fs;
+use std::io;
+
+use crate::billing;
+use crate::invoice::{dir_file, load_font_family, pdf_text};
+use crate::models::{Client, DEFAULT_FONT_SIZE, Entry, InvoiceSettings, PaperSize};
+
+/// Where sheet `id` is written in `invoice_dir`
+pub fn sheet_file(invoice_dir: &str, id: i64, csv: bool) -> String {
+    let ext = if csv { "csv" } else { "pdf" };
+    dir_file(invoice_dir, &format!("approval_{:04}.{}", id, ext))
+}
+
+/// The sheet as CSV, one row per finished entry with local start and end times
+pub fn sheet_csv(entries: &[&Entry]) -> String {
+    let mut out = String::from("entry_id,date,start,end,project,description,hours\n");
+    for entry in entries {
+        let (Some(end), Some(hours)) = (entry.end, billing::entry_hours(entry)) else {
+            continue;
+        };
+        let start = Local.from_utc_datetime(&entry.start.naive_utc());
+        let end = Local.from_utc_datetime(&end.naive_utc());
+        let (start_time, end_time) = if entry.days.is_some() {
+            (String::new(), String::new())
+        } else {
+            (
+                start.format("%H:%M").to_string(),
+                end.format("%H:%M").to_string(),
+            )
+        };
+        out.push_str(&format!(
+            "{},{},{},{},{},{},{:.2}\n",
+            entry.id,
+            start.format("%Y-%m-%d"),
+            start_time,
+            end_time,
+            csv_field(&entry.project),
+            csv_field(&entry.description),
+            hours
+        ));
+    }
+    out
+}
+
+/// Write the sheet's PDF to `path`: who it is from and for, the entries by
+/// date, the total, and lines for the client's name, date and signature
+pub fn write_pdf(
+    path: &str,
+    title: &str,
+    entries: &[&Entry],
+    client: Option<&Client>,
+    settings: &InvoiceSettings,
+) -> io::Result<()> {
+    let mut doc = Document::new(load_font_family()?);
+    doc.set_title(title);
+    doc.set_paper_size(match settings.paper_size {
+        PaperSize::A4 => genpdf::PaperSize::A4,
+        PaperSize::Letter => genpdf::PaperSize::Letter,
+        PaperSize::Legal => genpdf::PaperSize::Legal,
+    });
+    let base_size = if (6..=24).contains(&settings.font_size) {
+        settings.font_size
+    } else {
+        DEFAULT_FONT_SIZE
+    };
+    let size = |pt: i64| ((pt * base_size + 5) / 10) as u8;
+    doc.set_font_size(size(10));
+    let mut decorator = SimplePageDecorator::new();
+    decorator.set_margins(Margins::from(Mm::from(settings.margin_mm.clamp(0.0, 50.0))));
+    doc.set_page_decorator(decorator);
+
+    let normal_style = Style::new().with_font_size(size(10));
+    let bold_style = Style::new().bold().with_font_size(size(10));
+    let cell_style = Style::new().with_font_size(size(8));
+    let header_style = Style::new().bold().with_font_size(size(9));
+
+    doc.push(Paragraph::new(title).styled(Style::new().bold().with_font_size(size(20))));
+    doc.push(Break::new(1.0));
+    if !settings.business_name.is_empty() {
+        doc.push(
+            Paragraph::new(format!("From: {}", pdf_text(&settings.business_name)))
+                .styled(normal_style),
+        );
+    }
+    if let Some(client) = client {
+        doc.push(Paragraph::new(format!("For: {}", pdf_text(&client.name))).styled(normal_style));
+    }
+    doc.push(Break::new(1.0));
+
+    let mut table = TableLayout::new(vec![2, 2, 3, 6, 1]);
+    table.set_cell_decorator(genpdf::elements::FrameCellDecorator::new(
+        false, false, false,
+    ));
+    let mut header_row = table.row();
+    for heading in ["Date", "Time", "Project", "Description", "Hours"] {
+        header_row.push_element(Paragraph::new(heading).styled(header_style));
+    }
+    header_row.push().expect("Failed to push header row");
+    let mut total = 0.0;
+    for entry in entries {
+        let (Some(end), Some(hours)) = (entry.end, billing::entry_hours(entry)) else {
+            continue;
+        };
+        let start = Local.from_utc_datetime(&entry.start.naive_utc());
+        let end = Local.from_utc_datetime(&end.naive_utc());
+        let time = if entry.days.is_some() {
+            String::new()
+        } else {
+            format!("{}-{}", start.format("%H:%M"), end.format("%H:%M"))
+        };
+        let description = entry.description.lines().next().unwrap_or_default();
+        let mut row = table.row();
+        row.push_element(Paragraph::new(start.format("%Y-%m-%d").to_string()).styled(cell_style));
+        row.push_element(Paragraph::new(time).styled(cell_style));
+        row.push_element(Paragraph::new(pdf_text(&entry.project)).styled(cell_style));
+        row.push_element(Paragraph::new(pdf_text(description)).styled(cell_style));
+        row.push_element(Paragraph::new(format!("{:.2}", hours)).styled(cell_style));
+        row.push().expect("Failed to push row");
+        total += hours;
+    }
+    doc.push(table);
+    doc.push(Break::new(0.5));
+    doc.push(Paragraph::new(format!("Total: {:.2} hours", total)).styled(bold_style));
+
+    // Sign-off block
+    doc.push(Break::new(2.0));
+    doc.push(
+        Paragraph::new("I confirm the hours above are correct and may be invoiced.")
+            .styled(normal_style),
+    );
+    for label in ["Approved by", "Date", "Signature"] {
+        doc.push(Break::new(1.5));
+        doc.push(
+            Paragraph::new(format!("{}: ________________________________", label))
+                .styled(normal_style),
+        );
+    }
+
+    doc.render_to_file(path)
+        .map_err(|e| io::Error::other(e.to_string()))
+}
+
+/// Write the sheet's CSV to `path`
+pub fn write_csv(path: &str, entries: &[&Entry]) -> io::Result<()> {
+    fs::write(path, sheet_csv(entries))
+}
+
+/// Quote a CSV field if it holds a comma, quote or line break
+pub fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
@@ -0,0 +1,98 @@
+//! Files kept with expenses and invoices, usually receipts. Each file is copied
+//! into the data folder's `attachments` (see `paths`) so it stays around when the original is moved;
+//! the database records what it belongs to (see `Db::add_attachment`).
+
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+
+use chrono::Utc;
+use zip::write::SimpleFileOptions;
+
+use crate::models::Attachment;
+use crate::paths;
+
+/// Where stored copies are kept, created if needed
+pub fn attachments_dir() -> io::Result<PathBuf> {
+    let dir = paths::attachments_dir();
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+/// Copy `source` into the attachments directory. Returns the original file
+/// name and the path of the copy, which is prefixed with the time it was added
+/// so files with the same name don't overwrite each other.
+pub fn store(source: &Path) -> io::Result<(String, PathBuf)> {
+    let name = source
+        .file_name()
+        .and_then(|n| n.to_str())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "not a file name"))?
+        .to_string();
+    if !source.is_file() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{} is not a file", source.display()),
+        ));
+    }
+    let dir = attachments_dir()?;
+    let stamp = Utc::now().format("%Y%m%d%H%M%S");
+    let mut dest = dir.join(format!("{}-{}", stamp, name));
+    let mut n = 2;
+    while dest.exists() {
+        dest = dir.join(format!("{}-{}-{}", stamp, n, name));
+        n += 1;
+    }
+    fs::copy(source, &dest)?;
+    Ok((name, dest))
+}
+
+/// Open `path` with the system's default application
+pub fn open(path: &Path) -> io::Result<()> {
+    let mut command = if cfg!(target_os = "macos") {
+        process::Command::new("open")
+    } else if cfg!(windows) {
+        let mut command = process::Command::new("cmd");
+        command.args(["/C", "start", ""]);
+        command
+    } else {
+        process::Command::new("xdg-open")
+    };
+    command
+        .arg(path)
+        .stdout(process::Stdio::null())
+        .stderr(process::Stdio::null())
+        .spawn()
+        .map(|_| ())
+}
+
+/// Write a zip holding the invoice PDF at `pdf` and its `attachments` to
+/// `dest`. Receipts go in a `receipts/` folder, named `{id}-{name}` so two
+/// with the same name both fit. Returns how many attachments were missing on
+/// disk and left out.
+pub fn bundle(pdf: &Path, attachments: &[Attachment], dest: &Path) -> io::Result<usize> {
+    let mut zip = zip::ZipWriter::new(fs::File::create(dest)?);
+    let options = SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+
+    let pdf_name = pdf
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("invoice.pdf");
+    zip.start_file(pdf_name, options)?;
+    zip.write_all(&fs::read(pdf)?)?;
+
+    let mut missing = 0;
+    for attachment in attachments {
+        let Ok(data) = fs::read(&attachment.path) else {
+            missing += 1;
+            continue;
+        };
+        zip.start_file(
+            format!("receipts/{}-{}", attachment.id, attachment.name),
+            options,
+        )?;
+        zip.write_all(&data)?;
+    }
+    zip.finish()?;
+    Ok(missing)
+}
@@ -0,0 +1,329 @@
+//! Invoice arithmetic: hours, days worked, project sections, amounts, tax and
+//! totals.
+//!
+//! Everything here is a pure function of entries, rates and percentages, and
+//! `invoice::bill` puts it together for one invoice, so the PDF, the CLI and
+//! the TUI preview all come to the same numbers. Amounts are
+//! `Money` and are rounded to the cent at each step that produces one: once per
+//! project amount, once for the tax, and never again when adding them up.
+
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, NaiveTime, TimeZone, Weekday};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::models::{
+    BillingMode, Entry, Money, OvertimeKind, OvertimeRates, SectionOrder, TimeOff,
+};
+
+/// Hours in a finished entry, or `None` while it is running
+pub fn entry_hours(entry: &Entry) -> Option<f64> {
+    entry
+        .end
+        .map(|end| (end - entry.start).num_seconds() as f64 / 3600.0)
+}
+
+/// Hours across the finished entries
+pub fn total_hours(entries: &[&Entry]) -> f64 {
+    entries.iter().filter_map(|e| entry_hours(e)).sum()
+}
+
+/// Days worked: distinct local days with a finished timed entry, plus the days
+/// logged by day entries
+pub fn worked_days(entries: &[&Entry]) -> f64 {
+    let timed_days = entries
+        .iter()
+        .filter(|e| e.end.is_some() && e.days.is_none())
+        .map(|e| e.start.with_timezone(&Local).date_naive())
+        .collect::<BTreeSet<_>>()
+        .len();
+    timed_days as f64 + entries.iter().filter_map(|e| e.days).sum::<f64>()
+}
+
+/// Amount billed for one project's entries at `rate`: hours × rate, days worked
+/// × rate, or the flat fee, depending on `mode`. Rounded to the cent.
+pub fn amount(mode: BillingMode, rate: Money, entries: &[&Entry]) -> Money {
+    mode.amount(rate, total_hours(entries), worked_days(entries))
+}
+
+/// Project rate information for invoice calculations
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ProjectRate {
+    pub rate: Money,
+    pub currency: String,
+    #[serde(default)]
+    pub billing_mode: BillingMode,
+}
+
+impl ProjectRate {
+    /// Amount billed for a project's entries: hours × rate, days worked × rate,
+    /// or the flat fee, depending on the billing mode
+    pub fn amount(&self, entries: &[&Entry]) -> Money {
+        amount(self.billing_mode, self.rate, entries)
+    }
+}
+
+/// Group entries into one section per project, in `order`, with each section's
+/// entries oldest first
+pub fn project_sections<'a>(
+    entries: impl IntoIterator<Item = &'a Entry>,
+    order: SectionOrder,
+) -> Vec<(String, Vec<&'a Entry>)> {
+    let mut sections: Vec<(String, Vec<&Entry>)> = Vec::new();
+    for entry in entries {
+        match sections.iter_mut().find(|(p, _)| *p == entry.project) {
+            Some((_, list)) => list.push(entry),
+            None => sections.push((entry.project.clone(), vec![entry])),
+        }
+    }
+    for (_, list) in &mut sections {
+        list.sort_by_key(|e| (e.start, e.id));
+    }
+    sections.sort_by(|(a, a_entries), (b, b_entries)| {
+        let by_name = a.to_lowercase().cmp(&b.to_lowercase()).then(a.cmp(b));
+        match order {
+            SectionOrder::Alphabetical => by_name,
+            SectionOrder::Hours => total_hours(b_entries)
+                .total_cmp(&total_hours(a_entries))
+                .then(by_name),
+            SectionOrder::Date => a_entries[0].start.cmp(&b_entries[0].start).then(by_name),
+        }
+    });
+    sections
+}
+
+/// Currency the invoice totals are written in: the currency of its rated projects,
+/// or `$` when none are rated
+pub fn invoice_currency<'a>(mut rates: impl Iterator<Item = &'a ProjectRate>) -> String {
+    rates
+        .next()
+        .map(|r| r.currency.clone())
+        .unwrap_or_else(|| "$".to_string())
+}
+
+/// Subtotal, tax and total of an invoice
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Totals {
+    pub subtotal: Money,
+    pub tax_amount: Money,
+    pub total: Money,
+}
+
+impl Totals {
+    /// Totals for project amounts adding up to `subtotal`, with `tax_rate`
+    /// percent tax rounded to the `decimals` the currency is written with (2
+    /// for cents, 0 for yen), so it is the figure the invoice prints. The total
+    /// is exactly subtotal + tax.
+    pub fn new(subtotal: Money, tax_rate: f64, decimals: usize) -> Self {
+        let tax_amount = subtotal.percent(tax_rate).round_to(decimals);
+        Totals {
+            subtotal,
+            tax_amount,
+            total: subtotal + tax_amount,
+        }
+    }
+
+    /// Totals for a list of already rounded project amounts
+    pub fn from_amounts(
+        amounts: impl IntoIterator<Item = Money>,
+        tax_rate: f64,
+        decimals: usize,
+    ) -> Self {
+        let subtotal = amounts.into_iter().fold(Money::ZERO, |sum, a| sum + a);
+        Self::new(subtotal, tax_rate, decimals)
+    }
+
+    /// Totals for project amounts that already include `tax_rate` percent tax
+    /// and add up to `total`. The tax in it is rounded like `new`'s and the
+    /// subtotal is what is left, so the total is still exactly subtotal + tax.
+    pub fn tax_inclusive(total: Money, tax_rate: f64, decimals: usize) -> Self {
+        let tax_amount = total
+            .times(tax_rate / (100.0 + tax_rate))
+            .round_to(decimals);
+        Totals {
+            subtotal: Money::from_cents(total.cents() - tax_amount.cents()),
+            tax_amount,
+            total,
+        }
+    }
+
+    /// Totals for a list of already rounded, tax-inclusive project amounts
+    pub fn from_inclusive_amounts(
+        amounts: impl IntoIterator<Item = Money>,
+        tax_rate: f64,
+        decimals: usize,
+    ) -> Self {
+        let total = amounts.into_iter().fold(Money::ZERO, |sum, a| sum + a);
+        Self::tax_inclusive(total, tax_rate, decimals)
+    }
+}
+
+/// The entries with each finished timed entry's duration rounded up to a whole
+/// number of `minutes`, by moving its end. Day entries, running entries and
+/// `minutes` of 0 or less leave entries as they are.
+pub fn rounded_entries(entries: &[Entry], minutes: i64) -> Vec<Entry> {
+    let step = minutes * 60;
+    entries
+        .iter()
+        .cloned()
+        .map(|mut entry| {
+            if let (Some(end), None, true) = (entry.end, entry.days, step > 0) {
+                let secs = (end - entry.start).num_seconds().max(0);
+                let rounded = (secs + step - 1) / step * step;
+                entry.end = Some(entry.start + chrono::Duration::seconds(rounded));
+            }
+            entry
+        })
+        .collect()
+}
+
+/// Hours billed at an overtime multiplier
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OvertimeHours {
+    pub kind: OvertimeKind,
+    pub multiplier: f64,
+    pub hours: f64,
+}
+
+/// Hours of the finished timed entries that `rates` bill at a multiplier, by
+/// kind. Spans are split at local midnight and at the edges of the evening
+/// window; time that counts as more than one kind (a Saturday evening) goes to
+/// the highest multiplier. Day entries have no times and are left out.
+pub fn overtime_hours(
+    entries: &[&Entry],
+    rates: &OvertimeRates,
+    is_holiday: impl Fn(NaiveDate) -> bool,
+) -> Vec<OvertimeHours> {
+    let mut by_kind: BTreeMap<OvertimeKind, (f64, f64)> = BTreeMap::new();
+    for entry in entries.iter().filter(|e| e.days.is_none()) {
+        let Some(end) = entry.end else { continue };
+        let end = end.with_timezone(&Local);
+        let mut t = entry.start.with_timezone(&Local);
+        while t < end {
+            let next = next_overtime_boundary(t, rates).min(end);
+            let day = t.date_naive();
+            let weekend = matches!(day.weekday(), Weekday::Sat | Weekday::Sun);
+            let applies = [
+                (
+                    OvertimeKind::Holiday,
+                    rates.holiday.filter(|_| is_holiday(day)),
+                ),
+                (OvertimeKind::Weekend, rates.weekend.filter(|_| weekend)),
+                (
+                    OvertimeKind::Evening,
+                    rates.evening.filter(|_| rates.is_evening(t.time())),
+                ),
+            ];
+            let highest = applies
+                .into_iter()
+                .filter_map(|(kind, multiplier)| Some((kind, multiplier?)))
+                .max_by(|a, b| a.1.total_cmp(&b.1));
+            if let Some((kind, multiplier)) = highest {
+                let hours = (next - t).num_seconds() as f64 / 3600.0;
+                by_kind.entry(kind).or_insert((multiplier, 0.0)).1 += hours;
+            }
+            t = next;
+        }
+    }
+    by_kind
+        .into_iter()
+        .map(|(kind, (multiplier, hours))| OvertimeHours {
+            kind,
+            multiplier,
+            hours,
+        })
+        .collect()
+}
+
+/// The first local midnight, evening start or evening end after `t`
+fn next_overtime_boundary(t: DateTime<Local>, rates: &OvertimeRates) -> DateTime<Local> {
+    let day = t.date_naive();
+    let at = |date: NaiveDate, time: NaiveTime| {
+        Local.from_local_datetime(&date.and_time(time)).earliest()
+    };
+    [
+        day.succ_opt().and_then(|d| at(d, NaiveTime::MIN)),
+        at(day, rates.evening_start),
+        at(day, rates.evening_end),
+    ]
+    .into_iter()
+    .flatten()
+    .filter(|boundary| *boundary > t)
+    .min()
+    .unwrap_or(t + Duration::hours(1))
+}
+
+/// Amount for `hours` of hourly work at `rate`, with the `overtime` hours among
+/// them at the rate times their multiplier. Each part is rounded to the cent.
+pub fn amount_with_overtime(rate: Money, hours: f64, overtime: &[OvertimeHours]) -> Money {
+    let overtime_hours: f64 = overtime.iter().map(|o| o.hours).sum();
+    overtime
+        .iter()
+        .fold(rate.times(hours - overtime_hours), |sum, o| {
+            sum + rate.times(o.multiplier).times(o.hours)
+        })
+}
+
+/// One project's line on an invoice
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProjectLine {
+    /// Hours of the finished entries
+    pub hours: f64,
+    /// What the line bills, rounded to the currency's decimals
+    pub amount: Money,
+    /// The hours among `hours` billed at an overtime multiplier
+    pub overtime: Vec<OvertimeHours>,
+}
+
+/// The line for one project's entries at `rate`. Hourly projects bill the
+/// hours `overtime` covers (evenings, weekends and the `holidays` flagged as
+/// public holidays) at its multipliers. The amount is rounded to `decimals`.
+pub fn project_line(
+    rate: &ProjectRate,
+    entries: &[&Entry],
+    overtime: Option<&OvertimeRates>,
+    holidays: &[TimeOff],
+    decimals: usize,
+) -> ProjectLine {
+    let hours = total_hours(entries);
+    let overtime = match overtime {
+        Some(rates) if rate.billing_mode == BillingMode::Hourly => {
+            overtime_hours(entries, rates, |day| {
+                holidays.iter().any(|h| h.holiday && h.contains(day))
+            })
+        }
+        _ => Vec::new(),
+    };
+    let amount = if overtime.is_empty() {
+        rate.amount(entries)
+    } else {
+        amount_with_overtime(rate.rate, hours, &overtime)
+    };
+    ProjectLine {
+        hours,
+        amount: amount.round_to(decimals),
+        overtime,
+    }
+}
+
+/// Every amount an invoice bills
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bill {
+    /// The currency the invoice is written in
+    pub currency: String,
+    /// Lines of the projects that have a rate, in the order the invoice lists them
+    pub lines: Vec<(String, ProjectLine)>,
+    /// Expenses and mileage, rounded to the currency's decimals
+    pub expenses: Money,
+    pub totals: Totals,
+}
+
+impl Bill {
+    /// The line for `project`, if it has a rate
+    pub fn line(&self, project: &str) -> Option<&ProjectLine> {
+        self.lines
+            .iter()
+            .find(|(p, _)| p == project)
+            .map(|(_, line)| line)
+    }
+}
@@ -1,59 +1,348 @@
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand};
+
+use crate::completions::Shell;
+use crate::export::ExportFormat;
+use crate::import::ImportFormat;
+use crate::invoice::EmailTable;
+use crate::models::{
+    BillingMode, CapPeriod, DistanceUnit, EntryStatus, MailAction, NumberLocale, PaperSize,
+    SectionOrder, SyncService,
+};
+use crate::query::QueryFormat;
+use crate::tui::app::Screen;
 
 #[derive(Parser)]
 #[command(name = "meter")]
 #[command(about = "Track consulting hours and generate invoices", long_about = None)]
 pub struct Cli {
+    /// Screen to open the TUI on when run without a subcommand
+    #[arg(long)]
+    pub screen: Option<Screen>,
+
+    /// Without a subcommand, opens the TUI (see `meter tui-settings`)
     #[command(subcommand)]
-    pub command: Commands,
+    pub command: Option<Commands>,
+}
+
+/// Which entries a billing status change applies to.
+/// With no options, every entry that can reach the new status is changed.
+#[derive(Args)]
+pub struct EntrySelection {
+    /// A single entry ID
+    #[arg(short, long, conflicts_with_all = ["project", "month"])]
+    pub id: Option<i64>,
+
+    /// Only entries for this project
+    #[arg(short, long)]
+    pub project: Option<String>,
+
+    /// Only entries that ended in this month (YYYY-MM)
+    #[arg(short, long)]
+    pub month: Option<String>,
 }
 
 #[derive(Subcommand)]
+#[allow(clippy::large_enum_variant)]
 pub enum Commands {
     /// Start a new timer for a project
     Start {
         #[arg(short, long)]
         project: String,
-        #[arg(short, long, default_value = "Work session")]
-        desc: String,
+        /// Description. Defaults to "Work session", or the project's last description
+        /// when `timer-settings --reuse-description true` is set.
+        #[arg(short, long)]
+        desc: Option<String>,
+        /// Count down from a target duration such as 2h, 90m or 1h30m, with a
+        /// notification when it is reached. The timer keeps running until stopped.
+        #[arg(long = "for", value_name = "DURATION")]
+        target: Option<String>,
+        /// Issue reference such as ACME-123, #42 or a URL. Taken from the git branch
+        /// when `timer-settings --git-issues true` is set.
+        #[arg(short, long)]
+        issue: Option<String>,
+        /// Where the work is done: home, client-site, travel or your own text
+        #[arg(short, long)]
+        location: Option<String>,
+        /// Tag for the kind of work, e.g. support (repeat for more than one)
+        #[arg(long = "tag", value_name = "TAG")]
+        tags: Vec<String>,
+        /// When you actually started, if you forgot: HH:MM[:SS] today,
+        /// "YYYY-MM-DD HH:MM[:SS]", or -10m / -1h / -30s ago
+        #[arg(long, value_name = "TIME", allow_hyphen_values = true)]
+        at: Option<String>,
+    },
+
+    /// Start a new timer with the project, description, issue, location and tags
+    /// of the most recently stopped entry
+    Resume,
+
+    /// Discard the running timer without saving an entry, e.g. when it was
+    /// started for the wrong project
+    Cancel,
+
+    /// Stop the running timer and start one for another project in one step,
+    /// the new entry starting the moment the old one ends
+    Switch {
+        #[arg(short, long)]
+        project: String,
+        /// Description. Defaults as for `meter start`.
+        #[arg(short, long)]
+        desc: Option<String>,
+        /// Issue reference such as ACME-123, #42 or a URL. Defaults as for `meter start`.
+        #[arg(short, long)]
+        issue: Option<String>,
+        /// Where the work is done: home, client-site, travel or your own text
+        #[arg(short, long)]
+        location: Option<String>,
+        /// Tag for the kind of work, e.g. support (repeat for more than one)
+        #[arg(long = "tag", value_name = "TAG")]
+        tags: Vec<String>,
     },
 
     /// Stop the currently running timer
-    Stop,
+    Stop {
+        /// Discard the entry if it is shorter than the configured minimum duration
+        #[arg(long)]
+        discard_short: bool,
+        /// When you actually stopped, if you forgot: HH:MM[:SS] today,
+        /// "YYYY-MM-DD HH:MM[:SS]", or -10m / -1h / -30s ago
+        #[arg(long, value_name = "TIME", allow_hyphen_values = true)]
+        at: Option<String>,
+    },
+
+    /// Show the running timer and the time tracked today
+    Status {
+        /// Print one line of JSON for widgets, shell prompts and status lines:
+        /// project, description, elapsed time, today's total and the time left
+        /// in the Pomodoro work period
+        #[arg(long)]
+        widget: bool,
+    },
+
+    /// Change the running entry's project, description or start time without stopping it
+    Amend {
+        #[arg(short, long)]
+        project: Option<String>,
+
+        #[arg(short, long)]
+        desc: Option<String>,
+
+        /// New start time: HH:MM[:SS] today, "YYYY-MM-DD HH:MM[:SS]", or -10m / -1h / -30s ago
+        #[arg(short, long, allow_hyphen_values = true)]
+        start: Option<String>,
+
+        /// Issue reference such as ACME-123, #42 or a URL ("" to remove it)
+        #[arg(short, long)]
+        issue: Option<String>,
+
+        /// Where the work is done: home, client-site, travel or your own text ("" to remove it)
+        #[arg(short, long)]
+        location: Option<String>,
+    },
 
     /// Add a manual time entry
     Add {
+        #[arg(short, long, required_unless_present = "template")]
+        project: Option<String>,
+        #[arg(short, long, required_unless_present = "template")]
+        desc: Option<String>,
+        #[arg(
+            short,
+            long,
+            required_unless_present_any = ["days", "template"],
+            help = "Duration in hours (e.g. 1.5)"
+        )]
+        duration: Option<f64>,
+        /// Days worked, billed at the project's daily rate instead of by the hour (e.g. 1.5)
+        #[arg(long, conflicts_with_all = ["duration", "template"])]
+        days: Option<f64>,
+        /// Day the days were worked, YYYY-MM-DD (default: today)
+        #[arg(long, requires = "days", conflicts_with_all = ["duration", "template"])]
+        date: Option<String>,
+        /// Take the project, description, duration and tags from a saved template
+        /// (see `meter template`); options given as well override it
         #[arg(short, long)]
-        project: String,
+        template: Option<String>,
+        /// Issue reference such as ACME-123, #42 or a URL
         #[arg(short, long)]
-        desc: String,
-        #[arg(short, long, help = "Duration in hours (e.g. 1.5)")]
-        duration: f64,
+        issue: Option<String>,
+        /// Where the work was done: home, client-site, travel or your own text
+        #[arg(short, long)]
+        location: Option<String>,
+        /// Tag for the kind of work, e.g. support (repeat for more than one)
+        #[arg(long = "tag", value_name = "TAG")]
+        tags: Vec<String>,
+    },
+
+    /// Delete an entry, or every entry for a project and/or in a date range.
+    /// Asks before deleting unless --yes is given.
+    Delete {
+        /// Entry ID
+        #[arg(
+            required_unless_present_any = ["range", "project"],
+            conflicts_with_all = ["range", "project"]
+        )]
+        id: Option<i64>,
+
+        /// Entries that started on YYYY-MM-DD, or in an inclusive range YYYY-MM-DD..YYYY-MM-DD
+        #[arg(long)]
+        range: Option<String>,
+
+        /// Entries for this project
+        #[arg(short, long)]
+        project: Option<String>,
+
+        /// Delete without asking
+        #[arg(short, long)]
+        yes: bool,
+    },
+
+    /// Tag an entry with the kind of work it records, or take tags off it
+    Tag {
+        /// Entry ID
+        id: i64,
+
+        /// Tags to add, e.g. support meetings
+        #[arg(required = true)]
+        tags: Vec<String>,
+
+        /// Remove these tags instead
+        #[arg(long)]
+        remove: bool,
+    },
+
+    /// Flag an entry as needing review before it is invoiced, or mark it
+    /// reviewed. Idle stops, imports and midnight splits flag entries themselves.
+    Flag {
+        /// Entry ID
+        id: i64,
+
+        /// Mark the entry reviewed, clearing its flags
+        #[arg(long)]
+        clear: bool,
+    },
+
+    /// Add entries from lines like `2024-06-03 09:00-10:30 acme fix login bug #dev`
+    Log {
+        /// File to read, or - for standard input
+        file: String,
+
+        /// Check the lines and show what would be added without adding anything
+        #[arg(long)]
+        dry_run: bool,
     },
 
-    /// List all entries (filtered by status)
+    /// Import entries from another time tracker's data files, or restore a
+    /// `meter export json` file into an empty database
+    Import {
+        /// Format of the files: timewarrior, watson, csv (toggl, clockify) or json
+        format: ImportFormat,
+
+        /// Data files, e.g. ~/.timewarrior/data/*.data or ~/.config/watson/frames
+        #[arg(required = true)]
+        files: Vec<String>,
+
+        /// Show what would be imported without adding anything
+        #[arg(long)]
+        dry_run: bool,
+
+        /// CSV column holding a field, e.g. --map project=Client (fields: project,
+        /// description, start_date, start_time, end_date, end_time, duration, tags)
+        #[arg(long = "map", value_name = "FIELD=COLUMN")]
+        columns: Vec<String>,
+    },
+
+    /// Write the whole database to a file, to move it to another machine
+    /// with `meter import json`
+    Export {
+        /// Format to write: json
+        format: ExportFormat,
+
+        /// File to write (default: standard output)
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+
+    /// List entries (open entries by default)
     List {
+        /// Only show entries in this status (open, submitted, billed, invoiced, paid)
+        #[arg(short, long, conflicts_with = "all")]
+        status: Option<EntryStatus>,
+
+        /// Show entries in every status
+        #[arg(short, long)]
+        all: bool,
+
+        /// Only entries that started on or after this date (YYYY-MM-DD)
+        #[arg(long)]
+        from: Option<String>,
+
+        /// Only entries that started on or before this date (YYYY-MM-DD)
+        #[arg(long)]
+        to: Option<String>,
+
+        /// Only entries for this project
         #[arg(short, long)]
-        billed: bool,
+        project: Option<String>,
+
+        /// Show at most this many entries, the most recent first
+        #[arg(short = 'n', long)]
+        limit: Option<usize>,
+    },
+
+    /// Entries, or hours per group, matching a filter such as
+    /// `project=acme AND billed=false AND start>=2024-06-01 GROUP BY week`
+    Query {
+        /// Conditions `FIELD OP VALUE` joined by AND, then optionally GROUP BY day,
+        /// week, month, project, status or tag. Fields: project, description, status,
+        /// billed, tag, issue, location, start, end, hours. Operators: = != < <= > >= ~
+        #[arg(default_value = "")]
+        query: String,
+
+        /// Output format: json or csv
+        #[arg(short, long, default_value = "json")]
+        format: QueryFormat,
+    },
+
+    /// Hours and days worked at each location, e.g. on-site days for a tax return
+    Locations {
+        /// YYYY-MM-DD, or an inclusive range YYYY-MM-DD..YYYY-MM-DD (default: this year)
+        dates: Option<String>,
+    },
+
+    /// Print yesterday's and today's work grouped by project, for standups
+    Summary {
+        /// Format as a Markdown bullet list
+        #[arg(long)]
+        markdown: bool,
     },
 
-    /// Mark entries as billed
+    /// Mark entries as billed (ready to invoice)
     Bill {
-        #[arg(short, long)]
-        id: Option<i64>,
+        #[command(flatten)]
+        selection: EntrySelection,
     },
 
-    /// Mark entries as unbilled
+    /// Move billed or submitted entries back to open
     Unbill {
-        #[arg(short, long)]
-        id: Option<i64>,
+        #[command(flatten)]
+        selection: EntrySelection,
+    },
+
+    /// Move entries to a billing status (open, submitted, billed, invoiced, paid)
+    Mark {
+        /// Target status
+        status: EntryStatus,
+
+        #[command(flatten)]
+        selection: EntrySelection,
     },
 
     /// Generate a PDF invoice
     Invoice {
-        /// Month (1-12). Defaults to current month.
+        /// Month (1-12, or YYYY-MM). Defaults to current month.
         #[arg(short, long)]
-        month: Option<u32>,
+        month: Option<String>,
 
         /// Year. Defaults to current year.
         #[arg(short, long)]
@@ -63,15 +352,83 @@ pub enum Commands {
         #[arg(short, long)]
         client: Option<i64>,
 
+        /// One invoice per client with billed entries in the month, by project links
+        #[arg(long, conflicts_with = "client")]
+        all_clients: bool,
+
         /// Override tax rate for this invoice
         #[arg(short, long)]
         tax_rate: Option<f64>,
+
+        /// Business profile to invoice from (see `meter profiles`). Defaults to the client's.
+        #[arg(long)]
+        profile: Option<i64>,
+
+        /// Save a draft to review and edit instead; it gets a number when finalized
+        /// (see `meter drafts`)
+        #[arg(long)]
+        draft: bool,
+
+        /// Only entries with this tag, e.g. to invoice a support retainer on its own
+        /// (repeat to include entries with any of several tags)
+        #[arg(long = "tag", value_name = "TAG")]
+        tags: Vec<String>,
     },
 
     /// Launch the interactive TUI
-    Tui,
+    Tui {
+        /// Open the database read-only and disable every action that changes data
+        #[arg(long)]
+        read_only: bool,
+
+        /// Database file to open instead of ~/.local/share/meter/db.sqlite (e.g. a backup copy)
+        #[arg(long)]
+        db: Option<String>,
+
+        /// Screen to open on: timer, entries, invoice, projects, pomodoro, clients, settings, tags or review
+        #[arg(long)]
+        screen: Option<Screen>,
+    },
+
+    /// Walk through last week's entries one by one in the TUI: fix descriptions,
+    /// add tags, link clients, set billing status and check odd-looking entries
+    Review,
+
+    /// Check on or stop the macOS menu bar app
+    #[command(subcommand)]
+    Menubar(MenubarCommands),
 
-    /// Set or view project hourly rate
+    /// Set or view what `meter` without a subcommand does
+    TuiSettings {
+        /// Open the TUI (otherwise print this help)
+        #[arg(long)]
+        open_by_default: Option<bool>,
+
+        /// Screen the TUI opens on when no --screen is given
+        #[arg(long)]
+        default_screen: Option<Screen>,
+    },
+
+    /// Print the database schema version and JSON Schemas of the export formats
+    Schema,
+
+    /// Run a SELECT against the database for ad-hoc analysis, e.g.
+    /// `meter sql --read-only "SELECT project, count(*) FROM entries GROUP BY project"`
+    Sql {
+        /// The SQL statement: one SELECT (or another statement that only reads)
+        query: String,
+
+        /// Open the database read-only, so the statement can't change anything.
+        /// Required: the console never writes.
+        #[arg(long, required = true)]
+        read_only: bool,
+
+        /// Print CSV with a header row instead of a table
+        #[arg(long)]
+        csv: bool,
+    },
+
+    /// Set or view a project's rate and billing mode
     Rate {
         /// Project name
         #[arg(short, long)]
@@ -81,9 +438,64 @@ pub enum Commands {
         #[arg(short, long)]
         rate: Option<f64>,
 
-        /// Currency symbol (default: $)
+        /// Currency: an ISO code such as EUR or GBP, or a symbol such as € (default: $)
         #[arg(short, long, default_value = "$")]
         currency: String,
+
+        /// What the rate is charged per: hourly, daily (per day worked) or fixed (flat fee per invoice)
+        #[arg(short, long)]
+        mode: Option<BillingMode>,
+    },
+
+    /// Set or view a project's weekly or monthly hour cap
+    Cap {
+        /// Project name
+        #[arg(short, long)]
+        project: String,
+
+        /// Maximum hours per period (e.g., 20). Omit to view the cap and its usage.
+        #[arg(long, conflicts_with = "clear")]
+        hours: Option<f64>,
+
+        /// Period the cap applies to: week or month (default: week)
+        #[arg(long, requires = "hours")]
+        period: Option<CapPeriod>,
+
+        /// Remove the cap
+        #[arg(long)]
+        clear: bool,
+    },
+
+    /// Set or view how long a project's timer may sit idle before the menu bar
+    /// app stops it
+    Idle {
+        /// Project name
+        #[arg(short, long)]
+        project: String,
+
+        /// Idle minutes before the timer is stopped (0 = never). Omit to view.
+        #[arg(long, conflicts_with = "default")]
+        minutes: Option<i64>,
+
+        /// Use the default from `timer-settings --idle-stop`
+        #[arg(long)]
+        default: bool,
+    },
+
+    /// Set or view the area a project is grouped under, such as "Client work" or
+    /// "Open source", for rolling up hours in `meter report areas` and the TUI
+    Area {
+        /// Project name
+        #[arg(short, long)]
+        project: String,
+
+        /// Area name. Omit to view.
+        #[arg(long, conflicts_with = "clear")]
+        name: Option<String>,
+
+        /// Take the project out of its area
+        #[arg(long)]
+        clear: bool,
     },
 
     /// List all projects with their rates
@@ -116,6 +528,83 @@ pub enum Commands {
         cycles: Option<i32>,
     },
 
+    /// Configure timer behavior
+    #[command(name = "timer-settings")]
+    TimerSettings {
+        /// Reuse the project's last description when a timer is started without one
+        #[arg(long)]
+        reuse_description: Option<bool>,
+
+        /// Minimum entry duration in seconds; shorter entries are flagged on stop (0 = off)
+        #[arg(long)]
+        min_duration: Option<i64>,
+
+        /// Discard entries shorter than the minimum without asking
+        #[arg(long)]
+        discard_short: Option<bool>,
+
+        /// Split entries that cross midnight into one entry per day when they are stopped
+        #[arg(long)]
+        split_midnight: Option<bool>,
+
+        /// Let the menu bar app record the frontmost app while no timer is running,
+        /// to suggest entries (see `meter suggestions`)
+        #[arg(long)]
+        track_activity: Option<bool>,
+
+        /// Minutes away from the computer after which the menu bar app stops the
+        /// timer, for projects without their own setting (0 = off)
+        #[arg(long)]
+        idle_stop: Option<i64>,
+
+        /// Take the issue reference of timers started in a git repository from the
+        /// branch name, e.g. ACME-123 from feature/ACME-123-fix-login
+        #[arg(long)]
+        git_issues: Option<bool>,
+    },
+
+    /// Configure notifications (TUI and menu bar app)
+    Notifications {
+        /// Notify when a Pomodoro work period ends
+        #[arg(long)]
+        work_complete: Option<bool>,
+
+        /// Notify when a Pomodoro break ends
+        #[arg(long)]
+        break_complete: Option<bool>,
+
+        /// Notify about uninvoiced months, projects reaching their hour cap and
+        /// falling behind the monthly target
+        #[arg(long)]
+        reminders: Option<bool>,
+
+        /// Sound to play, e.g. Glass, Ping or Submarine ("" for silent)
+        #[arg(long)]
+        sound: Option<String>,
+
+        /// Hours with no notifications, as HH:MM-HH:MM (e.g. 22:00-08:00), or "off"
+        #[arg(long)]
+        quiet_hours: Option<String>,
+
+        /// Badge the menu bar icon once unbilled hours pass this (0 = off)
+        #[arg(long)]
+        badge_hours: Option<f64>,
+
+        /// Badge the menu bar icon while an invoice is past due
+        #[arg(long)]
+        badge_overdue: Option<bool>,
+
+        /// Billable hours to aim for each month; `meter status`, the TUI and the
+        /// menu bar app show the pace (0 = off)
+        #[arg(long, value_name = "HOURS")]
+        monthly_target: Option<f64>,
+
+        /// Hours billable time may fall behind the target's pace before a
+        /// notification (default 8)
+        #[arg(long, value_name = "HOURS")]
+        target_margin: Option<f64>,
+    },
+
     /// Configure invoice settings (your business info)
     #[command(name = "invoice-settings")]
     InvoiceSettings {
@@ -166,94 +655,986 @@ pub enum Commands {
         /// Default tax rate percentage (e.g., 8.5 for 8.5%)
         #[arg(long)]
         tax_rate: Option<f64>,
-    },
 
-    /// Manage clients
-    #[command(subcommand)]
-    Client(ClientCommands),
-}
-
-#[derive(Subcommand)]
-pub enum ClientCommands {
-    /// Add a new client
-    Add {
-        /// Client or company name
+        /// Rates include tax (GST/VAT-inclusive): the invoice backs the tax out of the total
         #[arg(long)]
-        name: String,
+        tax_inclusive: Option<bool>,
 
-        /// Contact person name
+        /// Day of the month from which last month's uninvoiced hours are flagged (0 = off)
         #[arg(long)]
-        contact: Option<String>,
+        reminder_day: Option<i64>,
 
-        /// Street address
+        /// Print the lines after the first line of an entry's description as notes
         #[arg(long)]
-        street: Option<String>,
+        include_notes: Option<bool>,
 
-        /// City
+        /// Paper size for invoice PDFs: a4, letter or legal
         #[arg(long)]
-        city: Option<String>,
+        paper_size: Option<PaperSize>,
 
-        /// State or province
+        /// Page margin in millimetres (default: 20)
         #[arg(long)]
-        state: Option<String>,
+        margin: Option<f64>,
 
-        /// Postal/ZIP code
+        /// Body font size in points (default: 10)
         #[arg(long)]
-        postal: Option<String>,
+        font_size: Option<i64>,
 
-        /// Country
+        /// Order of project sections: alphabetical, hours (most first) or date (earliest entry first)
         #[arg(long)]
-        country: Option<String>,
+        section_order: Option<SectionOrder>,
 
-        /// Email address
+        /// Separators for amounts: currency (each currency's own), en (1,234.56), de (1.234,56),
+        /// fr (1 234,56) or ch (1'234.56)
         #[arg(long)]
-        email: Option<String>,
-    },
+        number_locale: Option<NumberLocale>,
 
-    /// List all clients
-    List,
+        /// Bank account IBAN, shown in the invoice's payment details
+        #[arg(long)]
+        iban: Option<String>,
 
-    /// Edit a client
-    Edit {
-        /// Client ID
-        id: i64,
+        /// Bank BIC
+        #[arg(long)]
+        bic: Option<String>,
 
-        /// Client or company name
+        /// Bank account number
         #[arg(long)]
-        name: Option<String>,
+        account_number: Option<String>,
 
-        /// Contact person name
+        /// Bank routing number
         #[arg(long)]
-        contact: Option<String>,
+        routing_number: Option<String>,
 
-        /// Street address
+        /// Bank SWIFT code
         #[arg(long)]
-        street: Option<String>,
+        swift: Option<String>,
 
-        /// City
+        /// Move due dates on weekends and holidays (`meter off add --holiday`) to the next business day
         #[arg(long)]
-        city: Option<String>,
+        business_days: Option<bool>,
 
-        /// State or province
+        /// Also write invoice_NNNN.json with each invoice's line items, parties and totals
         #[arg(long)]
-        state: Option<String>,
+        json: Option<bool>,
 
-        /// Postal/ZIP code
+        /// Show each entry's issue reference (`meter start --issue`) in a Ref column
         #[arg(long)]
-        postal: Option<String>,
+        issue_refs: Option<bool>,
 
-        /// Country
+        /// Logo image (PNG or JPEG) for the top of invoices; empty removes it
         #[arg(long)]
-        country: Option<String>,
+        logo: Option<String>,
 
-        /// Email address
+        /// Business profile to set or view (default: the default profile)
         #[arg(long)]
-        email: Option<String>,
+        profile: Option<i64>,
     },
 
-    /// Delete a client
-    Delete {
-        /// Client ID
-        id: i64,
-    },
+    /// Manage the businesses you invoice as, each with its own details and invoice numbers
+    #[command(subcommand)]
+    Profiles(ProfilesCommands),
+
+    /// Manage clients
+    #[command(subcommand)]
+    Client(ClientCommands),
+
+    /// Manage generated invoices and their PDFs
+    #[command(subcommand)]
+    Invoices(InvoicesCommands),
+
+    /// Review, edit and finalize draft invoices
+    #[command(subcommand)]
+    Drafts(DraftsCommands),
+
+    /// Send a month's entries to a client for sign-off before invoicing them
+    #[command(subcommand)]
+    Approvals(ApprovalsCommands),
+
+    /// Review entries suggested from recorded app activity
+    #[command(subcommand)]
+    Suggestions(SuggestionsCommands),
+
+    /// Record vacations, holidays and other days off
+    #[command(subcommand)]
+    Off(OffCommands),
+
+    /// Save recurring fixed-length activities, such as a standup, for `meter add --template`
+    #[command(subcommand)]
+    Template(TemplateCommands),
+
+    /// Have the menu bar app suggest a project's timer when the Mac joins a Wi-Fi network
+    #[command(subcommand)]
+    Network(NetworkCommands),
+
+    /// Record expenses and mileage to bill to clients
+    #[command(subcommand)]
+    Expenses(ExpensesCommands),
+
+    /// Keep receipts and other files with expenses and invoices
+    #[command(subcommand)]
+    Attachments(AttachmentsCommands),
+
+    /// Push finished entries to Harvest or Toggl
+    #[command(subcommand)]
+    Sync(SyncCommands),
+
+    /// Read replies about invoices from an IMAP inbox and mark them acknowledged or paid
+    #[command(subcommand)]
+    Mail(MailCommands),
+
+    /// Match payments received through Stripe to invoices and mark those invoices paid
+    #[command(subcommand)]
+    Payments(PaymentsCommands),
+
+    /// Remind clients about overdue invoices on a schedule of days past due
+    #[command(subcommand)]
+    Dunning(DunningCommands),
+
+    /// Reports across all clients and projects
+    #[command(subcommand)]
+    Report(ReportCommands),
+
+    /// Compare tracked time against calendar events and list meetings with little
+    /// or no time tracked, to catch billable meetings that were never logged
+    Reconcile {
+        /// iCalendar (.ics) file, or an http(s):// or webcal:// feed URL
+        #[arg(long, value_name = "FILE|URL")]
+        ical: String,
+
+        /// YYYY-MM-DD, or an inclusive range YYYY-MM-DD..YYYY-MM-DD (default: the last 4 weeks)
+        dates: Option<String>,
+
+        /// Percentage of a meeting that tracked time must cover for it to count as logged
+        #[arg(long, default_value_t = 50, value_parser = clap::value_parser!(u8).range(1..=100))]
+        min_coverage: u8,
+
+        /// List every meeting with its tracked time, not just those missing it
+        #[arg(long)]
+        all: bool,
+    },
+
+    /// Check for problems such as invoice records whose PDF is missing
+    Doctor,
+
+    /// Print a shell completion script (bash, zsh or fish)
+    Completions {
+        /// Shell to write the script for
+        shell: Shell,
+    },
+
+    /// Complete a partial command line; called by the completion scripts
+    #[command(name = "__complete", hide = true)]
+    Complete {
+        /// Words after `meter`, the last being the one to complete
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        words: Vec<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum InvoicesCommands {
+    /// List recorded invoices and whether their PDF still exists
+    List,
+
+    /// Write an invoice's PDF again from the snapshot stored when it was issued
+    Regenerate {
+        /// Invoice number
+        number: i64,
+    },
+
+    /// Delete PDFs in the invoice directory that no invoice record refers to.
+    /// Asks before deleting unless --yes is given.
+    Clean {
+        /// Only list the files that would be deleted
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Delete without asking
+        #[arg(short, long)]
+        yes: bool,
+    },
+
+    /// Zip an invoice's PDF with its receipts, ready to send to the client
+    Bundle {
+        /// Invoice number
+        number: i64,
+    },
+
+    /// Email an invoice's PDF to the client through the SMTP server set up in the TUI
+    Send {
+        /// Invoice number
+        number: i64,
+
+        /// Recipient (default: the client's email)
+        #[arg(long)]
+        to: Option<String>,
+
+        /// Also put the line items and totals in the body as a table: markdown or html
+        #[arg(long)]
+        table: Option<EmailTable>,
+    },
+
+    /// Record an invoice as paid, e.g. by bank transfer, and mark its entries paid.
+    /// Stops late-payment reminders for it.
+    Paid {
+        /// Invoice number
+        number: i64,
+    },
+
+    /// Void an invoice, releasing its entries and expenses to be billed again
+    Void {
+        /// Invoice number
+        number: i64,
+
+        /// Why it is void (e.g. "client disputed the March 4 session")
+        #[arg(short, long)]
+        reason: String,
+
+        /// Save the released entries as a draft of a replacement invoice
+        #[arg(long)]
+        supersede: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum DraftsCommands {
+    /// List drafts with their entries and totals
+    List,
+
+    /// Change a draft's client, profile, tax rate, rounding or entries, and write its preview again
+    Edit {
+        /// Draft ID
+        id: i64,
+
+        /// Client ID to bill
+        #[arg(short, long)]
+        client: Option<i64>,
+
+        /// Business profile to invoice from
+        #[arg(long)]
+        profile: Option<i64>,
+
+        /// Tax rate for this invoice
+        #[arg(short, long)]
+        tax_rate: Option<f64>,
+
+        /// Round each timed entry up to this many minutes (0 for exact times)
+        #[arg(short, long)]
+        round: Option<i64>,
+
+        /// Add a billed entry (repeatable)
+        #[arg(long)]
+        add_entry: Vec<i64>,
+
+        /// Take an entry off the draft (repeatable)
+        #[arg(long)]
+        remove_entry: Vec<i64>,
+    },
+
+    /// Add a line item such as a one-off charge, or a discount with a negative amount
+    AddItem {
+        /// Draft ID
+        id: i64,
+
+        /// What the item is for
+        #[arg(short, long)]
+        desc: String,
+
+        /// Amount; negative for a discount
+        #[arg(short, long, allow_hyphen_values = true)]
+        amount: f64,
+    },
+
+    /// Remove a line item from a draft
+    RemoveItem {
+        /// Draft ID
+        id: i64,
+
+        /// Item ID, as shown by `meter drafts list`
+        item: i64,
+    },
+
+    /// Write a draft's preview PDF again
+    Preview {
+        /// Draft ID
+        id: i64,
+    },
+
+    /// Issue the invoice: give it the next number, write its PDF and mark its entries invoiced
+    Finalize {
+        /// Draft ID
+        id: i64,
+    },
+
+    /// Delete a draft, releasing its entries for other invoices
+    Discard {
+        /// Draft ID
+        id: i64,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ApprovalsCommands {
+    /// Write a sheet of a client's finished, uninvoiced entries for a month and
+    /// hold them back from invoices until it is approved
+    Sheet {
+        /// Client ID; entries are picked by their projects' client links
+        #[arg(short, long)]
+        client: i64,
+
+        /// Month (1-12, or YYYY-MM). Defaults to current month.
+        #[arg(short, long)]
+        month: Option<String>,
+
+        /// Year. Defaults to current year.
+        #[arg(short, long)]
+        year: Option<i32>,
+
+        /// Write a CSV instead of a PDF
+        #[arg(long)]
+        csv: bool,
+    },
+
+    /// List approval sheets and whether they were approved
+    List,
+
+    /// Record the client's sign-off: the sheet's entries are marked billed, ready to invoice
+    Approve {
+        /// Sheet ID
+        id: i64,
+
+        /// An entry the client did not approve; it goes back to open (repeatable)
+        #[arg(long = "reject", value_name = "ENTRY")]
+        reject: Vec<i64>,
+    },
+
+    /// Delete a sheet that won't be approved, releasing its entries
+    Withdraw {
+        /// Sheet ID
+        id: i64,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum SuggestionsCommands {
+    /// List stretches of app activity with no time entry
+    List {
+        /// Shortest stretch to suggest, in minutes
+        #[arg(long, default_value_t = 15)]
+        min_minutes: i64,
+    },
+
+    /// Turn a suggestion into a time entry
+    Add {
+        /// Suggestion ID
+        id: i64,
+
+        #[arg(short, long)]
+        project: String,
+
+        /// Description (default: the app's name)
+        #[arg(short, long)]
+        desc: Option<String>,
+    },
+
+    /// Stop suggesting a stretch of activity
+    Dismiss {
+        /// Suggestion ID
+        id: i64,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum OffCommands {
+    /// Record a day or range of days off
+    Add {
+        /// YYYY-MM-DD, or an inclusive range YYYY-MM-DD..YYYY-MM-DD
+        dates: String,
+
+        /// Why (e.g. "vacation")
+        #[arg(default_value = "")]
+        reason: String,
+
+        /// A public holiday: invoice due dates can be moved off it
+        #[arg(long)]
+        holiday: bool,
+    },
+
+    /// List recorded time off
+    List,
+
+    /// Delete recorded time off
+    Remove {
+        /// Time off ID
+        id: i64,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum TemplateCommands {
+    /// Save a template, replacing any with the same name
+    Add {
+        /// Name to pick it by, e.g. standup
+        name: String,
+
+        #[arg(short, long)]
+        project: String,
+
+        #[arg(short, long, default_value = "")]
+        desc: String,
+
+        /// Length in hours (e.g. 0.25)
+        #[arg(long)]
+        duration: f64,
+
+        /// Tag for the kind of work, e.g. meetings (repeat for more than one)
+        #[arg(long = "tag", value_name = "TAG")]
+        tags: Vec<String>,
+    },
+
+    /// List saved templates
+    List,
+
+    /// Delete a template
+    Remove {
+        /// Template name
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum NetworkCommands {
+    /// Suggest a project on a Wi-Fi network, replacing any rule for that network
+    Add {
+        /// Wi-Fi network name (SSID), exactly as macOS shows it
+        ssid: String,
+
+        #[arg(short, long)]
+        project: String,
+
+        /// Location for entries started from the suggestion, e.g. client-site
+        #[arg(long)]
+        location: Option<String>,
+    },
+
+    /// List network rules
+    List,
+
+    /// Delete the rule for a network
+    Remove {
+        /// Wi-Fi network name (SSID)
+        ssid: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ExpensesCommands {
+    /// Record an expense, or mileage with --distance
+    Add {
+        /// Amount spent
+        #[arg(short, long, required_unless_present = "distance")]
+        amount: Option<f64>,
+
+        /// Distance driven, charged at the mileage rate (see `meter expenses mileage`)
+        #[arg(long, conflicts_with = "amount")]
+        distance: Option<f64>,
+
+        /// What it was for
+        #[arg(short, long, default_value = "")]
+        desc: String,
+
+        /// YYYY-MM-DD (default: the entry's day, or today)
+        #[arg(long)]
+        date: Option<String>,
+
+        /// Entry the expense was incurred for; it is invoiced with the entry
+        #[arg(short, long)]
+        entry: Option<i64>,
+
+        /// Client to bill (default: the client of the entry's project)
+        #[arg(short, long)]
+        client: Option<i64>,
+    },
+
+    /// List expenses with their total
+    List {
+        /// Only this client's expenses
+        #[arg(short, long)]
+        client: Option<i64>,
+
+        /// Only expenses in this month (YYYY-MM)
+        #[arg(short, long)]
+        month: Option<String>,
+
+        /// Only expenses not yet invoiced
+        #[arg(long)]
+        uninvoiced: bool,
+    },
+
+    /// Delete an expense
+    Remove {
+        /// Expense ID
+        id: i64,
+    },
+
+    /// Set or view the mileage rate
+    Mileage {
+        /// Amount charged per unit of distance
+        #[arg(long)]
+        rate: Option<f64>,
+
+        /// km or mi
+        #[arg(long)]
+        unit: Option<DistanceUnit>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum AttachmentsCommands {
+    /// Attach a file to an expense or an invoice; a copy is kept in meter's attachments folder
+    Add {
+        /// File to attach
+        file: String,
+
+        /// Expense to attach it to
+        #[arg(short, long, required_unless_present = "invoice")]
+        expense: Option<i64>,
+
+        /// Invoice number to attach it to
+        #[arg(short, long, conflicts_with = "expense")]
+        invoice: Option<i64>,
+    },
+
+    /// List attachments
+    List {
+        /// Only this expense's attachments
+        #[arg(short, long)]
+        expense: Option<i64>,
+
+        /// Only attachments going out with this invoice, including its expenses'
+        #[arg(short, long, conflicts_with = "expense")]
+        invoice: Option<i64>,
+    },
+
+    /// Open an attachment with the default application
+    Open {
+        /// Attachment ID
+        id: i64,
+    },
+
+    /// Delete an attachment and its stored copy
+    Remove {
+        /// Attachment ID
+        id: i64,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum SyncCommands {
+    /// Set or view the service, account and API token
+    Settings {
+        /// harvest or toggl
+        #[arg(long)]
+        service: Option<SyncService>,
+
+        /// Harvest account ID or Toggl workspace ID
+        #[arg(long)]
+        account_id: Option<String>,
+
+        /// API token (Harvest personal access token or Toggl API token); - reads it from standard input, empty removes it
+        #[arg(long, allow_hyphen_values = true)]
+        token: Option<String>,
+    },
+
+    /// Push a project's entries to a project (and task) in the service
+    Map {
+        #[arg(short, long)]
+        project: String,
+
+        /// Project ID in the service
+        #[arg(long)]
+        remote_project: i64,
+
+        /// Task ID in the service (required for Harvest)
+        #[arg(long)]
+        task: Option<i64>,
+    },
+
+    /// Stop pushing a project's entries
+    Unmap {
+        #[arg(short, long)]
+        project: String,
+    },
+
+    /// Push finished entries of mapped projects that haven't been pushed yet
+    Push {
+        /// Also push entries started on or after this date (YYYY-MM-DD) from before the project was mapped
+        #[arg(long)]
+        since: Option<String>,
+
+        /// Show what would be pushed without sending anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum MailCommands {
+    /// Set or view the IMAP server and account
+    Settings {
+        /// IMAP server, e.g. imap.fastmail.com
+        #[arg(long)]
+        host: Option<String>,
+
+        /// IMAP port (TLS; default: 993)
+        #[arg(long)]
+        port: Option<i64>,
+
+        /// Account username
+        #[arg(long)]
+        username: Option<String>,
+
+        /// Mailbox to read (default: INBOX)
+        #[arg(long)]
+        mailbox: Option<String>,
+
+        /// Turn the watcher on or off
+        #[arg(long)]
+        enabled: Option<bool>,
+
+        /// Account password; - reads it from standard input, empty removes it
+        #[arg(long, allow_hyphen_values = true)]
+        password: Option<String>,
+    },
+
+    /// Add a rule: an email naming an invoice whose subject or sender contains
+    /// PATTERN marks that invoice acknowledged or paid
+    AddRule {
+        /// Text to look for, ignoring case (e.g. "payment received")
+        pattern: String,
+
+        /// acknowledged or paid
+        #[arg(long)]
+        action: MailAction,
+    },
+
+    /// List mail rules in the order they are tried
+    Rules,
+
+    /// Delete a mail rule
+    RemoveRule {
+        /// Rule ID
+        id: i64,
+    },
+
+    /// Read new emails once and apply the rules
+    Check,
+
+    /// Keep reading new emails and applying the rules until interrupted
+    Watch {
+        /// Minutes between checks
+        #[arg(long, default_value_t = 5)]
+        interval: u64,
+    },
+
+    /// Emails matched to invoices and what was done about them
+    Log,
+}
+
+#[derive(Subcommand)]
+pub enum PaymentsCommands {
+    /// Set or view the Stripe secret key
+    Settings {
+        /// Secret or restricted key with read access to balance transactions; - reads it
+        /// from standard input, empty removes it
+        #[arg(long, allow_hyphen_values = true)]
+        stripe_key: Option<String>,
+    },
+
+    /// Fetch new payments, mark the invoices they settle paid and record their fees as expenses
+    Sync {
+        /// Read charges from Stripe
+        #[arg(long)]
+        stripe: bool,
+
+        /// Only payments on or after this date (YYYY-MM-DD; default: 90 days ago)
+        #[arg(long)]
+        since: Option<String>,
+    },
+
+    /// List recorded payments and the invoices they settled
+    List,
+}
+
+#[derive(Subcommand)]
+pub enum DunningCommands {
+    /// Show or change when reminders go out and whether they are emailed
+    Schedule {
+        /// Days past the due date, e.g. 3,10,20 ("off" for no reminders)
+        #[arg(long, value_name = "DAYS")]
+        days: Option<String>,
+
+        /// Email each reminder to the client through the SMTP server set up in the TUI
+        #[arg(long)]
+        email: Option<bool>,
+    },
+
+    /// Send the reminders that are due now; the menu bar app does this once a day
+    Run,
+
+    /// Reminders sent so far
+    Log {
+        /// Only this invoice's reminders
+        number: Option<i64>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ProfilesCommands {
+    /// List business profiles and where their invoice numbers start
+    List,
+
+    /// Add a business profile; set its details with `meter invoice-settings --profile ID`
+    Add {
+        /// Name to tell the profile apart, e.g. "LLC"
+        name: String,
+
+        /// Number of the profile's first invoice. Numbers are shared by all profiles,
+        /// so give each its own range (e.g. 5001).
+        #[arg(long, default_value_t = 1)]
+        first_number: i64,
+    },
+
+    /// Rename a profile or change where its invoice numbers start
+    Edit {
+        /// Profile ID
+        id: i64,
+
+        /// New name
+        #[arg(long)]
+        name: Option<String>,
+
+        /// Number of the profile's first invoice
+        #[arg(long)]
+        first_number: Option<i64>,
+    },
+
+    /// Remove a profile that has no invoices; its clients and drafts move to the default profile
+    Remove {
+        /// Profile ID
+        id: i64,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ReportCommands {
+    /// Expected income per client from unbilled work, fixed fees and past invoices
+    Forecast {
+        /// Months ahead to forecast
+        #[arg(long, default_value_t = 3)]
+        months: u32,
+
+        /// Complete months of past invoices to average
+        #[arg(long, default_value_t = 6)]
+        history: u32,
+    },
+
+    /// Hours tracked by hour of day and weekday, to see when work actually happens
+    Heatmap {
+        /// YYYY-MM-DD, or an inclusive range YYYY-MM-DD..YYYY-MM-DD (default: the last 12 weeks)
+        dates: Option<String>,
+
+        /// Only entries for this project
+        #[arg(short, long)]
+        project: Option<String>,
+    },
+
+    /// Entries flagged for review (see `meter flag`) that haven't been invoiced
+    Flagged {
+        /// Include invoiced and paid entries
+        #[arg(long)]
+        all: bool,
+    },
+
+    /// Hours per area (see `meter area`) and per project within each
+    Areas {
+        /// YYYY-MM-DD, or an inclusive range YYYY-MM-DD..YYYY-MM-DD (default: this month)
+        dates: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ClientCommands {
+    /// Add a new client
+    Add {
+        /// Client or company name
+        #[arg(long)]
+        name: String,
+
+        /// Contact person name
+        #[arg(long)]
+        contact: Option<String>,
+
+        /// Street address
+        #[arg(long)]
+        street: Option<String>,
+
+        /// City
+        #[arg(long)]
+        city: Option<String>,
+
+        /// State or province
+        #[arg(long)]
+        state: Option<String>,
+
+        /// Postal/ZIP code
+        #[arg(long)]
+        postal: Option<String>,
+
+        /// Country
+        #[arg(long)]
+        country: Option<String>,
+
+        /// Email address
+        #[arg(long)]
+        email: Option<String>,
+
+        /// Month-end reminder day for this client (0 = off). Defaults to the invoice-settings day.
+        #[arg(long)]
+        reminder_day: Option<i64>,
+
+        /// Business profile to invoice this client from (0 = the default profile)
+        #[arg(long)]
+        profile: Option<i64>,
+    },
+
+    /// List all clients
+    List {
+        /// Include archived clients
+        #[arg(long)]
+        all: bool,
+    },
+
+    /// Edit a client
+    Edit {
+        /// Client ID
+        id: i64,
+
+        /// Client or company name
+        #[arg(long)]
+        name: Option<String>,
+
+        /// Contact person name
+        #[arg(long)]
+        contact: Option<String>,
+
+        /// Street address
+        #[arg(long)]
+        street: Option<String>,
+
+        /// City
+        #[arg(long)]
+        city: Option<String>,
+
+        /// State or province
+        #[arg(long)]
+        state: Option<String>,
+
+        /// Postal/ZIP code
+        #[arg(long)]
+        postal: Option<String>,
+
+        /// Country
+        #[arg(long)]
+        country: Option<String>,
+
+        /// Email address
+        #[arg(long)]
+        email: Option<String>,
+
+        /// Month-end reminder day for this client (0 = off). Defaults to the invoice-settings day.
+        #[arg(long)]
+        reminder_day: Option<i64>,
+
+        /// Business profile to invoice this client from (0 = the default profile)
+        #[arg(long)]
+        profile: Option<i64>,
+    },
+
+    /// Delete a client
+    Delete {
+        /// Client ID
+        id: i64,
+    },
+
+    /// Archive a client, keeping its invoice history
+    Archive {
+        /// Client ID
+        id: i64,
+    },
+
+    /// Restore an archived client
+    Unarchive {
+        /// Client ID
+        id: i64,
+    },
+
+    /// Merge a duplicate client into another, moving its invoices
+    Merge {
+        /// ID of the duplicate client (removed after merging)
+        id: i64,
+
+        /// ID of the client to keep
+        #[arg(long)]
+        into: i64,
+    },
+
+    /// Link a project to a client so invoices for it bill that client
+    Link {
+        /// Client ID
+        id: i64,
+
+        /// Project name
+        #[arg(long)]
+        project: String,
+    },
+
+    /// Remove a project's client link
+    Unlink {
+        /// Project name
+        #[arg(long)]
+        project: String,
+    },
+
+    /// Set or view the rate multipliers a client pays for evening, weekend and
+    /// holiday hours (1 turns one off)
+    Overtime {
+        /// Client ID
+        id: i64,
+
+        /// Multiplier for hours in the evening window, e.g. 1.5
+        #[arg(long)]
+        evening: Option<f64>,
+
+        /// The evening window as HH:MM-HH:MM (default: 18:00-08:00)
+        #[arg(long)]
+        evening_hours: Option<String>,
+
+        /// Multiplier for hours on Saturdays and Sundays
+        #[arg(long)]
+        weekend: Option<f64>,
+
+        /// Multiplier for hours on holidays recorded with `meter off add --holiday`
+        #[arg(long)]
+        holiday: Option<f64>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum MenubarCommands {
+    /// Show whether the menu bar app is running, and its pid
+    Status,
+
+    /// Quit the running menu bar app
+    Stop,
 }
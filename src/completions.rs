@@ -0,0 +1,353 @@
+//! Shell completions. The scripts printed by `meter completions` hand the
+//! command line to the hidden `meter __complete` subcommand, which walks the
+//! clap definition for subcommands and flags and looks up project names,
+//! client IDs, entry IDs and the like in the database as you type.
+
+use clap::{Command, CommandFactory};
+
+use crate::cli::Cli;
+use crate::db::Db;
+use crate::import::ImportFormat;
+use crate::invoice::EmailTable;
+use crate::models::{
+    BillingMode, CapPeriod, DistanceUnit, EntryStatus, NumberLocale, PaperSize, SectionOrder,
+    SyncService, WORK_LOCATIONS,
+};
+use crate::query::QueryFormat;
+use crate::tui::app::Screen;
+
+/// Most entries offered when completing an entry ID, newest first
+const MAX_ENTRY_IDS: usize = 50;
+
+/// Shells `meter completions` can write a script for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+impl std::fmt::Display for Shell {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Shell::Bash => "bash",
+            Shell::Zsh => "zsh",
+            Shell::Fish => "fish",
+        })
+    }
+}
+
+impl std::str::FromStr for Shell {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "bash" => Ok(Shell::Bash),
+            "zsh" => Ok(Shell::Zsh),
+            "fish" => Ok(Shell::Fish),
+            _ => Err(format!(
+                "Unsupported shell '{}' (expected bash, zsh or fish)",
+                s
+            )),
+        }
+    }
+}
+
+/// The completion script for `shell`
+pub fn script(shell: Shell) -> &'static str {
+    match shell {
+        Shell::Bash => {
+            r#"_meter() {
+    local IFS=$'\n'
+    COMPREPLY=($(meter __complete -- "${COMP_WORDS[@]:1:COMP_CWORD}" 2>/dev/null | cut -f1))
+}
+complete -o default -F _meter meter
+"#
+        }
+        Shell::Zsh => {
+            r#"#compdef meter
+_meter() {
+    local out
+    out=$(meter __complete -- "${(@)words[2,CURRENT]}" 2>/dev/null)
+    if [[ -z $out ]]; then
+        _files
+        return
+    fi
+    local -a candidates
+    candidates=("${(@f)out}")
+    candidates=("${(@)candidates//:/\\:}")
+    candidates=("${(@)candidates//$'\t'/:}")
+    _describe 'meter' candidates
+}
+compdef _meter meter
+"#
+        }
+        Shell::Fish => {
+            r#"function __meter_complete
+    meter __complete -- (commandline -opc)[2..-1] (commandline -ct) 2>/dev/null
+end
+complete -c meter -f -a '(__meter_complete)'
+"#
+        }
+    }
+}
+
+/// Candidates for the last of `words` (the words after `meter`), one per line as
+/// `value` or `value<TAB>description`
+pub fn complete(db: Option<&Db>, words: &[String]) -> Vec<String> {
+    let mut root = Cli::command();
+    root.build();
+
+    let (current, before) = match words.split_last() {
+        Some((current, before)) => (current.as_str(), before),
+        None => ("", &[][..]),
+    };
+
+    // Follow subcommands, noting the positional arguments given so far and
+    // whether the word before the current one is a flag waiting for its value
+    let mut cmd = &root;
+    let mut path: Vec<&str> = Vec::new();
+    let mut positionals = 0;
+    let mut pending_value: Option<&str> = None;
+    for word in before {
+        if pending_value.take().is_some() {
+            continue;
+        }
+        if word == "--" {
+            continue;
+        }
+        if word.starts_with('-') {
+            if !word.contains('=') {
+                pending_value = value_flag(cmd, word);
+            }
+            continue;
+        }
+        match cmd.find_subcommand(word) {
+            Some(sub) if positionals == 0 => {
+                path.push(sub.get_name());
+                cmd = sub;
+            }
+            _ => positionals += 1,
+        }
+    }
+
+    let candidates = if let Some(arg) = pending_value {
+        values(db, &path, arg)
+    } else if current.starts_with('-') {
+        flags(cmd)
+    } else if cmd.has_subcommands() {
+        cmd.get_subcommands()
+            .filter(|sub| !sub.is_hide_set())
+            .map(|sub| with_description(sub.get_name(), sub.get_about()))
+            .collect()
+    } else {
+        match cmd.get_positionals().nth(positionals) {
+            Some(arg) => values(db, &path, arg.get_id().as_str()),
+            None => Vec::new(),
+        }
+    };
+
+    candidates
+        .into_iter()
+        .filter(|c| c.starts_with(current))
+        .collect()
+}
+
+/// The ID of the option `word` names, if it takes a value
+fn value_flag<'a>(cmd: &'a Command, word: &str) -> Option<&'a str> {
+    cmd.get_arguments()
+        .find(|arg| match word.strip_prefix("--") {
+            Some(long) => arg.get_long() == Some(long),
+            None => word.len() == 2 && arg.get_short() == word.chars().nth(1),
+        })
+        .filter(|arg| arg.get_action().takes_values())
+        .map(|arg| arg.get_id().as_str())
+}
+
+/// Long flags of `cmd`, with their help text
+fn flags(cmd: &Command) -> Vec<String> {
+    cmd.get_arguments()
+        .filter(|arg| !arg.is_hide_set())
+        .filter_map(|arg| {
+            arg.get_long()
+                .map(|long| with_description(&format!("--{}", long), arg.get_help()))
+        })
+        .collect()
+}
+
+/// Values for the argument `arg` of the subcommand at `path`
+fn values(db: Option<&Db>, path: &[&str], arg: &str) -> Vec<String> {
+    let strings = |values: &[&str]| values.iter().map(|v| v.to_string()).collect();
+    match arg {
+        "status" => EntryStatus::ALL
+            .iter()
+            .map(|s| s.as_str().to_string())
+            .collect(),
+        "mode" => strings(&[
+            BillingMode::Hourly.as_str(),
+            BillingMode::Daily.as_str(),
+            BillingMode::Fixed.as_str(),
+        ]),
+        "period" => strings(&[CapPeriod::Week.as_str(), CapPeriod::Month.as_str()]),
+        "paper_size" => strings(&[
+            PaperSize::A4.as_str(),
+            PaperSize::Letter.as_str(),
+            PaperSize::Legal.as_str(),
+        ]),
+        "table" => strings(&[EmailTable::Markdown.as_str(), EmailTable::Html.as_str()]),
+        "number_locale" => strings(&[
+            NumberLocale::Currency.as_str(),
+            NumberLocale::En.as_str(),
+            NumberLocale::De.as_str(),
+            NumberLocale::Fr.as_str(),
+            NumberLocale::Ch.as_str(),
+        ]),
+        "section_order" => strings(&[
+            SectionOrder::Alphabetical.as_str(),
+            SectionOrder::Hours.as_str(),
+            SectionOrder::Date.as_str(),
+        ]),
+        "screen" | "default_screen" => Screen::ALL.iter().map(|s| s.as_str().to_string()).collect(),
+        "shell" => strings(&["bash", "zsh", "fish"]),
+        "location" => strings(&WORK_LOCATIONS),
+        "unit" => strings(&[DistanceUnit::Km.as_str(), DistanceUnit::Mi.as_str()]),
+        "service" => strings(&[SyncService::Harvest.as_str(), SyncService::Toggl.as_str()]),
+        "format" if path.first() == Some(&"query") => {
+            strings(&[QueryFormat::Json.as_str(), QueryFormat::Csv.as_str()])
+        }
+        "format" => strings(&[
+            ImportFormat::Timewarrior.as_str(),
+            ImportFormat::Watson.as_str(),
+            ImportFormat::Csv.as_str(),
+            ImportFormat::Json.as_str(),
+        ]),
+        _ => match db {
+            Some(db) => db_values(db, path, arg),
+            None => Vec::new(),
+        },
+    }
+}
+
+/// Values for `arg` that come from the database
+fn db_values(db: &Db, path: &[&str], arg: &str) -> Vec<String> {
+    let clients = || {
+        db.list_clients()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|c| format!("{}\t{}", c.id, c.name))
+            .collect()
+    };
+    match (path.first().copied(), arg) {
+        (_, "project") => db
+            .list_projects()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|p| p.name)
+            .collect(),
+        (Some("client"), "id" | "into") | (_, "client") => clients(),
+        (Some("invoices"), "number") | (_, "invoice") => db
+            .list_invoices()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|i| format!("{}\t{} {}", i.invoice_number, i.date_issued, i.total))
+            .collect(),
+        (Some("template"), "name") | (_, "template") => db
+            .list_templates()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|t| format!("{}\t{} {:.2} hrs", t.name, t.project, t.duration))
+            .collect(),
+        (Some("network"), "ssid") => db
+            .list_network_rules()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|r| format!("{}\t{}", r.ssid, r.project))
+            .collect(),
+        (Some("off"), "id") => db
+            .list_time_off()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|t| format!("{}\t{} {}", t.id, t.range(), t.reason))
+            .collect(),
+        (Some("expenses"), "id") | (_, "expense") => db
+            .list_expenses()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|x| format!("{}\t{} {} {}", x.id, x.date, x.amount, x.label()))
+            .collect(),
+        (Some("drafts"), "id") => db
+            .list_invoice_drafts()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|d| {
+                format!(
+                    "{}\t{}-{:02}, {} entries",
+                    d.id,
+                    d.year,
+                    d.month,
+                    d.entry_ids.len()
+                )
+            })
+            .collect(),
+        (Some("approvals"), "id") => db
+            .list_approval_sheets()
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|s| !s.is_approved())
+            .map(|s| {
+                format!(
+                    "{}\t{}-{:02}, {} entries",
+                    s.id,
+                    s.year,
+                    s.month,
+                    s.entry_ids.len()
+                )
+            })
+            .collect(),
+        (Some("mail"), "id") => db
+            .list_mail_rules()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|r| format!("{}\t{} {}", r.id, r.action, r.pattern))
+            .collect(),
+        (Some("profiles"), "id") | (_, "profile") => db
+            .list_profiles()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|p| format!("{}\t{}", p.id, p.name))
+            .collect(),
+        (Some("attachments"), "id") => db
+            .list_attachments()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|a| format!("{}\t{} ({})", a.id, a.name, a.owner()))
+            .collect(),
+        (_, "tags") => db
+            .tag_counts()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(tag, count)| format!("{}\t{} entries", tag, count))
+            .collect(),
+        (Some("suggestions"), "id") => db
+            .activity_suggestions(0)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|s| format!("{}\t{}", s.id, s.app))
+            .collect(),
+        (_, "id" | "entry" | "add_entry" | "remove_entry") => db
+            .list(None)
+            .unwrap_or_default()
+            .into_iter()
+            .take(MAX_ENTRY_IDS)
+            .map(|e| format!("{}\t{} - {}", e.id, e.project, e.description))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn with_description(value: &str, description: Option<&clap::builder::StyledStr>) -> String {
+    match description {
+        Some(description) => format!("{}\t{}", value, description),
+        None => value.to_string(),
+    }
+}
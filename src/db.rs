@@ -1,20 +1,150 @@
-use chrono::{DateTime, Utc};
-use rusqlite::{Connection, OptionalExtension, Result, params};
+use chrono::{DateTime, Datelike, Days, Local, Months, NaiveDate, NaiveTime, TimeZone, Utc};
+use rusqlite::{
+    Connection, ErrorCode, OpenFlags, OptionalExtension, Result, Row, Transaction,
+    TransactionBehavior, params,
+};
 
-use crate::models::{Client, Entry, Invoice, InvoiceSettings, PomodoroConfig, Project};
+use crate::models::{
+    ActivitySpan, ApprovalSheet, Attachment, BillingMode, BusinessProfile, CapPeriod, CapUsage,
+    Client, DEFAULT_DESCRIPTION, DEFAULT_PROFILE_ID, DatabaseExport, DunningLogEntry,
+    DunningReminder, DunningSettings, EmailSettings, Entry, EntryFilter, EntryStatus,
+    EntryTemplate, Expense, INVOICE_SETTINGS_COLUMNS, InboxSettings, Invoice, InvoiceDraft,
+    InvoiceItem, InvoiceReminder, InvoiceSettings, MailAction, MailMatch, MailRule,
+    MileageSettings, Money, NetworkRule, NotificationSettings, OvertimeRates, Payment,
+    PomodoroConfig, Project, ProjectTotals, ReviewReason, SyncMapping, SyncPush, SyncService,
+    SyncSettings, TargetPace, TimeOff, TimerSettings, TimerTarget, TuiSettings, validate_rate,
+};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+/// Longest gap between two samples of the same app that still extends one span.
+#[cfg(target_os = "macos")]
+const ACTIVITY_GAP_SECS: i64 = 120;
+/// Recorded activity older than this is deleted.
+#[cfg(target_os = "macos")]
+const ACTIVITY_RETENTION_DAYS: u64 = 30;
+
+/// SQL condition on invoice `i` that it is still waiting for payment: it has a
+/// snapshot (older invoices don't know their entries), the payments recorded
+/// for it add up to less than its total, no "paid" email is recorded for it,
+/// and it doesn't bill entries that are all paid. Invoices of only expenses and
+/// items stay unpaid until they are paid in full.
+const UNPAID_INVOICE: &str = "i.snapshot IS NOT NULL
+    AND (
+        SELECT ROUND(COALESCE(SUM(p.amount), 0) * 100) FROM payments p
+        WHERE p.invoice_number = i.invoice_number
+    ) < ROUND(i.total * 100)
+    AND NOT EXISTS (
+        SELECT 1 FROM mail_matches m
+        WHERE m.invoice_number = i.invoice_number AND m.action = 'paid'
+    )
+    AND NOT (
+        EXISTS (
+            SELECT 1 FROM json_each(i.snapshot, '$.line_items') li
+            JOIN entries e ON e.id = json_extract(li.value, '$.entry_id')
+        )
+        AND NOT EXISTS (
+            SELECT 1 FROM json_each(i.snapshot, '$.line_items') li
+            JOIN entries e ON e.id = json_extract(li.value, '$.entry_id')
+            WHERE e.status != 'paid'
+        )
+    )";
+
+/// How many times a connection retries a locked database before the statement
+/// fails with SQLITE_BUSY. Waits back off from 10ms to half a second, about ten
+/// seconds in all.
+const BUSY_RETRIES: i32 = 25;
+/// Retries after which waiting is reported, so brief contention stays quiet.
+const BUSY_NOTICE_AFTER: i32 = 3;
+
+/// Whether waiting on a locked database is reported on stderr.
+static BUSY_NOTICES: AtomicBool = AtomicBool::new(true);
+/// Set when a connection has waited on a locked database; see `take_busy_wait`.
+static BUSY_WAITED: AtomicBool = AtomicBool::new(false);
+
+/// Busy handler for every connection: sleep a little longer on each retry
+/// while another process (menu bar app, TUI, CLI) holds the write lock.
+fn busy_backoff(attempt: i32) -> bool {
+    if attempt >= BUSY_RETRIES {
+        return false;
+    }
+    if attempt == BUSY_NOTICE_AFTER {
+        BUSY_WAITED.store(true, Ordering::Relaxed);
+        if BUSY_NOTICES.load(Ordering::Relaxed) {
+            eprintln!("Database busy, retrying…");
+        }
+    }
+    let wait_ms = (10u64 << attempt.min(6)).min(500);
+    std::thread::sleep(Duration::from_millis(wait_ms));
+    true
+}
+
+/// Turn the "Database busy, retrying…" message on stderr on or off, e.g. off
+/// while the TUI owns the terminal.
+pub fn set_busy_notices(on: bool) {
+    BUSY_NOTICES.store(on, Ordering::Relaxed);
+}
+
+/// Whether any connection has waited on a locked database since the last call.
+pub fn take_busy_wait() -> bool {
+    BUSY_WAITED.swap(false, Ordering::Relaxed)
+}
+
+/// Whether `e` means another connection kept the database locked through every retry.
+pub fn is_busy(e: &rusqlite::Error) -> bool {
+    matches!(
+        e.sqlite_error_code(),
+        Some(ErrorCode::DatabaseBusy | ErrorCode::DatabaseLocked)
+    )
+}
 
 /// Wrapper around a SQLite connection.
 /// The inner `Connection` is intentionally private; use the `conn()` method to obtain
 /// a read‑only reference when you need to run custom queries.
 pub struct Db {
     conn: Connection,
+    read_only: bool,
 }
 
 impl Db {
     /// Create a new database connection.  The database file is created if it does not exist.
+    /// Every connection (CLI, TUI, menubar) gets the same setup: foreign key enforcement,
+    /// WAL journaling so readers don't block the writer, and a backing-off busy handler
+    /// so concurrent writers wait instead of failing immediately.
     pub fn new(path: &str) -> Result<Self> {
         let conn = Connection::open(path)?;
-        Ok(Self { conn })
+        conn.pragma_update_and_check(None, "journal_mode", "WAL", |_| Ok(()))?;
+        Self::from_connection(conn)
+    }
+
+    /// Wrap an already open connection, e.g. `Connection::open_in_memory()` for
+    /// tests, with the same foreign key and busy handler setup as `new`.
+    /// Tables are not created; call `models::init_all` for that.
+    pub fn from_connection(conn: Connection) -> Result<Self> {
+        conn.execute_batch("PRAGMA foreign_keys = ON;")?;
+        conn.busy_handler(Some(busy_backoff))?;
+        Ok(Self {
+            conn,
+            read_only: false,
+        })
+    }
+
+    /// Open an existing database without write access, e.g. to review data while
+    /// another process owns writes or to look at a backup copy. Tables are not
+    /// created or upgraded, and every write fails.
+    pub fn open_read_only(path: &str) -> Result<Self> {
+        let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+        conn.busy_handler(Some(busy_backoff))?;
+        Ok(Self {
+            conn,
+            read_only: true,
+        })
+    }
+
+    /// Whether this connection was opened with `open_read_only`.
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
     }
 
     /// Read‑only reference to the underlying connection.
@@ -22,60 +152,92 @@ impl Db {
         &self.conn
     }
 
-    /// Insert a new time entry.
-    pub fn insert(&self, entry: &Entry) -> Result<()> {
+    /// Start a transaction that takes the write lock up front. A deferred one
+    /// starts out reading, and in WAL mode turning it into a write fails at once
+    /// with SQLITE_BUSY if another connection wrote meanwhile, skipping the busy
+    /// handler.
+    fn write_transaction(&self) -> Result<Transaction<'_>> {
+        Transaction::new_unchecked(&self.conn, TransactionBehavior::Immediate)
+    }
+
+    /// Insert a new time entry. Returns the new entry's ID.
+    pub fn insert(&self, entry: &Entry) -> Result<i64> {
         self.conn.execute(
-            "INSERT INTO entries (project, description, start, end, billed)
-             VALUES (?1, ?2, ?3, ?4, ?5)",
+            "INSERT INTO entries (project, description, start, end, status, days, issue, location)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
             params![
                 entry.project,
                 entry.description,
                 entry.start.to_rfc3339(),
                 entry.end.map(|e| e.to_rfc3339()),
-                if entry.billed { 1 } else { 0 },
+                entry.status,
+                entry.days,
+                entry.issue,
+                entry.location,
             ],
         )?;
-        Ok(())
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    /// Insert several entries in one transaction. Returns their IDs.
+    pub fn insert_all(&self, entries: &[Entry]) -> Result<Vec<i64>> {
+        let tx = self.write_transaction()?;
+        let mut ids = Vec::new();
+        for entry in entries {
+            ids.push(self.insert(entry)?);
+        }
+        tx.commit()?;
+        Ok(ids)
     }
 
-    /// Retrieve all entries, optionally filtered by billed status.
-    pub fn list(&self, billed: Option<bool>) -> Result<Vec<Entry>> {
+    /// Retrieve all entries, optionally filtered by billing status.
+    pub fn list(&self, status: Option<EntryStatus>) -> Result<Vec<Entry>> {
+        self.list_filtered(&EntryFilter {
+            status,
+            ..EntryFilter::default()
+        })
+    }
+
+    /// Entries matching `filter`, most recent first.
+    pub fn list_filtered(&self, filter: &EntryFilter) -> Result<Vec<Entry>> {
         let mut stmt = self.conn.prepare(
-            "SELECT id, project, description, start, end, billed
+            "SELECT id, project, description, start, end, status, days, issue, location
              FROM entries
-             ORDER BY start DESC",
+             WHERE (?1 IS NULL OR status = ?1)
+               AND (?2 IS NULL OR project = ?2)
+               AND (?3 IS NULL OR julianday(start) >= julianday(?3))
+               AND (?4 IS NULL OR julianday(start) < julianday(?4))
+             ORDER BY start DESC
+             LIMIT ?5",
         )?;
-        let entries_iter = stmt.query_map(params![], |row| {
-            Ok(Entry {
-                id: row.get(0)?,
-                project: row.get(1)?,
-                description: row.get(2)?,
-                start: DateTime::parse_from_rfc3339(row.get::<_, String>(3)?.as_str())
-                    .unwrap()
-                    .with_timezone(&Utc),
-                end: match row.get::<_, Option<String>>(4)? {
-                    Some(s) => Some(
-                        DateTime::parse_from_rfc3339(&s)
-                            .unwrap()
-                            .with_timezone(&Utc),
-                    ),
-                    None => None,
-                },
-                billed: row.get::<_, i64>(5)? != 0,
-            })
-        })?;
+        let entries = stmt.query_map(
+            params![
+                filter.status,
+                filter.project,
+                filter.start_from.map(|t| t.to_rfc3339()),
+                filter.start_before.map(|t| t.to_rfc3339()),
+                // SQLite reads a negative limit as no limit
+                filter.limit.map_or(-1, |n| n as i64),
+            ],
+            entry_from_row,
+        )?;
+        entries.collect()
+    }
 
-        let mut entries = Vec::new();
-        for e in entries_iter {
-            let e = e?;
-            if let Some(b) = billed {
-                if e.billed != b {
-                    continue;
-                }
-            }
-            entries.push(e);
-        }
-        Ok(entries)
+    /// List one project's entries, optionally filtered by billing status.
+    pub fn list_for_project(
+        &self,
+        project: &str,
+        status: Option<EntryStatus>,
+    ) -> Result<Vec<Entry>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, project, description, start, end, status, days, issue, location
+             FROM entries
+             WHERE project = ?1 AND (?2 IS NULL OR status = ?2)
+             ORDER BY start DESC",
+        )?;
+        let entries = stmt.query_map(params![project, status], entry_from_row)?;
+        entries.collect()
     }
 
     /// Delete an entry by ID.
@@ -86,30 +248,30 @@ impl Db {
         Ok(rows_affected > 0)
     }
 
+    /// Delete several entries in one transaction. Returns how many were deleted.
+    pub fn delete_all(&self, ids: &[i64]) -> Result<usize> {
+        let tx = self.write_transaction()?;
+        let mut deleted = 0;
+        for id in ids {
+            deleted += self
+                .conn
+                .execute("DELETE FROM entries WHERE id = ?1", params![id])?;
+        }
+        tx.commit()?;
+        Ok(deleted)
+    }
+
     /// Get the active (unended) timer entry, if any.
     pub fn get_active_entry(&self) -> Result<Option<Entry>> {
         let mut stmt = self.conn.prepare(
-            "SELECT id, project, description, start, end, billed
+            "SELECT id, project, description, start, end, status, days, issue, location
              FROM entries
              WHERE end IS NULL
              ORDER BY start DESC
              LIMIT 1",
         )?;
 
-        let entry = stmt
-            .query_row([], |row| {
-                Ok(Entry {
-                    id: row.get(0)?,
-                    project: row.get(1)?,
-                    description: row.get(2)?,
-                    start: DateTime::parse_from_rfc3339(row.get::<_, String>(3)?.as_str())
-                        .unwrap()
-                        .with_timezone(&Utc),
-                    end: None,
-                    billed: row.get::<_, i64>(5)? != 0,
-                })
-            })
-            .optional()?;
+        let entry = stmt.query_row([], entry_from_row).optional()?;
 
         Ok(entry)
     }
@@ -117,28 +279,266 @@ impl Db {
     /// Start a new timer for a project.
     /// Returns the created entry.
     pub fn start_timer(&self, project: &str, description: &str) -> Result<Entry> {
+        self.start_timer_at(project, description, Utc::now())
+    }
+
+    /// Start a new timer for a project that began at `start`, for when it was
+    /// forgotten. Callers check `entry_ending_after` first to avoid overlaps.
+    /// Returns the created entry.
+    pub fn start_timer_at(
+        &self,
+        project: &str,
+        description: &str,
+        start: DateTime<Utc>,
+    ) -> Result<Entry> {
         let entry = Entry {
             id: 0,
             project: project.to_string(),
             description: description.to_string(),
-            start: Utc::now(),
+            start,
             end: None,
-            billed: false,
+            status: EntryStatus::Open,
+            days: None,
+            issue: None,
+            location: None,
         };
-        self.insert(&entry)?;
+        let id = self.insert(&entry)?;
+        Ok(Entry { id, ..entry })
+    }
 
-        // Get the inserted entry with its ID
-        self.get_active_entry()?
-            .ok_or(rusqlite::Error::QueryReturnedNoRows)
+    /// The finished timed entry ending last, if it ends after `time`: the one a
+    /// timer started at `time` would overlap.
+    pub fn entry_ending_after(&self, time: DateTime<Utc>) -> Result<Option<Entry>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, project, description, start, end, status, days, issue, location
+             FROM entries
+             WHERE end IS NOT NULL AND days IS NULL AND end > ?1
+             ORDER BY end DESC
+             LIMIT 1",
+        )?;
+        stmt.query_row(params![time.to_rfc3339()], entry_from_row)
+            .optional()
+    }
+
+    /// The finished timed entry that ended last: the one `meter resume` restarts.
+    pub fn last_stopped_entry(&self) -> Result<Option<Entry>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, project, description, start, end, status, days, issue, location
+             FROM entries
+             WHERE end IS NOT NULL AND days IS NULL
+             ORDER BY end DESC
+             LIMIT 1",
+        )?;
+        stmt.query_row([], entry_from_row).optional()
+    }
+
+    /// Seconds tracked from `since` until `now`: only the part of an entry after
+    /// `since` counts, and the running timer counts up to `now`. Day entries
+    /// aren't timed, so they don't count.
+    pub fn seconds_tracked_since(&self, since: DateTime<Utc>, now: DateTime<Utc>) -> Result<i64> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, project, description, start, end, status, days, issue, location
+             FROM entries
+             WHERE days IS NULL AND (end IS NULL OR end > ?1)",
+        )?;
+        let entries = stmt.query_map(params![since.to_rfc3339()], entry_from_row)?;
+        let mut secs = 0;
+        for entry in entries {
+            let entry = entry?;
+            let end = entry.end.unwrap_or(now).min(now);
+            secs += (end - entry.start.max(since)).num_seconds().max(0);
+        }
+        Ok(secs)
+    }
+
+    /// Give an entry a target duration, making its timer a countdown.
+    pub fn set_timer_target(&self, entry_id: i64, target_secs: i64) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO timer_targets (entry_id, target_secs, notified)
+             VALUES (?1, ?2, 0)",
+            params![entry_id, target_secs],
+        )?;
+        Ok(())
+    }
+
+    /// The target duration of an entry's countdown timer, if it has one.
+    pub fn timer_target(&self, entry_id: i64) -> Result<Option<i64>> {
+        self.conn
+            .query_row(
+                "SELECT target_secs FROM timer_targets WHERE entry_id = ?1",
+                params![entry_id],
+                |row| row.get(0),
+            )
+            .optional()
+    }
+
+    /// Claim the completion notification for an entry's countdown. Returns true
+    /// for the first caller only, so the TUI and menu bar app don't both notify.
+    pub fn claim_target_notification(&self, entry_id: i64) -> Result<bool> {
+        let changed = self.conn.execute(
+            "UPDATE timer_targets SET notified = 1 WHERE entry_id = ?1 AND notified = 0",
+            params![entry_id],
+        )?;
+        Ok(changed == 1)
+    }
+
+    /// An entry's tags, alphabetically.
+    pub fn entry_tags(&self, entry_id: i64) -> Result<Vec<String>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT tag FROM entry_tags WHERE entry_id = ?1 ORDER BY tag")?;
+        let rows = stmt.query_map(params![entry_id], |row| row.get(0))?;
+        rows.collect()
+    }
+
+    /// The tags of every tagged entry, by entry ID.
+    pub fn all_entry_tags(&self) -> Result<HashMap<i64, Vec<String>>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT entry_id, tag FROM entry_tags ORDER BY entry_id, tag")?;
+        let rows = stmt.query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get(1)?)))?;
+        let mut tags: HashMap<i64, Vec<String>> = HashMap::new();
+        for row in rows {
+            let (entry_id, tag) = row?;
+            tags.entry(entry_id).or_default().push(tag);
+        }
+        Ok(tags)
+    }
+
+    /// Every tag in use with how many entries have it, alphabetically.
+    pub fn tag_counts(&self) -> Result<Vec<(String, i64)>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT tag, COUNT(*) FROM entry_tags GROUP BY tag ORDER BY tag")?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        rows.collect()
+    }
+
+    /// Rename a tag on every entry that has it. Renaming to a tag already in use
+    /// merges the two. Returns how many entries had the old tag.
+    pub fn rename_tag(&self, tag: &str, new_tag: &str) -> Result<usize> {
+        let tx = self.write_transaction()?;
+        self.conn.execute(
+            "INSERT OR IGNORE INTO entry_tags (entry_id, tag)
+             SELECT entry_id, ?2 FROM entry_tags WHERE tag = ?1",
+            params![tag, new_tag],
+        )?;
+        let renamed = self
+            .conn
+            .execute("DELETE FROM entry_tags WHERE tag = ?1", params![tag])?;
+        // The merged tag keeps its own color, if it has one
+        self.conn.execute(
+            "UPDATE OR IGNORE tag_colors SET tag = ?2 WHERE tag = ?1",
+            params![tag, new_tag],
+        )?;
+        self.conn
+            .execute("DELETE FROM tag_colors WHERE tag = ?1", params![tag])?;
+        tx.commit()?;
+        Ok(renamed)
+    }
+
+    /// Take a tag off every entry. Returns how many entries had it.
+    pub fn delete_tag(&self, tag: &str) -> Result<usize> {
+        let tx = self.write_transaction()?;
+        let removed = self
+            .conn
+            .execute("DELETE FROM entry_tags WHERE tag = ?1", params![tag])?;
+        self.conn
+            .execute("DELETE FROM tag_colors WHERE tag = ?1", params![tag])?;
+        tx.commit()?;
+        Ok(removed)
+    }
+
+    /// The colors given to tags, by tag.
+    pub fn tag_colors(&self) -> Result<HashMap<String, String>> {
+        let mut stmt = self.conn.prepare("SELECT tag, color FROM tag_colors")?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        rows.collect()
+    }
+
+    /// Give a tag a color, or take it away with `None`.
+    pub fn set_tag_color(&self, tag: &str, color: Option<&str>) -> Result<()> {
+        match color {
+            Some(color) => self.conn.execute(
+                "INSERT OR REPLACE INTO tag_colors (tag, color) VALUES (?1, ?2)",
+                params![tag, color],
+            )?,
+            None => self
+                .conn
+                .execute("DELETE FROM tag_colors WHERE tag = ?1", params![tag])?,
+        };
+        Ok(())
+    }
+
+    /// Tag an entry. Tags it already has are left as they are.
+    pub fn add_entry_tags(&self, entry_id: i64, tags: &[String]) -> Result<()> {
+        for tag in tags {
+            self.conn.execute(
+                "INSERT OR IGNORE INTO entry_tags (entry_id, tag) VALUES (?1, ?2)",
+                params![entry_id, tag],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Take tags off an entry. Returns how many it had.
+    pub fn remove_entry_tags(&self, entry_id: i64, tags: &[String]) -> Result<usize> {
+        let mut removed = 0;
+        for tag in tags {
+            removed += self.conn.execute(
+                "DELETE FROM entry_tags WHERE entry_id = ?1 AND tag = ?2",
+                params![entry_id, tag],
+            )?;
+        }
+        Ok(removed)
+    }
+
+    /// Flag entries as needing review before they are invoiced.
+    pub fn flag_for_review(&self, ids: &[i64], reason: ReviewReason) -> Result<()> {
+        for id in ids {
+            self.conn.execute(
+                "INSERT OR IGNORE INTO review_flags (entry_id, reason) VALUES (?1, ?2)",
+                params![id, reason],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Mark an entry reviewed, clearing every flag on it. Returns how many it had.
+    pub fn clear_review_flags(&self, entry_id: i64) -> Result<usize> {
+        self.conn.execute(
+            "DELETE FROM review_flags WHERE entry_id = ?1",
+            params![entry_id],
+        )
+    }
+
+    /// Why each flagged entry needs review, by entry ID.
+    pub fn review_flags(&self) -> Result<HashMap<i64, Vec<ReviewReason>>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT entry_id, reason FROM review_flags ORDER BY entry_id, reason")?;
+        let rows = stmt.query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get(1)?)))?;
+        let mut flags: HashMap<i64, Vec<ReviewReason>> = HashMap::new();
+        for row in rows {
+            let (entry_id, reason) = row?;
+            flags.entry(entry_id).or_default().push(reason);
+        }
+        Ok(flags)
     }
 
     /// Stop the active timer by setting its end time to now.
     pub fn stop_active_timer(&self) -> Result<Option<Entry>> {
+        self.stop_active_timer_at(Utc::now())
+    }
+
+    /// Stop the active timer with the given end time, which is clamped so it is
+    /// never before the start.
+    pub fn stop_active_timer_at(&self, end: DateTime<Utc>) -> Result<Option<Entry>> {
         if let Some(entry) = self.get_active_entry()? {
-            let now = Utc::now().to_rfc3339();
+            let end = end.max(entry.start).to_rfc3339();
             self.conn.execute(
                 "UPDATE entries SET end = ?1 WHERE id = ?2",
-                params![now, entry.id],
+                params![end, entry.id],
             )?;
             self.get_entry_by_id(entry.id)
         } else {
@@ -146,45 +546,117 @@ impl Db {
         }
     }
 
+    /// Delete the running timer's entry instead of stopping it, for a timer
+    /// started by mistake. Returns the discarded entry, if a timer was running.
+    pub fn cancel_active_timer(&self) -> Result<Option<Entry>> {
+        let Some(entry) = self.get_active_entry()? else {
+            return Ok(None);
+        };
+        self.conn.execute(
+            "DELETE FROM entries WHERE id = ?1 AND end IS NULL",
+            params![entry.id],
+        )?;
+        Ok(Some(entry))
+    }
+
+    /// Stop the active timer and start one for another project in one transaction,
+    /// the new entry starting the moment the old one ends.
+    /// Returns the stopped entry, if a timer was running, and the started one.
+    pub fn switch_timer(&self, project: &str, description: &str) -> Result<(Option<Entry>, Entry)> {
+        let tx = self.write_transaction()?;
+        let now = Utc::now();
+        let stopped = self.stop_active_timer_at(now)?;
+        let entry = Entry {
+            id: 0,
+            project: project.to_string(),
+            description: description.to_string(),
+            start: now,
+            end: None,
+            status: EntryStatus::Open,
+            days: None,
+            issue: None,
+            location: None,
+        };
+        let id = self.insert(&entry)?;
+        tx.commit()?;
+        Ok((stopped, Entry { id, ..entry }))
+    }
+
+    /// Split an ended entry that crosses local midnight into one entry per day.
+    /// The original entry keeps the first day; later days are inserted as new entries
+    /// with the same project, description, status and tags. Every part is flagged
+    /// for review. Returns the number of entries added.
+    pub fn split_entry_at_midnight(&self, id: i64) -> Result<usize> {
+        let Some(entry) = self.get_entry_by_id(id)? else {
+            return Ok(0);
+        };
+        let Some(end) = entry.end else {
+            return Ok(0);
+        };
+        let segments = midnight_segments(entry.start, end);
+        if segments.len() < 2 {
+            return Ok(0);
+        }
+
+        let tx = self.write_transaction()?;
+        tx.execute(
+            "UPDATE entries SET end = ?1 WHERE id = ?2",
+            params![segments[0].1.to_rfc3339(), id],
+        )?;
+        tx.execute(
+            "INSERT OR IGNORE INTO review_flags (entry_id, reason) VALUES (?1, ?2)",
+            params![id, ReviewReason::Split],
+        )?;
+        for (start, end) in &segments[1..] {
+            tx.execute(
+                "INSERT INTO entries (project, description, start, end, status, issue, location)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![
+                    entry.project,
+                    entry.description,
+                    start.to_rfc3339(),
+                    end.to_rfc3339(),
+                    entry.status,
+                    entry.issue,
+                    entry.location,
+                ],
+            )?;
+            let new_id = tx.last_insert_rowid();
+            tx.execute(
+                "INSERT INTO entry_tags (entry_id, tag)
+                 SELECT ?1, tag FROM entry_tags WHERE entry_id = ?2",
+                params![new_id, id],
+            )?;
+            tx.execute(
+                "INSERT INTO review_flags (entry_id, reason)
+                 SELECT ?1, reason FROM review_flags WHERE entry_id = ?2",
+                params![new_id, id],
+            )?;
+        }
+        tx.commit()?;
+        Ok(segments.len() - 1)
+    }
+
     /// Get a single entry by ID.
     pub fn get_entry_by_id(&self, id: i64) -> Result<Option<Entry>> {
         let mut stmt = self.conn.prepare(
-            "SELECT id, project, description, start, end, billed
+            "SELECT id, project, description, start, end, status, days, issue, location
              FROM entries
              WHERE id = ?1",
         )?;
 
-        stmt.query_row(params![id], |row| {
-            Ok(Entry {
-                id: row.get(0)?,
-                project: row.get(1)?,
-                description: row.get(2)?,
-                start: DateTime::parse_from_rfc3339(row.get::<_, String>(3)?.as_str())
-                    .unwrap()
-                    .with_timezone(&Utc),
-                end: match row.get::<_, Option<String>>(4)? {
-                    Some(s) => Some(
-                        DateTime::parse_from_rfc3339(&s)
-                            .unwrap()
-                            .with_timezone(&Utc),
-                    ),
-                    None => None,
-                },
-                billed: row.get::<_, i64>(5)? != 0,
-            })
-        })
-        .optional()
+        stmt.query_row(params![id], entry_from_row).optional()
     }
 
-    /// List entries within a date range, optionally filtered by billed status.
+    /// List entries within a date range, optionally filtered by billing status.
     pub fn list_by_date_range(
         &self,
         start: DateTime<Utc>,
         end: DateTime<Utc>,
-        billed: Option<bool>,
+        status: Option<EntryStatus>,
     ) -> Result<Vec<Entry>> {
         let mut stmt = self.conn.prepare(
-            "SELECT id, project, description, start, end, billed
+            "SELECT id, project, description, start, end, status, days, issue, location
              FROM entries
              WHERE end IS NOT NULL
                AND end >= ?1
@@ -192,88 +664,108 @@ impl Db {
              ORDER BY start DESC",
         )?;
 
-        let entries_iter =
-            stmt.query_map(params![start.to_rfc3339(), end.to_rfc3339()], |row| {
-                Ok(Entry {
-                    id: row.get(0)?,
-                    project: row.get(1)?,
-                    description: row.get(2)?,
-                    start: DateTime::parse_from_rfc3339(row.get::<_, String>(3)?.as_str())
-                        .unwrap()
-                        .with_timezone(&Utc),
-                    end: match row.get::<_, Option<String>>(4)? {
-                        Some(s) => Some(
-                            DateTime::parse_from_rfc3339(&s)
-                                .unwrap()
-                                .with_timezone(&Utc),
-                        ),
-                        None => None,
-                    },
-                    billed: row.get::<_, i64>(5)? != 0,
-                })
-            })?;
+        let entries_iter = stmt.query_map(
+            params![start.to_rfc3339(), end.to_rfc3339()],
+            entry_from_row,
+        )?;
 
         let mut entries = Vec::new();
         for e in entries_iter {
             let e = e?;
-            if let Some(b) = billed {
-                if e.billed != b {
-                    continue;
-                }
+            if let Some(s) = status
+                && e.status != s
+            {
+                continue;
             }
             entries.push(e);
         }
         Ok(entries)
     }
 
-    /// Mark an entry as billed.
-    pub fn mark_billed(&self, id: i64) -> Result<bool> {
-        let rows_affected = self
-            .conn
-            .execute("UPDATE entries SET billed = 1 WHERE id = ?1", params![id])?;
+    /// Move an entry to a new billing status.
+    /// Returns false if the entry doesn't exist or its current status can't move to `status`
+    /// (see `EntryStatus::can_transition_to`).
+    pub fn set_entry_status(&self, id: i64, status: EntryStatus) -> Result<bool> {
+        let rows_affected = self.conn.execute(
+            &format!(
+                "UPDATE entries SET status = ?1 WHERE id = ?2 AND status IN ({})",
+                status_list(&EntryStatus::allowed_from(status))
+            ),
+            params![status, id],
+        )?;
         Ok(rows_affected > 0)
     }
 
-    /// Mark all pending entries as billed.
-    pub fn mark_all_billed(&self) -> Result<usize> {
-        let rows_affected = self
-            .conn
-            .execute("UPDATE entries SET billed = 1 WHERE billed = 0", params![])?;
-        Ok(rows_affected)
-    }
-
-    /// Mark an entry as unbilled.
-    pub fn unmark_billed(&self, id: i64) -> Result<bool> {
-        let rows_affected = self
-            .conn
-            .execute("UPDATE entries SET billed = 0 WHERE id = ?1", params![id])?;
-        Ok(rows_affected > 0)
+    /// Move several entries to `status` in one transaction. Entries whose current
+    /// status can't reach `status` are skipped. Returns the IDs that changed.
+    pub fn set_entries_status(&self, ids: &[i64], status: EntryStatus) -> Result<Vec<i64>> {
+        let sql = format!(
+            "UPDATE entries SET status = ?1 WHERE id = ?2 AND status IN ({})",
+            status_list(&EntryStatus::allowed_from(status))
+        );
+        let tx = self.write_transaction()?;
+        let mut changed = Vec::new();
+        for id in ids {
+            if tx.execute(&sql, params![status, id])? > 0 {
+                changed.push(*id);
+            }
+        }
+        tx.commit()?;
+        Ok(changed)
     }
 
-    /// Mark all billed entries as unbilled.
-    pub fn unmark_all_billed(&self) -> Result<usize> {
-        let rows_affected = self
-            .conn
-            .execute("UPDATE entries SET billed = 0 WHERE billed = 1", params![])?;
+    /// Move every entry that is allowed to reach `status` there.
+    /// Returns the number of entries updated.
+    pub fn set_all_entries_status(&self, status: EntryStatus) -> Result<usize> {
+        let rows_affected = self.conn.execute(
+            &format!(
+                "UPDATE entries SET status = ?1 WHERE status IN ({})",
+                status_list(&EntryStatus::allowed_from(status))
+            ),
+            params![status],
+        )?;
         Ok(rows_affected)
     }
 
-    /// Update an entry's fields.
+    /// Update an entry's fields. The billing status is left alone; use `set_entry_status`.
     pub fn update_entry(&self, entry: &Entry) -> Result<bool> {
         let rows_affected = self.conn.execute(
-            "UPDATE entries SET project = ?1, description = ?2, start = ?3, end = ?4, billed = ?5 WHERE id = ?6",
+            "UPDATE entries SET project = ?1, description = ?2, start = ?3, end = ?4, issue = ?5,
+                    location = ?6
+             WHERE id = ?7",
             params![
                 entry.project,
                 entry.description,
                 entry.start.to_rfc3339(),
                 entry.end.map(|e| e.to_rfc3339()),
-                if entry.billed { 1 } else { 0 },
+                entry.issue,
+                entry.location,
                 entry.id,
             ],
         )?;
         Ok(rows_affected > 0)
     }
 
+    /// Schema version of the open database (see `models::SCHEMA_VERSION`).
+    pub fn schema_version(&self) -> Result<i64> {
+        self.conn
+            .query_row("PRAGMA user_version", [], |row| row.get(0))
+    }
+
+    /// Whether nothing has been set up yet: no entries, projects, clients or invoices,
+    /// and no business name.
+    pub fn is_fresh(&self) -> Result<bool> {
+        let count: i64 = self.conn.query_row(
+            "SELECT (SELECT COUNT(*) FROM entries)
+                  + (SELECT COUNT(*) FROM projects)
+                  + (SELECT COUNT(*) FROM clients)
+                  + (SELECT COUNT(*) FROM invoices)",
+            [],
+            |row| row.get(0),
+        )?;
+        Ok(count == 0 && self.get_invoice_settings()?.business_name.is_empty())
+    }
+
     // === Project Methods ===
 
     /// Get or create a project by name.
@@ -293,17 +785,24 @@ impl Db {
 
     /// Get project by name.
     pub fn get_project_by_name(&self, name: &str) -> Result<Option<Project>> {
-        let mut stmt = self
-            .conn
-            .prepare("SELECT id, name, rate, currency FROM projects WHERE name = ?1")?;
+        let mut stmt = self.conn.prepare(
+            "SELECT id, name, rate, currency, client_id, hour_cap, cap_period, billing_mode,
+                    idle_stop_minutes, area
+             FROM projects WHERE name = ?1",
+        )?;
 
         stmt.query_row(params![name], |row| {
-            let rate_str: Option<String> = row.get(2)?;
             Ok(Project {
                 id: row.get(0)?,
                 name: row.get(1)?,
-                rate: rate_str.and_then(|s| s.parse().ok()),
+                rate: row.get(2)?,
                 currency: row.get(3)?,
+                client_id: row.get(4)?,
+                hour_cap: row.get(5)?,
+                cap_period: row.get(6)?,
+                billing_mode: row.get(7)?,
+                idle_stop_minutes: row.get(8)?,
+                area: row.get(9)?,
             })
         })
         .optional()
@@ -311,17 +810,24 @@ impl Db {
 
     /// List all projects.
     pub fn list_projects(&self) -> Result<Vec<Project>> {
-        let mut stmt = self
-            .conn
-            .prepare("SELECT id, name, rate, currency FROM projects ORDER BY name")?;
+        let mut stmt = self.conn.prepare(
+            "SELECT id, name, rate, currency, client_id, hour_cap, cap_period, billing_mode,
+                    idle_stop_minutes, area
+             FROM projects ORDER BY name",
+        )?;
 
         let projects = stmt.query_map([], |row| {
-            let rate_str: Option<String> = row.get(2)?;
             Ok(Project {
                 id: row.get(0)?,
                 name: row.get(1)?,
-                rate: rate_str.and_then(|s| s.parse().ok()),
+                rate: row.get(2)?,
                 currency: row.get(3)?,
+                client_id: row.get(4)?,
+                hour_cap: row.get(5)?,
+                cap_period: row.get(6)?,
+                billing_mode: row.get(7)?,
+                idle_stop_minutes: row.get(8)?,
+                area: row.get(9)?,
             })
         })?;
 
@@ -332,54 +838,227 @@ impl Db {
     pub fn set_project_rate(
         &self,
         name: &str,
-        rate: Option<f64>,
+        rate: Option<Money>,
         currency: Option<&str>,
     ) -> Result<bool> {
+        if let Some(Err(e)) = rate.map(|r| validate_rate(r.as_f64())) {
+            return Err(rusqlite::Error::ToSqlConversionFailure(e.into()));
+        }
         self.get_or_create_project(name)?;
 
-        let rate_str = rate.map(|r| format!("{:.2}", r));
         let rows = self.conn.execute(
             "UPDATE projects SET rate = ?1, currency = COALESCE(?2, currency) WHERE name = ?3",
-            params![rate_str, currency, name],
+            params![rate, currency, name],
         )?;
 
         Ok(rows > 0)
     }
 
-    /// Get distinct project names from entries (for migration/sync).
-    pub fn get_distinct_entry_projects(&self) -> Result<Vec<String>> {
-        let mut stmt = self
-            .conn
-            .prepare("SELECT DISTINCT project FROM entries ORDER BY project")?;
+    /// Link a project to the client it is billed to (or unlink with `None`).
+    pub fn set_project_client(&self, name: &str, client_id: Option<i64>) -> Result<bool> {
+        self.get_or_create_project(name)?;
 
-        let projects = stmt.query_map([], |row| row.get(0))?;
-        projects.collect()
-    }
+        let rows = self.conn.execute(
+            "UPDATE projects SET client_id = ?1 WHERE name = ?2",
+            params![client_id, name],
+        )?;
 
-    /// Sync projects table with existing entry projects.
-    pub fn sync_projects_from_entries(&self) -> Result<()> {
-        let entry_projects = self.get_distinct_entry_projects()?;
-        for name in entry_projects {
-            self.get_or_create_project(&name)?;
-        }
-        Ok(())
+        Ok(rows > 0)
     }
 
-    // === Pomodoro Methods ===
+    /// Set what a project's rate is charged per.
+    pub fn set_project_billing_mode(&self, name: &str, mode: BillingMode) -> Result<bool> {
+        self.get_or_create_project(name)?;
 
-    /// Get the current Pomodoro configuration.
-    pub fn get_pomodoro_config(&self) -> Result<PomodoroConfig> {
-        let mut stmt = self.conn.prepare(
-            "SELECT enabled, work_duration, short_break, long_break, cycles_before_long
-             FROM pomodoro_config WHERE id = 1",
+        let rows = self.conn.execute(
+            "UPDATE projects SET billing_mode = ?1 WHERE name = ?2",
+            params![mode, name],
         )?;
 
-        stmt.query_row([], |row| {
-            Ok(PomodoroConfig {
-                enabled: row.get::<_, i32>(0)? != 0,
-                work_duration: row.get(1)?,
-                short_break: row.get(2)?,
-                long_break: row.get(3)?,
+        Ok(rows > 0)
+    }
+
+    /// Set or clear a project's hour cap.
+    pub fn set_project_cap(
+        &self,
+        name: &str,
+        hour_cap: Option<f64>,
+        period: CapPeriod,
+    ) -> Result<bool> {
+        self.get_or_create_project(name)?;
+
+        let rows = self.conn.execute(
+            "UPDATE projects SET hour_cap = ?1, cap_period = ?2 WHERE name = ?3",
+            params![hour_cap, period, name],
+        )?;
+
+        Ok(rows > 0)
+    }
+
+    /// Set how many idle minutes stop a project's timer (0 = never), or `None`
+    /// to use the default from the timer settings.
+    pub fn set_project_idle_stop(&self, name: &str, minutes: Option<i64>) -> Result<bool> {
+        self.get_or_create_project(name)?;
+
+        let rows = self.conn.execute(
+            "UPDATE projects SET idle_stop_minutes = ?1 WHERE name = ?2",
+            params![minutes, name],
+        )?;
+
+        Ok(rows > 0)
+    }
+
+    /// Set or clear the area a project is grouped under.
+    pub fn set_project_area(&self, name: &str, area: Option<&str>) -> Result<bool> {
+        self.get_or_create_project(name)?;
+
+        let rows = self.conn.execute(
+            "UPDATE projects SET area = ?1 WHERE name = ?2",
+            params![area, name],
+        )?;
+
+        Ok(rows > 0)
+    }
+
+    /// Hours logged on a capped project in the week or month containing `now`,
+    /// counting a running timer up to `now`. `None` if the project has no cap.
+    pub fn project_cap_usage(&self, name: &str, now: DateTime<Utc>) -> Result<Option<CapUsage>> {
+        let Some(project) = self.get_project_by_name(name)? else {
+            return Ok(None);
+        };
+        let Some(cap) = project.hour_cap else {
+            return Ok(None);
+        };
+
+        let (from, to) = project
+            .cap_period
+            .range(now.with_timezone(&Local).date_naive());
+        let mut stmt = self.conn.prepare(
+            "SELECT id, project, description, start, end, status, days, issue, location FROM entries
+             WHERE project = ?1",
+        )?;
+        let entries = stmt.query_map(params![name], entry_from_row)?;
+
+        let mut secs = 0;
+        for e in entries {
+            let e = e?;
+            let day = e.start.with_timezone(&Local).date_naive();
+            if day >= from && day < to {
+                secs += (e.end.unwrap_or(now) - e.start).num_seconds().max(0);
+            }
+        }
+
+        Ok(Some(CapUsage {
+            cap,
+            period: project.cap_period,
+            used: secs as f64 / 3600.0,
+        }))
+    }
+
+    /// Billable hours this month against the monthly target, if one is set.
+    /// Time counts on projects with a rate, by the local day it started; a
+    /// running entry counts up to `now`.
+    pub fn target_pace(&self, now: DateTime<Utc>) -> Result<Option<TargetPace>> {
+        let target = self.get_notification_settings()?.monthly_target_hours;
+        if target <= 0.0 {
+            return Ok(None);
+        }
+
+        let today = now.with_timezone(&Local).date_naive();
+        let (from, to) = CapPeriod::Month.range(today);
+        let mut stmt = self.conn.prepare(
+            "SELECT e.id, e.project, e.description, e.start, e.end, e.status, e.days, e.issue,
+                    e.location
+             FROM entries e JOIN projects p ON p.name = e.project
+             WHERE e.days IS NULL AND p.rate > 0",
+        )?;
+        let entries = stmt.query_map([], entry_from_row)?;
+
+        let mut secs = 0;
+        for e in entries {
+            let e = e?;
+            let day = e.start.with_timezone(&Local).date_naive();
+            if day >= from && day < to {
+                secs += (e.end.unwrap_or(now) - e.start).num_seconds().max(0);
+            }
+        }
+
+        Ok(Some(TargetPace {
+            target,
+            billable: secs as f64 / 3600.0,
+            today,
+        }))
+    }
+
+    /// Total and unbilled hours, unbilled days and last entry start for every project.
+    /// A running entry counts up to `now`.
+    pub fn project_totals(&self, now: DateTime<Utc>) -> Result<HashMap<String, ProjectTotals>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT p.name,
+                    COALESCE(SUM(julianday(COALESCE(e.end, ?1)) - julianday(e.start)), 0) * 24,
+                    COALESCE(SUM(CASE WHEN e.status IN ('open', 'submitted', 'billed')
+                        THEN julianday(COALESCE(e.end, ?1)) - julianday(e.start) END), 0) * 24,
+                    COUNT(DISTINCT CASE WHEN e.status IN ('open', 'submitted', 'billed')
+                        AND e.days IS NULL THEN date(e.start, 'localtime') END)
+                    + COALESCE(SUM(CASE WHEN e.status IN ('open', 'submitted', 'billed')
+                        THEN e.days END), 0),
+                    MAX(e.start)
+             FROM projects p
+             LEFT JOIN entries e ON e.project = p.name
+             GROUP BY p.name",
+        )?;
+        let rows = stmt.query_map(params![now.to_rfc3339()], |row| {
+            let last: Option<String> = row.get(4)?;
+            Ok((
+                row.get::<_, String>(0)?,
+                ProjectTotals {
+                    total_hours: row.get(1)?,
+                    unbilled_hours: row.get(2)?,
+                    unbilled_days: row.get(3)?,
+                    last_entry: last
+                        .and_then(|s| DateTime::parse_from_rfc3339(&s).ok())
+                        .map(|d| d.with_timezone(&Utc)),
+                },
+            ))
+        })?;
+
+        rows.collect()
+    }
+
+    /// Get distinct project names from entries (for migration/sync).
+    pub fn get_distinct_entry_projects(&self) -> Result<Vec<String>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT DISTINCT project FROM entries ORDER BY project")?;
+
+        let projects = stmt.query_map([], |row| row.get(0))?;
+        projects.collect()
+    }
+
+    /// Sync projects table with existing entry projects.
+    pub fn sync_projects_from_entries(&self) -> Result<()> {
+        let entry_projects = self.get_distinct_entry_projects()?;
+        for name in entry_projects {
+            self.get_or_create_project(&name)?;
+        }
+        Ok(())
+    }
+
+    // === Pomodoro Methods ===
+
+    /// Get the current Pomodoro configuration.
+    pub fn get_pomodoro_config(&self) -> Result<PomodoroConfig> {
+        let mut stmt = self.conn.prepare(
+            "SELECT enabled, work_duration, short_break, long_break, cycles_before_long
+             FROM pomodoro_config WHERE id = 1",
+        )?;
+
+        stmt.query_row([], |row| {
+            Ok(PomodoroConfig {
+                enabled: row.get::<_, i32>(0)? != 0,
+                work_duration: row.get(1)?,
+                short_break: row.get(2)?,
+                long_break: row.get(3)?,
                 cycles_before_long: row.get(4)?,
             })
         })
@@ -415,231 +1094,2702 @@ impl Db {
         Ok(())
     }
 
-    // === Invoice Settings Methods ===
-
-    /// Get the current invoice settings.
-    pub fn get_invoice_settings(&self) -> Result<InvoiceSettings> {
-        let mut stmt = self.conn.prepare(
-            "SELECT business_name, address_street, address_city, address_state,
-                    address_postal, address_country, email, phone, tax_id,
-                    payment_instructions, default_payment_terms, default_tax_rate
-             FROM invoice_settings WHERE id = 1",
-        )?;
+    // === Timer Settings Methods ===
 
-        stmt.query_row([], |row| {
-            Ok(InvoiceSettings {
-                business_name: row.get(0)?,
-                address_street: row.get(1)?,
-                address_city: row.get(2)?,
-                address_state: row.get(3)?,
-                address_postal: row.get(4)?,
-                address_country: row.get(5)?,
-                email: row.get(6)?,
-                phone: row.get(7)?,
-                tax_id: row.get(8)?,
-                payment_instructions: row.get(9)?,
-                default_payment_terms: row.get(10)?,
-                default_tax_rate: row.get(11)?,
-            })
-        })
+    /// Get the current timer settings.
+    pub fn get_timer_settings(&self) -> Result<TimerSettings> {
+        self.conn.query_row(
+            "SELECT reuse_last_description, min_duration_secs, discard_short_entries,
+                    split_at_midnight, track_activity, idle_stop_minutes, issue_from_git_branch
+             FROM timer_settings WHERE id = 1",
+            [],
+            |row| {
+                Ok(TimerSettings {
+                    reuse_last_description: row.get::<_, i64>(0)? != 0,
+                    min_duration_secs: row.get(1)?,
+                    discard_short_entries: row.get::<_, i64>(2)? != 0,
+                    split_at_midnight: row.get::<_, i64>(3)? != 0,
+                    track_activity: row.get::<_, i64>(4)? != 0,
+                    idle_stop_minutes: row.get(5)?,
+                    issue_from_git_branch: row.get::<_, i64>(6)? != 0,
+                })
+            },
+        )
     }
 
-    /// Update invoice settings.
-    pub fn set_invoice_settings(&self, settings: &InvoiceSettings) -> Result<()> {
+    /// Update the timer settings.
+    pub fn set_timer_settings(&self, settings: &TimerSettings) -> Result<()> {
         self.conn.execute(
-            "UPDATE invoice_settings SET
-                business_name = ?1,
-                address_street = ?2,
-                address_city = ?3,
-                address_state = ?4,
-                address_postal = ?5,
-                address_country = ?6,
-                email = ?7,
-                phone = ?8,
-                tax_id = ?9,
-                payment_instructions = ?10,
-                default_payment_terms = ?11,
-                default_tax_rate = ?12
+            "UPDATE timer_settings SET
+                reuse_last_description = ?1,
+                min_duration_secs = ?2,
+                discard_short_entries = ?3,
+                split_at_midnight = ?4,
+                track_activity = ?5,
+                idle_stop_minutes = ?6,
+                issue_from_git_branch = ?7
              WHERE id = 1",
             params![
-                settings.business_name,
-                settings.address_street,
-                settings.address_city,
-                settings.address_state,
-                settings.address_postal,
-                settings.address_country,
-                settings.email,
-                settings.phone,
-                settings.tax_id,
-                settings.payment_instructions,
-                settings.default_payment_terms,
-                settings.default_tax_rate,
+                if settings.reuse_last_description {
+                    1
+                } else {
+                    0
+                },
+                settings.min_duration_secs,
+                if settings.discard_short_entries { 1 } else { 0 },
+                if settings.split_at_midnight { 1 } else { 0 },
+                if settings.track_activity { 1 } else { 0 },
+                settings.idle_stop_minutes,
+                if settings.issue_from_git_branch { 1 } else { 0 },
             ],
         )?;
         Ok(())
     }
 
-    // === Client Methods ===
+    // === Activity Methods ===
 
-    /// Add a new client.
-    pub fn add_client(&self, client: &Client) -> Result<i64> {
+    /// Record that `app` is frontmost at `now`, extending the latest span when it
+    /// is the same app and was seen recently, and pruning old activity.
+    #[cfg(target_os = "macos")]
+    pub fn record_activity(&self, app: &str, now: DateTime<Utc>) -> Result<()> {
+        let last: Option<(i64, String, String)> = self
+            .conn
+            .query_row(
+                "SELECT id, app, end FROM activity ORDER BY end DESC LIMIT 1",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .optional()?;
+        let extends = last.as_ref().is_some_and(|(_, last_app, end)| {
+            last_app == app
+                && DateTime::parse_from_rfc3339(end).is_ok_and(|end| {
+                    (now - end.with_timezone(&Utc)).num_seconds() <= ACTIVITY_GAP_SECS
+                })
+        });
+        match last {
+            Some((id, ..)) if extends => {
+                self.conn.execute(
+                    "UPDATE activity SET end = ?1 WHERE id = ?2",
+                    params![now.to_rfc3339(), id],
+                )?;
+            }
+            _ => {
+                self.conn.execute(
+                    "INSERT INTO activity (app, start, end) VALUES (?1, ?2, ?2)",
+                    params![app, now.to_rfc3339()],
+                )?;
+            }
+        }
+        let cutoff = now - Days::new(ACTIVITY_RETENTION_DAYS);
         self.conn.execute(
-            "INSERT INTO clients (name, contact_person, address_street, address_city,
-                                  address_state, address_postal, address_country, email)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
-            params![
-                client.name,
-                client.contact_person,
-                client.address_street,
-                client.address_city,
-                client.address_state,
-                client.address_postal,
-                client.address_country,
-                client.email,
-            ],
+            "DELETE FROM activity WHERE end < ?1",
+            params![cutoff.to_rfc3339()],
         )?;
-        Ok(self.conn.last_insert_rowid())
+        Ok(())
     }
 
-    /// Get a client by ID.
-    pub fn get_client(&self, id: i64) -> Result<Option<Client>> {
+    /// Activity spans lasting at least `min_secs` that haven't been dismissed and
+    /// don't overlap any time entry, newest first.
+    pub fn activity_suggestions(&self, min_secs: i64) -> Result<Vec<ActivitySpan>> {
         let mut stmt = self.conn.prepare(
-            "SELECT id, name, contact_person, address_street, address_city,
-                    address_state, address_postal, address_country, email
-             FROM clients WHERE id = ?1",
+            "SELECT a.id, a.app, a.start, a.end FROM activity a
+             WHERE a.dismissed = 0
+               AND (julianday(a.end) - julianday(a.start)) * 86400 >= ?1
+               AND NOT EXISTS (
+                   SELECT 1 FROM entries e
+                   WHERE julianday(e.start) < julianday(a.end)
+                     AND julianday(COALESCE(e.end, ?2)) > julianday(a.start)
+               )
+             ORDER BY a.start DESC",
         )?;
+        let rows = stmt.query_map(
+            params![min_secs, Utc::now().to_rfc3339()],
+            activity_from_row,
+        )?;
+        rows.collect()
+    }
 
-        stmt.query_row(params![id], |row| {
-            Ok(Client {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                contact_person: row.get(2)?,
-                address_street: row.get(3)?,
-                address_city: row.get(4)?,
-                address_state: row.get(5)?,
-                address_postal: row.get(6)?,
-                address_country: row.get(7)?,
-                email: row.get(8)?,
-            })
-        })
-        .optional()
+    /// Get one recorded activity span.
+    pub fn get_activity(&self, id: i64) -> Result<Option<ActivitySpan>> {
+        self.conn
+            .query_row(
+                "SELECT id, app, start, end FROM activity WHERE id = ?1",
+                params![id],
+                activity_from_row,
+            )
+            .optional()
     }
 
-    /// List all clients.
-    pub fn list_clients(&self) -> Result<Vec<Client>> {
-        let mut stmt = self.conn.prepare(
-            "SELECT id, name, contact_person, address_street, address_city,
-                    address_state, address_postal, address_country, email
-             FROM clients ORDER BY name",
+    /// Stop suggesting an activity span. Returns false if there is no such span.
+    pub fn dismiss_activity(&self, id: i64) -> Result<bool> {
+        let changed = self.conn.execute(
+            "UPDATE activity SET dismissed = 1 WHERE id = ?1",
+            params![id],
         )?;
-
-        let clients = stmt.query_map([], |row| {
-            Ok(Client {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                contact_person: row.get(2)?,
-                address_street: row.get(3)?,
-                address_city: row.get(4)?,
-                address_state: row.get(5)?,
-                address_postal: row.get(6)?,
-                address_country: row.get(7)?,
-                email: row.get(8)?,
-            })
-        })?;
-
-        clients.collect()
+        Ok(changed > 0)
     }
 
-    /// Update a client.
-    pub fn update_client(&self, client: &Client) -> Result<bool> {
-        let rows = self.conn.execute(
-            "UPDATE clients SET
-                name = ?1,
-                contact_person = ?2,
-                address_street = ?3,
-                address_city = ?4,
-                address_state = ?5,
-                address_postal = ?6,
-                address_country = ?7,
-                email = ?8
-             WHERE id = ?9",
+    // === Expense Methods ===
+
+    /// Record an expense. Returns the new ID.
+    pub fn add_expense(&self, expense: &Expense) -> Result<i64> {
+        self.conn.execute(
+            "INSERT INTO expenses
+                (date, kind, description, amount, distance, unit, entry_id, client_id)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
             params![
-                client.name,
-                client.contact_person,
-                client.address_street,
-                client.address_city,
-                client.address_state,
-                client.address_postal,
-                client.address_country,
-                client.email,
-                client.id,
+                expense.date.format("%Y-%m-%d").to_string(),
+                expense.kind,
+                expense.description,
+                expense.amount,
+                expense.distance,
+                expense.unit,
+                expense.entry_id,
+                expense.client_id,
             ],
         )?;
-        Ok(rows > 0)
+        Ok(self.conn.last_insert_rowid())
     }
 
-    /// Delete a client.
-    pub fn delete_client(&self, id: i64) -> Result<bool> {
-        let rows = self
-            .conn
-            .execute("DELETE FROM clients WHERE id = ?1", params![id])?;
-        Ok(rows > 0)
+    /// All expenses, oldest first.
+    pub fn list_expenses(&self) -> Result<Vec<Expense>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, date, kind, description, amount, distance, unit, entry_id, client_id,
+                    invoice_number
+             FROM expenses ORDER BY date, id",
+        )?;
+        let rows = stmt.query_map([], expense_from_row)?;
+        rows.collect()
     }
 
-    // === Invoice Record Methods ===
+    /// Note the invoice an expense was billed on.
+    pub fn set_expense_invoiced(&self, id: i64, invoice_number: i64) -> Result<()> {
+        self.conn.execute(
+            "UPDATE expenses SET invoice_number = ?1 WHERE id = ?2",
+            params![invoice_number, id],
+        )?;
+        Ok(())
+    }
 
-    /// Get the next invoice number.
-    pub fn get_next_invoice_number(&self) -> Result<i64> {
-        let mut stmt = self
+    /// Delete an expense. Returns whether it existed.
+    pub fn delete_expense(&self, id: i64) -> Result<bool> {
+        let changed = self
             .conn
-            .prepare("SELECT COALESCE(MAX(invoice_number), 0) + 1 FROM invoices")?;
-        stmt.query_row([], |row| row.get(0))
+            .execute("DELETE FROM expenses WHERE id = ?1", params![id])?;
+        Ok(changed > 0)
     }
 
-    /// Record a generated invoice.
-    pub fn record_invoice(&self, invoice: &Invoice) -> Result<i64> {
+    /// Get the mileage rate.
+    pub fn get_mileage_settings(&self) -> Result<MileageSettings> {
+        self.conn.query_row(
+            "SELECT rate, unit FROM mileage_settings WHERE id = 1",
+            [],
+            |row| {
+                Ok(MileageSettings {
+                    rate: row.get(0)?,
+                    unit: row.get(1)?,
+                })
+            },
+        )
+    }
+
+    /// Update the mileage rate.
+    pub fn set_mileage_settings(&self, settings: &MileageSettings) -> Result<()> {
         self.conn.execute(
-            "INSERT INTO invoices (invoice_number, client_id, date_issued, due_date,
-                                   subtotal, tax_rate, tax_amount, total, file_path)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            "UPDATE mileage_settings SET rate = ?1, unit = ?2 WHERE id = 1",
+            params![settings.rate, settings.unit],
+        )?;
+        Ok(())
+    }
+
+    // === Attachment Methods ===
+
+    /// Record a stored file against an expense or an invoice. Returns the new ID.
+    pub fn add_attachment(
+        &self,
+        expense_id: Option<i64>,
+        invoice_number: Option<i64>,
+        name: &str,
+        path: &str,
+    ) -> Result<i64> {
+        self.conn.execute(
+            "INSERT INTO attachments (expense_id, invoice_number, name, path, added_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
             params![
-                invoice.invoice_number,
-                invoice.client_id,
-                invoice.date_issued,
-                invoice.due_date,
-                invoice.subtotal,
-                invoice.tax_rate,
-                invoice.tax_amount,
-                invoice.total,
-                invoice.file_path,
+                expense_id,
+                invoice_number,
+                name,
+                path,
+                Utc::now().to_rfc3339()
             ],
         )?;
         Ok(self.conn.last_insert_rowid())
     }
 
-    /// List all recorded invoices.
-    pub fn list_invoices(&self) -> Result<Vec<Invoice>> {
+    /// All attachments, newest first.
+    pub fn list_attachments(&self) -> Result<Vec<Attachment>> {
         let mut stmt = self.conn.prepare(
-            "SELECT id, invoice_number, client_id, date_issued, due_date,
-                    subtotal, tax_rate, tax_amount, total, file_path
-             FROM invoices ORDER BY invoice_number DESC",
+            "SELECT id, expense_id, invoice_number, name, path, added_at
+             FROM attachments ORDER BY id DESC",
         )?;
+        let rows = stmt.query_map([], attachment_from_row)?;
+        rows.collect()
+    }
 
-        let invoices = stmt.query_map([], |row| {
-            Ok(Invoice {
+    /// Get an attachment by ID.
+    pub fn get_attachment(&self, id: i64) -> Result<Option<Attachment>> {
+        self.conn
+            .query_row(
+                "SELECT id, expense_id, invoice_number, name, path, added_at
+                 FROM attachments WHERE id = ?1",
+                params![id],
+                attachment_from_row,
+            )
+            .optional()
+    }
+
+    /// Attachments that go out with an invoice: those attached to it directly,
+    /// and those of the expenses billed on it. Oldest first.
+    pub fn invoice_attachments(&self, invoice_number: i64) -> Result<Vec<Attachment>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, expense_id, invoice_number, name, path, added_at
+             FROM attachments
+             WHERE invoice_number = ?1
+                OR expense_id IN (SELECT id FROM expenses WHERE invoice_number = ?1)
+             ORDER BY id",
+        )?;
+        let rows = stmt.query_map(params![invoice_number], attachment_from_row)?;
+        rows.collect()
+    }
+
+    /// Delete an attachment's record. Returns whether it existed. The stored
+    /// file is left for the caller to remove.
+    pub fn delete_attachment(&self, id: i64) -> Result<bool> {
+        let changed = self
+            .conn
+            .execute("DELETE FROM attachments WHERE id = ?1", params![id])?;
+        Ok(changed > 0)
+    }
+
+    // === Template Methods ===
+
+    /// Save a template, replacing any with the same name. Returns its ID.
+    pub fn save_template(&self, template: &EntryTemplate) -> Result<i64> {
+        self.conn.execute(
+            "INSERT INTO entry_templates (name, project, description, tags, duration)
+             VALUES (?1, ?2, ?3, ?4, ?5)
+             ON CONFLICT(name) DO UPDATE SET
+                project = ?2, description = ?3, tags = ?4, duration = ?5",
+            params![
+                template.name,
+                template.project,
+                template.description,
+                template.tags.join(" "),
+                template.duration
+            ],
+        )?;
+        self.conn.query_row(
+            "SELECT id FROM entry_templates WHERE name = ?1",
+            params![template.name],
+            |row| row.get(0),
+        )
+    }
+
+    /// All templates, by name.
+    pub fn list_templates(&self) -> Result<Vec<EntryTemplate>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, name, project, description, tags, duration FROM entry_templates
+             ORDER BY name",
+        )?;
+        let rows = stmt.query_map([], template_from_row)?;
+        rows.collect()
+    }
+
+    /// The template called `name`, ignoring case.
+    pub fn get_template(&self, name: &str) -> Result<Option<EntryTemplate>> {
+        self.conn
+            .query_row(
+                "SELECT id, name, project, description, tags, duration FROM entry_templates
+                 WHERE name = ?1",
+                params![name],
+                template_from_row,
+            )
+            .optional()
+    }
+
+    /// Delete a template. Returns false if there is no template called `name`.
+    pub fn delete_template(&self, name: &str) -> Result<bool> {
+        let changed = self
+            .conn
+            .execute("DELETE FROM entry_templates WHERE name = ?1", params![name])?;
+        Ok(changed > 0)
+    }
+
+    // === Network Rule Methods ===
+
+    /// Save a rule for a Wi-Fi network, replacing any for the same network.
+    pub fn save_network_rule(
+        &self,
+        ssid: &str,
+        project: &str,
+        location: Option<&str>,
+    ) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO network_rules (ssid, project, location) VALUES (?1, ?2, ?3)
+             ON CONFLICT(ssid) DO UPDATE SET project = ?2, location = ?3",
+            params![ssid, project, location],
+        )?;
+        Ok(())
+    }
+
+    /// All network rules, by network name.
+    pub fn list_network_rules(&self) -> Result<Vec<NetworkRule>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT id, ssid, project, location FROM network_rules ORDER BY ssid")?;
+        let rows = stmt.query_map([], network_rule_from_row)?;
+        rows.collect()
+    }
+
+    /// The rule for the Wi-Fi network called `ssid`, if any.
+    #[cfg(any(target_os = "macos", test))]
+    pub fn network_rule_for(&self, ssid: &str) -> Result<Option<NetworkRule>> {
+        self.conn
+            .query_row(
+                "SELECT id, ssid, project, location FROM network_rules WHERE ssid = ?1",
+                params![ssid],
+                network_rule_from_row,
+            )
+            .optional()
+    }
+
+    /// Delete the rule for a network. Returns false if there is none.
+    pub fn delete_network_rule(&self, ssid: &str) -> Result<bool> {
+        let changed = self
+            .conn
+            .execute("DELETE FROM network_rules WHERE ssid = ?1", params![ssid])?;
+        Ok(changed > 0)
+    }
+
+    // === Time Off Methods ===
+
+    /// Record days off from `start` to `end`, both included. Returns the new ID.
+    pub fn add_time_off(
+        &self,
+        start: NaiveDate,
+        end: NaiveDate,
+        reason: &str,
+        holiday: bool,
+    ) -> Result<i64> {
+        self.conn.execute(
+            "INSERT INTO time_off (start, end, reason, holiday) VALUES (?1, ?2, ?3, ?4)",
+            params![
+                start.format("%Y-%m-%d").to_string(),
+                end.format("%Y-%m-%d").to_string(),
+                reason,
+                holiday
+            ],
+        )?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    /// All recorded time off, earliest first.
+    pub fn list_time_off(&self) -> Result<Vec<TimeOff>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT id, start, end, reason, holiday FROM time_off ORDER BY start, id")?;
+        let rows = stmt.query_map([], time_off_from_row)?;
+        rows.collect()
+    }
+
+    /// Time off marked as public holidays, earliest first.
+    pub fn holidays(&self) -> Result<Vec<TimeOff>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, start, end, reason, holiday FROM time_off
+             WHERE holiday = 1
+             ORDER BY start, id",
+        )?;
+        let rows = stmt.query_map([], time_off_from_row)?;
+        rows.collect()
+    }
+
+    /// Time off overlapping `from..=to`, earliest first.
+    pub fn time_off_between(&self, from: NaiveDate, to: NaiveDate) -> Result<Vec<TimeOff>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, start, end, reason, holiday FROM time_off
+             WHERE start <= ?2 AND end >= ?1
+             ORDER BY start, id",
+        )?;
+        let rows = stmt.query_map(
+            params![
+                from.format("%Y-%m-%d").to_string(),
+                to.format("%Y-%m-%d").to_string()
+            ],
+            time_off_from_row,
+        )?;
+        rows.collect()
+    }
+
+    /// Delete recorded time off. Returns false if there is no such record.
+    pub fn delete_time_off(&self, id: i64) -> Result<bool> {
+        let changed = self
+            .conn
+            .execute("DELETE FROM time_off WHERE id = ?1", params![id])?;
+        Ok(changed > 0)
+    }
+
+    // === Notification Settings Methods ===
+
+    /// Get the notification preferences.
+    pub fn get_notification_settings(&self) -> Result<NotificationSettings> {
+        self.conn.query_row(
+            "SELECT work_complete, break_complete, reminders, sound, quiet_start, quiet_end,
+                    badge_unbilled_hours, badge_overdue, monthly_target_hours, target_margin_hours
+             FROM notification_settings WHERE id = 1",
+            [],
+            |row| {
+                let time = |idx: usize| -> Result<Option<NaiveTime>> {
+                    Ok(row
+                        .get::<_, Option<String>>(idx)?
+                        .and_then(|t| NaiveTime::parse_from_str(&t, "%H:%M").ok()))
+                };
+                Ok(NotificationSettings {
+                    work_complete: row.get::<_, i64>(0)? != 0,
+                    break_complete: row.get::<_, i64>(1)? != 0,
+                    reminders: row.get::<_, i64>(2)? != 0,
+                    sound: row.get(3)?,
+                    quiet_start: time(4)?,
+                    quiet_end: time(5)?,
+                    badge_unbilled_hours: row.get(6)?,
+                    badge_overdue: row.get::<_, i64>(7)? != 0,
+                    monthly_target_hours: row.get(8)?,
+                    target_margin_hours: row.get(9)?,
+                })
+            },
+        )
+    }
+
+    /// Update the notification preferences.
+    pub fn set_notification_settings(&self, settings: &NotificationSettings) -> Result<()> {
+        let time = |t: Option<NaiveTime>| t.map(|t| t.format("%H:%M").to_string());
+        self.conn.execute(
+            "UPDATE notification_settings SET
+                work_complete = ?1,
+                break_complete = ?2,
+                reminders = ?3,
+                sound = ?4,
+                quiet_start = ?5,
+                quiet_end = ?6,
+                badge_unbilled_hours = ?7,
+                badge_overdue = ?8,
+                monthly_target_hours = ?9,
+                target_margin_hours = ?10
+             WHERE id = 1",
+            params![
+                settings.work_complete,
+                settings.break_complete,
+                settings.reminders,
+                settings.sound,
+                time(settings.quiet_start),
+                time(settings.quiet_end),
+                settings.badge_unbilled_hours,
+                settings.badge_overdue,
+                settings.monthly_target_hours,
+                settings.target_margin_hours,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// The description of the most recent entry for a project, if any.
+    pub fn last_description_for_project(&self, project: &str) -> Result<Option<String>> {
+        self.conn
+            .query_row(
+                "SELECT description FROM entries
+                 WHERE project = ?1
+                 ORDER BY start DESC
+                 LIMIT 1",
+                params![project],
+                |row| row.get(0),
+            )
+            .optional()
+    }
+
+    /// The description to use when a timer for `project` is started without one:
+    /// the project's last description if that setting is on, otherwise the global default.
+    pub fn default_description(&self, project: &str) -> Result<String> {
+        if self.get_timer_settings()?.reuse_last_description
+            && let Some(desc) = self.last_description_for_project(project)?
+        {
+            return Ok(desc);
+        }
+        Ok(DEFAULT_DESCRIPTION.to_string())
+    }
+
+    // === TUI Settings Methods ===
+
+    /// Get what bare `meter` does.
+    pub fn get_tui_settings(&self) -> Result<TuiSettings> {
+        self.conn.query_row(
+            "SELECT open_by_default, default_screen FROM tui_settings WHERE id = 1",
+            [],
+            |row| {
+                Ok(TuiSettings {
+                    open_by_default: row.get::<_, i64>(0)? != 0,
+                    default_screen: row.get(1)?,
+                })
+            },
+        )
+    }
+
+    /// Update what bare `meter` does.
+    pub fn set_tui_settings(&self, settings: &TuiSettings) -> Result<()> {
+        self.conn.execute(
+            "UPDATE tui_settings SET open_by_default = ?1, default_screen = ?2 WHERE id = 1",
+            params![
+                if settings.open_by_default { 1 } else { 0 },
+                settings.default_screen,
+            ],
+        )?;
+        Ok(())
+    }
+
+    // === Sync Methods ===
+
+    /// Get where finished entries are pushed.
+    pub fn get_sync_settings(&self) -> Result<SyncSettings> {
+        self.conn.query_row(
+            "SELECT service, account_id FROM sync_settings WHERE id = 1",
+            [],
+            |row| {
+                Ok(SyncSettings {
+                    service: row.get(0)?,
+                    account_id: row.get(1)?,
+                })
+            },
+        )
+    }
+
+    /// Update where finished entries are pushed.
+    pub fn set_sync_settings(&self, settings: &SyncSettings) -> Result<()> {
+        self.conn.execute(
+            "UPDATE sync_settings SET service = ?1, account_id = ?2 WHERE id = 1",
+            params![settings.service, settings.account_id],
+        )?;
+        Ok(())
+    }
+
+    /// Map a project to a project (and task) in the sync service. Remapping keeps
+    /// the original mapping date.
+    pub fn map_sync_project(
+        &self,
+        service: SyncService,
+        project: &str,
+        remote_project: i64,
+        remote_task: Option<i64>,
+    ) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO sync_projects (service, project, remote_project, remote_task, mapped_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)
+             ON CONFLICT (service, project)
+             DO UPDATE SET remote_project = ?3, remote_task = ?4",
+            params![
+                service,
+                project,
+                remote_project,
+                remote_task,
+                Utc::now().to_rfc3339()
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Stop pushing a project's entries. Returns false if it wasn't mapped.
+    pub fn unmap_sync_project(&self, service: SyncService, project: &str) -> Result<bool> {
+        let rows_affected = self.conn.execute(
+            "DELETE FROM sync_projects WHERE service = ?1 AND project = ?2",
+            params![service, project],
+        )?;
+        Ok(rows_affected > 0)
+    }
+
+    /// Projects mapped for `service`, by name.
+    pub fn sync_mappings(&self, service: SyncService) -> Result<Vec<SyncMapping>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT service, project, remote_project, remote_task, mapped_at
+             FROM sync_projects WHERE service = ?1
+             ORDER BY project",
+        )?;
+        let rows = stmt.query_map(params![service], sync_mapping_from_row)?;
+        rows.collect()
+    }
+
+    /// Finished timed entries of mapped projects that haven't been pushed to
+    /// `service`, oldest first, with their mapping. Only entries started since
+    /// `since`, or since their project was mapped.
+    pub fn unpushed_entries(
+        &self,
+        service: SyncService,
+        since: Option<DateTime<Utc>>,
+    ) -> Result<Vec<(Entry, SyncMapping)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT e.id, e.project, e.description, e.start, e.end, e.status, e.days, e.issue,
+                    e.location,
+                    m.service, m.project, m.remote_project, m.remote_task, m.mapped_at
+             FROM entries e
+             JOIN sync_projects m ON m.project = e.project AND m.service = ?1
+             WHERE e.end IS NOT NULL AND e.days IS NULL
+               AND julianday(e.start) >= julianday(COALESCE(?2, m.mapped_at))
+               AND NOT EXISTS (SELECT 1 FROM sync_pushed p
+                               WHERE p.service = ?1 AND p.entry_id = e.id)
+             ORDER BY e.start",
+        )?;
+        let rows = stmt.query_map(params![service, since.map(|s| s.to_rfc3339())], |row| {
+            Ok((entry_from_row(row)?, sync_mapping_at(row, 9)?))
+        })?;
+        rows.collect()
+    }
+
+    /// Remember that an entry was pushed, so it isn't pushed again.
+    pub fn record_push(&self, service: SyncService, entry_id: i64, remote_id: i64) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO sync_pushed (service, entry_id, remote_id, pushed_at)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![service, entry_id, remote_id, Utc::now().to_rfc3339()],
+        )?;
+        Ok(())
+    }
+
+    // === Invoice Settings Methods ===
+
+    /// Get the default profile's invoice settings.
+    pub fn get_invoice_settings(&self) -> Result<InvoiceSettings> {
+        self.get_profile_settings(DEFAULT_PROFILE_ID)
+    }
+
+    /// Update the default profile's invoice settings.
+    pub fn set_invoice_settings(&self, settings: &InvoiceSettings) -> Result<()> {
+        self.set_profile_settings(DEFAULT_PROFILE_ID, settings)
+    }
+
+    /// Get a business profile's invoice settings.
+    pub fn get_profile_settings(&self, id: i64) -> Result<InvoiceSettings> {
+        Ok(self
+            .get_profile(id)?
+            .ok_or(rusqlite::Error::QueryReturnedNoRows)?
+            .settings)
+    }
+
+    /// Update a business profile's invoice settings.
+    pub fn set_profile_settings(&self, id: i64, settings: &InvoiceSettings) -> Result<()> {
+        self.conn.execute(
+            "UPDATE invoice_settings SET
+                business_name = ?1,
+                address_street = ?2,
+                address_city = ?3,
+                address_state = ?4,
+                address_postal = ?5,
+                address_country = ?6,
+                email = ?7,
+                phone = ?8,
+                tax_id = ?9,
+                payment_instructions = ?10,
+                default_payment_terms = ?11,
+                default_tax_rate = ?12,
+                reminder_day = ?13,
+                include_entry_notes = ?14,
+                paper_size = ?15,
+                margin_mm = ?16,
+                font_size = ?17,
+                section_order = ?18,
+                iban = ?19,
+                bic = ?20,
+                account_number = ?21,
+                routing_number = ?22,
+                swift = ?23,
+                business_day_due_dates = ?24,
+                json_snapshot = ?25,
+                issue_refs = ?26,
+                logo_path = ?27,
+                tax_inclusive = ?28,
+                number_locale = ?29
+             WHERE id = ?30",
+            params![
+                settings.business_name,
+                settings.address_street,
+                settings.address_city,
+                settings.address_state,
+                settings.address_postal,
+                settings.address_country,
+                settings.email,
+                settings.phone,
+                settings.tax_id,
+                settings.payment_instructions,
+                settings.default_payment_terms,
+                settings.default_tax_rate,
+                settings.reminder_day,
+                settings.include_entry_notes,
+                settings.paper_size,
+                settings.margin_mm,
+                settings.font_size,
+                settings.section_order,
+                settings.iban,
+                settings.bic,
+                settings.account_number,
+                settings.routing_number,
+                settings.swift,
+                settings.business_day_due_dates,
+                settings.json_snapshot,
+                settings.issue_refs,
+                settings.logo_path,
+                settings.tax_inclusive,
+                settings.number_locale,
+                id,
+            ],
+        )?;
+        Ok(())
+    }
+
+    // === Business Profile Methods ===
+
+    /// All business profiles, the default first.
+    pub fn list_profiles(&self) -> Result<Vec<BusinessProfile>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM invoice_settings ORDER BY id",
+            INVOICE_SETTINGS_COLUMNS
+        ))?;
+        let rows = stmt.query_map([], profile_from_row)?;
+        rows.collect()
+    }
+
+    /// Get a business profile by ID.
+    pub fn get_profile(&self, id: i64) -> Result<Option<BusinessProfile>> {
+        self.conn
+            .query_row(
+                &format!(
+                    "SELECT {} FROM invoice_settings WHERE id = ?1",
+                    INVOICE_SETTINGS_COLUMNS
+                ),
+                params![id],
+                profile_from_row,
+            )
+            .optional()
+    }
+
+    /// Add a business profile with default settings. Returns its ID.
+    pub fn add_profile(&self, name: &str, first_number: i64) -> Result<i64> {
+        self.conn.execute(
+            "INSERT INTO invoice_settings (name, first_number) VALUES (?1, ?2)",
+            params![name, first_number],
+        )?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    /// Rename a business profile and set where its invoice numbers start.
+    pub fn update_profile(&self, id: i64, name: &str, first_number: i64) -> Result<()> {
+        self.conn.execute(
+            "UPDATE invoice_settings SET name = ?1, first_number = ?2 WHERE id = ?3",
+            params![name, first_number, id],
+        )?;
+        Ok(())
+    }
+
+    /// Delete a business profile; its clients and drafts move to the default
+    /// profile. The default profile and profiles with invoices are kept.
+    /// Returns whether it was deleted.
+    pub fn delete_profile(&self, id: i64) -> Result<bool> {
+        if id == DEFAULT_PROFILE_ID {
+            return Ok(false);
+        }
+        let invoices: i64 = self.conn.query_row(
+            "SELECT COUNT(*) FROM invoices WHERE profile_id = ?1",
+            params![id],
+            |row| row.get(0),
+        )?;
+        if invoices > 0 {
+            return Ok(false);
+        }
+        let tx = self.write_transaction()?;
+        tx.execute(
+            "UPDATE clients SET profile_id = NULL WHERE profile_id = ?1",
+            params![id],
+        )?;
+        tx.execute(
+            "UPDATE invoice_drafts SET profile_id = ?1 WHERE profile_id = ?2",
+            params![DEFAULT_PROFILE_ID, id],
+        )?;
+        let changed = tx.execute("DELETE FROM invoice_settings WHERE id = ?1", params![id])?;
+        tx.commit()?;
+        Ok(changed > 0)
+    }
+
+    /// The profile a client is invoiced from: its own, or the default.
+    pub fn profile_for_client(&self, client_id: Option<i64>) -> Result<i64> {
+        let Some(client_id) = client_id else {
+            return Ok(DEFAULT_PROFILE_ID);
+        };
+        let profile: Option<i64> = self
+            .conn
+            .query_row(
+                "SELECT c.profile_id FROM clients c
+                 JOIN invoice_settings p ON p.id = c.profile_id
+                 WHERE c.id = ?1",
+                params![client_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(profile.unwrap_or(DEFAULT_PROFILE_ID))
+    }
+
+    // === Email Settings Methods ===
+
+    /// Get the outgoing mail server settings.
+    pub fn get_email_settings(&self) -> Result<EmailSettings> {
+        self.conn.query_row(
+            "SELECT smtp_host, smtp_port, smtp_username, from_address, security
+             FROM email_settings WHERE id = 1",
+            [],
+            |row| {
+                Ok(EmailSettings {
+                    smtp_host: row.get(0)?,
+                    smtp_port: row.get(1)?,
+                    smtp_username: row.get(2)?,
+                    from_address: row.get(3)?,
+                    security: row.get(4)?,
+                })
+            },
+        )
+    }
+
+    /// Update the outgoing mail server settings.
+    pub fn set_email_settings(&self, settings: &EmailSettings) -> Result<()> {
+        self.conn.execute(
+            "UPDATE email_settings SET
+                smtp_host = ?1,
+                smtp_port = ?2,
+                smtp_username = ?3,
+                from_address = ?4,
+                security = ?5
+             WHERE id = 1",
+            params![
+                settings.smtp_host,
+                settings.smtp_port,
+                settings.smtp_username,
+                settings.from_address,
+                settings.security,
+            ],
+        )?;
+        Ok(())
+    }
+
+    // === Inbox Methods ===
+
+    /// Get the incoming mail server settings.
+    pub fn get_inbox_settings(&self) -> Result<InboxSettings> {
+        self.conn.query_row(
+            "SELECT imap_host, imap_port, username, mailbox, enabled, last_uid
+             FROM inbox_settings WHERE id = 1",
+            [],
+            |row| {
+                Ok(InboxSettings {
+                    imap_host: row.get(0)?,
+                    imap_port: row.get(1)?,
+                    username: row.get(2)?,
+                    mailbox: row.get(3)?,
+                    enabled: row.get(4)?,
+                    last_uid: row.get(5)?,
+                })
+            },
+        )
+    }
+
+    /// Update the incoming mail server settings.
+    pub fn set_inbox_settings(&self, settings: &InboxSettings) -> Result<()> {
+        self.conn.execute(
+            "UPDATE inbox_settings SET
+                imap_host = ?1,
+                imap_port = ?2,
+                username = ?3,
+                mailbox = ?4,
+                enabled = ?5,
+                last_uid = ?6
+             WHERE id = 1",
+            params![
+                settings.imap_host,
+                settings.imap_port,
+                settings.username,
+                settings.mailbox,
+                settings.enabled,
+                settings.last_uid,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Add a mail rule. Returns its ID.
+    pub fn add_mail_rule(&self, pattern: &str, action: MailAction) -> Result<i64> {
+        self.conn.execute(
+            "INSERT INTO mail_rules (pattern, action) VALUES (?1, ?2)",
+            params![pattern, action],
+        )?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    /// Mail rules in the order they are tried.
+    pub fn list_mail_rules(&self) -> Result<Vec<MailRule>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT id, pattern, action FROM mail_rules ORDER BY id")?;
+        let rows = stmt.query_map([], |row| {
+            Ok(MailRule {
                 id: row.get(0)?,
-                invoice_number: row.get(1)?,
-                client_id: row.get(2)?,
-                date_issued: row.get(3)?,
-                due_date: row.get(4)?,
-                subtotal: row.get(5)?,
-                tax_rate: row.get(6)?,
-                tax_amount: row.get(7)?,
-                total: row.get(8)?,
-                file_path: row.get(9)?,
+                pattern: row.get(1)?,
+                action: row.get(2)?,
             })
         })?;
+        rows.collect()
+    }
 
-        invoices.collect()
+    /// Delete a mail rule. Returns whether it existed.
+    pub fn delete_mail_rule(&self, id: i64) -> Result<bool> {
+        let changed = self
+            .conn
+            .execute("DELETE FROM mail_rules WHERE id = ?1", params![id])?;
+        Ok(changed > 0)
+    }
+
+    /// Record that an email was matched to an invoice.
+    pub fn record_mail_match(&self, m: &MailMatch) -> Result<i64> {
+        self.conn.execute(
+            "INSERT INTO mail_matches (invoice_number, action, subject, sender, matched_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                m.invoice_number,
+                m.action,
+                m.subject,
+                m.sender,
+                m.matched_at.to_rfc3339()
+            ],
+        )?;
+        Ok(self.conn.last_insert_rowid())
     }
+
+    /// Emails matched to invoices, oldest first.
+    pub fn list_mail_matches(&self) -> Result<Vec<MailMatch>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, invoice_number, action, subject, sender, matched_at
+             FROM mail_matches ORDER BY id",
+        )?;
+        let rows = stmt.query_map([], mail_match_from_row)?;
+        rows.collect()
+    }
+
+    // === Payment Methods ===
+
+    /// Record a payment received through a provider. Returns its ID.
+    pub fn record_payment(&self, payment: &Payment) -> Result<i64> {
+        self.conn.execute(
+            "INSERT INTO payments
+                (provider, external_id, invoice_number, amount, fee, currency, paid_at, expense_id)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                payment.provider,
+                payment.external_id,
+                payment.invoice_number,
+                payment.amount,
+                payment.fee,
+                payment.currency,
+                payment.paid_at.to_rfc3339(),
+                payment.expense_id,
+            ],
+        )?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    /// What has been paid towards an invoice so far.
+    pub fn invoice_paid_amount(&self, invoice_number: i64) -> Result<Money> {
+        self.conn.query_row(
+            "SELECT COALESCE(SUM(amount), 0) FROM payments WHERE invoice_number = ?1",
+            params![invoice_number],
+            |row| row.get(0),
+        )
+    }
+
+    /// All recorded payments, oldest first.
+    pub fn list_payments(&self) -> Result<Vec<Payment>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, provider, external_id, invoice_number, amount, fee, currency, paid_at,
+                    expense_id
+             FROM payments ORDER BY paid_at, id",
+        )?;
+        let rows = stmt.query_map([], payment_from_row)?;
+        rows.collect()
+    }
+
+    // === Client Methods ===
+
+    /// Add a new client.
+    pub fn add_client(&self, client: &Client) -> Result<i64> {
+        self.conn.execute(
+            "INSERT INTO clients (name, contact_person, address_street, address_city,
+                                  address_state, address_postal, address_country, email,
+                                  reminder_day, profile_id)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            params![
+                client.name,
+                client.contact_person,
+                client.address_street,
+                client.address_city,
+                client.address_state,
+                client.address_postal,
+                client.address_country,
+                client.email,
+                client.reminder_day,
+                client.profile_id,
+            ],
+        )?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    /// Get a client by ID.
+    pub fn get_client(&self, id: i64) -> Result<Option<Client>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, name, contact_person, address_street, address_city,
+                    address_state, address_postal, address_country, email, archived,
+                    reminder_day, profile_id
+             FROM clients WHERE id = ?1",
+        )?;
+
+        stmt.query_row(params![id], |row| {
+            Ok(Client {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                contact_person: row.get(2)?,
+                address_street: row.get(3)?,
+                address_city: row.get(4)?,
+                address_state: row.get(5)?,
+                address_postal: row.get(6)?,
+                address_country: row.get(7)?,
+                email: row.get(8)?,
+                archived: row.get::<_, i64>(9)? != 0,
+                reminder_day: row.get(10)?,
+                profile_id: row.get(11)?,
+            })
+        })
+        .optional()
+    }
+
+    /// A client's overtime multipliers, `None` if they pay the normal rate at all hours.
+    pub fn get_overtime_rates(&self, client_id: i64) -> Result<Option<OvertimeRates>> {
+        self.conn
+            .query_row(
+                "SELECT evening, evening_start, evening_end, weekend, holiday
+                 FROM overtime_rates WHERE client_id = ?1",
+                params![client_id],
+                |row| {
+                    let defaults = OvertimeRates::default();
+                    let time = |idx: usize, default: NaiveTime| -> Result<NaiveTime> {
+                        Ok(
+                            NaiveTime::parse_from_str(&row.get::<_, String>(idx)?, "%H:%M")
+                                .unwrap_or(default),
+                        )
+                    };
+                    Ok(OvertimeRates {
+                        evening: row.get(0)?,
+                        evening_start: time(1, defaults.evening_start)?,
+                        evening_end: time(2, defaults.evening_end)?,
+                        weekend: row.get(3)?,
+                        holiday: row.get(4)?,
+                    })
+                },
+            )
+            .optional()
+    }
+
+    /// Set a client's overtime multipliers. Rates with no multiplier left are removed.
+    pub fn set_overtime_rates(&self, client_id: i64, rates: &OvertimeRates) -> Result<()> {
+        if rates.is_empty() {
+            self.conn.execute(
+                "DELETE FROM overtime_rates WHERE client_id = ?1",
+                params![client_id],
+            )?;
+            return Ok(());
+        }
+        self.conn.execute(
+            "INSERT OR REPLACE INTO overtime_rates
+                (client_id, evening, evening_start, evening_end, weekend, holiday)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                client_id,
+                rates.evening,
+                rates.evening_start.format("%H:%M").to_string(),
+                rates.evening_end.format("%H:%M").to_string(),
+                rates.weekend,
+                rates.holiday,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Find a client by name, ignoring case and surrounding whitespace.
+    pub fn find_client_by_name(&self, name: &str) -> Result<Option<Client>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, name, contact_person, address_street, address_city,
+                    address_state, address_postal, address_country, email, archived,
+                    reminder_day, profile_id
+             FROM clients WHERE name = ?1 COLLATE NOCASE
+             LIMIT 1",
+        )?;
+
+        stmt.query_row(params![name.trim()], |row| {
+            Ok(Client {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                contact_person: row.get(2)?,
+                address_street: row.get(3)?,
+                address_city: row.get(4)?,
+                address_state: row.get(5)?,
+                address_postal: row.get(6)?,
+                address_country: row.get(7)?,
+                email: row.get(8)?,
+                archived: row.get::<_, i64>(9)? != 0,
+                reminder_day: row.get(10)?,
+                profile_id: row.get(11)?,
+            })
+        })
+        .optional()
+    }
+
+    /// List all clients.
+    pub fn list_clients(&self) -> Result<Vec<Client>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, name, contact_person, address_street, address_city,
+                    address_state, address_postal, address_country, email, archived,
+                    reminder_day, profile_id
+             FROM clients ORDER BY name",
+        )?;
+
+        let clients = stmt.query_map([], |row| {
+            Ok(Client {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                contact_person: row.get(2)?,
+                address_street: row.get(3)?,
+                address_city: row.get(4)?,
+                address_state: row.get(5)?,
+                address_postal: row.get(6)?,
+                address_country: row.get(7)?,
+                email: row.get(8)?,
+                archived: row.get::<_, i64>(9)? != 0,
+                reminder_day: row.get(10)?,
+                profile_id: row.get(11)?,
+            })
+        })?;
+
+        clients.collect()
+    }
+
+    /// Update a client.
+    pub fn update_client(&self, client: &Client) -> Result<bool> {
+        let rows = self.conn.execute(
+            "UPDATE clients SET
+                name = ?1,
+                contact_person = ?2,
+                address_street = ?3,
+                address_city = ?4,
+                address_state = ?5,
+                address_postal = ?6,
+                address_country = ?7,
+                email = ?8,
+                reminder_day = ?9,
+                profile_id = ?10
+             WHERE id = ?11",
+            params![
+                client.name,
+                client.contact_person,
+                client.address_street,
+                client.address_city,
+                client.address_state,
+                client.address_postal,
+                client.address_country,
+                client.email,
+                client.reminder_day,
+                client.profile_id,
+                client.id,
+            ],
+        )?;
+        Ok(rows > 0)
+    }
+
+    /// Delete a client. Linked projects are unlinked; clients that still have
+    /// invoices are protected by the foreign key and fail to delete.
+    pub fn delete_client(&self, id: i64) -> Result<bool> {
+        let tx = self.write_transaction()?;
+        tx.execute(
+            "UPDATE projects SET client_id = NULL WHERE client_id = ?1",
+            params![id],
+        )?;
+        let rows = tx.execute("DELETE FROM clients WHERE id = ?1", params![id])?;
+        tx.commit()?;
+        Ok(rows > 0)
+    }
+
+    /// Count the invoices issued to a client.
+    pub fn count_client_invoices(&self, id: i64) -> Result<i64> {
+        self.conn.query_row(
+            "SELECT COUNT(*) FROM invoices WHERE client_id = ?1",
+            params![id],
+            |row| row.get(0),
+        )
+    }
+
+    /// Archive or restore a client.
+    pub fn set_client_archived(&self, id: i64, archived: bool) -> Result<bool> {
+        let rows = self.conn.execute(
+            "UPDATE clients SET archived = ?1 WHERE id = ?2",
+            params![if archived { 1 } else { 0 }, id],
+        )?;
+        Ok(rows > 0)
+    }
+
+    /// Merge a duplicate client into another: its invoices and project links
+    /// are re-pointed to `into_id` and the duplicate is removed.
+    /// Returns the number of invoices that were moved.
+    pub fn merge_clients(&self, from_id: i64, into_id: i64) -> Result<usize> {
+        let tx = self.write_transaction()?;
+        let invoices = tx.execute(
+            "UPDATE invoices SET client_id = ?1 WHERE client_id = ?2",
+            params![into_id, from_id],
+        )?;
+        tx.execute(
+            "UPDATE projects SET client_id = ?1 WHERE client_id = ?2",
+            params![into_id, from_id],
+        )?;
+        tx.execute("DELETE FROM clients WHERE id = ?1", params![from_id])?;
+        tx.commit()?;
+        Ok(invoices)
+    }
+
+    // === Invoice Record Methods ===
+
+    /// Get the next invoice number for a business profile: one past its last
+    /// invoice, and no lower than the profile's first number. Numbers are unique
+    /// across profiles, so any another profile has used are skipped.
+    pub fn get_next_invoice_number(&self, profile_id: i64) -> Result<i64> {
+        let mut number: i64 = self.conn.query_row(
+            "SELECT MAX(first_number,
+                        (SELECT COALESCE(MAX(invoice_number), 0) + 1
+                         FROM invoices WHERE profile_id = ?1))
+             FROM invoice_settings WHERE id = ?1",
+            params![profile_id],
+            |row| row.get(0),
+        )?;
+        while self.invoice_number_taken(number)? {
+            number += 1;
+        }
+        Ok(number)
+    }
+
+    /// Whether an invoice already has this number, from any profile.
+    pub fn invoice_number_taken(&self, invoice_number: i64) -> Result<bool> {
+        self.conn.query_row(
+            "SELECT EXISTS (SELECT 1 FROM invoices WHERE invoice_number = ?1)",
+            params![invoice_number],
+            |row| row.get(0),
+        )
+    }
+
+    /// Record a generated invoice.
+    pub fn record_invoice(&self, invoice: &Invoice) -> Result<i64> {
+        self.conn.execute(
+            "INSERT INTO invoices (invoice_number, client_id, date_issued, due_date,
+                                   subtotal, tax_rate, tax_amount, total, file_path, snapshot,
+                                   supersedes, profile_id)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+            params![
+                invoice.invoice_number,
+                invoice.client_id,
+                invoice.date_issued,
+                invoice.due_date,
+                invoice.subtotal,
+                invoice.tax_rate,
+                invoice.tax_amount,
+                invoice.total,
+                invoice.file_path,
+                invoice.snapshot,
+                invoice.supersedes,
+                invoice.profile_id,
+            ],
+        )?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    /// List all recorded invoices.
+    pub fn list_invoices(&self) -> Result<Vec<Invoice>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, invoice_number, client_id, date_issued, due_date,
+                    subtotal, tax_rate, tax_amount, total, file_path, snapshot,
+                    void_reason, supersedes, profile_id
+             FROM invoices ORDER BY invoice_number DESC",
+        )?;
+
+        let invoices = stmt.query_map([], |row| {
+            Ok(Invoice {
+                id: row.get(0)?,
+                invoice_number: row.get(1)?,
+                client_id: row.get(2)?,
+                date_issued: row.get(3)?,
+                due_date: row.get(4)?,
+                subtotal: row.get(5)?,
+                tax_rate: row.get(6)?,
+                tax_amount: row.get(7)?,
+                total: row.get(8)?,
+                file_path: row.get(9)?,
+                snapshot: row.get(10)?,
+                void_reason: row.get(11)?,
+                supersedes: row.get(12)?,
+                profile_id: row.get(13)?,
+            })
+        })?;
+
+        invoices.collect()
+    }
+
+    /// Point an invoice record at a new PDF, e.g. after regenerating it.
+    pub fn set_invoice_file_path(&self, id: i64, file_path: &str) -> Result<()> {
+        self.conn.execute(
+            "UPDATE invoices SET file_path = ?1 WHERE id = ?2",
+            params![file_path, id],
+        )?;
+        Ok(())
+    }
+
+    /// Rewrite stored invoice PDF, approval sheet and attachment paths under
+    /// `from` to point under `to` instead, for moving the folder (see
+    /// `paths::legacy_folders`). Returns how many paths changed.
+    pub fn relocate_files(&self, from: &str, to: &str) -> Result<usize> {
+        let mut changed = 0;
+        for (table, column) in [
+            ("invoices", "file_path"),
+            ("approval_sheets", "file_path"),
+            ("attachments", "path"),
+        ] {
+            changed += self.conn.execute(
+                &format!(
+                    "UPDATE {0} SET {1} = ?2 || substr({1}, length(?1) + 1)
+                     WHERE {1} = ?1 OR substr({1}, 1, length(?1) + 1) IN (?1 || '/', ?1 || '\\')",
+                    table, column
+                ),
+                params![from, to],
+            )?;
+        }
+        Ok(changed)
+    }
+
+    /// Mark an invoice void and release what it billed: `entry_ids` that are
+    /// still invoiced go back to billed, and its expenses are uninvoiced.
+    /// Returns the IDs of the released entries.
+    pub fn void_invoice(
+        &self,
+        invoice_number: i64,
+        reason: &str,
+        entry_ids: &[i64],
+    ) -> Result<Vec<i64>> {
+        let tx = self.write_transaction()?;
+        tx.execute(
+            "UPDATE invoices SET void_reason = ?1 WHERE invoice_number = ?2",
+            params![reason, invoice_number],
+        )?;
+        // Voiding is the one way back from invoiced, so this bypasses
+        // `EntryStatus::can_transition_to`
+        let mut released = Vec::new();
+        for id in entry_ids {
+            let changed = tx.execute(
+                "UPDATE entries SET status = ?1 WHERE id = ?2 AND status = ?3",
+                params![EntryStatus::Billed, id, EntryStatus::Invoiced],
+            )?;
+            if changed > 0 {
+                released.push(*id);
+            }
+        }
+        tx.execute(
+            "UPDATE expenses SET invoice_number = NULL WHERE invoice_number = ?1",
+            params![invoice_number],
+        )?;
+        tx.commit()?;
+        Ok(released)
+    }
+
+    // === Invoice Draft Methods ===
+
+    /// Save a new draft with its entries. Returns the new ID.
+    pub fn create_invoice_draft(&self, draft: &InvoiceDraft) -> Result<i64> {
+        let tx = self.write_transaction()?;
+        tx.execute(
+            "INSERT INTO invoice_drafts
+                (client_id, year, month, tax_rate, rounding_minutes, supersedes, profile_id,
+                 created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                draft.client_id,
+                draft.year,
+                draft.month,
+                draft.tax_rate,
+                draft.rounding_minutes,
+                draft.supersedes,
+                draft.profile_id,
+                Utc::now().to_rfc3339()
+            ],
+        )?;
+        let id = tx.last_insert_rowid();
+        for entry_id in &draft.entry_ids {
+            tx.execute(
+                "INSERT INTO invoice_draft_entries (draft_id, entry_id) VALUES (?1, ?2)",
+                params![id, entry_id],
+            )?;
+        }
+        tx.commit()?;
+        Ok(id)
+    }
+
+    /// All drafts, oldest first.
+    pub fn list_invoice_drafts(&self) -> Result<Vec<InvoiceDraft>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, client_id, year, month, tax_rate, rounding_minutes, created_at, supersedes,
+                    profile_id
+             FROM invoice_drafts ORDER BY id",
+        )?;
+        let drafts = stmt
+            .query_map([], invoice_draft_from_row)?
+            .collect::<Result<Vec<_>>>()?;
+        drafts
+            .into_iter()
+            .map(|draft| self.with_draft_lines(draft))
+            .collect()
+    }
+
+    /// Get a draft by ID.
+    pub fn get_invoice_draft(&self, id: i64) -> Result<Option<InvoiceDraft>> {
+        let draft = self
+            .conn
+            .query_row(
+                "SELECT id, client_id, year, month, tax_rate, rounding_minutes, created_at,
+                        supersedes, profile_id
+                 FROM invoice_drafts WHERE id = ?1",
+                params![id],
+                invoice_draft_from_row,
+            )
+            .optional()?;
+        draft.map(|draft| self.with_draft_lines(draft)).transpose()
+    }
+
+    /// Fill in a draft's entry IDs and extra items
+    fn with_draft_lines(&self, mut draft: InvoiceDraft) -> Result<InvoiceDraft> {
+        let mut stmt = self.conn.prepare(
+            "SELECT entry_id FROM invoice_draft_entries WHERE draft_id = ?1 ORDER BY entry_id",
+        )?;
+        draft.entry_ids = stmt
+            .query_map(params![draft.id], |row| row.get(0))?
+            .collect::<Result<_>>()?;
+        let mut stmt = self.conn.prepare(
+            "SELECT id, description, amount FROM invoice_draft_items
+             WHERE draft_id = ?1 ORDER BY id",
+        )?;
+        draft.items = stmt
+            .query_map(params![draft.id], |row| {
+                Ok(InvoiceItem {
+                    id: row.get(0)?,
+                    description: row.get(1)?,
+                    amount: row.get(2)?,
+                })
+            })?
+            .collect::<Result<_>>()?;
+        Ok(draft)
+    }
+
+    /// Save a draft's client, profile, tax rate, rounding and entries. Its
+    /// items are changed with `add_draft_item` and `delete_draft_item`.
+    pub fn update_invoice_draft(&self, draft: &InvoiceDraft) -> Result<()> {
+        let tx = self.write_transaction()?;
+        tx.execute(
+            "UPDATE invoice_drafts SET client_id = ?1, tax_rate = ?2, rounding_minutes = ?3,
+                                       profile_id = ?4
+             WHERE id = ?5",
+            params![
+                draft.client_id,
+                draft.tax_rate,
+                draft.rounding_minutes,
+                draft.profile_id,
+                draft.id
+            ],
+        )?;
+        tx.execute(
+            "DELETE FROM invoice_draft_entries WHERE draft_id = ?1",
+            params![draft.id],
+        )?;
+        for entry_id in &draft.entry_ids {
+            tx.execute(
+                "INSERT INTO invoice_draft_entries (draft_id, entry_id) VALUES (?1, ?2)",
+                params![draft.id, entry_id],
+            )?;
+        }
+        tx.commit()
+    }
+
+    /// Add a line item to a draft. Returns the new item's ID.
+    pub fn add_draft_item(&self, draft_id: i64, description: &str, amount: Money) -> Result<i64> {
+        self.conn.execute(
+            "INSERT INTO invoice_draft_items (draft_id, description, amount) VALUES (?1, ?2, ?3)",
+            params![draft_id, description, amount],
+        )?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    /// Remove a line item from a draft. Returns whether it existed.
+    pub fn delete_draft_item(&self, draft_id: i64, item_id: i64) -> Result<bool> {
+        let changed = self.conn.execute(
+            "DELETE FROM invoice_draft_items WHERE id = ?1 AND draft_id = ?2",
+            params![item_id, draft_id],
+        )?;
+        Ok(changed > 0)
+    }
+
+    /// Delete a draft, releasing its entries. Returns whether it existed.
+    pub fn delete_invoice_draft(&self, id: i64) -> Result<bool> {
+        let changed = self
+            .conn
+            .execute("DELETE FROM invoice_drafts WHERE id = ?1", params![id])?;
+        Ok(changed > 0)
+    }
+
+    /// IDs of entries held by a draft, which other invoices leave out
+    pub fn drafted_entry_ids(&self) -> Result<Vec<i64>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT entry_id FROM invoice_draft_entries ORDER BY entry_id")?;
+        let rows = stmt.query_map([], |row| row.get(0))?;
+        rows.collect()
+    }
+
+    // === Approval Sheet Methods ===
+
+    /// Record a sheet of entries sent for approval. Returns its ID.
+    pub fn create_approval_sheet(&self, sheet: &ApprovalSheet) -> Result<i64> {
+        let tx = self.write_transaction()?;
+        tx.execute(
+            "INSERT INTO approval_sheets (client_id, year, month, file_path, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                sheet.client_id,
+                sheet.year,
+                sheet.month,
+                sheet.file_path,
+                Utc::now().to_rfc3339()
+            ],
+        )?;
+        let id = tx.last_insert_rowid();
+        for entry_id in &sheet.entry_ids {
+            tx.execute(
+                "INSERT INTO approval_sheet_entries (sheet_id, entry_id) VALUES (?1, ?2)",
+                params![id, entry_id],
+            )?;
+        }
+        tx.commit()?;
+        Ok(id)
+    }
+
+    /// Record where a sheet's file was written.
+    pub fn set_approval_sheet_file(&self, id: i64, file_path: &str) -> Result<()> {
+        self.conn.execute(
+            "UPDATE approval_sheets SET file_path = ?1 WHERE id = ?2",
+            params![file_path, id],
+        )?;
+        Ok(())
+    }
+
+    /// All approval sheets, oldest first.
+    pub fn list_approval_sheets(&self) -> Result<Vec<ApprovalSheet>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, client_id, year, month, file_path, created_at, approved_at
+             FROM approval_sheets ORDER BY id",
+        )?;
+        let sheets = stmt
+            .query_map([], approval_sheet_from_row)?
+            .collect::<Result<Vec<_>>>()?;
+        sheets
+            .into_iter()
+            .map(|sheet| self.with_sheet_entries(sheet))
+            .collect()
+    }
+
+    /// Get an approval sheet by ID.
+    pub fn get_approval_sheet(&self, id: i64) -> Result<Option<ApprovalSheet>> {
+        let sheet = self
+            .conn
+            .query_row(
+                "SELECT id, client_id, year, month, file_path, created_at, approved_at
+                 FROM approval_sheets WHERE id = ?1",
+                params![id],
+                approval_sheet_from_row,
+            )
+            .optional()?;
+        sheet
+            .map(|sheet| self.with_sheet_entries(sheet))
+            .transpose()
+    }
+
+    fn with_sheet_entries(&self, mut sheet: ApprovalSheet) -> Result<ApprovalSheet> {
+        let mut stmt = self.conn.prepare(
+            "SELECT entry_id FROM approval_sheet_entries WHERE sheet_id = ?1 ORDER BY entry_id",
+        )?;
+        sheet.entry_ids = stmt
+            .query_map(params![sheet.id], |row| row.get(0))?
+            .collect::<Result<_>>()?;
+        Ok(sheet)
+    }
+
+    /// Mark a sheet approved, first taking the `rejected` entries off it.
+    pub fn approve_sheet(&self, id: i64, rejected: &[i64]) -> Result<()> {
+        let tx = self.write_transaction()?;
+        for entry_id in rejected {
+            tx.execute(
+                "DELETE FROM approval_sheet_entries WHERE sheet_id = ?1 AND entry_id = ?2",
+                params![id, entry_id],
+            )?;
+        }
+        tx.execute(
+            "UPDATE approval_sheets SET approved_at = ?1 WHERE id = ?2",
+            params![Utc::now().to_rfc3339(), id],
+        )?;
+        tx.commit()
+    }
+
+    /// Delete an approval sheet, releasing its entries. Returns whether it existed.
+    pub fn delete_approval_sheet(&self, id: i64) -> Result<bool> {
+        let changed = self
+            .conn
+            .execute("DELETE FROM approval_sheets WHERE id = ?1", params![id])?;
+        Ok(changed > 0)
+    }
+
+    /// IDs of entries on a sheet the client hasn't approved yet, which invoices
+    /// leave out
+    pub fn awaiting_approval_entry_ids(&self) -> Result<Vec<i64>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT DISTINCT e.entry_id FROM approval_sheet_entries e
+             JOIN approval_sheets s ON s.id = e.sheet_id
+             WHERE s.approved_at IS NULL
+             ORDER BY e.entry_id",
+        )?;
+        let rows = stmt.query_map([], |row| row.get(0))?;
+        rows.collect()
+    }
+
+    /// Hours of finished entries that haven't been invoiced yet
+    #[cfg(any(target_os = "macos", test))]
+    pub fn unbilled_hours(&self) -> Result<f64> {
+        self.conn.query_row(
+            "SELECT COALESCE(SUM(julianday(end) - julianday(start)), 0) * 24
+             FROM entries
+             WHERE end IS NOT NULL AND status IN ('open', 'submitted', 'billed')",
+            [],
+            |row| row.get(0),
+        )
+    }
+
+    /// Number of standing invoices due before `today` that aren't paid (see
+    /// `UNPAID_INVOICE`). Invoices from before snapshots were stored never count.
+    #[cfg(any(target_os = "macos", test))]
+    pub fn overdue_invoice_count(&self, today: NaiveDate) -> Result<i64> {
+        self.conn.query_row(
+            &format!(
+                "SELECT COUNT(*) FROM invoices i
+                 WHERE i.void_reason IS NULL AND i.due_date < ?1 AND {}",
+                UNPAID_INVOICE
+            ),
+            params![today.format("%Y-%m-%d").to_string()],
+            |row| row.get(0),
+        )
+    }
+
+    // === Dunning Methods ===
+
+    /// The late-payment reminder schedule.
+    pub fn get_dunning_settings(&self) -> Result<DunningSettings> {
+        self.conn.query_row(
+            "SELECT schedule, email FROM dunning_settings WHERE id = 1",
+            [],
+            |row| {
+                let schedule: String = row.get(0)?;
+                Ok(DunningSettings {
+                    schedule: schedule
+                        .split(',')
+                        .filter_map(|d| d.trim().parse().ok())
+                        .collect(),
+                    email: row.get::<_, i64>(1)? != 0,
+                })
+            },
+        )
+    }
+
+    /// Update the late-payment reminder schedule.
+    pub fn set_dunning_settings(&self, settings: &DunningSettings) -> Result<()> {
+        let schedule: Vec<String> = settings.schedule.iter().map(|d| d.to_string()).collect();
+        self.conn.execute(
+            "UPDATE dunning_settings SET schedule = ?1, email = ?2 WHERE id = 1",
+            params![schedule.join(","), settings.email],
+        )?;
+        Ok(())
+    }
+
+    /// Late-payment reminders due on `today`: for each invoice that
+    /// `overdue_invoice_count` counts, the latest step of `schedule` it has
+    /// reached, unless that step has gone out already. Earlier steps missed
+    /// while nothing was running are skipped rather than sent late.
+    pub fn due_dunning_reminders(
+        &self,
+        today: NaiveDate,
+        schedule: &[i64],
+    ) -> Result<Vec<DunningReminder>> {
+        if schedule.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT i.invoice_number, i.client_id, i.profile_id, i.total, i.due_date,
+                    COALESCE((SELECT json_extract(r.value, '$.currency')
+                              FROM json_each(i.snapshot, '$.rates') r LIMIT 1), '$')
+             FROM invoices i
+             WHERE i.void_reason IS NULL AND i.due_date < ?1 AND {}
+             ORDER BY i.due_date, i.invoice_number",
+            UNPAID_INVOICE
+        ))?;
+        let rows = stmt.query_map(params![today.format("%Y-%m-%d").to_string()], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, Option<i64>>(1)?,
+                row.get::<_, i64>(2)?,
+                row.get::<_, Money>(3)?,
+                row.get::<_, String>(4)?,
+                row.get::<_, String>(5)?,
+            ))
+        })?;
+
+        let mut reminders = Vec::new();
+        for row in rows {
+            let (invoice_number, client_id, profile_id, total, due_date, currency) = row?;
+            let Ok(due_date) = NaiveDate::parse_from_str(&due_date, "%Y-%m-%d") else {
+                continue;
+            };
+            let days_overdue = (today - due_date).num_days();
+            let Some(&step) = schedule.iter().rev().find(|&&d| d <= days_overdue) else {
+                continue;
+            };
+            let sent: bool = self.conn.query_row(
+                "SELECT EXISTS (SELECT 1 FROM dunning_log WHERE invoice_number = ?1 AND step = ?2)",
+                params![invoice_number, step],
+                |row| row.get(0),
+            )?;
+            if !sent {
+                reminders.push(DunningReminder {
+                    invoice_number,
+                    client_id,
+                    profile_id,
+                    total,
+                    currency,
+                    due_date,
+                    step,
+                    days_overdue,
+                });
+            }
+        }
+        Ok(reminders)
+    }
+
+    /// Claim a reminder step for an invoice before sending it. Returns true for
+    /// the first caller only, so the menu bar app and `meter dunning run` never
+    /// both send it.
+    pub fn claim_dunning_reminder(
+        &self,
+        invoice_number: i64,
+        step: i64,
+        sent_at: DateTime<Utc>,
+    ) -> Result<bool> {
+        let changed = self.conn.execute(
+            "INSERT OR IGNORE INTO dunning_log (invoice_number, step, sent_at)
+             VALUES (?1, ?2, ?3)",
+            params![invoice_number, step, sent_at.to_rfc3339()],
+        )?;
+        Ok(changed == 1)
+    }
+
+    /// Record where a claimed reminder was emailed, or why it couldn't be.
+    pub fn set_dunning_email_result(
+        &self,
+        invoice_number: i64,
+        step: i64,
+        emailed_to: Option<&str>,
+        error: Option<&str>,
+    ) -> Result<()> {
+        self.conn.execute(
+            "UPDATE dunning_log SET emailed_to = ?3, error = ?4
+             WHERE invoice_number = ?1 AND step = ?2",
+            params![invoice_number, step, emailed_to, error],
+        )?;
+        Ok(())
+    }
+
+    /// Reminders sent, oldest first; only those for one invoice if given.
+    pub fn dunning_log(&self, invoice_number: Option<i64>) -> Result<Vec<DunningLogEntry>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT invoice_number, step, sent_at, emailed_to, error
+             FROM dunning_log
+             WHERE ?1 IS NULL OR invoice_number = ?1
+             ORDER BY sent_at, id",
+        )?;
+        let rows = stmt.query_map(params![invoice_number], |row| {
+            let sent_at: String = row.get(2)?;
+            Ok(DunningLogEntry {
+                invoice_number: row.get(0)?,
+                step: row.get(1)?,
+                sent_at: DateTime::parse_from_rfc3339(&sent_at)
+                    .map(|d| d.with_timezone(&Utc))
+                    .map_err(|e| {
+                        rusqlite::Error::FromSqlConversionFailure(
+                            2,
+                            rusqlite::types::Type::Text,
+                            Box::new(e),
+                        )
+                    })?,
+                emailed_to: row.get(3)?,
+                error: row.get(4)?,
+            })
+        })?;
+        rows.collect()
+    }
+
+    /// Hours from finished months that haven't been invoiced yet, grouped by month
+    /// and client, oldest first. A month is only reported once its client's
+    /// reminder day (or the business default) has been reached in the following month.
+    pub fn invoice_reminders(&self, today: NaiveDate) -> Result<Vec<InvoiceReminder>> {
+        let default_day = self.get_invoice_settings()?.reminder_day;
+        let mut stmt = self.conn.prepare(
+            "SELECT e.start, e.end, c.name, c.reminder_day
+             FROM entries e
+             LEFT JOIN projects p ON p.name = e.project
+             LEFT JOIN clients c ON c.id = p.client_id
+             WHERE e.end IS NOT NULL
+               AND e.status IN ('open', 'submitted', 'billed')",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, Option<String>>(2)?,
+                row.get::<_, Option<i64>>(3)?,
+            ))
+        })?;
+
+        let mut hours: BTreeMap<(i32, u32, Option<String>), f64> = BTreeMap::new();
+        for row in rows {
+            let (start, end, client, client_day) = row?;
+            let (Ok(start), Ok(end)) = (
+                DateTime::parse_from_rfc3339(&start),
+                DateTime::parse_from_rfc3339(&end),
+            ) else {
+                continue;
+            };
+            let day = client_day.unwrap_or(default_day);
+            if day <= 0 {
+                continue;
+            }
+            let ended = end.with_timezone(&Local).date_naive();
+            let Some(due) = NaiveDate::from_ymd_opt(ended.year(), ended.month(), 1)
+                .and_then(|first| first.checked_add_months(Months::new(1)))
+                .and_then(|next| next.checked_add_days(Days::new(day as u64 - 1)))
+            else {
+                continue;
+            };
+            if today < due {
+                continue;
+            }
+            *hours
+                .entry((ended.year(), ended.month(), client))
+                .or_insert(0.0) += (end - start).num_seconds() as f64 / 3600.0;
+        }
+
+        Ok(hours
+            .into_iter()
+            .map(|((year, month, client), hours)| InvoiceReminder {
+                year,
+                month,
+                client,
+                hours,
+            })
+            .collect())
+    }
+
+    // === Export Methods ===
+
+    /// Everything `meter export json` writes: profiles, clients, projects,
+    /// entries with their tags, invoices and settings.
+    pub fn export_all(&self) -> Result<DatabaseExport> {
+        let mut entries = self.list(None)?;
+        entries.reverse();
+        let mut invoices = self.list_invoices()?;
+        invoices.reverse();
+        Ok(DatabaseExport {
+            schema_version: self.schema_version()?,
+            exported_at: Utc::now(),
+            profiles: self.list_profiles()?,
+            clients: self.list_clients()?,
+            projects: self.list_projects()?,
+            entries,
+            entry_tags: self.all_entry_tags()?.into_iter().collect(),
+            review_flags: self.review_flags()?.into_iter().collect(),
+            invoices,
+            templates: self.list_templates()?,
+            timer_settings: self.get_timer_settings()?,
+            notification_settings: self.get_notification_settings()?,
+            tui_settings: self.get_tui_settings()?,
+            pomodoro: self.get_pomodoro_config()?,
+            timer_targets: self.timer_targets()?,
+            tag_colors: self.tag_colors()?.into_iter().collect(),
+            network_rules: self.list_network_rules()?,
+            time_off: self.list_time_off()?,
+            overtime_rates: self.all_overtime_rates()?,
+            expenses: self.list_expenses()?,
+            mileage_settings: Some(self.get_mileage_settings()?),
+            attachments: self.list_attachments()?,
+            invoice_drafts: self.list_invoice_drafts()?,
+            approval_sheets: self.list_approval_sheets()?,
+            payments: self.list_payments()?,
+            email_settings: Some(self.get_email_settings()?),
+            inbox_settings: Some(self.get_inbox_settings()?),
+            mail_rules: self.list_mail_rules()?,
+            mail_matches: self.list_mail_matches()?,
+            sync_settings: Some(self.get_sync_settings()?),
+            sync_mappings: self.all_sync_mappings()?,
+            sync_pushes: self.sync_pushes()?,
+            dunning_settings: Some(self.get_dunning_settings()?),
+            dunning_log: self.dunning_log(None)?,
+        })
+    }
+
+    /// Every countdown target, for the export
+    fn timer_targets(&self) -> Result<Vec<TimerTarget>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT entry_id, target_secs, notified FROM timer_targets ORDER BY entry_id",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(TimerTarget {
+                entry_id: row.get(0)?,
+                target_secs: row.get(1)?,
+                notified: row.get::<_, i64>(2)? != 0,
+            })
+        })?;
+        rows.collect()
+    }
+
+    /// Every client's overtime multipliers, by client ID, for the export
+    fn all_overtime_rates(&self) -> Result<BTreeMap<i64, OvertimeRates>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT client_id FROM overtime_rates ORDER BY client_id")?;
+        let client_ids = stmt
+            .query_map([], |row| row.get(0))?
+            .collect::<Result<Vec<i64>>>()?;
+        let mut rates = BTreeMap::new();
+        for client_id in client_ids {
+            if let Some(r) = self.get_overtime_rates(client_id)? {
+                rates.insert(client_id, r);
+            }
+        }
+        Ok(rates)
+    }
+
+    /// Projects mapped for every sync service, for the export
+    fn all_sync_mappings(&self) -> Result<Vec<SyncMapping>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT service, project, remote_project, remote_task, mapped_at
+             FROM sync_projects ORDER BY service, project",
+        )?;
+        let rows = stmt.query_map([], sync_mapping_from_row)?;
+        rows.collect()
+    }
+
+    /// Every entry pushed to a sync service, for the export
+    fn sync_pushes(&self) -> Result<Vec<SyncPush>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT service, entry_id, remote_id, pushed_at
+             FROM sync_pushed ORDER BY service, entry_id",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            let pushed_at: String = row.get(3)?;
+            Ok(SyncPush {
+                service: row.get(0)?,
+                entry_id: row.get(1)?,
+                remote_id: row.get(2)?,
+                pushed_at: DateTime::parse_from_rfc3339(&pushed_at)
+                    .map(|d| d.with_timezone(&Utc))
+                    .map_err(|e| {
+                        rusqlite::Error::FromSqlConversionFailure(
+                            3,
+                            rusqlite::types::Type::Text,
+                            Box::new(e),
+                        )
+                    })?,
+            })
+        })?;
+        rows.collect()
+    }
+
+    /// Restore a `meter export json` file in one transaction, keeping every ID.
+    /// Meant for an empty database (see `is_fresh`): rows with IDs already in
+    /// use make it fail and nothing is imported.
+    pub fn import_all(&self, data: &DatabaseExport) -> Result<()> {
+        let tx = self.write_transaction()?;
+        for profile in &data.profiles {
+            self.conn.execute(
+                "INSERT INTO invoice_settings (id, name, first_number) VALUES (?1, ?2, ?3)
+                 ON CONFLICT(id) DO UPDATE SET name = ?2, first_number = ?3",
+                params![profile.id, profile.name, profile.first_number],
+            )?;
+            self.set_profile_settings(profile.id, &profile.settings)?;
+        }
+        for client in &data.clients {
+            self.conn.execute(
+                "INSERT INTO clients (id, name, contact_person, address_street, address_city,
+                                      address_state, address_postal, address_country, email,
+                                      archived, reminder_day, profile_id)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+                params![
+                    client.id,
+                    client.name,
+                    client.contact_person,
+                    client.address_street,
+                    client.address_city,
+                    client.address_state,
+                    client.address_postal,
+                    client.address_country,
+                    client.email,
+                    client.archived,
+                    client.reminder_day,
+                    client.profile_id,
+                ],
+            )?;
+        }
+        for project in &data.projects {
+            self.conn.execute(
+                "INSERT INTO projects (id, name, rate, currency, client_id, hour_cap, cap_period,
+                                       billing_mode, idle_stop_minutes, area)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                params![
+                    project.id,
+                    project.name,
+                    project.rate,
+                    project.currency,
+                    project.client_id,
+                    project.hour_cap,
+                    project.cap_period,
+                    project.billing_mode,
+                    project.idle_stop_minutes,
+                    project.area,
+                ],
+            )?;
+        }
+        for entry in &data.entries {
+            self.conn.execute(
+                "INSERT INTO entries (id, project, description, start, end, status, days, issue,
+                                      location)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                params![
+                    entry.id,
+                    entry.project,
+                    entry.description,
+                    entry.start.to_rfc3339(),
+                    entry.end.map(|e| e.to_rfc3339()),
+                    entry.status,
+                    entry.days,
+                    entry.issue,
+                    entry.location,
+                ],
+            )?;
+        }
+        for (entry_id, tags) in &data.entry_tags {
+            for tag in tags {
+                self.conn.execute(
+                    "INSERT INTO entry_tags (entry_id, tag) VALUES (?1, ?2)",
+                    params![entry_id, tag],
+                )?;
+            }
+        }
+        for (entry_id, reasons) in &data.review_flags {
+            for reason in reasons {
+                self.conn.execute(
+                    "INSERT INTO review_flags (entry_id, reason) VALUES (?1, ?2)",
+                    params![entry_id, reason],
+                )?;
+            }
+        }
+        for invoice in &data.invoices {
+            self.conn.execute(
+                "INSERT INTO invoices (id, invoice_number, client_id, date_issued, due_date,
+                                       subtotal, tax_rate, tax_amount, total, file_path,
+                                       snapshot, void_reason, supersedes, profile_id)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+                params![
+                    invoice.id,
+                    invoice.invoice_number,
+                    invoice.client_id,
+                    invoice.date_issued,
+                    invoice.due_date,
+                    invoice.subtotal,
+                    invoice.tax_rate,
+                    invoice.tax_amount,
+                    invoice.total,
+                    invoice.file_path,
+                    invoice.snapshot,
+                    invoice.void_reason,
+                    invoice.supersedes,
+                    invoice.profile_id,
+                ],
+            )?;
+        }
+        for template in &data.templates {
+            self.save_template(template)?;
+        }
+        self.set_timer_settings(&data.timer_settings)?;
+        self.set_notification_settings(&data.notification_settings)?;
+        self.set_tui_settings(&data.tui_settings)?;
+        self.set_pomodoro_config(&data.pomodoro)?;
+        for target in &data.timer_targets {
+            self.conn.execute(
+                "INSERT INTO timer_targets (entry_id, target_secs, notified) VALUES (?1, ?2, ?3)",
+                params![target.entry_id, target.target_secs, target.notified],
+            )?;
+        }
+        for (tag, color) in &data.tag_colors {
+            self.set_tag_color(tag, Some(color))?;
+        }
+        for rule in &data.network_rules {
+            self.conn.execute(
+                "INSERT INTO network_rules (id, ssid, project, location) VALUES (?1, ?2, ?3, ?4)",
+                params![rule.id, rule.ssid, rule.project, rule.location],
+            )?;
+        }
+        for time_off in &data.time_off {
+            self.conn.execute(
+                "INSERT INTO time_off (id, start, end, reason, holiday)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![
+                    time_off.id,
+                    time_off.start.format("%Y-%m-%d").to_string(),
+                    time_off.end.format("%Y-%m-%d").to_string(),
+                    time_off.reason,
+                    time_off.holiday,
+                ],
+            )?;
+        }
+        for (client_id, rates) in &data.overtime_rates {
+            self.set_overtime_rates(*client_id, rates)?;
+        }
+        for expense in &data.expenses {
+            self.conn.execute(
+                "INSERT INTO expenses (id, date, kind, description, amount, distance, unit,
+                                       entry_id, client_id, invoice_number)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                params![
+                    expense.id,
+                    expense.date.format("%Y-%m-%d").to_string(),
+                    expense.kind,
+                    expense.description,
+                    expense.amount,
+                    expense.distance,
+                    expense.unit,
+                    expense.entry_id,
+                    expense.client_id,
+                    expense.invoice_number,
+                ],
+            )?;
+        }
+        if let Some(settings) = &data.mileage_settings {
+            self.set_mileage_settings(settings)?;
+        }
+        for attachment in &data.attachments {
+            self.conn.execute(
+                "INSERT INTO attachments (id, expense_id, invoice_number, name, path, added_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![
+                    attachment.id,
+                    attachment.expense_id,
+                    attachment.invoice_number,
+                    attachment.name,
+                    attachment.path,
+                    attachment.added_at.to_rfc3339(),
+                ],
+            )?;
+        }
+        for draft in &data.invoice_drafts {
+            self.conn.execute(
+                "INSERT INTO invoice_drafts (id, client_id, year, month, tax_rate,
+                                             rounding_minutes, supersedes, profile_id, created_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                params![
+                    draft.id,
+                    draft.client_id,
+                    draft.year,
+                    draft.month,
+                    draft.tax_rate,
+                    draft.rounding_minutes,
+                    draft.supersedes,
+                    draft.profile_id,
+                    draft.created_at.to_rfc3339(),
+                ],
+            )?;
+            for entry_id in &draft.entry_ids {
+                self.conn.execute(
+                    "INSERT INTO invoice_draft_entries (draft_id, entry_id) VALUES (?1, ?2)",
+                    params![draft.id, entry_id],
+                )?;
+            }
+            for item in &draft.items {
+                self.conn.execute(
+                    "INSERT INTO invoice_draft_items (id, draft_id, description, amount)
+                     VALUES (?1, ?2, ?3, ?4)",
+                    params![item.id, draft.id, item.description, item.amount],
+                )?;
+            }
+        }
+        for sheet in &data.approval_sheets {
+            self.conn.execute(
+                "INSERT INTO approval_sheets (id, client_id, year, month, file_path, created_at,
+                                              approved_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![
+                    sheet.id,
+                    sheet.client_id,
+                    sheet.year,
+                    sheet.month,
+                    sheet.file_path,
+                    sheet.created_at.to_rfc3339(),
+                    sheet.approved_at.map(|t| t.to_rfc3339()),
+                ],
+            )?;
+            for entry_id in &sheet.entry_ids {
+                self.conn.execute(
+                    "INSERT INTO approval_sheet_entries (sheet_id, entry_id) VALUES (?1, ?2)",
+                    params![sheet.id, entry_id],
+                )?;
+            }
+        }
+        for payment in &data.payments {
+            self.conn.execute(
+                "INSERT INTO payments (id, provider, external_id, invoice_number, amount, fee,
+                                       currency, paid_at, expense_id)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                params![
+                    payment.id,
+                    payment.provider,
+                    payment.external_id,
+                    payment.invoice_number,
+                    payment.amount,
+                    payment.fee,
+                    payment.currency,
+                    payment.paid_at.to_rfc3339(),
+                    payment.expense_id,
+                ],
+            )?;
+        }
+        if let Some(settings) = &data.email_settings {
+            self.set_email_settings(settings)?;
+        }
+        if let Some(settings) = &data.inbox_settings {
+            self.set_inbox_settings(settings)?;
+        }
+        for rule in &data.mail_rules {
+            self.conn.execute(
+                "INSERT INTO mail_rules (id, pattern, action) VALUES (?1, ?2, ?3)",
+                params![rule.id, rule.pattern, rule.action],
+            )?;
+        }
+        for m in &data.mail_matches {
+            self.conn.execute(
+                "INSERT INTO mail_matches (id, invoice_number, action, subject, sender,
+                                           matched_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![
+                    m.id,
+                    m.invoice_number,
+                    m.action,
+                    m.subject,
+                    m.sender,
+                    m.matched_at.to_rfc3339(),
+                ],
+            )?;
+        }
+        if let Some(settings) = &data.sync_settings {
+            self.set_sync_settings(settings)?;
+        }
+        for mapping in &data.sync_mappings {
+            self.conn.execute(
+                "INSERT INTO sync_projects (service, project, remote_project, remote_task,
+                                            mapped_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![
+                    mapping.service,
+                    mapping.project,
+                    mapping.remote_project,
+                    mapping.remote_task,
+                    mapping.mapped_at.to_rfc3339(),
+                ],
+            )?;
+        }
+        for push in &data.sync_pushes {
+            self.conn.execute(
+                "INSERT INTO sync_pushed (service, entry_id, remote_id, pushed_at)
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![
+                    push.service,
+                    push.entry_id,
+                    push.remote_id,
+                    push.pushed_at.to_rfc3339(),
+                ],
+            )?;
+        }
+        if let Some(settings) = &data.dunning_settings {
+            self.set_dunning_settings(settings)?;
+        }
+        for sent in &data.dunning_log {
+            self.conn.execute(
+                "INSERT INTO dunning_log (invoice_number, step, sent_at, emailed_to, error)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![
+                    sent.invoice_number,
+                    sent.step,
+                    sent.sent_at.to_rfc3339(),
+                    sent.emailed_to,
+                    sent.error,
+                ],
+            )?;
+        }
+        tx.commit()
+    }
+}
+
+/// Map a `SELECT id, project, description, start, end, status, days, issue, location` row to an `Entry`.
+fn entry_from_row(row: &Row) -> Result<Entry> {
+    Ok(Entry {
+        id: row.get(0)?,
+        project: row.get(1)?,
+        description: row.get(2)?,
+        start: DateTime::parse_from_rfc3339(row.get::<_, String>(3)?.as_str())
+            .unwrap()
+            .with_timezone(&Utc),
+        end: match row.get::<_, Option<String>>(4)? {
+            Some(s) => Some(
+                DateTime::parse_from_rfc3339(&s)
+                    .unwrap()
+                    .with_timezone(&Utc),
+            ),
+            None => None,
+        },
+        status: row.get(5)?,
+        days: row.get(6)?,
+        issue: row.get(7)?,
+        location: row.get(8)?,
+    })
+}
+
+/// Map a `SELECT id, app, start, end` row to an `ActivitySpan`.
+fn activity_from_row(row: &Row) -> Result<ActivitySpan> {
+    let time = |idx: usize| -> Result<DateTime<Utc>> {
+        let s: String = row.get(idx)?;
+        DateTime::parse_from_rfc3339(&s)
+            .map(|t| t.with_timezone(&Utc))
+            .map_err(|e| {
+                rusqlite::Error::FromSqlConversionFailure(
+                    idx,
+                    rusqlite::types::Type::Text,
+                    Box::new(e),
+                )
+            })
+    };
+    Ok(ActivitySpan {
+        id: row.get(0)?,
+        app: row.get(1)?,
+        start: time(2)?,
+        end: time(3)?,
+    })
+}
+
+fn template_from_row(row: &Row) -> Result<EntryTemplate> {
+    let tags: String = row.get(4)?;
+    Ok(EntryTemplate {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        project: row.get(2)?,
+        description: row.get(3)?,
+        tags: tags.split_whitespace().map(str::to_string).collect(),
+        duration: row.get(5)?,
+    })
+}
+
+fn network_rule_from_row(row: &Row) -> Result<NetworkRule> {
+    Ok(NetworkRule {
+        id: row.get(0)?,
+        ssid: row.get(1)?,
+        project: row.get(2)?,
+        location: row.get(3)?,
+    })
+}
+
+fn time_off_from_row(row: &Row) -> Result<TimeOff> {
+    let date = |idx: usize| -> Result<NaiveDate> {
+        let s: String = row.get(idx)?;
+        NaiveDate::parse_from_str(&s, "%Y-%m-%d").map_err(|e| {
+            rusqlite::Error::FromSqlConversionFailure(idx, rusqlite::types::Type::Text, Box::new(e))
+        })
+    };
+    Ok(TimeOff {
+        id: row.get(0)?,
+        start: date(1)?,
+        end: date(2)?,
+        reason: row.get(3)?,
+        holiday: row.get(4)?,
+    })
+}
+
+fn expense_from_row(row: &Row) -> Result<Expense> {
+    let date: String = row.get(1)?;
+    let date = NaiveDate::parse_from_str(&date, "%Y-%m-%d").map_err(|e| {
+        rusqlite::Error::FromSqlConversionFailure(1, rusqlite::types::Type::Text, Box::new(e))
+    })?;
+    Ok(Expense {
+        id: row.get(0)?,
+        date,
+        kind: row.get(2)?,
+        description: row.get(3)?,
+        amount: row.get(4)?,
+        distance: row.get(5)?,
+        unit: row.get(6)?,
+        entry_id: row.get(7)?,
+        client_id: row.get(8)?,
+        invoice_number: row.get(9)?,
+    })
+}
+
+/// Map an `invoice_drafts` row to an `InvoiceDraft` without its entries or items.
+fn invoice_draft_from_row(row: &Row) -> Result<InvoiceDraft> {
+    let created_at: String = row.get(6)?;
+    Ok(InvoiceDraft {
+        id: row.get(0)?,
+        client_id: row.get(1)?,
+        year: row.get(2)?,
+        month: row.get(3)?,
+        tax_rate: row.get(4)?,
+        rounding_minutes: row.get(5)?,
+        entry_ids: Vec::new(),
+        items: Vec::new(),
+        supersedes: row.get(7)?,
+        profile_id: row.get(8)?,
+        created_at: DateTime::parse_from_rfc3339(&created_at)
+            .map(|d| d.with_timezone(&Utc))
+            .map_err(|e| {
+                rusqlite::Error::FromSqlConversionFailure(
+                    6,
+                    rusqlite::types::Type::Text,
+                    Box::new(e),
+                )
+            })?,
+    })
+}
+
+fn approval_sheet_from_row(row: &Row) -> Result<ApprovalSheet> {
+    let time = |idx: usize, value: String| {
+        DateTime::parse_from_rfc3339(&value)
+            .map(|d| d.with_timezone(&Utc))
+            .map_err(|e| {
+                rusqlite::Error::FromSqlConversionFailure(
+                    idx,
+                    rusqlite::types::Type::Text,
+                    Box::new(e),
+                )
+            })
+    };
+    Ok(ApprovalSheet {
+        id: row.get(0)?,
+        client_id: row.get(1)?,
+        year: row.get(2)?,
+        month: row.get(3)?,
+        file_path: row.get(4)?,
+        entry_ids: Vec::new(),
+        created_at: time(5, row.get(5)?)?,
+        approved_at: row
+            .get::<_, Option<String>>(6)?
+            .map(|t| time(6, t))
+            .transpose()?,
+    })
+}
+
+fn attachment_from_row(row: &Row) -> Result<Attachment> {
+    let added_at: String = row.get(5)?;
+    Ok(Attachment {
+        id: row.get(0)?,
+        expense_id: row.get(1)?,
+        invoice_number: row.get(2)?,
+        name: row.get(3)?,
+        path: row.get(4)?,
+        added_at: DateTime::parse_from_rfc3339(&added_at)
+            .map(|d| d.with_timezone(&Utc))
+            .map_err(|e| {
+                rusqlite::Error::FromSqlConversionFailure(
+                    5,
+                    rusqlite::types::Type::Text,
+                    Box::new(e),
+                )
+            })?,
+    })
+}
+
+/// Map a `SELECT service, project, remote_project, remote_task, mapped_at` row to
+/// a `SyncMapping`.
+fn mail_match_from_row(row: &Row) -> Result<MailMatch> {
+    let matched_at: String = row.get(5)?;
+    Ok(MailMatch {
+        id: row.get(0)?,
+        invoice_number: row.get(1)?,
+        action: row.get(2)?,
+        subject: row.get(3)?,
+        sender: row.get(4)?,
+        matched_at: DateTime::parse_from_rfc3339(&matched_at)
+            .map(|d| d.with_timezone(&Utc))
+            .map_err(|e| {
+                rusqlite::Error::FromSqlConversionFailure(
+                    5,
+                    rusqlite::types::Type::Text,
+                    Box::new(e),
+                )
+            })?,
+    })
+}
+
+fn payment_from_row(row: &Row) -> Result<Payment> {
+    let paid_at: String = row.get(7)?;
+    Ok(Payment {
+        id: row.get(0)?,
+        provider: row.get(1)?,
+        external_id: row.get(2)?,
+        invoice_number: row.get(3)?,
+        amount: row.get(4)?,
+        fee: row.get(5)?,
+        currency: row.get(6)?,
+        paid_at: DateTime::parse_from_rfc3339(&paid_at)
+            .map(|d| d.with_timezone(&Utc))
+            .map_err(|e| {
+                rusqlite::Error::FromSqlConversionFailure(
+                    7,
+                    rusqlite::types::Type::Text,
+                    Box::new(e),
+                )
+            })?,
+        expense_id: row.get(8)?,
+    })
+}
+
+fn profile_from_row(row: &Row) -> Result<BusinessProfile> {
+    Ok(BusinessProfile {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        first_number: row.get(2)?,
+        settings: InvoiceSettings {
+            business_name: row.get(3)?,
+            address_street: row.get(4)?,
+            address_city: row.get(5)?,
+            address_state: row.get(6)?,
+            address_postal: row.get(7)?,
+            address_country: row.get(8)?,
+            email: row.get(9)?,
+            phone: row.get(10)?,
+            tax_id: row.get(11)?,
+            payment_instructions: row.get(12)?,
+            default_payment_terms: row.get(13)?,
+            default_tax_rate: row.get(14)?,
+            reminder_day: row.get(15)?,
+            include_entry_notes: row.get(16)?,
+            paper_size: row.get(17)?,
+            margin_mm: row.get(18)?,
+            font_size: row.get(19)?,
+            section_order: row.get(20)?,
+            iban: row.get(21)?,
+            bic: row.get(22)?,
+            account_number: row.get(23)?,
+            routing_number: row.get(24)?,
+            swift: row.get(25)?,
+            business_day_due_dates: row.get(26)?,
+            json_snapshot: row.get(27)?,
+            issue_refs: row.get(28)?,
+            logo_path: row.get(29)?,
+            tax_inclusive: row.get(30)?,
+            number_locale: row.get(31)?,
+        },
+    })
+}
+
+fn sync_mapping_from_row(row: &Row) -> Result<SyncMapping> {
+    sync_mapping_at(row, 0)
+}
+
+/// Read a `SyncMapping` from the five columns starting at `idx`.
+fn sync_mapping_at(row: &Row, idx: usize) -> Result<SyncMapping> {
+    let mapped_at: String = row.get(idx + 4)?;
+    Ok(SyncMapping {
+        service: row.get(idx)?,
+        project: row.get(idx + 1)?,
+        remote_project: row.get(idx + 2)?,
+        remote_task: row.get(idx + 3)?,
+        mapped_at: DateTime::parse_from_rfc3339(&mapped_at)
+            .map(|d| d.with_timezone(&Utc))
+            .map_err(|e| {
+                rusqlite::Error::FromSqlConversionFailure(
+                    idx + 4,
+                    rusqlite::types::Type::Text,
+                    Box::new(e),
+                )
+            })?,
+    })
+}
+
+/// Break `start..end` at each local midnight in between.
+fn midnight_segments(
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+    let mut segments = Vec::new();
+    let mut seg_start = start;
+    loop {
+        let next_day = seg_start.with_timezone(&Local).date_naive() + Days::new(1);
+        let midnight = Local
+            .from_local_datetime(&next_day.and_time(NaiveTime::MIN))
+            .earliest()
+            .map(|m| m.with_timezone(&Utc));
+        match midnight {
+            Some(m) if m < end => {
+                segments.push((seg_start, m));
+                seg_start = m;
+            }
+            _ => {
+                segments.push((seg_start, end));
+                return segments;
+            }
+        }
+    }
+}
+
+/// Quote statuses for an SQL `IN (...)` list.
+fn status_list(statuses: &[EntryStatus]) -> String {
+    statuses
+        .iter()
+        .map(|s| format!("'{}'", s.as_str()))
+        .collect::<Vec<_>>()
+        .join(", ")
 }
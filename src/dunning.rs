@@ -0,0 +1,92 @@
+//! Late-payment reminders ("dunning"). Each overdue invoice gets a reminder at
+//! every step of the schedule set with `meter dunning schedule`, e.g. 3, 10 and
+//! 20 days past due. The menu bar app sends them once a day as notifications,
+//! and `meter dunning run` does the same from the command line; either can also
+//! email them to the client. Every reminder sent is logged, so none goes out twice.
+
+use chrono::{NaiveDate, Utc};
+
+use crate::db::Db;
+use crate::email;
+use crate::models::{DunningReminder, format_money_in};
+
+/// A reminder that went out, and where it was emailed or why it couldn't be
+/// (`None` when emailing is off)
+pub struct SentReminder {
+    pub reminder: DunningReminder,
+    pub email: Option<Result<String, String>>,
+}
+
+/// Send every reminder due on `today` and log it. Reminders another process
+/// has already claimed are left out.
+pub fn send_due(db: &Db, today: NaiveDate) -> rusqlite::Result<Vec<SentReminder>> {
+    let settings = db.get_dunning_settings()?;
+    let mut sent = Vec::new();
+    for reminder in db.due_dunning_reminders(today, &settings.schedule)? {
+        if !db.claim_dunning_reminder(reminder.invoice_number, reminder.step, Utc::now())? {
+            continue;
+        }
+        let email = settings.email.then(|| email_reminder(db, &reminder));
+        if let Some(result) = &email {
+            db.set_dunning_email_result(
+                reminder.invoice_number,
+                reminder.step,
+                result.as_deref().ok(),
+                result.as_ref().err().map(String::as_str),
+            )?;
+        }
+        sent.push(SentReminder { reminder, email });
+    }
+    Ok(sent)
+}
+
+/// Email a reminder to the invoice's client. Returns the address it went to.
+fn email_reminder(db: &Db, reminder: &DunningReminder) -> Result<String, String> {
+    let client = match reminder.client_id {
+        Some(id) => db.get_client(id).map_err(|e| e.to_string())?,
+        None => None,
+    };
+    let Some(client) = client.filter(|c| !c.email.trim().is_empty()) else {
+        return Err(format!(
+            "Invoice #{:04} has no client email",
+            reminder.invoice_number
+        ));
+    };
+    let settings = db
+        .get_profile_settings(reminder.profile_id)
+        .or_else(|_| db.get_invoice_settings())
+        .map_err(|e| e.to_string())?;
+
+    let greeting = if client.contact_person.is_empty() {
+        format!("Hello {},", client.name)
+    } else {
+        format!("Hello {},", client.contact_person)
+    };
+    let subject = if settings.business_name.is_empty() {
+        format!(
+            "Reminder: invoice #{:04} is past due",
+            reminder.invoice_number
+        )
+    } else {
+        format!(
+            "Reminder: invoice #{:04} from {} is past due",
+            reminder.invoice_number, settings.business_name
+        )
+    };
+    let body = format!(
+        "{}\n\nThis is a friendly reminder that invoice #{:04} for {}, due {}, is now {} days \
+         past due. If you have already paid it, please disregard this message.\n\n\
+         Thank you,\n{}\n",
+        greeting,
+        reminder.invoice_number,
+        format_money_in(reminder.total, &reminder.currency, settings.number_locale),
+        reminder.due_date.format("%Y-%m-%d"),
+        reminder.days_overdue,
+        settings.business_name
+    );
+
+    let email_settings = db.get_email_settings().map_err(|e| e.to_string())?;
+    let to = client.email.trim();
+    email::send(&email_settings, to, &subject, &body)?;
+    Ok(to.to_string())
+}
@@ -0,0 +1,124 @@
+//! Outgoing email over SMTP. The SMTP password is kept with the other secrets
+//! (see `secrets`), keyed by the account it belongs to.
+
+use lettre::message::header::ContentType;
+use lettre::message::{Attachment, Mailbox, MessageBuilder, MultiPart, SinglePart};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{Message, SmtpTransport, Transport};
+use std::fs;
+use std::path::Path;
+
+use crate::models::{EmailSettings, SmtpSecurity};
+use crate::secrets;
+
+fn password_secret(settings: &EmailSettings) -> String {
+    format!("smtp:{}@{}", settings.smtp_username, settings.smtp_host)
+}
+
+/// The stored SMTP password, if there is one
+pub fn smtp_password(settings: &EmailSettings) -> Result<Option<String>, String> {
+    secrets::get(&password_secret(settings))
+}
+
+/// Store the SMTP password, or remove it when `password` is empty
+pub fn set_smtp_password(settings: &EmailSettings, password: &str) -> Result<(), String> {
+    if password.is_empty() {
+        secrets::delete(&password_secret(settings))
+    } else {
+        secrets::set(&password_secret(settings), password)
+    }
+}
+
+/// Send a plain-text email through the configured server
+pub fn send(settings: &EmailSettings, to: &str, subject: &str, body: &str) -> Result<(), String> {
+    let message = message(settings, to, subject)?
+        .body(body.to_string())
+        .map_err(|e| format!("Failed to build email: {}", e))?;
+    deliver(settings, &message)
+}
+
+/// Send an email with a file attached, e.g. an invoice PDF. With `html`, the
+/// body is sent both as plain text and as HTML and mail apps show the HTML.
+pub fn send_with_attachment(
+    settings: &EmailSettings,
+    to: &str,
+    subject: &str,
+    text: &str,
+    html: Option<&str>,
+    attachment: &Path,
+) -> Result<(), String> {
+    let content = fs::read(attachment)
+        .map_err(|e| format!("Failed to read {}: {}", attachment.display(), e))?;
+    let filename = attachment
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let content_type = match attachment.extension().and_then(|e| e.to_str()) {
+        Some("pdf") => ContentType::parse("application/pdf"),
+        Some("zip") => ContentType::parse("application/zip"),
+        _ => ContentType::parse("application/octet-stream"),
+    }
+    .map_err(|e| format!("Invalid attachment type: {}", e))?;
+
+    let body = match html {
+        Some(html) => MultiPart::mixed().multipart(MultiPart::alternative_plain_html(
+            text.to_string(),
+            html.to_string(),
+        )),
+        None => MultiPart::mixed().singlepart(SinglePart::plain(text.to_string())),
+    }
+    .singlepart(Attachment::new(filename).body(content, content_type));
+    let message = message(settings, to, subject)?
+        .multipart(body)
+        .map_err(|e| format!("Failed to build email: {}", e))?;
+    deliver(settings, &message)
+}
+
+/// A message from the configured address to `to`, ready for its body
+fn message(settings: &EmailSettings, to: &str, subject: &str) -> Result<MessageBuilder, String> {
+    if !settings.is_configured() {
+        return Err("Email is not set up: set an SMTP host and from address".to_string());
+    }
+    let from: Mailbox = settings
+        .from_address
+        .parse()
+        .map_err(|e| format!("Invalid from address '{}': {}", settings.from_address, e))?;
+    let to: Mailbox = to
+        .parse()
+        .map_err(|e| format!("Invalid recipient '{}': {}", to, e))?;
+    Ok(Message::builder().from(from).to(to).subject(subject))
+}
+
+/// Send a message through the configured server
+fn deliver(settings: &EmailSettings, message: &Message) -> Result<(), String> {
+    let host = settings.smtp_host.as_str();
+    let builder = match settings.security {
+        SmtpSecurity::StartTls => SmtpTransport::starttls_relay(host),
+        SmtpSecurity::Tls => SmtpTransport::relay(host),
+        SmtpSecurity::None => Ok(SmtpTransport::builder_dangerous(host)),
+    }
+    .map_err(|e| format!("Invalid SMTP host '{}': {}", host, e))?;
+    let port = u16::try_from(settings.smtp_port)
+        .map_err(|_| format!("Invalid SMTP port {}", settings.smtp_port))?;
+    let mut builder = builder.port(port);
+    if !settings.smtp_username.is_empty() {
+        let password = smtp_password(settings)?.unwrap_or_default();
+        builder = builder.credentials(Credentials::new(settings.smtp_username.clone(), password));
+    }
+
+    builder
+        .build()
+        .send(message)
+        .map_err(|e| format!("Failed to send email: {}", e))?;
+    Ok(())
+}
+
+/// Send a short message to the from address to check the settings work
+pub fn send_test_email(settings: &EmailSettings) -> Result<(), String> {
+    send(
+        settings,
+        &settings.from_address,
+        "Meter test email",
+        "This is a test email from Meter. Your SMTP settings work.",
+    )
+}
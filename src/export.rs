@@ -0,0 +1,58 @@
+//! `meter export json`: the whole database as one JSON document (see
+//! `DatabaseExport`), to be restored on another machine with `meter import json`.
+
+use crate::db::Db;
+use crate::models::{DatabaseExport, SCHEMA_VERSION};
+
+/// Formats `meter export` writes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// Every profile, client, project, entry, invoice and setting
+    Json,
+}
+
+impl ExportFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ExportFormat::Json => "json",
+        }
+    }
+}
+
+impl std::fmt::Display for ExportFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for ExportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "json" => Ok(ExportFormat::Json),
+            _ => Err(format!("Unknown export format '{}' (expected json)", s)),
+        }
+    }
+}
+
+/// The database as pretty-printed JSON
+pub fn to_json(db: &Db) -> Result<String, String> {
+    let export = db.export_all().map_err(|e| e.to_string())?;
+    serde_json::to_string_pretty(&export).map_err(|e| e.to_string())
+}
+
+/// Read a `meter export json` file. Files from a newer schema are refused, as
+/// this version would drop what it doesn't know about.
+pub fn parse_json(data: &str) -> Result<DatabaseExport, String> {
+    let export: DatabaseExport =
+        serde_json::from_str(data).map_err(|e| format!("invalid export file: {}", e))?;
+    if export.schema_version > SCHEMA_VERSION {
+        return Err(format!(
+            "exported by a newer version of meter (schema {}, this one reads up to {}); \
+             upgrade meter first",
+            export.schema_version, SCHEMA_VERSION
+        ));
+    }
+    Ok(export)
+}
@@ -0,0 +1,561 @@
+//! Events read from an iCalendar (.ics) file or feed, for `meter reconcile` to
+//! compare against tracked time. Only what that needs is read: each event's
+//! summary, start and end, and its repeat rule. Times with a `TZID` are taken
+//! as local time, since there's no time zone database to convert them with.
+//! Recurring events are expanded into one event per occurrence (`RRULE` with
+//! `FREQ` DAILY, WEEKLY, MONTHLY or YEARLY, less `EXDATE`s and moved or
+//! cancelled occurrences); a rule that can't be expanded is an error rather
+//! than a meeting silently missed.
+
+use std::collections::HashSet;
+
+use chrono::{
+    DateTime, Datelike, Days, Duration, Local, Months, NaiveDate, NaiveDateTime, TimeZone, Utc,
+    Weekday,
+};
+
+const USER_AGENT: &str = "meter (https://github.com/bpeterson92/meter)";
+
+/// One calendar event
+#[derive(Debug, Clone, PartialEq)]
+pub struct CalendarEvent {
+    pub summary: String,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    /// A whole-day event such as a holiday, rather than a meeting
+    pub all_day: bool,
+}
+
+/// Read a calendar from a file, or fetch it from an `http(s)://` or `webcal://` URL
+pub fn load(source: &str) -> Result<String, String> {
+    let url = match source.strip_prefix("webcal://") {
+        Some(rest) => format!("https://{}", rest),
+        None => source.to_string(),
+    };
+    if url.starts_with("https://") || url.starts_with("http://") {
+        return match ureq::get(&url).set("User-Agent", USER_AGENT).call() {
+            Ok(response) => response
+                .into_string()
+                .map_err(|e| format!("Failed to read the calendar: {}", e)),
+            Err(ureq::Error::Status(code, _)) => {
+                Err(format!("The calendar server answered {}", code))
+            }
+            Err(e) => Err(format!("Failed to fetch the calendar: {}", e)),
+        };
+    }
+    std::fs::read_to_string(source).map_err(|e| format!("Failed to read {}: {}", source, e))
+}
+
+/// The events in an iCalendar document that start before `until`, by start
+/// time, with recurring events expanded. Cancelled events and events without a
+/// readable start or length are left out.
+pub fn parse(data: &str, until: DateTime<Utc>) -> Result<Vec<CalendarEvent>, String> {
+    let mut fields = Vec::new();
+    let mut current: Option<EventFields> = None;
+    // Components inside the event, such as alarms, whose properties aren't its own
+    let mut nested = 0;
+    for line in unfold(data) {
+        let Some((name, params, value)) = split_property(&line) else {
+            continue;
+        };
+        let is_event = value.eq_ignore_ascii_case("VEVENT");
+        match name.as_str() {
+            "BEGIN" if is_event => {
+                current = Some(EventFields::default());
+                nested = 0;
+            }
+            "END" if is_event => fields.extend(current.take()),
+            "BEGIN" => nested += 1,
+            "END" => nested -= 1,
+            _ => {
+                let Some(fields) = current.as_mut().filter(|_| nested == 0) else {
+                    continue;
+                };
+                match name.as_str() {
+                    "SUMMARY" => fields.summary = unescape(value),
+                    "UID" => fields.uid = value.to_string(),
+                    "DTSTART" => {
+                        fields.start = parse_time(&params, value);
+                        fields.utc = value.ends_with('Z');
+                    }
+                    "DTEND" => fields.end = parse_time(&params, value),
+                    "DURATION" => fields.duration = Some(parse_duration(value)),
+                    "STATUS" => fields.cancelled = value.eq_ignore_ascii_case("CANCELLED"),
+                    "RRULE" => fields.rrule = Some(value.to_string()),
+                    "EXDATE" => fields.exdates.extend(
+                        value
+                            .split(',')
+                            .filter_map(|v| parse_time(&params, v.trim())),
+                    ),
+                    "RECURRENCE-ID" => {
+                        fields.recurrence_id = parse_time(&params, value).map(|(t, _)| t)
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    // Occurrences moved or cancelled by an event of their own
+    let overridden: HashSet<(String, DateTime<Utc>)> = fields
+        .iter()
+        .filter_map(|f| Some((f.uid.clone(), f.recurrence_id?)))
+        .collect();
+    let mut events = Vec::new();
+    for f in fields {
+        let rule = match (&f.rrule, f.recurrence_id) {
+            (Some(rrule), None) => Some(
+                RepeatRule::parse(rrule)
+                    .map_err(|e| format!("Can't expand '{}' ({}): {}", f.summary, rrule, e))?,
+            ),
+            _ => None,
+        };
+        let uid = f.uid.clone();
+        let utc = f.utc;
+        let exdates = f.exdates.clone();
+        let Some(event) = f.finish() else {
+            continue;
+        };
+        let Some(rule) = rule else {
+            if event.start < until {
+                events.push(event);
+            }
+            continue;
+        };
+        let length = event.end - event.start;
+        for start in rule.occurrences(event.start, utc, until) {
+            let excluded = exdates.iter().any(|&(ex, is_date)| {
+                ex == start
+                    || (is_date
+                        && ex.with_timezone(&Local).date_naive()
+                            == start.with_timezone(&Local).date_naive())
+            });
+            if excluded || overridden.contains(&(uid.clone(), start)) {
+                continue;
+            }
+            let Some(end) = start.checked_add_signed(length) else {
+                continue;
+            };
+            events.push(CalendarEvent {
+                start,
+                end,
+                ..event.clone()
+            });
+        }
+    }
+    events.sort_by_key(|e| e.start);
+    Ok(events)
+}
+
+/// What has been read of a `VEVENT` so far
+#[derive(Default)]
+struct EventFields {
+    summary: String,
+    uid: String,
+    start: Option<(DateTime<Utc>, bool)>,
+    /// The start is a UTC time, so occurrences repeat in UTC rather than local time
+    utc: bool,
+    end: Option<(DateTime<Utc>, bool)>,
+    /// `Some(None)` for a DURATION that couldn't be read
+    duration: Option<Option<Duration>>,
+    cancelled: bool,
+    rrule: Option<String>,
+    /// Excluded occurrences, and whether each was given as a date
+    exdates: Vec<(DateTime<Utc>, bool)>,
+    /// The occurrence of a recurring event this event replaces
+    recurrence_id: Option<DateTime<Utc>>,
+}
+
+impl EventFields {
+    /// The event, ending at DTEND, or after DURATION, or else after a day for a
+    /// date and at once for a time, as RFC 5545 has it
+    fn finish(self) -> Option<CalendarEvent> {
+        let (start, all_day) = self.start?;
+        if self.cancelled {
+            return None;
+        }
+        let end = match (self.end, self.duration) {
+            (Some((end, _)), _) => end,
+            (None, Some(duration)) => start.checked_add_signed(duration?)?,
+            (None, None) if all_day => start + Duration::days(1),
+            (None, None) => start,
+        };
+        Some(CalendarEvent {
+            summary: self.summary,
+            start,
+            end: end.max(start),
+            all_day,
+        })
+    }
+}
+
+/// How often a repeat rule repeats
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+/// The parts of an `RRULE` that can be expanded
+#[derive(Debug)]
+struct RepeatRule {
+    frequency: Frequency,
+    interval: u32,
+    count: Option<usize>,
+    /// Last start allowed, and whether it was given as a date
+    until: Option<(DateTime<Utc>, bool)>,
+    /// Weekdays, each with the week of the month for MONTHLY rules (`2TU`,
+    /// `-1FR`), or `None` for every one
+    by_day: Vec<(Option<i32>, Weekday)>,
+    /// Days of the month for MONTHLY rules, negative from the end
+    by_month_day: Vec<i32>,
+    week_start: Weekday,
+}
+
+impl RepeatRule {
+    fn parse(rrule: &str) -> Result<Self, String> {
+        let mut frequency = None;
+        let mut rule = RepeatRule {
+            frequency: Frequency::Daily,
+            interval: 1,
+            count: None,
+            until: None,
+            by_day: Vec::new(),
+            by_month_day: Vec::new(),
+            week_start: Weekday::Mon,
+        };
+        for part in rrule.split(';').filter(|p| !p.is_empty()) {
+            let (key, value) = part
+                .split_once('=')
+                .ok_or_else(|| format!("unreadable part '{}'", part))?;
+            let bad = || format!("unreadable {}", part);
+            match key.to_ascii_uppercase().as_str() {
+                "FREQ" => {
+                    frequency = Some(match value.to_ascii_uppercase().as_str() {
+                        "DAILY" => Frequency::Daily,
+                        "WEEKLY" => Frequency::Weekly,
+                        "MONTHLY" => Frequency::Monthly,
+                        "YEARLY" => Frequency::Yearly,
+                        _ => return Err(format!("FREQ={} isn't supported", value)),
+                    })
+                }
+                "INTERVAL" => {
+                    rule.interval = value.parse().ok().filter(|n| *n > 0).ok_or_else(bad)?
+                }
+                "COUNT" => rule.count = Some(value.parse().map_err(|_| bad())?),
+                "UNTIL" => {
+                    rule.until = Some(parse_time(&[], value).ok_or_else(bad)?);
+                }
+                "BYDAY" => {
+                    for day in value.split(',') {
+                        let day = day.trim();
+                        let split = day
+                            .len()
+                            .checked_sub(2)
+                            .filter(|i| day.is_char_boundary(*i))
+                            .ok_or_else(bad)?;
+                        let (ordinal, name) = day.split_at(split);
+                        let ordinal = match ordinal.trim_start_matches('+') {
+                            "" => None,
+                            n => Some(n.parse().ok().filter(|n| *n != 0).ok_or_else(bad)?),
+                        };
+                        rule.by_day
+                            .push((ordinal, parse_weekday(name).ok_or_else(bad)?));
+                    }
+                }
+                "BYMONTHDAY" => {
+                    for day in value.split(',') {
+                        let day: i32 = day.trim().parse().map_err(|_| bad())?;
+                        if day == 0 || day.abs() > 31 {
+                            return Err(bad());
+                        }
+                        rule.by_month_day.push(day);
+                    }
+                }
+                "WKST" => rule.week_start = parse_weekday(value).ok_or_else(bad)?,
+                other => return Err(format!("{} isn't supported", other)),
+            }
+        }
+        rule.frequency = frequency.ok_or("FREQ is missing")?;
+        let monthly = rule.frequency == Frequency::Monthly;
+        if !rule.by_month_day.is_empty() && !monthly {
+            return Err("BYMONTHDAY is only supported with FREQ=MONTHLY".to_string());
+        }
+        if !rule.by_day.is_empty() && rule.frequency == Frequency::Yearly {
+            return Err("BYDAY isn't supported with FREQ=YEARLY".to_string());
+        }
+        if !monthly && rule.by_day.iter().any(|(ordinal, _)| ordinal.is_some()) {
+            return Err("numbered BYDAY is only supported with FREQ=MONTHLY".to_string());
+        }
+        if !rule.by_day.is_empty() && !rule.by_month_day.is_empty() {
+            return Err("BYDAY with BYMONTHDAY isn't supported".to_string());
+        }
+        Ok(rule)
+    }
+
+    /// Start times of the occurrences from `first` (always the first) up to
+    /// `until`. The rule repeats in UTC for a UTC start, else in local time.
+    fn occurrences(
+        &self,
+        first: DateTime<Utc>,
+        utc: bool,
+        until: DateTime<Utc>,
+    ) -> Vec<DateTime<Utc>> {
+        let start = if utc {
+            first.naive_utc()
+        } else {
+            first.with_timezone(&Local).naive_local()
+        };
+        let to_utc = |date: NaiveDate| {
+            let time = date.and_time(start.time());
+            if utc {
+                Some(Utc.from_utc_datetime(&time))
+            } else {
+                Local
+                    .from_local_datetime(&time)
+                    .earliest()
+                    .map(|t| t.with_timezone(&Utc))
+            }
+        };
+        let within_until = |t: DateTime<Utc>| match self.until {
+            Some((last, true)) => {
+                t.with_timezone(&Local).date_naive() <= last.with_timezone(&Local).date_naive()
+            }
+            Some((last, false)) => t <= last,
+            None => true,
+        };
+
+        let last_date = if utc {
+            until.date_naive()
+        } else {
+            until.with_timezone(&Local).date_naive()
+        };
+
+        let mut occurrences = vec![first];
+        let mut period = 0;
+        'periods: while let Some((period_start, dates)) = self.period_dates(start.date(), period) {
+            if period_start > last_date {
+                break;
+            }
+            period += 1;
+            for date in dates.into_iter().filter(|d| *d > start.date()) {
+                let Some(t) = to_utc(date) else {
+                    continue;
+                };
+                if t >= until
+                    || !within_until(t)
+                    || self.count.is_some_and(|n| occurrences.len() >= n)
+                {
+                    break 'periods;
+                }
+                occurrences.push(t);
+            }
+        }
+        occurrences.truncate(self.count.unwrap_or(usize::MAX));
+        occurrences.retain(|t| *t < until && within_until(*t));
+        occurrences
+    }
+
+    /// The first day of the `n`th period (day, week, month or year) after
+    /// `first`'s, and the dates the rule picks in it, in order. `None` past
+    /// the end of the calendar.
+    fn period_dates(&self, first: NaiveDate, n: u32) -> Option<(NaiveDate, Vec<NaiveDate>)> {
+        let step = n.checked_mul(self.interval)?;
+        let on_day = |date: &NaiveDate| {
+            self.by_day.is_empty() || self.by_day.iter().any(|(_, d)| *d == date.weekday())
+        };
+        match self.frequency {
+            Frequency::Daily => {
+                let date = first.checked_add_days(Days::new(step.into()))?;
+                Some((date, [date].into_iter().filter(on_day).collect()))
+            }
+            Frequency::Weekly => {
+                let back = first.weekday().days_since(self.week_start);
+                let week = first
+                    .checked_sub_days(Days::new(back.into()))?
+                    .checked_add_days(Days::new(u64::from(step) * 7))?;
+                let dates = (0..7)
+                    .filter_map(|d| week.checked_add_days(Days::new(d)))
+                    .filter(|d| {
+                        if self.by_day.is_empty() {
+                            d.weekday() == first.weekday()
+                        } else {
+                            on_day(d)
+                        }
+                    })
+                    .collect();
+                Some((week, dates))
+            }
+            Frequency::Monthly => {
+                let month = first.with_day(1)?.checked_add_months(Months::new(step))?;
+                let days_in_month = month.checked_add_months(Months::new(1))?.pred_opt()?.day();
+                // Day of the month, counting back from the end when negative
+                let day = |d: i32| {
+                    let d = if d < 0 {
+                        days_in_month as i32 + 1 + d
+                    } else {
+                        d
+                    };
+                    u32::try_from(d).ok().and_then(|d| month.with_day(d))
+                };
+                let mut dates: Vec<NaiveDate> = if !self.by_day.is_empty() {
+                    self.by_day
+                        .iter()
+                        .flat_map(|&(ordinal, weekday)| {
+                            let all: Vec<NaiveDate> = (1..=days_in_month)
+                                .filter_map(|d| month.with_day(d))
+                                .filter(|d| d.weekday() == weekday)
+                                .collect();
+                            match ordinal {
+                                None => all,
+                                Some(n) => {
+                                    let index = if n > 0 {
+                                        n as usize - 1
+                                    } else {
+                                        all.len().wrapping_sub(n.unsigned_abs() as usize)
+                                    };
+                                    all.get(index).copied().into_iter().collect()
+                                }
+                            }
+                        })
+                        .collect()
+                } else if !self.by_month_day.is_empty() {
+                    self.by_month_day.iter().filter_map(|&d| day(d)).collect()
+                } else {
+                    day(first.day() as i32).into_iter().collect()
+                };
+                dates.sort();
+                dates.dedup();
+                Some((month, dates))
+            }
+            Frequency::Yearly => {
+                let year = first.year().checked_add(i32::try_from(step).ok()?)?;
+                let january = NaiveDate::from_ymd_opt(year, 1, 1)?;
+                // February 29th only repeats in leap years
+                let date = NaiveDate::from_ymd_opt(year, first.month(), first.day());
+                Some((january, date.into_iter().collect()))
+            }
+        }
+    }
+}
+
+/// A two-letter iCalendar weekday such as `MO`
+fn parse_weekday(name: &str) -> Option<Weekday> {
+    Some(match name.trim().to_ascii_uppercase().as_str() {
+        "MO" => Weekday::Mon,
+        "TU" => Weekday::Tue,
+        "WE" => Weekday::Wed,
+        "TH" => Weekday::Thu,
+        "FR" => Weekday::Fri,
+        "SA" => Weekday::Sat,
+        "SU" => Weekday::Sun,
+        _ => return None,
+    })
+}
+
+/// Lines with folded continuations (those starting with a space or tab)
+/// joined back on
+fn unfold(data: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in data.lines() {
+        let line = line.trim_end_matches('\r');
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+/// A content line's upper-cased name, its parameters and its value
+type Property<'a> = (String, Vec<(String, String)>, &'a str);
+
+/// Split `NAME;PARAM=X;PARAM=Y:VALUE` into the upper-cased name, the
+/// parameters and the value
+fn split_property(line: &str) -> Option<Property<'_>> {
+    let (head, value) = line.split_once(':')?;
+    let mut parts = head.split(';');
+    let name = parts.next()?.trim().to_ascii_uppercase();
+    let params = parts
+        .filter_map(|p| p.split_once('='))
+        .map(|(k, v)| {
+            (
+                k.trim().to_ascii_uppercase(),
+                v.trim_matches('"').to_string(),
+            )
+        })
+        .collect();
+    Some((name, params, value.trim()))
+}
+
+/// A DTSTART or DTEND value, and whether it is a date rather than a time.
+/// `Z` times are UTC; others, with a `TZID` or floating, are local time.
+fn parse_time(params: &[(String, String)], value: &str) -> Option<(DateTime<Utc>, bool)> {
+    let is_date = params
+        .iter()
+        .any(|(k, v)| k == "VALUE" && v.eq_ignore_ascii_case("DATE"))
+        || value.len() == 8;
+    if is_date {
+        let date = NaiveDate::parse_from_str(value, "%Y%m%d").ok()?;
+        let local = Local
+            .from_local_datetime(&date.and_hms_opt(0, 0, 0)?)
+            .earliest()?;
+        return Some((local.with_timezone(&Utc), true));
+    }
+    if let Some(utc) = value.strip_suffix('Z') {
+        let time = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").ok()?;
+        return Some((Utc.from_utc_datetime(&time), false));
+    }
+    let time = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()?;
+    let local = Local.from_local_datetime(&time).earliest()?;
+    Some((local.with_timezone(&Utc), false))
+}
+
+/// A DURATION value such as `PT1H30M`, `P1D` or `P1W`; `None` if it is
+/// unreadable or too long to represent
+fn parse_duration(value: &str) -> Option<Duration> {
+    let value = value.strip_prefix('+').unwrap_or(value);
+    let rest = value.strip_prefix('P')?;
+    let mut total = Duration::zero();
+    let mut number = String::new();
+    for c in rest.chars() {
+        match c {
+            '0'..='9' => number.push(c),
+            'T' => {}
+            'W' | 'D' | 'H' | 'M' | 'S' => {
+                let n: i64 = number.parse().ok()?;
+                number.clear();
+                let part = match c {
+                    'W' => Duration::try_weeks(n),
+                    'D' => Duration::try_days(n),
+                    'H' => Duration::try_hours(n),
+                    'M' => Duration::try_minutes(n),
+                    _ => Duration::try_seconds(n),
+                }?;
+                total = total.checked_add(&part)?;
+            }
+            _ => return None,
+        }
+    }
+    Some(total)
+}
+
+/// Undo TEXT escaping: `\,` `\;` `\\` and `\n`
+fn unescape(value: &str) -> String {
+    let mut out = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') | Some('N') => out.push(' '),
+            Some(other) => out.push(other),
+            None => {}
+        }
+    }
+    out
+}
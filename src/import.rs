@@ -0,0 +1,419 @@
+//! Importers for other time trackers' data files. Each turns a file's contents
+//! into finished entries; skipping what is already recorded and adding them is
+//! up to the caller.
+//!
+//! Imported tags are appended to the description as `#tag`, the way tags are
+//! written in `meter log` lines.
+
+use chrono::{DateTime, Duration, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+
+use crate::export;
+use crate::models::{Entry, EntryStatus};
+
+/// Project for imported time without one: Timewarrior intervals without any
+/// tags and CSV rows with an empty project
+pub const UNTAGGED_PROJECT: &str = "untagged";
+
+/// Entry fields a CSV column can fill, for `--map FIELD=COLUMN`
+pub const CSV_FIELDS: [&str; 8] = [
+    "project",
+    "description",
+    "start_date",
+    "start_time",
+    "end_date",
+    "end_time",
+    "duration",
+    "tags",
+];
+
+/// Data file formats `meter import` reads
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportFormat {
+    /// Timewarrior's monthly `~/.timewarrior/data/YYYY-MM.data` files
+    Timewarrior,
+    /// Watson's `~/.config/watson/frames` JSON file
+    Watson,
+    /// A CSV export with a header row, such as Toggl's or Clockify's detailed report
+    Csv,
+    /// A `meter export json` file, restored whole into an empty database
+    Json,
+}
+
+impl ImportFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ImportFormat::Timewarrior => "timewarrior",
+            ImportFormat::Watson => "watson",
+            ImportFormat::Csv => "csv",
+            ImportFormat::Json => "json",
+        }
+    }
+}
+
+impl std::fmt::Display for ImportFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for ImportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "timewarrior" | "timew" => Ok(ImportFormat::Timewarrior),
+            "watson" => Ok(ImportFormat::Watson),
+            "csv" | "toggl" | "clockify" => Ok(ImportFormat::Csv),
+            "json" => Ok(ImportFormat::Json),
+            _ => Err(format!(
+                "Unknown import format '{}' (expected timewarrior, watson, csv or json)",
+                s
+            )),
+        }
+    }
+}
+
+/// Entries in `data`, a file in `format`. `columns` maps CSV fields to the
+/// columns holding them (see `parse_column_map`).
+pub fn parse(
+    format: ImportFormat,
+    data: &str,
+    columns: &[(String, String)],
+) -> Result<Vec<Entry>, String> {
+    match format {
+        ImportFormat::Timewarrior => parse_timewarrior(data),
+        ImportFormat::Watson => parse_watson(data),
+        ImportFormat::Csv => parse_csv(data, columns),
+        ImportFormat::Json => export::parse_json(data).map(|export| export.entries),
+    }
+}
+
+/// Timewarrior intervals, one per line:
+/// `inc 20240603T090000Z - 20240603T103000Z # acme dev "code review" # "fix login bug"`.
+/// The first tag is the project, the rest become `#tags`, and the annotation is
+/// the description. The open interval of a running timer is left out.
+pub fn parse_timewarrior(data: &str) -> Result<Vec<Entry>, String> {
+    let mut entries = Vec::new();
+    for (i, line) in data.lines().enumerate() {
+        let words = split_quoted(line);
+        let mut words = words.iter().map(String::as_str);
+        match words.next() {
+            Some("inc") => {}
+            None => continue,
+            Some(_) => return Err(format!("line {}: expected 'inc'", i + 1)),
+        }
+        let timestamp = |s: Option<&str>| {
+            s.and_then(|s| NaiveDateTime::parse_from_str(s, "%Y%m%dT%H%M%SZ").ok())
+                .map(|dt| dt.and_utc())
+                .ok_or_else(|| format!("line {}: invalid timestamp", i + 1))
+        };
+        let start = timestamp(words.next())?;
+        let mut rest: Vec<&str> = words.collect();
+        let end = match rest.first() {
+            Some(&"-") => {
+                let end = timestamp(rest.get(1).copied())?;
+                rest.drain(..2);
+                end
+            }
+            _ => continue,
+        };
+
+        // Tags follow the first '#', the annotation the second
+        let mut sections = rest.split(|w| *w == "#").skip(1);
+        let tags = sections.next().unwrap_or_default();
+        let annotation = sections.next().unwrap_or_default().join(" ");
+        let (project, tags) = match tags.split_first() {
+            Some((project, tags)) => (project.to_string(), tags),
+            None => (UNTAGGED_PROJECT.to_string(), tags),
+        };
+        entries.push(entry(project, &annotation, tags, start, end));
+    }
+    Ok(entries)
+}
+
+/// Watson frames: a JSON array of `[start, stop, project, id, tags, updated]`,
+/// with times as Unix timestamps
+pub fn parse_watson(data: &str) -> Result<Vec<Entry>, String> {
+    let frames: Vec<serde_json::Value> =
+        serde_json::from_str(data).map_err(|e| format!("invalid frames file: {}", e))?;
+    frames
+        .iter()
+        .enumerate()
+        .map(|(i, frame)| {
+            let invalid = || format!("frame {}: expected [start, stop, project, id, tags]", i + 1);
+            let time = |v: &serde_json::Value| {
+                v.as_i64()
+                    .and_then(|secs| DateTime::from_timestamp(secs, 0))
+                    .ok_or_else(invalid)
+            };
+            let start = time(&frame[0])?;
+            let end = time(&frame[1])?;
+            let project = frame[2].as_str().ok_or_else(invalid)?;
+            let tags: Vec<&str> = frame[4]
+                .as_array()
+                .map(|tags| tags.iter().filter_map(|t| t.as_str()).collect())
+                .unwrap_or_default();
+            Ok(entry(project.to_string(), "", &tags, start, end))
+        })
+        .collect()
+}
+
+/// Parse `--map FIELD=COLUMN` arguments, e.g. `project=Client`
+pub fn parse_column_map(args: &[String]) -> Result<Vec<(String, String)>, String> {
+    args.iter()
+        .map(|arg| {
+            let (field, column) = arg
+                .split_once('=')
+                .ok_or_else(|| format!("invalid mapping '{}': expected FIELD=COLUMN", arg))?;
+            let field = field.trim().to_ascii_lowercase();
+            if !CSV_FIELDS.contains(&field.as_str()) {
+                return Err(format!(
+                    "unknown field '{}' (expected one of {})",
+                    field,
+                    CSV_FIELDS.join(", ")
+                ));
+            }
+            Ok((field, column.trim().to_string()))
+        })
+        .collect()
+}
+
+/// Header names tried for a field that isn't mapped, covering Toggl's and
+/// Clockify's detailed exports
+fn default_columns(field: &str) -> &'static [&'static str] {
+    match field {
+        "project" => &["project"],
+        "description" => &["description"],
+        "start_date" => &["start date"],
+        "start_time" => &["start time"],
+        "end_date" => &["end date"],
+        "end_time" => &["end time"],
+        "duration" => &["duration", "duration (h)"],
+        "tags" => &["tags"],
+        _ => &[],
+    }
+}
+
+/// CSV rows with a header row. Start and end times are local; a row needs a
+/// project, start date and start time, and an end time or a duration. An end
+/// time before the start is on the next day. Tags are comma separated.
+pub fn parse_csv(data: &str, columns: &[(String, String)]) -> Result<Vec<Entry>, String> {
+    let mut rows = csv_records(data).into_iter();
+    let header: Vec<String> = rows
+        .next()
+        .ok_or("the file is empty")?
+        .iter()
+        .map(|h| h.trim().trim_start_matches('\u{feff}').to_lowercase())
+        .collect();
+    let column = |field: &str| -> Result<Option<usize>, String> {
+        match columns.iter().find(|(f, _)| f == field) {
+            Some((_, name)) => header
+                .iter()
+                .position(|h| *h == name.to_lowercase())
+                .map(Some)
+                .ok_or_else(|| format!("no '{}' column for {}", name, field)),
+            None => Ok(default_columns(field)
+                .iter()
+                .find_map(|name| header.iter().position(|h| h == name))),
+        }
+    };
+    let required = |field: &str| {
+        column(field)?.ok_or_else(|| {
+            format!(
+                "no {} column; name it with --map {}=COLUMN",
+                field.replace('_', " "),
+                field
+            )
+        })
+    };
+    let project_col = required("project")?;
+    let start_date_col = required("start_date")?;
+    let start_time_col = required("start_time")?;
+    let description_col = column("description")?;
+    let end_date_col = column("end_date")?;
+    let end_time_col = column("end_time")?;
+    let duration_col = column("duration")?;
+    let tags_col = column("tags")?;
+    if end_time_col.is_none() && duration_col.is_none() {
+        return Err("no end time or duration column; name one with --map".to_string());
+    }
+
+    let mut entries = Vec::new();
+    for (i, row) in rows.enumerate() {
+        if row.iter().all(|field| field.trim().is_empty()) {
+            continue;
+        }
+        let line = i + 2;
+        let field = |col: Option<usize>| {
+            col.and_then(|c| row.get(c))
+                .map(|s| s.trim())
+                .unwrap_or_default()
+        };
+        let date = |s: &str| {
+            parse_csv_date(s).ok_or_else(|| format!("row {}: invalid date '{}'", line, s))
+        };
+        let time = |s: &str| {
+            parse_csv_time(s).ok_or_else(|| format!("row {}: invalid time '{}'", line, s))
+        };
+
+        let start_date = date(field(Some(start_date_col)))?;
+        let start = local(start_date.and_time(time(field(Some(start_time_col)))?))
+            .ok_or_else(|| format!("row {}: start time doesn't exist locally", line))?;
+        let end =
+            match (field(end_time_col), field(duration_col)) {
+                (end_time, _) if !end_time.is_empty() => {
+                    let end_date = match field(end_date_col) {
+                        "" => start_date,
+                        s => date(s)?,
+                    };
+                    let mut end = local(end_date.and_time(time(end_time)?))
+                        .ok_or_else(|| format!("row {}: end time doesn't exist locally", line))?;
+                    if end < start {
+                        end += Duration::days(1);
+                    }
+                    end
+                }
+                (_, duration) if !duration.is_empty() => {
+                    start
+                        + Duration::seconds(parse_csv_duration(duration).ok_or_else(|| {
+                            format!("row {}: invalid duration '{}'", line, duration)
+                        })?)
+                }
+                _ => return Err(format!("row {}: no end time or duration", line)),
+            };
+
+        let project = match field(Some(project_col)) {
+            "" => UNTAGGED_PROJECT.to_string(),
+            project => project.to_string(),
+        };
+        let tags: Vec<&str> = field(tags_col)
+            .split(',')
+            .map(str::trim)
+            .filter(|t| !t.is_empty())
+            .collect();
+        entries.push(entry(project, field(description_col), &tags, start, end));
+    }
+    Ok(entries)
+}
+
+fn local(naive: NaiveDateTime) -> Option<DateTime<Utc>> {
+    Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .map(|dt| dt.with_timezone(&Utc))
+}
+
+/// Dates as Toggl (`2024-06-03`) and Clockify (`06/03/2024` or `03.06.2024`) write them
+fn parse_csv_date(s: &str) -> Option<NaiveDate> {
+    ["%Y-%m-%d", "%m/%d/%Y", "%d.%m.%Y"]
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(s, format).ok())
+}
+
+/// Times in 24-hour or 12-hour form, with or without seconds
+fn parse_csv_time(s: &str) -> Option<NaiveTime> {
+    ["%H:%M:%S", "%H:%M", "%I:%M:%S %p", "%I:%M %p"]
+        .iter()
+        .find_map(|format| NaiveTime::parse_from_str(s, format).ok())
+}
+
+/// Seconds in a duration written `H:MM:SS`, `H:MM` or as decimal hours (`1.5`)
+fn parse_csv_duration(s: &str) -> Option<i64> {
+    if !s.contains(':') {
+        let hours: f64 = s.replace(',', ".").parse().ok()?;
+        return (hours >= 0.0).then(|| (hours * 3600.0).round() as i64);
+    }
+    let parts: Vec<i64> = s
+        .split(':')
+        .map(|p| p.parse().ok())
+        .collect::<Option<_>>()?;
+    match parts[..] {
+        [h, m, sec] => Some(h * 3600 + m * 60 + sec),
+        [h, m] => Some(h * 3600 + m * 60),
+        _ => None,
+    }
+}
+
+/// Split CSV text into records of fields. Quoted fields may hold commas,
+/// newlines and `""` for a quote.
+fn csv_records(data: &str) -> Vec<Vec<String>> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = data.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => record.push(std::mem::take(&mut field)),
+            '\r' if !in_quotes => {}
+            '\n' if !in_quotes => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            c => field.push(c),
+        }
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    records
+}
+
+fn entry(
+    project: String,
+    description: &str,
+    tags: &[&str],
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Entry {
+    let mut description = description.trim().to_string();
+    for tag in tags {
+        if !description.is_empty() {
+            description.push(' ');
+        }
+        description.push('#');
+        description.push_str(&tag.split_whitespace().collect::<Vec<_>>().join("-"));
+    }
+    Entry {
+        id: 0,
+        project,
+        description,
+        start,
+        end: Some(end.max(start)),
+        status: EntryStatus::Open,
+        days: None,
+        issue: None,
+        location: None,
+    }
+}
+
+/// Split a line on whitespace, keeping double-quoted words (with `\"` escapes)
+/// together and unquoted
+fn split_quoted(line: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_quotes = false;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if in_quotes => word.extend(chars.next()),
+            '"' => in_quotes = !in_quotes,
+            c if c.is_whitespace() && !in_quotes => {
+                if !word.is_empty() {
+                    words.push(std::mem::take(&mut word));
+                }
+            }
+            c => word.push(c),
+        }
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+}
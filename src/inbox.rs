@@ -0,0 +1,157 @@
+//! Incoming email over IMAP, read so replies about invoices can update their
+//! status without bookkeeping by hand. The mailbox is opened read-only and only
+//! headers are fetched, so nothing is marked read or moved. The IMAP password is
+//! kept with the other secrets (see `secrets`), keyed by the account it belongs
+//! to.
+
+use std::net::TcpStream;
+
+use rustls_connector::RustlsConnector;
+
+use crate::models::InboxSettings;
+use crate::secrets;
+
+/// The headers of one email
+#[derive(Debug, Clone, PartialEq)]
+pub struct InboxMessage {
+    pub uid: u32,
+    pub subject: String,
+    pub from: String,
+}
+
+fn password_secret(settings: &InboxSettings) -> String {
+    format!("imap:{}@{}", settings.username, settings.imap_host)
+}
+
+/// The stored IMAP password, if there is one
+pub fn imap_password(settings: &InboxSettings) -> Result<Option<String>, String> {
+    secrets::get(&password_secret(settings))
+}
+
+/// Store the IMAP password, or remove it when `password` is empty
+pub fn set_imap_password(settings: &InboxSettings, password: &str) -> Result<(), String> {
+    if password.is_empty() {
+        secrets::delete(&password_secret(settings))
+    } else {
+        secrets::set(&password_secret(settings), password)
+    }
+}
+
+/// Headers of the emails in the configured mailbox with a UID above
+/// `settings.last_uid`, oldest first. Connects over TLS.
+pub fn fetch_new(settings: &InboxSettings) -> Result<Vec<InboxMessage>, String> {
+    if !settings.is_configured() {
+        return Err("The inbox is not set up: set an IMAP host and username".to_string());
+    }
+    let host = settings.imap_host.as_str();
+    let port = u16::try_from(settings.imap_port)
+        .map_err(|_| format!("Invalid IMAP port {}", settings.imap_port))?;
+    let password = imap_password(settings)?
+        .ok_or_else(|| "No IMAP password stored: meter mail settings --password -".to_string())?;
+
+    let stream = TcpStream::connect((host, port))
+        .map_err(|e| format!("Failed to connect to {}: {}", host, e))?;
+    let connector = RustlsConnector::new_with_native_certs()
+        .map_err(|e| format!("Failed to load root certificates: {}", e))?;
+    let tls = connector
+        .connect(host, stream)
+        .map_err(|e| format!("TLS handshake with {} failed: {}", host, e))?;
+    let mut client = imap::Client::new(tls);
+    client
+        .read_greeting()
+        .map_err(|e| format!("IMAP server did not greet: {}", e))?;
+    let mut session = client
+        .login(&settings.username, &password)
+        .map_err(|(e, _)| format!("IMAP login failed: {}", e))?;
+    session
+        .examine(&settings.mailbox)
+        .map_err(|e| format!("Failed to open mailbox '{}': {}", settings.mailbox, e))?;
+
+    // `n:*` always includes the newest message, even when its UID is below n
+    let mut uids: Vec<u32> = session
+        .uid_search(format!("UID {}:*", settings.last_uid + 1))
+        .map_err(|e| format!("IMAP search failed: {}", e))?
+        .into_iter()
+        .filter(|uid| i64::from(*uid) > settings.last_uid)
+        .collect();
+    uids.sort_unstable();
+
+    let mut messages = Vec::new();
+    if !uids.is_empty() {
+        let set: Vec<String> = uids.iter().map(|uid| uid.to_string()).collect();
+        let fetches = session
+            .uid_fetch(set.join(","), "(UID RFC822.HEADER)")
+            .map_err(|e| format!("IMAP fetch failed: {}", e))?;
+        for fetch in fetches.iter() {
+            let (Some(uid), Some(header)) = (fetch.uid, fetch.header()) else {
+                continue;
+            };
+            let (subject, from) = parse_headers(&String::from_utf8_lossy(header));
+            messages.push(InboxMessage { uid, subject, from });
+        }
+    }
+    let _ = session.logout();
+    messages.sort_by_key(|m| m.uid);
+    Ok(messages)
+}
+
+/// The Subject and From of a raw header block, with folded lines joined.
+/// Encoded words (`=?UTF-8?...?=`) are left as they are.
+pub fn parse_headers(header: &str) -> (String, String) {
+    let mut subject = String::new();
+    let mut from = String::new();
+    let mut current: Option<&mut String> = None;
+    for line in header.lines() {
+        if line.starts_with([' ', '\t']) {
+            if let Some(value) = current.as_mut() {
+                value.push(' ');
+                value.push_str(line.trim());
+            }
+            continue;
+        }
+        current = None;
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let target = if name.eq_ignore_ascii_case("subject") {
+            &mut subject
+        } else if name.eq_ignore_ascii_case("from") {
+            &mut from
+        } else {
+            continue;
+        };
+        target.clear();
+        target.push_str(value.trim());
+        current = Some(target);
+    }
+    (subject, from)
+}
+
+/// The invoice number an email subject refers to: the digits after `#`, or
+/// after the word "invoice" (optionally followed by "no." or "number"), so
+/// "Re: Invoice #0012" and "Payment for invoice 12" both give 12
+pub fn invoice_number_in_subject(subject: &str) -> Option<i64> {
+    let lower = subject.to_lowercase();
+    let digits_at = |rest: &str| -> Option<i64> {
+        let digits: String = rest.chars().take_while(|c| c.is_ascii_digit()).collect();
+        digits.parse().ok()
+    };
+    for (i, _) in lower.match_indices('#') {
+        if let Some(number) = digits_at(&lower[i + 1..]) {
+            return Some(number);
+        }
+    }
+    for (i, word) in lower.match_indices("invoice") {
+        let mut rest = lower[i + word.len()..].trim_start();
+        while let Some(stripped) = ["number", "no.", "no", "nr.", "nr", ":"]
+            .iter()
+            .find_map(|filler| rest.strip_prefix(filler))
+        {
+            rest = stripped.trim_start();
+        }
+        if let Some(number) = digits_at(rest) {
+            return Some(number);
+        }
+    }
+    None
+}
@@ -1,54 +1,574 @@
-use chrono::{Datelike, Days, Local, TimeZone, Utc};
-use genpdf::elements::{Break, Paragraph, TableLayout};
+use chrono::{DateTime, Datelike, Days, Local, NaiveDate, TimeZone, Utc, Weekday};
+use genpdf::elements::{Break, Image, Paragraph, TableLayout};
 use genpdf::fonts::{FontData, FontFamily};
 use genpdf::style::Style;
-use genpdf::{Document, Element, SimplePageDecorator};
-use std::collections::HashMap;
+use genpdf::{
+    Alignment, Context, Document, Element, Margins, Mm, PageDecorator, Position, RenderResult,
+    render,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::cell::Cell;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
 use std::io;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
 
-use crate::models::{Client, Entry, InvoiceSettings};
+use crate::billing::{
+    self, Bill, ProjectLine, ProjectRate, Totals, invoice_currency, project_sections, worked_days,
+};
+use crate::models::{
+    BillingMode, Client, DEFAULT_FONT_SIZE, Entry, EntryStatus, Expense, Invoice, InvoiceItem,
+    InvoiceSettings, Money, OvertimeRates, PaperSize, Project, TimeOff, currency_decimals,
+    format_days, format_money_in,
+};
+use crate::paths;
 
-/// Project rate information for invoice calculations
-#[derive(Debug, Clone)]
-pub struct ProjectRate {
-    pub rate: f64,
-    pub currency: String,
+/// Heading of the entry table's quantity column: "Hours", "Days" when every
+/// entry was logged by the day, or "Qty" for a mix
+fn quantity_heading(entries: &[&Entry]) -> &'static str {
+    match entries.iter().filter(|e| e.days.is_some()).count() {
+        0 => "Hours",
+        n if n == entries.len() => "Days",
+        _ => "Qty",
+    }
+}
+
+/// Which client an invoice's entries belong to, based on project links
+#[derive(Debug, Clone, PartialEq)]
+pub enum ClientMatch {
+    /// At least one entry's project isn't linked to a client
+    Unlinked,
+    /// Every entry's project is linked to the same client
+    Single(i64),
+    /// Linked projects point at more than one client
+    Mixed(Vec<i64>),
+}
+
+/// Work out the client for a set of entries from their projects' client links
+pub fn detect_client(entries: &[Entry], projects: &[Project]) -> ClientMatch {
+    let mut client_ids: Vec<i64> = Vec::new();
+    let mut has_unlinked = false;
+
+    for entry in entries {
+        match projects
+            .iter()
+            .find(|p| p.name == entry.project)
+            .and_then(|p| p.client_id)
+        {
+            Some(id) => {
+                if !client_ids.contains(&id) {
+                    client_ids.push(id);
+                }
+            }
+            None => has_unlinked = true,
+        }
+    }
+
+    match client_ids.len() {
+        0 => ClientMatch::Unlinked,
+        1 if !has_unlinked => ClientMatch::Single(client_ids[0]),
+        1 => ClientMatch::Unlinked,
+        _ => ClientMatch::Mixed(client_ids),
+    }
+}
+
+/// Expenses to bill with `entries` on an invoice for `client_id` and `year`-`month`:
+/// those tied to one of the entries, and the client's other expenses from that
+/// month. Expenses already invoiced are left out.
+pub fn expenses_for_invoice(
+    expenses: &[Expense],
+    entries: &[Entry],
+    client_id: Option<i64>,
+    year: i32,
+    month: u32,
+) -> Vec<Expense> {
+    expenses
+        .iter()
+        .filter(|x| x.invoice_number.is_none())
+        .filter(|x| match x.entry_id {
+            Some(id) => entries.iter().any(|e| e.id == id),
+            None => {
+                client_id.is_some()
+                    && x.client_id == client_id
+                    && x.date.year() == year
+                    && x.date.month() == month
+            }
+        })
+        .cloned()
+        .collect()
 }
 
 /// Parameters for invoice generation
 pub struct InvoiceParams<'a> {
     pub entries: &'a [Entry],
+    /// Minutes each finished timed entry is rounded up to before it is billed,
+    /// or 0 to bill entries as tracked
+    pub rounding_minutes: i64,
+    /// Expenses and mileage billed after the services
+    pub expenses: &'a [Expense],
+    /// Other charges and discounts, billed last
+    pub items: &'a [InvoiceItem],
     pub project_rates: &'a HashMap<String, ProjectRate>,
     pub year: i32,
     pub month: u32,
     pub invoice_number: i64,
+    /// A preview of a draft: `invoice_number` is the draft's ID, and the PDF is
+    /// marked as a draft and written as `draft_NNNN.pdf`
+    pub draft: bool,
+    /// Number of the voided invoice this one replaces, noted on the PDF
+    pub supersedes: Option<i64>,
     pub settings: &'a InvoiceSettings,
     pub client: Option<&'a Client>,
+    /// The client's multipliers for evening, weekend and holiday hours
+    pub overtime: Option<&'a OvertimeRates>,
     pub tax_rate: f64,
+    /// Public holidays, which the due date is moved off when business-day due
+    /// dates are on, and which holiday overtime applies to
+    pub holidays: &'a [TimeOff],
+    /// Date issued, due date and due date note to print instead of today's, when
+    /// regenerating an existing invoice
+    pub reissue_dates: Option<(String, String, Option<String>)>,
+}
+
+/// What the invoice `params` describes bills, as its PDF prints and records
+/// it: the entries rounded up to `rounding_minutes`, a line per project, the
+/// expenses and the other items, each rounded to the currency's decimals, and
+/// the tax added to their sum or, with tax-inclusive prices, taken out of it
+pub fn bill(params: &InvoiceParams) -> Bill {
+    let entries = billing::rounded_entries(params.entries, params.rounding_minutes);
+    let sections = project_sections(&entries, params.settings.section_order);
+    let currency = invoice_currency(
+        sections
+            .iter()
+            .filter_map(|(p, _)| params.project_rates.get(p)),
+    );
+    let decimals = currency_decimals(&currency);
+
+    let lines: Vec<(String, ProjectLine)> = sections
+        .iter()
+        .filter_map(|(project, entries)| {
+            let rate = params.project_rates.get(project)?;
+            let line =
+                billing::project_line(rate, entries, params.overtime, params.holidays, decimals);
+            Some((project.clone(), line))
+        })
+        .collect();
+    let expenses = params
+        .expenses
+        .iter()
+        .map(|x| x.amount)
+        .sum::<Money>()
+        .round_to(decimals);
+    let amounts = lines
+        .iter()
+        .map(|(_, line)| line.amount)
+        .chain([expenses])
+        .chain(
+            params
+                .items
+                .iter()
+                .map(|item| item.amount.round_to(decimals)),
+        );
+    let totals = if params.settings.tax_inclusive && params.tax_rate > 0.0 {
+        Totals::from_inclusive_amounts(amounts, params.tax_rate, decimals)
+    } else {
+        Totals::from_amounts(amounts, params.tax_rate, decimals)
+    };
+    Bill {
+        currency,
+        lines,
+        expenses,
+        totals,
+    }
 }
 
 /// Result of invoice generation
 #[derive(Debug)]
 pub struct InvoiceResult {
     pub file_path: String,
+    /// Where the JSON copy was written, when `json_snapshot` is on
+    pub json_path: Option<String>,
     pub date_issued: String,
     pub due_date: String,
-    pub subtotal: f64,
-    pub tax_amount: f64,
-    pub total: f64,
+    pub subtotal: Money,
+    pub tax_amount: Money,
+    pub total: Money,
+    pub snapshot: InvoiceSnapshot,
+}
+
+/// Everything an invoice said when it was issued. Stored as JSON on the invoice
+/// record so later edits to clients, settings or rates don't change history.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct InvoiceSnapshot {
+    pub invoice_number: i64,
+    pub year: i32,
+    pub month: u32,
+    pub date_issued: String,
+    pub due_date: String,
+    /// Why the due date was moved off a weekend or holiday, if it was
+    #[serde(default)]
+    pub due_date_note: Option<String>,
+    pub settings: InvoiceSettings,
+    pub client: Option<Client>,
+    /// Rates for the projects on this invoice
+    pub rates: BTreeMap<String, ProjectRate>,
+    pub line_items: Vec<SnapshotLineItem>,
+    #[serde(default)]
+    pub expenses: Vec<Expense>,
+    #[serde(default)]
+    pub items: Vec<InvoiceItem>,
+    /// Number of the voided invoice this one replaces
+    #[serde(default)]
+    pub supersedes: Option<i64>,
+    /// Overtime multipliers applied to hourly work
+    #[serde(default)]
+    pub overtime: Option<OvertimeRates>,
+    /// Holidays the entries fall on, for holiday overtime
+    #[serde(default)]
+    pub holidays: Vec<NaiveDate>,
+    pub tax_rate: f64,
+    pub subtotal: Money,
+    pub tax_amount: Money,
+    pub total: Money,
+}
+
+/// One billed entry as it appeared on the invoice
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SnapshotLineItem {
+    pub entry_id: i64,
+    pub project: String,
+    pub description: String,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub hours: f64,
+    #[serde(default)]
+    pub days: Option<f64>,
+    #[serde(default)]
+    pub issue: Option<String>,
+}
+
+impl InvoiceSnapshot {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(json)
+    }
+
+    /// Rows of the table of this invoice put in an email body: each entry with
+    /// its hours, each expense and other item with its amount, then the totals.
+    /// Columns are date, project, description, hours and amount.
+    fn email_rows(&self) -> Vec<[String; 5]> {
+        let currency = invoice_currency(self.rates.values());
+        let money = |amount: Money| format_money_in(amount, &currency, self.settings.number_locale);
+        let mut rows = Vec::new();
+        for item in &self.line_items {
+            let (description, _) = split_description(&item.description);
+            let quantity = match item.days {
+                Some(days) => format!("{} d", format_days(days)),
+                None => format!("{:.2}", item.hours),
+            };
+            rows.push([
+                item.start
+                    .with_timezone(&Local)
+                    .format("%Y-%m-%d")
+                    .to_string(),
+                item.project.clone(),
+                description.to_string(),
+                quantity,
+                String::new(),
+            ]);
+        }
+        for expense in &self.expenses {
+            rows.push([
+                expense.date.to_string(),
+                "Expense".to_string(),
+                expense.label(),
+                String::new(),
+                money(expense.amount),
+            ]);
+        }
+        for item in &self.items {
+            rows.push([
+                String::new(),
+                String::new(),
+                item.description.clone(),
+                String::new(),
+                money(item.amount),
+            ]);
+        }
+        let inclusive = self.settings.tax_inclusive && self.tax_rate > 0.0;
+        let mut totals = vec![(
+            if inclusive {
+                "Subtotal (excl. tax)".to_string()
+            } else {
+                "Subtotal".to_string()
+            },
+            self.subtotal,
+        )];
+        if self.tax_rate > 0.0 {
+            let label = if inclusive { "Includes tax" } else { "Tax" };
+            totals.push((
+                format!("{} ({:.1}%)", label, self.tax_rate),
+                self.tax_amount,
+            ));
+        }
+        totals.push(("Total due".to_string(), self.total));
+        for (label, amount) in totals {
+            rows.push([
+                String::new(),
+                String::new(),
+                label,
+                String::new(),
+                money(amount),
+            ]);
+        }
+        rows
+    }
+
+    /// The invoice as a Markdown table, for a plain-text email body
+    pub fn markdown_table(&self) -> String {
+        let cell = |text: &str| text.replace('|', "\\|");
+        let mut out = String::from(
+            "| Date | Project | Description | Hours | Amount |\n|---|---|---|--:|--:|\n",
+        );
+        for row in self.email_rows() {
+            let cells: Vec<String> = row.iter().map(|c| cell(c)).collect();
+            out.push_str(&format!("| {} |\n", cells.join(" | ")));
+        }
+        out
+    }
+
+    /// The invoice as an HTML table, for an HTML email body
+    pub fn html_table(&self) -> String {
+        let mut out = String::from(
+            "<table cellpadding=\"4\" style=\"border-collapse: collapse\">\n<tr><th align=\"left\">Date</th><th align=\"left\">Project</th><th align=\"left\">Description</th><th align=\"right\">Hours</th><th align=\"right\">Amount</th></tr>\n",
+        );
+        for row in self.email_rows() {
+            out.push_str(&format!(
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td align=\"right\">{}</td><td align=\"right\">{}</td></tr>\n",
+                html_text(&row[0]),
+                html_text(&row[1]),
+                html_text(&row[2]),
+                html_text(&row[3]),
+                html_text(&row[4])
+            ));
+        }
+        out.push_str("</table>\n");
+        out
+    }
+}
+
+/// How an invoice email shows the invoice besides the attached PDF
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmailTable {
+    /// A Markdown table in the plain-text body
+    Markdown,
+    /// An HTML table, with the Markdown one in the plain-text alternative
+    Html,
+}
+
+impl EmailTable {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EmailTable::Markdown => "markdown",
+            EmailTable::Html => "html",
+        }
+    }
+}
+
+impl std::str::FromStr for EmailTable {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "markdown" | "md" => Ok(EmailTable::Markdown),
+            "html" => Ok(EmailTable::Html),
+            _ => Err(format!(
+                "Unknown table format '{}' (expected markdown or html)",
+                s
+            )),
+        }
+    }
+}
+
+/// Escape text for an HTML email body
+pub fn html_text(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Write an invoice's PDF again from the snapshot stored when it was issued,
+/// with the same number, dates, client, settings, rates and line items.
+pub fn regenerate_invoice(snapshot: &InvoiceSnapshot) -> io::Result<InvoiceResult> {
+    let entries: Vec<Entry> = snapshot
+        .line_items
+        .iter()
+        .map(|item| Entry {
+            id: item.entry_id,
+            project: item.project.clone(),
+            description: item.description.clone(),
+            start: item.start,
+            end: Some(item.end),
+            status: EntryStatus::Invoiced,
+            days: item.days,
+            issue: item.issue.clone(),
+            location: None,
+        })
+        .collect();
+    let project_rates: HashMap<String, ProjectRate> = snapshot
+        .rates
+        .iter()
+        .map(|(project, rate)| (project.clone(), rate.clone()))
+        .collect();
+    let holidays: Vec<TimeOff> = snapshot
+        .holidays
+        .iter()
+        .map(|day| TimeOff {
+            id: 0,
+            start: *day,
+            end: *day,
+            reason: String::new(),
+            holiday: true,
+        })
+        .collect();
+
+    write_invoice(&InvoiceParams {
+        entries: &entries,
+        // The snapshot has the entries as they were billed
+        rounding_minutes: 0,
+        expenses: &snapshot.expenses,
+        items: &snapshot.items,
+        project_rates: &project_rates,
+        year: snapshot.year,
+        month: snapshot.month,
+        invoice_number: snapshot.invoice_number,
+        draft: false,
+        supersedes: snapshot.supersedes,
+        settings: &snapshot.settings,
+        client: snapshot.client.as_ref(),
+        overtime: snapshot.overtime.as_ref(),
+        tax_rate: snapshot.tax_rate,
+        holidays: &holidays,
+        reissue_dates: Some((
+            snapshot.date_issued.clone(),
+            snapshot.due_date.clone(),
+            snapshot.due_date_note.clone(),
+        )),
+    })
+}
+
+/// Where the preview PDF of draft `id` is written in `invoice_dir`
+pub fn draft_file(invoice_dir: &str, id: i64) -> String {
+    dir_file(invoice_dir, &format!("draft_{:04}.pdf", id))
+}
+
+/// `name` in `dir`, joined with the platform's path separator
+pub fn dir_file(dir: &str, name: &str) -> String {
+    Path::new(dir).join(name).to_string_lossy().into_owned()
+}
+
+/// PDFs in the invoice directory that no invoice record points at. A PDF is
+/// kept when its path is the same file as a recorded one, however it is
+/// spelled, or when it is named `invoice_NNNN.pdf` after a recorded invoice
+/// number. Draft previews and approval sheets are left out.
+pub fn orphan_invoice_files(invoices: &[Invoice]) -> io::Result<Vec<PathBuf>> {
+    orphan_invoice_files_in(invoices, &get_invoice_dir()?)
+}
+
+/// Orphaned PDFs in `invoice_dir` instead of the invoices folder
+pub fn orphan_invoice_files_in(
+    invoices: &[Invoice],
+    invoice_dir: &str,
+) -> io::Result<Vec<PathBuf>> {
+    let referenced: HashSet<PathBuf> = invoices
+        .iter()
+        .filter_map(|i| fs::canonicalize(&i.file_path).ok())
+        .collect();
+    let numbered: HashSet<String> = invoices
+        .iter()
+        .map(|i| format!("invoice_{:04}.pdf", i.invoice_number))
+        .collect();
+    let mut orphans = Vec::new();
+    for dir_entry in fs::read_dir(invoice_dir)? {
+        let path = dir_entry?.path();
+        let is_pdf = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("pdf"));
+        let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+        let is_draft = name.starts_with("draft_") || name.starts_with("approval_");
+        let is_referenced = numbered.contains(name)
+            || fs::canonicalize(&path).is_ok_and(|path| referenced.contains(&path));
+        if is_pdf && !is_draft && !is_referenced {
+            orphans.push(path);
+        }
+    }
+    orphans.sort();
+    Ok(orphans)
+}
+
+/// Longest run of characters without a space; longer words (URLs, ticket IDs) are
+/// broken up so they can't overflow their table cell
+const MAX_WORD_CHARS: usize = 40;
+
+/// Entry descriptions and notes longer than this are cut short on the invoice
+const MAX_DESCRIPTION_CHARS: usize = 300;
+
+/// Make user-entered text safe to render: line breaks, tabs and other control
+/// characters become spaces, runs of whitespace collapse to one, and overlong
+/// words are broken up.
+pub fn pdf_text(text: &str) -> String {
+    let mut out = String::new();
+    for word in text
+        .split(|c: char| c.is_whitespace() || c.is_control())
+        .filter(|w| !w.is_empty())
+    {
+        let chars: Vec<char> = word.chars().collect();
+        for chunk in chars.chunks(MAX_WORD_CHARS) {
+            if !out.is_empty() {
+                out.push(' ');
+            }
+            out.extend(chunk);
+        }
+    }
+    out
+}
+
+/// `pdf_text`, cut to `MAX_DESCRIPTION_CHARS` characters
+fn pdf_description(text: &str) -> String {
+    let text = pdf_text(text);
+    if text.chars().count() <= MAX_DESCRIPTION_CHARS {
+        return text;
+    }
+    let cut: String = text.chars().take(MAX_DESCRIPTION_CHARS - 3).collect();
+    format!("{}...", cut.trim_end())
+}
+
+/// Split an entry description into the line shown in the table and the lines
+/// after it, which are notes
+fn split_description(description: &str) -> (&str, Option<&str>) {
+    let description = description.trim();
+    match description.split_once('\n') {
+        Some((first, rest)) if !rest.trim().is_empty() => (first, Some(rest.trim())),
+        Some((first, _)) => (first, None),
+        None => (description, None),
+    }
 }
 
 /// Get the invoice directory path (creates if needed)
 pub fn get_invoice_dir() -> io::Result<String> {
-    let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
-    let invoice_dir = format!("{}/meter/invoices", home);
+    let invoice_dir = paths::invoices_dir();
     fs::create_dir_all(&invoice_dir)?;
-    Ok(invoice_dir)
+    Ok(invoice_dir.to_string_lossy().into_owned())
 }
 
 /// Load font from system paths
-fn load_font_family() -> io::Result<FontFamily<FontData>> {
+pub fn load_font_family() -> io::Result<FontFamily<FontData>> {
     let font_configs = [
         (
             "/System/Library/Fonts/Supplemental/Arial.ttf",
@@ -68,6 +588,12 @@ fn load_font_family() -> io::Result<FontFamily<FontData>> {
             "/usr/share/fonts/truetype/liberation/LiberationSans-Italic.ttf",
             "/usr/share/fonts/truetype/liberation/LiberationSans-BoldItalic.ttf",
         ),
+        (
+            r"C:\Windows\Fonts\arial.ttf",
+            r"C:\Windows\Fonts\arialbd.ttf",
+            r"C:\Windows\Fonts\ariali.ttf",
+            r"C:\Windows\Fonts\arialbi.ttf",
+        ),
     ];
 
     for (regular, bold, italic, bold_italic) in &font_configs {
@@ -105,8 +631,14 @@ fn load_font_family() -> io::Result<FontFamily<FontData>> {
     ))
 }
 
-/// Calculate due date based on payment terms
-fn calculate_due_date(payment_terms: &str) -> String {
+/// Calculate due date based on payment terms. With business-day due dates on,
+/// a date on a weekend or holiday moves to the next business day, and the note
+/// returned with it says so.
+fn calculate_due_date(
+    settings: &InvoiceSettings,
+    holidays: &[TimeOff],
+) -> (String, Option<String>) {
+    let payment_terms = &settings.default_payment_terms;
     let today = Utc::now();
     let days = if payment_terms.to_lowercase().contains("net 30") {
         30
@@ -118,49 +650,284 @@ fn calculate_due_date(payment_terms: &str) -> String {
         0 // Due on receipt
     };
 
-    if days > 0 {
-        let due = today.checked_add_days(Days::new(days)).unwrap_or(today);
-        due.format("%Y-%m-%d").to_string()
+    let due = if days > 0 {
+        today.checked_add_days(Days::new(days)).unwrap_or(today)
     } else {
-        today.format("%Y-%m-%d").to_string()
+        today
+    }
+    .date_naive();
+    if !settings.business_day_due_dates {
+        return (due.format("%Y-%m-%d").to_string(), None);
     }
+
+    let Some(reason) = non_business_day(due, holidays) else {
+        return (due.format("%Y-%m-%d").to_string(), None);
+    };
+    let mut moved = due;
+    while non_business_day(moved, holidays).is_some() {
+        moved = moved.succ_opt().unwrap_or(moved);
+    }
+    let note = format!(
+        "Moved from {} ({}) to the next business day",
+        due.format("%Y-%m-%d"),
+        reason
+    );
+    (moved.format("%Y-%m-%d").to_string(), Some(note))
+}
+
+/// Why `day` isn't a business day: the weekday for weekends, the holiday's
+/// name for holidays
+fn non_business_day(day: NaiveDate, holidays: &[TimeOff]) -> Option<String> {
+    if matches!(day.weekday(), Weekday::Sat | Weekday::Sun) {
+        return Some(day.format("%A").to_string());
+    }
+    holidays
+        .iter()
+        .find(|h| h.contains(day))
+        .map(|h| match h.reason.as_str() {
+            "" => "holiday".to_string(),
+            reason => reason.to_string(),
+        })
 }
 
 /// Generate and write invoice to PDF file
 pub fn write_invoice(params: &InvoiceParams) -> io::Result<InvoiceResult> {
-    let invoice_dir = get_invoice_dir()?;
-    let file_path = format!("{}/invoice_{:04}.pdf", invoice_dir, params.invoice_number);
+    write_invoice_in(params, &get_invoice_dir()?)
+}
+
+/// Generate a PDF invoice into `invoice_dir` instead of the invoices folder
+pub fn write_invoice_in(params: &InvoiceParams, invoice_dir: &str) -> io::Result<InvoiceResult> {
+    let file_path = if params.draft {
+        draft_file(invoice_dir, params.invoice_number)
+    } else {
+        dir_file(
+            invoice_dir,
+            &format!("invoice_{:04}.pdf", params.invoice_number),
+        )
+    };
+
+    let (date_issued, due_date, due_date_note) = match &params.reissue_dates {
+        Some((issued, due, note)) => (issued.clone(), due.clone(), note.clone()),
+        None => {
+            let (due, note) = calculate_due_date(params.settings, params.holidays);
+            (Utc::now().format("%Y-%m-%d").to_string(), due, note)
+        }
+    };
+
+    // Group the entries, as billed, by project
+    let entries = billing::rounded_entries(params.entries, params.rounding_minutes);
+    let sections = project_sections(&entries, params.settings.section_order);
+    let bill = bill(params);
+
+    // Lay the document out once to count its pages, then again with the total
+    // in each page's footer
+    let pages = Rc::new(Cell::new(0));
+    let doc = build_document(
+        params,
+        &sections,
+        &bill,
+        &date_issued,
+        &due_date,
+        due_date_note.as_deref(),
+        &pages,
+        None,
+    )?;
+    doc.render(io::sink())
+        .map_err(|e| io::Error::other(e.to_string()))?;
+    let total_pages = pages.get();
+    let doc = build_document(
+        params,
+        &sections,
+        &bill,
+        &date_issued,
+        &due_date,
+        due_date_note.as_deref(),
+        &pages,
+        Some(total_pages),
+    )?;
+
+    // Render to file
+    doc.render_to_file(&file_path)
+        .map_err(|e| io::Error::other(e.to_string()))?;
+    let Totals {
+        subtotal,
+        tax_amount,
+        total,
+    } = bill.totals;
+
+    let snapshot = InvoiceSnapshot {
+        invoice_number: params.invoice_number,
+        year: params.year,
+        month: params.month,
+        date_issued: date_issued.clone(),
+        due_date: due_date.clone(),
+        due_date_note,
+        settings: params.settings.clone(),
+        client: params.client.cloned(),
+        rates: sections
+            .iter()
+            .filter_map(|(p, _)| params.project_rates.get(p).map(|r| (p.clone(), r.clone())))
+            .collect(),
+        line_items: entries
+            .iter()
+            .filter_map(|e| {
+                e.end.map(|end| SnapshotLineItem {
+                    entry_id: e.id,
+                    project: e.project.clone(),
+                    description: e.description.clone(),
+                    start: e.start,
+                    end,
+                    hours: billing::entry_hours(e).unwrap_or_default(),
+                    days: e.days,
+                    issue: e.issue.clone(),
+                })
+            })
+            .collect(),
+        expenses: params.expenses.to_vec(),
+        items: params.items.to_vec(),
+        supersedes: params.supersedes,
+        overtime: params.overtime.cloned(),
+        holidays: match params.overtime {
+            Some(rates) if rates.holiday.is_some() => holiday_days(&entries, params.holidays),
+            _ => Vec::new(),
+        },
+        tax_rate: params.tax_rate,
+        subtotal,
+        tax_amount,
+        total,
+    };
 
-    let date_issued = Utc::now().format("%Y-%m-%d").to_string();
-    let due_date = calculate_due_date(&params.settings.default_payment_terms);
+    // The same data for rendering the invoice elsewhere
+    let json_path = if params.settings.json_snapshot && !params.draft {
+        let json_path = dir_file(
+            invoice_dir,
+            &format!("invoice_{:04}.json", params.invoice_number),
+        );
+        let json =
+            serde_json::to_string_pretty(&snapshot).map_err(|e| io::Error::other(e.to_string()))?;
+        fs::write(&json_path, json)?;
+        Some(json_path)
+    } else {
+        None
+    };
+
+    Ok(InvoiceResult {
+        file_path,
+        json_path,
+        date_issued,
+        due_date,
+        subtotal,
+        tax_amount,
+        total,
+        snapshot,
+    })
+}
 
-    // Group entries by project
-    let mut entries_by_project: HashMap<String, Vec<&Entry>> = HashMap::new();
-    for entry in params.entries {
-        entries_by_project
-            .entry(entry.project.clone())
-            .or_default()
-            .push(entry);
+/// Days the entries were worked on that are public holidays
+fn holiday_days(entries: &[Entry], holidays: &[TimeOff]) -> Vec<NaiveDate> {
+    let mut days = std::collections::BTreeSet::new();
+    for entry in entries {
+        let Some(end) = entry.end else { continue };
+        let last = end.with_timezone(&Local).date_naive();
+        for day in entry
+            .start
+            .with_timezone(&Local)
+            .date_naive()
+            .iter_days()
+            .take_while(|day| *day <= last)
+        {
+            if holidays.iter().any(|h| h.holiday && h.contains(day)) {
+                days.insert(day);
+            }
+        }
     }
+    days.into_iter().collect()
+}
 
+/// Lay out the invoice PDF. The footer reads "Page X of Y" once `total_pages` is
+/// known; the number of pages laid out so far is kept in `pages`.
+#[allow(clippy::too_many_arguments)]
+fn build_document(
+    params: &InvoiceParams,
+    sections: &[(String, Vec<&Entry>)],
+    bill: &Bill,
+    date_issued: &str,
+    due_date: &str,
+    due_date_note: Option<&str>,
+    pages: &Rc<Cell<usize>>,
+    total_pages: Option<usize>,
+) -> io::Result<Document> {
     // Load font and create document
     let font_family = load_font_family()?;
     let mut doc = Document::new(font_family);
-    doc.set_title(format!("Invoice #{:04}", params.invoice_number));
+    let number = if params.draft {
+        "DRAFT".to_string()
+    } else {
+        format!("#{:04}", params.invoice_number)
+    };
+    doc.set_title(format!("Invoice {}", number));
 
-    let mut decorator = SimplePageDecorator::new();
-    decorator.set_margins(20);
-    doc.set_page_decorator(decorator);
+    doc.set_paper_size(match params.settings.paper_size {
+        PaperSize::A4 => genpdf::PaperSize::A4,
+        PaperSize::Letter => genpdf::PaperSize::Letter,
+        PaperSize::Legal => genpdf::PaperSize::Legal,
+    });
+    let base_size = if (6..=24).contains(&params.settings.font_size) {
+        params.settings.font_size
+    } else {
+        DEFAULT_FONT_SIZE
+    };
+    // Sizes below are for 10 pt body text, scaled to the configured size
+    let size = |pt: i64| ((pt * base_size + 5) / 10) as u8;
+    doc.set_font_size(size(10));
+
+    let title = if params.settings.business_name.is_empty() {
+        format!("Invoice {}", number)
+    } else {
+        format!(
+            "{} - Invoice {}",
+            pdf_text(&params.settings.business_name),
+            number
+        )
+    };
+    let table_continues = Rc::new(Cell::new(false));
+    let table_quantity = Rc::new(Cell::new("Hours"));
+    let table_refs = Rc::new(Cell::new(false));
+    doc.set_page_decorator(InvoicePageDecorator {
+        margins: Margins::from(Mm::from(params.settings.margin_mm.clamp(0.0, 50.0))),
+        title,
+        style: Style::new().with_font_size(size(8)),
+        column_style: Style::new().bold().with_font_size(size(9)),
+        page: 0,
+        pages: Rc::clone(pages),
+        total_pages,
+        table_continues: Rc::clone(&table_continues),
+        table_quantity: Rc::clone(&table_quantity),
+        table_refs: Rc::clone(&table_refs),
+    });
 
     // Styles
-    let title_style = Style::new().bold().with_font_size(24);
-    let heading_style = Style::new().bold().with_font_size(14);
-    let normal_style = Style::new().with_font_size(10);
-    let small_style = Style::new().with_font_size(9);
-    let bold_style = Style::new().bold().with_font_size(10);
-
-    // === HEADER: Invoice title and number ===
-    doc.push(Paragraph::new(format!("INVOICE #{:04}", params.invoice_number)).styled(title_style));
+    let title_style = Style::new().bold().with_font_size(size(24));
+    let heading_style = Style::new().bold().with_font_size(size(14));
+    let normal_style = Style::new().with_font_size(size(10));
+    let small_style = Style::new().with_font_size(size(9));
+    let bold_style = Style::new().bold().with_font_size(size(10));
+
+    // === HEADER: Logo, invoice title and number ===
+    // A logo that has since been moved away is left out rather than failing
+    // regeneration of older invoices
+    let logo = params.settings.logo_path.as_str();
+    if !logo.is_empty() && Path::new(logo).is_file() {
+        let image = Image::from_path(logo).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Failed to load logo {}: {}", logo, e),
+            )
+        })?;
+        doc.push(image.with_dpi(300.0));
+        doc.push(Break::new(1.0));
+    }
+    doc.push(Paragraph::new(format!("INVOICE {}", number)).styled(title_style));
     doc.push(Break::new(1.0));
 
     // === FROM / TO Section ===
@@ -169,22 +936,23 @@ pub fn write_invoice(params: &InvoiceParams) -> io::Result<InvoiceResult> {
     // From (Your business info)
     if !params.settings.business_name.is_empty() {
         doc.push(Paragraph::new("From:").styled(bold_style));
-        doc.push(Paragraph::new(&params.settings.business_name).styled(normal_style));
+        doc.push(Paragraph::new(pdf_text(&params.settings.business_name)).styled(normal_style));
         let addr = params.settings.formatted_address();
         if !addr.is_empty() {
             for line in addr.lines() {
-                doc.push(Paragraph::new(line).styled(small_style));
+                doc.push(Paragraph::new(pdf_text(line)).styled(small_style));
             }
         }
         if !params.settings.email.is_empty() {
-            doc.push(Paragraph::new(&params.settings.email).styled(small_style));
+            doc.push(Paragraph::new(pdf_text(&params.settings.email)).styled(small_style));
         }
         if !params.settings.phone.is_empty() {
-            doc.push(Paragraph::new(&params.settings.phone).styled(small_style));
+            doc.push(Paragraph::new(pdf_text(&params.settings.phone)).styled(small_style));
         }
         if !params.settings.tax_id.is_empty() {
             doc.push(
-                Paragraph::new(format!("Tax ID: {}", params.settings.tax_id)).styled(small_style),
+                Paragraph::new(format!("Tax ID: {}", pdf_text(&params.settings.tax_id)))
+                    .styled(small_style),
             );
         }
         doc.push(Break::new(0.5));
@@ -193,20 +961,21 @@ pub fn write_invoice(params: &InvoiceParams) -> io::Result<InvoiceResult> {
     // To (Client info)
     if let Some(client) = params.client {
         doc.push(Paragraph::new("Bill To:").styled(bold_style));
-        doc.push(Paragraph::new(&client.name).styled(normal_style));
+        doc.push(Paragraph::new(pdf_text(&client.name)).styled(normal_style));
         if !client.contact_person.is_empty() {
             doc.push(
-                Paragraph::new(format!("Attn: {}", client.contact_person)).styled(small_style),
+                Paragraph::new(format!("Attn: {}", pdf_text(&client.contact_person)))
+                    .styled(small_style),
             );
         }
         let addr = client.formatted_address();
         if !addr.is_empty() {
             for line in addr.lines() {
-                doc.push(Paragraph::new(line).styled(small_style));
+                doc.push(Paragraph::new(pdf_text(line)).styled(small_style));
             }
         }
         if !client.email.is_empty() {
-            doc.push(Paragraph::new(&client.email).styled(small_style));
+            doc.push(Paragraph::new(pdf_text(&client.email)).styled(small_style));
         }
         doc.push(Break::new(0.5));
     }
@@ -215,9 +984,20 @@ pub fn write_invoice(params: &InvoiceParams) -> io::Result<InvoiceResult> {
     doc.push(Break::new(0.5));
     doc.push(Paragraph::new(format!("Invoice Date: {}", date_issued)).styled(normal_style));
     doc.push(Paragraph::new(format!("Due Date: {}", due_date)).styled(normal_style));
+    if let Some(number) = params.supersedes {
+        doc.push(
+            Paragraph::new(format!("Replaces voided invoice #{:04}", number)).styled(normal_style),
+        );
+    }
+    if let Some(note) = due_date_note {
+        doc.push(Paragraph::new(pdf_text(note)).styled(small_style));
+    }
     doc.push(
-        Paragraph::new(format!("Terms: {}", params.settings.default_payment_terms))
-            .styled(normal_style),
+        Paragraph::new(format!(
+            "Terms: {}",
+            pdf_text(&params.settings.default_payment_terms)
+        ))
+        .styled(normal_style),
     );
     doc.push(
         Paragraph::new(format!("Period: {}-{:02}", params.year, params.month)).styled(normal_style),
@@ -228,77 +1008,168 @@ pub fn write_invoice(params: &InvoiceParams) -> io::Result<InvoiceResult> {
     doc.push(Paragraph::new("Services").styled(heading_style));
     doc.push(Break::new(0.5));
 
-    let mut subtotal = 0.0;
+    // Amounts are written with the locale's separators, as billing worked
+    // them out, so the recorded totals are the ones on the PDF
+    let currency = &bill.currency;
+    let locale = params.settings.number_locale;
 
-    for (project, proj_entries) in &entries_by_project {
+    for (project, proj_entries) in sections {
         let rate_info = params.project_rates.get(project);
 
         // Project header
-        let project_style = Style::new().bold().with_font_size(12);
-        doc.push(Paragraph::new(format!("Project: {}", project)).styled(project_style));
+        let project_style = Style::new().bold().with_font_size(size(12));
+        doc.push(Paragraph::new(format!("Project: {}", pdf_text(project))).styled(project_style));
 
         if let Some(r) = rate_info {
-            let rate_style = Style::new().with_font_size(9).italic();
+            let rate_style = Style::new().with_font_size(size(9)).italic();
             doc.push(
-                Paragraph::new(format!("Rate: {}{:.2}/hr", r.currency, r.rate)).styled(rate_style),
+                Paragraph::new(format!(
+                    "Rate: {}{}",
+                    format_money_in(r.rate, &r.currency, locale),
+                    r.billing_mode.rate_suffix()
+                ))
+                .styled(rate_style),
             );
         }
         doc.push(Break::new(0.3));
 
         // Create table for entries
-        let mut table = TableLayout::new(vec![4, 2, 2, 1]);
-        table.set_cell_decorator(genpdf::elements::FrameCellDecorator::new(
-            false, false, false,
-        ));
+        let quantity = quantity_heading(proj_entries);
+        table_quantity.set(quantity);
+        let refs = params.settings.issue_refs && proj_entries.iter().any(|e| e.issue.is_some());
+        table_refs.set(refs);
+        let mut table = entry_table(Style::new().bold().with_font_size(size(9)), quantity, refs);
 
-        // Table header
-        let header_style = Style::new().bold().with_font_size(9);
-        let mut header_row = table.row();
-        header_row.push_element(Paragraph::new("Description").styled(header_style));
-        header_row.push_element(Paragraph::new("Start").styled(header_style));
-        header_row.push_element(Paragraph::new("End").styled(header_style));
-        header_row.push_element(Paragraph::new("Hours").styled(header_style));
-        header_row.push().expect("Failed to push header row");
-
-        let cell_style = Style::new().with_font_size(8);
+        let cell_style = Style::new().with_font_size(size(8));
+        let notes_style = Style::new().with_font_size(size(7)).italic();
         let mut project_total = 0.0;
 
         for entry in proj_entries {
-            if let Some(end) = entry.end {
-                let hours = (end - entry.start).num_seconds() as f64 / 3600.0;
+            if let (Some(end), Some(hours)) = (entry.end, billing::entry_hours(entry)) {
                 let start_local = Local.from_utc_datetime(&entry.start.naive_utc());
                 let end_local = Local.from_utc_datetime(&end.naive_utc());
 
+                let (description, notes) = split_description(&entry.description);
                 let mut row = table.row();
-                row.push_element(Paragraph::new(&entry.description).styled(cell_style));
-                row.push_element(
-                    Paragraph::new(start_local.format("%m/%d %H:%M").to_string())
-                        .styled(cell_style),
-                );
-                row.push_element(
-                    Paragraph::new(end_local.format("%m/%d %H:%M").to_string()).styled(cell_style),
-                );
-                row.push_element(Paragraph::new(format!("{:.2}", hours)).styled(cell_style));
+                row.push_element(Paragraph::new(pdf_description(description)).styled(cell_style));
+                if refs {
+                    let issue = entry.issue.as_deref().unwrap_or_default();
+                    row.push_element(Paragraph::new(pdf_text(issue)).styled(cell_style));
+                }
+                // Day entries show the day worked and how many days, not times
+                let (start_text, end_text, quantity_text) = match entry.days {
+                    Some(days) => (
+                        start_local.format("%m/%d").to_string(),
+                        String::new(),
+                        if quantity == "Qty" {
+                            format!("{} d", format_days(days))
+                        } else {
+                            format_days(days)
+                        },
+                    ),
+                    None => (
+                        start_local.format("%m/%d %H:%M").to_string(),
+                        end_local.format("%m/%d %H:%M").to_string(),
+                        if quantity == "Qty" {
+                            format!("{:.2} h", hours)
+                        } else {
+                            format!("{:.2}", hours)
+                        },
+                    ),
+                };
+                row.push_element(Paragraph::new(start_text).styled(cell_style));
+                row.push_element(Paragraph::new(end_text).styled(cell_style));
+                row.push_element(Paragraph::new(quantity_text).styled(cell_style));
                 row.push().expect("Failed to push row");
 
+                if let Some(notes) = notes.filter(|_| params.settings.include_entry_notes) {
+                    let mut notes_row = table.row();
+                    notes_row
+                        .push_element(Paragraph::new(pdf_description(notes)).styled(notes_style));
+                    for _ in 0..if refs { 4 } else { 3 } {
+                        notes_row.push_element(Paragraph::new(""));
+                    }
+                    notes_row.push().expect("Failed to push notes row");
+                }
+
                 project_total += hours;
             }
         }
 
-        doc.push(table);
+        doc.push(ContinuedTable {
+            table,
+            continues: Rc::clone(&table_continues),
+        });
         doc.push(Break::new(0.2));
 
+        // Hours at an overtime multiplier are billed on lines of their own
+        let line = rate_info.and(bill.line(project));
+
         // Project subtotal
-        if let Some(r) = rate_info {
-            let project_cost = project_total * r.rate;
+        let overtime_line = line.filter(|l| !l.overtime.is_empty());
+        if let (Some(r), Some(line)) = (rate_info, overtime_line) {
+            let regular_hours = project_total - line.overtime.iter().map(|o| o.hours).sum::<f64>();
+            doc.push(
+                Paragraph::new(format!(
+                    "  {:.2} hrs × {} = {}",
+                    regular_hours,
+                    format_money_in(r.rate, &r.currency, locale),
+                    format_money_in(r.rate.times(regular_hours), &r.currency, locale)
+                ))
+                .styled(normal_style),
+            );
+            for o in &line.overtime {
+                let rate = r.rate.times(o.multiplier);
+                doc.push(
+                    Paragraph::new(format!(
+                        "  {} {}×: {:.2} hrs × {} = {}",
+                        o.kind.label(),
+                        o.multiplier,
+                        o.hours,
+                        format_money_in(rate, &r.currency, locale),
+                        format_money_in(rate.times(o.hours), &r.currency, locale)
+                    ))
+                    .styled(normal_style),
+                );
+            }
+            let project_cost = line.amount;
             doc.push(
                 Paragraph::new(format!(
-                    "  {:.2} hrs × {}{:.2} = {}{:.2}",
-                    project_total, r.currency, r.rate, r.currency, project_cost
+                    "  {:.2} hrs: {}",
+                    project_total,
+                    format_money_in(project_cost, &r.currency, locale)
                 ))
                 .styled(bold_style),
             );
-            subtotal += project_cost;
+        } else if let (Some(r), Some(line)) = (rate_info, line) {
+            let project_cost = line.amount;
+            let line = match r.billing_mode {
+                BillingMode::Hourly => format!(
+                    "  {:.2} hrs × {} = {}",
+                    project_total,
+                    format_money_in(r.rate, &r.currency, locale),
+                    format_money_in(project_cost, &r.currency, locale)
+                ),
+                BillingMode::Daily if project_total == 0.0 => format!(
+                    "  {} days × {} = {}",
+                    format_days(worked_days(proj_entries)),
+                    format_money_in(r.rate, &r.currency, locale),
+                    format_money_in(project_cost, &r.currency, locale)
+                ),
+                BillingMode::Daily => format!(
+                    "  {} days × {} = {} ({:.2} hrs)",
+                    format_days(worked_days(proj_entries)),
+                    format_money_in(r.rate, &r.currency, locale),
+                    format_money_in(project_cost, &r.currency, locale),
+                    project_total
+                ),
+                BillingMode::Fixed => format!(
+                    "  Fixed fee: {} ({:.2} hrs)",
+                    format_money_in(project_cost, &r.currency, locale),
+                    project_total
+                ),
+            };
+            doc.push(Paragraph::new(line).styled(bold_style));
         } else {
             doc.push(Paragraph::new(format!("  {:.2} hrs", project_total)).styled(bold_style));
         }
@@ -306,24 +1177,144 @@ pub fn write_invoice(params: &InvoiceParams) -> io::Result<InvoiceResult> {
         doc.push(Break::new(0.8));
     }
 
+    // === EXPENSES ===
+    if !params.expenses.is_empty() {
+        doc.push(Paragraph::new("Expenses").styled(heading_style));
+        doc.push(Break::new(0.5));
+        let header_style = Style::new().bold().with_font_size(size(9));
+        let cell_style = Style::new().with_font_size(size(8));
+        let mut table = TableLayout::new(vec![6, 2, 2]);
+        table.set_cell_decorator(genpdf::elements::FrameCellDecorator::new(
+            false, false, false,
+        ));
+        let mut header_row = table.row();
+        header_row.push_element(Paragraph::new("Description").styled(header_style));
+        header_row.push_element(Paragraph::new("Date").styled(header_style));
+        header_row.push_element(Paragraph::new("Amount").styled(header_style));
+        header_row.push().expect("Failed to push header row");
+        for expense in params.expenses {
+            let mut row = table.row();
+            row.push_element(Paragraph::new(pdf_description(&expense.label())).styled(cell_style));
+            row.push_element(
+                Paragraph::new(expense.date.format("%m/%d").to_string()).styled(cell_style),
+            );
+            row.push_element(
+                Paragraph::new(format_money_in(expense.amount, currency, locale))
+                    .styled(cell_style),
+            );
+            row.push().expect("Failed to push expense row");
+        }
+        doc.push(table);
+        doc.push(Break::new(0.2));
+
+        doc.push(
+            Paragraph::new(format!(
+                "  Expenses: {}",
+                format_money_in(bill.expenses, currency, locale)
+            ))
+            .styled(bold_style),
+        );
+        doc.push(Break::new(0.8));
+    }
+
+    // === OTHER ITEMS ===
+    if !params.items.is_empty() {
+        doc.push(Paragraph::new("Other Items").styled(heading_style));
+        doc.push(Break::new(0.5));
+        let header_style = Style::new().bold().with_font_size(size(9));
+        let cell_style = Style::new().with_font_size(size(8));
+        let mut table = TableLayout::new(vec![8, 2]);
+        table.set_cell_decorator(genpdf::elements::FrameCellDecorator::new(
+            false, false, false,
+        ));
+        let mut header_row = table.row();
+        header_row.push_element(Paragraph::new("Description").styled(header_style));
+        header_row.push_element(Paragraph::new("Amount").styled(header_style));
+        header_row.push().expect("Failed to push header row");
+        for item in params.items {
+            let mut row = table.row();
+            row.push_element(Paragraph::new(pdf_description(&item.description)).styled(cell_style));
+            row.push_element(
+                Paragraph::new(format_money_in(item.amount, currency, locale)).styled(cell_style),
+            );
+            row.push().expect("Failed to push item row");
+        }
+        doc.push(table);
+        doc.push(Break::new(0.8));
+    }
+
     // === TOTALS ===
     doc.push(Break::new(0.5));
 
-    let total_style = Style::new().bold().with_font_size(12);
-
-    doc.push(Paragraph::new(format!("Subtotal: ${:.2}", subtotal)).styled(normal_style));
+    let total_style = Style::new().bold().with_font_size(size(12));
+    let inclusive = params.settings.tax_inclusive && params.tax_rate > 0.0;
+    let Totals {
+        subtotal,
+        tax_amount,
+        total,
+    } = bill.totals;
 
-    let tax_amount = subtotal * (params.tax_rate / 100.0);
-    if params.tax_rate > 0.0 {
+    if inclusive {
+        // The prices above include tax, so show how much of the total it is
         doc.push(
-            Paragraph::new(format!("Tax ({:.1}%): ${:.2}", params.tax_rate, tax_amount))
-                .styled(normal_style),
+            Paragraph::new(format!(
+                "Subtotal (excl. tax): {}",
+                format_money_in(subtotal, currency, locale)
+            ))
+            .styled(normal_style),
         );
+        doc.push(
+            Paragraph::new(format!(
+                "Includes tax ({:.1}%): {}",
+                params.tax_rate,
+                format_money_in(tax_amount, currency, locale)
+            ))
+            .styled(normal_style),
+        );
+    } else {
+        doc.push(
+            Paragraph::new(format!(
+                "Subtotal: {}",
+                format_money_in(subtotal, currency, locale)
+            ))
+            .styled(normal_style),
+        );
+        if params.tax_rate > 0.0 {
+            doc.push(
+                Paragraph::new(format!(
+                    "Tax ({:.1}%): {}",
+                    params.tax_rate,
+                    format_money_in(tax_amount, currency, locale)
+                ))
+                .styled(normal_style),
+            );
+        }
     }
 
-    let total = subtotal + tax_amount;
     doc.push(Break::new(0.3));
-    doc.push(Paragraph::new(format!("TOTAL DUE: ${:.2}", total)).styled(total_style));
+    doc.push(
+        Paragraph::new(format!(
+            "TOTAL DUE: {}",
+            format_money_in(total, currency, locale)
+        ))
+        .styled(total_style),
+    );
+
+    // === PAYMENT DETAILS ===
+    let payment_details = params.settings.payment_details();
+    if !payment_details.is_empty() {
+        doc.push(Break::new(1.5));
+        doc.push(Paragraph::new("Payment Details").styled(heading_style));
+        doc.push(Break::new(0.3));
+        let mut details = TableLayout::new(vec![1, 4]);
+        for (label, value) in payment_details {
+            let mut row = details.row();
+            row.push_element(Paragraph::new(label).styled(small_style));
+            row.push_element(Paragraph::new(pdf_text(value)).styled(normal_style));
+            row.push().expect("Failed to push payment details row");
+        }
+        doc.push(details);
+    }
 
     // === PAYMENT INSTRUCTIONS ===
     if !params.settings.payment_instructions.is_empty() {
@@ -331,22 +1322,114 @@ pub fn write_invoice(params: &InvoiceParams) -> io::Result<InvoiceResult> {
         doc.push(Paragraph::new("Payment Instructions").styled(heading_style));
         doc.push(Break::new(0.3));
         for line in params.settings.payment_instructions.lines() {
-            doc.push(Paragraph::new(line).styled(small_style));
+            doc.push(Paragraph::new(pdf_text(line)).styled(small_style));
         }
     }
 
-    // Render to file
-    doc.render_to_file(&file_path)
-        .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))?;
+    Ok(doc)
+}
 
-    Ok(InvoiceResult {
-        file_path,
-        date_issued,
-        due_date,
-        subtotal,
-        tax_amount,
-        total,
-    })
+/// The entry table, with its row of column headings. `refs` adds a column for
+/// issue references after the description.
+fn entry_table(header_style: Style, quantity_heading: &str, refs: bool) -> TableLayout {
+    let mut table = TableLayout::new(if refs {
+        vec![4, 2, 2, 2, 1]
+    } else {
+        vec![4, 2, 2, 1]
+    });
+    table.set_cell_decorator(genpdf::elements::FrameCellDecorator::new(
+        false, false, false,
+    ));
+    let mut header_row = table.row();
+    header_row.push_element(Paragraph::new("Description").styled(header_style));
+    if refs {
+        header_row.push_element(Paragraph::new("Ref").styled(header_style));
+    }
+    header_row.push_element(Paragraph::new("Start").styled(header_style));
+    header_row.push_element(Paragraph::new("End").styled(header_style));
+    header_row.push_element(Paragraph::new(quantity_heading).styled(header_style));
+    header_row.push().expect("Failed to push header row");
+    table
+}
+
+/// An entry table that records whether it runs onto the next page, so the page
+/// decorator can repeat the column headings there
+struct ContinuedTable {
+    table: TableLayout,
+    continues: Rc<Cell<bool>>,
+}
+
+impl Element for ContinuedTable {
+    fn render(
+        &mut self,
+        context: &Context,
+        area: render::Area<'_>,
+        style: Style,
+    ) -> Result<RenderResult, genpdf::error::Error> {
+        let result = self.table.render(context, area, style)?;
+        self.continues.set(result.has_more);
+        Ok(result)
+    }
+}
+
+/// Draws the business name and invoice number at the top of every page and
+/// "Page X of Y" at the bottom, and repeats the entry table's column headings on
+/// pages where a table carries on.
+struct InvoicePageDecorator {
+    margins: Margins,
+    title: String,
+    style: Style,
+    column_style: Style,
+    page: usize,
+    pages: Rc<Cell<usize>>,
+    total_pages: Option<usize>,
+    table_continues: Rc<Cell<bool>>,
+    /// Quantity column heading of the table being laid out
+    table_quantity: Rc<Cell<&'static str>>,
+    /// Whether the table being laid out has a Ref column
+    table_refs: Rc<Cell<bool>>,
+}
+
+impl PageDecorator for InvoicePageDecorator {
+    fn decorate_page<'a>(
+        &mut self,
+        context: &Context,
+        mut area: render::Area<'a>,
+        style: Style,
+    ) -> Result<render::Area<'a>, genpdf::error::Error> {
+        self.page += 1;
+        self.pages.set(self.page);
+        area.add_margins(self.margins);
+
+        let footer_height = Mm::from(8);
+        let mut footer_area = area.clone();
+        footer_area.add_offset(Position::new(0, area.size().height - footer_height));
+        let footer = match self.total_pages {
+            Some(total) => format!("Page {} of {}", self.page, total),
+            None => format!("Page {}", self.page),
+        };
+        Paragraph::new(footer)
+            .aligned(Alignment::Center)
+            .styled(self.style)
+            .render(context, footer_area, style)?;
+        area.set_height(area.size().height - footer_height);
+
+        let header = Paragraph::new(self.title.as_str())
+            .styled(self.style)
+            .render(context, area.clone(), style)?;
+        area.add_offset(Position::new(0, header.size.height + Mm::from(4)));
+
+        if self.table_continues.get() {
+            let columns = entry_table(
+                self.column_style,
+                self.table_quantity.get(),
+                self.table_refs.get(),
+            )
+            .render(context, area.clone(), style)?;
+            area.add_offset(Position::new(0, columns.size.height));
+        }
+        Ok(area)
+    }
 }
 
 /// Filter entries by month and year
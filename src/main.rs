@@ -1,170 +1,1053 @@
-use chrono::{Datelike, Duration, Utc};
-use clap::Parser;
-use std::collections::HashMap;
-use std::env;
+use chrono::{
+    DateTime, Datelike, Duration, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc,
+};
+use clap::{CommandFactory, Parser};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::process;
+use std::thread;
 
+mod approval;
+mod attachments;
+mod billing;
 mod cli;
+mod completions;
 mod db;
+mod dunning;
+mod email;
+mod export;
+mod ical;
+mod import;
+mod inbox;
 mod invoice;
 mod models;
 mod notification;
+mod paths;
+mod payments;
+mod query;
+mod report;
+mod secrets;
+mod sync;
 mod tui;
 
-use cli::{Cli, ClientCommands, Commands};
+#[cfg(test)]
+mod tests;
+
+use billing::{ProjectRate, invoice_currency};
+use cli::{
+    ApprovalsCommands, AttachmentsCommands, Cli, ClientCommands, Commands, DraftsCommands,
+    DunningCommands, EntrySelection, ExpensesCommands, InvoicesCommands, MailCommands,
+    MenubarCommands, NetworkCommands, OffCommands, PaymentsCommands, ProfilesCommands,
+    ReportCommands, SuggestionsCommands, SyncCommands, TemplateCommands,
+};
 use db::Db;
-use invoice::{InvoiceParams, ProjectRate, filter_entries_by_month, write_invoice};
-use models::{Client, Entry, InvoiceSettings};
+use export::ExportFormat;
+use import::ImportFormat;
+use invoice::{
+    ClientMatch, EmailTable, InvoiceParams, InvoiceResult, InvoiceSnapshot, detect_client,
+    draft_file, expenses_for_invoice, filter_entries_by_month, get_invoice_dir, html_text,
+    orphan_invoice_files, regenerate_invoice, write_invoice,
+};
+use models::{
+    ApprovalSheet, BillingMode, BusinessProfile, CapPeriod, Client, DEFAULT_PROFILE_ID,
+    DatabaseExport, Entry, EntryFilter, EntryStatus, EntryTemplate, Expense, ExpenseKind, Invoice,
+    InvoiceDraft, MailAction, MailMatch, Money, Payment, PomodoroConfig, ReviewReason, SyncService,
+    TimeOff, format_days, format_money, format_money_in, format_short_duration, issue_from_branch,
+    normalize_currency, normalize_location, normalize_tag, parse_date_range,
+    parse_dunning_schedule, parse_log_line, parse_quiet_hours, parse_time_window, validate_rate,
+};
+use query::QueryFormat;
+use tui::app::Screen;
 
 fn main() {
     let cli = Cli::parse();
+    // Bare `meter` opens the TUI, unless turned off in the TUI settings
+    let bare = cli.command.is_none();
+    let command = cli.command.unwrap_or(Commands::Tui {
+        read_only: false,
+        db: None,
+        screen: cli.screen,
+    });
 
-    // DB lives in the home directory
-    let home = env::var("HOME").expect("HOME not set");
-    let db_path = format!("{}/.meter/db.sqlite", home);
-    let db = Db::new(&db_path).expect("Failed to open DB");
-
-    // Ensure tables exist
-    db.conn()
-        .execute_batch(
-            "
-        PRAGMA foreign_keys = ON;
-    ",
-        )
-        .unwrap();
+    // Move the database and secrets older versions kept in ~/.meter to their
+    // XDG locations; completion and read-only viewing never write
+    let moved = match &command {
+        Commands::Completions { .. }
+        | Commands::Complete { .. }
+        | Commands::Sql { .. }
+        | Commands::Tui {
+            read_only: true, ..
+        } => Vec::new(),
+        _ => paths::migrate().unwrap_or_else(|e| {
+            eprintln!(
+                "Warning: failed to move meter's files to their new locations: {}",
+                e
+            );
+            Vec::new()
+        }),
+    };
+
+    let db_path = match &command {
+        Commands::Tui { db: Some(path), .. } => path.clone(),
+        _ => paths::db_path().to_string_lossy().into_owned(),
+    };
+
+    // Completion never writes, and shouldn't fail because the DB is missing
+    match &command {
+        Commands::Completions { shell } => {
+            print!("{}", completions::script(*shell));
+            return;
+        }
+        Commands::Complete { words } => {
+            let db = Db::open_read_only(&db_path).ok();
+            for candidate in completions::complete(db.as_ref(), words) {
+                println!("{}", candidate);
+            }
+            return;
+        }
+        _ => {}
+    }
+
+    // Neither does the SQL console
+    if let Commands::Sql { query, csv, .. } = &command {
+        let db = Db::open_read_only(&db_path)
+            .unwrap_or_else(|e| exit_on_db_error(e, "open DB read-only"));
+        if let Err(e) = run_sql(&db, query, *csv) {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+        return;
+    }
+
+    // Read-only viewing skips every write, including schema setup
+    if let Commands::Tui {
+        read_only: true,
+        screen,
+        ..
+    } = &command
+    {
+        let db = Db::open_read_only(&db_path).expect("Failed to open DB read-only");
+        let screen = tui_screen(&db, screen);
+        tui::run_tui(db, screen).expect("Failed to run TUI");
+        return;
+    }
+
+    // A new install has nowhere to put the database yet
+    if let Err(e) = fs::create_dir_all(paths::data_dir()) {
+        eprintln!("Failed to create {}: {}", paths::data_dir().display(), e);
+        process::exit(1);
+    }
+    let db = Db::new(&db_path).unwrap_or_else(|e| exit_on_db_error(e, "open DB"));
 
     // Create tables if not present
-    models::init_db(db.conn()).expect("Failed to init DB");
-    models::init_projects_db(db.conn()).expect("Failed to init projects DB");
-    models::init_pomodoro_db(db.conn()).expect("Failed to init Pomodoro DB");
-    models::init_invoice_settings_db(db.conn()).expect("Failed to init invoice settings DB");
-    models::init_clients_db(db.conn()).expect("Failed to init clients DB");
-    models::init_invoices_db(db.conn()).expect("Failed to init invoices DB");
+    models::init_all(db.conn()).unwrap_or_else(|e| exit_on_db_error(e, "init DB"));
+
+    for (from, to) in &moved {
+        eprintln!("Moved {} to {}", from.display(), to.display());
+    }
+    // The invoice and attachment folders only move along with the paths stored
+    // in meter's own database, which are rewritten first so an interrupted move
+    // is finished on the next run
+    if !matches!(command, Commands::Tui { db: Some(_), .. }) {
+        for (from, to) in paths::legacy_folders() {
+            let (from_str, to_str) = (from.to_string_lossy(), to.to_string_lossy());
+            db.relocate_files(&from_str, &to_str)
+                .expect("Failed to update stored file paths");
+            match paths::move_folder(&from, &to) {
+                Ok(()) => eprintln!("Moved {} to {}", from.display(), to.display()),
+                Err(e) => {
+                    eprintln!(
+                        "Warning: failed to move {} to {}: {}",
+                        from.display(),
+                        to.display(),
+                        e
+                    );
+                    db.relocate_files(&to_str, &from_str)
+                        .expect("Failed to update stored file paths");
+                }
+            }
+        }
+    }
 
     // Sync existing entry projects to projects table
     db.sync_projects_from_entries()
         .expect("Failed to sync projects");
 
-    match &cli.command {
-        Commands::Start { project, desc } => {
-            db.start_timer(project, desc)
+    match &command {
+        Commands::Start {
+            project,
+            desc,
+            target,
+            issue,
+            location,
+            tags,
+            at,
+        } => {
+            let target_secs = match target.as_deref().map(parse_duration_arg) {
+                Some(Some(secs)) if secs > 0 => Some(secs),
+                Some(_) => {
+                    eprintln!("Invalid duration: use e.g. 2h, 90m or 1h30m");
+                    return;
+                }
+                None => None,
+            };
+            let start = match at {
+                Some(s) => {
+                    let Some(start) = parse_time_arg(s) else {
+                        eprintln!(
+                            "Invalid time '{}' (expected HH:MM[:SS], \"YYYY-MM-DD HH:MM[:SS]\" or -10m)",
+                            s
+                        );
+                        return;
+                    };
+                    if start > Utc::now() {
+                        eprintln!("A timer can't start in the future");
+                        return;
+                    }
+                    if let Some(previous) = db
+                        .entry_ending_after(start)
+                        .expect("Failed to check for overlapping entries")
+                    {
+                        eprintln!(
+                            "That overlaps the previous entry for '{}', which ended at {}",
+                            previous.project,
+                            previous
+                                .end
+                                .unwrap_or(previous.start)
+                                .with_timezone(&Local)
+                                .format("%Y-%m-%d %H:%M:%S")
+                        );
+                        return;
+                    }
+                    start
+                }
+                None => Utc::now(),
+            };
+            let desc = match desc {
+                Some(d) => d.clone(),
+                None => db
+                    .default_description(project)
+                    .expect("Failed to get default description"),
+            };
+            let mut entry = db
+                .start_timer_at(project, &desc, start)
                 .expect("Failed to start timer");
-            println!("Started timer for project '{}'", project);
-        }
-        Commands::Stop => match db.stop_active_timer().expect("Failed to stop timer") {
-            Some(entry) => {
-                let duration = entry
-                    .end
-                    .map(|end| (end - entry.start).num_seconds() as f64 / 3600.0)
-                    .unwrap_or(0.0);
+            set_up_started(&db, &mut entry, issue, location, tags);
+            match target_secs {
+                Some(secs) => {
+                    db.set_timer_target(entry.id, secs)
+                        .expect("Failed to set timer target");
+                    println!(
+                        "Started {} countdown for project '{}'",
+                        format_short_duration(secs),
+                        project
+                    );
+                }
+                None => println!("Started timer for project '{}'", project),
+            }
+            if at.is_some() {
                 println!(
-                    "Stopped timer for project '{}', duration {:.2} hrs",
-                    entry.project, duration
+                    "Started at {} ({} ago)",
+                    start.with_timezone(&Local).format("%H:%M:%S"),
+                    format_elapsed((Utc::now() - start).num_seconds())
                 );
             }
-            None => {
-                println!("No running timer");
+            report_started(&db, &entry);
+        }
+        Commands::Resume => {
+            if let Some(active) = db.get_active_entry().expect("Failed to get active entry") {
+                eprintln!("A timer is already running for '{}'", active.project);
+                return;
+            }
+            let Some(last) = db
+                .last_stopped_entry()
+                .expect("Failed to get the last entry")
+            else {
+                eprintln!("No stopped timer to resume");
+                return;
+            };
+            let mut entry = db
+                .start_timer(&last.project, &last.description)
+                .expect("Failed to start timer");
+            entry.issue = last.issue.clone();
+            entry.location = last.location.clone();
+            if entry.issue.is_some() || entry.location.is_some() {
+                db.update_entry(&entry)
+                    .expect("Failed to set issue and location");
             }
+            let tags = db.entry_tags(last.id).expect("Failed to get tags");
+            tag_entry(&db, entry.id, &tags);
+            println!("Resumed '{}': {}", entry.project, entry.description);
+        }
+        Commands::Cancel => match db.cancel_active_timer().expect("Failed to cancel timer") {
+            Some(entry) => println!(
+                "Discarded the running timer for project '{}' ({})",
+                entry.project,
+                format_elapsed((Utc::now() - entry.start).num_seconds())
+            ),
+            None => println!("No running timer"),
         },
+        Commands::Switch {
+            project,
+            desc,
+            issue,
+            location,
+            tags,
+        } => {
+            let active = db.get_active_entry().expect("Failed to get active entry");
+            if active.is_some_and(|entry| entry.project == *project) {
+                eprintln!("Already tracking '{}'", project);
+                return;
+            }
+            let desc = match desc {
+                Some(d) => d.clone(),
+                None => db
+                    .default_description(project)
+                    .expect("Failed to get default description"),
+            };
+            let (stopped, mut started) = db
+                .switch_timer(project, &desc)
+                .expect("Failed to switch timer");
+            if let Some(entry) = stopped {
+                report_stopped(&db, &entry, false);
+            }
+            set_up_started(&db, &mut started, issue, location, tags);
+            println!("Started timer for project '{}'", project);
+            report_started(&db, &started);
+        }
+        Commands::Stop { discard_short, at } => {
+            let end = match at {
+                Some(s) => {
+                    let Some(end) = parse_time_arg(s) else {
+                        eprintln!(
+                            "Invalid time '{}' (expected HH:MM[:SS], \"YYYY-MM-DD HH:MM[:SS]\" or -10m)",
+                            s
+                        );
+                        return;
+                    };
+                    if end > Utc::now() {
+                        eprintln!("A timer can't stop in the future");
+                        return;
+                    }
+                    let active = db.get_active_entry().expect("Failed to get active entry");
+                    if let Some(entry) = active.filter(|entry| end < entry.start) {
+                        eprintln!(
+                            "The timer for '{}' started at {}, after that",
+                            entry.project,
+                            entry
+                                .start
+                                .with_timezone(&Local)
+                                .format("%Y-%m-%d %H:%M:%S")
+                        );
+                        return;
+                    }
+                    end
+                }
+                None => Utc::now(),
+            };
+            match db.stop_active_timer_at(end).expect("Failed to stop timer") {
+                Some(entry) => report_stopped(&db, &entry, *discard_short),
+                None => {
+                    println!("No running timer");
+                }
+            }
+        }
+        Commands::Status { widget } => {
+            let entry = db.get_active_entry().expect("Failed to get active entry");
+            let now = Utc::now();
+            let today = db
+                .seconds_tracked_since(local_midnight(Local::now().date_naive()), now)
+                .expect("Failed to total today's time");
+            if *widget {
+                let pomodoro = db
+                    .get_pomodoro_config()
+                    .expect("Failed to get Pomodoro settings");
+                println!("{}", widget_status(entry.as_ref(), today, &pomodoro, now));
+                return;
+            }
+            match entry {
+                Some(entry) => println!(
+                    "{} - {} ({}, today {})",
+                    entry.project,
+                    entry.description,
+                    format_elapsed((now - entry.start).num_seconds()),
+                    format_elapsed(today)
+                ),
+                None => println!("No running timer (today {})", format_elapsed(today)),
+            }
+            if let Some(pace) = db
+                .target_pace(now)
+                .expect("Failed to check the monthly target")
+            {
+                let margin = db
+                    .get_notification_settings()
+                    .expect("Failed to get notification settings")
+                    .target_margin_hours;
+                println!("Billable target - {}", pace.summary(margin));
+            }
+        }
+        Commands::Amend {
+            project,
+            desc,
+            start,
+            issue,
+            location,
+        } => {
+            let Some(mut entry) = db.get_active_entry().expect("Failed to get active entry") else {
+                eprintln!("No running timer");
+                return;
+            };
+            if let Some(p) = project {
+                entry.project = p.trim().to_string();
+            }
+            if let Some(d) = desc {
+                entry.description = d.clone();
+            }
+            if let Some(i) = issue {
+                entry.issue = Some(i.trim().to_string()).filter(|i| !i.is_empty());
+            }
+            if let Some(l) = location {
+                entry.location = normalize_location(l);
+            }
+            if let Some(s) = start {
+                let Some(start) = parse_time_arg(s) else {
+                    eprintln!(
+                        "Invalid start '{}' (expected HH:MM[:SS], \"YYYY-MM-DD HH:MM[:SS]\" or -10m)",
+                        s
+                    );
+                    return;
+                };
+                if start > Utc::now() {
+                    eprintln!("A running entry can't start in the future");
+                    return;
+                }
+                entry.start = start;
+            }
+            if entry.project.is_empty() {
+                eprintln!("Project can't be empty");
+                return;
+            }
+            db.update_entry(&entry).expect("Failed to amend entry");
+            db.get_or_create_project(&entry.project)
+                .expect("Failed to create project");
+            println!(
+                "Amended running entry: '{}' | {} | started {} ({})",
+                entry.project,
+                entry.description_with_issue(),
+                entry
+                    .start
+                    .with_timezone(&Local)
+                    .format("%Y-%m-%d %H:%M:%S"),
+                format_elapsed((Utc::now() - entry.start).num_seconds())
+            );
+        }
         Commands::Add {
+            template: Some(name),
             project,
             desc,
             duration,
+            issue,
+            location,
+            tags,
+            ..
+        } => {
+            let Some(template) = db.get_template(name).expect("Failed to get template") else {
+                eprintln!("Template '{}' not found", name);
+                return;
+            };
+            let mut all_tags = template.tags.clone();
+            all_tags.extend(tags.iter().cloned());
+            add_timed_entry(
+                &db,
+                project.as_deref().unwrap_or(&template.project),
+                desc.as_deref().unwrap_or(&template.description),
+                duration.unwrap_or(template.duration),
+                issue,
+                location,
+                &all_tags,
+            );
+        }
+        Commands::Add {
+            project: Some(project),
+            desc: Some(desc),
+            days: Some(days),
+            date,
+            issue,
+            location,
+            tags,
+            ..
         } => {
+            if !days.is_finite() || *days <= 0.0 {
+                eprintln!("Days must be more than 0");
+                return;
+            }
+            let start = match date {
+                Some(d) => match NaiveDate::parse_from_str(d, "%Y-%m-%d") {
+                    Ok(date) => local_midnight(date),
+                    Err(_) => {
+                        eprintln!("Invalid date '{}': expected YYYY-MM-DD", d);
+                        return;
+                    }
+                },
+                None => Utc::now(),
+            };
+            let billing_mode = db
+                .get_project_by_name(project)
+                .expect("Failed to get project")
+                .map(|p| p.billing_mode)
+                .unwrap_or_default();
+            if billing_mode != BillingMode::Daily {
+                eprintln!(
+                    "'{}' is billed {}: set a day rate first with `meter rate --project \"{}\" --rate AMOUNT --mode daily`",
+                    project, billing_mode, project
+                );
+                return;
+            }
             let entry = Entry {
                 id: 0,
                 project: project.clone(),
                 description: desc.clone(),
-                start: Utc::now() - Duration::hours(*duration as i64),
-                end: Some(Utc::now()),
-                billed: false,
+                start,
+                end: Some(start),
+                status: EntryStatus::Open,
+                days: Some(*days),
+                issue: issue.clone(),
+                location: location.as_deref().and_then(normalize_location),
             };
-            db.insert(&entry).expect("Failed to insert entry");
+            let id = db.insert(&entry).expect("Failed to insert entry");
+            tag_entry(&db, id, tags);
             println!(
-                "Added manual entry for project '{}', duration {:.2} hrs",
-                project, duration
+                "Added day entry for project '{}', {} days",
+                project,
+                format_days(*days)
             );
         }
-        Commands::List { billed } => {
-            let entries = db.list(Some(*billed)).expect("Failed to list entries");
+        Commands::Add {
+            project: Some(project),
+            desc: Some(desc),
+            duration,
+            days: None,
+            issue,
+            location,
+            tags,
+            ..
+        } => add_timed_entry(
+            &db,
+            project,
+            desc,
+            duration.unwrap_or_default(),
+            issue,
+            location,
+            tags,
+        ),
+        Commands::Add { .. } => eprintln!("Give --project and --desc, or --template"),
+        Commands::Delete {
+            id,
+            range,
+            project,
+            yes,
+        } => {
+            let entries = match id {
+                Some(id) => match db.get_entry_by_id(*id).expect("Failed to get entry") {
+                    Some(entry) => vec![entry],
+                    None => {
+                        eprintln!("Entry {} not found", id);
+                        return;
+                    }
+                },
+                None => {
+                    let range = match range.as_deref().map(parse_date_range).transpose() {
+                        Ok(range) => range,
+                        Err(e) => {
+                            eprintln!("{}", e);
+                            return;
+                        }
+                    };
+                    let mut entries = db.list(None).expect("Failed to list entries");
+                    entries.retain(|e| {
+                        let day = e.start.with_timezone(&Local).date_naive();
+                        project.as_deref().is_none_or(|p| e.project == p)
+                            && range.is_none_or(|(from, to)| from <= day && day <= to)
+                    });
+                    entries.reverse();
+                    entries
+                }
+            };
+            if entries.is_empty() {
+                println!("No entries to delete");
+                return;
+            }
+            for e in &entries {
+                println!(
+                    "[{}] {} | {} | {} | {}",
+                    e.id,
+                    e.project,
+                    e.description_with_issue(),
+                    e.start.with_timezone(&Local).format("%Y-%m-%d %H:%M"),
+                    e.status
+                );
+            }
+            let invoiced = entries
+                .iter()
+                .filter(|e| matches!(e.status, EntryStatus::Invoiced | EntryStatus::Paid))
+                .count();
+            if invoiced > 0 {
+                println!(
+                    "{} of these are on invoices, which will still list them",
+                    invoiced
+                );
+            }
+            if !*yes && !confirm(&format!("Delete {} entries?", entries.len())) {
+                println!("Nothing deleted");
+                return;
+            }
+            let ids: Vec<i64> = entries.iter().map(|e| e.id).collect();
+            let deleted = db.delete_all(&ids).expect("Failed to delete entries");
+            println!("Deleted {} entries", deleted);
+        }
+        Commands::Tag { id, tags, remove } => {
+            if db
+                .get_entry_by_id(*id)
+                .expect("Failed to get entry")
+                .is_none()
+            {
+                eprintln!("Entry {} not found", id);
+                return;
+            }
+            let tags: Vec<String> = tags.iter().filter_map(|t| normalize_tag(t)).collect();
+            if *remove {
+                let removed = db
+                    .remove_entry_tags(*id, &tags)
+                    .expect("Failed to remove tags");
+                println!("Removed {} tag(s) from entry {}", removed, id);
+            } else {
+                db.add_entry_tags(*id, &tags).expect("Failed to add tags");
+            }
+            let tags = db.entry_tags(*id).expect("Failed to get tags");
+            if tags.is_empty() {
+                println!("Entry {} has no tags", id);
+            } else {
+                println!("Entry {} tags: {}", id, tags.join(", "));
+            }
+        }
+        Commands::Flag { id, clear } => {
+            if db
+                .get_entry_by_id(*id)
+                .expect("Failed to get entry")
+                .is_none()
+            {
+                eprintln!("Entry {} not found", id);
+                return;
+            }
+            if *clear {
+                db.clear_review_flags(*id)
+                    .expect("Failed to clear review flags");
+            } else {
+                db.flag_for_review(&[*id], ReviewReason::Manual)
+                    .expect("Failed to flag entry");
+            }
+            let flags = db.review_flags().expect("Failed to get review flags");
+            match flags.get(id) {
+                Some(reasons) => println!(
+                    "Entry {} needs review: {}",
+                    id,
+                    describe_review_reasons(reasons)
+                ),
+                None => println!("Entry {} is not flagged for review", id),
+            }
+        }
+        Commands::Log { file, dry_run } => {
+            let input = if file == "-" {
+                io::read_to_string(io::stdin())
+            } else {
+                fs::read_to_string(file)
+            };
+            let input = match input {
+                Ok(input) => input,
+                Err(e) => {
+                    eprintln!("Failed to read {}: {}", file, e);
+                    return;
+                }
+            };
+
+            // Check every line before adding any, so a typo doesn't leave half a day logged
+            let mut entries = Vec::new();
+            let mut errors = Vec::new();
+            for (i, line) in input.lines().enumerate() {
+                let line = line.trim();
+                if line.is_empty() || line.starts_with('#') {
+                    continue;
+                }
+                match parse_log_line(line) {
+                    Ok(entry) => entries.push(entry),
+                    Err(e) => errors.push(format!("  line {}: {}", i + 1, e)),
+                }
+            }
+            if !errors.is_empty() {
+                eprintln!("No entries added, {} line(s) need fixing:", errors.len());
+                for error in errors {
+                    eprintln!("{}", error);
+                }
+                return;
+            }
+
+            // Lines already logged by an earlier run are skipped
+            let entries = unrecorded_entries(&db, entries, true);
+            for e in &entries {
+                println!(
+                    "{} | {} | {} | {}",
+                    e.start.with_timezone(&Local).format("%Y-%m-%d %H:%M"),
+                    e.project,
+                    e.description,
+                    format_short_duration((e.end.unwrap_or(e.start) - e.start).num_seconds())
+                );
+            }
+            insert_entries(&db, &entries, *dry_run, None);
+        }
+        Commands::Import {
+            format: ImportFormat::Json,
+            files,
+            dry_run,
+            ..
+        } => import_database(&db, files, *dry_run),
+        Commands::Import {
+            format,
+            files,
+            dry_run,
+            columns,
+        } => {
+            let columns = match import::parse_column_map(columns) {
+                Ok(columns) => columns,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    return;
+                }
+            };
+            let mut entries = Vec::new();
+            for file in files {
+                let parsed = fs::read_to_string(file)
+                    .map_err(|e| e.to_string())
+                    .and_then(|data| import::parse(*format, &data, &columns));
+                match parsed {
+                    Ok(parsed) => entries.extend(parsed),
+                    Err(e) => {
+                        eprintln!("Failed to import {}: {}", file, e);
+                        return;
+                    }
+                }
+            }
+
+            // Importing the same file again only adds what is new. CSV exports
+            // round durations, so a row matches on its project and start alone.
+            let entries = unrecorded_entries(&db, entries, *format != ImportFormat::Csv);
+            let mut by_project: Vec<(&str, Vec<&Entry>)> = Vec::new();
+            for e in &entries {
+                match by_project.iter_mut().find(|(p, _)| *p == e.project) {
+                    Some((_, list)) => list.push(e),
+                    None => by_project.push((&e.project, vec![e])),
+                }
+            }
+            by_project.sort_by_key(|(p, _)| p.to_lowercase());
+            for (project, list) in &by_project {
+                println!(
+                    "  {:<20} {:>5} entries {:>9.2} hrs",
+                    project,
+                    list.len(),
+                    billing::total_hours(list)
+                );
+            }
+            insert_entries(&db, &entries, *dry_run, Some(ReviewReason::Imported));
+            if !*dry_run {
+                for (project, _) in &by_project {
+                    db.get_or_create_project(project)
+                        .expect("Failed to create project");
+                }
+            }
+        }
+        Commands::Export { format, output } => {
+            let data = match format {
+                ExportFormat::Json => export::to_json(&db),
+            };
+            let data = match data {
+                Ok(data) => data,
+                Err(e) => {
+                    eprintln!("Failed to export: {}", e);
+                    return;
+                }
+            };
+            match output {
+                Some(path) => {
+                    fs::write(path, data + "\n").expect("Failed to write export file");
+                    println!("Exported the database to {}", path);
+                }
+                None => println!("{}", data),
+            }
+        }
+        Commands::List {
+            status,
+            all,
+            from,
+            to,
+            project,
+            limit,
+        } => {
+            let parse_day = |day: &Option<String>| {
+                day.as_deref()
+                    .map(|d| {
+                        NaiveDate::parse_from_str(d.trim(), "%Y-%m-%d").map_err(|_| {
+                            format!("Invalid date '{}': expected YYYY-MM-DD", d.trim())
+                        })
+                    })
+                    .transpose()
+            };
+            let (from, to) = match (parse_day(from), parse_day(to)) {
+                (Ok(from), Ok(to)) => (from, to),
+                (Err(e), _) | (_, Err(e)) => {
+                    eprintln!("{}", e);
+                    return;
+                }
+            };
+            if let (Some(from), Some(to)) = (from, to)
+                && to < from
+            {
+                eprintln!("--to {} is before --from {}", to, from);
+                return;
+            }
+            let filter = EntryFilter {
+                status: if *all {
+                    None
+                } else {
+                    Some(status.unwrap_or(EntryStatus::Open))
+                },
+                project: project.clone(),
+                start_from: from.map(local_midnight),
+                start_before: to.map(|d| local_midnight(d + Duration::days(1))),
+                limit: *limit,
+            };
+            let entries = db.list_filtered(&filter).expect("Failed to list entries");
+            if entries.is_empty() {
+                println!("No entries found");
+                return;
+            }
+            let entry_tags = db.all_entry_tags().expect("Failed to list tags");
             for e in entries {
+                let tags = entry_tags
+                    .get(&e.id)
+                    .map(|tags| tags.iter().map(|t| format!(" #{}", t)).collect::<String>())
+                    .unwrap_or_default();
+                if let Some(days) = e.days {
+                    println!(
+                        "[{}] {} | {} | {} days | {}{}",
+                        e.id,
+                        e.project,
+                        e.description_with_issue(),
+                        format_days(days),
+                        e.status,
+                        tags
+                    );
+                    continue;
+                }
                 let dur = match e.end {
                     Some(end) => (end - e.start).num_seconds() as f64 / 3600.0,
                     None => 0.0,
                 };
                 println!(
-                    "[{}] {} | {} | {:.2} hrs | {}",
+                    "[{}] {} | {} | {:.2} hrs | {}{}",
                     e.id,
                     e.project,
-                    e.description,
+                    e.description_with_issue(),
                     dur,
-                    if e.billed { "billed" } else { "pending" }
+                    e.status,
+                    tags
                 );
             }
         }
-        Commands::Bill { id } => {
-            if let Some(entry_id) = id {
-                db.mark_billed(*entry_id).expect("Failed to bill entry");
-                println!("Marked entry {} as billed", entry_id);
-            } else {
-                let count = db.mark_all_billed().expect("Failed to bill all entries");
-                println!("Marked {} entries as billed", count);
+        Commands::Locations { dates } => {
+            let (start, end) = match dates.as_deref().map(parse_date_range) {
+                Some(Ok(range)) => range,
+                Some(Err(e)) => {
+                    eprintln!("{}", e);
+                    return;
+                }
+                None => {
+                    let today = Local::now().date_naive();
+                    let new_year = NaiveDate::from_ymd_opt(today.year(), 1, 1).expect("valid date");
+                    (new_year, today)
+                }
+            };
+            let entries = db.list(None).expect("Failed to list entries");
+            let mut locations: Vec<(Option<&str>, Vec<&Entry>)> = Vec::new();
+            for e in entries.iter().filter(|e| {
+                e.end.is_some()
+                    && (start..=end).contains(&e.start.with_timezone(&Local).date_naive())
+            }) {
+                let location = e.location.as_deref();
+                match locations.iter_mut().find(|(l, _)| *l == location) {
+                    Some((_, entries)) => entries.push(e),
+                    None => locations.push((location, vec![e])),
+                }
+            }
+            if locations.is_empty() {
+                println!("No finished entries from {} to {}", start, end);
+                return;
+            }
+            // Alphabetical, with entries that have no location last
+            locations.sort_by_key(|(l, _)| (l.is_none(), *l));
+
+            println!("Work locations, {} to {}", start, end);
+            println!("{:<24} {:>10} {:>8}", "Location", "Hours", "Days");
+            println!("{}", "-".repeat(44));
+            for (location, entries) in &locations {
+                println!(
+                    "{:<24} {:>10.2} {:>8}",
+                    location.unwrap_or("(not set)"),
+                    billing::total_hours(entries),
+                    format_days(billing::worked_days(entries))
+                );
             }
+            println!(
+                "
+A day worked in two places counts toward both."
+            );
         }
-        Commands::Unbill { id } => {
-            if let Some(entry_id) = id {
-                db.unmark_billed(*entry_id).expect("Failed to unbill entry");
-                println!("Marked entry {} as unbilled", entry_id);
-            } else {
-                let count = db
-                    .unmark_all_billed()
-                    .expect("Failed to unbill all entries");
-                println!("Marked {} entries as unbilled", count);
+        Commands::Query { query, format } => {
+            let query = match query::parse(query) {
+                Ok(query) => query,
+                Err(e) => {
+                    eprintln!("Invalid query: {}", e);
+                    return;
+                }
+            };
+            let entries = db.list(None).expect("Failed to list entries");
+            let tags = db.all_entry_tags().expect("Failed to get tags");
+            let result = query.run(&entries, &tags);
+            match format {
+                QueryFormat::Json => println!(
+                    "{}",
+                    result.to_json().expect("Failed to serialize query result")
+                ),
+                QueryFormat::Csv => print!("{}", result.to_csv()),
             }
         }
+        Commands::Summary { markdown } => {
+            let entries = db.list(None).expect("Failed to list entries");
+            let today = Local::now().date_naive();
+            let yesterday = today - Duration::days(1);
+            let time_off = db
+                .time_off_between(yesterday, today)
+                .expect("Failed to list time off");
+            let off = |day: NaiveDate| time_off.iter().find(|t| t.contains(day));
+            print_day_summary("Yesterday", yesterday, &entries, off(yesterday), *markdown);
+            println!();
+            print_day_summary("Today", today, &entries, off(today), *markdown);
+        }
+        Commands::Bill { selection } => set_entries_status(&db, selection, EntryStatus::Billed),
+        Commands::Unbill { selection } => set_entries_status(&db, selection, EntryStatus::Open),
+        Commands::Mark { status, selection } => set_entries_status(&db, selection, *status),
         Commands::Invoice {
             month,
             year,
             client,
+            all_clients,
             tax_rate,
+            profile,
+            draft,
+            tags,
         } => {
-            let all_entries = db.list(Some(true)).expect("Failed to list billed entries");
-            let month = month.unwrap_or(Utc::now().month() as u32);
-            let year = year.unwrap_or(Utc::now().year());
+            let Some((year, month)) = invoice_month(month.as_deref(), *year) else {
+                eprintln!("Invalid month: use 1-12 or YYYY-MM");
+                return;
+            };
+            let all_entries = db
+                .list(Some(EntryStatus::Billed))
+                .expect("Failed to list billed entries");
 
-            // Filter entries by month
-            let entries = filter_entries_by_month(&all_entries, year, month);
+            // Filter entries by month, leaving out those held by a draft or
+            // waiting for the client's approval
+            let mut drafted = db
+                .drafted_entry_ids()
+                .expect("Failed to list drafted entries");
+            drafted.extend(
+                db.awaiting_approval_entry_ids()
+                    .expect("Failed to list entries awaiting approval"),
+            );
+            let tags: Vec<String> = tags.iter().filter_map(|t| normalize_tag(t)).collect();
+            let entry_tags = db.all_entry_tags().expect("Failed to list tags");
+            let entries: Vec<Entry> = filter_entries_by_month(&all_entries, year, month)
+                .into_iter()
+                .filter(|e| !drafted.contains(&e.id))
+                .filter(|e| {
+                    tags.is_empty()
+                        || entry_tags
+                            .get(&e.id)
+                            .is_some_and(|t| t.iter().any(|t| tags.contains(t)))
+                })
+                .collect();
 
             if entries.is_empty() {
-                println!("No billed entries found for {}-{:02}", year, month);
+                if tags.is_empty() {
+                    println!("No billed entries found for {}-{:02}", year, month);
+                } else {
+                    println!(
+                        "No billed entries tagged {} found for {}-{:02}",
+                        tags.join(" or "),
+                        year,
+                        month
+                    );
+                }
                 return;
             }
 
-            // Build project rates map
-            let mut project_rates: HashMap<String, ProjectRate> = HashMap::new();
-            for entry in &entries {
-                if !project_rates.contains_key(&entry.project) {
-                    if let Ok(Some(proj)) = db.get_project_by_name(&entry.project) {
-                        if let Some(rate) = proj.rate {
-                            project_rates.insert(
-                                entry.project.clone(),
-                                ProjectRate {
-                                    rate,
-                                    currency: proj.currency.unwrap_or_else(|| "$".to_string()),
-                                },
-                            );
-                        }
-                    }
-                }
+            if let Some(id) = profile
+                && db
+                    .get_profile(*id)
+                    .expect("Failed to get profile")
+                    .is_none()
+            {
+                eprintln!("Business profile with ID {} not found", id);
+                return;
             }
+            let flags = db.review_flags().expect("Failed to get review flags");
+            let flagged = entries.iter().filter(|e| flags.contains_key(&e.id)).count();
+            if flagged > 0 {
+                eprintln!(
+                    "Warning: {} of these entries are flagged for review (see `meter report flagged`)",
+                    flagged
+                );
+            }
+            let projects = db.list_projects().expect("Failed to list projects");
 
-            // Get invoice settings
-            let settings = db
-                .get_invoice_settings()
-                .expect("Failed to get invoice settings");
+            if *all_clients {
+                invoice_all_clients(
+                    &db, &entries, &projects, year, month, *tax_rate, *profile, *draft,
+                );
+                return;
+            }
+
+            // Use the specified client, or infer it from the entries' project links
+            let client_match = detect_client(&entries, &projects);
+            if let ClientMatch::Mixed(ids) = &client_match {
+                let names: Vec<String> = ids
+                    .iter()
+                    .map(|id| match db.get_client(*id) {
+                        Ok(Some(c)) => c.name,
+                        _ => format!("#{}", id),
+                    })
+                    .collect();
+                eprintln!(
+                    "Warning: entries on this invoice belong to multiple clients ({})",
+                    names.join(", ")
+                );
+            }
+            let client_id = match (client, &client_match) {
+                (Some(id), _) => Some(*id),
+                (None, ClientMatch::Single(id)) => Some(*id),
+                _ => None,
+            };
 
-            // Get client if specified
-            let client_info = if let Some(client_id) = client {
-                match db.get_client(*client_id) {
+            let client_info = if let Some(client_id) = client_id {
+                match db.get_client(client_id) {
                     Ok(Some(c)) => Some(c),
                     Ok(None) => {
                         eprintln!("Client with ID {} not found", client_id);
@@ -178,78 +1061,361 @@ fn main() {
             } else {
                 None
             };
+            if let (None, Some(c)) = (client, &client_info) {
+                println!("Billing to client '{}' (from project links)", c.name);
+            }
 
-            // Get next invoice number
-            let invoice_number = db
-                .get_next_invoice_number()
-                .expect("Failed to get invoice number");
-
-            // Determine tax rate
+            // Invoice from the chosen profile, else the client's, using its tax rate
+            let profile_id = match profile {
+                Some(id) => *id,
+                None => db
+                    .profile_for_client(client_id)
+                    .expect("Failed to get client's profile"),
+            };
+            let settings = db
+                .get_profile_settings(profile_id)
+                .expect("Failed to get invoice settings");
             let effective_tax_rate = tax_rate.unwrap_or(settings.default_tax_rate);
 
-            let params = InvoiceParams {
-                entries: &entries,
-                project_rates: &project_rates,
+            let mut invoice_draft = InvoiceDraft {
+                id: 0,
+                client_id,
                 year,
                 month,
-                invoice_number,
-                settings: &settings,
-                client: client_info.as_ref(),
                 tax_rate: effective_tax_rate,
+                rounding_minutes: 0,
+                entry_ids: entries.iter().map(|e| e.id).collect(),
+                items: Vec::new(),
+                supersedes: None,
+                profile_id,
+                created_at: Utc::now(),
             };
 
-            match write_invoice(&params) {
-                Ok(result) => {
-                    // Record the invoice in database
-                    let invoice_record = models::Invoice {
-                        id: 0,
-                        invoice_number,
-                        client_id: *client,
-                        date_issued: result.date_issued.clone(),
-                        due_date: result.due_date.clone(),
-                        subtotal: result.subtotal,
-                        tax_rate: effective_tax_rate,
-                        tax_amount: result.tax_amount,
-                        total: result.total,
-                        file_path: result.file_path.clone(),
-                    };
-                    db.record_invoice(&invoice_record)
-                        .expect("Failed to record invoice");
+            if *draft {
+                invoice_draft.id = db
+                    .create_invoice_draft(&invoice_draft)
+                    .expect("Failed to save draft");
+                println!(
+                    "Draft {} saved with {} entries",
+                    invoice_draft.id,
+                    entries.len()
+                );
+                preview_draft(&db, &invoice_draft);
+                return;
+            }
 
+            match issue_invoice(&db, &invoice_draft) {
+                Ok((invoice_number, result)) => {
                     println!(
                         "Invoice #{} written to {}",
                         invoice_number, result.file_path
                     );
-                    println!("  Subtotal: ${:.2}", result.subtotal);
-                    if effective_tax_rate > 0.0 {
-                        println!(
-                            "  Tax ({:.1}%): ${:.2}",
-                            effective_tax_rate, result.tax_amount
-                        );
+                    if let Some(json_path) = &result.json_path {
+                        println!("  JSON: {}", json_path);
                     }
-                    println!("  Total: ${:.2}", result.total);
+                    print_invoice_totals(&result);
                 }
                 Err(e) => {
                     eprintln!("Failed to write invoice: {}", e);
                 }
             }
         }
-        Commands::Tui => {
-            tui::run_tui(db).expect("Failed to run TUI");
+        Commands::Drafts(cmd) => match cmd {
+            DraftsCommands::List => {
+                let drafts = db.list_invoice_drafts().expect("Failed to list drafts");
+                if drafts.is_empty() {
+                    println!("No drafts");
+                    return;
+                }
+                for d in &drafts {
+                    let client = match d.client_id.map(|id| db.get_client(id)) {
+                        Some(Ok(Some(c))) => c.name,
+                        _ => "(no client)".to_string(),
+                    };
+                    let mut notes = if d.rounding_minutes > 0 {
+                        format!(", rounded up to {} min", d.rounding_minutes)
+                    } else {
+                        String::new()
+                    };
+                    if let Some(number) = d.supersedes {
+                        notes.push_str(&format!(", replaces #{}", number));
+                    }
+                    println!(
+                        "Draft {}  {}-{:02}  {}  {} entries, tax {:.1}%{}",
+                        d.id,
+                        d.year,
+                        d.month,
+                        client,
+                        d.entry_ids.len(),
+                        d.tax_rate,
+                        notes
+                    );
+                    for item in &d.items {
+                        println!(
+                            "  item {:>3}  {:>10}  {}",
+                            item.id, item.amount, item.description
+                        );
+                    }
+                }
+            }
+            DraftsCommands::Edit {
+                id,
+                client,
+                profile,
+                tax_rate,
+                round,
+                add_entry,
+                remove_entry,
+            } => {
+                let Some(mut d) = db.get_invoice_draft(*id).expect("Failed to get draft") else {
+                    eprintln!("Draft {} not found", id);
+                    return;
+                };
+                if let Some(client_id) = client {
+                    if db
+                        .get_client(*client_id)
+                        .expect("Failed to get client")
+                        .is_none()
+                    {
+                        eprintln!("Client with ID {} not found", client_id);
+                        return;
+                    }
+                    d.client_id = Some(*client_id);
+                }
+                if let Some(profile_id) = profile {
+                    if db
+                        .get_profile(*profile_id)
+                        .expect("Failed to get profile")
+                        .is_none()
+                    {
+                        eprintln!("Business profile with ID {} not found", profile_id);
+                        return;
+                    }
+                    d.profile_id = *profile_id;
+                }
+                if let Some(rate) = tax_rate {
+                    if let Err(e) = validate_rate(*rate) {
+                        eprintln!("{}", e);
+                        return;
+                    }
+                    d.tax_rate = *rate;
+                }
+                if let Some(minutes) = round {
+                    if *minutes < 0 {
+                        eprintln!("Rounding must be 0 or more minutes");
+                        return;
+                    }
+                    d.rounding_minutes = *minutes;
+                }
+                let drafted = db
+                    .drafted_entry_ids()
+                    .expect("Failed to list drafted entries");
+                let awaiting = db
+                    .awaiting_approval_entry_ids()
+                    .expect("Failed to list entries awaiting approval");
+                for entry_id in add_entry {
+                    match db.get_entry_by_id(*entry_id).expect("Failed to get entry") {
+                        None => {
+                            eprintln!("Entry {} not found", entry_id);
+                            return;
+                        }
+                        Some(e) if e.status != EntryStatus::Billed => {
+                            eprintln!("Entry {} is {}, not billed", entry_id, e.status);
+                            return;
+                        }
+                        Some(_) if drafted.contains(entry_id) => {
+                            eprintln!("Entry {} is already on a draft", entry_id);
+                            return;
+                        }
+                        Some(_) if awaiting.contains(entry_id) => {
+                            eprintln!("Entry {} is waiting for the client's approval", entry_id);
+                            return;
+                        }
+                        Some(_) => d.entry_ids.push(*entry_id),
+                    }
+                }
+                d.entry_ids.retain(|e| !remove_entry.contains(e));
+                db.update_invoice_draft(&d).expect("Failed to update draft");
+                println!("Draft {} updated", id);
+                preview_draft(&db, &d);
+            }
+            DraftsCommands::AddItem { id, desc, amount } => {
+                let Some(d) = db.get_invoice_draft(*id).expect("Failed to get draft") else {
+                    eprintln!("Draft {} not found", id);
+                    return;
+                };
+                let item = db
+                    .add_draft_item(*id, desc.trim(), Money::from_f64(*amount))
+                    .expect("Failed to add item");
+                println!("Added item {} to draft {}", item, id);
+                let d = db
+                    .get_invoice_draft(d.id)
+                    .expect("Failed to get draft")
+                    .unwrap_or(d);
+                preview_draft(&db, &d);
+            }
+            DraftsCommands::RemoveItem { id, item } => {
+                if !db
+                    .delete_draft_item(*id, *item)
+                    .expect("Failed to remove item")
+                {
+                    eprintln!("Draft {} has no item {}", id, item);
+                    return;
+                }
+                println!("Removed item {} from draft {}", item, id);
+                if let Some(d) = db.get_invoice_draft(*id).expect("Failed to get draft") {
+                    preview_draft(&db, &d);
+                }
+            }
+            DraftsCommands::Preview { id } => {
+                match db.get_invoice_draft(*id).expect("Failed to get draft") {
+                    Some(d) => preview_draft(&db, &d),
+                    None => eprintln!("Draft {} not found", id),
+                }
+            }
+            DraftsCommands::Finalize { id } => {
+                let Some(d) = db.get_invoice_draft(*id).expect("Failed to get draft") else {
+                    eprintln!("Draft {} not found", id);
+                    return;
+                };
+                if d.entry_ids.is_empty() && d.items.is_empty() {
+                    eprintln!("Draft {} has nothing to invoice", id);
+                    return;
+                }
+                match issue_invoice(&db, &d) {
+                    Ok((invoice_number, result)) => {
+                        db.delete_invoice_draft(d.id)
+                            .expect("Failed to delete draft");
+                        remove_draft_preview(d.id);
+                        println!(
+                            "Draft {} finalized as invoice #{}, written to {}",
+                            id, invoice_number, result.file_path
+                        );
+                        if let Some(json_path) = &result.json_path {
+                            println!("  JSON: {}", json_path);
+                        }
+                        print_invoice_totals(&result);
+                    }
+                    Err(e) => eprintln!("Failed to write invoice: {}", e),
+                }
+            }
+            DraftsCommands::Discard { id } => {
+                if db
+                    .delete_invoice_draft(*id)
+                    .expect("Failed to delete draft")
+                {
+                    remove_draft_preview(*id);
+                    println!("Discarded draft {}; its entries can be invoiced again", id);
+                } else {
+                    eprintln!("Draft {} not found", id);
+                }
+            }
+        },
+        Commands::Schema => {
+            let version = db.schema_version().expect("Failed to read schema version");
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&schema_document(version))
+                    .expect("Failed to serialize schema")
+            );
+        }
+        Commands::Tui { screen, .. } => {
+            if bare
+                && !db
+                    .get_tui_settings()
+                    .expect("Failed to get TUI settings")
+                    .open_by_default
+            {
+                Cli::command().print_help().expect("Failed to print help");
+                return;
+            }
+            let screen = tui_screen(&db, screen);
+            tui::run_tui(db, screen).expect("Failed to run TUI");
+        }
+        Commands::Menubar(cmd) => match (cmd, menubar_pid()) {
+            (_, None) => println!("The menu bar app is not running"),
+            (MenubarCommands::Status, Some(pid)) => {
+                println!("The menu bar app is running (pid: {})", pid);
+            }
+            (MenubarCommands::Stop, Some(pid)) => {
+                if stop_process(pid) {
+                    println!("Stopped the menu bar app (pid: {})", pid);
+                } else {
+                    eprintln!("Failed to stop the menu bar app (pid: {})", pid);
+                }
+            }
+        },
+        Commands::Review => {
+            tui::run_tui(db, Screen::Review).expect("Failed to run TUI");
+        }
+        Commands::TuiSettings {
+            open_by_default,
+            default_screen,
+        } => {
+            let mut settings = db.get_tui_settings().expect("Failed to get TUI settings");
+
+            if open_by_default.is_some() || default_screen.is_some() {
+                if let Some(open) = open_by_default {
+                    settings.open_by_default = *open;
+                }
+                if let Some(screen) = default_screen {
+                    settings.default_screen = screen.to_string();
+                }
+                db.set_tui_settings(&settings)
+                    .expect("Failed to update TUI settings");
+                println!("TUI settings updated");
+            }
+
+            println!("\nTUI Settings:");
+            println!(
+                "  Bare `meter`:   {}",
+                if settings.open_by_default {
+                    "opens the TUI"
+                } else {
+                    "prints help"
+                }
+            );
+            println!("  Default screen: {}", settings.default_screen);
         }
+        // Handled before the database is opened
+        Commands::Completions { .. } | Commands::Complete { .. } | Commands::Sql { .. } => {}
         Commands::Rate {
             project,
             rate,
             currency,
+            mode,
         } => {
-            if let Some(rate_value) = rate {
-                db.set_project_rate(project, Some(*rate_value), Some(currency))
+            let currency = match normalize_currency(currency) {
+                Ok(c) => c,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    return;
+                }
+            };
+            if let Some(Err(e)) = rate.map(validate_rate) {
+                eprintln!("{}", e);
+                return;
+            }
+            if let Some(mode) = mode {
+                db.set_project_billing_mode(project, *mode)
+                    .expect("Failed to set billing mode");
+                println!("Set billing mode for '{}' to {}", project, mode);
+            }
+            if let Some(rate_value) = rate.map(Money::from_f64) {
+                db.set_project_rate(project, Some(rate_value), Some(&currency))
                     .expect("Failed to set rate");
+                let suffix = db
+                    .get_project_by_name(project)
+                    .expect("Failed to get project")
+                    .map(|p| p.billing_mode.rate_suffix())
+                    .unwrap_or("/hr");
                 println!(
-                    "Set rate for '{}' to {}{:.2}/hr",
-                    project, currency, rate_value
+                    "Set rate for '{}' to {}{}",
+                    project,
+                    format_money(rate_value, &currency),
+                    suffix
                 );
-            } else {
+            } else if mode.is_none() {
                 match db.get_project_by_name(project) {
                     Ok(Some(proj)) => {
                         if let Some(formatted) = proj.formatted_rate() {
@@ -263,18 +1429,133 @@ fn main() {
                 }
             }
         }
+        Commands::Cap {
+            project,
+            hours,
+            period,
+            clear,
+        } => {
+            if *clear {
+                db.set_project_cap(project, None, CapPeriod::default())
+                    .expect("Failed to clear cap");
+                println!("Removed hour cap for '{}'", project);
+            } else if let Some(hours) = hours {
+                let period = period.unwrap_or_default();
+                db.set_project_cap(project, Some(*hours), period)
+                    .expect("Failed to set cap");
+                println!("Capped '{}' at {:.1}h per {}", project, hours, period);
+            }
+            match db
+                .project_cap_usage(project, Utc::now())
+                .expect("Failed to check hour cap")
+            {
+                Some(usage) => println!("'{}': {}", project, usage.summary()),
+                None if !*clear => println!("No hour cap set for '{}'", project),
+                None => {}
+            }
+        }
+        Commands::Idle {
+            project,
+            minutes,
+            default,
+        } => {
+            if *default {
+                db.set_project_idle_stop(project, None)
+                    .expect("Failed to update project");
+            } else if let Some(minutes) = minutes {
+                if *minutes < 0 {
+                    eprintln!("Minutes must not be negative");
+                    return;
+                }
+                db.set_project_idle_stop(project, Some(*minutes))
+                    .expect("Failed to update project");
+            }
+            let default_minutes = db
+                .get_timer_settings()
+                .expect("Failed to get timer settings")
+                .idle_stop_minutes;
+            let Some(proj) = db
+                .get_project_by_name(project)
+                .expect("Failed to get project")
+            else {
+                eprintln!("Project '{}' not found", project);
+                return;
+            };
+            let source = if proj.idle_stop_minutes.is_some() {
+                "project setting"
+            } else {
+                "default"
+            };
+            match proj.idle_limit(default_minutes) {
+                Some(m) => println!(
+                    "'{}': timer stops after {} idle minutes ({})",
+                    project, m, source
+                ),
+                None => println!("'{}': timer never stops when idle ({})", project, source),
+            }
+        }
+        Commands::Area {
+            project,
+            name,
+            clear,
+        } => {
+            if *clear {
+                db.set_project_area(project, None)
+                    .expect("Failed to update project");
+            } else if let Some(name) = name {
+                let name = name.trim();
+                if name.is_empty() {
+                    eprintln!("Area name must not be empty (use --clear to remove it)");
+                    return;
+                }
+                db.set_project_area(project, Some(name))
+                    .expect("Failed to update project");
+            }
+            let Some(proj) = db
+                .get_project_by_name(project)
+                .expect("Failed to get project")
+            else {
+                eprintln!("Project '{}' not found", project);
+                return;
+            };
+            match proj.area {
+                Some(area) => println!("'{}': in area '{}'", project, area),
+                None => println!("'{}': not in an area", project),
+            }
+        }
         Commands::Projects => {
             let projects = db.list_projects().expect("Failed to list projects");
             if projects.is_empty() {
                 println!("No projects found");
             } else {
-                println!("{:<30} {:<15}", "Project", "Rate");
-                println!("{}", "-".repeat(45));
+                let clients = db.list_clients().expect("Failed to list clients");
+                println!(
+                    "{:<30} {:<15} {:<25} {:<20} {:<30}",
+                    "Project", "Rate", "Cap", "Area", "Client"
+                );
+                println!("{}", "-".repeat(121));
                 for proj in projects {
                     let rate_str = proj
                         .formatted_rate()
                         .unwrap_or_else(|| "Not set".to_string());
-                    println!("{:<30} {:<15}", proj.name, rate_str);
+                    let client_str = proj
+                        .client_id
+                        .and_then(|id| clients.iter().find(|c| c.id == id))
+                        .map(|c| c.name.as_str())
+                        .unwrap_or("-");
+                    let cap_str = db
+                        .project_cap_usage(&proj.name, Utc::now())
+                        .expect("Failed to check hour cap")
+                        .map(|usage| usage.summary())
+                        .unwrap_or_else(|| "-".to_string());
+                    println!(
+                        "{:<30} {:<15} {:<25} {:<20} {:<30}",
+                        proj.name,
+                        rate_str,
+                        cap_str,
+                        proj.area.as_deref().unwrap_or("-"),
+                        client_str
+                    );
                 }
             }
         }
@@ -335,6 +1616,227 @@ fn main() {
             println!("  Long break:        {} minutes", config.long_break);
             println!("  Cycles before long break: {}", config.cycles_before_long);
         }
+        Commands::TimerSettings {
+            reuse_description,
+            min_duration,
+            discard_short,
+            split_midnight,
+            track_activity,
+            idle_stop,
+            git_issues,
+        } => {
+            let mut settings = db
+                .get_timer_settings()
+                .expect("Failed to get timer settings");
+
+            let has_changes = reuse_description.is_some()
+                || min_duration.is_some()
+                || discard_short.is_some()
+                || split_midnight.is_some()
+                || track_activity.is_some()
+                || idle_stop.is_some()
+                || git_issues.is_some();
+
+            if has_changes {
+                if let Some(reuse) = reuse_description {
+                    settings.reuse_last_description = *reuse;
+                }
+                if let Some(secs) = min_duration {
+                    settings.min_duration_secs = (*secs).max(0);
+                }
+                if let Some(discard) = discard_short {
+                    settings.discard_short_entries = *discard;
+                }
+                if let Some(split) = split_midnight {
+                    settings.split_at_midnight = *split;
+                }
+                if let Some(track) = track_activity {
+                    settings.track_activity = *track;
+                }
+                if let Some(minutes) = idle_stop {
+                    settings.idle_stop_minutes = (*minutes).max(0);
+                }
+                if let Some(git) = git_issues {
+                    settings.issue_from_git_branch = *git;
+                }
+                db.set_timer_settings(&settings)
+                    .expect("Failed to update timer settings");
+                println!("Timer settings updated");
+            }
+
+            println!("\nTimer Settings:");
+            println!(
+                "  Reuse last description: {}",
+                if settings.reuse_last_description {
+                    "Yes"
+                } else {
+                    "No"
+                }
+            );
+            if settings.min_duration_secs > 0 {
+                println!(
+                    "  Minimum duration:       {} seconds",
+                    settings.min_duration_secs
+                );
+            } else {
+                println!("  Minimum duration:       Off");
+            }
+            println!(
+                "  Discard short entries:  {}",
+                if settings.discard_short_entries {
+                    "Yes"
+                } else {
+                    "No"
+                }
+            );
+            println!(
+                "  Split at midnight:      {}",
+                if settings.split_at_midnight {
+                    "Yes"
+                } else {
+                    "No"
+                }
+            );
+            println!(
+                "  Track activity:         {}",
+                if settings.track_activity { "Yes" } else { "No" }
+            );
+            if settings.idle_stop_minutes > 0 {
+                println!(
+                    "  Stop when idle:         after {} minutes",
+                    settings.idle_stop_minutes
+                );
+            } else {
+                println!("  Stop when idle:         Off");
+            }
+            println!(
+                "  Issues from git branch: {}",
+                if settings.issue_from_git_branch {
+                    "Yes"
+                } else {
+                    "No"
+                }
+            );
+        }
+        Commands::Notifications {
+            work_complete,
+            break_complete,
+            reminders,
+            sound,
+            quiet_hours,
+            badge_hours,
+            badge_overdue,
+            monthly_target,
+            target_margin,
+        } => {
+            let mut settings = db
+                .get_notification_settings()
+                .expect("Failed to get notification settings");
+
+            let has_changes = work_complete.is_some()
+                || break_complete.is_some()
+                || reminders.is_some()
+                || sound.is_some()
+                || quiet_hours.is_some()
+                || badge_hours.is_some()
+                || badge_overdue.is_some()
+                || monthly_target.is_some()
+                || target_margin.is_some();
+
+            if has_changes {
+                if let Some(v) = work_complete {
+                    settings.work_complete = *v;
+                }
+                if let Some(v) = break_complete {
+                    settings.break_complete = *v;
+                }
+                if let Some(v) = reminders {
+                    settings.reminders = *v;
+                }
+                if let Some(v) = sound {
+                    settings.sound = v.trim().to_string();
+                }
+                if let Some(v) = quiet_hours {
+                    match parse_quiet_hours(v) {
+                        Ok(hours) => {
+                            settings.quiet_start = hours.map(|(start, _)| start);
+                            settings.quiet_end = hours.map(|(_, end)| end);
+                        }
+                        Err(e) => {
+                            eprintln!("{}", e);
+                            return;
+                        }
+                    }
+                }
+                if let Some(v) = badge_hours {
+                    if *v < 0.0 {
+                        eprintln!("Badge hours can't be negative");
+                        return;
+                    }
+                    settings.badge_unbilled_hours = *v;
+                }
+                if let Some(v) = badge_overdue {
+                    settings.badge_overdue = *v;
+                }
+                if let Some(v) = monthly_target {
+                    if *v < 0.0 {
+                        eprintln!("The monthly target can't be negative");
+                        return;
+                    }
+                    settings.monthly_target_hours = *v;
+                }
+                if let Some(v) = target_margin {
+                    if *v < 0.0 {
+                        eprintln!("The target margin can't be negative");
+                        return;
+                    }
+                    settings.target_margin_hours = *v;
+                }
+                db.set_notification_settings(&settings)
+                    .expect("Failed to update notification settings");
+                println!("Notification settings updated");
+            }
+
+            let on_off = |on: bool| if on { "On" } else { "Off" };
+            println!("\nNotification Settings:");
+            println!("  Work complete:  {}", on_off(settings.work_complete));
+            println!("  Break complete: {}", on_off(settings.break_complete));
+            println!("  Reminders:      {}", on_off(settings.reminders));
+            println!(
+                "  Sound:          {}",
+                if settings.sound.is_empty() {
+                    "(silent)"
+                } else {
+                    &settings.sound
+                }
+            );
+            println!("  Quiet hours:    {}", settings.quiet_hours());
+            println!(
+                "  Badge:          {}",
+                match (settings.badge_unbilled_hours > 0.0, settings.badge_overdue) {
+                    (true, true) => format!(
+                        "Over {:.1} unbilled hours or an overdue invoice",
+                        settings.badge_unbilled_hours
+                    ),
+                    (true, false) => {
+                        format!("Over {:.1} unbilled hours", settings.badge_unbilled_hours)
+                    }
+                    (false, true) => "An overdue invoice".to_string(),
+                    (false, false) => "Off".to_string(),
+                }
+            );
+            println!(
+                "  Monthly target: {}",
+                if settings.monthly_target_hours > 0.0 {
+                    format!(
+                        "{:.1} billable hours, warn {:.1}h behind pace",
+                        settings.monthly_target_hours, settings.target_margin_hours
+                    )
+                } else {
+                    "Off".to_string()
+                }
+            );
+        }
         Commands::InvoiceSettings {
             business_name,
             street,
@@ -348,10 +1850,35 @@ fn main() {
             payment_instructions,
             payment_terms,
             tax_rate,
+            tax_inclusive,
+            reminder_day,
+            include_notes,
+            paper_size,
+            margin,
+            font_size,
+            section_order,
+            number_locale,
+            iban,
+            bic,
+            account_number,
+            routing_number,
+            swift,
+            business_days,
+            json,
+            issue_refs,
+            logo,
+            profile,
         } => {
-            let mut settings = db
-                .get_invoice_settings()
-                .expect("Failed to get invoice settings");
+            let profile_id = profile.unwrap_or(DEFAULT_PROFILE_ID);
+            let Some(BusinessProfile {
+                name: profile_name,
+                mut settings,
+                ..
+            }) = db.get_profile(profile_id).expect("Failed to get profile")
+            else {
+                eprintln!("Business profile with ID {} not found", profile_id);
+                return;
+            };
 
             // Check if any arguments were provided
             let has_changes = business_name.is_some()
@@ -365,7 +1892,24 @@ fn main() {
                 || tax_id.is_some()
                 || payment_instructions.is_some()
                 || payment_terms.is_some()
-                || tax_rate.is_some();
+                || tax_rate.is_some()
+                || tax_inclusive.is_some()
+                || reminder_day.is_some()
+                || include_notes.is_some()
+                || paper_size.is_some()
+                || margin.is_some()
+                || font_size.is_some()
+                || section_order.is_some()
+                || number_locale.is_some()
+                || iban.is_some()
+                || bic.is_some()
+                || account_number.is_some()
+                || routing_number.is_some()
+                || swift.is_some()
+                || business_days.is_some()
+                || json.is_some()
+                || issue_refs.is_some()
+                || logo.is_some();
 
             if has_changes {
                 if let Some(v) = business_name {
@@ -404,14 +1948,74 @@ fn main() {
                 if let Some(v) = tax_rate {
                     settings.default_tax_rate = *v;
                 }
+                if let Some(v) = tax_inclusive {
+                    settings.tax_inclusive = *v;
+                }
+                if let Some(v) = reminder_day {
+                    settings.reminder_day = *v;
+                }
+                if let Some(v) = include_notes {
+                    settings.include_entry_notes = *v;
+                }
+                if let Some(v) = paper_size {
+                    settings.paper_size = *v;
+                }
+                if let Some(v) = margin {
+                    settings.margin_mm = *v;
+                }
+                if let Some(v) = font_size {
+                    settings.font_size = *v;
+                }
+                if let Some(v) = section_order {
+                    settings.section_order = *v;
+                }
+                if let Some(v) = number_locale {
+                    settings.number_locale = *v;
+                }
+                if let Some(v) = iban {
+                    settings.iban = v.clone();
+                }
+                if let Some(v) = bic {
+                    settings.bic = v.clone();
+                }
+                if let Some(v) = account_number {
+                    settings.account_number = v.clone();
+                }
+                if let Some(v) = routing_number {
+                    settings.routing_number = v.clone();
+                }
+                if let Some(v) = swift {
+                    settings.swift = v.clone();
+                }
+                if let Some(v) = business_days {
+                    settings.business_day_due_dates = *v;
+                }
+                if let Some(v) = json {
+                    settings.json_snapshot = *v;
+                }
+                if let Some(v) = issue_refs {
+                    settings.issue_refs = *v;
+                }
+                if let Some(v) = logo {
+                    let path = v.trim();
+                    if !path.is_empty() && !std::path::Path::new(path).is_file() {
+                        eprintln!("Logo file {} not found", path);
+                        return;
+                    }
+                    settings.logo_path = path.to_string();
+                }
+                if let Err(e) = settings.validate_layout() {
+                    eprintln!("{}", e);
+                    return;
+                }
 
-                db.set_invoice_settings(&settings)
+                db.set_profile_settings(profile_id, &settings)
                     .expect("Failed to update invoice settings");
                 println!("Invoice settings updated\n");
             }
 
             // Display current settings
-            println!("Invoice Settings:");
+            println!("Invoice Settings ({} profile):", profile_name);
             println!("  Business Name:     {}", settings.business_name);
             println!("  Address:");
             if !settings.address_street.is_empty() {
@@ -435,41 +2039,216 @@ fn main() {
                 println!("  Tax ID:            {}", settings.tax_id);
             }
             println!("  Payment Terms:     {}", settings.default_payment_terms);
-            println!("  Default Tax Rate:  {}%", settings.default_tax_rate);
-            if !settings.payment_instructions.is_empty() {
-                println!("  Payment Instructions:");
-                for line in settings.payment_instructions.lines() {
-                    println!("    {}", line);
+            println!(
+                "  Due Dates:         {}",
+                if settings.business_day_due_dates {
+                    "next business day"
+                } else {
+                    "any day"
+                }
+            );
+            println!(
+                "  Default Tax Rate:  {}%{}",
+                settings.default_tax_rate,
+                if settings.tax_inclusive {
+                    " (included in rates)"
+                } else {
+                    ""
                 }
+            );
+            if settings.reminder_day > 0 {
+                println!("  Invoice Reminder:  day {}", settings.reminder_day);
+            } else {
+                println!("  Invoice Reminder:  off");
             }
-        }
-        Commands::Client(cmd) => match cmd {
-            ClientCommands::Add {
-                name,
-                contact,
-                street,
-                city,
-                state,
-                postal,
-                country,
-                email,
-            } => {
-                let client = Client {
-                    id: 0,
-                    name: name.clone(),
-                    contact_person: contact.clone().unwrap_or_default(),
-                    address_street: street.clone().unwrap_or_default(),
+            println!(
+                "  Page Layout:       {}, {} mm margins, {} pt text",
+                settings.paper_size, settings.margin_mm, settings.font_size
+            );
+            println!("  Section Order:     {}", settings.section_order);
+            println!(
+                "  Amounts:           {} ({})",
+                settings.number_locale,
+                format_money_in(
+                    Money::from_cents(123_456_789),
+                    "USD",
+                    settings.number_locale
+                )
+            );
+            println!(
+                "  Entry Notes:       {}",
+                if settings.include_entry_notes {
+                    "shown"
+                } else {
+                    "hidden"
+                }
+            );
+            println!(
+                "  JSON Copy:         {}",
+                if settings.json_snapshot {
+                    "written next to each PDF"
+                } else {
+                    "off"
+                }
+            );
+            println!(
+                "  Issue References:  {}",
+                if settings.issue_refs {
+                    "shown"
+                } else {
+                    "hidden"
+                }
+            );
+            if !settings.logo_path.is_empty() {
+                println!("  Logo:              {}", settings.logo_path);
+            }
+            let payment_details = settings.payment_details();
+            if !payment_details.is_empty() {
+                println!("  Payment Details:");
+                for (label, value) in payment_details {
+                    println!("    {:<15}{}", format!("{}:", label), value);
+                }
+            }
+            if !settings.payment_instructions.is_empty() {
+                println!("  Payment Instructions:");
+                for line in settings.payment_instructions.lines() {
+                    println!("    {}", line);
+                }
+            }
+        }
+        Commands::Profiles(cmd) => match cmd {
+            ProfilesCommands::List => {
+                let profiles = db.list_profiles().expect("Failed to list profiles");
+                println!(
+                    "{:<5} {:<20} {:<30} {:>12} {:>12}",
+                    "ID", "Name", "Business Name", "First No.", "Next No."
+                );
+                for profile in profiles {
+                    let next = db
+                        .get_next_invoice_number(profile.id)
+                        .expect("Failed to get next invoice number");
+                    println!(
+                        "{:<5} {:<20} {:<30} {:>12} {:>12}",
+                        profile.id,
+                        profile.name,
+                        profile.settings.business_name,
+                        profile.first_number,
+                        next
+                    );
+                }
+            }
+            ProfilesCommands::Add { name, first_number } => {
+                let name = name.trim();
+                if name.is_empty() {
+                    eprintln!("Profile name cannot be empty");
+                    return;
+                }
+                if *first_number < 1 {
+                    eprintln!("The first invoice number must be 1 or more");
+                    return;
+                }
+                match db.add_profile(name, *first_number) {
+                    Ok(id) => println!(
+                        "Added profile '{}' with ID {}; set its details with `meter invoice-settings --profile {}`",
+                        name, id, id
+                    ),
+                    Err(e) => eprintln!("Failed to add profile '{}': {}", name, e),
+                }
+            }
+            ProfilesCommands::Edit {
+                id,
+                name,
+                first_number,
+            } => {
+                let Some(profile) = db.get_profile(*id).expect("Failed to get profile") else {
+                    eprintln!("Business profile with ID {} not found", id);
+                    return;
+                };
+                let name = name.as_deref().map(str::trim).unwrap_or(&profile.name);
+                let first_number = first_number.unwrap_or(profile.first_number);
+                if name.is_empty() {
+                    eprintln!("Profile name cannot be empty");
+                    return;
+                }
+                if first_number < 1 {
+                    eprintln!("The first invoice number must be 1 or more");
+                    return;
+                }
+                match db.update_profile(*id, name, first_number) {
+                    Ok(()) => println!("Updated profile '{}'", name),
+                    Err(e) => eprintln!("Failed to update profile: {}", e),
+                }
+            }
+            ProfilesCommands::Remove { id } => {
+                if *id == DEFAULT_PROFILE_ID {
+                    eprintln!("The default profile can't be removed");
+                    return;
+                }
+                if db.delete_profile(*id).expect("Failed to remove profile") {
+                    println!("Removed profile {}", id);
+                } else {
+                    eprintln!(
+                        "Profile {} not found, or it has invoices and can't be removed",
+                        id
+                    );
+                }
+            }
+        },
+        Commands::Client(cmd) => match cmd {
+            ClientCommands::Add {
+                name,
+                contact,
+                street,
+                city,
+                state,
+                postal,
+                country,
+                email,
+                reminder_day,
+                profile,
+            } => {
+                let profile_id = match client_profile(&db, *profile) {
+                    Ok(id) => id,
+                    Err(e) => {
+                        eprintln!("{}", e);
+                        return;
+                    }
+                };
+                let client = Client {
+                    id: 0,
+                    name: name.trim().to_string(),
+                    contact_person: contact.clone().unwrap_or_default(),
+                    address_street: street.clone().unwrap_or_default(),
                     address_city: city.clone().unwrap_or_default(),
                     address_state: state.clone().unwrap_or_default(),
                     address_postal: postal.clone().unwrap_or_default(),
                     address_country: country.clone().unwrap_or_default(),
                     email: email.clone().unwrap_or_default(),
+                    archived: false,
+                    reminder_day: *reminder_day,
+                    profile_id,
                 };
+                if let Err(e) = client.validate() {
+                    eprintln!("{}", e);
+                    return;
+                }
+                if let Ok(Some(existing)) = db.find_client_by_name(&client.name) {
+                    eprintln!(
+                        "Client '{}' already exists with ID {}",
+                        existing.name, existing.id
+                    );
+                    return;
+                }
                 let id = db.add_client(&client).expect("Failed to add client");
-                println!("Added client '{}' with ID {}", name, id);
+                println!("Added client '{}' with ID {}", client.name, id);
             }
-            ClientCommands::List => {
-                let clients = db.list_clients().expect("Failed to list clients");
+            ClientCommands::List { all } => {
+                let clients: Vec<Client> = db
+                    .list_clients()
+                    .expect("Failed to list clients")
+                    .into_iter()
+                    .filter(|c| *all || !c.archived)
+                    .collect();
                 if clients.is_empty() {
                     println!("No clients found");
                 } else {
@@ -479,9 +2258,14 @@ fn main() {
                     );
                     println!("{}", "-".repeat(95));
                     for client in clients {
+                        let name = if client.archived {
+                            format!("{} (archived)", client.name)
+                        } else {
+                            client.name.clone()
+                        };
                         println!(
                             "{:<5} {:<30} {:<30} {:<30}",
-                            client.id, client.name, client.contact_person, client.email
+                            client.id, name, client.contact_person, client.email
                         );
                     }
                 }
@@ -496,6 +2280,8 @@ fn main() {
                 postal,
                 country,
                 email,
+                reminder_day,
+                profile,
             } => {
                 let mut client = match db.get_client(*id) {
                     Ok(Some(c)) => c,
@@ -510,7 +2296,7 @@ fn main() {
                 };
 
                 if let Some(v) = name {
-                    client.name = v.clone();
+                    client.name = v.trim().to_string();
                 }
                 if let Some(v) = contact {
                     client.contact_person = v.clone();
@@ -533,17 +2319,3266 @@ fn main() {
                 if let Some(v) = email {
                     client.email = v.clone();
                 }
+                if let Some(v) = reminder_day {
+                    client.reminder_day = Some(*v);
+                }
+                if profile.is_some() {
+                    match client_profile(&db, *profile) {
+                        Ok(id) => client.profile_id = id,
+                        Err(e) => {
+                            eprintln!("{}", e);
+                            return;
+                        }
+                    }
+                }
+
+                if let Err(e) = client.validate() {
+                    eprintln!("{}", e);
+                    return;
+                }
+                if let Ok(Some(existing)) = db.find_client_by_name(&client.name)
+                    && existing.id != client.id
+                {
+                    eprintln!(
+                        "Client '{}' already exists with ID {}",
+                        existing.name, existing.id
+                    );
+                    return;
+                }
 
                 db.update_client(&client).expect("Failed to update client");
                 println!("Updated client '{}'", client.name);
             }
             ClientCommands::Delete { id } => {
+                let invoice_count = db
+                    .count_client_invoices(*id)
+                    .expect("Failed to count client invoices");
+                if invoice_count > 0 {
+                    eprintln!(
+                        "Client {} has {} invoice(s) and can't be deleted; use `meter client archive {}` instead",
+                        id, invoice_count, id
+                    );
+                    return;
+                }
                 if db.delete_client(*id).expect("Failed to delete client") {
                     println!("Deleted client with ID {}", id);
                 } else {
                     eprintln!("Client with ID {} not found", id);
                 }
             }
+            ClientCommands::Archive { id } => {
+                if db
+                    .set_client_archived(*id, true)
+                    .expect("Failed to archive client")
+                {
+                    println!("Archived client with ID {}", id);
+                } else {
+                    eprintln!("Client with ID {} not found", id);
+                }
+            }
+            ClientCommands::Unarchive { id } => {
+                if db
+                    .set_client_archived(*id, false)
+                    .expect("Failed to restore client")
+                {
+                    println!("Restored client with ID {}", id);
+                } else {
+                    eprintln!("Client with ID {} not found", id);
+                }
+            }
+            ClientCommands::Merge { id, into } => {
+                if id == into {
+                    eprintln!("Cannot merge a client into itself");
+                    return;
+                }
+                let (from, target) = match (db.get_client(*id), db.get_client(*into)) {
+                    (Ok(Some(f)), Ok(Some(t))) => (f, t),
+                    (Ok(None), _) => {
+                        eprintln!("Client with ID {} not found", id);
+                        return;
+                    }
+                    (_, Ok(None)) => {
+                        eprintln!("Client with ID {} not found", into);
+                        return;
+                    }
+                    (Err(e), _) | (_, Err(e)) => {
+                        eprintln!("Failed to get client: {}", e);
+                        return;
+                    }
+                };
+                let moved = db
+                    .merge_clients(from.id, target.id)
+                    .expect("Failed to merge clients");
+                println!(
+                    "Merged client '{}' into '{}' ({} invoices moved)",
+                    from.name, target.name, moved
+                );
+            }
+            ClientCommands::Link { id, project } => {
+                let client = match db.get_client(*id) {
+                    Ok(Some(c)) => c,
+                    Ok(None) => {
+                        eprintln!("Client with ID {} not found", id);
+                        return;
+                    }
+                    Err(e) => {
+                        eprintln!("Failed to get client: {}", e);
+                        return;
+                    }
+                };
+                if client.archived {
+                    eprintln!(
+                        "Client '{}' is archived; run `meter client unarchive {}` first",
+                        client.name, client.id
+                    );
+                    return;
+                }
+                db.set_project_client(project, Some(client.id))
+                    .expect("Failed to link project");
+                println!("Linked project '{}' to client '{}'", project, client.name);
+            }
+            ClientCommands::Unlink { project } => {
+                db.set_project_client(project, None)
+                    .expect("Failed to unlink project");
+                println!("Removed client link from project '{}'", project);
+            }
+            ClientCommands::Overtime {
+                id,
+                evening,
+                evening_hours,
+                weekend,
+                holiday,
+            } => {
+                let client = match db.get_client(*id) {
+                    Ok(Some(c)) => c,
+                    Ok(None) => {
+                        eprintln!("Client with ID {} not found", id);
+                        return;
+                    }
+                    Err(e) => {
+                        eprintln!("Failed to get client: {}", e);
+                        return;
+                    }
+                };
+                let mut rates = db
+                    .get_overtime_rates(client.id)
+                    .expect("Failed to get overtime rates")
+                    .unwrap_or_default();
+                let changing = evening.is_some()
+                    || evening_hours.is_some()
+                    || weekend.is_some()
+                    || holiday.is_some();
+                if changing {
+                    for (name, multiplier) in [
+                        ("evening", evening),
+                        ("weekend", weekend),
+                        ("holiday", holiday),
+                    ] {
+                        if multiplier.is_some_and(|m| !(m > 0.0 && m <= 10.0)) {
+                            eprintln!("The {} multiplier must be more than 0 and at most 10", name);
+                            return;
+                        }
+                    }
+                    // A multiplier of 1 is the normal rate
+                    let multiplier = |m: f64| Some(m).filter(|m| *m != 1.0);
+                    if let Some(m) = evening {
+                        rates.evening = multiplier(*m);
+                    }
+                    if let Some(m) = weekend {
+                        rates.weekend = multiplier(*m);
+                    }
+                    if let Some(m) = holiday {
+                        rates.holiday = multiplier(*m);
+                    }
+                    if let Some(window) = evening_hours {
+                        match parse_time_window(window, "evening hours") {
+                            Ok((start, end)) => {
+                                rates.evening_start = start;
+                                rates.evening_end = end;
+                            }
+                            Err(e) => {
+                                eprintln!("{}", e);
+                                return;
+                            }
+                        }
+                    }
+                    db.set_overtime_rates(client.id, &rates)
+                        .expect("Failed to set overtime rates");
+                    println!("Updated overtime rates for '{}'", client.name);
+                }
+
+                println!("Overtime rates for '{}':", client.name);
+                let shown = |m: Option<f64>| match m {
+                    Some(m) => format!("{}×", m),
+                    None => "normal rate".to_string(),
+                };
+                println!(
+                    "  Evenings ({}-{}): {}",
+                    rates.evening_start.format("%H:%M"),
+                    rates.evening_end.format("%H:%M"),
+                    shown(rates.evening)
+                );
+                println!("  Weekends: {}", shown(rates.weekend));
+                println!("  Holidays: {}", shown(rates.holiday));
+                if !rates.is_empty() {
+                    println!(
+                        "Hourly work in these times is billed on its own invoice lines; \
+                         time that counts twice uses the higher multiplier."
+                    );
+                }
+            }
         },
-    }
+        Commands::Invoices(cmd) => match cmd {
+            InvoicesCommands::List => {
+                let invoices = db.list_invoices().expect("Failed to list invoices");
+                if invoices.is_empty() {
+                    println!("No invoices found");
+                    return;
+                }
+                let mail = db.list_mail_matches().expect("Failed to list mail matches");
+                println!(
+                    "{:<8} {:<12} {:<12} {:>12}  File",
+                    "Number", "Issued", "Due", "Total"
+                );
+                for inv in &invoices {
+                    let mut file = if inv.file_missing() {
+                        format!("MISSING ({})", inv.file_path)
+                    } else {
+                        inv.file_path.clone()
+                    };
+                    if let Some(reason) = &inv.void_reason {
+                        file = format!("VOID: {} ({})", reason, file);
+                    }
+                    if let Some(number) = inv.supersedes {
+                        file = format!("{} (replaces #{})", file, number);
+                    }
+                    // The latest email about the invoice, if the inbox watcher saw one
+                    if let Some(m) = mail
+                        .iter()
+                        .rev()
+                        .find(|m| m.invoice_number == inv.invoice_number)
+                    {
+                        file = format!(
+                            "{} ({} {})",
+                            file,
+                            m.action,
+                            m.matched_at.with_timezone(&Local).format("%Y-%m-%d")
+                        );
+                    }
+                    println!(
+                        "{:<8} {:<12} {:<12} {:>12}  {}",
+                        inv.invoice_number, inv.date_issued, inv.due_date, inv.total, file
+                    );
+                }
+            }
+            InvoicesCommands::Regenerate { number } => {
+                let invoices = db.list_invoices().expect("Failed to list invoices");
+                let Some(inv) = invoices.iter().find(|i| i.invoice_number == *number) else {
+                    eprintln!("Invoice #{} not found", number);
+                    return;
+                };
+                match regenerate_invoice_file(&db, inv) {
+                    Ok(path) => println!("Invoice #{} written to {}", number, path),
+                    Err(e) => eprintln!("Failed to regenerate invoice #{}: {}", number, e),
+                }
+            }
+            InvoicesCommands::Clean { dry_run, yes } => {
+                let invoices = db.list_invoices().expect("Failed to list invoices");
+                let orphans =
+                    orphan_invoice_files(&invoices).expect("Failed to read invoice directory");
+                if orphans.is_empty() {
+                    println!("No orphaned invoice PDFs");
+                    return;
+                }
+                for path in &orphans {
+                    println!("{}", path.display());
+                }
+                if *dry_run {
+                    println!("Dry run, nothing removed");
+                    return;
+                }
+                if !*yes && !confirm(&format!("Remove {} PDFs?", orphans.len())) {
+                    return;
+                }
+                for path in &orphans {
+                    if let Err(e) = std::fs::remove_file(path) {
+                        eprintln!("Failed to remove {}: {}", path.display(), e);
+                    } else {
+                        println!("Removed {}", path.display());
+                    }
+                }
+            }
+            InvoicesCommands::Paid { number } => {
+                let invoices = db.list_invoices().expect("Failed to list invoices");
+                let Some(inv) = invoices.iter().find(|i| i.invoice_number == *number) else {
+                    eprintln!("Invoice #{} not found", number);
+                    return;
+                };
+                if inv.is_void() {
+                    eprintln!("Invoice #{} is void", number);
+                    return;
+                }
+                // Whatever earlier, partial payments left open
+                let paid = db
+                    .invoice_paid_amount(*number)
+                    .expect("Failed to sum payments");
+                let outstanding = Money::from_cents(inv.total.cents() - paid.cents());
+                if outstanding <= Money::ZERO {
+                    eprintln!("Invoice #{} is already paid in full", number);
+                    return;
+                }
+                let currency = inv
+                    .snapshot
+                    .as_deref()
+                    .and_then(|json| InvoiceSnapshot::from_json(json).ok())
+                    .map(|s| invoice_currency(s.rates.values()))
+                    .unwrap_or_else(|| "$".to_string());
+                db.record_payment(&Payment {
+                    id: 0,
+                    provider: "manual".to_string(),
+                    external_id: format!("invoice-{}", inv.id),
+                    invoice_number: Some(*number),
+                    amount: outstanding,
+                    fee: Money::ZERO,
+                    currency,
+                    paid_at: Utc::now(),
+                    expense_id: None,
+                })
+                .expect("Failed to record payment");
+                let paid = mark_invoice_paid(&db, inv).expect("Failed to mark entries paid");
+                println!(
+                    "Invoice #{} paid, {} entries marked paid",
+                    number,
+                    paid.len()
+                );
+            }
+            InvoicesCommands::Void {
+                number,
+                reason,
+                supersede,
+            } => {
+                let invoices = db.list_invoices().expect("Failed to list invoices");
+                let Some(inv) = invoices.iter().find(|i| i.invoice_number == *number) else {
+                    eprintln!("Invoice #{} not found", number);
+                    return;
+                };
+                if inv.is_void() {
+                    eprintln!("Invoice #{} is already void", number);
+                    return;
+                }
+                let Some(snapshot) = inv
+                    .snapshot
+                    .as_deref()
+                    .and_then(|json| InvoiceSnapshot::from_json(json).ok())
+                else {
+                    eprintln!(
+                        "Invoice #{} was issued before snapshots were stored, so its entries aren't known",
+                        number
+                    );
+                    return;
+                };
+                let entry_ids: Vec<i64> = snapshot.line_items.iter().map(|i| i.entry_id).collect();
+                for id in &entry_ids {
+                    if let Ok(Some(entry)) = db.get_entry_by_id(*id)
+                        && entry.status == EntryStatus::Paid
+                    {
+                        eprintln!(
+                            "Entry {} on invoice #{} is paid; a paid invoice can't be voided",
+                            id, number
+                        );
+                        return;
+                    }
+                }
+
+                let released = db
+                    .void_invoice(*number, reason.trim(), &entry_ids)
+                    .expect("Failed to void invoice");
+                println!(
+                    "Invoice #{} voided; {} entries are billed again",
+                    number,
+                    released.len()
+                );
+
+                if *supersede {
+                    let mut replacement = InvoiceDraft {
+                        id: 0,
+                        client_id: inv.client_id,
+                        year: snapshot.year,
+                        month: snapshot.month,
+                        tax_rate: inv.tax_rate,
+                        rounding_minutes: 0,
+                        entry_ids: released,
+                        items: snapshot.items.clone(),
+                        supersedes: Some(*number),
+                        profile_id: inv.profile_id,
+                        created_at: Utc::now(),
+                    };
+                    replacement.id = db
+                        .create_invoice_draft(&replacement)
+                        .expect("Failed to save draft");
+                    for item in &replacement.items {
+                        db.add_draft_item(replacement.id, &item.description, item.amount)
+                            .expect("Failed to add item");
+                    }
+                    let replacement = db
+                        .get_invoice_draft(replacement.id)
+                        .expect("Failed to get draft")
+                        .unwrap_or(replacement);
+                    println!(
+                        "Draft {} replaces invoice #{}; correct it, then finalize it",
+                        replacement.id, number
+                    );
+                    preview_draft(&db, &replacement);
+                }
+            }
+            InvoicesCommands::Bundle { number } => {
+                let invoices = db.list_invoices().expect("Failed to list invoices");
+                let Some(inv) = invoices.iter().find(|i| i.invoice_number == *number) else {
+                    eprintln!("Invoice #{} not found", number);
+                    return;
+                };
+                if inv.file_missing() {
+                    eprintln!(
+                        "Invoice #{} PDF is missing; run `meter invoices regenerate {}` first",
+                        number, number
+                    );
+                    return;
+                }
+                let list = db
+                    .invoice_attachments(*number)
+                    .expect("Failed to list attachments");
+                let pdf = std::path::Path::new(&inv.file_path);
+                let dest = pdf.with_extension("zip");
+                match attachments::bundle(pdf, &list, &dest) {
+                    Ok(missing) => {
+                        println!(
+                            "Invoice #{} and {} receipt(s) written to {}",
+                            number,
+                            list.len() - missing,
+                            dest.display()
+                        );
+                        if missing > 0 {
+                            eprintln!("{} attachment(s) missing on disk were left out", missing);
+                        }
+                    }
+                    Err(e) => eprintln!("Failed to bundle invoice #{}: {}", number, e),
+                }
+            }
+            InvoicesCommands::Send { number, to, table } => {
+                let invoices = db.list_invoices().expect("Failed to list invoices");
+                let Some(inv) = invoices.iter().find(|i| i.invoice_number == *number) else {
+                    eprintln!("Invoice #{} not found", number);
+                    return;
+                };
+                if inv.is_void() {
+                    eprintln!("Invoice #{} is void", number);
+                    return;
+                }
+                if inv.file_missing() {
+                    eprintln!(
+                        "Invoice #{} PDF is missing; run `meter invoices regenerate {}` first",
+                        number, number
+                    );
+                    return;
+                }
+                let Some(snapshot) = inv
+                    .snapshot
+                    .as_deref()
+                    .and_then(|json| InvoiceSnapshot::from_json(json).ok())
+                else {
+                    eprintln!("Invoice #{} has no stored snapshot to describe it", number);
+                    return;
+                };
+                let client = match inv.client_id {
+                    Some(id) => db.get_client(id).expect("Failed to get client"),
+                    None => None,
+                }
+                .or_else(|| snapshot.client.clone());
+                let Some(to) = to
+                    .clone()
+                    .or_else(|| client.as_ref().map(|c| c.email.clone()))
+                    .filter(|to| !to.trim().is_empty())
+                else {
+                    eprintln!(
+                        "Invoice #{} has no client email; give one with --to",
+                        number
+                    );
+                    return;
+                };
+
+                let settings = &snapshot.settings;
+                let currency = invoice_currency(snapshot.rates.values());
+                let greeting = match &client {
+                    Some(c) if !c.contact_person.is_empty() => {
+                        format!("Hello {},", c.contact_person)
+                    }
+                    Some(c) => format!("Hello {},", c.name),
+                    None => "Hello,".to_string(),
+                };
+                let subject = if settings.business_name.is_empty() {
+                    format!("Invoice #{:04}", number)
+                } else {
+                    format!("Invoice #{:04} from {}", number, settings.business_name)
+                };
+                let summary = format!(
+                    "Please find attached invoice #{:04} for {}-{:02}, for {}, due {}.",
+                    number,
+                    snapshot.year,
+                    snapshot.month,
+                    format_money_in(snapshot.total, &currency, settings.number_locale),
+                    snapshot.due_date
+                );
+                let mut text = format!("{}\n\n{}\n\n", greeting, summary);
+                if table.is_some() {
+                    text.push_str(&snapshot.markdown_table());
+                    text.push('\n');
+                }
+                text.push_str(&format!("Thank you,\n{}\n", settings.business_name));
+                let html = (*table == Some(EmailTable::Html)).then(|| {
+                    format!(
+                        "<p>{}</p>\n<p>{}</p>\n{}<p>Thank you,<br>{}</p>\n",
+                        html_text(&greeting),
+                        html_text(&summary),
+                        snapshot.html_table(),
+                        html_text(&settings.business_name)
+                    )
+                });
+
+                let email_settings = db
+                    .get_email_settings()
+                    .expect("Failed to get email settings");
+                match email::send_with_attachment(
+                    &email_settings,
+                    to.trim(),
+                    &subject,
+                    &text,
+                    html.as_deref(),
+                    std::path::Path::new(&inv.file_path),
+                ) {
+                    Ok(()) => println!("Invoice #{} sent to {}", number, to.trim()),
+                    Err(e) => eprintln!("{}", e),
+                }
+            }
+        },
+        Commands::Approvals(cmd) => match cmd {
+            ApprovalsCommands::Sheet {
+                client,
+                month,
+                year,
+                csv,
+            } => {
+                let Some((year, month)) = invoice_month(month.as_deref(), *year) else {
+                    eprintln!("Invalid month: use 1-12 or YYYY-MM");
+                    return;
+                };
+                let Some(client) = db.get_client(*client).expect("Failed to get client") else {
+                    eprintln!("Client with ID {} not found", client);
+                    return;
+                };
+                let projects = db.list_projects().expect("Failed to list projects");
+                let client_projects: Vec<&str> = projects
+                    .iter()
+                    .filter(|p| p.client_id == Some(client.id))
+                    .map(|p| p.name.as_str())
+                    .collect();
+
+                // Finished work not yet invoiced, and not held by a draft or another sheet
+                let mut held = db
+                    .drafted_entry_ids()
+                    .expect("Failed to list drafted entries");
+                held.extend(
+                    db.awaiting_approval_entry_ids()
+                        .expect("Failed to list entries awaiting approval"),
+                );
+                let all_entries = db.list(None).expect("Failed to list entries");
+                let entries: Vec<Entry> = filter_entries_by_month(&all_entries, year, month)
+                    .into_iter()
+                    .filter(|e| {
+                        e.end.is_some()
+                            && matches!(
+                                e.status,
+                                EntryStatus::Open | EntryStatus::Submitted | EntryStatus::Billed
+                            )
+                            && client_projects.contains(&e.project.as_str())
+                            && !held.contains(&e.id)
+                    })
+                    .collect();
+                if entries.is_empty() {
+                    println!(
+                        "No uninvoiced entries for {} in {}-{:02}",
+                        client.name, year, month
+                    );
+                    return;
+                }
+
+                let mut sheet = ApprovalSheet {
+                    id: 0,
+                    client_id: Some(client.id),
+                    year,
+                    month,
+                    file_path: String::new(),
+                    entry_ids: entries.iter().map(|e| e.id).collect(),
+                    created_at: Utc::now(),
+                    approved_at: None,
+                };
+                sheet.id = db
+                    .create_approval_sheet(&sheet)
+                    .expect("Failed to save approval sheet");
+                let invoice_dir = get_invoice_dir().expect("Failed to create invoice directory");
+                let path = approval::sheet_file(&invoice_dir, sheet.id, *csv);
+                let refs: Vec<&Entry> = entries.iter().collect();
+                let written = if *csv {
+                    approval::write_csv(&path, &refs)
+                } else {
+                    let profile_id = db
+                        .profile_for_client(Some(client.id))
+                        .expect("Failed to get client's profile");
+                    let settings = db
+                        .get_profile_settings(profile_id)
+                        .expect("Failed to get invoice settings");
+                    let title = format!("Hours for approval, {}-{:02}", year, month);
+                    approval::write_pdf(&path, &title, &refs, Some(&client), &settings)
+                };
+                if let Err(e) = written {
+                    db.delete_approval_sheet(sheet.id)
+                        .expect("Failed to delete approval sheet");
+                    eprintln!("Failed to write approval sheet: {}", e);
+                    return;
+                }
+                db.set_approval_sheet_file(sheet.id, &path)
+                    .expect("Failed to save approval sheet");
+                db.set_entries_status(&sheet.entry_ids, EntryStatus::Submitted)
+                    .expect("Failed to update entries");
+                println!(
+                    "Approval sheet {} with {} entries ({:.2} h) written to {}",
+                    sheet.id,
+                    entries.len(),
+                    billing::total_hours(&refs),
+                    path
+                );
+                println!(
+                    "Its entries are held back from invoices until `meter approvals approve {}`",
+                    sheet.id
+                );
+            }
+            ApprovalsCommands::List => {
+                let sheets = db
+                    .list_approval_sheets()
+                    .expect("Failed to list approval sheets");
+                if sheets.is_empty() {
+                    println!("No approval sheets");
+                    return;
+                }
+                for s in &sheets {
+                    let client = match s.client_id.map(|id| db.get_client(id)) {
+                        Some(Ok(Some(c))) => c.name,
+                        _ => "(no client)".to_string(),
+                    };
+                    let status = match s.approved_at {
+                        Some(at) => {
+                            format!("approved {}", at.with_timezone(&Local).format("%Y-%m-%d"))
+                        }
+                        None => "awaiting approval".to_string(),
+                    };
+                    println!(
+                        "Sheet {}  {}-{:02}  {}  {} entries, {}  {}",
+                        s.id,
+                        s.year,
+                        s.month,
+                        client,
+                        s.entry_ids.len(),
+                        status,
+                        s.file_path
+                    );
+                }
+            }
+            ApprovalsCommands::Approve { id, reject } => {
+                let Some(sheet) = db
+                    .get_approval_sheet(*id)
+                    .expect("Failed to get approval sheet")
+                else {
+                    eprintln!("Approval sheet {} not found", id);
+                    return;
+                };
+                if sheet.is_approved() {
+                    eprintln!("Approval sheet {} is already approved", id);
+                    return;
+                }
+                if let Some(entry_id) = reject.iter().find(|e| !sheet.entry_ids.contains(e)) {
+                    eprintln!("Entry {} is not on approval sheet {}", entry_id, id);
+                    return;
+                }
+                let approved: Vec<i64> = sheet
+                    .entry_ids
+                    .iter()
+                    .copied()
+                    .filter(|e| !reject.contains(e))
+                    .collect();
+                db.approve_sheet(*id, reject)
+                    .expect("Failed to approve sheet");
+                db.set_entries_status(reject, EntryStatus::Open)
+                    .expect("Failed to update entries");
+                let billed = db
+                    .set_entries_status(&approved, EntryStatus::Billed)
+                    .expect("Failed to update entries");
+                println!(
+                    "Approval sheet {} approved: {} entries marked billed",
+                    id,
+                    billed.len()
+                );
+                if !reject.is_empty() {
+                    println!("{} rejected entries moved back to open", reject.len());
+                }
+            }
+            ApprovalsCommands::Withdraw { id } => {
+                let Some(sheet) = db
+                    .get_approval_sheet(*id)
+                    .expect("Failed to get approval sheet")
+                else {
+                    eprintln!("Approval sheet {} not found", id);
+                    return;
+                };
+                if sheet.is_approved() {
+                    eprintln!("Approval sheet {} is already approved", id);
+                    return;
+                }
+                db.delete_approval_sheet(*id)
+                    .expect("Failed to delete approval sheet");
+                let _ = fs::remove_file(&sheet.file_path);
+                println!(
+                    "Approval sheet {} withdrawn; its {} entries are left as submitted",
+                    id,
+                    sheet.entry_ids.len()
+                );
+            }
+        },
+        Commands::Suggestions(cmd) => match cmd {
+            SuggestionsCommands::List { min_minutes } => {
+                let suggestions = db
+                    .activity_suggestions(min_minutes * 60)
+                    .expect("Failed to list suggestions");
+                if suggestions.is_empty() {
+                    println!("No suggestions");
+                    return;
+                }
+                for span in &suggestions {
+                    let start = span.start.with_timezone(&Local);
+                    let end = span.end.with_timezone(&Local);
+                    println!(
+                        "{:>4}  {} {}-{}  You were in {} for {:.1}h - add entry?",
+                        span.id,
+                        start.format("%Y-%m-%d"),
+                        start.format("%H:%M"),
+                        end.format("%H:%M"),
+                        span.app,
+                        span.hours()
+                    );
+                }
+                println!("\nAdd one with `meter suggestions add ID --project NAME`");
+            }
+            SuggestionsCommands::Add { id, project, desc } => {
+                let Some(span) = db.get_activity(*id).expect("Failed to get suggestion") else {
+                    eprintln!("Suggestion {} not found", id);
+                    return;
+                };
+                let entry = Entry {
+                    id: 0,
+                    project: project.clone(),
+                    description: desc.clone().unwrap_or_else(|| span.app.clone()),
+                    start: span.start,
+                    end: Some(span.end),
+                    status: EntryStatus::Open,
+                    days: None,
+                    issue: None,
+                    location: None,
+                };
+                let entry_id = db.insert(&entry).expect("Failed to add entry");
+                db.dismiss_activity(span.id)
+                    .expect("Failed to dismiss suggestion");
+                println!(
+                    "Added entry {} for '{}' ({:.2}h)",
+                    entry_id,
+                    project,
+                    span.hours()
+                );
+            }
+            SuggestionsCommands::Dismiss { id } => {
+                if db
+                    .dismiss_activity(*id)
+                    .expect("Failed to dismiss suggestion")
+                {
+                    println!("Dismissed suggestion {}", id);
+                } else {
+                    eprintln!("Suggestion {} not found", id);
+                }
+            }
+        },
+        Commands::Off(cmd) => match cmd {
+            OffCommands::Add {
+                dates,
+                reason,
+                holiday,
+            } => {
+                let (start, end) = match parse_date_range(dates) {
+                    Ok(range) => range,
+                    Err(e) => {
+                        eprintln!("{}", e);
+                        return;
+                    }
+                };
+                let id = db
+                    .add_time_off(start, end, reason, *holiday)
+                    .expect("Failed to add time off");
+                let days = (end - start).num_days() + 1;
+                println!(
+                    "Added time off {} ({} day{})",
+                    id,
+                    days,
+                    if days == 1 { "" } else { "s" }
+                );
+            }
+            OffCommands::List => {
+                let time_off = db.list_time_off().expect("Failed to list time off");
+                if time_off.is_empty() {
+                    println!("No time off recorded");
+                    return;
+                }
+                for t in &time_off {
+                    println!(
+                        "{:>4}  {:<22}  {:>3}d  {:<8}  {}",
+                        t.id,
+                        t.range(),
+                        t.days(),
+                        if t.holiday { "holiday" } else { "" },
+                        t.reason
+                    );
+                }
+            }
+            OffCommands::Remove { id } => {
+                if db.delete_time_off(*id).expect("Failed to delete time off") {
+                    println!("Deleted time off {}", id);
+                } else {
+                    eprintln!("Time off {} not found", id);
+                }
+            }
+        },
+        Commands::Template(cmd) => match cmd {
+            TemplateCommands::Add {
+                name,
+                project,
+                desc,
+                duration,
+                tags,
+            } => {
+                if *duration <= 0.0 {
+                    eprintln!("Duration must be more than 0");
+                    return;
+                }
+                let template = EntryTemplate {
+                    id: 0,
+                    name: name.trim().to_string(),
+                    project: project.clone(),
+                    description: desc.clone(),
+                    tags: tags.iter().filter_map(|t| normalize_tag(t)).collect(),
+                    duration: *duration,
+                };
+                if template.name.is_empty() || template.project.trim().is_empty() {
+                    eprintln!("Templates need a name and a project");
+                    return;
+                }
+                db.save_template(&template)
+                    .expect("Failed to save template");
+                println!(
+                    "Saved template '{}': {} | {:.2} hrs",
+                    template.name, template.project, template.duration
+                );
+            }
+            TemplateCommands::List => {
+                let templates = db.list_templates().expect("Failed to list templates");
+                if templates.is_empty() {
+                    println!("No templates saved");
+                    return;
+                }
+                for t in &templates {
+                    let tags: Vec<String> = t.tags.iter().map(|tag| format!("#{}", tag)).collect();
+                    println!(
+                        "{:<16} {:<20} {:>5.2} hrs  {} {}",
+                        t.name,
+                        t.project,
+                        t.duration,
+                        t.description,
+                        tags.join(" ")
+                    );
+                }
+            }
+            TemplateCommands::Remove { name } => {
+                if db.delete_template(name).expect("Failed to delete template") {
+                    println!("Deleted template '{}'", name);
+                } else {
+                    eprintln!("Template '{}' not found", name);
+                }
+            }
+        },
+        Commands::Network(cmd) => match cmd {
+            NetworkCommands::Add {
+                ssid,
+                project,
+                location,
+            } => {
+                if ssid.is_empty() || project.trim().is_empty() {
+                    eprintln!("Network rules need a network name and a project");
+                    return;
+                }
+                let location = location.as_deref().and_then(normalize_location);
+                db.save_network_rule(ssid, project, location.as_deref())
+                    .expect("Failed to save network rule");
+                println!(
+                    "On '{}' Wi-Fi, the menu bar app will suggest '{}'",
+                    ssid, project
+                );
+            }
+            NetworkCommands::List => {
+                let rules = db
+                    .list_network_rules()
+                    .expect("Failed to list network rules");
+                if rules.is_empty() {
+                    println!("No network rules");
+                    return;
+                }
+                for rule in &rules {
+                    println!(
+                        "{:<24} {:<20} {}",
+                        rule.ssid,
+                        rule.project,
+                        rule.location.as_deref().unwrap_or("")
+                    );
+                }
+            }
+            NetworkCommands::Remove { ssid } => {
+                if db
+                    .delete_network_rule(ssid)
+                    .expect("Failed to delete network rule")
+                {
+                    println!("Deleted the rule for '{}'", ssid);
+                } else {
+                    eprintln!("No rule for '{}'", ssid);
+                }
+            }
+        },
+        Commands::Expenses(cmd) => match cmd {
+            ExpensesCommands::Add {
+                amount,
+                distance,
+                desc,
+                date,
+                entry,
+                client,
+            } => {
+                let entry = match entry {
+                    Some(id) => match db.get_entry_by_id(*id).expect("Failed to get entry") {
+                        Some(entry) => Some(entry),
+                        None => {
+                            eprintln!("Entry {} not found", id);
+                            return;
+                        }
+                    },
+                    None => None,
+                };
+                let date = match date {
+                    Some(d) => match NaiveDate::parse_from_str(d, "%Y-%m-%d") {
+                        Ok(date) => date,
+                        Err(_) => {
+                            eprintln!("Invalid date '{}': expected YYYY-MM-DD", d);
+                            return;
+                        }
+                    },
+                    None => entry
+                        .as_ref()
+                        .map(|e| e.start.with_timezone(&Local).date_naive())
+                        .unwrap_or_else(|| Local::now().date_naive()),
+                };
+                let client_id = client.or_else(|| {
+                    let project = db
+                        .get_project_by_name(&entry.as_ref()?.project)
+                        .expect("Failed to get project");
+                    project?.client_id
+                });
+
+                let mut expense = Expense {
+                    id: 0,
+                    date,
+                    kind: ExpenseKind::Expense,
+                    description: desc.trim().to_string(),
+                    amount: Money::ZERO,
+                    distance: None,
+                    unit: None,
+                    entry_id: entry.as_ref().map(|e| e.id),
+                    client_id,
+                    invoice_number: None,
+                };
+                if let Some(distance) = distance {
+                    if !distance.is_finite() || *distance <= 0.0 {
+                        eprintln!("Distance must be more than 0");
+                        return;
+                    }
+                    let mileage = db
+                        .get_mileage_settings()
+                        .expect("Failed to get mileage settings");
+                    if mileage.rate == Money::ZERO {
+                        eprintln!(
+                            "Set a mileage rate first: meter expenses mileage --rate AMOUNT --unit km"
+                        );
+                        return;
+                    }
+                    expense.kind = ExpenseKind::Mileage;
+                    expense.amount = mileage.rate.times(*distance);
+                    expense.distance = Some(*distance);
+                    expense.unit = Some(mileage.unit);
+                } else {
+                    let amount = amount.unwrap_or_default();
+                    if !amount.is_finite() || amount <= 0.0 {
+                        eprintln!("Amount must be more than 0");
+                        return;
+                    }
+                    expense.amount = Money::from(amount);
+                }
+
+                let id = db.add_expense(&expense).expect("Failed to add expense");
+                println!(
+                    "Added expense {}: {} ({})",
+                    id,
+                    expense.label(),
+                    expense.amount
+                );
+            }
+            ExpensesCommands::List {
+                client,
+                month,
+                uninvoiced,
+            } => {
+                let month = match month.as_deref().map(|m| (m, parse_year_month(m))) {
+                    Some((_, Some(month))) => Some(month),
+                    Some((m, None)) => {
+                        eprintln!("Invalid month '{}' (expected YYYY-MM)", m);
+                        return;
+                    }
+                    None => None,
+                };
+                let expenses: Vec<Expense> = db
+                    .list_expenses()
+                    .expect("Failed to list expenses")
+                    .into_iter()
+                    .filter(|x| client.is_none() || x.client_id == *client)
+                    .filter(|x| {
+                        month.is_none_or(|(year, month)| {
+                            x.date.year() == year && x.date.month() == month
+                        })
+                    })
+                    .filter(|x| !*uninvoiced || x.invoice_number.is_none())
+                    .collect();
+                if expenses.is_empty() {
+                    println!("No expenses found");
+                    return;
+                }
+                let clients = db.list_clients().expect("Failed to list clients");
+                for x in &expenses {
+                    let client = x
+                        .client_id
+                        .and_then(|id| clients.iter().find(|c| c.id == id))
+                        .map(|c| c.name.as_str())
+                        .unwrap_or("-");
+                    let invoiced = x
+                        .invoice_number
+                        .map(|n| format!("invoice #{:04}", n))
+                        .unwrap_or_default();
+                    println!(
+                        "{:>4}  {}  {:>10}  {:<40}  {:<20}  {}",
+                        x.id,
+                        x.date,
+                        x.amount,
+                        x.label(),
+                        client,
+                        invoiced
+                    );
+                }
+                let total: Money = expenses.iter().map(|x| x.amount).sum();
+                let mileage: Money = expenses
+                    .iter()
+                    .filter(|x| x.kind == ExpenseKind::Mileage)
+                    .map(|x| x.amount)
+                    .sum();
+                println!("\nTotal: {} (mileage {})", total, mileage);
+            }
+            ExpensesCommands::Remove { id } => {
+                let attached: Vec<_> = db
+                    .list_attachments()
+                    .expect("Failed to list attachments")
+                    .into_iter()
+                    .filter(|a| a.expense_id == Some(*id))
+                    .collect();
+                if db.delete_expense(*id).expect("Failed to delete expense") {
+                    for attachment in &attached {
+                        let _ = fs::remove_file(&attachment.path);
+                    }
+                    println!("Deleted expense {}", id);
+                } else {
+                    eprintln!("Expense {} not found", id);
+                }
+            }
+            ExpensesCommands::Mileage { rate, unit } => {
+                let mut settings = db
+                    .get_mileage_settings()
+                    .expect("Failed to get mileage settings");
+
+                if rate.is_some() || unit.is_some() {
+                    if let Some(rate) = rate {
+                        if let Err(e) = validate_rate(*rate) {
+                            eprintln!("{}", e);
+                            return;
+                        }
+                        settings.rate = Money::from(*rate);
+                    }
+                    if let Some(unit) = unit {
+                        settings.unit = *unit;
+                    }
+                    db.set_mileage_settings(&settings)
+                        .expect("Failed to update mileage settings");
+                    println!("Mileage settings updated");
+                }
+
+                println!("\nMileage Settings:");
+                println!("  Rate: {} per {}", settings.rate, settings.unit);
+            }
+        },
+        Commands::Attachments(cmd) => match cmd {
+            AttachmentsCommands::Add {
+                file,
+                expense,
+                invoice,
+            } => {
+                if let Some(id) = expense {
+                    let expenses = db.list_expenses().expect("Failed to list expenses");
+                    if !expenses.iter().any(|x| x.id == *id) {
+                        eprintln!("Expense {} not found", id);
+                        return;
+                    }
+                }
+                if let Some(number) = invoice {
+                    let invoices = db.list_invoices().expect("Failed to list invoices");
+                    if !invoices.iter().any(|i| i.invoice_number == *number) {
+                        eprintln!("Invoice #{} not found", number);
+                        return;
+                    }
+                }
+                let (name, path) = match attachments::store(std::path::Path::new(file)) {
+                    Ok(stored) => stored,
+                    Err(e) => {
+                        eprintln!("Failed to store {}: {}", file, e);
+                        return;
+                    }
+                };
+                let id = db
+                    .add_attachment(*expense, *invoice, &name, &path.to_string_lossy())
+                    .expect("Failed to add attachment");
+                let owner = match (expense, invoice) {
+                    (Some(id), _) => format!("expense {}", id),
+                    (None, Some(number)) => format!("invoice #{}", number),
+                    (None, None) => unreachable!("clap requires --expense or --invoice"),
+                };
+                println!("Attached {} to {} (attachment {})", name, owner, id);
+            }
+            AttachmentsCommands::List { expense, invoice } => {
+                let list = match invoice {
+                    Some(number) => db
+                        .invoice_attachments(*number)
+                        .expect("Failed to list attachments"),
+                    None => db
+                        .list_attachments()
+                        .expect("Failed to list attachments")
+                        .into_iter()
+                        .filter(|a| expense.is_none() || a.expense_id == *expense)
+                        .collect(),
+                };
+                if list.is_empty() {
+                    println!("No attachments found");
+                    return;
+                }
+                println!("{:>4}  {:<12} {:<14} Name", "ID", "Added", "For");
+                for attachment in &list {
+                    let missing = if attachment.file_missing() {
+                        " (MISSING)"
+                    } else {
+                        ""
+                    };
+                    println!(
+                        "{:>4}  {:<12} {:<14} {}{}",
+                        attachment.id,
+                        attachment.added_at.with_timezone(&Local).format("%Y-%m-%d"),
+                        attachment.owner(),
+                        attachment.name,
+                        missing
+                    );
+                }
+            }
+            AttachmentsCommands::Open { id } => {
+                let Some(attachment) = db.get_attachment(*id).expect("Failed to get attachment")
+                else {
+                    eprintln!("Attachment {} not found", id);
+                    return;
+                };
+                if attachment.file_missing() {
+                    eprintln!("Stored file {} is missing", attachment.path);
+                    return;
+                }
+                if let Err(e) = attachments::open(std::path::Path::new(&attachment.path)) {
+                    eprintln!("Failed to open {}: {}", attachment.name, e);
+                }
+            }
+            AttachmentsCommands::Remove { id } => {
+                let Some(attachment) = db.get_attachment(*id).expect("Failed to get attachment")
+                else {
+                    eprintln!("Attachment {} not found", id);
+                    return;
+                };
+                db.delete_attachment(*id)
+                    .expect("Failed to delete attachment");
+                let _ = fs::remove_file(&attachment.path);
+                println!("Deleted attachment {} ({})", id, attachment.name);
+            }
+        },
+        Commands::Sync(cmd) => match cmd {
+            SyncCommands::Settings {
+                service,
+                account_id,
+                token,
+            } => {
+                let mut settings = db.get_sync_settings().expect("Failed to get sync settings");
+
+                if service.is_some() || account_id.is_some() {
+                    if let Some(v) = service {
+                        settings.service = *v;
+                    }
+                    if let Some(v) = account_id {
+                        settings.account_id = v.trim().to_string();
+                    }
+                    db.set_sync_settings(&settings)
+                        .expect("Failed to update sync settings");
+                    println!("Sync settings updated");
+                }
+                if let Some(token) = token {
+                    if !settings.is_configured() {
+                        eprintln!("Set --account-id before storing a token");
+                        return;
+                    }
+                    let token = if token == "-" {
+                        let mut line = String::new();
+                        io::stdin()
+                            .read_line(&mut line)
+                            .expect("Failed to read token");
+                        line
+                    } else {
+                        token.clone()
+                    };
+                    if let Err(e) = sync::set_token(&settings, token.trim()) {
+                        eprintln!("Failed to store token: {}", e);
+                        return;
+                    }
+                    println!("Token updated");
+                }
+
+                let has_token = sync::token(&settings).ok().flatten().is_some();
+                println!("\nSync Settings:");
+                println!("  Service:      {}", settings.service);
+                println!(
+                    "  {:<13} {}",
+                    format!("{}:", settings.service.account_label()),
+                    if settings.is_configured() {
+                        settings.account_id.as_str()
+                    } else {
+                        "(not set)"
+                    }
+                );
+                println!(
+                    "  Token:        {}",
+                    if has_token { "stored" } else { "(not set)" }
+                );
+                let mappings = db
+                    .sync_mappings(settings.service)
+                    .expect("Failed to list mapped projects");
+                if !mappings.is_empty() {
+                    println!("  Projects:");
+                }
+                for m in &mappings {
+                    println!(
+                        "    {:<20} -> project {}{}",
+                        m.project,
+                        m.remote_project,
+                        m.remote_task
+                            .map(|t| format!(", task {}", t))
+                            .unwrap_or_default()
+                    );
+                }
+            }
+            SyncCommands::Map {
+                project,
+                remote_project,
+                task,
+            } => {
+                let settings = db.get_sync_settings().expect("Failed to get sync settings");
+                if settings.service == SyncService::Harvest && task.is_none() {
+                    eprintln!("Harvest entries need a task: add --task ID");
+                    return;
+                }
+                db.map_sync_project(settings.service, project, *remote_project, *task)
+                    .expect("Failed to map project");
+                println!(
+                    "Entries of '{}' from now on will be pushed to {} project {}",
+                    project, settings.service, remote_project
+                );
+            }
+            SyncCommands::Unmap { project } => {
+                let settings = db.get_sync_settings().expect("Failed to get sync settings");
+                if db
+                    .unmap_sync_project(settings.service, project)
+                    .expect("Failed to unmap project")
+                {
+                    println!("'{}' is no longer pushed to {}", project, settings.service);
+                } else {
+                    eprintln!("'{}' is not mapped for {}", project, settings.service);
+                }
+            }
+            SyncCommands::Push { since, dry_run } => {
+                let settings = db.get_sync_settings().expect("Failed to get sync settings");
+                if !settings.is_configured() {
+                    eprintln!(
+                        "Sync is not set up: meter sync settings --service {} --account-id ID --token TOKEN",
+                        settings.service
+                    );
+                    return;
+                }
+                let since = match since.as_deref().map(parse_date_range) {
+                    Some(Ok((day, _))) => Local
+                        .from_local_datetime(&day.and_time(NaiveTime::MIN))
+                        .earliest()
+                        .map(|dt| dt.with_timezone(&Utc)),
+                    Some(Err(e)) => {
+                        eprintln!("{}", e);
+                        return;
+                    }
+                    None => None,
+                };
+                let pending = db
+                    .unpushed_entries(settings.service, since)
+                    .expect("Failed to list entries");
+                if pending.is_empty() {
+                    println!("Nothing to push");
+                    return;
+                }
+                for (e, _) in &pending {
+                    println!(
+                        "[{}] {} | {} | {} | {}",
+                        e.id,
+                        e.start.with_timezone(&Local).format("%Y-%m-%d %H:%M"),
+                        e.project,
+                        e.description,
+                        format_short_duration((e.end.unwrap_or(e.start) - e.start).num_seconds())
+                    );
+                }
+                if *dry_run {
+                    println!(
+                        "Would push {} entries to {} (dry run, nothing sent)",
+                        pending.len(),
+                        settings.service
+                    );
+                    return;
+                }
+
+                let token = match sync::token(&settings) {
+                    Ok(Some(token)) => token,
+                    Ok(None) => {
+                        eprintln!("No API token stored: meter sync settings --token TOKEN");
+                        return;
+                    }
+                    Err(e) => {
+                        eprintln!("Failed to read token: {}", e);
+                        return;
+                    }
+                };
+                // Record each push as it happens, so a failure part way doesn't
+                // send the earlier entries twice next time
+                let mut pushed = 0;
+                for (entry, mapping) in &pending {
+                    match sync::push(&settings, &token, entry, mapping) {
+                        Ok(remote_id) => {
+                            db.record_push(settings.service, entry.id, remote_id)
+                                .expect("Failed to record push");
+                            pushed += 1;
+                        }
+                        Err(e) => {
+                            eprintln!("{}", e);
+                            break;
+                        }
+                    }
+                }
+                println!(
+                    "Pushed {} of {} entries to {}",
+                    pushed,
+                    pending.len(),
+                    settings.service
+                );
+            }
+        },
+        Commands::Mail(cmd) => match cmd {
+            MailCommands::Settings {
+                host,
+                port,
+                username,
+                mailbox,
+                enabled,
+                password,
+            } => {
+                let mut settings = db
+                    .get_inbox_settings()
+                    .expect("Failed to get inbox settings");
+                let account = (
+                    settings.imap_host.clone(),
+                    settings.username.clone(),
+                    settings.mailbox.clone(),
+                );
+                if let Some(v) = host {
+                    settings.imap_host = v.trim().to_string();
+                }
+                if let Some(v) = port {
+                    settings.imap_port = *v;
+                }
+                if let Some(v) = username {
+                    settings.username = v.trim().to_string();
+                }
+                if let Some(v) = mailbox {
+                    settings.mailbox = v.trim().to_string();
+                }
+                if let Some(v) = enabled {
+                    settings.enabled = *v;
+                }
+                if host.is_some()
+                    || port.is_some()
+                    || username.is_some()
+                    || mailbox.is_some()
+                    || enabled.is_some()
+                {
+                    // UIDs only mean something within one mailbox
+                    if account
+                        != (
+                            settings.imap_host.clone(),
+                            settings.username.clone(),
+                            settings.mailbox.clone(),
+                        )
+                    {
+                        settings.last_uid = 0;
+                    }
+                    db.set_inbox_settings(&settings)
+                        .expect("Failed to update inbox settings");
+                    println!("Inbox settings updated");
+                }
+                if let Some(password) = password {
+                    if !settings.is_configured() {
+                        eprintln!("Set --host and --username before storing a password");
+                        return;
+                    }
+                    let password = if password == "-" {
+                        let mut line = String::new();
+                        io::stdin()
+                            .read_line(&mut line)
+                            .expect("Failed to read password");
+                        line
+                    } else {
+                        password.clone()
+                    };
+                    if let Err(e) = inbox::set_imap_password(&settings, password.trim()) {
+                        eprintln!("Failed to store password: {}", e);
+                        return;
+                    }
+                    println!("Password updated");
+                }
+
+                let has_password = inbox::imap_password(&settings).ok().flatten().is_some();
+                let or_unset = |v: &str| {
+                    if v.is_empty() {
+                        "(not set)".to_string()
+                    } else {
+                        v.to_string()
+                    }
+                };
+                println!("\nInbox Settings:");
+                println!(
+                    "  Watcher:   {}",
+                    if settings.enabled { "on" } else { "off" }
+                );
+                println!("  Host:      {}", or_unset(&settings.imap_host));
+                println!("  Port:      {}", settings.imap_port);
+                println!("  Username:  {}", or_unset(&settings.username));
+                println!("  Mailbox:   {}", settings.mailbox);
+                println!(
+                    "  Password:  {}",
+                    if has_password { "stored" } else { "(not set)" }
+                );
+            }
+            MailCommands::AddRule { pattern, action } => {
+                let pattern = pattern.trim();
+                if pattern.is_empty() {
+                    eprintln!("The pattern can't be empty");
+                    return;
+                }
+                let id = db
+                    .add_mail_rule(pattern, *action)
+                    .expect("Failed to add mail rule");
+                println!(
+                    "Rule {}: emails containing '{}' mark the invoice they name {}",
+                    id, pattern, action
+                );
+            }
+            MailCommands::Rules => {
+                let rules = db.list_mail_rules().expect("Failed to list mail rules");
+                if rules.is_empty() {
+                    println!("No mail rules");
+                    return;
+                }
+                for r in &rules {
+                    println!("{:>4}  {:<14} {}", r.id, r.action, r.pattern);
+                }
+            }
+            MailCommands::RemoveRule { id } => {
+                if db
+                    .delete_mail_rule(*id)
+                    .expect("Failed to delete mail rule")
+                {
+                    println!("Rule {} deleted", id);
+                } else {
+                    eprintln!("Rule {} not found", id);
+                }
+            }
+            MailCommands::Check => match check_inbox(&db) {
+                Ok(matches) if matches.is_empty() => println!("No new emails about invoices"),
+                Ok(matches) => print_mail_matches(&matches),
+                Err(e) => eprintln!("{}", e),
+            },
+            MailCommands::Watch { interval } => {
+                let interval = std::time::Duration::from_secs((*interval).max(1) * 60);
+                println!("Watching for emails about invoices; press Ctrl+C to stop");
+                loop {
+                    match check_inbox(&db) {
+                        Ok(matches) => print_mail_matches(&matches),
+                        Err(e) => eprintln!("{}", e),
+                    }
+                    thread::sleep(interval);
+                }
+            }
+            MailCommands::Log => {
+                let matches = db.list_mail_matches().expect("Failed to list mail matches");
+                if matches.is_empty() {
+                    println!("No emails matched to invoices yet");
+                    return;
+                }
+                print_mail_matches(&matches);
+            }
+        },
+        Commands::Payments(cmd) => match cmd {
+            PaymentsCommands::Settings { stripe_key } => {
+                if let Some(key) = stripe_key {
+                    let key = if key == "-" {
+                        let mut line = String::new();
+                        io::stdin()
+                            .read_line(&mut line)
+                            .expect("Failed to read key");
+                        line
+                    } else {
+                        key.clone()
+                    };
+                    if let Err(e) = payments::set_stripe_key(key.trim()) {
+                        eprintln!("Failed to store key: {}", e);
+                        return;
+                    }
+                    println!("Stripe key updated");
+                }
+                let has_key = payments::stripe_key().ok().flatten().is_some();
+                println!("\nPayment Settings:");
+                println!(
+                    "  Stripe key:  {}",
+                    if has_key { "stored" } else { "(not set)" }
+                );
+            }
+            PaymentsCommands::Sync { stripe, since } => {
+                if !*stripe {
+                    eprintln!("Choose where to read payments from: --stripe");
+                    return;
+                }
+                let key = match payments::stripe_key() {
+                    Ok(Some(key)) => key,
+                    Ok(None) => {
+                        eprintln!("No Stripe key stored: meter payments settings --stripe-key -");
+                        return;
+                    }
+                    Err(e) => {
+                        eprintln!("Failed to read Stripe key: {}", e);
+                        return;
+                    }
+                };
+                let since = match since.as_deref().map(parse_date_range) {
+                    Some(Ok((day, _))) => Local
+                        .from_local_datetime(&day.and_time(NaiveTime::MIN))
+                        .earliest()
+                        .map(|dt| dt.with_timezone(&Utc))
+                        .unwrap_or_else(Utc::now),
+                    Some(Err(e)) => {
+                        eprintln!("{}", e);
+                        return;
+                    }
+                    None => Utc::now() - Duration::days(90),
+                };
+                let charges = match payments::fetch_stripe(&key, since) {
+                    Ok(charges) => charges,
+                    Err(e) => {
+                        eprintln!("{}", e);
+                        return;
+                    }
+                };
+
+                let recorded = db.list_payments().expect("Failed to list payments");
+                let invoices = db.list_invoices().expect("Failed to list invoices");
+                // Invoices already paid, by an earlier payment or an email
+                let mut settled: Vec<i64> =
+                    recorded.iter().filter_map(|p| p.invoice_number).collect();
+                settled.extend(
+                    db.list_mail_matches()
+                        .expect("Failed to list mail matches")
+                        .iter()
+                        .filter(|m| m.action == MailAction::Paid)
+                        .map(|m| m.invoice_number),
+                );
+                let mut matched = 0;
+                let mut unmatched = 0;
+                for charge in charges.iter().filter(|c| {
+                    !recorded
+                        .iter()
+                        .any(|p| p.provider == "stripe" && p.external_id == c.id)
+                }) {
+                    let invoice_number = payments::match_invoice(charge, &invoices, &settled);
+                    let date = charge.created.with_timezone(&Local).date_naive();
+                    // The fee is a cost of the business, not billed to anyone
+                    let expense_id = (charge.fee > Money::ZERO).then(|| {
+                        db.add_expense(&Expense {
+                            id: 0,
+                            date,
+                            kind: ExpenseKind::Expense,
+                            description: match invoice_number {
+                                Some(n) => format!("Stripe fee, invoice #{}", n),
+                                None => format!("Stripe fee ({})", charge.id),
+                            },
+                            amount: charge.fee,
+                            distance: None,
+                            unit: None,
+                            entry_id: None,
+                            client_id: None,
+                            invoice_number: None,
+                        })
+                        .expect("Failed to record fee")
+                    });
+                    db.record_payment(&Payment {
+                        id: 0,
+                        provider: "stripe".to_string(),
+                        external_id: charge.id.clone(),
+                        invoice_number,
+                        amount: charge.amount,
+                        fee: charge.fee,
+                        currency: charge.currency.clone(),
+                        paid_at: charge.created,
+                        expense_id,
+                    })
+                    .expect("Failed to record payment");
+
+                    let invoice = invoice_number
+                        .and_then(|n| invoices.iter().find(|i| i.invoice_number == n));
+                    match invoice {
+                        Some(invoice) => {
+                            matched += 1;
+                            // Only part of it is paid yet, so it stays open
+                            let paid_so_far = db
+                                .invoice_paid_amount(invoice.invoice_number)
+                                .expect("Failed to sum payments");
+                            if paid_so_far < invoice.total {
+                                println!(
+                                    "{}  {} {}  invoice #{} partly paid, {} of {}",
+                                    date,
+                                    charge.amount,
+                                    charge.currency,
+                                    invoice.invoice_number,
+                                    paid_so_far,
+                                    invoice.total
+                                );
+                                continue;
+                            }
+                            let paid = mark_invoice_paid(&db, invoice)
+                                .expect("Failed to mark entries paid");
+                            settled.push(invoice.invoice_number);
+                            println!(
+                                "{}  {} {}  invoice #{} paid, {} entries marked paid",
+                                date,
+                                charge.amount,
+                                charge.currency,
+                                invoice.invoice_number,
+                                paid.len()
+                            );
+                        }
+                        None => {
+                            unmatched += 1;
+                            println!(
+                                "{}  {} {}  no matching invoice: {}",
+                                date, charge.amount, charge.currency, charge.description
+                            );
+                        }
+                    }
+                }
+                if matched + unmatched == 0 {
+                    println!(
+                        "No new payments since {}",
+                        since.with_timezone(&Local).date_naive()
+                    );
+                } else {
+                    println!(
+                        "\n{} payments matched to invoices, {} unmatched",
+                        matched, unmatched
+                    );
+                }
+            }
+            PaymentsCommands::List => {
+                let list = db.list_payments().expect("Failed to list payments");
+                if list.is_empty() {
+                    println!("No payments recorded");
+                    return;
+                }
+                println!(
+                    "{:<12} {:<8} {:>12} {:>10}  Invoice",
+                    "Date", "Source", "Amount", "Fee"
+                );
+                for p in &list {
+                    println!(
+                        "{:<12} {:<8} {:>12} {:>10}  {}",
+                        p.paid_at.with_timezone(&Local).format("%Y-%m-%d"),
+                        p.provider,
+                        format!("{} {}", p.amount, p.currency),
+                        p.fee,
+                        p.invoice_number
+                            .map(|n| format!("#{}", n))
+                            .unwrap_or_else(|| "(unmatched)".to_string())
+                    );
+                }
+            }
+        },
+        Commands::Dunning(cmd) => match cmd {
+            DunningCommands::Schedule { days, email } => {
+                let mut settings = db
+                    .get_dunning_settings()
+                    .expect("Failed to get reminder schedule");
+                if days.is_some() || email.is_some() {
+                    if let Some(days) = days {
+                        match parse_dunning_schedule(days) {
+                            Ok(days) => settings.schedule = days,
+                            Err(e) => {
+                                eprintln!("{}", e);
+                                return;
+                            }
+                        }
+                    }
+                    if let Some(email) = email {
+                        settings.email = *email;
+                    }
+                    db.set_dunning_settings(&settings)
+                        .expect("Failed to update reminder schedule");
+                    println!("Reminder schedule updated");
+                }
+                println!("\nLate-Payment Reminders:");
+                println!("  Schedule: {}", settings.schedule_summary());
+                println!(
+                    "  Email:    {}",
+                    if settings.email {
+                        "To the client, and a notification"
+                    } else {
+                        "Off (notification only)"
+                    }
+                );
+            }
+            DunningCommands::Run => {
+                let sent = dunning::send_due(&db, Local::now().date_naive())
+                    .expect("Failed to send reminders");
+                if sent.is_empty() {
+                    println!("No reminders due");
+                }
+                for s in &sent {
+                    match &s.email {
+                        Some(Ok(to)) => {
+                            println!("{}; reminder emailed to {}", s.reminder.message(), to)
+                        }
+                        Some(Err(e)) => {
+                            println!("{}; reminder not emailed: {}", s.reminder.message(), e)
+                        }
+                        None => println!("{}", s.reminder.message()),
+                    }
+                }
+            }
+            DunningCommands::Log { number } => {
+                let log = db.dunning_log(*number).expect("Failed to list reminders");
+                if log.is_empty() {
+                    println!("No reminders sent");
+                    return;
+                }
+                println!("{:<17} {:<9} {:>5}  Emailed", "Sent", "Invoice", "Step");
+                for entry in &log {
+                    println!(
+                        "{:<17} {:<9} {:>5}  {}",
+                        entry.sent_at.with_timezone(&Local).format("%Y-%m-%d %H:%M"),
+                        format!("#{:04}", entry.invoice_number),
+                        format!("+{}", entry.step),
+                        match (&entry.emailed_to, &entry.error) {
+                            (Some(to), _) => to.clone(),
+                            (None, Some(e)) => format!("failed: {}", e),
+                            (None, None) => "-".to_string(),
+                        }
+                    );
+                }
+            }
+        },
+        Commands::Reconcile {
+            ical: source,
+            dates,
+            min_coverage,
+            all,
+        } => {
+            let (start, end) = match dates.as_deref().map(parse_date_range) {
+                Some(Ok(range)) => range,
+                Some(Err(e)) => {
+                    eprintln!("{}", e);
+                    return;
+                }
+                None => {
+                    let today = Local::now().date_naive();
+                    (today - Duration::weeks(4) + Duration::days(1), today)
+                }
+            };
+            let data = match ical::load(source) {
+                Ok(data) => data,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    return;
+                }
+            };
+            // Meetings that haven't started yet can't have been tracked
+            let now = Utc::now();
+            let events: Vec<_> = match ical::parse(&data, now) {
+                Ok(events) => events,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    return;
+                }
+            };
+            let events: Vec<_> = events
+                .into_iter()
+                .filter(|e| (start..=end).contains(&e.start.with_timezone(&Local).date_naive()))
+                .collect();
+            let entries = db.list(None).expect("Failed to list entries");
+            let rows = report::reconcile(&events, &entries, now);
+            if rows.is_empty() {
+                println!("No meetings in the calendar from {} to {}", start, end);
+                return;
+            }
+
+            let coverage = *min_coverage as f64 / 100.0;
+            let untracked: Vec<_> = rows.iter().filter(|r| r.is_untracked(coverage)).collect();
+            let missing_secs: i64 = untracked
+                .iter()
+                .map(|r| r.event_secs() - r.tracked_secs)
+                .sum();
+            println!(
+                "{} meetings from {} to {}, {} without tracked time ({} untracked)",
+                rows.len(),
+                start,
+                end,
+                untracked.len(),
+                format_short_duration(missing_secs)
+            );
+            let listed: Vec<_> = if *all {
+                rows.iter().collect()
+            } else {
+                untracked
+            };
+            if listed.is_empty() {
+                return;
+            }
+            println!(
+                "\n{:<10}  {:<11}  {:>6}  {:>7}  Event",
+                "Date", "Time", "Length", "Tracked"
+            );
+            for row in listed {
+                let from = row.event.start.with_timezone(&Local);
+                let to = row.event.end.with_timezone(&Local);
+                let tracked = if row.tracked_secs > 0 {
+                    format_short_duration(row.tracked_secs)
+                } else {
+                    "-".to_string()
+                };
+                let mut event = row.event.summary.clone();
+                if !row.projects.is_empty() {
+                    event.push_str(&format!(" ({})", row.projects.join(", ")));
+                }
+                println!(
+                    "{:<10}  {:<11}  {:>6}  {:>7}  {}{}",
+                    from.format("%Y-%m-%d"),
+                    format!("{}-{}", from.format("%H:%M"), to.format("%H:%M")),
+                    format_short_duration(row.event_secs()),
+                    tracked,
+                    event,
+                    if *all && row.is_untracked(coverage) {
+                        "  <- missing"
+                    } else {
+                        ""
+                    }
+                );
+            }
+        }
+        Commands::Report(cmd) => match cmd {
+            ReportCommands::Forecast { months, history } => {
+                let entries = db.list(None).expect("Failed to list entries");
+                let projects = db.list_projects().expect("Failed to list projects");
+                let clients = db.list_clients().expect("Failed to list clients");
+                let invoices = db.list_invoices().expect("Failed to list invoices");
+                let today = Local::now().date_naive();
+                let rows = report::forecast(
+                    &entries, &projects, &clients, &invoices, today, *months, *history,
+                );
+                if rows.is_empty() {
+                    println!("Nothing to forecast: no unbilled work, fixed fees or invoices");
+                    return;
+                }
+
+                println!(
+                    "Income forecast for the next {} month(s), before tax",
+                    months
+                );
+                println!(
+                    "{:<24} {:>9} {:>14} {:>14} {:>14} {:>14}",
+                    "Client", "Unbilled", "Unbilled amt", "Fixed/month", "Avg/month", "Forecast"
+                );
+                println!("{}", "-".repeat(94));
+                for row in &rows {
+                    println!(
+                        "{:<24} {:>8.2}h {:>14} {:>14} {:>14} {:>14}",
+                        row.client.as_deref().unwrap_or("(no client)"),
+                        row.unbilled_hours,
+                        format_money(row.unbilled, &row.currency),
+                        format_money(row.recurring, &row.currency),
+                        format_money(row.monthly_average, &row.currency),
+                        format_money(row.forecast, &row.currency),
+                    );
+                }
+                let mut totals: Vec<(&str, Money)> = Vec::new();
+                for row in &rows {
+                    match totals.iter_mut().find(|(c, _)| *c == row.currency) {
+                        Some((_, total)) => *total += row.forecast,
+                        None => totals.push((&row.currency, row.forecast)),
+                    }
+                }
+                println!("{}", "-".repeat(94));
+                for (currency, total) in totals {
+                    println!("{:<24} {:>69}", "Total", format_money(total, currency));
+                }
+                println!(
+                    "\nAverages cover the {} complete month(s) before this one; voided invoices are left out.",
+                    history
+                );
+            }
+            ReportCommands::Heatmap { dates, project } => {
+                let (start, end) = match dates.as_deref().map(parse_date_range) {
+                    Some(Ok(range)) => range,
+                    Some(Err(e)) => {
+                        eprintln!("{}", e);
+                        return;
+                    }
+                    None => {
+                        let today = Local::now().date_naive();
+                        (today - Duration::weeks(12) + Duration::days(1), today)
+                    }
+                };
+                let entries = db.list(None).expect("Failed to list entries");
+                let entries: Vec<&Entry> = entries
+                    .iter()
+                    .filter(|e| {
+                        project.as_deref().is_none_or(|p| e.project == p)
+                            && (start..=end).contains(&e.start.with_timezone(&Local).date_naive())
+                    })
+                    .collect();
+                let grid = report::hour_heatmap(&entries);
+                let max = grid.iter().flatten().copied().fold(0.0, f64::max);
+                if max <= 0.0 {
+                    println!("No timed entries from {} to {}", start, end);
+                    return;
+                }
+
+                println!("Hours tracked by time of day, {} to {}", start, end);
+                let header: String = (0..24)
+                    .map(|h| {
+                        if h % 3 == 0 {
+                            format!("{:<6}", h)
+                        } else {
+                            String::new()
+                        }
+                    })
+                    .collect();
+                println!("     {}{:>8}", header, "Hours");
+                for (day, hours) in ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"]
+                    .iter()
+                    .zip(&grid)
+                {
+                    let cells: String = hours
+                        .iter()
+                        .map(|h| report::heat_shade(*h, max).to_string().repeat(2))
+                        .collect();
+                    println!("{}  {}  {:>6.1}", day, cells, hours.iter().sum::<f64>());
+                }
+                println!("\nEach column is an hour; the darkest cell is {:.1}h.", max);
+            }
+            ReportCommands::Flagged { all } => {
+                let flags = db.review_flags().expect("Failed to get review flags");
+                let mut entries = db.list(None).expect("Failed to list entries");
+                entries.retain(|e| {
+                    flags.contains_key(&e.id)
+                        && (*all || !matches!(e.status, EntryStatus::Invoiced | EntryStatus::Paid))
+                });
+                if entries.is_empty() {
+                    println!("No entries flagged for review");
+                    return;
+                }
+                entries.reverse();
+
+                let mut counts: Vec<(ReviewReason, usize)> = Vec::new();
+                for e in &entries {
+                    let length = match (e.days, e.end) {
+                        (Some(days), _) => format!("{} days", format_days(days)),
+                        (None, Some(end)) => format_short_duration((end - e.start).num_seconds()),
+                        (None, None) => "running".to_string(),
+                    };
+                    println!(
+                        "[{}] {} | {} | {} | {} | {} | {}",
+                        e.id,
+                        e.project,
+                        e.description_with_issue(),
+                        e.start.with_timezone(&Local).format("%Y-%m-%d %H:%M"),
+                        length,
+                        e.status,
+                        describe_review_reasons(&flags[&e.id])
+                    );
+                    for reason in &flags[&e.id] {
+                        match counts.iter_mut().find(|(r, _)| r == reason) {
+                            Some((_, n)) => *n += 1,
+                            None => counts.push((*reason, 1)),
+                        }
+                    }
+                }
+                counts.sort();
+                let counts: Vec<String> = counts
+                    .iter()
+                    .map(|(reason, n)| format!("{} {}", n, reason.describe()))
+                    .collect();
+                println!(
+                    "\n{} entries flagged for review ({}). Check them, then clear each with \
+                     `meter flag <id> --clear`.",
+                    entries.len(),
+                    counts.join(", ")
+                );
+            }
+            ReportCommands::Areas { dates } => {
+                let (start, end) = match dates.as_deref().map(parse_date_range) {
+                    Some(Ok(range)) => range,
+                    Some(Err(e)) => {
+                        eprintln!("{}", e);
+                        return;
+                    }
+                    None => {
+                        let today = Local::now().date_naive();
+                        (today.with_day(1).unwrap_or(today), today)
+                    }
+                };
+                let entries = db.list(None).expect("Failed to list entries");
+                let entries: Vec<&Entry> = entries
+                    .iter()
+                    .filter(|e| (start..=end).contains(&e.start.with_timezone(&Local).date_naive()))
+                    .collect();
+                let projects = db.list_projects().expect("Failed to list projects");
+                let areas =
+                    report::area_hours(&report::project_hours(&entries, Utc::now()), &projects);
+                if areas.is_empty() {
+                    println!("No timed entries from {} to {}", start, end);
+                    return;
+                }
+
+                let total: f64 = areas.iter().map(|a| a.hours).sum();
+                println!("Hours by area, {} to {}\n", start, end);
+                for area in &areas {
+                    println!(
+                        "{:<32} {:>8.1}h {:>4.0}%",
+                        area.area.as_deref().unwrap_or("No area"),
+                        area.hours,
+                        area.hours / total * 100.0
+                    );
+                    for (project, hours) in &area.projects {
+                        println!("  {:<30} {:>8.1}h", project, hours);
+                    }
+                }
+                println!("\n{:<32} {:>8.1}h", "Total", total);
+            }
+        },
+        Commands::Doctor => {
+            let invoices = db.list_invoices().expect("Failed to list invoices");
+            let missing: Vec<&models::Invoice> =
+                invoices.iter().filter(|i| i.file_missing()).collect();
+            let orphans = orphan_invoice_files(&invoices).unwrap_or_default();
+            if missing.is_empty() && orphans.is_empty() {
+                println!("No problems found");
+                return;
+            }
+            for inv in &missing {
+                println!(
+                    "Invoice #{}: PDF missing at {} (fix: meter invoices regenerate {})",
+                    inv.invoice_number, inv.file_path, inv.invoice_number
+                );
+            }
+            if !orphans.is_empty() {
+                println!(
+                    "{} PDF(s) in the invoice directory belong to no invoice (fix: meter invoices clean)",
+                    orphans.len()
+                );
+            }
+        }
+    }
+}
+
+/// The pid of the running menu bar app, which holds an exclusive lock on its
+/// pid file for as long as it runs
+#[cfg(target_os = "macos")]
+fn menubar_pid() -> Option<u32> {
+    use std::os::fd::AsRawFd;
+
+    let path = paths::menubar_pid_file();
+    let file = fs::File::open(&path).ok()?;
+    // A shared lock is only refused while the app holds its exclusive one
+    if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_SH | libc::LOCK_NB) } == 0 {
+        return None;
+    }
+    fs::read_to_string(&path).ok()?.trim().parse().ok()
+}
+
+/// The menu bar app only runs on macOS
+#[cfg(not(target_os = "macos"))]
+fn menubar_pid() -> Option<u32> {
+    None
+}
+
+/// Ask process `pid` to quit
+#[cfg(target_os = "macos")]
+fn stop_process(pid: u32) -> bool {
+    unsafe { libc::kill(pid as libc::pid_t, libc::SIGTERM) == 0 }
+}
+
+#[cfg(not(target_os = "macos"))]
+fn stop_process(_pid: u32) -> bool {
+    false
+}
+
+/// Exit with a plain message when another meter process kept the database
+/// locked through every retry; any other error panics as usual
+fn exit_on_db_error(e: rusqlite::Error, action: &str) -> ! {
+    if db::is_busy(&e) {
+        eprintln!(
+            "The database is busy: another meter process (the menu bar app or the TUI) kept it locked. Try again in a moment."
+        );
+        process::exit(1);
+    }
+    panic!("Failed to {}: {}", action, e);
+}
+
+/// Run one statement that only reads and print its rows as a table, or as CSV.
+/// Statements that would write are refused before they run, on top of the
+/// connection being read-only.
+fn run_sql(db: &Db, sql: &str, csv: bool) -> Result<(), String> {
+    let mut stmt = db.conn().prepare(sql).map_err(|e| e.to_string())?;
+    if !stmt.readonly() {
+        return Err("Only statements that read are allowed, such as SELECT".to_string());
+    }
+    let columns: Vec<String> = stmt.column_names().iter().map(|c| c.to_string()).collect();
+    let mut rows: Vec<Vec<String>> = Vec::new();
+    let mut result = stmt.query([]).map_err(|e| e.to_string())?;
+    while let Some(row) = result.next().map_err(|e| e.to_string())? {
+        let mut values = Vec::new();
+        for i in 0..columns.len() {
+            let value = match row.get_ref(i).map_err(|e| e.to_string())? {
+                rusqlite::types::ValueRef::Null => String::new(),
+                rusqlite::types::ValueRef::Integer(n) => n.to_string(),
+                rusqlite::types::ValueRef::Real(x) => x.to_string(),
+                rusqlite::types::ValueRef::Text(t) => String::from_utf8_lossy(t).into_owned(),
+                rusqlite::types::ValueRef::Blob(b) => format!("<{} bytes>", b.len()),
+            };
+            values.push(value);
+        }
+        rows.push(values);
+    }
+
+    if csv {
+        for line in std::iter::once(&columns).chain(&rows) {
+            let fields: Vec<String> = line.iter().map(|f| approval::csv_field(f)).collect();
+            println!("{}", fields.join(","));
+        }
+        return Ok(());
+    }
+    let mut widths: Vec<usize> = columns.iter().map(|c| c.chars().count()).collect();
+    for row in &rows {
+        for (width, value) in widths.iter_mut().zip(row) {
+            *width = (*width).max(value.chars().count());
+        }
+    }
+    let print_row = |values: &[String]| {
+        let cells: Vec<String> = values
+            .iter()
+            .zip(&widths)
+            .map(|(v, w)| format!("{:<w$}", v.replace('\n', " "), w = *w))
+            .collect();
+        println!("{}", cells.join(" | ").trim_end());
+    };
+    print_row(&columns);
+    println!(
+        "{}",
+        widths
+            .iter()
+            .map(|w| "-".repeat(*w))
+            .collect::<Vec<_>>()
+            .join("-+-")
+    );
+    for row in &rows {
+        print_row(row);
+    }
+    println!(
+        "({} row{})",
+        rows.len(),
+        if rows.len() == 1 { "" } else { "s" }
+    );
+    Ok(())
+}
+
+/// The screen to open the TUI on: the one asked for, or the configured default
+fn tui_screen(db: &Db, screen: &Option<Screen>) -> Screen {
+    screen.clone().unwrap_or_else(|| {
+        db.get_tui_settings()
+            .unwrap_or_default()
+            .default_screen
+            .parse()
+            .unwrap_or_default()
+    })
+}
+
+/// Rewrite an invoice's PDF from its stored snapshot and point the record at it.
+/// Returns the new file path.
+fn regenerate_invoice_file(db: &Db, invoice: &models::Invoice) -> Result<String, String> {
+    let snapshot = invoice
+        .snapshot
+        .as_deref()
+        .ok_or("it was issued before snapshots were stored")?;
+    let snapshot = InvoiceSnapshot::from_json(snapshot).map_err(|e| e.to_string())?;
+    let result = regenerate_invoice(&snapshot).map_err(|e| e.to_string())?;
+    db.set_invoice_file_path(invoice.id, &result.file_path)
+        .map_err(|e| e.to_string())?;
+    Ok(result.file_path)
+}
+
+/// Rates for the projects of `entries` that have one
+fn project_rates_for(db: &Db, entries: &[Entry]) -> HashMap<String, ProjectRate> {
+    let mut project_rates: HashMap<String, ProjectRate> = HashMap::new();
+    for entry in entries {
+        if !project_rates.contains_key(&entry.project)
+            && let Ok(Some(proj)) = db.get_project_by_name(&entry.project)
+            && let Some(rate) = proj.rate
+        {
+            project_rates.insert(
+                entry.project.clone(),
+                ProjectRate {
+                    rate,
+                    currency: proj.currency.unwrap_or_else(|| "$".to_string()),
+                    billing_mode: proj.billing_mode,
+                },
+            );
+        }
+    }
+    project_rates
+}
+
+/// Normalize `tags` and add them to entry `id`
+/// Add a finished entry of `hours` ending now, as `meter add` does.
+fn add_timed_entry(
+    db: &Db,
+    project: &str,
+    desc: &str,
+    hours: f64,
+    issue: &Option<String>,
+    location: &Option<String>,
+    tags: &[String],
+) {
+    let now = Utc::now();
+    let entry = Entry {
+        id: 0,
+        project: project.to_string(),
+        description: desc.to_string(),
+        start: now - Duration::seconds((hours * 3600.0).round() as i64),
+        end: Some(now),
+        status: EntryStatus::Open,
+        days: None,
+        issue: issue.clone(),
+        location: location.as_deref().and_then(normalize_location),
+    };
+    let id = db.insert(&entry).expect("Failed to insert entry");
+    tag_entry(db, id, tags);
+    println!(
+        "Added manual entry for project '{}', duration {:.2} hrs",
+        project, hours
+    );
+    let settings = db
+        .get_timer_settings()
+        .expect("Failed to get timer settings");
+    if settings.split_at_midnight {
+        split_and_report(db, id);
+    }
+}
+
+/// Give a just-started entry its issue (from the git branch when none is given
+/// and `timer-settings --git-issues` is on), location and tags.
+fn set_up_started(
+    db: &Db,
+    entry: &mut Entry,
+    issue: &Option<String>,
+    location: &Option<String>,
+    tags: &[String],
+) {
+    entry.issue = issue.clone().or_else(|| {
+        let settings = db
+            .get_timer_settings()
+            .expect("Failed to get timer settings");
+        if settings.issue_from_git_branch {
+            git_branch().and_then(|branch| issue_from_branch(&branch))
+        } else {
+            None
+        }
+    });
+    entry.location = location.as_deref().and_then(normalize_location);
+    if entry.issue.is_some() || entry.location.is_some() {
+        db.update_entry(entry)
+            .expect("Failed to set issue and location");
+    }
+    tag_entry(db, entry.id, tags);
+}
+
+/// Print a started entry's issue, and warn when its project is already at its
+/// hour cap.
+fn report_started(db: &Db, entry: &Entry) {
+    if let Some(issue) = &entry.issue {
+        println!("Issue: {}", issue);
+    }
+    if let Some(usage) = db
+        .project_cap_usage(&entry.project, Utc::now())
+        .expect("Failed to check hour cap")
+        && usage.is_reached()
+    {
+        println!(
+            "Warning: '{}' is already at its hour cap ({})",
+            entry.project,
+            usage.summary()
+        );
+    }
+}
+
+/// Report a just-stopped entry: discard it when it is shorter than the minimum
+/// and `discard_short` or the `discard_short_entries` setting says so, and
+/// split it at midnight when that setting is on.
+fn report_stopped(db: &Db, entry: &Entry, discard_short: bool) {
+    let settings = db
+        .get_timer_settings()
+        .expect("Failed to get timer settings");
+    let secs = entry
+        .end
+        .map(|end| (end - entry.start).num_seconds())
+        .unwrap_or(0);
+    if settings.is_too_short(entry) {
+        if discard_short || settings.discard_short_entries {
+            db.delete(entry.id).expect("Failed to discard entry");
+            println!(
+                "Discarded {}s entry for project '{}' (minimum is {}s)",
+                secs, entry.project, settings.min_duration_secs
+            );
+        } else {
+            println!(
+                "Kept {}s entry for project '{}' (minimum is {}s); remove it with \
+                 `meter delete {}`, or drop entries like this automatically with \
+                 `meter timer-settings --discard-short true`",
+                secs, entry.project, settings.min_duration_secs, entry.id
+            );
+        }
+        return;
+    }
+    println!(
+        "Stopped timer for project '{}', duration {:.2} hrs",
+        entry.project,
+        secs as f64 / 3600.0
+    );
+    if settings.split_at_midnight {
+        split_and_report(db, entry.id);
+    }
+}
+
+fn tag_entry(db: &Db, id: i64, tags: &[String]) {
+    let tags: Vec<String> = tags.iter().filter_map(|t| normalize_tag(t)).collect();
+    if !tags.is_empty() {
+        db.add_entry_tags(id, &tags).expect("Failed to tag entry");
+    }
+}
+
+/// Write a draft's PDF: a preview marked as a draft, or the issued invoice when
+/// `invoice_number` is given. Returns the result with the entries and expenses
+/// on it.
+fn write_draft(
+    db: &Db,
+    draft: &InvoiceDraft,
+    invoice_number: Option<i64>,
+) -> Result<(InvoiceResult, Vec<Entry>, Vec<Expense>), String> {
+    let mut entries = Vec::new();
+    for id in &draft.entry_ids {
+        if let Some(entry) = db.get_entry_by_id(*id).map_err(|e| e.to_string())? {
+            entries.push(entry);
+        }
+    }
+    let expenses = expenses_for_invoice(
+        &db.list_expenses().map_err(|e| e.to_string())?,
+        &entries,
+        draft.client_id,
+        draft.year,
+        draft.month,
+    );
+    let client = match draft.client_id {
+        Some(id) => db.get_client(id).map_err(|e| e.to_string())?,
+        None => None,
+    };
+    let overtime = match draft.client_id {
+        Some(id) => db.get_overtime_rates(id).map_err(|e| e.to_string())?,
+        None => None,
+    };
+    let settings = db
+        .get_profile_settings(draft.profile_id)
+        .map_err(|e| e.to_string())?;
+    let holidays = db.holidays().map_err(|e| e.to_string())?;
+    let project_rates = project_rates_for(db, &entries);
+
+    let result = write_invoice(&InvoiceParams {
+        entries: &entries,
+        rounding_minutes: draft.rounding_minutes,
+        expenses: &expenses,
+        items: &draft.items,
+        project_rates: &project_rates,
+        year: draft.year,
+        month: draft.month,
+        invoice_number: invoice_number.unwrap_or(draft.id),
+        draft: invoice_number.is_none(),
+        supersedes: draft.supersedes,
+        settings: &settings,
+        client: client.as_ref(),
+        overtime: overtime.as_ref(),
+        tax_rate: draft.tax_rate,
+        holidays: &holidays,
+        reissue_dates: None,
+    })
+    .map_err(|e| e.to_string())?;
+    Ok((result, entries, expenses))
+}
+
+/// Write a draft's preview PDF and print its totals
+fn preview_draft(db: &Db, draft: &InvoiceDraft) {
+    match write_draft(db, draft, None) {
+        Ok((result, ..)) => {
+            println!("Preview written to {}", result.file_path);
+            print_invoice_totals(&result);
+            println!(
+                "Edit it with `meter drafts edit {}` or `meter drafts add-item {}`, then `meter drafts finalize {}`",
+                draft.id, draft.id, draft.id
+            );
+        }
+        Err(e) => eprintln!("Failed to write preview of draft {}: {}", draft.id, e),
+    }
+}
+
+/// Delete a draft's preview PDF, if it was written
+fn remove_draft_preview(id: i64) {
+    if let Ok(dir) = get_invoice_dir() {
+        let _ = fs::remove_file(draft_file(&dir, id));
+    }
+}
+
+/// Give `draft` the next invoice number of its profile, write its PDF and
+/// record it, marking its entries and expenses invoiced. The draft needn't have
+/// been saved. Returns the invoice number and the result.
+fn issue_invoice(db: &Db, draft: &InvoiceDraft) -> Result<(i64, InvoiceResult), String> {
+    let invoice_number = db
+        .get_next_invoice_number(draft.profile_id)
+        .map_err(|e| e.to_string())?;
+    let (result, entries, expenses) = write_draft(db, draft, Some(invoice_number))?;
+    record_issued_invoice(
+        db,
+        invoice_number,
+        draft.client_id,
+        draft.profile_id,
+        draft.tax_rate,
+        &result,
+        &entries,
+        &expenses,
+    );
+    Ok((invoice_number, result))
+}
+
+/// Invoice (or draft, with `draft`) each client's share of `entries`, found
+/// through project links, and print a table of what was produced. Each client
+/// is invoiced from `profile`, or else their own profile, at `tax_rate` or that
+/// profile's default. Entries on projects without a client are left out.
+#[allow(clippy::too_many_arguments)]
+fn invoice_all_clients(
+    db: &Db,
+    entries: &[Entry],
+    projects: &[models::Project],
+    year: i32,
+    month: u32,
+    tax_rate: Option<f64>,
+    profile: Option<i64>,
+    draft: bool,
+) {
+    let mut by_client: std::collections::BTreeMap<i64, Vec<&Entry>> = Default::default();
+    let mut unlinked = 0;
+    for entry in entries {
+        match projects
+            .iter()
+            .find(|p| p.name == entry.project)
+            .and_then(|p| p.client_id)
+        {
+            Some(id) => by_client.entry(id).or_default().push(entry),
+            None => unlinked += 1,
+        }
+    }
+    if by_client.is_empty() {
+        println!(
+            "No billed entries on projects linked to a client for {}-{:02}",
+            year, month
+        );
+    } else {
+        println!(
+            "{:<24} {:>9} {:>8} {:>8} {:>12}  File",
+            "Client", "Invoice", "Entries", "Hours", "Total"
+        );
+    }
+
+    for (client_id, client_entries) in &by_client {
+        let name = match db.get_client(*client_id) {
+            Ok(Some(c)) => c.name,
+            _ => format!("#{}", client_id),
+        };
+        let profile_id = match profile {
+            Some(id) => id,
+            None => db
+                .profile_for_client(Some(*client_id))
+                .expect("Failed to get client's profile"),
+        };
+        let tax_rate = match tax_rate {
+            Some(rate) => rate,
+            None => {
+                db.get_profile_settings(profile_id)
+                    .expect("Failed to get invoice settings")
+                    .default_tax_rate
+            }
+        };
+        let mut invoice_draft = InvoiceDraft {
+            id: 0,
+            client_id: Some(*client_id),
+            year,
+            month,
+            tax_rate,
+            rounding_minutes: 0,
+            entry_ids: client_entries.iter().map(|e| e.id).collect(),
+            items: Vec::new(),
+            supersedes: None,
+            profile_id,
+            created_at: Utc::now(),
+        };
+        let produced = if draft {
+            db.create_invoice_draft(&invoice_draft)
+                .map_err(|e| e.to_string())
+                .and_then(|id| {
+                    invoice_draft.id = id;
+                    write_draft(db, &invoice_draft, None)
+                })
+                .map(|(result, ..)| (format!("draft {}", invoice_draft.id), result))
+        } else {
+            issue_invoice(db, &invoice_draft)
+                .map(|(number, result)| (format!("#{}", number), result))
+        };
+        match produced {
+            Ok((label, result)) => {
+                let currency = invoice_currency(result.snapshot.rates.values());
+                println!(
+                    "{:<24} {:>9} {:>8} {:>8.2} {:>12}  {}",
+                    name,
+                    label,
+                    client_entries.len(),
+                    billing::total_hours(client_entries),
+                    format_money(result.total, &currency),
+                    result.file_path
+                );
+            }
+            Err(e) => eprintln!("Failed to invoice {}: {}", name, e),
+        }
+    }
+
+    if unlinked > 0 {
+        println!(
+            "\n{} entries on projects without a client were left out; invoice them with `meter invoice`",
+            unlinked
+        );
+    }
+}
+
+/// The business profile `--profile` sets for a client: `None` for 0, which
+/// means the default profile, else the profile if it exists
+fn client_profile(db: &Db, profile: Option<i64>) -> Result<Option<i64>, String> {
+    match profile {
+        None | Some(0) => Ok(None),
+        Some(id) => match db.get_profile(id) {
+            Ok(Some(_)) => Ok(Some(id)),
+            Ok(None) => Err(format!("Business profile with ID {} not found", id)),
+            Err(e) => Err(format!("Failed to get profile: {}", e)),
+        },
+    }
+}
+
+/// Record an issued invoice and mark its entries and expenses as invoiced on it
+#[allow(clippy::too_many_arguments)]
+fn record_issued_invoice(
+    db: &Db,
+    invoice_number: i64,
+    client_id: Option<i64>,
+    profile_id: i64,
+    tax_rate: f64,
+    result: &InvoiceResult,
+    entries: &[Entry],
+    expenses: &[Expense],
+) {
+    let invoice_record = models::Invoice {
+        id: 0,
+        invoice_number,
+        client_id,
+        date_issued: result.date_issued.clone(),
+        due_date: result.due_date.clone(),
+        subtotal: result.subtotal,
+        tax_rate,
+        tax_amount: result.tax_amount,
+        total: result.total,
+        file_path: result.file_path.clone(),
+        snapshot: Some(result.snapshot.to_json()),
+        void_reason: None,
+        supersedes: result.snapshot.supersedes,
+        profile_id,
+    };
+    db.record_invoice(&invoice_record)
+        .expect("Failed to record invoice");
+    for entry in entries {
+        db.set_entry_status(entry.id, EntryStatus::Invoiced)
+            .expect("Failed to mark entries as invoiced");
+    }
+    for expense in expenses {
+        db.set_expense_invoiced(expense.id, invoice_number)
+            .expect("Failed to mark expenses as invoiced");
+    }
+}
+
+/// Print an invoice's expenses, other items, subtotal, tax and total
+fn print_invoice_totals(result: &InvoiceResult) {
+    let snapshot = &result.snapshot;
+    let currency = invoice_currency(snapshot.rates.values());
+    let locale = snapshot.settings.number_locale;
+    if !snapshot.expenses.is_empty() {
+        let total: Money = snapshot.expenses.iter().map(|x| x.amount).sum();
+        println!(
+            "  Expenses: {} ({})",
+            format_money_in(total, &currency, locale),
+            snapshot.expenses.len()
+        );
+    }
+    for item in &snapshot.items {
+        println!(
+            "  {}: {}",
+            item.description,
+            format_money_in(item.amount, &currency, locale)
+        );
+    }
+    if snapshot.settings.tax_inclusive && snapshot.tax_rate > 0.0 {
+        println!(
+            "  Subtotal (excl. tax): {}",
+            format_money_in(result.subtotal, &currency, locale)
+        );
+        println!(
+            "  Includes tax ({:.1}%): {}",
+            snapshot.tax_rate,
+            format_money_in(result.tax_amount, &currency, locale)
+        );
+    } else {
+        println!(
+            "  Subtotal: {}",
+            format_money_in(result.subtotal, &currency, locale)
+        );
+        if snapshot.tax_rate > 0.0 {
+            println!(
+                "  Tax ({:.1}%): {}",
+                snapshot.tax_rate,
+                format_money_in(result.tax_amount, &currency, locale)
+            );
+        }
+    }
+    println!(
+        "  Total: {}",
+        format_money_in(result.total, &currency, locale)
+    );
+}
+
+/// Machine-readable contract for integrators: the database schema version and a
+/// JSON Schema for every JSON format meter produces.
+fn schema_document(version: i64) -> serde_json::Value {
+    serde_json::json!({
+        "schema_version": version,
+        "formats": {
+            "invoice_snapshot": schemars::schema_for!(InvoiceSnapshot),
+            "database_export": schemars::schema_for!(DatabaseExport),
+        },
+    })
+}
+
+/// Move the selected entries to `status` and report what changed.
+/// Entries whose current status can't reach `status` are left alone.
+fn set_entries_status(db: &Db, selection: &EntrySelection, status: EntryStatus) {
+    if let Some(entry_id) = selection.id {
+        match db.get_entry_by_id(entry_id).expect("Failed to get entry") {
+            Some(entry) => {
+                if db
+                    .set_entry_status(entry_id, status)
+                    .expect("Failed to update entry")
+                {
+                    println!("Marked entry {} as {}", entry_id, status);
+                } else {
+                    eprintln!(
+                        "Entry {} is {} and can't be marked {}",
+                        entry_id, entry.status, status
+                    );
+                }
+            }
+            None => eprintln!("Entry {} not found", entry_id),
+        }
+        return;
+    }
+
+    if selection.project.is_none() && selection.month.is_none() {
+        let count = db
+            .set_all_entries_status(status)
+            .expect("Failed to update entries");
+        println!("Marked {} entries as {}", count, status);
+        return;
+    }
+
+    let mut entries = db.list(None).expect("Failed to list entries");
+    if let Some(project) = &selection.project {
+        entries.retain(|e| &e.project == project);
+    }
+    if let Some(month) = &selection.month {
+        let Some((year, month)) = parse_year_month(month) else {
+            eprintln!("Invalid month '{}' (expected YYYY-MM)", month);
+            return;
+        };
+        entries = filter_entries_by_month(&entries, year, month);
+    }
+
+    let ids: Vec<i64> = entries
+        .iter()
+        .filter(|e| e.status.can_transition_to(status))
+        .map(|e| e.id)
+        .collect();
+    let changed = db
+        .set_entries_status(&ids, status)
+        .expect("Failed to update entries");
+
+    for e in entries.iter().filter(|e| changed.contains(&e.id)) {
+        let dur = match e.end {
+            Some(end) => (end - e.start).num_seconds() as f64 / 3600.0,
+            None => 0.0,
+        };
+        println!(
+            "[{}] {} | {} | {:.2} hrs | {} -> {}",
+            e.id, e.project, e.description, dur, e.status, status
+        );
+    }
+    println!("Marked {} entries as {}", changed.len(), status);
+    let unchanged = entries.len() - changed.len();
+    if unchanged > 0 {
+        println!(
+            "{} matching entries left unchanged (already {} or not allowed from their status)",
+            unchanged, status
+        );
+    }
+}
+
+/// Print one day's entries grouped by project, with each project's descriptions
+/// and durations. A running timer counts up to now.
+fn print_day_summary(
+    label: &str,
+    day: NaiveDate,
+    entries: &[Entry],
+    off: Option<&TimeOff>,
+    markdown: bool,
+) {
+    let now = Utc::now();
+    // Projects and descriptions in the order they were first worked on that day
+    let mut projects: Vec<(String, Vec<(String, f64)>)> = Vec::new();
+    let mut day_entries: Vec<&Entry> = entries
+        .iter()
+        .filter(|e| e.start.with_timezone(&Local).date_naive() == day)
+        .collect();
+    day_entries.sort_by_key(|e| e.start);
+    for e in day_entries {
+        let hours = (e.end.unwrap_or(now) - e.start).num_seconds() as f64 / 3600.0;
+        let idx = match projects.iter().position(|(p, _)| p == &e.project) {
+            Some(idx) => idx,
+            None => {
+                projects.push((e.project.clone(), Vec::new()));
+                projects.len() - 1
+            }
+        };
+        let descs = &mut projects[idx].1;
+        let description = e.description_with_issue();
+        match descs.iter_mut().find(|(d, _)| d == &description) {
+            Some((_, h)) => *h += hours,
+            None => descs.push((description, hours)),
+        }
+    }
+
+    let total: f64 = projects
+        .iter()
+        .flat_map(|(_, descs)| descs.iter().map(|(_, h)| h))
+        .sum();
+    let heading = format!("{} ({})", label, day.format("%a %Y-%m-%d"));
+    if markdown {
+        println!("**{}** - {:.2} hrs", heading, total);
+    } else {
+        println!("{} - {:.2} hrs", heading, total);
+    }
+    let indent = if markdown { "- " } else { "  " };
+    if let Some(off) = off {
+        if off.reason.is_empty() {
+            println!("{}Off", indent);
+        } else {
+            println!("{}Off ({})", indent, off.reason);
+        }
+    }
+    if projects.is_empty() {
+        if off.is_none() {
+            println!("{}No entries", indent);
+        }
+        return;
+    }
+    for (project, descs) in &projects {
+        let hours: f64 = descs.iter().map(|(_, h)| h).sum();
+        if markdown {
+            println!("- **{}** ({:.2} hrs)", project, hours);
+        } else {
+            println!("  {} - {:.2} hrs", project, hours);
+        }
+        for (desc, h) in descs {
+            if markdown {
+                println!("  - {} ({:.2} hrs)", desc, h);
+            } else {
+                println!("    {} ({:.2} hrs)", desc, h);
+            }
+        }
+    }
+}
+
+/// The entries that aren't recorded yet (no entry with the same project, start
+/// and, if `match_end`, end), with blank descriptions filled in as for a timer.
+/// Says how many were skipped. Without `match_end`, repeats within `entries`
+/// are skipped too, for overlapping exports.
+fn unrecorded_entries(db: &Db, entries: Vec<Entry>, match_end: bool) -> Vec<Entry> {
+    let key = |e: &Entry| {
+        (
+            e.project.clone(),
+            e.start,
+            if match_end { e.end } else { None },
+        )
+    };
+    let mut recorded: HashSet<_> = db
+        .list(None)
+        .expect("Failed to list entries")
+        .iter()
+        .map(key)
+        .collect();
+    let total = entries.len();
+    let mut entries: Vec<Entry> = entries
+        .into_iter()
+        .filter(|e| {
+            if match_end {
+                !recorded.contains(&key(e))
+            } else {
+                recorded.insert(key(e))
+            }
+        })
+        .collect();
+    for entry in &mut entries {
+        if entry.description.is_empty() {
+            entry.description = db
+                .default_description(&entry.project)
+                .expect("Failed to get default description");
+        }
+    }
+    if entries.len() < total {
+        println!("Skipped {} already recorded", total - entries.len());
+    }
+    entries
+}
+
+/// Restore a `meter export json` file. Only an empty database is restored
+/// into, so IDs can be kept and nothing is merged by guesswork.
+fn import_database(db: &Db, files: &[String], dry_run: bool) {
+    let [file] = files else {
+        eprintln!("Import one export file at a time");
+        return;
+    };
+    let data = match fs::read_to_string(file)
+        .map_err(|e| e.to_string())
+        .and_then(|data| export::parse_json(&data))
+    {
+        Ok(data) => data,
+        Err(e) => {
+            eprintln!("Failed to import {}: {}", file, e);
+            return;
+        }
+    };
+    if !db.is_fresh().expect("Failed to check the database") {
+        eprintln!(
+            "This database already has data; `meter import json` only restores into an empty one"
+        );
+        return;
+    }
+
+    println!(
+        "{} profiles, {} clients, {} projects, {} entries, {} invoices",
+        data.profiles.len(),
+        data.clients.len(),
+        data.projects.len(),
+        data.entries.len(),
+        data.invoices.len()
+    );
+    if dry_run {
+        println!("Dry run, nothing imported");
+        return;
+    }
+    db.import_all(&data).expect("Failed to import the database");
+    println!(
+        "Imported the database exported {}",
+        data.exported_at
+            .with_timezone(&Local)
+            .format("%Y-%m-%d %H:%M")
+    );
+}
+
+/// Add entries in one go, flagging them for review with `flag` if given and
+/// splitting them at midnight when that setting is on. With `dry_run`, only say
+/// what would be added.
+fn insert_entries(db: &Db, entries: &[Entry], dry_run: bool, flag: Option<ReviewReason>) {
+    let hours = billing::total_hours(&entries.iter().collect::<Vec<_>>());
+    if dry_run {
+        println!(
+            "Would add {} entries, {:.2} hrs (dry run, nothing added)",
+            entries.len(),
+            hours
+        );
+        return;
+    }
+
+    let ids = db.insert_all(entries).expect("Failed to insert entries");
+    println!("Added {} entries, {:.2} hrs", ids.len(), hours);
+    if let Some(reason) = flag {
+        db.flag_for_review(&ids, reason)
+            .expect("Failed to flag entries");
+        if !ids.is_empty() {
+            println!("They are flagged for review until checked (see `meter report flagged`)");
+        }
+    }
+    let settings = db
+        .get_timer_settings()
+        .expect("Failed to get timer settings");
+    if settings.split_at_midnight {
+        for id in ids {
+            split_and_report(db, id);
+        }
+    }
+}
+
+/// Review reasons as a list for people, e.g. "imported, split at midnight"
+fn describe_review_reasons(reasons: &[ReviewReason]) -> String {
+    reasons
+        .iter()
+        .map(|r| r.describe())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Split an entry at midnight and say so if it crossed one.
+fn split_and_report(db: &Db, id: i64) {
+    let added = db
+        .split_entry_at_midnight(id)
+        .expect("Failed to split entry at midnight");
+    if added > 0 {
+        println!("Split across {} days at midnight", added + 1);
+    }
+}
+
+/// Parse a duration such as `90m`, `2h`, `45s` or `1h30m` into seconds.
+fn parse_duration_arg(s: &str) -> Option<i64> {
+    let mut secs: i64 = 0;
+    let mut amount = String::new();
+    for c in s.trim().chars() {
+        if c.is_ascii_digit() {
+            amount.push(c);
+            continue;
+        }
+        let n: i64 = amount.parse().ok()?;
+        let unit = match c {
+            's' => 1,
+            'm' => 60,
+            'h' => 3600,
+            _ => return None,
+        };
+        secs = n.checked_mul(unit).and_then(|n| secs.checked_add(n))?;
+        amount.clear();
+    }
+    if !amount.is_empty() || s.trim().is_empty() {
+        return None;
+    }
+    Some(secs)
+}
+
+/// The current git branch, when run inside a repository with a branch checked out
+fn git_branch() -> Option<String> {
+    let output = process::Command::new("git")
+        .args(["rev-parse", "--abbrev-ref", "HEAD"])
+        .output()
+        .ok()?;
+    let branch = String::from_utf8(output.stdout).ok()?.trim().to_string();
+    (output.status.success() && !branch.is_empty() && branch != "HEAD").then_some(branch)
+}
+
+/// Parse a time argument: `HH:MM[:SS]` today, `YYYY-MM-DD HH:MM[:SS]`, or an
+/// offset into the past like `-10m`, `-1h` or `-30s`. Times are local.
+fn parse_time_arg(s: &str) -> Option<DateTime<Utc>> {
+    let s = s.trim();
+    if let Some(offset) = s.strip_prefix('-') {
+        let offset = Duration::try_seconds(parse_duration_arg(offset)?)?;
+        return Utc::now().checked_sub_signed(offset);
+    }
+
+    let naive = NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S")
+        .or_else(|_| NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M"))
+        .ok()
+        .or_else(|| {
+            let time = NaiveTime::parse_from_str(s, "%H:%M:%S")
+                .or_else(|_| NaiveTime::parse_from_str(s, "%H:%M"))
+                .ok()?;
+            Some(Local::now().date_naive().and_time(time))
+        })?;
+    Local
+        .from_local_datetime(&naive)
+        .single()
+        .map(|dt| dt.with_timezone(&Utc))
+}
+
+/// The running timer and today's total as one line of JSON for home screen
+/// widgets, shell prompts and tmux status lines. Pomodoro work periods are
+/// counted from the entry's start, as the menu bar app counts them.
+fn widget_status(
+    entry: Option<&Entry>,
+    today_secs: i64,
+    pomodoro: &PomodoroConfig,
+    now: DateTime<Utc>,
+) -> String {
+    let mut value = serde_json::json!({
+        "running": entry.is_some(),
+        "today_secs": today_secs,
+        "today": format_elapsed(today_secs),
+    });
+    if let Some(entry) = entry {
+        let elapsed = (now - entry.start).num_seconds().max(0);
+        let pomodoro_remaining = pomodoro
+            .enabled
+            .then(|| (pomodoro.work_duration as i64 * 60 - elapsed).max(0));
+        value["project"] = entry.project.clone().into();
+        value["description"] = entry.description.clone().into();
+        value["started"] = entry.start.to_rfc3339().into();
+        value["elapsed_secs"] = elapsed.into();
+        value["elapsed"] = format_elapsed(elapsed).into();
+        value["pomodoro_remaining_secs"] = pomodoro_remaining.into();
+    }
+    value.to_string()
+}
+
+/// The start of local `day`, or the first moment of it when midnight is skipped
+/// for daylight saving.
+fn local_midnight(day: NaiveDate) -> DateTime<Utc> {
+    let midnight = day.and_time(NaiveTime::MIN);
+    Local
+        .from_local_datetime(&midnight)
+        .earliest()
+        .unwrap_or_else(|| Local.from_utc_datetime(&midnight))
+        .with_timezone(&Utc)
+}
+
+/// Ask a yes/no question on the terminal. Anything but y or yes, including no
+/// answer at all, is a no.
+fn confirm(question: &str) -> bool {
+    print!("{} [y/N] ", question);
+    let _ = io::Write::flush(&mut io::stdout());
+    let mut answer = String::new();
+    if io::stdin().read_line(&mut answer).is_err() {
+        return false;
+    }
+    matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
+}
+
+/// Format seconds as `HH:MM:SS`.
+fn format_elapsed(secs: i64) -> String {
+    format!(
+        "{:02}:{:02}:{:02}",
+        secs / 3600,
+        (secs % 3600) / 60,
+        secs % 60
+    )
+}
+
+/// Mark the entries on `invoice` paid. Invoices from before snapshots were
+/// stored don't know their entries, so nothing changes for those. Returns the
+/// IDs of the entries that changed.
+fn mark_invoice_paid(db: &Db, invoice: &Invoice) -> Result<Vec<i64>, String> {
+    let entry_ids: Vec<i64> = invoice
+        .snapshot
+        .as_deref()
+        .and_then(|json| InvoiceSnapshot::from_json(json).ok())
+        .map(|s| s.line_items.iter().map(|i| i.entry_id).collect())
+        .unwrap_or_default();
+    db.set_entries_status(&entry_ids, EntryStatus::Paid)
+        .map_err(|e| e.to_string())
+}
+
+/// Read new emails and apply the mail rules to the invoices they name. An email
+/// matches when its subject names a standing invoice and a rule's pattern
+/// appears in its subject or sender; each invoice is marked acknowledged or
+/// paid at most once. Returns the new matches.
+fn check_inbox(db: &Db) -> Result<Vec<MailMatch>, String> {
+    let mut settings = db.get_inbox_settings().map_err(|e| e.to_string())?;
+    if !settings.enabled {
+        return Err("The inbox watcher is off: meter mail settings --enabled true".to_string());
+    }
+    let messages = inbox::fetch_new(&settings)?;
+    let rules = db.list_mail_rules().map_err(|e| e.to_string())?;
+    let invoices = db.list_invoices().map_err(|e| e.to_string())?;
+    let mut seen = db.list_mail_matches().map_err(|e| e.to_string())?;
+    let mut matches = Vec::new();
+    for message in &messages {
+        let Some(number) = inbox::invoice_number_in_subject(&message.subject) else {
+            continue;
+        };
+        let Some(rule) = rules
+            .iter()
+            .find(|r| r.matches(&message.subject, &message.from))
+        else {
+            continue;
+        };
+        let Some(invoice) = invoices
+            .iter()
+            .find(|i| i.invoice_number == number && !i.is_void())
+        else {
+            continue;
+        };
+        if seen
+            .iter()
+            .any(|m| m.invoice_number == number && m.action == rule.action)
+        {
+            continue;
+        }
+        if rule.action == MailAction::Paid {
+            mark_invoice_paid(db, invoice)?;
+        }
+        let mut m = MailMatch {
+            id: 0,
+            invoice_number: number,
+            action: rule.action,
+            subject: message.subject.clone(),
+            sender: message.from.clone(),
+            matched_at: Utc::now(),
+        };
+        m.id = db.record_mail_match(&m).map_err(|e| e.to_string())?;
+        seen.push(m.clone());
+        matches.push(m);
+    }
+    if let Some(last) = messages.last() {
+        settings.last_uid = i64::from(last.uid);
+        db.set_inbox_settings(&settings)
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(matches)
+}
+
+/// One line per email matched to an invoice
+fn print_mail_matches(matches: &[MailMatch]) {
+    for m in matches {
+        println!(
+            "{}  #{:<5} {:<13} {} ({})",
+            m.matched_at.with_timezone(&Local).format("%Y-%m-%d %H:%M"),
+            m.invoice_number,
+            m.action,
+            m.subject,
+            m.sender
+        );
+    }
+}
+
+/// The year and month `meter invoice` bills: `month` as 1-12 (in `year`, or this
+/// year) or YYYY-MM, defaulting to this month
+fn invoice_month(month: Option<&str>, year: Option<i32>) -> Option<(i32, u32)> {
+    let now = Utc::now();
+    match month {
+        Some(m) if m.contains('-') => parse_year_month(m),
+        Some(m) => m
+            .trim()
+            .parse()
+            .ok()
+            .filter(|m| (1..=12).contains(m))
+            .map(|m| (year.unwrap_or(now.year()), m)),
+        None => Some((year.unwrap_or(now.year()), now.month())),
+    }
+}
+
+/// Parse a `YYYY-MM` month argument.
+fn parse_year_month(s: &str) -> Option<(i32, u32)> {
+    let date = NaiveDate::parse_from_str(&format!("{}-01", s.trim()), "%Y-%m-%d").ok()?;
+    Some((date.year(), date.month()))
 }
@@ -0,0 +1,1474 @@
+use std::time::{Duration, Instant};
+
+use chrono::{Local, NaiveDate, Utc};
+use global_hotkey::{
+    GlobalHotKeyEvent, GlobalHotKeyManager,
+    hotkey::{Code, HotKey, Modifiers},
+};
+use notify_rust::Notification;
+use objc2::MainThreadMarker;
+use objc2_app_kit::{
+    NSAlert, NSAlertFirstButtonReturn, NSApplication, NSApplicationActivationPolicy, NSWorkspace,
+};
+use objc2_foundation::NSString;
+use tao::{
+    event::Event,
+    event_loop::{ControlFlow, EventLoopBuilder},
+};
+use tray_icon::{
+    Icon, TrayIconBuilder, TrayIconEvent,
+    menu::{Menu, MenuEvent, MenuItem, PredefinedMenuItem, Submenu},
+};
+
+use crate::db::Db;
+use crate::models::{
+    DEFAULT_DESCRIPTION, Entry, EntryStatus, InvoiceReminder, NetworkRule, NotificationKind,
+    PomodoroConfig, ReviewReason, format_short_duration,
+};
+use crate::{dunning, models, paths};
+
+/// Pomodoro state for menubar
+#[derive(Debug, Clone, PartialEq)]
+enum PomodoroState {
+    Idle,
+    Working,
+    WorkComplete,
+    OnBreak,
+    BreakComplete,
+}
+
+impl Default for PomodoroState {
+    fn default() -> Self {
+        PomodoroState::Idle
+    }
+}
+
+/// Hide the app from the Dock and app switcher (menu bar only)
+fn set_activation_policy_accessory() {
+    // Safety: This is called from the main thread at app startup
+    let mtm = unsafe { MainThreadMarker::new_unchecked() };
+    let app = NSApplication::sharedApplication(mtm);
+    app.setActivationPolicy(NSApplicationActivationPolicy::Accessory);
+}
+
+/// Ask whether to discard a just-stopped entry that is shorter than the minimum duration.
+/// Returns true if the user chose to discard it.
+fn confirm_discard_short_entry(entry: &Entry, min_secs: i64) -> bool {
+    // Safety: menu and hotkey events are handled on the main thread
+    let mtm = unsafe { MainThreadMarker::new_unchecked() };
+    let secs = entry
+        .end
+        .map(|end| (end - entry.start).num_seconds())
+        .unwrap_or(0);
+
+    // Accessory apps aren't frontmost, so bring the alert forward
+    #[allow(deprecated)]
+    NSApplication::sharedApplication(mtm).activateIgnoringOtherApps(true);
+
+    let alert = NSAlert::new(mtm);
+    alert.setMessageText(&NSString::from_str(&format!(
+        "Timer for '{}' ran only {}s",
+        entry.project, secs
+    )));
+    alert.setInformativeText(&NSString::from_str(&format!(
+        "That's shorter than the {}s minimum. Discard the entry?",
+        min_secs
+    )));
+    alert.addButtonWithTitle(&NSString::from_str("Discard"));
+    alert.addButtonWithTitle(&NSString::from_str("Keep"));
+    alert.runModal() == NSAlertFirstButtonReturn
+}
+
+/// Tidy up a just-stopped entry: drop it if it is shorter than the configured minimum
+/// (automatically or after asking), and split it at midnight if that setting is on.
+fn finish_stopped_entry(db: &Db, entry: &Entry) {
+    let settings = db.get_timer_settings().unwrap_or_default();
+    if settings.is_too_short(entry)
+        && (settings.discard_short_entries
+            || confirm_discard_short_entry(entry, settings.min_duration_secs))
+    {
+        let _ = db.delete(entry.id);
+        return;
+    }
+    if settings.split_at_midnight {
+        let _ = db.split_entry_at_midnight(entry.id);
+    }
+}
+
+enum UserEvent {
+    TrayIconEvent(tray_icon::TrayIconEvent),
+    MenuEvent(tray_icon::menu::MenuEvent),
+    Tick,
+    HotKey(GlobalHotKeyEvent),
+}
+
+/// Create a timer icon with progress ring
+/// - When idle: gray ring outline
+/// - When running: white ring that fills based on elapsed time
+/// - Pomodoro enabled: "P" in center instead of dot
+/// - On break: blue ring color
+/// - Billing badge: red dot in the top right corner
+fn create_icon(
+    is_running: bool,
+    elapsed_seconds: Option<i64>,
+    pomodoro_enabled: bool,
+    pomodoro_state: &PomodoroState,
+    pomodoro_total_secs: Option<i64>,
+    badge: bool,
+) -> Icon {
+    let size = 22u32; // Standard macOS menu bar icon size
+    let mut rgba = vec![0u8; (size * size * 4) as usize];
+
+    let center = size as f32 / 2.0;
+    let outer_radius = size as f32 / 2.0 - 1.0;
+    let inner_radius = outer_radius - 3.5;
+
+    // Determine ring color based on state
+    let (ring_r, ring_g, ring_b) = match pomodoro_state {
+        PomodoroState::OnBreak => (100, 149, 237), // Cornflower blue for break
+        PomodoroState::WorkComplete | PomodoroState::BreakComplete => (255, 200, 0), // Yellow for prompts
+        _ => (255, 255, 255), // White for working
+    };
+
+    // Calculate progress (0.0 to 1.0)
+    let progress = if is_running || *pomodoro_state == PomodoroState::OnBreak {
+        if let (Some(elapsed), Some(total)) = (elapsed_seconds, pomodoro_total_secs) {
+            // Pomodoro mode: progress based on interval
+            (elapsed as f32 / total as f32).min(1.0)
+        } else if let Some(secs) = elapsed_seconds {
+            // Normal mode: cycles every hour
+            (secs % 3600) as f32 / 3600.0
+        } else {
+            0.0
+        }
+    } else {
+        0.0
+    };
+
+    for y in 0..size {
+        for x in 0..size {
+            let dx = x as f32 - center;
+            let dy = y as f32 - center;
+            let dist = (dx * dx + dy * dy).sqrt();
+
+            let idx = ((y * size + x) * 4) as usize;
+
+            // Check if pixel is in the ring area
+            if dist <= outer_radius && dist >= inner_radius {
+                // Calculate angle (0 at 12 o'clock, clockwise)
+                let raw_angle = dx.atan2(-dy);
+                let angle = if raw_angle < 0.0 {
+                    (raw_angle + 2.0 * std::f32::consts::PI) / (2.0 * std::f32::consts::PI)
+                } else {
+                    raw_angle / (2.0 * std::f32::consts::PI)
+                };
+
+                if is_running || *pomodoro_state == PomodoroState::OnBreak {
+                    if angle <= progress {
+                        // Filled portion
+                        rgba[idx] = ring_r;
+                        rgba[idx + 1] = ring_g;
+                        rgba[idx + 2] = ring_b;
+                        rgba[idx + 3] = 255;
+                    } else {
+                        // Unfilled portion - dark gray
+                        rgba[idx] = 60;
+                        rgba[idx + 1] = 60;
+                        rgba[idx + 2] = 60;
+                        rgba[idx + 3] = 255;
+                    }
+                } else {
+                    // Idle - gray ring
+                    rgba[idx] = 128;
+                    rgba[idx + 1] = 128;
+                    rgba[idx + 2] = 128;
+                    rgba[idx + 3] = 255;
+                }
+            }
+
+            // Draw center: "P" if Pomodoro enabled, otherwise dot
+            if pomodoro_enabled {
+                // Draw a simple "P" shape in the center
+                // P is roughly 5x7 pixels, centered
+                let px = (x as i32) - (center as i32);
+                let py = (y as i32) - (center as i32);
+
+                // Define "P" shape (relative to center, scaled down)
+                let is_p =
+                    // Vertical stem
+                    (px >= -2 && px <= -1 && py >= -3 && py <= 3) ||
+                    // Top horizontal of P
+                    (px >= -1 && px <= 2 && py >= -3 && py <= -2) ||
+                    // Right curve of P (top)
+                    (px >= 2 && px <= 3 && py >= -2 && py <= 0) ||
+                    // Middle horizontal of P
+                    (px >= -1 && px <= 2 && py >= 0 && py <= 1);
+
+                if is_p {
+                    if is_running || *pomodoro_state == PomodoroState::OnBreak {
+                        rgba[idx] = ring_r;
+                        rgba[idx + 1] = ring_g;
+                        rgba[idx + 2] = ring_b;
+                        rgba[idx + 3] = 255;
+                    } else {
+                        rgba[idx] = 128;
+                        rgba[idx + 1] = 128;
+                        rgba[idx + 2] = 128;
+                        rgba[idx + 3] = 255;
+                    }
+                }
+            } else {
+                // Draw center dot
+                if dist <= 3.0 {
+                    if is_running {
+                        rgba[idx] = 255;
+                        rgba[idx + 1] = 255;
+                        rgba[idx + 2] = 255;
+                        rgba[idx + 3] = 255;
+                    } else {
+                        rgba[idx] = 128;
+                        rgba[idx + 1] = 128;
+                        rgba[idx + 2] = 128;
+                        rgba[idx + 3] = 255;
+                    }
+                }
+            }
+
+            // Billing badge, drawn over the ring
+            if badge {
+                let bx = x as f32 - (size as f32 - 4.5);
+                let by = y as f32 - 4.5;
+                if bx * bx + by * by <= 4.0 * 4.0 {
+                    rgba[idx] = 255;
+                    rgba[idx + 1] = 59;
+                    rgba[idx + 2] = 48;
+                    rgba[idx + 3] = 255;
+                }
+            }
+        }
+    }
+
+    Icon::from_rgba(rgba, size, size).expect("Failed to create icon")
+}
+
+/// Whether unbilled hours or an overdue invoice call for the billing badge,
+/// as set in the notification settings
+fn billing_badge(db: &Db) -> bool {
+    let settings = db.get_notification_settings().unwrap_or_default();
+    let over_hours = settings.badge_unbilled_hours > 0.0
+        && db
+            .unbilled_hours()
+            .is_ok_and(|hours| hours > settings.badge_unbilled_hours);
+    let overdue = settings.badge_overdue
+        && db
+            .overdue_invoice_count(Local::now().date_naive())
+            .is_ok_and(|count| count > 0);
+    over_hours || overdue
+}
+
+fn format_duration(seconds: i64) -> String {
+    let hours = seconds / 3600;
+    let minutes = (seconds % 3600) / 60;
+    let secs = seconds % 60;
+    format!("{:02}:{:02}:{:02}", hours, minutes, secs)
+}
+
+fn format_remaining(seconds: i64) -> String {
+    let minutes = seconds / 60;
+    let secs = seconds % 60;
+    format!("{:02}:{:02}", minutes, secs)
+}
+
+#[link(name = "CoreGraphics", kind = "framework")]
+unsafe extern "C" {
+    fn CGEventSourceSecondsSinceLastEventType(state_id: i32, event_type: u32) -> f64;
+}
+
+/// Seconds since the last keyboard or mouse input in this login session
+fn idle_secs() -> i64 {
+    // kCGEventSourceStateCombinedSessionState, kCGAnyInputEventType
+    unsafe { CGEventSourceSecondsSinceLastEventType(0, u32::MAX) as i64 }
+}
+
+/// How often the frontmost app is sampled when activity tracking is on
+const ACTIVITY_SAMPLE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How often unbilled hours and overdue invoices are rechecked for the badge
+const BADGE_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How often the Wi-Fi network is checked while there are network rules
+const NETWORK_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Name of the Wi-Fi network the Mac is on, from `networksetup`
+fn current_ssid() -> Option<String> {
+    let run = |args: &[&str]| {
+        std::process::Command::new("/usr/sbin/networksetup")
+            .args(args)
+            .output()
+            .ok()
+            .filter(|out| out.status.success())
+            .map(|out| String::from_utf8_lossy(&out.stdout).into_owned())
+    };
+    // The Wi-Fi interface is usually en0, but not on every Mac
+    let ports = run(&["-listallhardwareports"])?;
+    let device = ports
+        .split("\n\n")
+        .find(|port| port.contains("Hardware Port: Wi-Fi"))?
+        .lines()
+        .find_map(|line| line.strip_prefix("Device: "))?
+        .trim()
+        .to_string();
+    let network = run(&["-getairportnetwork", &device])?;
+    network
+        .trim()
+        .strip_prefix("Current Wi-Fi Network: ")
+        .map(str::to_string)
+}
+
+/// Name of the frontmost application
+fn frontmost_app() -> Option<String> {
+    let app = NSWorkspace::sharedWorkspace().frontmostApplication()?;
+    Some(app.localizedName()?.to_string())
+}
+
+/// Show a notification unless the user's settings turn its kind off or it is
+/// quiet hours
+fn notify(db: &Db, kind: NotificationKind, summary: &str, body: &str) {
+    let settings = db.get_notification_settings().unwrap_or_default();
+    if !settings.allows(kind, Local::now().time()) {
+        return;
+    }
+    let mut notification = Notification::new();
+    notification.summary(summary).body(body);
+    if !settings.sound.is_empty() {
+        notification.sound_name(&settings.sound);
+    }
+    let _ = notification.show();
+}
+
+fn notify_work_complete(db: &Db) {
+    notify(
+        db,
+        NotificationKind::WorkComplete,
+        "Meter - Pomodoro",
+        "Work period complete! Time for a break.",
+    );
+}
+
+fn notify_break_complete(db: &Db) {
+    notify(
+        db,
+        NotificationKind::BreakComplete,
+        "Meter - Pomodoro",
+        "Break complete! Ready to resume work?",
+    );
+}
+
+/// Remind about finished months that still have uninvoiced hours
+fn notify_invoice_reminders(db: &Db, reminders: &[InvoiceReminder]) {
+    let body = reminders
+        .iter()
+        .map(InvoiceReminder::message)
+        .collect::<Vec<_>>()
+        .join("\n");
+    notify(db, NotificationKind::Reminder, "Meter - Invoicing", &body);
+}
+
+/// Tell the user their timer was stopped while they were away
+fn notify_idle_stop(db: &Db, project: &str, idle_minutes: i64) {
+    notify(
+        db,
+        NotificationKind::Reminder,
+        "Meter - Timer stopped",
+        &format!(
+            "Stopped '{}' after {} minutes away; the idle time was not recorded",
+            project, idle_minutes
+        ),
+    );
+}
+
+/// Announce that a countdown timer has reached its target
+fn notify_target_reached(db: &Db, project: &str, target_secs: i64) {
+    notify(
+        db,
+        NotificationKind::WorkComplete,
+        "Meter - Timer",
+        &format!(
+            "{} on '{}' is up",
+            format_short_duration(target_secs),
+            project
+        ),
+    );
+}
+
+/// Warn that a project has reached its hour cap
+fn notify_cap_reached(db: &Db, project: &str, usage: &str) {
+    notify(
+        db,
+        NotificationKind::Reminder,
+        "Meter - Hour cap",
+        &format!("'{}' has reached its hour cap ({})", project, usage),
+    );
+}
+
+/// Announce a late-payment reminder, and whether it reached the client
+fn notify_overdue(db: &Db, sent: &dunning::SentReminder) {
+    let body = match &sent.email {
+        Some(Ok(to)) => format!("{}; reminder emailed to {}", sent.reminder.message(), to),
+        Some(Err(e)) => format!("{}; reminder not emailed: {}", sent.reminder.message(), e),
+        None => sent.reminder.message(),
+    };
+    notify(
+        db,
+        NotificationKind::Reminder,
+        "Meter - Overdue invoice",
+        &body,
+    );
+}
+
+/// Suggest starting the timer a network rule names
+fn notify_network_rule(db: &Db, rule: &NetworkRule) {
+    notify(
+        db,
+        NotificationKind::Reminder,
+        "Meter - Start timer?",
+        &format!(
+            "On '{}' Wi-Fi with no timer running. Start '{}' from the menu bar",
+            rule.ssid, rule.project
+        ),
+    );
+}
+
+/// Warn that billable hours have fallen behind the monthly target's pace
+fn notify_behind_target(db: &Db, pace: &str) {
+    notify(
+        db,
+        NotificationKind::Reminder,
+        "Meter - Monthly target",
+        &format!("Billable hours are {}", pace),
+    );
+}
+
+/// Show the pace against the monthly target under the status line while a
+/// target is set, and notify once a day while billable hours are behind it.
+/// Returns whether the pace line is in the menu.
+fn update_target_pace(
+    db: &Db,
+    menu: &Menu,
+    pace_i: &MenuItem,
+    shown: bool,
+    warned_day: &mut Option<NaiveDate>,
+) -> bool {
+    let Some(pace) = db.target_pace(Utc::now()).ok().flatten() else {
+        if shown {
+            let _ = menu.remove(pace_i);
+        }
+        return false;
+    };
+    let margin = db
+        .get_notification_settings()
+        .unwrap_or_default()
+        .target_margin_hours;
+    let summary = pace.summary(margin);
+    pace_i.set_text(format!("Monthly target - {}", summary));
+    if !shown {
+        let _ = menu.insert(pace_i, 1);
+    }
+    if pace.is_behind(margin) && *warned_day != Some(pace.today) {
+        *warned_day = Some(pace.today);
+        notify_behind_target(db, &summary);
+    }
+    true
+}
+
+/// Show "Start <project>" above the timer items while a network rule applies
+/// and no timer is running. Returns whether the item is in the menu.
+fn update_network_suggestion(
+    menu: &Menu,
+    network_i: &MenuItem,
+    suggestion: Option<&NetworkRule>,
+    shown: bool,
+    pace_shown: bool,
+) -> bool {
+    let Some(rule) = suggestion else {
+        if shown {
+            let _ = menu.remove(network_i);
+        }
+        return false;
+    };
+    network_i.set_text(format!("Start {} ({} Wi-Fi)", rule.project, rule.ssid));
+    if !shown {
+        // Under the status, the pace line if any, and the separator
+        let _ = menu.insert(network_i, if pace_shown { 3 } else { 2 });
+    }
+    true
+}
+
+/// Take the single-instance lock: an exclusive `flock` on the pid file, held
+/// until the process exits. Returns `None` while another copy holds it.
+fn lock_single_instance() -> Option<std::fs::File> {
+    use std::os::fd::AsRawFd;
+
+    let path = paths::menubar_pid_file();
+    if let Some(dir) = path.parent() {
+        let _ = std::fs::create_dir_all(dir);
+    }
+    let file = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(&path)
+        .expect("Failed to open the menu bar pid file");
+    let locked = unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) } == 0;
+    locked.then_some(file)
+}
+
+/// Record this process in the locked pid file, for `meter menubar status` and `stop`
+fn write_pid(file: &mut std::fs::File) {
+    use std::io::Write;
+
+    let _ = file
+        .set_len(0)
+        .and_then(|_| file.write_all(format!("{}\n", std::process::id()).as_bytes()));
+}
+
+pub fn main() {
+    // One tray icon and one Pomodoro state machine: a second copy just exits.
+    // The lock is taken before forking so the message reaches the terminal, and
+    // the forked child keeps holding it.
+    let Some(mut lock) = lock_single_instance() else {
+        let pid = std::fs::read_to_string(paths::menubar_pid_file()).unwrap_or_default();
+        eprintln!(
+            "Meter menubar is already running (pid: {}). Stop it with `meter menubar stop`",
+            pid.trim()
+        );
+        std::process::exit(1);
+    };
+
+    // Daemonize: fork and detach from terminal
+    // Pass --no-fork to skip (useful for debugging)
+    if !std::env::args().any(|arg| arg == "--no-fork") {
+        unsafe {
+            let pid = libc::fork();
+            if pid < 0 {
+                eprintln!("Failed to fork");
+                std::process::exit(1);
+            }
+            if pid > 0 {
+                // Parent process exits immediately
+                println!("Meter menubar started (pid: {})", pid);
+                std::process::exit(0);
+            }
+            // Child process continues
+            // Create new session to detach from terminal
+            libc::setsid();
+        }
+    }
+    write_pid(&mut lock);
+
+    // Hide from Dock and app switcher - MUST be set before event loop is created
+    set_activation_policy_accessory();
+
+    let db = Db::new(&paths::db_path().to_string_lossy()).expect("Failed to open DB");
+    models::init_all(db.conn()).expect("Failed to init DB");
+
+    let event_loop = EventLoopBuilder::<UserEvent>::with_user_event().build();
+
+    // Re-apply activation policy after event loop creation (tao may reset it)
+    set_activation_policy_accessory();
+
+    // Set up global hotkey (Cmd+Control+T)
+    let hotkey_manager = GlobalHotKeyManager::new().expect("Failed to create hotkey manager");
+    let hotkey = HotKey::new(Some(Modifiers::META | Modifiers::CONTROL), Code::KeyT);
+    let hotkey_id = hotkey.id();
+    hotkey_manager
+        .register(hotkey)
+        .expect("Failed to register hotkey");
+
+    // Set up hotkey event handler
+    let proxy = event_loop.create_proxy();
+    GlobalHotKeyEvent::set_event_handler(Some(move |event| {
+        let _ = proxy.send_event(UserEvent::HotKey(event));
+    }));
+
+    // Set up event handlers
+    let proxy = event_loop.create_proxy();
+    TrayIconEvent::set_event_handler(Some(move |event| {
+        let _ = proxy.send_event(UserEvent::TrayIconEvent(event));
+    }));
+
+    let proxy = event_loop.create_proxy();
+    MenuEvent::set_event_handler(Some(move |event| {
+        let _ = proxy.send_event(UserEvent::MenuEvent(event));
+    }));
+
+    // Set up a timer for periodic updates
+    let proxy = event_loop.create_proxy();
+    std::thread::spawn(move || {
+        loop {
+            std::thread::sleep(Duration::from_secs(1));
+            let _ = proxy.send_event(UserEvent::Tick);
+        }
+    });
+
+    // Menu items
+    let start_i = MenuItem::with_id("start", "Start Timer...", true, None);
+    let stop_i = MenuItem::with_id("stop", "Stop Timer", false, None);
+    let status_i = MenuItem::with_id("status", "No active timer", false, None);
+    // Pace against the monthly target, inserted under the status while one is set
+    let pace_i = MenuItem::with_id("pace", "", false, None);
+    // Starts the project a network rule suggests, inserted while one applies
+    let network_i = MenuItem::with_id("network_start", "", true, None);
+    let pomodoro_i = MenuItem::with_id("pomodoro", "Pomodoro: OFF", true, None);
+    let separator = PredefinedMenuItem::separator();
+    let quit_i = MenuItem::with_id("quit", "Quit Meter", true, None);
+
+    // Recent projects submenu
+    let projects_submenu = Submenu::new("Recent Projects", true);
+    // Stops the running timer and starts another project in one step
+    let switch_submenu = Submenu::new("Switch to", false);
+    // Stops the running timer a little while ago, for when it was left running
+    let stop_at_submenu = Submenu::new("Stop at…", false);
+    for (minutes, label) in [
+        (5, "5 minutes ago"),
+        (15, "15 minutes ago"),
+        (30, "30 minutes ago"),
+        (60, "1 hour ago"),
+    ] {
+        let item = MenuItem::with_id(format!("stop_at:{}", minutes), label, true, None);
+        let _ = stop_at_submenu.append(&item);
+    }
+
+    let tray_menu = Menu::new();
+    tray_menu
+        .append_items(&[
+            &status_i,
+            &separator,
+            &start_i,
+            &stop_i,
+            &stop_at_submenu,
+            &PredefinedMenuItem::separator(),
+            &pomodoro_i,
+            &PredefinedMenuItem::separator(),
+            &projects_submenu,
+            &switch_submenu,
+            &PredefinedMenuItem::separator(),
+            &quit_i,
+        ])
+        .unwrap();
+
+    let mut tray_icon = None;
+    let mut current_entry: Option<Entry> = None;
+    let mut recent_projects: Vec<String> = Vec::new();
+    // Project the "Switch to" submenu was last built for
+    let mut switch_from: Option<String> = None;
+    let mut last_tooltip: Option<String> = None;
+    let _hotkey_manager = hotkey_manager;
+
+    // Pomodoro state
+    let mut pomodoro_config: PomodoroConfig = db.get_pomodoro_config().unwrap_or_default();
+    let mut pomodoro_state = PomodoroState::Idle;
+    let mut pomodoro_interval_start: Option<chrono::DateTime<chrono::Utc>> = None;
+    let mut pomodoro_cycles_completed: u32 = 0;
+    let mut pomodoro_last_project: Option<String> = None;
+    let mut pomodoro_last_description: Option<String> = None;
+
+    // Running entry whose project has already been flagged as over its hour cap
+    let mut cap_warned_entry: Option<i64> = None;
+
+    // Month-end invoice reminders are checked once a day
+    let mut last_reminder_check: Option<NaiveDate> = None;
+    // The frontmost app is sampled for entry suggestions every so often
+    let mut last_activity_sample: Option<Instant> = None;
+    // Whether the icon shows the billing badge, rechecked every so often
+    let mut badge = billing_badge(&db);
+    let mut last_badge_check = Instant::now();
+    // Whether the pace line is in the menu, and the day it last warned
+    let mut pace_warned_day: Option<NaiveDate> = None;
+    let mut pace_shown = update_target_pace(&db, &tray_menu, &pace_i, false, &mut pace_warned_day);
+    // The Wi-Fi network last seen, the rule for it, and whether its menu item is shown
+    let mut last_network_check: Option<Instant> = None;
+    let mut network_ssid: Option<String> = None;
+    let mut network_rule: Option<NetworkRule> = None;
+    let mut network_shown = false;
+
+    event_loop.run(move |event, _, control_flow| {
+        *control_flow = ControlFlow::Wait;
+
+        match event {
+            Event::NewEvents(tao::event::StartCause::Init) => {
+                set_activation_policy_accessory();
+
+                // Check for active timer on startup
+                current_entry = db.get_active_entry().unwrap_or(None);
+                let is_running = current_entry.is_some();
+
+                // Load Pomodoro config
+                pomodoro_config = db.get_pomodoro_config().unwrap_or_default();
+
+                // If timer is running and Pomodoro is enabled, set state to Working
+                if is_running && pomodoro_config.enabled {
+                    pomodoro_state = PomodoroState::Working;
+                    pomodoro_interval_start = current_entry.as_ref().map(|e| e.start);
+                }
+
+                // Load recent projects
+                recent_projects = load_recent_projects(&db);
+
+                // Update menu state
+                update_menu_state(
+                    &status_i,
+                    &start_i,
+                    &stop_i,
+                    &current_entry,
+                    &pomodoro_state,
+                );
+                update_pomodoro_menu(&pomodoro_i, &pomodoro_config);
+                update_projects_submenu(&projects_submenu, &recent_projects);
+
+                let elapsed = current_entry
+                    .as_ref()
+                    .map(|e| (Utc::now() - e.start).num_seconds());
+                let total_secs = if pomodoro_config.enabled {
+                    Some(pomodoro_config.work_duration as i64 * 60)
+                } else {
+                    None
+                };
+
+                tray_icon = Some(
+                    TrayIconBuilder::new()
+                        .with_menu(Box::new(tray_menu.clone()))
+                        .with_tooltip("Meter - Time Tracking")
+                        .with_icon(create_icon(
+                            is_running,
+                            elapsed,
+                            pomodoro_config.enabled,
+                            &pomodoro_state,
+                            total_secs,
+                            badge,
+                        ))
+                        .build()
+                        .unwrap(),
+                );
+
+                #[cfg(target_os = "macos")]
+                {
+                    use objc2_core_foundation::CFRunLoop;
+                    CFRunLoop::main().unwrap().wake_up();
+                }
+            }
+
+            Event::UserEvent(UserEvent::Tick) => {
+                let today = Local::now().date_naive();
+                if last_reminder_check != Some(today) {
+                    last_reminder_check = Some(today);
+                    if let Ok(reminders) = db.invoice_reminders(today) {
+                        if !reminders.is_empty() {
+                            notify_invoice_reminders(&db, &reminders);
+                        }
+                    }
+                    if let Ok(sent) = dunning::send_due(&db, today) {
+                        for s in &sent {
+                            notify_overdue(&db, s);
+                        }
+                    }
+                }
+
+                // Refresh Pomodoro config from DB
+                pomodoro_config = db.get_pomodoro_config().unwrap_or_default();
+                update_pomodoro_menu(&pomodoro_i, &pomodoro_config);
+
+                // Refresh state from database
+                let new_entry = db.get_active_entry().unwrap_or(None);
+                let is_running = new_entry.is_some();
+
+                // Detect external timer changes
+                let timer_changed = match (&current_entry, &new_entry) {
+                    (Some(old), Some(new)) => old.id != new.id,
+                    (Some(_), None) => true,
+                    (None, Some(_)) => true,
+                    (None, None) => false,
+                };
+
+                if timer_changed {
+                    if new_entry.is_some() && pomodoro_config.enabled {
+                        // Timer started externally
+                        pomodoro_state = PomodoroState::Working;
+                        pomodoro_interval_start = Some(Utc::now());
+                    } else if new_entry.is_none() && pomodoro_state == PomodoroState::Working {
+                        // Timer stopped externally
+                        pomodoro_state = PomodoroState::Idle;
+                        pomodoro_interval_start = None;
+                    }
+                }
+
+                current_entry = new_entry;
+
+                // Rebuild the switch list when the running project changes
+                let running = current_entry.as_ref().map(|e| e.project.clone());
+                if running != switch_from {
+                    let recent = load_recent_projects(&db);
+                    update_switch_submenu(&switch_submenu, &recent, running.as_deref());
+                    stop_at_submenu.set_enabled(running.is_some());
+                    switch_from = running;
+                }
+
+                // Stop the timer once the user has been away longer than the
+                // project allows, ending the entry when they left
+                if let Some(entry) = &current_entry {
+                    let default_minutes = db
+                        .get_timer_settings()
+                        .map(|s| s.idle_stop_minutes)
+                        .unwrap_or(0);
+                    let limit = db
+                        .get_project_by_name(&entry.project)
+                        .ok()
+                        .flatten()
+                        .and_then(|p| p.idle_limit(default_minutes));
+                    let idle = idle_secs();
+                    if limit.is_some_and(|minutes| idle >= minutes * 60) {
+                        let left_at = Utc::now() - chrono::Duration::seconds(idle);
+                        if let Ok(Some(stopped)) = db.stop_active_timer_at(left_at) {
+                            notify_idle_stop(&db, &stopped.project, idle / 60);
+                            // Flagged before any midnight split, so each part is flagged
+                            let _ = db.flag_for_review(&[stopped.id], ReviewReason::Idle);
+                            finish_stopped_entry(&db, &stopped);
+                        }
+                        current_entry = None;
+                        if pomodoro_state == PomodoroState::Working {
+                            pomodoro_state = PomodoroState::Idle;
+                            pomodoro_interval_start = None;
+                        }
+                    }
+                }
+
+                if last_badge_check.elapsed() >= BADGE_CHECK_INTERVAL {
+                    last_badge_check = Instant::now();
+                    badge = billing_badge(&db);
+                    pace_shown = update_target_pace(
+                        &db,
+                        &tray_menu,
+                        &pace_i,
+                        pace_shown,
+                        &mut pace_warned_day,
+                    );
+                }
+
+                // With network rules set, watch the Wi-Fi network and suggest its
+                // project when the Mac joins one of theirs with no timer running
+                if last_network_check.is_none_or(|t| t.elapsed() >= NETWORK_CHECK_INTERVAL) {
+                    last_network_check = Some(Instant::now());
+                    let has_rules = db.list_network_rules().is_ok_and(|rules| !rules.is_empty());
+                    let ssid = if has_rules { current_ssid() } else { None };
+                    network_rule = ssid
+                        .as_deref()
+                        .and_then(|ssid| db.network_rule_for(ssid).ok().flatten());
+                    if ssid != network_ssid {
+                        if let (Some(rule), None) = (&network_rule, &current_entry) {
+                            notify_network_rule(&db, rule);
+                        }
+                        network_ssid = ssid;
+                    }
+                }
+                network_shown = update_network_suggestion(
+                    &tray_menu,
+                    &network_i,
+                    network_rule.as_ref().filter(|_| current_entry.is_none()),
+                    network_shown,
+                    pace_shown,
+                );
+
+                let current_target = current_entry
+                    .as_ref()
+                    .and_then(|e| db.timer_target(e.id).ok().flatten());
+
+                // While no timer runs, note the frontmost app so the time can be
+                // suggested as an entry later (opt-in, kept in the local database)
+                if current_entry.is_none()
+                    && last_activity_sample.is_none_or(|t| t.elapsed() >= ACTIVITY_SAMPLE_INTERVAL)
+                {
+                    last_activity_sample = Some(Instant::now());
+                    if db
+                        .get_timer_settings()
+                        .is_ok_and(|settings| settings.track_activity)
+                    {
+                        if let Some(app) = frontmost_app() {
+                            let _ = db.record_activity(&app, Utc::now());
+                        }
+                    }
+                }
+
+                // Announce once when a countdown timer reaches its target
+                if let (Some(entry), Some(target)) = (&current_entry, current_target) {
+                    if (Utc::now() - entry.start).num_seconds() >= target
+                        && db.claim_target_notification(entry.id).unwrap_or(false)
+                    {
+                        notify_target_reached(&db, &entry.project, target);
+                    }
+                }
+
+                // Warn once per entry when its project is at or past its hour cap,
+                // whether it started that way or the running timer just crossed it
+                if let Some(entry) = &current_entry {
+                    if cap_warned_entry != Some(entry.id) {
+                        if let Ok(Some(usage)) = db.project_cap_usage(&entry.project, Utc::now()) {
+                            if usage.is_reached() {
+                                cap_warned_entry = Some(entry.id);
+                                notify_cap_reached(&db, &entry.project, &usage.summary());
+                            }
+                        }
+                    }
+                }
+
+                // Pomodoro state machine
+                if pomodoro_config.enabled {
+                    if let Some(interval_start) = pomodoro_interval_start {
+                        let elapsed_secs = (Utc::now() - interval_start).num_seconds();
+
+                        match pomodoro_state {
+                            PomodoroState::Working => {
+                                let work_secs = pomodoro_config.work_duration as i64 * 60;
+                                if elapsed_secs >= work_secs {
+                                    // Work period complete
+                                    if let Some(ref entry) = current_entry {
+                                        pomodoro_last_project = Some(entry.project.clone());
+                                        pomodoro_last_description = Some(entry.description.clone());
+                                    }
+                                    if let Ok(Some(stopped)) = db.stop_active_timer() {
+                                        finish_stopped_entry(&db, &stopped);
+                                    }
+                                    current_entry = None;
+                                    pomodoro_state = PomodoroState::WorkComplete;
+                                    pomodoro_interval_start = None;
+                                    notify_work_complete(&db);
+                                }
+                            }
+                            PomodoroState::OnBreak => {
+                                let is_long = (pomodoro_cycles_completed + 1)
+                                    >= pomodoro_config.cycles_before_long as u32;
+                                let break_secs = if is_long {
+                                    pomodoro_config.long_break as i64 * 60
+                                } else {
+                                    pomodoro_config.short_break as i64 * 60
+                                };
+
+                                if elapsed_secs >= break_secs {
+                                    // Break complete
+                                    pomodoro_state = PomodoroState::BreakComplete;
+                                    pomodoro_interval_start = None;
+                                    notify_break_complete(&db);
+                                }
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+
+                // Update menu state
+                update_menu_state(
+                    &status_i,
+                    &start_i,
+                    &stop_i,
+                    &current_entry,
+                    &pomodoro_state,
+                );
+                if let (Some(entry), Some(target)) = (&current_entry, current_target) {
+                    let remaining = target - (Utc::now() - entry.start).num_seconds();
+                    status_i.set_text(if remaining >= 0 {
+                        format!("{} - {} left", entry.project, format_duration(remaining))
+                    } else {
+                        format!("{} - {} over", entry.project, format_duration(-remaining))
+                    });
+                }
+
+                // Calculate values for icon
+                let elapsed = match pomodoro_state {
+                    PomodoroState::Working => {
+                        pomodoro_interval_start.map(|s| (Utc::now() - s).num_seconds())
+                    }
+                    PomodoroState::OnBreak => {
+                        pomodoro_interval_start.map(|s| (Utc::now() - s).num_seconds())
+                    }
+                    _ => current_entry
+                        .as_ref()
+                        .map(|e| (Utc::now() - e.start).num_seconds()),
+                };
+
+                let total_secs = match pomodoro_state {
+                    PomodoroState::Working => Some(pomodoro_config.work_duration as i64 * 60),
+                    PomodoroState::OnBreak => {
+                        let is_long = (pomodoro_cycles_completed + 1)
+                            >= pomodoro_config.cycles_before_long as u32;
+                        if is_long {
+                            Some(pomodoro_config.long_break as i64 * 60)
+                        } else {
+                            Some(pomodoro_config.short_break as i64 * 60)
+                        }
+                    }
+                    _ => None,
+                };
+
+                // Update icon
+                if let Some(ref tray) = tray_icon {
+                    let _ = tray.set_icon(Some(create_icon(
+                        is_running,
+                        elapsed,
+                        pomodoro_config.enabled,
+                        &pomodoro_state,
+                        total_secs,
+                        badge,
+                    )));
+                }
+
+                // Update tooltip
+                let new_tooltip = match pomodoro_state {
+                    PomodoroState::Working => {
+                        if let Some(remaining) = total_secs.and_then(|t| elapsed.map(|e| t - e)) {
+                            format!(
+                                "Meter - Working ({} remaining)",
+                                format_remaining(remaining.max(0))
+                            )
+                        } else {
+                            "Meter - Working".to_string()
+                        }
+                    }
+                    PomodoroState::OnBreak => {
+                        if let Some(remaining) = total_secs.and_then(|t| elapsed.map(|e| t - e)) {
+                            format!(
+                                "Meter - Break ({} remaining)",
+                                format_remaining(remaining.max(0))
+                            )
+                        } else {
+                            "Meter - Break".to_string()
+                        }
+                    }
+                    PomodoroState::WorkComplete => {
+                        "Meter - Work complete! Start break?".to_string()
+                    }
+                    PomodoroState::BreakComplete => {
+                        "Meter - Break complete! Resume work?".to_string()
+                    }
+                    PomodoroState::Idle => {
+                        if let Some(entry) = &current_entry {
+                            let elapsed = (Utc::now() - entry.start).num_seconds();
+                            format!("Meter - {} ({})", entry.project, format_duration(elapsed))
+                        } else {
+                            "Meter - No active timer".to_string()
+                        }
+                    }
+                };
+
+                if last_tooltip.as_ref() != Some(&new_tooltip) {
+                    if let Some(tray) = &tray_icon {
+                        let _ = tray.set_tooltip(Some(&new_tooltip));
+                    }
+                    last_tooltip = Some(new_tooltip);
+                }
+            }
+
+            Event::UserEvent(UserEvent::MenuEvent(event)) => {
+                let id = event.id.0.as_str();
+
+                if id == "quit" {
+                    tray_icon.take();
+                    *control_flow = ControlFlow::Exit;
+                } else if id == "pomodoro" {
+                    // Toggle Pomodoro mode
+                    pomodoro_config.enabled = !pomodoro_config.enabled;
+                    let _ = db.set_pomodoro_enabled(pomodoro_config.enabled);
+                    update_pomodoro_menu(&pomodoro_i, &pomodoro_config);
+
+                    if pomodoro_config.enabled {
+                        if current_entry.is_some() {
+                            pomodoro_state = PomodoroState::Working;
+                            pomodoro_interval_start = Some(Utc::now());
+                        }
+                    } else {
+                        pomodoro_state = PomodoroState::Idle;
+                        pomodoro_interval_start = None;
+                        pomodoro_cycles_completed = 0;
+                    }
+                } else if id == "stop" || id.starts_with("stop_at:") {
+                    let minutes_ago = id
+                        .strip_prefix("stop_at:")
+                        .and_then(|m| m.parse().ok())
+                        .unwrap_or(0);
+                    let end = Utc::now() - chrono::Duration::minutes(minutes_ago);
+                    if let Ok(Some(stopped)) = db.stop_active_timer_at(end) {
+                        finish_stopped_entry(&db, &stopped);
+                        current_entry = None;
+                        pomodoro_state = PomodoroState::Idle;
+                        pomodoro_interval_start = None;
+                        pomodoro_cycles_completed = 0;
+                        update_menu_state(
+                            &status_i,
+                            &start_i,
+                            &stop_i,
+                            &current_entry,
+                            &pomodoro_state,
+                        );
+                        if let Some(ref tray) = tray_icon {
+                            let _ = tray.set_icon(Some(create_icon(
+                                false,
+                                None,
+                                pomodoro_config.enabled,
+                                &pomodoro_state,
+                                None,
+                                badge,
+                            )));
+                            let _ = tray.set_tooltip(Some("Meter - Timer stopped"));
+                        }
+                    }
+                } else if id == "start_break" {
+                    // Start break (from WorkComplete state)
+                    if pomodoro_state == PomodoroState::WorkComplete {
+                        pomodoro_state = PomodoroState::OnBreak;
+                        pomodoro_interval_start = Some(Utc::now());
+                    }
+                } else if id == "resume_work" {
+                    // Resume work (from BreakComplete state)
+                    if pomodoro_state == PomodoroState::BreakComplete {
+                        pomodoro_cycles_completed += 1;
+                        if pomodoro_cycles_completed >= pomodoro_config.cycles_before_long as u32 {
+                            pomodoro_cycles_completed = 0;
+                        }
+                        pomodoro_state = PomodoroState::Idle;
+                        pomodoro_interval_start = None;
+
+                        // Start new timer with last project
+                        let project = pomodoro_last_project
+                            .clone()
+                            .unwrap_or_else(|| "Work".to_string());
+                        let description = pomodoro_last_description
+                            .clone()
+                            .unwrap_or_else(|| DEFAULT_DESCRIPTION.to_string());
+                        let entry = Entry {
+                            id: 0,
+                            project: project.clone(),
+                            description,
+                            start: Utc::now(),
+                            end: None,
+                            status: EntryStatus::Open,
+                            days: None,
+                            issue: None,
+                            location: None,
+                        };
+                        if db.insert(&entry).is_ok() {
+                            current_entry = db.get_active_entry().unwrap_or(None);
+                            pomodoro_state = PomodoroState::Working;
+                            pomodoro_interval_start = Some(Utc::now());
+                        }
+                    }
+                } else if id.starts_with("switch:") {
+                    let project = id.strip_prefix("switch:").unwrap_or("Work");
+                    let description = db
+                        .default_description(project)
+                        .unwrap_or_else(|_| DEFAULT_DESCRIPTION.to_string());
+                    if let Ok((stopped, started)) = db.switch_timer(project, &description) {
+                        if let Some(stopped) = stopped {
+                            finish_stopped_entry(&db, &stopped);
+                        }
+                        // A running Pomodoro interval carries on across the switch
+                        current_entry = Some(started);
+                        pomodoro_last_project = Some(project.to_string());
+                        pomodoro_last_description = Some(description);
+
+                        update_menu_state(
+                            &status_i,
+                            &start_i,
+                            &stop_i,
+                            &current_entry,
+                            &pomodoro_state,
+                        );
+                        if let Some(ref tray) = tray_icon {
+                            let _ =
+                                tray.set_tooltip(Some(format!("Meter - Switched to: {}", project)));
+                        }
+                    }
+                } else if id.starts_with("project:") || id == "network_start" {
+                    // A network rule's suggestion also gives the entry its location
+                    let (project, location) = match (id.strip_prefix("project:"), &network_rule) {
+                        (Some(project), _) => (project.to_string(), None),
+                        (None, Some(rule)) => (rule.project.clone(), rule.location.clone()),
+                        (None, None) => return,
+                    };
+                    let description = db
+                        .default_description(&project)
+                        .unwrap_or_else(|_| DEFAULT_DESCRIPTION.to_string());
+                    let entry = Entry {
+                        id: 0,
+                        project: project.clone(),
+                        description: description.clone(),
+                        start: Utc::now(),
+                        end: None,
+                        status: EntryStatus::Open,
+                        days: None,
+                        issue: None,
+                        location,
+                    };
+                    if db.insert(&entry).is_ok() {
+                        current_entry = db.get_active_entry().unwrap_or(None);
+                        pomodoro_last_project = Some(project);
+                        pomodoro_last_description = Some(description);
+
+                        if pomodoro_config.enabled {
+                            pomodoro_state = PomodoroState::Working;
+                            pomodoro_interval_start = Some(Utc::now());
+                        }
+
+                        update_menu_state(
+                            &status_i,
+                            &start_i,
+                            &stop_i,
+                            &current_entry,
+                            &pomodoro_state,
+                        );
+                        if let Some(ref tray) = tray_icon {
+                            let total = if pomodoro_config.enabled {
+                                Some(pomodoro_config.work_duration as i64 * 60)
+                            } else {
+                                None
+                            };
+                            let _ = tray.set_icon(Some(create_icon(
+                                true,
+                                Some(0),
+                                pomodoro_config.enabled,
+                                &pomodoro_state,
+                                total,
+                                badge,
+                            )));
+                        }
+                    }
+                }
+            }
+
+            Event::UserEvent(UserEvent::TrayIconEvent(_event)) => {
+                // Handle tray icon clicks if needed
+            }
+
+            Event::UserEvent(UserEvent::HotKey(event)) => {
+                if event.id == hotkey_id {
+                    // Handle based on Pomodoro state
+                    match pomodoro_state {
+                        PomodoroState::WorkComplete => {
+                            // Start break
+                            pomodoro_state = PomodoroState::OnBreak;
+                            pomodoro_interval_start = Some(Utc::now());
+                        }
+                        PomodoroState::BreakComplete => {
+                            // Resume work
+                            pomodoro_cycles_completed += 1;
+                            if pomodoro_cycles_completed
+                                >= pomodoro_config.cycles_before_long as u32
+                            {
+                                pomodoro_cycles_completed = 0;
+                            }
+
+                            let project = pomodoro_last_project
+                                .clone()
+                                .unwrap_or_else(|| "Work".to_string());
+                            let description = pomodoro_last_description
+                                .clone()
+                                .unwrap_or_else(|| DEFAULT_DESCRIPTION.to_string());
+                            let entry = Entry {
+                                id: 0,
+                                project,
+                                description,
+                                start: Utc::now(),
+                                end: None,
+                                status: EntryStatus::Open,
+                                days: None,
+                                issue: None,
+                                location: None,
+                            };
+                            if db.insert(&entry).is_ok() {
+                                current_entry = db.get_active_entry().unwrap_or(None);
+                                pomodoro_state = PomodoroState::Working;
+                                pomodoro_interval_start = Some(Utc::now());
+                            }
+                        }
+                        PomodoroState::OnBreak => {
+                            // During break, hotkey does nothing
+                        }
+                        _ => {
+                            // Normal toggle behavior
+                            if current_entry.is_some() {
+                                if let Ok(Some(stopped)) = db.stop_active_timer() {
+                                    finish_stopped_entry(&db, &stopped);
+                                    current_entry = None;
+                                    pomodoro_state = PomodoroState::Idle;
+                                    pomodoro_interval_start = None;
+                                    pomodoro_cycles_completed = 0;
+                                    update_menu_state(
+                                        &status_i,
+                                        &start_i,
+                                        &stop_i,
+                                        &current_entry,
+                                        &pomodoro_state,
+                                    );
+                                    if let Some(ref tray) = tray_icon {
+                                        let _ = tray.set_icon(Some(create_icon(
+                                            false,
+                                            None,
+                                            pomodoro_config.enabled,
+                                            &pomodoro_state,
+                                            None,
+                                            badge,
+                                        )));
+                                        let _ = tray
+                                            .set_tooltip(Some("Meter - Timer stopped via hotkey"));
+                                    }
+                                }
+                            } else {
+                                let project = recent_projects
+                                    .first()
+                                    .cloned()
+                                    .unwrap_or_else(|| "Work".to_string());
+                                let description = db
+                                    .default_description(&project)
+                                    .unwrap_or_else(|_| DEFAULT_DESCRIPTION.to_string());
+                                let entry = Entry {
+                                    id: 0,
+                                    project: project.clone(),
+                                    description: description.clone(),
+                                    start: Utc::now(),
+                                    end: None,
+                                    status: EntryStatus::Open,
+                                    days: None,
+                                    issue: None,
+                                    location: None,
+                                };
+                                if db.insert(&entry).is_ok() {
+                                    current_entry = db.get_active_entry().unwrap_or(None);
+                                    pomodoro_last_project = Some(project.clone());
+                                    pomodoro_last_description = Some(description);
+
+                                    if pomodoro_config.enabled {
+                                        pomodoro_state = PomodoroState::Working;
+                                        pomodoro_interval_start = Some(Utc::now());
+                                    }
+
+                                    update_menu_state(
+                                        &status_i,
+                                        &start_i,
+                                        &stop_i,
+                                        &current_entry,
+                                        &pomodoro_state,
+                                    );
+                                    if let Some(ref tray) = tray_icon {
+                                        let total = if pomodoro_config.enabled {
+                                            Some(pomodoro_config.work_duration as i64 * 60)
+                                        } else {
+                                            None
+                                        };
+                                        let _ = tray.set_icon(Some(create_icon(
+                                            true,
+                                            Some(0),
+                                            pomodoro_config.enabled,
+                                            &pomodoro_state,
+                                            total,
+                                            badge,
+                                        )));
+                                        let _ = tray.set_tooltip(Some(format!(
+                                            "Meter - Started: {}",
+                                            project
+                                        )));
+                                    }
+
+                                    recent_projects = load_recent_projects(&db);
+                                    update_projects_submenu(&projects_submenu, &recent_projects);
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+
+            _ => {}
+        }
+    });
+}
+
+fn update_menu_state(
+    status_i: &MenuItem,
+    start_i: &MenuItem,
+    stop_i: &MenuItem,
+    current_entry: &Option<Entry>,
+    pomodoro_state: &PomodoroState,
+) {
+    match pomodoro_state {
+        PomodoroState::WorkComplete => {
+            status_i.set_text("Work complete! Click to start break");
+            start_i.set_enabled(false);
+            stop_i.set_enabled(false);
+        }
+        PomodoroState::BreakComplete => {
+            status_i.set_text("Break complete! Click to resume");
+            start_i.set_enabled(false);
+            stop_i.set_enabled(false);
+        }
+        PomodoroState::OnBreak => {
+            status_i.set_text("On break...");
+            start_i.set_enabled(false);
+            stop_i.set_enabled(false);
+        }
+        _ => {
+            if let Some(entry) = current_entry {
+                let elapsed = (Utc::now() - entry.start).num_seconds();
+                status_i.set_text(format!("{} - {}", entry.project, format_duration(elapsed)));
+                start_i.set_enabled(false);
+                stop_i.set_enabled(true);
+            } else {
+                status_i.set_text("No active timer");
+                start_i.set_enabled(true);
+                stop_i.set_enabled(false);
+            }
+        }
+    }
+}
+
+fn update_pomodoro_menu(pomodoro_i: &MenuItem, config: &PomodoroConfig) {
+    if config.enabled {
+        pomodoro_i.set_text(format!("Pomodoro: ON ({}m)", config.work_duration));
+    } else {
+        pomodoro_i.set_text("Pomodoro: OFF");
+    }
+}
+
+/// The last five projects with entries, newest first
+fn load_recent_projects(db: &Db) -> Vec<String> {
+    let Ok(entries) = db.list(None) else {
+        return Vec::new();
+    };
+    let mut seen = std::collections::HashSet::new();
+    entries
+        .iter()
+        .filter(|e| seen.insert(e.project.clone()))
+        .take(5)
+        .map(|e| e.project.clone())
+        .collect()
+}
+
+/// Fill the "Switch to" submenu with the recent projects other than the running one.
+/// It is disabled while no timer is running.
+fn update_switch_submenu(submenu: &Submenu, projects: &[String], running: Option<&str>) {
+    while submenu.remove_at(0).is_some() {}
+    for project in projects.iter().filter(|p| Some(p.as_str()) != running) {
+        let item = MenuItem::with_id(format!("switch:{}", project), project, true, None);
+        let _ = submenu.append(&item);
+    }
+    submenu.set_enabled(running.is_some() && !submenu.items().is_empty());
+}
+
+fn update_projects_submenu(submenu: &Submenu, projects: &[String]) {
+    for project in projects {
+        let item = MenuItem::with_id(
+            format!("project:{}", project),
+            format!("Start: {}", project),
+            true,
+            None,
+        );
+        let _ = submenu.append(&item);
+    }
+}
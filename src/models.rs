@@ -1,57 +1,1945 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Datelike, Days, Local, Months, NaiveDate, NaiveTime, TimeZone, Utc};
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, ValueRef};
 use rusqlite::{Connection, Result, params};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Entry {
     pub id: i64,
     pub project: String,
     pub description: String,
     pub start: DateTime<Utc>,
     pub end: Option<DateTime<Utc>>,
-    pub billed: bool,
+    pub status: EntryStatus,
+    /// Days worked, for entries logged by the day (`meter add --days`) rather
+    /// than timed. These start and end at the same instant and are billed at a
+    /// daily project rate.
+    pub days: Option<f64>,
+    /// Issue tracker reference, such as a Jira key (`ACME-123`), a GitHub
+    /// issue (`#42`) or a URL
+    pub issue: Option<String>,
+    /// Where the work was done: home, client-site, travel or free text (see
+    /// `normalize_location`)
+    pub location: Option<String>,
+}
+
+impl Entry {
+    /// The description followed by the issue reference, if there is one
+    pub fn description_with_issue(&self) -> String {
+        match &self.issue {
+            Some(issue) => format!("{} [{}]", self.description, issue),
+            None => self.description.clone(),
+        }
+    }
+}
+
+/// Where an entry is in the billing workflow:
+/// open → submitted → billed → invoiced → paid
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum EntryStatus {
+    #[default]
+    Open,
+    Submitted,
+    Billed,
+    Invoiced,
+    Paid,
+}
+
+impl EntryStatus {
+    pub const ALL: [EntryStatus; 5] = [
+        EntryStatus::Open,
+        EntryStatus::Submitted,
+        EntryStatus::Billed,
+        EntryStatus::Invoiced,
+        EntryStatus::Paid,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EntryStatus::Open => "open",
+            EntryStatus::Submitted => "submitted",
+            EntryStatus::Billed => "billed",
+            EntryStatus::Invoiced => "invoiced",
+            EntryStatus::Paid => "paid",
+        }
+    }
+
+    /// Whether an entry in this status may move to `next`.
+    /// Open, submitted and billed entries can move freely between each other;
+    /// once invoiced, an entry can only go forward to paid.
+    pub fn can_transition_to(&self, next: EntryStatus) -> bool {
+        use EntryStatus::*;
+        matches!(
+            (self, next),
+            (Open, Submitted)
+                | (Open, Billed)
+                | (Submitted, Open)
+                | (Submitted, Billed)
+                | (Billed, Open)
+                | (Billed, Submitted)
+                | (Billed, Invoiced)
+                | (Invoiced, Paid)
+        )
+    }
+
+    /// The statuses an entry may be in to move to `next`.
+    pub fn allowed_from(next: EntryStatus) -> Vec<EntryStatus> {
+        Self::ALL
+            .into_iter()
+            .filter(|s| s.can_transition_to(next))
+            .collect()
+    }
+}
+
+impl std::fmt::Display for EntryStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for EntryStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|status| status.as_str().eq_ignore_ascii_case(s.trim()))
+            .ok_or_else(|| {
+                format!(
+                    "Unknown status '{}' (expected open, submitted, billed, invoiced or paid)",
+                    s
+                )
+            })
+    }
+}
+
+impl ToSql for EntryStatus {
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.as_str()))
+    }
+}
+
+impl FromSql for EntryStatus {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        value
+            .as_str()?
+            .parse()
+            .map_err(|e: String| FromSqlError::Other(e.into()))
+    }
+}
+
+/// Why an entry needs a look before it is invoiced: meter changed or created it
+/// rather than you, or you flagged it yourself
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, JsonSchema,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum ReviewReason {
+    /// Ended at the moment you left, by the menu bar app's idle stop
+    Idle,
+    /// Imported from another time tracker
+    Imported,
+    /// Split at midnight into one entry per day
+    Split,
+    /// Flagged by hand
+    Manual,
+}
+
+impl ReviewReason {
+    pub const ALL: [ReviewReason; 4] = [
+        ReviewReason::Idle,
+        ReviewReason::Imported,
+        ReviewReason::Split,
+        ReviewReason::Manual,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReviewReason::Idle => "idle",
+            ReviewReason::Imported => "imported",
+            ReviewReason::Split => "split",
+            ReviewReason::Manual => "manual",
+        }
+    }
+
+    /// What happened to the entry, for lists of flagged entries
+    pub fn describe(&self) -> &'static str {
+        match self {
+            ReviewReason::Idle => "stopped when idle",
+            ReviewReason::Imported => "imported",
+            ReviewReason::Split => "split at midnight",
+            ReviewReason::Manual => "flagged by hand",
+        }
+    }
+}
+
+impl std::fmt::Display for ReviewReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for ReviewReason {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|reason| reason.as_str().eq_ignore_ascii_case(s.trim()))
+            .ok_or_else(|| {
+                format!(
+                    "Unknown review reason '{}' (expected idle, imported, split or manual)",
+                    s
+                )
+            })
+    }
+}
+
+impl ToSql for ReviewReason {
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.as_str()))
+    }
+}
+
+impl FromSql for ReviewReason {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        value
+            .as_str()?
+            .parse()
+            .map_err(|e: String| FromSqlError::Other(e.into()))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Project {
+    pub id: i64,
+    pub name: String,
+    pub rate: Option<Money>,
+    pub currency: Option<String>,
+    pub client_id: Option<i64>,
+    /// Maximum hours per `cap_period`, if the contract caps them
+    pub hour_cap: Option<f64>,
+    pub cap_period: CapPeriod,
+    /// What `rate` is charged per
+    pub billing_mode: BillingMode,
+    /// Minutes away from the computer after which the menu bar app stops this
+    /// project's timer (0 = never; `None` = the timer settings' default)
+    pub idle_stop_minutes: Option<i64>,
+    /// Area of work or life the project is grouped under, such as "Client work"
+    /// or "Open source", for rolling up hours
+    #[serde(default)]
+    pub area: Option<String>,
+}
+
+/// How a project's rate turns into an invoice amount
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum BillingMode {
+    /// Rate per hour worked
+    #[default]
+    Hourly,
+    /// Rate per day with any time logged
+    Daily,
+    /// Rate is a flat fee per invoice, however long the work took
+    Fixed,
+}
+
+impl BillingMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BillingMode::Hourly => "hourly",
+            BillingMode::Daily => "daily",
+            BillingMode::Fixed => "fixed",
+        }
+    }
+
+    /// Unit shown after a rate, e.g. "/hr"
+    pub fn rate_suffix(&self) -> &'static str {
+        match self {
+            BillingMode::Hourly => "/hr",
+            BillingMode::Daily => "/day",
+            BillingMode::Fixed => " fixed",
+        }
+    }
+
+    /// Amount billed at `rate` for `hours` worked across `days` days
+    pub fn amount(&self, rate: Money, hours: f64, days: f64) -> Money {
+        match self {
+            BillingMode::Hourly => rate.times(hours),
+            BillingMode::Daily => rate.times(days),
+            BillingMode::Fixed => rate,
+        }
+    }
+}
+
+impl std::fmt::Display for BillingMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for BillingMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "hourly" | "hour" => Ok(BillingMode::Hourly),
+            "daily" | "day" => Ok(BillingMode::Daily),
+            "fixed" => Ok(BillingMode::Fixed),
+            _ => Err(format!(
+                "Unknown billing mode '{}' (expected hourly, daily or fixed)",
+                s
+            )),
+        }
+    }
+}
+
+impl ToSql for BillingMode {
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.as_str()))
+    }
+}
+
+impl FromSql for BillingMode {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        value
+            .as_str()?
+            .parse()
+            .map_err(|e: String| FromSqlError::Other(e.into()))
+    }
+}
+
+/// The period a project's hour cap applies to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum CapPeriod {
+    #[default]
+    Week,
+    Month,
+}
+
+impl CapPeriod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CapPeriod::Week => "week",
+            CapPeriod::Month => "month",
+        }
+    }
+
+    /// The local days `[from, to)` of the period containing `today`.
+    /// Weeks start on Monday.
+    pub fn range(&self, today: NaiveDate) -> (NaiveDate, NaiveDate) {
+        match self {
+            CapPeriod::Week => {
+                let monday = today - Days::new(today.weekday().num_days_from_monday() as u64);
+                (monday, monday + Days::new(7))
+            }
+            CapPeriod::Month => {
+                let first = today.with_day(1).unwrap_or(today);
+                let next = first.checked_add_months(Months::new(1)).unwrap_or(first);
+                (first, next)
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for CapPeriod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for CapPeriod {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "week" | "weekly" => Ok(CapPeriod::Week),
+            "month" | "monthly" => Ok(CapPeriod::Month),
+            _ => Err(format!("Unknown period '{}' (expected week or month)", s)),
+        }
+    }
+}
+
+impl ToSql for CapPeriod {
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.as_str()))
+    }
+}
+
+impl FromSql for CapPeriod {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        value
+            .as_str()?
+            .parse()
+            .map_err(|e: String| FromSqlError::Other(e.into()))
+    }
+}
+
+/// How much of a project's hour cap the current week or month has used
+#[derive(Debug, Clone)]
+pub struct CapUsage {
+    pub cap: f64,
+    pub period: CapPeriod,
+    pub used: f64,
+}
+
+impl CapUsage {
+    pub fn is_reached(&self) -> bool {
+        self.used >= self.cap
+    }
+
+    /// e.g. "12.5/20.0h this week"
+    pub fn summary(&self) -> String {
+        format!("{:.1}/{:.1}h this {}", self.used, self.cap, self.period)
+    }
+}
+
+/// Billable hours tracked this month against the monthly target
+#[derive(Debug, Clone)]
+pub struct TargetPace {
+    pub target: f64,
+    /// Hours this month on projects with a rate
+    pub billable: f64,
+    /// Local day the pace is measured on
+    pub today: NaiveDate,
+}
+
+impl TargetPace {
+    /// Hours the target calls for by the end of `today`, spread evenly over
+    /// the days of the month
+    pub fn expected(&self) -> f64 {
+        let (first, next) = CapPeriod::Month.range(self.today);
+        let days = (next - first).num_days() as f64;
+        let elapsed = (self.today - first).num_days() as f64 + 1.0;
+        self.target * elapsed / days
+    }
+
+    /// Whether billable hours trail the expected pace by more than `margin` hours
+    pub fn is_behind(&self, margin: f64) -> bool {
+        self.expected() - self.billable > margin
+    }
+
+    /// e.g. "on track: 62/120h by the 15th" or
+    /// "behind: 40/120h by the 15th, 60h expected"
+    pub fn summary(&self, margin: f64) -> String {
+        let progress = format!(
+            "{:.0}/{:.0}h by the {}",
+            self.billable,
+            self.target,
+            ordinal(self.today.day())
+        );
+        if self.is_behind(margin) {
+            format!("behind: {}, {:.0}h expected", progress, self.expected())
+        } else {
+            format!("on track: {}", progress)
+        }
+    }
+}
+
+/// Day of the month with its English suffix, e.g. "1st", "12th" or "23rd"
+fn ordinal(day: u32) -> String {
+    let suffix = match (day % 10, day % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+    format!("{}{}", day, suffix)
+}
+
+/// Which entries `Db::list_filtered` returns. Every condition left as `None`
+/// matches all entries.
+#[derive(Debug, Clone, Default)]
+pub struct EntryFilter {
+    pub status: Option<EntryStatus>,
+    pub project: Option<String>,
+    /// Entries that started at or after this moment
+    pub start_from: Option<DateTime<Utc>>,
+    /// Entries that started before this moment
+    pub start_before: Option<DateTime<Utc>>,
+    /// At most this many entries, the most recent first
+    pub limit: Option<usize>,
+}
+
+/// Lifetime totals for a project, for the projects overview
+#[derive(Debug, Clone, Default)]
+pub struct ProjectTotals {
+    pub total_hours: f64,
+    /// Hours in entries that haven't been invoiced yet (open, submitted or billed)
+    pub unbilled_hours: f64,
+    /// Distinct days with unbilled time plus unbilled day entries, for day-rate
+    /// projects
+    pub unbilled_days: f64,
+    pub last_entry: Option<DateTime<Utc>>,
+}
+
+impl ProjectTotals {
+    /// What the unbilled time would come to at the project's rate, if it has one
+    pub fn unbilled_amount(&self, project: &Project) -> Option<Money> {
+        let rate = project.rate?;
+        if self.unbilled_days == 0.0 {
+            return Some(Money::ZERO);
+        }
+        Some(
+            project
+                .billing_mode
+                .amount(rate, self.unbilled_hours, self.unbilled_days),
+        )
+    }
+}
+
+impl Project {
+    /// Minutes idle after which this project's timer is stopped, if ever
+    pub fn idle_limit(&self, default_minutes: i64) -> Option<i64> {
+        Some(self.idle_stop_minutes.unwrap_or(default_minutes)).filter(|m| *m > 0)
+    }
+
+    /// Format the rate with currency for display (e.g., "$150.00/hr", "95,00 €/day")
+    pub fn formatted_rate(&self) -> Option<String> {
+        let rate = self.rate?;
+        let currency = self.currency.as_deref().unwrap_or("$");
+        Some(format!(
+            "{}{}",
+            format_money(rate, currency),
+            self.billing_mode.rate_suffix()
+        ))
+    }
+}
+
+/// How amounts in each known currency are conventionally written: ISO code, symbol,
+/// whether the symbol follows the number, thousands separator, decimal separator
+/// and number of decimals
+const CURRENCIES: &[(&str, &str, bool, &str, &str, usize)] = &[
+    ("USD", "$", false, ",", ".", 2),
+    ("EUR", "€", true, ".", ",", 2),
+    ("GBP", "£", false, ",", ".", 2),
+    ("JPY", "¥", false, ",", ".", 0),
+    ("CHF", "CHF ", false, "'", ".", 2),
+    ("CAD", "CA$", false, ",", ".", 2),
+    ("AUD", "A$", false, ",", ".", 2),
+    ("SEK", "kr", true, " ", ",", 2),
+    ("INR", "₹", false, ",", ".", 2),
+];
+
+/// Decimal places `currency` is written with: 0 for currencies such as yen
+/// that have no cents, else 2
+pub fn currency_decimals(currency: &str) -> usize {
+    CURRENCIES
+        .iter()
+        .find(|(code, symbol, ..)| code.eq_ignore_ascii_case(currency) || symbol.trim() == currency)
+        .map_or(2, |(.., decimals)| *decimals)
+}
+
+/// Check a currency typed by the user: a three-letter ISO 4217 code (stored
+/// upper-case) or one of the known symbols.
+pub fn normalize_currency(input: &str) -> std::result::Result<String, String> {
+    let input = input.trim();
+    if input.len() == 3 && input.chars().all(|c| c.is_ascii_alphabetic()) {
+        return Ok(input.to_ascii_uppercase());
+    }
+    if CURRENCIES
+        .iter()
+        .any(|(_, symbol, ..)| symbol.trim() == input)
+    {
+        return Ok(input.to_string());
+    }
+    Err(format!(
+        "Unknown currency '{}': use an ISO code such as EUR or a symbol such as €",
+        input
+    ))
+}
+
+/// Check a rate typed by the user: a finite, non-negative number.
+pub fn validate_rate(rate: f64) -> std::result::Result<(), String> {
+    if rate.is_finite() && rate >= 0.0 {
+        Ok(())
+    } else {
+        Err(format!("Invalid rate {}: must be zero or more", rate))
+    }
+}
+
+/// An amount of money in minor units (cents), so subtotals, tax and totals add up
+/// exactly. Stored in the database as REAL and written to JSON as a plain number,
+/// both rounded to the cent.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Serialize, Deserialize,
+)]
+#[serde(from = "f64", into = "f64")]
+pub struct Money(i64);
+
+impl Money {
+    pub const ZERO: Money = Money(0);
+
+    pub fn from_cents(cents: i64) -> Self {
+        Money(cents)
+    }
+
+    /// Rounds to the nearest cent
+    pub fn from_f64(amount: f64) -> Self {
+        Money((amount * 100.0).round() as i64)
+    }
+
+    pub fn cents(self) -> i64 {
+        self.0
+    }
+
+    pub fn as_f64(self) -> f64 {
+        self.0 as f64 / 100.0
+    }
+
+    /// This amount times a quantity such as hours worked, rounded to the cent
+    pub fn times(self, quantity: f64) -> Money {
+        Money((self.0 as f64 * quantity).round() as i64)
+    }
+
+    /// `percent`% of this amount (e.g. tax), rounded to the cent
+    pub fn percent(self, percent: f64) -> Money {
+        self.times(percent / 100.0)
+    }
+
+    /// This amount rounded to `decimals` decimal places, e.g. to whole yen
+    /// with 0. Amounts are kept in cents, so 2 or more leaves it as it is.
+    pub fn round_to(self, decimals: usize) -> Money {
+        if decimals >= 2 {
+            return self;
+        }
+        let unit = 10i64.pow(2 - decimals as u32);
+        Money((self.0 as f64 / unit as f64).round() as i64 * unit)
+    }
+}
+
+impl std::ops::Add for Money {
+    type Output = Money;
+
+    fn add(self, other: Money) -> Money {
+        Money(self.0 + other.0)
+    }
+}
+
+impl std::ops::AddAssign for Money {
+    fn add_assign(&mut self, other: Money) {
+        self.0 += other.0;
+    }
+}
+
+impl std::iter::Sum for Money {
+    fn sum<I: Iterator<Item = Money>>(iter: I) -> Money {
+        Money(iter.map(|m| m.0).sum())
+    }
+}
+
+/// Plain amount with two decimals, e.g. "1234.56"; honours width and alignment
+impl std::fmt::Display for Money {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let sign = if self.0 < 0 { "-" } else { "" };
+        let cents = self.0.unsigned_abs();
+        f.pad(&format!("{}{}.{:02}", sign, cents / 100, cents % 100))
+    }
+}
+
+impl From<f64> for Money {
+    fn from(amount: f64) -> Self {
+        Money::from_f64(amount)
+    }
+}
+
+impl From<Money> for f64 {
+    fn from(amount: Money) -> Self {
+        amount.as_f64()
+    }
+}
+
+impl JsonSchema for Money {
+    fn schema_name() -> String {
+        "Money".to_string()
+    }
+
+    fn json_schema(generator: &mut schemars::r#gen::SchemaGenerator) -> schemars::schema::Schema {
+        f64::json_schema(generator)
+    }
+}
+
+impl ToSql for Money {
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.as_f64()))
+    }
+}
+
+impl FromSql for Money {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        f64::column_result(value).map(Money::from_f64)
+    }
+}
+
+/// Format an amount the way its currency is usually written, e.g. "$1,234.56",
+/// "1.234,56 €" or "CHF 1'234.56". Unknown codes are written after the number
+/// and unknown symbols before it.
+pub fn format_money(amount: Money, currency: &str) -> String {
+    format_money_in(amount, currency, NumberLocale::Currency)
+}
+
+/// Format an amount like `format_money`, with `locale`'s separators in place
+/// of the currency's own, e.g. "$1.234,56" for `NumberLocale::De`
+pub fn format_money_in(amount: Money, currency: &str, locale: NumberLocale) -> String {
+    let known = CURRENCIES.iter().find(|(code, symbol, ..)| {
+        code.eq_ignore_ascii_case(currency) || symbol.trim() == currency
+    });
+    let Some((_, symbol, symbol_after, thousands, decimal, decimals)) = known else {
+        let (thousands, decimal) = locale.separators().unwrap_or((",", "."));
+        let number = group_digits(amount, 2, thousands, decimal);
+        return if currency.len() == 3 && currency.chars().all(|c| c.is_ascii_alphabetic()) {
+            format!("{} {}", number, currency)
+        } else {
+            format!("{}{}", currency, number)
+        };
+    };
+    let (thousands, decimal) = locale.separators().unwrap_or((thousands, decimal));
+    let number = group_digits(amount, *decimals, thousands, decimal);
+    if *symbol_after {
+        format!("{} {}", number, symbol)
+    } else {
+        format!("{}{}", symbol, number)
+    }
+}
+
+/// Write `amount` with 2 or 0 decimals and the given separators
+fn group_digits(amount: Money, decimals: usize, thousands: &str, decimal: &str) -> String {
+    let cents = amount.cents().unsigned_abs();
+    let (whole, fraction) = if decimals == 0 {
+        ((cents + 50) / 100, String::new())
+    } else {
+        (cents / 100, format!("{}{:02}", decimal, cents % 100))
+    };
+    let whole = whole.to_string();
+    let mut grouped = String::new();
+    for (i, digit) in whole.chars().enumerate() {
+        if i > 0 && (whole.len() - i) % 3 == 0 {
+            grouped.push_str(thousands);
+        }
+        grouped.push(digit);
+    }
+    // Decided on the rounded value, so nothing prints as "-0"
+    let sign =
+        if amount < Money::ZERO && (whole != "0" || (decimals > 0 && !cents.is_multiple_of(100))) {
+            "-"
+        } else {
+            ""
+        };
+    format!("{}{}{}", sign, grouped, fraction)
+}
+
+/// Version of the database schema, recorded in `PRAGMA user_version`.
+/// Bump it whenever `init_all` adds or changes a table or column.
+pub const SCHEMA_VERSION: i64 = 39;
+
+/// A countdown timer's target, as kept in a `DatabaseExport`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct TimerTarget {
+    pub entry_id: i64,
+    pub target_secs: i64,
+    /// Whether its completion has been notified already
+    pub notified: bool,
+}
+
+/// An entry pushed to a sync service, as kept in a `DatabaseExport`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct SyncPush {
+    pub service: SyncService,
+    pub entry_id: i64,
+    pub remote_id: i64,
+    pub pushed_at: DateTime<Utc>,
+}
+
+/// Everything `meter export json` writes and `meter import json` restores, for
+/// moving to another machine. IDs are kept so invoices, projects and tags still
+/// point at the right clients and entries. Every table is included except
+/// recorded app activity, which never leaves the local database.
+///
+/// Fields marked `serde(default)` are missing from exports made by older
+/// versions of meter; settings missing that way keep the database's defaults.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DatabaseExport {
+    /// `SCHEMA_VERSION` of the database it was exported from
+    pub schema_version: i64,
+    pub exported_at: DateTime<Utc>,
+    pub profiles: Vec<BusinessProfile>,
+    pub clients: Vec<Client>,
+    pub projects: Vec<Project>,
+    pub entries: Vec<Entry>,
+    /// Tags by entry ID
+    pub entry_tags: BTreeMap<i64, Vec<String>>,
+    /// Why entries are flagged for review, by entry ID. Missing from exports
+    /// made before review flags existed.
+    #[serde(default)]
+    pub review_flags: BTreeMap<i64, Vec<ReviewReason>>,
+    pub invoices: Vec<Invoice>,
+    /// Missing from exports made before templates existed
+    #[serde(default)]
+    pub templates: Vec<EntryTemplate>,
+    pub timer_settings: TimerSettings,
+    pub notification_settings: NotificationSettings,
+    pub tui_settings: TuiSettings,
+    pub pomodoro: PomodoroConfig,
+    #[serde(default)]
+    pub timer_targets: Vec<TimerTarget>,
+    /// Colors by tag
+    #[serde(default)]
+    pub tag_colors: BTreeMap<String, String>,
+    #[serde(default)]
+    pub network_rules: Vec<NetworkRule>,
+    #[serde(default)]
+    pub time_off: Vec<TimeOff>,
+    /// Overtime multipliers by client ID
+    #[serde(default)]
+    pub overtime_rates: BTreeMap<i64, OvertimeRates>,
+    #[serde(default)]
+    pub expenses: Vec<Expense>,
+    #[serde(default)]
+    pub mileage_settings: Option<MileageSettings>,
+    /// Records of attached files; the files themselves aren't exported
+    #[serde(default)]
+    pub attachments: Vec<Attachment>,
+    #[serde(default)]
+    pub invoice_drafts: Vec<InvoiceDraft>,
+    #[serde(default)]
+    pub approval_sheets: Vec<ApprovalSheet>,
+    #[serde(default)]
+    pub payments: Vec<Payment>,
+    #[serde(default)]
+    pub email_settings: Option<EmailSettings>,
+    #[serde(default)]
+    pub inbox_settings: Option<InboxSettings>,
+    #[serde(default)]
+    pub mail_rules: Vec<MailRule>,
+    #[serde(default)]
+    pub mail_matches: Vec<MailMatch>,
+    #[serde(default)]
+    pub sync_settings: Option<SyncSettings>,
+    #[serde(default)]
+    pub sync_mappings: Vec<SyncMapping>,
+    #[serde(default)]
+    pub sync_pushes: Vec<SyncPush>,
+    #[serde(default)]
+    pub dunning_settings: Option<DunningSettings>,
+    #[serde(default)]
+    pub dunning_log: Vec<DunningLogEntry>,
+}
+
+/// Create or upgrade every table. Both the CLI/TUI and the menubar app call this
+/// so they always see the same schema.
+pub fn init_all(conn: &Connection) -> Result<()> {
+    init_db(conn)?;
+    init_timer_targets_db(conn)?;
+    init_entry_tags_db(conn)?;
+    init_review_flags_db(conn)?;
+    init_entry_templates_db(conn)?;
+    init_network_rules_db(conn)?;
+    init_activity_db(conn)?;
+    init_time_off_db(conn)?;
+    init_clients_db(conn)?;
+    init_overtime_db(conn)?;
+    init_projects_db(conn)?;
+    init_pomodoro_db(conn)?;
+    init_timer_settings_db(conn)?;
+    init_notification_settings_db(conn)?;
+    init_tui_settings_db(conn)?;
+    init_invoice_settings_db(conn)?;
+    init_email_settings_db(conn)?;
+    init_sync_db(conn)?;
+    init_expenses_db(conn)?;
+    init_attachments_db(conn)?;
+    init_invoices_db(conn)?;
+    init_invoice_drafts_db(conn)?;
+    init_approval_sheets_db(conn)?;
+    init_inbox_db(conn)?;
+    init_dunning_db(conn)?;
+    init_payments_db(conn)?;
+    conn.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+    Ok(())
+}
+
+pub fn init_db(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS entries (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            project TEXT NOT NULL,
+            description TEXT NOT NULL,
+            start TEXT NOT NULL,
+            end TEXT,
+            status TEXT NOT NULL DEFAULT 'open'
+        )",
+        params![],
+    )?;
+    // Older databases tracked a single billed flag; fold it into the status column
+    if column_exists(conn, "entries", "billed")? {
+        add_column_if_missing(conn, "entries", "status", "TEXT NOT NULL DEFAULT 'open'")?;
+        conn.execute(
+            "UPDATE entries SET status = 'billed' WHERE billed = 1",
+            params![],
+        )?;
+        conn.execute("ALTER TABLE entries DROP COLUMN billed", params![])?;
+    }
+    add_column_if_missing(conn, "entries", "days", "REAL")?;
+    add_column_if_missing(conn, "entries", "issue", "TEXT")?;
+    add_column_if_missing(conn, "entries", "location", "TEXT")?;
+    Ok(())
+}
+
+pub fn init_projects_db(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS projects (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE,
+            rate REAL,
+            currency TEXT DEFAULT '$',
+            client_id INTEGER REFERENCES clients(id),
+            hour_cap REAL,
+            cap_period TEXT NOT NULL DEFAULT 'week',
+            billing_mode TEXT NOT NULL DEFAULT 'hourly',
+            idle_stop_minutes INTEGER,
+            area TEXT
+        )",
+        params![],
+    )?;
+    add_column_if_missing(
+        conn,
+        "projects",
+        "client_id",
+        "INTEGER REFERENCES clients(id)",
+    )?;
+    add_column_if_missing(conn, "projects", "hour_cap", "REAL")?;
+    add_column_if_missing(
+        conn,
+        "projects",
+        "cap_period",
+        "TEXT NOT NULL DEFAULT 'week'",
+    )?;
+    add_column_if_missing(
+        conn,
+        "projects",
+        "billing_mode",
+        "TEXT NOT NULL DEFAULT 'hourly'",
+    )?;
+    add_column_if_missing(conn, "projects", "idle_stop_minutes", "INTEGER")?;
+    add_column_if_missing(conn, "projects", "area", "TEXT")?;
+    // Rates used to be stored as TEXT. Copying them into a REAL column converts every
+    // number; anything malformed stays text, so reading it reports an error instead
+    // of the rate silently looking unset.
+    if column_type(conn, "projects", "rate")?.eq_ignore_ascii_case("TEXT") {
+        conn.execute("ALTER TABLE projects ADD COLUMN rate_real REAL", params![])?;
+        conn.execute(
+            "UPDATE projects SET rate_real = NULLIF(TRIM(rate), '')",
+            params![],
+        )?;
+        conn.execute("ALTER TABLE projects DROP COLUMN rate", params![])?;
+        conn.execute(
+            "ALTER TABLE projects RENAME COLUMN rate_real TO rate",
+            params![],
+        )?;
+    }
+    Ok(())
+}
+
+/// Add a column to an existing table if an older database doesn't have it yet.
+fn add_column_if_missing(
+    conn: &Connection,
+    table: &str,
+    column: &str,
+    definition: &str,
+) -> Result<()> {
+    if !column_exists(conn, table, column)? {
+        conn.execute(
+            &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition),
+            params![],
+        )?;
+    }
+    Ok(())
+}
+
+fn column_type(conn: &Connection, table: &str, column: &str) -> Result<String> {
+    conn.query_row(
+        &format!(
+            "SELECT type FROM pragma_table_info('{}') WHERE name = ?1",
+            table
+        ),
+        params![column],
+        |row| row.get(0),
+    )
+}
+
+fn column_exists(conn: &Connection, table: &str, column: &str) -> Result<bool> {
+    conn.prepare(&format!(
+        "SELECT 1 FROM pragma_table_info('{}') WHERE name = ?1",
+        table
+    ))?
+    .exists(params![column])
+}
+
+/// Description used when a timer is started without one
+pub const DEFAULT_DESCRIPTION: &str = "Work session";
+
+/// Timer behavior settings
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct TimerSettings {
+    /// Timers started without a description reuse the project's last description
+    pub reuse_last_description: bool,
+    /// Stopped entries shorter than this many seconds are treated as noise (0 = off)
+    pub min_duration_secs: i64,
+    /// Discard too-short entries without asking
+    pub discard_short_entries: bool,
+    /// Split stopped entries that cross midnight into one entry per day
+    pub split_at_midnight: bool,
+    /// The menu bar app records the frontmost app while no timer is running,
+    /// to suggest entries later
+    pub track_activity: bool,
+    /// Minutes away from the computer after which the menu bar app stops the
+    /// timer, for projects without their own setting (0 = off)
+    pub idle_stop_minutes: i64,
+    /// Timers started in a git repository take their issue reference from
+    /// the branch name (see `issue_from_branch`)
+    pub issue_from_git_branch: bool,
+}
+
+impl TimerSettings {
+    /// Whether a stopped entry is shorter than the minimum duration worth keeping
+    pub fn is_too_short(&self, entry: &Entry) -> bool {
+        match entry.end {
+            Some(end) => {
+                self.min_duration_secs > 0
+                    && (end - entry.start).num_seconds() < self.min_duration_secs
+            }
+            None => false,
+        }
+    }
+}
+
+/// Format a number of days without trailing zeros, e.g. "1.5" or "2"
+pub fn format_days(days: f64) -> String {
+    let s = format!("{:.2}", days);
+    s.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+/// Format a duration compactly, e.g. "45m", "2h" or "1h30m"
+pub fn format_short_duration(secs: i64) -> String {
+    let (hours, minutes) = (secs / 3600, (secs % 3600) / 60);
+    match (hours, minutes) {
+        (0, 0) => format!("{}s", secs),
+        (0, m) => format!("{}m", m),
+        (h, 0) => format!("{}h", h),
+        (h, m) => format!("{}h{}m", h, m),
+    }
+}
+
+/// A stretch of time one app was frontmost while no timer was running, recorded
+/// by the menu bar app when activity tracking is on
+#[derive(Debug, Clone)]
+pub struct ActivitySpan {
+    pub id: i64,
+    pub app: String,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+impl ActivitySpan {
+    pub fn hours(&self) -> f64 {
+        (self.end - self.start).num_seconds() as f64 / 3600.0
+    }
+}
+
+/// Recorded app activity. It never leaves the local database and is pruned
+/// after a few weeks.
+pub fn init_activity_db(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS activity (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            app TEXT NOT NULL,
+            start TEXT NOT NULL,
+            end TEXT NOT NULL,
+            dismissed INTEGER NOT NULL DEFAULT 0
+        )",
+        params![],
+    )?;
+    Ok(())
+}
+
+/// Target durations of countdown timers ("work 2h on Acme"), one per entry.
+/// `notified` is set once the target is reached and someone has announced it.
+pub fn init_timer_targets_db(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS timer_targets (
+            entry_id INTEGER PRIMARY KEY REFERENCES entries(id) ON DELETE CASCADE,
+            target_secs INTEGER NOT NULL,
+            notified INTEGER NOT NULL DEFAULT 0
+        )",
+        params![],
+    )?;
+    Ok(())
+}
+
+/// Entries flagged as needing review before they are invoiced, one row per
+/// reason. Marking an entry reviewed deletes all of its rows.
+pub fn init_review_flags_db(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS review_flags (
+            entry_id INTEGER NOT NULL REFERENCES entries(id) ON DELETE CASCADE,
+            reason TEXT NOT NULL,
+            PRIMARY KEY (entry_id, reason)
+        )",
+        params![],
+    )?;
+    Ok(())
+}
+
+/// Tags on entries for the kind of work they record (support, meetings, ...),
+/// so a retainer can be invoiced for some kinds of work only.
+pub fn init_entry_tags_db(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS entry_tags (
+            entry_id INTEGER NOT NULL REFERENCES entries(id) ON DELETE CASCADE,
+            tag TEXT NOT NULL,
+            PRIMARY KEY (entry_id, tag)
+        )",
+        params![],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS tag_colors (
+            tag TEXT PRIMARY KEY,
+            color TEXT NOT NULL
+        )",
+        params![],
+    )?;
+    Ok(())
+}
+
+/// A recurring activity of a fixed length, such as a daily standup, that
+/// `meter add --template` and the TUI's template picker turn into an entry
+/// ending now.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct EntryTemplate {
+    pub id: i64,
+    /// Name to pick it by, e.g. "standup"
+    pub name: String,
+    pub project: String,
+    pub description: String,
+    pub tags: Vec<String>,
+    /// Length in hours
+    pub duration: f64,
+}
+
+pub fn init_entry_templates_db(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS entry_templates (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE COLLATE NOCASE,
+            project TEXT NOT NULL,
+            description TEXT NOT NULL DEFAULT '',
+            tags TEXT NOT NULL DEFAULT '',
+            duration REAL NOT NULL
+        )",
+        params![],
+    )?;
+    Ok(())
+}
+
+/// "On this Wi-Fi network, I'm usually working on this project": when the Mac
+/// joins `ssid` while no timer is running, the menu bar app suggests starting
+/// `project`'s timer.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct NetworkRule {
+    pub id: i64,
+    /// Wi-Fi network name, matched exactly
+    pub ssid: String,
+    pub project: String,
+    /// Location given to entries started from the suggestion, e.g. client-site
+    pub location: Option<String>,
+}
+
+/// Rules for suggesting timers by Wi-Fi network. The menu bar app only looks
+/// at the network while there is at least one.
+pub fn init_network_rules_db(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS network_rules (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            ssid TEXT NOT NULL UNIQUE,
+            project TEXT NOT NULL,
+            location TEXT
+        )",
+        params![],
+    )?;
+    Ok(())
+}
+
+/// Colors a tag can be shown in, in the order the TUI cycles through them
+pub const TAG_COLORS: [&str; 6] = ["red", "green", "yellow", "blue", "magenta", "cyan"];
+
+/// Days away from work (vacation, public holidays, sick leave). `start` and
+/// `end` are both included.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct TimeOff {
+    pub id: i64,
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+    pub reason: String,
+    /// A public holiday, which invoice due dates can be moved off
+    pub holiday: bool,
+}
+
+impl TimeOff {
+    pub fn contains(&self, day: NaiveDate) -> bool {
+        self.start <= day && day <= self.end
+    }
+
+    pub fn days(&self) -> i64 {
+        (self.end - self.start).num_days() + 1
+    }
+
+    /// "2024-12-24" for a single day, "2024-12-24..2024-12-31" otherwise
+    pub fn range(&self) -> String {
+        if self.start == self.end {
+            self.start.format("%Y-%m-%d").to_string()
+        } else {
+            format!(
+                "{}..{}",
+                self.start.format("%Y-%m-%d"),
+                self.end.format("%Y-%m-%d")
+            )
+        }
+    }
+}
+
+/// Parse "YYYY-MM-DD" or an inclusive range "YYYY-MM-DD..YYYY-MM-DD"
+pub fn parse_date_range(s: &str) -> std::result::Result<(NaiveDate, NaiveDate), String> {
+    let parse = |d: &str| {
+        NaiveDate::parse_from_str(d.trim(), "%Y-%m-%d")
+            .map_err(|_| format!("Invalid date '{}': expected YYYY-MM-DD", d.trim()))
+    };
+    let (start, end) = match s.split_once("..") {
+        Some((start, end)) => (parse(start)?, parse(end)?),
+        None => {
+            let day = parse(s)?;
+            (day, day)
+        }
+    };
+    if end < start {
+        return Err(format!("'{}' ends before it starts", s));
+    }
+    Ok((start, end))
+}
+
+/// Parse a line of `meter log` input: `YYYY-MM-DD HH:MM-HH:MM project description`,
+/// e.g. `2024-06-03 09:00-10:30 acme fix login bug #dev`. Times are local, and an
+/// end time before the start is on the next day. The description may be empty.
+pub fn parse_log_line(line: &str) -> std::result::Result<Entry, String> {
+    let mut words = line.split_whitespace();
+    let (Some(date), Some(times), Some(project)) = (words.next(), words.next(), words.next())
+    else {
+        return Err("expected 'YYYY-MM-DD HH:MM-HH:MM project description'".to_string());
+    };
+    let date = NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map_err(|_| format!("invalid date '{}': expected YYYY-MM-DD", date))?;
+    let (from, to) = times
+        .split_once('-')
+        .ok_or_else(|| format!("invalid times '{}': expected HH:MM-HH:MM", times))?;
+    let time = |t: &str| {
+        NaiveTime::parse_from_str(t, "%H:%M")
+            .map_err(|_| format!("invalid time '{}': expected HH:MM", t))
+    };
+    let (from, to) = (time(from)?, time(to)?);
+    if from == to {
+        return Err(format!("'{}' has no length", times));
+    }
+    let end_date = if to < from { date + Days::new(1) } else { date };
+    let local = |day: NaiveDate, t: NaiveTime| {
+        Local
+            .from_local_datetime(&day.and_time(t))
+            .single()
+            .map(|dt| dt.with_timezone(&Utc))
+            .ok_or_else(|| format!("{} {} doesn't exist in local time", day, t.format("%H:%M")))
+    };
+
+    Ok(Entry {
+        id: 0,
+        project: project.to_string(),
+        description: words.collect::<Vec<_>>().join(" "),
+        start: local(date, from)?,
+        end: Some(local(end_date, to)?),
+        status: EntryStatus::Open,
+        days: None,
+        issue: None,
+        location: None,
+    })
+}
+
+/// Work locations offered for entries; any other text is kept as written
+pub const WORK_LOCATIONS: [&str; 3] = ["home", "client-site", "travel"];
+
+/// Tidy a location typed for an entry, so reports group it with the same place
+/// written differently: common spellings of the `WORK_LOCATIONS` become those,
+/// and anything else is trimmed. `None` when blank.
+pub fn normalize_location(location: &str) -> Option<String> {
+    let location = location.trim();
+    let known = match location.to_ascii_lowercase().as_str() {
+        "home" | "remote" | "wfh" => "home",
+        "client-site" | "client site" | "client" | "onsite" | "on-site" | "on site" => {
+            "client-site"
+        }
+        "travel" | "travelling" | "traveling" => "travel",
+        "" => return None,
+        _ => return Some(location.to_string()),
+    };
+    Some(known.to_string())
+}
+
+/// Tidy a tag typed for an entry: lower case, without a leading `#`, with runs
+/// of spaces turned into `-`. `None` when blank.
+pub fn normalize_tag(tag: &str) -> Option<String> {
+    let tag = tag.trim().trim_start_matches('#').to_lowercase();
+    let tag = tag.split_whitespace().collect::<Vec<_>>().join("-");
+    Some(tag).filter(|t| !t.is_empty())
+}
+
+/// The issue a git branch is named after: a Jira-style key anywhere in the
+/// name (`feature/ACME-123-fix-login` → `ACME-123`), or a GitHub issue number
+/// leading its last part (`fix/42-login` → `#42`).
+pub fn issue_from_branch(branch: &str) -> Option<String> {
+    let is_key = |s: &str| {
+        s.len() >= 2
+            && s.starts_with(|c: char| c.is_ascii_uppercase())
+            && s.chars()
+                .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+    };
+    let is_number = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_digit());
+
+    for word in branch.split(|c: char| !(c.is_ascii_alphanumeric() || c == '-')) {
+        let parts: Vec<&str> = word.split('-').collect();
+        if let Some(pair) = parts
+            .windows(2)
+            .find(|pair| is_key(pair[0]) && is_number(pair[1]))
+        {
+            return Some(format!("{}-{}", pair[0], pair[1]));
+        }
+    }
+    let number = branch.rsplit('/').next()?.split('-').next()?;
+    is_number(number).then(|| format!("#{}", number))
+}
+
+pub fn init_time_off_db(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS time_off (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            start TEXT NOT NULL,
+            end TEXT NOT NULL,
+            reason TEXT NOT NULL DEFAULT '',
+            holiday INTEGER NOT NULL DEFAULT 0
+        )",
+        params![],
+    )?;
+    add_column_if_missing(conn, "time_off", "holiday", "INTEGER NOT NULL DEFAULT 0")?;
+    Ok(())
+}
+
+pub fn init_timer_settings_db(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS timer_settings (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            reuse_last_description INTEGER NOT NULL DEFAULT 0,
+            min_duration_secs INTEGER NOT NULL DEFAULT 0,
+            discard_short_entries INTEGER NOT NULL DEFAULT 0,
+            split_at_midnight INTEGER NOT NULL DEFAULT 0
+        )",
+        params![],
+    )?;
+    add_column_if_missing(
+        conn,
+        "timer_settings",
+        "min_duration_secs",
+        "INTEGER NOT NULL DEFAULT 0",
+    )?;
+    add_column_if_missing(
+        conn,
+        "timer_settings",
+        "discard_short_entries",
+        "INTEGER NOT NULL DEFAULT 0",
+    )?;
+    add_column_if_missing(
+        conn,
+        "timer_settings",
+        "split_at_midnight",
+        "INTEGER NOT NULL DEFAULT 0",
+    )?;
+    add_column_if_missing(
+        conn,
+        "timer_settings",
+        "track_activity",
+        "INTEGER NOT NULL DEFAULT 0",
+    )?;
+    add_column_if_missing(
+        conn,
+        "timer_settings",
+        "idle_stop_minutes",
+        "INTEGER NOT NULL DEFAULT 0",
+    )?;
+    add_column_if_missing(
+        conn,
+        "timer_settings",
+        "issue_from_git_branch",
+        "INTEGER NOT NULL DEFAULT 0",
+    )?;
+    conn.execute(
+        "INSERT OR IGNORE INTO timer_settings (id) VALUES (1)",
+        params![],
+    )?;
+    Ok(())
+}
+
+/// Kinds of notification that can be turned on or off
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotificationKind {
+    /// A Pomodoro work period or countdown timer has ended
+    WorkComplete,
+    /// A Pomodoro break has ended
+    BreakComplete,
+    /// Invoicing reminders, hour-cap and monthly target warnings
+    Reminder,
+}
+
+/// Which notifications are shown, with what sound, and when they are held back.
+/// Fields missing from older exports take their defaults.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct NotificationSettings {
+    pub work_complete: bool,
+    pub break_complete: bool,
+    pub reminders: bool,
+    /// macOS sound played with each notification (empty = silent)
+    pub sound: String,
+    /// Local time from which no notifications are shown
+    pub quiet_start: Option<NaiveTime>,
+    /// Local time at which quiet hours end; may be earlier than the start to
+    /// span midnight
+    pub quiet_end: Option<NaiveTime>,
+    /// Unbilled hours past which the menu bar icon shows a badge (0 = off)
+    pub badge_unbilled_hours: f64,
+    /// The menu bar icon shows a badge while an invoice is past due
+    pub badge_overdue: bool,
+    /// Billable hours to aim for each month (0 = off)
+    pub monthly_target_hours: f64,
+    /// Hours billable time may trail the target's pace before a warning
+    pub target_margin_hours: f64,
+}
+
+impl Default for NotificationSettings {
+    fn default() -> Self {
+        Self {
+            work_complete: true,
+            break_complete: true,
+            reminders: true,
+            sound: "Glass".to_string(),
+            quiet_start: None,
+            quiet_end: None,
+            badge_unbilled_hours: 0.0,
+            badge_overdue: false,
+            monthly_target_hours: 0.0,
+            target_margin_hours: 8.0,
+        }
+    }
+}
+
+impl NotificationSettings {
+    /// Whether `time` falls within quiet hours
+    pub fn is_quiet(&self, time: NaiveTime) -> bool {
+        match (self.quiet_start, self.quiet_end) {
+            (Some(start), Some(end)) if start <= end => time >= start && time < end,
+            (Some(start), Some(end)) => time >= start || time < end,
+            _ => false,
+        }
+    }
+
+    /// Whether a notification of this kind should be shown at local time `time`
+    pub fn allows(&self, kind: NotificationKind, time: NaiveTime) -> bool {
+        let enabled = match kind {
+            NotificationKind::WorkComplete => self.work_complete,
+            NotificationKind::BreakComplete => self.break_complete,
+            NotificationKind::Reminder => self.reminders,
+        };
+        enabled && !self.is_quiet(time)
+    }
+
+    /// Quiet hours as "HH:MM-HH:MM", or "off"
+    pub fn quiet_hours(&self) -> String {
+        match (self.quiet_start, self.quiet_end) {
+            (Some(start), Some(end)) => {
+                format!("{}-{}", start.format("%H:%M"), end.format("%H:%M"))
+            }
+            _ => "off".to_string(),
+        }
+    }
+}
+
+/// Parse quiet hours given as "HH:MM-HH:MM" (e.g. "22:00-08:00"), or "off"
+pub fn parse_quiet_hours(s: &str) -> std::result::Result<Option<(NaiveTime, NaiveTime)>, String> {
+    let s = s.trim();
+    if s.eq_ignore_ascii_case("off") || s.is_empty() {
+        return Ok(None);
+    }
+    parse_time_window(s, "quiet hours").map(Some)
+}
+
+/// Parse a window of the day given as "HH:MM-HH:MM", which may run past
+/// midnight. `what` names it in error messages.
+pub fn parse_time_window(
+    s: &str,
+    what: &str,
+) -> std::result::Result<(NaiveTime, NaiveTime), String> {
+    let parse = |t: &str| {
+        NaiveTime::parse_from_str(t.trim(), "%H:%M")
+            .map_err(|_| format!("Invalid time '{}' in {} (expected HH:MM)", t.trim(), what))
+    };
+    let (start, end) = s
+        .trim()
+        .split_once('-')
+        .ok_or_else(|| format!("Invalid {} '{}' (expected HH:MM-HH:MM)", what, s.trim()))?;
+    let (start, end) = (parse(start)?, parse(end)?);
+    if start == end {
+        return Err(format!(
+            "The {} must start and end at different times",
+            what
+        ));
+    }
+    Ok((start, end))
+}
+
+pub fn init_notification_settings_db(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS notification_settings (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            work_complete INTEGER NOT NULL DEFAULT 1,
+            break_complete INTEGER NOT NULL DEFAULT 1,
+            reminders INTEGER NOT NULL DEFAULT 1,
+            sound TEXT NOT NULL DEFAULT 'Glass',
+            quiet_start TEXT,
+            quiet_end TEXT,
+            badge_unbilled_hours REAL NOT NULL DEFAULT 0,
+            badge_overdue INTEGER NOT NULL DEFAULT 0,
+            monthly_target_hours REAL NOT NULL DEFAULT 0,
+            target_margin_hours REAL NOT NULL DEFAULT 8
+        )",
+        params![],
+    )?;
+    add_column_if_missing(
+        conn,
+        "notification_settings",
+        "badge_unbilled_hours",
+        "REAL NOT NULL DEFAULT 0",
+    )?;
+    add_column_if_missing(
+        conn,
+        "notification_settings",
+        "badge_overdue",
+        "INTEGER NOT NULL DEFAULT 0",
+    )?;
+    add_column_if_missing(
+        conn,
+        "notification_settings",
+        "monthly_target_hours",
+        "REAL NOT NULL DEFAULT 0",
+    )?;
+    add_column_if_missing(
+        conn,
+        "notification_settings",
+        "target_margin_hours",
+        "REAL NOT NULL DEFAULT 8",
+    )?;
+    conn.execute(
+        "INSERT OR IGNORE INTO notification_settings (id) VALUES (1)",
+        params![],
+    )?;
+    Ok(())
+}
+
+/// What bare `meter` (no subcommand) does
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct TuiSettings {
+    /// Open the TUI; otherwise print the help
+    pub open_by_default: bool,
+    /// Screen the TUI opens on unless `--screen` says otherwise
+    pub default_screen: String,
+}
+
+impl Default for TuiSettings {
+    fn default() -> Self {
+        Self {
+            open_by_default: true,
+            default_screen: "timer".to_string(),
+        }
+    }
+}
+
+pub fn init_tui_settings_db(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS tui_settings (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            open_by_default INTEGER NOT NULL DEFAULT 1,
+            default_screen TEXT NOT NULL DEFAULT 'timer'
+        )",
+        params![],
+    )?;
+    conn.execute(
+        "INSERT OR IGNORE INTO tui_settings (id) VALUES (1)",
+        params![],
+    )?;
+    Ok(())
+}
+
+/// Time tracking service finished entries are pushed to (`meter sync push`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SyncService {
+    #[default]
+    Harvest,
+    Toggl,
+}
+
+impl SyncService {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SyncService::Harvest => "harvest",
+            SyncService::Toggl => "toggl",
+        }
+    }
+
+    /// What the service calls the ID `meter sync settings --account-id` takes
+    pub fn account_label(&self) -> &'static str {
+        match self {
+            SyncService::Harvest => "Account ID",
+            SyncService::Toggl => "Workspace ID",
+        }
+    }
+}
+
+impl std::fmt::Display for SyncService {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for SyncService {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "harvest" => Ok(SyncService::Harvest),
+            "toggl" => Ok(SyncService::Toggl),
+            _ => Err(format!(
+                "Unknown service '{}' (expected harvest or toggl)",
+                s
+            )),
+        }
+    }
+}
+
+impl ToSql for SyncService {
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.as_str()))
+    }
+}
+
+impl FromSql for SyncService {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        value
+            .as_str()?
+            .parse()
+            .map_err(|e: String| FromSqlError::Other(e.into()))
+    }
+}
+
+/// Where finished entries are pushed. The API token is kept with the other
+/// secrets, not here.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct SyncSettings {
+    pub service: SyncService,
+    /// Harvest account ID or Toggl workspace ID
+    pub account_id: String,
+}
+
+impl SyncSettings {
+    pub fn is_configured(&self) -> bool {
+        !self.account_id.is_empty()
+    }
+}
+
+/// A meter project's counterpart in the sync service
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SyncMapping {
+    pub service: SyncService,
+    pub project: String,
+    pub remote_project: i64,
+    /// Harvest task (required by Harvest) or Toggl task
+    pub remote_task: Option<i64>,
+    /// Entries started before this are not pushed unless asked for
+    pub mapped_at: DateTime<Utc>,
+}
+
+pub fn init_sync_db(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS sync_settings (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            service TEXT NOT NULL DEFAULT 'harvest',
+            account_id TEXT NOT NULL DEFAULT ''
+        )",
+        params![],
+    )?;
+    conn.execute(
+        "INSERT OR IGNORE INTO sync_settings (id) VALUES (1)",
+        params![],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS sync_projects (
+            service TEXT NOT NULL,
+            project TEXT NOT NULL,
+            remote_project INTEGER NOT NULL,
+            remote_task INTEGER,
+            mapped_at TEXT NOT NULL,
+            PRIMARY KEY (service, project)
+        )",
+        params![],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS sync_pushed (
+            service TEXT NOT NULL,
+            entry_id INTEGER NOT NULL REFERENCES entries(id) ON DELETE CASCADE,
+            remote_id INTEGER NOT NULL,
+            pushed_at TEXT NOT NULL,
+            PRIMARY KEY (service, entry_id)
+        )",
+        params![],
+    )?;
+    Ok(())
 }
 
-#[derive(Debug, Clone)]
-pub struct Project {
+/// What an expense is: money spent, or a distance driven that is billed at the
+/// mileage rate
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ExpenseKind {
+    #[default]
+    Expense,
+    Mileage,
+}
+
+impl ExpenseKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ExpenseKind::Expense => "expense",
+            ExpenseKind::Mileage => "mileage",
+        }
+    }
+}
+
+impl std::fmt::Display for ExpenseKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for ExpenseKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "expense" => Ok(ExpenseKind::Expense),
+            "mileage" => Ok(ExpenseKind::Mileage),
+            _ => Err(format!(
+                "Unknown expense kind '{}' (expected expense or mileage)",
+                s
+            )),
+        }
+    }
+}
+
+impl ToSql for ExpenseKind {
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.as_str()))
+    }
+}
+
+impl FromSql for ExpenseKind {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        value
+            .as_str()?
+            .parse()
+            .map_err(|e: String| FromSqlError::Other(e.into()))
+    }
+}
+
+/// Unit mileage is recorded and charged in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum DistanceUnit {
+    #[default]
+    Km,
+    Mi,
+}
+
+impl DistanceUnit {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DistanceUnit::Km => "km",
+            DistanceUnit::Mi => "mi",
+        }
+    }
+}
+
+impl std::fmt::Display for DistanceUnit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for DistanceUnit {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "km" | "kilometers" | "kilometres" => Ok(DistanceUnit::Km),
+            "mi" | "miles" => Ok(DistanceUnit::Mi),
+            _ => Err(format!("Unknown distance unit '{}' (expected km or mi)", s)),
+        }
+    }
+}
+
+impl ToSql for DistanceUnit {
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.as_str()))
+    }
+}
+
+impl FromSql for DistanceUnit {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        value
+            .as_str()?
+            .parse()
+            .map_err(|e: String| FromSqlError::Other(e.into()))
+    }
+}
+
+/// A cost passed on to a client. Expenses tied to an entry go on the invoice
+/// that bills the entry; others go on their client's invoice for the month
+/// they were incurred in.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Expense {
     pub id: i64,
-    pub name: String,
-    pub rate: Option<f64>,
-    pub currency: Option<String>,
+    pub date: NaiveDate,
+    pub kind: ExpenseKind,
+    pub description: String,
+    pub amount: Money,
+    /// Distance driven, for mileage
+    pub distance: Option<f64>,
+    /// Unit of `distance`
+    pub unit: Option<DistanceUnit>,
+    pub entry_id: Option<i64>,
+    pub client_id: Option<i64>,
+    /// Invoice the expense was billed on
+    pub invoice_number: Option<i64>,
 }
 
-impl Project {
-    /// Format the rate with currency for display (e.g., "$150.00/hr")
-    pub fn formatted_rate(&self) -> Option<String> {
-        match (&self.rate, &self.currency) {
-            (Some(r), Some(c)) => Some(format!("{}{:.2}/hr", c, r)),
-            (Some(r), None) => Some(format!("${:.2}/hr", r)),
-            _ => None,
+impl Expense {
+    /// How the expense reads on an invoice, e.g. "Mileage: client visit (42 km)"
+    pub fn label(&self) -> String {
+        match (self.kind, self.distance, self.unit) {
+            (ExpenseKind::Mileage, Some(distance), Some(unit)) if self.description.is_empty() => {
+                format!("Mileage ({} {})", distance, unit)
+            }
+            (ExpenseKind::Mileage, Some(distance), Some(unit)) => {
+                format!("Mileage: {} ({} {})", self.description, distance, unit)
+            }
+            _ => self.description.clone(),
         }
     }
 }
 
-pub fn init_db(conn: &Connection) -> Result<()> {
+/// Rate mileage is charged at
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct MileageSettings {
+    /// Amount per `unit` driven
+    pub rate: Money,
+    pub unit: DistanceUnit,
+}
+
+pub fn init_expenses_db(conn: &Connection) -> Result<()> {
     conn.execute(
-        "CREATE TABLE IF NOT EXISTS entries (
+        "CREATE TABLE IF NOT EXISTS expenses (
             id INTEGER PRIMARY KEY AUTOINCREMENT,
-            project TEXT NOT NULL,
-            description TEXT NOT NULL,
-            start TEXT NOT NULL,
-            end TEXT,
-            billed INTEGER NOT NULL DEFAULT 0
+            date TEXT NOT NULL,
+            kind TEXT NOT NULL DEFAULT 'expense',
+            description TEXT NOT NULL DEFAULT '',
+            amount REAL NOT NULL,
+            distance REAL,
+            unit TEXT,
+            entry_id INTEGER REFERENCES entries(id) ON DELETE SET NULL,
+            client_id INTEGER REFERENCES clients(id) ON DELETE SET NULL,
+            invoice_number INTEGER
+        )",
+        params![],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS mileage_settings (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            rate REAL NOT NULL DEFAULT 0,
+            unit TEXT NOT NULL DEFAULT 'km'
         )",
         params![],
     )?;
+    conn.execute(
+        "INSERT OR IGNORE INTO mileage_settings (id) VALUES (1)",
+        params![],
+    )?;
     Ok(())
 }
 
-pub fn init_projects_db(conn: &Connection) -> Result<()> {
+/// A file (usually a receipt) kept with an expense or an invoice. The file is
+/// copied into meter's attachments folder; `name` is what it was called originally.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Attachment {
+    pub id: i64,
+    pub expense_id: Option<i64>,
+    pub invoice_number: Option<i64>,
+    pub name: String,
+    /// The stored copy
+    pub path: String,
+    pub added_at: DateTime<Utc>,
+}
+
+impl Attachment {
+    /// True when the stored copy is no longer on disk
+    pub fn file_missing(&self) -> bool {
+        !std::path::Path::new(&self.path).exists()
+    }
+
+    /// What the attachment belongs to, e.g. "expense 3" or "invoice #12"
+    pub fn owner(&self) -> String {
+        match (self.expense_id, self.invoice_number) {
+            (Some(id), _) => format!("expense {}", id),
+            (None, Some(number)) => format!("invoice #{}", number),
+            (None, None) => "-".to_string(),
+        }
+    }
+}
+
+pub fn init_attachments_db(conn: &Connection) -> Result<()> {
     conn.execute(
-        "CREATE TABLE IF NOT EXISTS projects (
+        "CREATE TABLE IF NOT EXISTS attachments (
             id INTEGER PRIMARY KEY AUTOINCREMENT,
-            name TEXT NOT NULL UNIQUE,
-            rate TEXT,
-            currency TEXT DEFAULT '$'
+            expense_id INTEGER REFERENCES expenses(id) ON DELETE CASCADE,
+            invoice_number INTEGER,
+            name TEXT NOT NULL,
+            path TEXT NOT NULL,
+            added_at TEXT NOT NULL
         )",
         params![],
     )?;
@@ -59,7 +1947,7 @@ pub fn init_projects_db(conn: &Connection) -> Result<()> {
 }
 
 /// Pomodoro timer configuration
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct PomodoroConfig {
     pub enabled: bool,
     pub work_duration: i32,      // minutes (default: 45)
@@ -115,8 +2003,222 @@ pub fn init_pomodoro_db(conn: &Connection) -> Result<()> {
     Ok(())
 }
 
+/// Paper the invoice PDF is laid out on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum PaperSize {
+    /// 210 × 297 mm, used outside North America
+    #[default]
+    A4,
+    /// 8.5 × 11 in, used in the US and Canada
+    Letter,
+    /// 8.5 × 14 in
+    Legal,
+}
+
+impl PaperSize {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PaperSize::A4 => "a4",
+            PaperSize::Letter => "letter",
+            PaperSize::Legal => "legal",
+        }
+    }
+}
+
+impl std::fmt::Display for PaperSize {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for PaperSize {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "a4" => Ok(PaperSize::A4),
+            "letter" => Ok(PaperSize::Letter),
+            "legal" => Ok(PaperSize::Legal),
+            _ => Err(format!(
+                "Unknown paper size '{}' (expected a4, letter or legal)",
+                s
+            )),
+        }
+    }
+}
+
+impl ToSql for PaperSize {
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.as_str()))
+    }
+}
+
+impl FromSql for PaperSize {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        value
+            .as_str()?
+            .parse()
+            .map_err(|e: String| FromSqlError::Other(e.into()))
+    }
+}
+
+/// Order of the project sections on an invoice
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SectionOrder {
+    /// By project name
+    #[default]
+    Alphabetical,
+    /// Most hours first
+    Hours,
+    /// By each project's earliest entry
+    Date,
+}
+
+impl SectionOrder {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SectionOrder::Alphabetical => "alphabetical",
+            SectionOrder::Hours => "hours",
+            SectionOrder::Date => "date",
+        }
+    }
+}
+
+impl std::fmt::Display for SectionOrder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for SectionOrder {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "alphabetical" | "name" => Ok(SectionOrder::Alphabetical),
+            "hours" => Ok(SectionOrder::Hours),
+            "date" | "first-entry" => Ok(SectionOrder::Date),
+            _ => Err(format!(
+                "Unknown section order '{}' (expected alphabetical, hours or date)",
+                s
+            )),
+        }
+    }
+}
+
+impl ToSql for SectionOrder {
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.as_str()))
+    }
+}
+
+impl FromSql for SectionOrder {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        value
+            .as_str()?
+            .parse()
+            .map_err(|e: String| FromSqlError::Other(e.into()))
+    }
+}
+
+/// How amounts on invoices are written: the currency's own convention, or one
+/// locale's separators for every currency
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum NumberLocale {
+    /// Each currency as it is usually written, e.g. "$1,234.56" and "1.234,56 €"
+    #[default]
+    Currency,
+    /// 1,234.56
+    En,
+    /// 1.234,56
+    De,
+    /// 1 234,56
+    Fr,
+    /// 1'234.56
+    Ch,
+}
+
+impl NumberLocale {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NumberLocale::Currency => "currency",
+            NumberLocale::En => "en",
+            NumberLocale::De => "de",
+            NumberLocale::Fr => "fr",
+            NumberLocale::Ch => "ch",
+        }
+    }
+
+    /// Thousands and decimal separators, `None` to use the currency's own
+    fn separators(&self) -> Option<(&'static str, &'static str)> {
+        match self {
+            NumberLocale::Currency => None,
+            NumberLocale::En => Some((",", ".")),
+            NumberLocale::De => Some((".", ",")),
+            NumberLocale::Fr => Some((" ", ",")),
+            NumberLocale::Ch => Some(("'", ".")),
+        }
+    }
+}
+
+impl std::fmt::Display for NumberLocale {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for NumberLocale {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().replace('_', "-").as_str() {
+            "currency" | "" => Ok(NumberLocale::Currency),
+            "en" | "en-us" | "en-gb" | "en-au" => Ok(NumberLocale::En),
+            "de" | "de-de" | "nl" | "it" | "es" => Ok(NumberLocale::De),
+            "fr" | "fr-fr" | "sv" | "nb" => Ok(NumberLocale::Fr),
+            "ch" | "de-ch" => Ok(NumberLocale::Ch),
+            _ => Err(format!(
+                "Unknown number locale '{}' (expected currency, en, de, fr or ch)",
+                s
+            )),
+        }
+    }
+}
+
+impl ToSql for NumberLocale {
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.as_str()))
+    }
+}
+
+impl FromSql for NumberLocale {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        value
+            .as_str()?
+            .parse()
+            .map_err(|e: String| FromSqlError::Other(e.into()))
+    }
+}
+
+/// Page margin used when none is configured
+const DEFAULT_MARGIN_MM: f64 = 20.0;
+/// Body text size used when none is configured
+pub const DEFAULT_FONT_SIZE: i64 = 10;
+
+fn default_margin_mm() -> f64 {
+    DEFAULT_MARGIN_MM
+}
+
+fn default_font_size() -> i64 {
+    DEFAULT_FONT_SIZE
+}
+
 /// Invoice settings (your business info)
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
 pub struct InvoiceSettings {
     pub business_name: String,
     pub address_street: String,
@@ -130,62 +2232,395 @@ pub struct InvoiceSettings {
     pub payment_instructions: String,
     pub default_payment_terms: String,
     pub default_tax_rate: f64,
+    /// Day of the month from which uninvoiced hours of the previous month are
+    /// flagged, for clients without their own schedule (0 = off)
+    pub reminder_day: i64,
+    /// Print the lines after the first line of an entry's description as a notes
+    /// row beneath it on the invoice
+    pub include_entry_notes: bool,
+    pub paper_size: PaperSize,
+    /// Margin on every side of the page, in millimetres
+    #[serde(default = "default_margin_mm")]
+    pub margin_mm: f64,
+    /// Body text size in points; headings and tables are scaled to match
+    #[serde(default = "default_font_size")]
+    pub font_size: i64,
+    pub section_order: SectionOrder,
+    pub iban: String,
+    pub bic: String,
+    pub account_number: String,
+    pub routing_number: String,
+    pub swift: String,
+    /// Move due dates that fall on a weekend or public holiday to the next
+    /// business day
+    pub business_day_due_dates: bool,
+    /// Also write each invoice's data as `invoice_NNNN.json` next to the PDF
+    pub json_snapshot: bool,
+    /// Add a "Ref" column with each entry's issue reference to the entry tables
+    /// of projects that have any
+    pub issue_refs: bool,
+    /// PNG or JPEG printed at the top of the invoice at 300 dpi; empty for none
+    pub logo_path: String,
+    /// Rates and line amounts already include tax, which is backed out of the
+    /// total instead of added on top (GST/VAT-inclusive pricing)
+    pub tax_inclusive: bool,
+    /// Separators amounts are written with
+    pub number_locale: NumberLocale,
+}
+
+impl InvoiceSettings {
+    /// The bank fields that are set, with their labels, in the order they are
+    /// printed in the invoice's payment details block
+    pub fn payment_details(&self) -> Vec<(&'static str, &str)> {
+        [
+            ("IBAN", self.iban.as_str()),
+            ("BIC", self.bic.as_str()),
+            ("SWIFT", self.swift.as_str()),
+            ("Account", self.account_number.as_str()),
+            ("Routing", self.routing_number.as_str()),
+        ]
+        .into_iter()
+        .filter(|(_, value)| !value.is_empty())
+        .collect()
+    }
+
+    /// Check the page layout: margins up to 50 mm and a body font of 6 to 24 pt
+    pub fn validate_layout(&self) -> std::result::Result<(), String> {
+        if !(0.0..=50.0).contains(&self.margin_mm) {
+            return Err(format!(
+                "Invalid margin {} mm: must be between 0 and 50",
+                self.margin_mm
+            ));
+        }
+        if !(6..=24).contains(&self.font_size) {
+            return Err(format!(
+                "Invalid font size {} pt: must be between 6 and 24",
+                self.font_size
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn formatted_address(&self) -> String {
+        let mut parts = Vec::new();
+        if !self.address_street.is_empty() {
+            parts.push(self.address_street.clone());
+        }
+        let city_state_postal = [
+            self.address_city.as_str(),
+            self.address_state.as_str(),
+            self.address_postal.as_str(),
+        ]
+        .iter()
+        .filter(|s| !s.is_empty())
+        .copied()
+        .collect::<Vec<_>>()
+        .join(", ");
+        if !city_state_postal.is_empty() {
+            parts.push(city_state_postal);
+        }
+        if !self.address_country.is_empty() {
+            parts.push(self.address_country.clone());
+        }
+        parts.join("\n")
+    }
+}
+
+/// The profile invoices come from unless their client or the command names
+/// another. It can't be removed.
+pub const DEFAULT_PROFILE_ID: i64 = 1;
+
+/// One business you invoice as (e.g. an LLC and personal consulting), with its
+/// own details, bank account, logo and invoice numbers
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct BusinessProfile {
+    pub id: i64,
+    pub name: String,
+    /// Number of this profile's first invoice. Profiles share one set of
+    /// invoice numbers, so give each its own range (e.g. 1 and 5001).
+    pub first_number: i64,
+    pub settings: InvoiceSettings,
+}
+
+/// Columns of `invoice_settings`, in the order `Db` reads a profile
+pub const INVOICE_SETTINGS_COLUMNS: &str = "id, name, first_number, business_name, address_street,
+    address_city, address_state, address_postal, address_country, email, phone, tax_id,
+    payment_instructions, default_payment_terms, default_tax_rate, reminder_day,
+    include_entry_notes, paper_size, margin_mm, font_size, section_order, iban, bic,
+    account_number, routing_number, swift, business_day_due_dates, json_snapshot, issue_refs,
+    logo_path, tax_inclusive, number_locale";
+
+/// One row per business profile; row 1 is the default profile
+const INVOICE_SETTINGS_TABLE: &str = "CREATE TABLE IF NOT EXISTS invoice_settings (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL DEFAULT 'default',
+            first_number INTEGER NOT NULL DEFAULT 1,
+            business_name TEXT NOT NULL DEFAULT '',
+            address_street TEXT NOT NULL DEFAULT '',
+            address_city TEXT NOT NULL DEFAULT '',
+            address_state TEXT NOT NULL DEFAULT '',
+            address_postal TEXT NOT NULL DEFAULT '',
+            address_country TEXT NOT NULL DEFAULT '',
+            email TEXT NOT NULL DEFAULT '',
+            phone TEXT NOT NULL DEFAULT '',
+            tax_id TEXT NOT NULL DEFAULT '',
+            payment_instructions TEXT NOT NULL DEFAULT '',
+            default_payment_terms TEXT NOT NULL DEFAULT 'Due on receipt',
+            default_tax_rate REAL NOT NULL DEFAULT 0.0,
+            reminder_day INTEGER NOT NULL DEFAULT 1,
+            include_entry_notes INTEGER NOT NULL DEFAULT 0,
+            paper_size TEXT NOT NULL DEFAULT 'a4',
+            margin_mm REAL NOT NULL DEFAULT 20.0,
+            font_size INTEGER NOT NULL DEFAULT 10,
+            section_order TEXT NOT NULL DEFAULT 'alphabetical',
+            iban TEXT NOT NULL DEFAULT '',
+            bic TEXT NOT NULL DEFAULT '',
+            account_number TEXT NOT NULL DEFAULT '',
+            routing_number TEXT NOT NULL DEFAULT '',
+            swift TEXT NOT NULL DEFAULT '',
+            business_day_due_dates INTEGER NOT NULL DEFAULT 0,
+            json_snapshot INTEGER NOT NULL DEFAULT 0,
+            issue_refs INTEGER NOT NULL DEFAULT 0,
+            logo_path TEXT NOT NULL DEFAULT '',
+            tax_inclusive INTEGER NOT NULL DEFAULT 0,
+            number_locale TEXT NOT NULL DEFAULT 'currency'
+        )";
+
+pub fn init_invoice_settings_db(conn: &Connection) -> Result<()> {
+    conn.execute(INVOICE_SETTINGS_TABLE, params![])?;
+    add_column_if_missing(
+        conn,
+        "invoice_settings",
+        "reminder_day",
+        "INTEGER NOT NULL DEFAULT 1",
+    )?;
+    add_column_if_missing(
+        conn,
+        "invoice_settings",
+        "include_entry_notes",
+        "INTEGER NOT NULL DEFAULT 0",
+    )?;
+    add_column_if_missing(
+        conn,
+        "invoice_settings",
+        "paper_size",
+        "TEXT NOT NULL DEFAULT 'a4'",
+    )?;
+    add_column_if_missing(
+        conn,
+        "invoice_settings",
+        "margin_mm",
+        "REAL NOT NULL DEFAULT 20.0",
+    )?;
+    add_column_if_missing(
+        conn,
+        "invoice_settings",
+        "font_size",
+        "INTEGER NOT NULL DEFAULT 10",
+    )?;
+    add_column_if_missing(
+        conn,
+        "invoice_settings",
+        "section_order",
+        "TEXT NOT NULL DEFAULT 'alphabetical'",
+    )?;
+    for column in ["iban", "bic", "account_number", "routing_number", "swift"] {
+        add_column_if_missing(conn, "invoice_settings", column, "TEXT NOT NULL DEFAULT ''")?;
+    }
+    add_column_if_missing(
+        conn,
+        "invoice_settings",
+        "business_day_due_dates",
+        "INTEGER NOT NULL DEFAULT 0",
+    )?;
+    add_column_if_missing(
+        conn,
+        "invoice_settings",
+        "json_snapshot",
+        "INTEGER NOT NULL DEFAULT 0",
+    )?;
+    add_column_if_missing(
+        conn,
+        "invoice_settings",
+        "issue_refs",
+        "INTEGER NOT NULL DEFAULT 0",
+    )?;
+    add_column_if_missing(
+        conn,
+        "invoice_settings",
+        "name",
+        "TEXT NOT NULL DEFAULT 'default'",
+    )?;
+    add_column_if_missing(
+        conn,
+        "invoice_settings",
+        "first_number",
+        "INTEGER NOT NULL DEFAULT 1",
+    )?;
+    add_column_if_missing(
+        conn,
+        "invoice_settings",
+        "logo_path",
+        "TEXT NOT NULL DEFAULT ''",
+    )?;
+    add_column_if_missing(
+        conn,
+        "invoice_settings",
+        "tax_inclusive",
+        "INTEGER NOT NULL DEFAULT 0",
+    )?;
+    add_column_if_missing(
+        conn,
+        "invoice_settings",
+        "number_locale",
+        "TEXT NOT NULL DEFAULT 'currency'",
+    )?;
+
+    // Older databases allowed only the one row; rebuild the table so profiles
+    // can be added. No other table references it, so it can be swapped out.
+    let sql: String = conn.query_row(
+        "SELECT sql FROM sqlite_master WHERE type = 'table' AND name = 'invoice_settings'",
+        params![],
+        |row| row.get(0),
+    )?;
+    if sql.contains("CHECK (id = 1)") {
+        conn.execute(
+            "ALTER TABLE invoice_settings RENAME TO invoice_settings_single",
+            params![],
+        )?;
+        conn.execute(INVOICE_SETTINGS_TABLE, params![])?;
+        conn.execute(
+            &format!(
+                "INSERT INTO invoice_settings ({0}) SELECT {0} FROM invoice_settings_single",
+                INVOICE_SETTINGS_COLUMNS
+            ),
+            params![],
+        )?;
+        conn.execute("DROP TABLE invoice_settings_single", params![])?;
+    }
+    conn.execute(
+        "INSERT OR IGNORE INTO invoice_settings (id) VALUES (1)",
+        params![],
+    )?;
+    conn.execute(
+        "CREATE UNIQUE INDEX IF NOT EXISTS invoice_settings_name ON invoice_settings (name)",
+        params![],
+    )?;
+    Ok(())
+}
+
+/// How the connection to the SMTP server is secured
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SmtpSecurity {
+    /// Plain connection upgraded with STARTTLS (usually port 587)
+    #[default]
+    StartTls,
+    /// TLS from the start (usually port 465)
+    Tls,
+    /// No encryption, for local relays only
+    None,
+}
+
+impl SmtpSecurity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SmtpSecurity::StartTls => "starttls",
+            SmtpSecurity::Tls => "tls",
+            SmtpSecurity::None => "none",
+        }
+    }
+}
+
+impl std::fmt::Display for SmtpSecurity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for SmtpSecurity {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "starttls" => Ok(SmtpSecurity::StartTls),
+            "tls" | "ssl" => Ok(SmtpSecurity::Tls),
+            "none" | "plain" => Ok(SmtpSecurity::None),
+            _ => Err(format!(
+                "Unknown SMTP security '{}' (expected starttls, tls or none)",
+                s
+            )),
+        }
+    }
+}
+
+impl ToSql for SmtpSecurity {
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.as_str()))
+    }
+}
+
+impl FromSql for SmtpSecurity {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        value
+            .as_str()?
+            .parse()
+            .map_err(|e: String| FromSqlError::Other(e.into()))
+    }
+}
+
+/// Outgoing mail server used to send invoices and digests. The password is kept
+/// in the OS keychain rather than in the database.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct EmailSettings {
+    pub smtp_host: String,
+    pub smtp_port: i64,
+    pub smtp_username: String,
+    /// Sender address; test emails are sent here too
+    pub from_address: String,
+    pub security: SmtpSecurity,
 }
 
-impl InvoiceSettings {
-    pub fn formatted_address(&self) -> String {
-        let mut parts = Vec::new();
-        if !self.address_street.is_empty() {
-            parts.push(self.address_street.clone());
-        }
-        let city_state_postal = [
-            self.address_city.as_str(),
-            self.address_state.as_str(),
-            self.address_postal.as_str(),
-        ]
-        .iter()
-        .filter(|s| !s.is_empty())
-        .copied()
-        .collect::<Vec<_>>()
-        .join(", ");
-        if !city_state_postal.is_empty() {
-            parts.push(city_state_postal);
-        }
-        if !self.address_country.is_empty() {
-            parts.push(self.address_country.clone());
+impl Default for EmailSettings {
+    fn default() -> Self {
+        Self {
+            smtp_host: String::new(),
+            smtp_port: 587,
+            smtp_username: String::new(),
+            from_address: String::new(),
+            security: SmtpSecurity::StartTls,
         }
-        parts.join("\n")
     }
 }
 
-pub fn init_invoice_settings_db(conn: &Connection) -> Result<()> {
+impl EmailSettings {
+    /// Whether enough is set to attempt sending
+    pub fn is_configured(&self) -> bool {
+        !self.smtp_host.is_empty() && !self.from_address.is_empty()
+    }
+}
+
+pub fn init_email_settings_db(conn: &Connection) -> Result<()> {
     conn.execute(
-        "CREATE TABLE IF NOT EXISTS invoice_settings (
+        "CREATE TABLE IF NOT EXISTS email_settings (
             id INTEGER PRIMARY KEY CHECK (id = 1),
-            business_name TEXT NOT NULL DEFAULT '',
-            address_street TEXT NOT NULL DEFAULT '',
-            address_city TEXT NOT NULL DEFAULT '',
-            address_state TEXT NOT NULL DEFAULT '',
-            address_postal TEXT NOT NULL DEFAULT '',
-            address_country TEXT NOT NULL DEFAULT '',
-            email TEXT NOT NULL DEFAULT '',
-            phone TEXT NOT NULL DEFAULT '',
-            tax_id TEXT NOT NULL DEFAULT '',
-            payment_instructions TEXT NOT NULL DEFAULT '',
-            default_payment_terms TEXT NOT NULL DEFAULT 'Due on receipt',
-            default_tax_rate REAL NOT NULL DEFAULT 0.0
+            smtp_host TEXT NOT NULL DEFAULT '',
+            smtp_port INTEGER NOT NULL DEFAULT 587,
+            smtp_username TEXT NOT NULL DEFAULT '',
+            from_address TEXT NOT NULL DEFAULT '',
+            security TEXT NOT NULL DEFAULT 'starttls'
         )",
         params![],
     )?;
     conn.execute(
-        "INSERT OR IGNORE INTO invoice_settings (id) VALUES (1)",
+        "INSERT OR IGNORE INTO email_settings (id) VALUES (1)",
         params![],
     )?;
     Ok(())
 }
 
 /// Client information for invoicing
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
 pub struct Client {
     pub id: i64,
     pub name: String,
@@ -196,9 +2631,26 @@ pub struct Client {
     pub address_postal: String,
     pub address_country: String,
     pub email: String,
+    pub archived: bool,
+    /// Month-end reminder day for this client; `None` uses the business default
+    pub reminder_day: Option<i64>,
+    /// Business profile this client is invoiced from; `None` uses the default
+    #[serde(default)]
+    pub profile_id: Option<i64>,
 }
 
 impl Client {
+    /// Check required fields and formats before saving
+    pub fn validate(&self) -> std::result::Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("Client name is required".to_string());
+        }
+        if !self.email.is_empty() && !is_valid_email(&self.email) {
+            return Err(format!("Invalid email address '{}'", self.email));
+        }
+        Ok(())
+    }
+
     pub fn formatted_address(&self) -> String {
         let mut parts = Vec::new();
         if !self.address_street.is_empty() {
@@ -235,26 +2687,293 @@ pub fn init_clients_db(conn: &Connection) -> Result<()> {
             address_state TEXT NOT NULL DEFAULT '',
             address_postal TEXT NOT NULL DEFAULT '',
             address_country TEXT NOT NULL DEFAULT '',
-            email TEXT NOT NULL DEFAULT ''
+            email TEXT NOT NULL DEFAULT '',
+            archived INTEGER NOT NULL DEFAULT 0,
+            reminder_day INTEGER
         )",
         params![],
     )?;
+    add_column_if_missing(conn, "clients", "archived", "INTEGER NOT NULL DEFAULT 0")?;
+    add_column_if_missing(conn, "clients", "reminder_day", "INTEGER")?;
+    add_column_if_missing(conn, "clients", "profile_id", "INTEGER")?;
+    // Client names are unique regardless of case. Older databases may already
+    // contain duplicates; the index is skipped until `meter client merge` has
+    // cleaned them up.
+    let _ = conn.execute(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_clients_name ON clients(name COLLATE NOCASE)",
+        params![],
+    );
     Ok(())
 }
 
-/// Invoice record for tracking issued invoices
+/// Rate multipliers a client pays for work outside normal hours. Each is `None`
+/// when that kind of work is billed at the normal rate.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct OvertimeRates {
+    /// Multiplier for hours between `evening_start` and `evening_end`
+    pub evening: Option<f64>,
+    pub evening_start: NaiveTime,
+    /// End of the evening window, the next morning when before `evening_start`
+    pub evening_end: NaiveTime,
+    /// Multiplier for hours on Saturdays and Sundays
+    pub weekend: Option<f64>,
+    /// Multiplier for hours on public holidays (see `meter off add --holiday`)
+    pub holiday: Option<f64>,
+}
+
+impl Default for OvertimeRates {
+    fn default() -> Self {
+        OvertimeRates {
+            evening: None,
+            evening_start: NaiveTime::from_hms_opt(18, 0, 0).expect("valid time"),
+            evening_end: NaiveTime::from_hms_opt(8, 0, 0).expect("valid time"),
+            weekend: None,
+            holiday: None,
+        }
+    }
+}
+
+impl OvertimeRates {
+    pub fn is_empty(&self) -> bool {
+        self.evening.is_none() && self.weekend.is_none() && self.holiday.is_none()
+    }
+
+    /// Whether `time` of day falls in the evening window
+    pub fn is_evening(&self, time: NaiveTime) -> bool {
+        if self.evening_start <= self.evening_end {
+            self.evening_start <= time && time < self.evening_end
+        } else {
+            time >= self.evening_start || time < self.evening_end
+        }
+    }
+}
+
+/// The kinds of work `OvertimeRates` can charge more for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum OvertimeKind {
+    Evening,
+    Weekend,
+    Holiday,
+}
+
+impl OvertimeKind {
+    /// Name of the line item on the invoice
+    pub fn label(&self) -> &'static str {
+        match self {
+            OvertimeKind::Evening => "After-hours",
+            OvertimeKind::Weekend => "Weekend",
+            OvertimeKind::Holiday => "Holiday",
+        }
+    }
+}
+
+pub fn init_overtime_db(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS overtime_rates (
+            client_id INTEGER PRIMARY KEY REFERENCES clients(id) ON DELETE CASCADE,
+            evening REAL,
+            evening_start TEXT NOT NULL DEFAULT '18:00',
+            evening_end TEXT NOT NULL DEFAULT '08:00',
+            weekend REAL,
+            holiday REAL
+        )",
+        params![],
+    )?;
+    Ok(())
+}
+
+/// Hours from a finished month that still haven't been invoiced
+#[derive(Debug, Clone)]
+pub struct InvoiceReminder {
+    pub year: i32,
+    pub month: u32,
+    /// Client the hours bill to, if their projects are linked to one
+    pub client: Option<String>,
+    pub hours: f64,
+}
+
+impl InvoiceReminder {
+    /// e.g. "June 2025 has 42.0 unbilled hours for Acme - generate invoice?"
+    pub fn message(&self) -> String {
+        let month = NaiveDate::from_ymd_opt(self.year, self.month, 1)
+            .map(|d| d.format("%B %Y").to_string())
+            .unwrap_or_default();
+        match &self.client {
+            Some(client) => format!(
+                "{} has {:.1} unbilled hours for {} - generate invoice?",
+                month, self.hours, client
+            ),
+            None => format!(
+                "{} has {:.1} unbilled hours - generate invoice?",
+                month, self.hours
+            ),
+        }
+    }
+}
+
+/// When late-payment reminders go out for an overdue invoice, and whether they
+/// are emailed to the client as well as shown as a notification
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct DunningSettings {
+    /// Days past the due date, in ascending order (empty = off)
+    pub schedule: Vec<i64>,
+    /// Email each reminder to the client
+    pub email: bool,
+}
+
+impl DunningSettings {
+    /// The schedule as e.g. "+3, +10, +20 days", or "off"
+    pub fn schedule_summary(&self) -> String {
+        if self.schedule.is_empty() {
+            return "off".to_string();
+        }
+        let steps: Vec<String> = self.schedule.iter().map(|d| format!("+{}", d)).collect();
+        format!("{} days", steps.join(", "))
+    }
+}
+
+/// Parse a reminder schedule given as days past due, e.g. "3,10,20" or
+/// "+3, +10, +20", or "off"
+pub fn parse_dunning_schedule(s: &str) -> std::result::Result<Vec<i64>, String> {
+    let s = s.trim();
+    if s.eq_ignore_ascii_case("off") || s.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut days = Vec::new();
+    for part in s.split(',') {
+        let part = part.trim();
+        match part.trim_start_matches('+').parse::<i64>() {
+            Ok(d) if d > 0 => days.push(d),
+            _ => {
+                return Err(format!(
+                    "Invalid reminder day '{}' (expected days past due, e.g. 3,10,20)",
+                    part
+                ));
+            }
+        }
+    }
+    days.sort_unstable();
+    days.dedup();
+    Ok(days)
+}
+
+/// A late-payment reminder due for an overdue invoice
 #[derive(Debug, Clone)]
+pub struct DunningReminder {
+    pub invoice_number: i64,
+    pub client_id: Option<i64>,
+    pub profile_id: i64,
+    pub total: Money,
+    /// Currency of the invoice's first rate, as `invoice_currency` picks it
+    pub currency: String,
+    pub due_date: NaiveDate,
+    /// Step of the schedule, in days past due, this reminder is for
+    pub step: i64,
+    pub days_overdue: i64,
+}
+
+impl DunningReminder {
+    /// e.g. "Invoice #0007 is 12 days past due"
+    pub fn message(&self) -> String {
+        format!(
+            "Invoice #{:04} is {} day{} past due",
+            self.invoice_number,
+            self.days_overdue,
+            if self.days_overdue == 1 { "" } else { "s" }
+        )
+    }
+}
+
+/// A late-payment reminder that has gone out
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DunningLogEntry {
+    pub invoice_number: i64,
+    pub step: i64,
+    pub sent_at: DateTime<Utc>,
+    /// Address the reminder was emailed to
+    pub emailed_to: Option<String>,
+    /// Why emailing it failed
+    pub error: Option<String>,
+}
+
+pub fn init_dunning_db(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS dunning_settings (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            schedule TEXT NOT NULL DEFAULT '',
+            email INTEGER NOT NULL DEFAULT 0
+        )",
+        params![],
+    )?;
+    conn.execute(
+        "INSERT OR IGNORE INTO dunning_settings (id) VALUES (1)",
+        params![],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS dunning_log (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            invoice_number INTEGER NOT NULL,
+            step INTEGER NOT NULL,
+            sent_at TEXT NOT NULL,
+            emailed_to TEXT,
+            error TEXT,
+            UNIQUE (invoice_number, step)
+        )",
+        params![],
+    )?;
+    Ok(())
+}
+
+/// Loose email format check: one `@`, a non-empty local part, and a dotted domain
+pub fn is_valid_email(email: &str) -> bool {
+    if email.chars().any(char::is_whitespace) {
+        return false;
+    }
+    match email.split_once('@') {
+        Some((local, domain)) => {
+            !local.is_empty()
+                && !domain.contains('@')
+                && domain.contains('.')
+                && !domain.starts_with('.')
+                && !domain.ends_with('.')
+        }
+        None => false,
+    }
+}
+
+/// Invoice record for tracking issued invoices
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Invoice {
     pub id: i64,
     pub invoice_number: i64,
     pub client_id: Option<i64>,
     pub date_issued: String,
     pub due_date: String,
-    pub subtotal: f64,
+    pub subtotal: Money,
     pub tax_rate: f64,
-    pub tax_amount: f64,
-    pub total: f64,
+    pub tax_amount: Money,
+    pub total: Money,
     pub file_path: String,
+    /// JSON snapshot of what the invoice said when issued (client, settings, rates,
+    /// line items), so later edits don't rewrite history. `None` for older invoices.
+    pub snapshot: Option<String>,
+    /// Why the invoice was voided; `None` while it stands
+    pub void_reason: Option<String>,
+    /// Number of the voided invoice this one replaces
+    pub supersedes: Option<i64>,
+    /// Business profile the invoice was issued from
+    pub profile_id: i64,
+}
+
+impl Invoice {
+    pub fn is_void(&self) -> bool {
+        self.void_reason.is_some()
+    }
+
+    /// Whether the PDF this record points at has been moved or deleted
+    pub fn file_missing(&self) -> bool {
+        !std::path::Path::new(&self.file_path).exists()
+    }
 }
 
 pub fn init_invoices_db(conn: &Connection) -> Result<()> {
@@ -270,9 +2989,324 @@ pub fn init_invoices_db(conn: &Connection) -> Result<()> {
             tax_amount REAL NOT NULL,
             total REAL NOT NULL,
             file_path TEXT NOT NULL,
+            snapshot TEXT,
             FOREIGN KEY (client_id) REFERENCES clients(id)
         )",
         params![],
     )?;
+    add_column_if_missing(conn, "invoices", "snapshot", "TEXT")?;
+    add_column_if_missing(conn, "invoices", "void_reason", "TEXT")?;
+    add_column_if_missing(conn, "invoices", "supersedes", "INTEGER")?;
+    add_column_if_missing(conn, "invoices", "profile_id", "INTEGER NOT NULL DEFAULT 1")?;
+    Ok(())
+}
+
+/// A line on an invoice that isn't an entry or an expense, e.g. a discount or a
+/// one-off charge. Negative amounts are allowed.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct InvoiceItem {
+    pub id: i64,
+    pub description: String,
+    pub amount: Money,
+}
+
+/// An invoice being prepared. Its entries are held back from other invoices
+/// but stay billed, and it has no number, until it is finalized.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct InvoiceDraft {
+    pub id: i64,
+    pub client_id: Option<i64>,
+    pub year: i32,
+    pub month: u32,
+    pub tax_rate: f64,
+    /// Timed entries are billed rounded up to this many minutes; 0 for exact
+    pub rounding_minutes: i64,
+    pub entry_ids: Vec<i64>,
+    pub items: Vec<InvoiceItem>,
+    /// Number of the voided invoice this draft will replace
+    pub supersedes: Option<i64>,
+    /// Business profile the invoice will be issued from
+    pub profile_id: i64,
+    pub created_at: DateTime<Utc>,
+}
+
+pub fn init_invoice_drafts_db(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS invoice_drafts (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            client_id INTEGER REFERENCES clients(id) ON DELETE SET NULL,
+            year INTEGER NOT NULL,
+            month INTEGER NOT NULL,
+            tax_rate REAL NOT NULL DEFAULT 0,
+            rounding_minutes INTEGER NOT NULL DEFAULT 0,
+            created_at TEXT NOT NULL
+        )",
+        params![],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS invoice_draft_entries (
+            draft_id INTEGER NOT NULL REFERENCES invoice_drafts(id) ON DELETE CASCADE,
+            entry_id INTEGER NOT NULL REFERENCES entries(id) ON DELETE CASCADE,
+            PRIMARY KEY (draft_id, entry_id)
+        )",
+        params![],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS invoice_draft_items (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            draft_id INTEGER NOT NULL REFERENCES invoice_drafts(id) ON DELETE CASCADE,
+            description TEXT NOT NULL,
+            amount REAL NOT NULL
+        )",
+        params![],
+    )?;
+    add_column_if_missing(conn, "invoice_drafts", "supersedes", "INTEGER")?;
+    add_column_if_missing(
+        conn,
+        "invoice_drafts",
+        "profile_id",
+        "INTEGER NOT NULL DEFAULT 1",
+    )?;
+    Ok(())
+}
+
+/// A timesheet sent to a client to sign off before their entries are invoiced.
+/// Until it is approved, its entries are left off invoices.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ApprovalSheet {
+    pub id: i64,
+    pub client_id: Option<i64>,
+    pub year: i32,
+    pub month: u32,
+    /// The PDF or CSV sent to the client
+    pub file_path: String,
+    pub entry_ids: Vec<i64>,
+    pub created_at: DateTime<Utc>,
+    pub approved_at: Option<DateTime<Utc>>,
+}
+
+impl ApprovalSheet {
+    pub fn is_approved(&self) -> bool {
+        self.approved_at.is_some()
+    }
+}
+
+pub fn init_approval_sheets_db(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS approval_sheets (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            client_id INTEGER REFERENCES clients(id) ON DELETE SET NULL,
+            year INTEGER NOT NULL,
+            month INTEGER NOT NULL,
+            file_path TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            approved_at TEXT
+        )",
+        params![],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS approval_sheet_entries (
+            sheet_id INTEGER NOT NULL REFERENCES approval_sheets(id) ON DELETE CASCADE,
+            entry_id INTEGER NOT NULL REFERENCES entries(id) ON DELETE CASCADE,
+            PRIMARY KEY (sheet_id, entry_id)
+        )",
+        params![],
+    )?;
+    Ok(())
+}
+
+/// What an email matched by a mail rule says about the invoice it names
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum MailAction {
+    /// The client confirmed they received the invoice
+    Acknowledged,
+    /// The invoice was paid; its entries are marked paid
+    Paid,
+}
+
+impl MailAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MailAction::Acknowledged => "acknowledged",
+            MailAction::Paid => "paid",
+        }
+    }
+}
+
+impl std::fmt::Display for MailAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for MailAction {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "acknowledged" => Ok(MailAction::Acknowledged),
+            "paid" => Ok(MailAction::Paid),
+            _ => Err(format!(
+                "Unknown action '{}' (expected acknowledged or paid)",
+                s
+            )),
+        }
+    }
+}
+
+impl ToSql for MailAction {
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.as_str()))
+    }
+}
+
+impl FromSql for MailAction {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        value
+            .as_str()?
+            .parse()
+            .map_err(|e: String| FromSqlError::Other(e.into()))
+    }
+}
+
+/// Incoming mail server watched for replies about invoices (`meter mail`). The
+/// password is kept in the OS keychain rather than in the database.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct InboxSettings {
+    pub imap_host: String,
+    pub imap_port: i64,
+    pub username: String,
+    /// Mailbox to read, opened read-only
+    pub mailbox: String,
+    /// The watcher does nothing until this is turned on
+    pub enabled: bool,
+    /// Highest message UID already looked at
+    pub last_uid: i64,
+}
+
+impl Default for InboxSettings {
+    fn default() -> Self {
+        Self {
+            imap_host: String::new(),
+            imap_port: 993,
+            username: String::new(),
+            mailbox: "INBOX".to_string(),
+            enabled: false,
+            last_uid: 0,
+        }
+    }
+}
+
+impl InboxSettings {
+    /// Whether enough is set to attempt connecting
+    pub fn is_configured(&self) -> bool {
+        !self.imap_host.is_empty() && !self.username.is_empty()
+    }
+}
+
+/// Says what an email means for the invoice whose number is in its subject:
+/// the first rule whose pattern appears in the subject or sender wins
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct MailRule {
+    pub id: i64,
+    /// Text to look for, ignoring case, e.g. "payment received" or "@billing.acme.com"
+    pub pattern: String,
+    pub action: MailAction,
+}
+
+impl MailRule {
+    pub fn matches(&self, subject: &str, from: &str) -> bool {
+        let pattern = self.pattern.to_lowercase();
+        subject.to_lowercase().contains(&pattern) || from.to_lowercase().contains(&pattern)
+    }
+}
+
+/// An email the watcher matched to an invoice, and what it did about it
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct MailMatch {
+    pub id: i64,
+    pub invoice_number: i64,
+    pub action: MailAction,
+    pub subject: String,
+    pub sender: String,
+    pub matched_at: DateTime<Utc>,
+}
+
+pub fn init_inbox_db(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS inbox_settings (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            imap_host TEXT NOT NULL DEFAULT '',
+            imap_port INTEGER NOT NULL DEFAULT 993,
+            username TEXT NOT NULL DEFAULT '',
+            mailbox TEXT NOT NULL DEFAULT 'INBOX',
+            enabled INTEGER NOT NULL DEFAULT 0,
+            last_uid INTEGER NOT NULL DEFAULT 0
+        )",
+        params![],
+    )?;
+    conn.execute(
+        "INSERT OR IGNORE INTO inbox_settings (id) VALUES (1)",
+        params![],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS mail_rules (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            pattern TEXT NOT NULL,
+            action TEXT NOT NULL
+        )",
+        params![],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS mail_matches (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            invoice_number INTEGER NOT NULL,
+            action TEXT NOT NULL,
+            subject TEXT NOT NULL,
+            sender TEXT NOT NULL,
+            matched_at TEXT NOT NULL
+        )",
+        params![],
+    )?;
+    Ok(())
+}
+
+/// A payment received through a payment provider (`meter payments sync`), and
+/// the invoice it was matched to
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Payment {
+    pub id: i64,
+    /// Where the payment came from, e.g. "stripe"
+    pub provider: String,
+    /// The provider's ID for it, so it is recorded only once
+    pub external_id: String,
+    /// Invoice the payment settled; `None` when no invoice matched
+    pub invoice_number: Option<i64>,
+    pub amount: Money,
+    /// What the provider kept, recorded as an expense
+    pub fee: Money,
+    pub currency: String,
+    pub paid_at: DateTime<Utc>,
+    /// The expense recording the fee
+    pub expense_id: Option<i64>,
+}
+
+pub fn init_payments_db(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS payments (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            provider TEXT NOT NULL,
+            external_id TEXT NOT NULL,
+            invoice_number INTEGER,
+            amount REAL NOT NULL,
+            fee REAL NOT NULL DEFAULT 0,
+            currency TEXT NOT NULL,
+            paid_at TEXT NOT NULL,
+            expense_id INTEGER REFERENCES expenses(id) ON DELETE SET NULL,
+            UNIQUE (provider, external_id)
+        )",
+        params![],
+    )?;
     Ok(())
 }
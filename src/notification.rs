@@ -1,28 +1,76 @@
-//! System notifications for Pomodoro timer events
+//! System notifications for Pomodoro timer events and hour caps, subject to the
+//! user's notification settings
 
+use chrono::Local;
 #[cfg(target_os = "macos")]
 use notify_rust::Notification;
 
-/// Send a notification when work period is complete
-pub fn notify_work_complete() {
+use crate::models::{NotificationKind, NotificationSettings};
+
+/// Show a notification unless its kind is turned off or it is quiet hours
+fn show(settings: &NotificationSettings, kind: NotificationKind, summary: &str, body: &str) {
+    if !settings.allows(kind, Local::now().time()) {
+        return;
+    }
     #[cfg(target_os = "macos")]
     {
-        let _ = Notification::new()
-            .summary("Meter - Pomodoro")
-            .body("Work period complete! Time for a break.")
-            .sound_name("Glass")
-            .show();
+        let mut notification = Notification::new();
+        notification.summary(summary).body(body);
+        if !settings.sound.is_empty() {
+            notification.sound_name(&settings.sound);
+        }
+        let _ = notification.show();
     }
+    #[cfg(not(target_os = "macos"))]
+    let _ = (summary, body);
+}
+
+/// Send a notification when work period is complete
+pub fn notify_work_complete(settings: &NotificationSettings) {
+    show(
+        settings,
+        NotificationKind::WorkComplete,
+        "Meter - Pomodoro",
+        "Work period complete! Time for a break.",
+    );
 }
 
 /// Send a notification when break is complete
-pub fn notify_break_complete() {
-    #[cfg(target_os = "macos")]
-    {
-        let _ = Notification::new()
-            .summary("Meter - Pomodoro")
-            .body("Break complete! Ready to resume work?")
-            .sound_name("Glass")
-            .show();
-    }
+pub fn notify_break_complete(settings: &NotificationSettings) {
+    show(
+        settings,
+        NotificationKind::BreakComplete,
+        "Meter - Pomodoro",
+        "Break complete! Ready to resume work?",
+    );
+}
+
+/// Send a notification when a countdown timer reaches its target
+pub fn notify_target_reached(settings: &NotificationSettings, project: &str, target: &str) {
+    show(
+        settings,
+        NotificationKind::WorkComplete,
+        "Meter - Timer",
+        &format!("{} on '{}' is up", target, project),
+    );
+}
+
+/// Send a notification when billable hours fall behind the monthly target's pace
+pub fn notify_behind_target(settings: &NotificationSettings, pace: &str) {
+    show(
+        settings,
+        NotificationKind::Reminder,
+        "Meter - Monthly target",
+        &format!("Billable hours are {}", pace),
+    );
+}
+
+/// Send a notification when the running timer pushes a project past its hour cap
+pub fn notify_cap_reached(settings: &NotificationSettings, project: &str, usage: &str) {
+    show(
+        settings,
+        NotificationKind::Reminder,
+        "Meter - Hour cap",
+        &format!("'{}' has reached its hour cap ({})", project, usage),
+    );
 }
@@ -0,0 +1,181 @@
+//! Where meter keeps its files, following the XDG base directory spec:
+//!
+//! - the database and attachments in `$XDG_DATA_HOME/meter` (`~/.local/share/meter`)
+//! - the encrypted secrets file in `$XDG_CONFIG_HOME/meter` (`~/.config/meter`)
+//! - invoice PDFs in `$METER_INVOICES_DIR`, or else `meter/invoices` under
+//!   `$XDG_DOCUMENTS_DIR` (`~/Documents`)
+//!
+//! On Windows, where the XDG variables are usually unset, the defaults are the
+//! known folders instead: `%APPDATA%\meter` for data and config and
+//! `Documents\meter\invoices` for invoices.
+//!
+//! Older versions kept everything in `~/.meter` and invoices in
+//! `~/meter/invoices`; `migrate` and `move_folder` move them over.
+
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Environment variable naming the directory invoice PDFs are written to
+pub const INVOICES_DIR_VAR: &str = "METER_INVOICES_DIR";
+
+fn home() -> PathBuf {
+    dirs::home_dir().unwrap_or_else(|| PathBuf::from("."))
+}
+
+/// `$var`, or when it is unset `default` under the home directory (the
+/// `known` folder on Windows). Relative paths are ignored, as the spec requires.
+fn xdg_dir(var: &str, known: fn() -> Option<PathBuf>, default: &str) -> PathBuf {
+    env::var_os(var)
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .or_else(|| if cfg!(windows) { known() } else { None })
+        .unwrap_or_else(|| home().join(default))
+}
+
+/// Where older versions kept the database, attachments and secrets
+fn legacy_dir() -> PathBuf {
+    home().join(".meter")
+}
+
+/// Where older versions wrote invoice PDFs
+fn legacy_invoices_dir() -> PathBuf {
+    home().join("meter").join("invoices")
+}
+
+pub fn data_dir() -> PathBuf {
+    xdg_dir("XDG_DATA_HOME", dirs::data_dir, ".local/share").join("meter")
+}
+
+pub fn config_dir() -> PathBuf {
+    xdg_dir("XDG_CONFIG_HOME", dirs::config_dir, ".config").join("meter")
+}
+
+pub fn invoices_dir() -> PathBuf {
+    match env::var_os(INVOICES_DIR_VAR).filter(|dir| !dir.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => xdg_dir("XDG_DOCUMENTS_DIR", dirs::document_dir, "Documents")
+            .join("meter")
+            .join("invoices"),
+    }
+}
+
+/// The database file. Until `migrate` has moved it, this is the old one in
+/// `~/.meter`, so a failed or postponed move never opens an empty database.
+pub fn db_path() -> PathBuf {
+    newer_or_legacy(data_dir().join("db.sqlite"), legacy_dir().join("db.sqlite"))
+}
+
+/// The encrypted secrets file, in `~/.meter` until `migrate` has moved it
+pub fn secrets_file() -> PathBuf {
+    newer_or_legacy(
+        config_dir().join("secrets.enc"),
+        legacy_dir().join("secrets.enc"),
+    )
+}
+
+pub fn attachments_dir() -> PathBuf {
+    data_dir().join("attachments")
+}
+
+/// Holds the menu bar app's pid while it runs. The app keeps it locked, so a
+/// second copy, or `meter menubar status`, can tell it is there.
+#[cfg(target_os = "macos")]
+pub fn menubar_pid_file() -> PathBuf {
+    data_dir().join("menubar.pid")
+}
+
+fn newer_or_legacy(path: PathBuf, legacy: PathBuf) -> PathBuf {
+    if !path.exists() && legacy.exists() {
+        legacy
+    } else {
+        path
+    }
+}
+
+/// Move the database and secrets from where older versions kept them to their
+/// XDG locations. Nothing is moved over something already in the new place,
+/// and the database stays put while another process has it open (its `-wal`
+/// file exists), to be moved on a later run. Returns each `(from, to)` moved.
+/// The invoice and attachment folders are left to `move_folder`, since paths
+/// stored in the database point into them.
+pub fn migrate() -> io::Result<Vec<(PathBuf, PathBuf)>> {
+    let legacy = legacy_dir();
+    let mut moved = Vec::new();
+
+    let db = data_dir().join("db.sqlite");
+    let old_db = legacy.join("db.sqlite");
+    if old_db.exists() && !db.exists() && !legacy.join("db.sqlite-wal").exists() {
+        fs::create_dir_all(data_dir())?;
+        fs::rename(&old_db, &db)?;
+        let old_shm = legacy.join("db.sqlite-shm");
+        if old_shm.exists() {
+            fs::remove_file(old_shm)?;
+        }
+        moved.push((old_db, db));
+    }
+
+    let old_secrets = legacy.join("secrets.enc");
+    let secrets = config_dir().join("secrets.enc");
+    if old_secrets.exists() && !secrets.exists() {
+        move_folder(&old_secrets, &secrets)?;
+        moved.push((old_secrets, secrets));
+    }
+
+    // Leave no empty folder behind
+    let _ = fs::remove_dir(&legacy);
+    Ok(moved)
+}
+
+/// Folders older versions kept invoices and attachments in that still have to
+/// be moved, as `(from, to)`. Rewrite the paths stored under `from` (see
+/// `Db::relocate_files`) before moving each with `move_folder`, so a move that
+/// is interrupted is picked up again on the next run.
+pub fn legacy_folders() -> Vec<(PathBuf, PathBuf)> {
+    [
+        (legacy_dir().join("attachments"), attachments_dir()),
+        (legacy_invoices_dir(), invoices_dir()),
+    ]
+    .into_iter()
+    .filter(|(from, to)| from.exists() && !to.exists())
+    .collect()
+}
+
+/// Move a file or folder to `to`, creating its parent folders, then remove the
+/// legacy folders if nothing is left in them
+pub fn move_folder(from: &Path, to: &Path) -> io::Result<()> {
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    rename_or_copy(from, to)?;
+    let _ = fs::remove_dir(legacy_dir());
+    let _ = fs::remove_dir(home().join("meter"));
+    Ok(())
+}
+
+/// Rename `from` to `to`, copying and then deleting it when they are on
+/// different filesystems
+fn rename_or_copy(from: &Path, to: &Path) -> io::Result<()> {
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    copy_all(from, to)?;
+    if from.is_dir() {
+        fs::remove_dir_all(from)
+    } else {
+        fs::remove_file(from)
+    }
+}
+
+fn copy_all(from: &Path, to: &Path) -> io::Result<()> {
+    if !from.is_dir() {
+        return fs::copy(from, to).map(|_| ());
+    }
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        copy_all(&entry.path(), &to.join(entry.file_name()))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,172 @@
+//! Payments received through Stripe, matched to invoices so they can be marked
+//! paid without checking the dashboard by hand. Charges are read from Stripe's
+//! balance transactions, which also give the fee Stripe kept. The secret key is
+//! kept with the other secrets (see `secrets`).
+
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+
+use crate::inbox::invoice_number_in_subject;
+use crate::models::{Invoice, Money};
+use crate::secrets;
+
+const STRIPE_URL: &str = "https://api.stripe.com/v1/balance_transactions";
+const USER_AGENT: &str = "meter (https://github.com/bpeterson92/meter)";
+const KEY_SECRET: &str = "stripe:secret_key";
+
+/// Currencies Stripe counts in whole units rather than cents
+const ZERO_DECIMAL_CURRENCIES: [&str; 16] = [
+    "bif", "clp", "djf", "gnf", "jpy", "kmf", "krw", "mga", "pyg", "rwf", "ugx", "vnd", "vuv",
+    "xaf", "xof", "xpf",
+];
+
+/// A charge, as Stripe's balance transaction for it reports it
+#[derive(Debug, Clone, PartialEq)]
+pub struct StripePayment {
+    /// The balance transaction ID (`txn_...`)
+    pub id: String,
+    pub amount: Money,
+    pub fee: Money,
+    /// Upper-case currency code, e.g. "USD"
+    pub currency: String,
+    pub created: DateTime<Utc>,
+    pub description: String,
+    /// `invoice_number` or `invoice` from the charge's metadata
+    pub invoice_ref: Option<String>,
+}
+
+/// The stored Stripe secret key, if there is one
+pub fn stripe_key() -> Result<Option<String>, String> {
+    secrets::get(KEY_SECRET)
+}
+
+/// Store the Stripe secret key, or remove it when `key` is empty
+pub fn set_stripe_key(key: &str) -> Result<(), String> {
+    if key.is_empty() {
+        secrets::delete(KEY_SECRET)
+    } else {
+        secrets::set(KEY_SECRET, key)
+    }
+}
+
+/// Charges made on or after `since`, oldest first
+pub fn fetch_stripe(key: &str, since: DateTime<Utc>) -> Result<Vec<StripePayment>, String> {
+    let mut payments = Vec::new();
+    let mut starting_after: Option<String> = None;
+    loop {
+        let mut request = ureq::get(STRIPE_URL)
+            .set("Authorization", &format!("Bearer {}", key))
+            .set("User-Agent", USER_AGENT)
+            .query("type", "charge")
+            .query("limit", "100")
+            .query("created[gte]", &since.timestamp().to_string())
+            .query("expand[]", "data.source");
+        if let Some(id) = &starting_after {
+            request = request.query("starting_after", id);
+        }
+        let page: Value = match request.call() {
+            Ok(response) => response
+                .into_json()
+                .map_err(|e| format!("Unexpected response from Stripe: {}", e))?,
+            Err(ureq::Error::Status(code, response)) => {
+                let body = response.into_string().unwrap_or_default();
+                return Err(format!(
+                    "Stripe refused the request ({}): {}",
+                    code,
+                    body.trim()
+                ));
+            }
+            Err(e) => return Err(format!("Failed to reach Stripe: {}", e)),
+        };
+        payments.extend(parse_balance_transactions(&page));
+
+        let last = page["data"]
+            .as_array()
+            .and_then(|data| data.last())
+            .and_then(|t| t["id"].as_str());
+        match (page["has_more"].as_bool(), last) {
+            (Some(true), Some(last)) => starting_after = Some(last.to_string()),
+            _ => break,
+        }
+    }
+    payments.sort_by_key(|p| p.created);
+    Ok(payments)
+}
+
+/// The charges in a page of `GET /v1/balance_transactions` with their sources
+/// expanded. Transactions missing an ID, amount or time are skipped.
+pub fn parse_balance_transactions(page: &Value) -> Vec<StripePayment> {
+    let Some(data) = page["data"].as_array() else {
+        return Vec::new();
+    };
+    data.iter()
+        .filter_map(|t| {
+            let currency = t["currency"].as_str().unwrap_or("usd").to_ascii_lowercase();
+            let source = &t["source"];
+            let metadata = &source["metadata"];
+            let invoice_ref = ["invoice_number", "invoice"]
+                .iter()
+                .find_map(|key| metadata[key].as_str())
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty());
+            Some(StripePayment {
+                id: t["id"].as_str()?.to_string(),
+                amount: from_minor_units(t["amount"].as_i64()?, &currency),
+                fee: from_minor_units(t["fee"].as_i64().unwrap_or(0), &currency),
+                created: DateTime::from_timestamp(t["created"].as_i64()?, 0)?,
+                description: source["description"]
+                    .as_str()
+                    .or(t["description"].as_str())
+                    .unwrap_or_default()
+                    .to_string(),
+                invoice_ref,
+                currency: currency.to_ascii_uppercase(),
+            })
+        })
+        .collect()
+}
+
+/// The invoice `payment` settles: the number in its metadata, else one named in
+/// its description, else the only invoice not in `settled` whose total is
+/// exactly the amount paid. Voided invoices never match.
+pub fn match_invoice(
+    payment: &StripePayment,
+    invoices: &[Invoice],
+    settled: &[i64],
+) -> Option<i64> {
+    let standing = |number: i64| {
+        invoices
+            .iter()
+            .any(|i| i.invoice_number == number && !i.is_void())
+    };
+    let referenced = payment
+        .invoice_ref
+        .as_deref()
+        .and_then(|r| {
+            r.trim_start_matches('#')
+                .parse()
+                .ok()
+                .or_else(|| invoice_number_in_subject(r))
+        })
+        .or_else(|| invoice_number_in_subject(&payment.description));
+    if let Some(number) = referenced {
+        return Some(number).filter(|n| standing(*n));
+    }
+
+    let mut by_amount = invoices.iter().filter(|i| {
+        !i.is_void() && !settled.contains(&i.invoice_number) && i.total == payment.amount
+    });
+    match (by_amount.next(), by_amount.next()) {
+        (Some(invoice), None) => Some(invoice.invoice_number),
+        _ => None,
+    }
+}
+
+/// An amount in a currency's smallest unit, e.g. cents, as `Money`
+fn from_minor_units(amount: i64, currency: &str) -> Money {
+    if ZERO_DECIMAL_CURRENCIES.contains(&currency) {
+        Money::from_cents(amount * 100)
+    } else {
+        Money::from_cents(amount)
+    }
+}
@@ -0,0 +1,544 @@
+//! `meter query`: a small filter language over entries for scripts and one-off
+//! reports, such as
+//!
+//! ```text
+//! project=acme AND billed=false AND start>=2024-06-01 GROUP BY week
+//! ```
+//!
+//! Conditions are `FIELD OP VALUE` joined by `AND`, optionally followed by
+//! `GROUP BY` a key to total hours per group instead of listing entries. Values
+//! with spaces or operator characters go in double quotes. Like `report`,
+//! running a query is a pure function of the entries and tags passed in.
+
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{DateTime, Datelike, Local, NaiveDate, NaiveDateTime, Utc};
+use serde::Serialize;
+
+use crate::approval::csv_field;
+use crate::billing;
+use crate::models::{Entry, EntryStatus};
+
+/// Output format of `meter query`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueryFormat {
+    Json,
+    Csv,
+}
+
+impl QueryFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            QueryFormat::Json => "json",
+            QueryFormat::Csv => "csv",
+        }
+    }
+}
+
+impl std::fmt::Display for QueryFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for QueryFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "json" => Ok(QueryFormat::Json),
+            "csv" => Ok(QueryFormat::Csv),
+            _ => Err(format!("Unknown format '{}' (expected json or csv)", s)),
+        }
+    }
+}
+
+/// Entry fields a condition can test
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Project,
+    Description,
+    Status,
+    /// Billed, invoiced or paid
+    Billed,
+    Tag,
+    Issue,
+    Location,
+    Start,
+    End,
+    Hours,
+}
+
+impl Field {
+    const NAMES: &'static str =
+        "project, description, status, billed, tag, issue, location, start, end or hours";
+
+    fn parse(s: &str) -> Result<Field, String> {
+        match s.to_ascii_lowercase().as_str() {
+            "project" => Ok(Field::Project),
+            "description" | "desc" => Ok(Field::Description),
+            "status" => Ok(Field::Status),
+            "billed" => Ok(Field::Billed),
+            "tag" | "tags" => Ok(Field::Tag),
+            "issue" => Ok(Field::Issue),
+            "location" => Ok(Field::Location),
+            "start" => Ok(Field::Start),
+            "end" => Ok(Field::End),
+            "hours" => Ok(Field::Hours),
+            _ => Err(format!("unknown field '{}' (expected {})", s, Field::NAMES)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    /// Contains, ignoring case
+    Contains,
+}
+
+impl Op {
+    fn parse(s: &str) -> Result<Op, String> {
+        match s {
+            "=" | "==" => Ok(Op::Eq),
+            "!=" => Ok(Op::Ne),
+            "<" => Ok(Op::Lt),
+            "<=" => Ok(Op::Le),
+            ">" => Ok(Op::Gt),
+            ">=" => Ok(Op::Ge),
+            "~" => Ok(Op::Contains),
+            _ => Err(format!(
+                "unknown operator '{}' (expected =, !=, <, <=, >, >= or ~)",
+                s
+            )),
+        }
+    }
+
+    fn compare<T: PartialOrd>(self, left: T, right: T) -> bool {
+        match self {
+            Op::Eq | Op::Contains => left == right,
+            Op::Ne => left != right,
+            Op::Lt => left < right,
+            Op::Le => left <= right,
+            Op::Gt => left > right,
+            Op::Ge => left >= right,
+        }
+    }
+}
+
+/// A condition's value, checked against its field when the query is parsed
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Text(String),
+    Status(EntryStatus),
+    Bool(bool),
+    Date(NaiveDate),
+    DateTime(NaiveDateTime),
+    Number(f64),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Condition {
+    field: Field,
+    op: Op,
+    value: Value,
+}
+
+/// What `GROUP BY` totals hours by
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GroupBy {
+    Day,
+    Week,
+    Month,
+    Project,
+    Status,
+    Tag,
+}
+
+impl GroupBy {
+    fn parse(s: &str) -> Result<GroupBy, String> {
+        match s.to_ascii_lowercase().as_str() {
+            "day" => Ok(GroupBy::Day),
+            "week" => Ok(GroupBy::Week),
+            "month" => Ok(GroupBy::Month),
+            "project" => Ok(GroupBy::Project),
+            "status" => Ok(GroupBy::Status),
+            "tag" => Ok(GroupBy::Tag),
+            _ => Err(format!(
+                "can't group by '{}' (expected day, week, month, project, status or tag)",
+                s
+            )),
+        }
+    }
+
+    /// The groups `entry` falls in: one, or one per tag
+    fn keys(self, entry: &Entry, tags: &[String]) -> Vec<String> {
+        let day = entry.start.with_timezone(&Local).date_naive();
+        match self {
+            GroupBy::Day => vec![day.format("%Y-%m-%d").to_string()],
+            GroupBy::Week => {
+                let week = day.iso_week();
+                vec![format!("{}-W{:02}", week.year(), week.week())]
+            }
+            GroupBy::Month => vec![day.format("%Y-%m").to_string()],
+            GroupBy::Project => vec![entry.project.clone()],
+            GroupBy::Status => vec![entry.status.to_string()],
+            GroupBy::Tag if tags.is_empty() => vec![String::new()],
+            GroupBy::Tag => tags.to_vec(),
+        }
+    }
+}
+
+/// A parsed query
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    conditions: Vec<Condition>,
+    pub group_by: Option<GroupBy>,
+}
+
+/// One entry in a query's result
+#[derive(Debug, Clone, Serialize)]
+pub struct QueryRow {
+    pub id: i64,
+    pub project: String,
+    pub description: String,
+    pub start: DateTime<Utc>,
+    /// `None` while the entry is running
+    pub end: Option<DateTime<Utc>>,
+    pub status: EntryStatus,
+    pub hours: Option<f64>,
+    pub tags: Vec<String>,
+}
+
+/// Totals for one group of a `GROUP BY` query
+#[derive(Debug, Clone, Serialize)]
+pub struct QueryGroup {
+    /// Day, week, month, project, status or tag; empty for untagged entries
+    pub group: String,
+    pub entries: usize,
+    pub hours: f64,
+}
+
+/// What a query returns: matching entries, or totals per group
+#[derive(Debug, Clone)]
+pub enum QueryResult {
+    Entries(Vec<QueryRow>),
+    Groups(Vec<QueryGroup>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Quoted(String),
+    Op(String),
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, String> {
+    let is_op = |c: char| matches!(c, '=' | '!' | '<' | '>' | '~');
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '"' {
+            chars.next();
+            let mut text = String::new();
+            loop {
+                match chars.next() {
+                    Some('"') => break,
+                    Some(c) => text.push(c),
+                    None => return Err("unclosed quote".to_string()),
+                }
+            }
+            tokens.push(Token::Quoted(text));
+        } else if is_op(c) {
+            let mut op = String::new();
+            while let Some(&c) = chars.peek().filter(|c| is_op(**c)) {
+                op.push(c);
+                chars.next();
+            }
+            tokens.push(Token::Op(op));
+        } else {
+            let mut word = String::new();
+            while let Some(&c) = chars
+                .peek()
+                .filter(|c| !c.is_whitespace() && !is_op(**c) && **c != '"')
+            {
+                word.push(c);
+                chars.next();
+            }
+            tokens.push(Token::Word(word));
+        }
+    }
+    Ok(tokens)
+}
+
+fn is_keyword(token: Option<&Token>, keyword: &str) -> bool {
+    matches!(token, Some(Token::Word(w)) if w.eq_ignore_ascii_case(keyword))
+}
+
+/// Parse a query such as `project=acme AND start>=2024-06-01 GROUP BY week`.
+/// An empty query matches every entry.
+pub fn parse(input: &str) -> Result<Query, String> {
+    let tokens = tokenize(input)?;
+    let mut tokens = tokens.iter().peekable();
+    let mut conditions = Vec::new();
+    let mut group_by = None;
+    while tokens.peek().is_some() {
+        if is_keyword(tokens.peek().copied(), "group") {
+            tokens.next();
+            if !is_keyword(tokens.next(), "by") {
+                return Err("expected BY after GROUP".to_string());
+            }
+            match tokens.next() {
+                Some(Token::Word(key)) => group_by = Some(GroupBy::parse(key)?),
+                _ => return Err("expected a key after GROUP BY".to_string()),
+            }
+            if tokens.peek().is_some() {
+                return Err("GROUP BY must come last".to_string());
+            }
+            break;
+        }
+        if !conditions.is_empty() && !is_keyword(tokens.next(), "and") {
+            return Err("expected AND between conditions".to_string());
+        }
+        let (name, field) = match tokens.next() {
+            Some(Token::Word(name)) => (name, Field::parse(name)?),
+            _ => return Err("expected a field name".to_string()),
+        };
+        let op = match tokens.next() {
+            Some(Token::Op(op)) => Op::parse(op)?,
+            _ => return Err(format!("expected an operator after {}", name)),
+        };
+        let value = match tokens.next() {
+            Some(Token::Word(value) | Token::Quoted(value)) => parse_value(field, op, value)?,
+            _ => return Err(format!("expected a value to compare {} with", name)),
+        };
+        conditions.push(Condition { field, op, value });
+    }
+    Ok(Query {
+        conditions,
+        group_by,
+    })
+}
+
+fn parse_value(field: Field, op: Op, value: &str) -> Result<Value, String> {
+    let ordered = matches!(op, Op::Lt | Op::Le | Op::Gt | Op::Ge);
+    let equality_only = || -> Result<(), String> {
+        if ordered || op == Op::Contains {
+            Err("status and billed only support = and !=".to_string())
+        } else {
+            Ok(())
+        }
+    };
+    match field {
+        Field::Project | Field::Description | Field::Tag | Field::Issue | Field::Location => {
+            if ordered {
+                return Err("text fields only support =, != and ~".to_string());
+            }
+            Ok(Value::Text(value.to_string()))
+        }
+        Field::Status => {
+            equality_only()?;
+            Ok(Value::Status(value.parse()?))
+        }
+        Field::Billed => {
+            equality_only()?;
+            match value.to_ascii_lowercase().as_str() {
+                "true" | "yes" => Ok(Value::Bool(true)),
+                "false" | "no" => Ok(Value::Bool(false)),
+                _ => Err(format!(
+                    "invalid billed value '{}' (expected true or false)",
+                    value
+                )),
+            }
+        }
+        Field::Start | Field::End => {
+            if op == Op::Contains {
+                return Err("dates don't support ~".to_string());
+            }
+            NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .map(Value::Date)
+                .or_else(|_| {
+                    NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M").map(Value::DateTime)
+                })
+                .map_err(|_| {
+                    format!(
+                        "invalid date '{}' (expected YYYY-MM-DD or \"YYYY-MM-DD HH:MM\")",
+                        value
+                    )
+                })
+        }
+        Field::Hours => {
+            if op == Op::Contains {
+                return Err("hours don't support ~".to_string());
+            }
+            value
+                .parse()
+                .map(Value::Number)
+                .map_err(|_| format!("invalid hours '{}'", value))
+        }
+    }
+}
+
+impl Query {
+    /// Whether `entry`, with `tags`, meets every condition. Running entries
+    /// have no end or hours, so conditions on those never match them.
+    pub fn matches(&self, entry: &Entry, tags: &[String]) -> bool {
+        self.conditions.iter().all(|c| c.matches(entry, tags))
+    }
+
+    /// Run the query over `entries`, oldest first
+    pub fn run(&self, entries: &[Entry], tags: &HashMap<i64, Vec<String>>) -> QueryResult {
+        let no_tags = Vec::new();
+        let mut matching: Vec<(&Entry, &Vec<String>)> = entries
+            .iter()
+            .map(|e| (e, tags.get(&e.id).unwrap_or(&no_tags)))
+            .filter(|(e, tags)| self.matches(e, tags))
+            .collect();
+        matching.sort_by_key(|(e, _)| (e.start, e.id));
+
+        let Some(group_by) = self.group_by else {
+            return QueryResult::Entries(
+                matching
+                    .into_iter()
+                    .map(|(e, tags)| QueryRow {
+                        id: e.id,
+                        project: e.project.clone(),
+                        description: e.description.clone(),
+                        start: e.start,
+                        end: e.end,
+                        status: e.status,
+                        hours: billing::entry_hours(e),
+                        tags: tags.clone(),
+                    })
+                    .collect(),
+            );
+        };
+        let mut groups: BTreeMap<String, Vec<&Entry>> = BTreeMap::new();
+        for (entry, tags) in matching {
+            for key in group_by.keys(entry, tags) {
+                groups.entry(key).or_default().push(entry);
+            }
+        }
+        QueryResult::Groups(
+            groups
+                .into_iter()
+                .map(|(group, entries)| QueryGroup {
+                    group,
+                    entries: entries.len(),
+                    hours: billing::total_hours(&entries),
+                })
+                .collect(),
+        )
+    }
+}
+
+impl Condition {
+    fn matches(&self, entry: &Entry, tags: &[String]) -> bool {
+        let text = |s: &str| match &self.value {
+            Value::Text(v) if self.op == Op::Contains => {
+                s.to_lowercase().contains(&v.to_lowercase())
+            }
+            Value::Text(v) => self.op.compare(s.to_lowercase(), v.to_lowercase()),
+            _ => false,
+        };
+        // Equality for fields without an order
+        let equals = |same: bool| same == (self.op == Op::Eq);
+        let time = |t: DateTime<Utc>| {
+            let local = t.with_timezone(&Local).naive_local();
+            match self.value {
+                Value::Date(d) => self.op.compare(local.date(), d),
+                Value::DateTime(dt) => self.op.compare(local, dt),
+                _ => false,
+            }
+        };
+        match self.field {
+            Field::Project => text(&entry.project),
+            Field::Description => text(&entry.description),
+            Field::Issue => text(entry.issue.as_deref().unwrap_or_default()),
+            Field::Location => text(entry.location.as_deref().unwrap_or_default()),
+            // `tag!=x` is an entry without tag x, not one with some other tag
+            Field::Tag => match &self.value {
+                Value::Text(v) if self.op == Op::Ne => {
+                    !tags.iter().any(|tag| tag.eq_ignore_ascii_case(v))
+                }
+                _ => tags.iter().any(|tag| text(tag.as_str())),
+            },
+            Field::Status => match self.value {
+                Value::Status(status) => equals(entry.status == status),
+                _ => false,
+            },
+            Field::Billed => match self.value {
+                Value::Bool(billed) => equals(
+                    matches!(
+                        entry.status,
+                        EntryStatus::Billed | EntryStatus::Invoiced | EntryStatus::Paid
+                    ) == billed,
+                ),
+                _ => false,
+            },
+            Field::Start => time(entry.start),
+            Field::End => entry.end.is_some_and(time),
+            Field::Hours => match (&self.value, billing::entry_hours(entry)) {
+                (Value::Number(v), Some(hours)) => self.op.compare(hours, *v),
+                _ => false,
+            },
+        }
+    }
+}
+
+impl QueryResult {
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        match self {
+            QueryResult::Entries(rows) => serde_json::to_string_pretty(rows),
+            QueryResult::Groups(groups) => serde_json::to_string_pretty(groups),
+        }
+    }
+
+    /// CSV with a header row. Times are RFC 3339; tags are space separated.
+    pub fn to_csv(&self) -> String {
+        let mut out = String::new();
+        match self {
+            QueryResult::Entries(rows) => {
+                out.push_str("id,project,description,start,end,status,hours,tags\n");
+                for row in rows {
+                    let fields = [
+                        row.id.to_string(),
+                        row.project.clone(),
+                        row.description.clone(),
+                        row.start.to_rfc3339(),
+                        row.end.map(|e| e.to_rfc3339()).unwrap_or_default(),
+                        row.status.to_string(),
+                        row.hours.map(|h| format!("{:.2}", h)).unwrap_or_default(),
+                        row.tags.join(" "),
+                    ];
+                    push_csv_row(&mut out, &fields);
+                }
+            }
+            QueryResult::Groups(groups) => {
+                out.push_str("group,entries,hours\n");
+                for group in groups {
+                    let fields = [
+                        group.group.clone(),
+                        group.entries.to_string(),
+                        format!("{:.2}", group.hours),
+                    ];
+                    push_csv_row(&mut out, &fields);
+                }
+            }
+        }
+        out
+    }
+}
+
+fn push_csv_row(out: &mut String, fields: &[String]) {
+    let fields: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+    out.push_str(&fields.join(","));
+    out.push('\n');
+}
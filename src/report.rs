@@ -0,0 +1,318 @@
+//! Reports that look across the whole database rather than one invoice.
+//!
+//! Like `billing`, everything here is a pure function of entries, projects and
+//! invoices passed in, so the numbers can be tested without a terminal.
+
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, Timelike, Utc};
+
+use crate::billing;
+use crate::billing::invoice_currency;
+use crate::ical::CalendarEvent;
+use crate::invoice::InvoiceSnapshot;
+use crate::models::{BillingMode, Client, Entry, EntryStatus, Invoice, Money, Project};
+
+/// Expected income from one client in one currency. Amounts are before tax.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ForecastRow {
+    /// Client name, or `None` for projects without a client
+    pub client: Option<String>,
+    pub currency: String,
+    /// Hours finished but not yet invoiced, whether or not they are rated
+    pub unbilled_hours: f64,
+    /// What those hours come to at their projects' rates
+    pub unbilled: Money,
+    /// Fixed fees invoiced every month
+    pub recurring: Money,
+    /// Average invoiced per month over the history window, voided invoices left out
+    pub monthly_average: Money,
+    /// Unbilled work plus `months` months at the larger of the average and the
+    /// recurring fees
+    pub forecast: Money,
+}
+
+/// Forecast income for the `months` months after `today` from three sources:
+/// work done but not invoiced yet, fixed-fee projects (which are invoiced every
+/// month), and what each client was invoiced per month over the `history`
+/// complete months before this one. The monthly average already includes past
+/// fixed fees, so each future month counts whichever of the two is larger
+/// rather than both. Rows are sorted by client, with no client last.
+pub fn forecast(
+    entries: &[Entry],
+    projects: &[Project],
+    clients: &[Client],
+    invoices: &[Invoice],
+    today: NaiveDate,
+    months: u32,
+    history: u32,
+) -> Vec<ForecastRow> {
+    let project_by_name: HashMap<&str, &Project> =
+        projects.iter().map(|p| (p.name.as_str(), p)).collect();
+    let mut rows: ForecastRows = BTreeMap::new();
+
+    // Unbilled work, priced per project the way an invoice would price it
+    let mut unbilled: BTreeMap<&str, Vec<&Entry>> = BTreeMap::new();
+    for entry in entries.iter().filter(|e| {
+        e.end.is_some()
+            && matches!(
+                e.status,
+                EntryStatus::Open | EntryStatus::Submitted | EntryStatus::Billed
+            )
+    }) {
+        unbilled.entry(&entry.project).or_default().push(entry);
+    }
+    for (name, entries) in &unbilled {
+        let project = project_by_name.get(name);
+        let currency = project.and_then(|p| p.currency.as_deref()).unwrap_or("$");
+        let r = forecast_row(
+            &mut rows,
+            clients,
+            project.and_then(|p| p.client_id),
+            currency,
+        );
+        r.unbilled_hours += billing::total_hours(entries);
+        // Fixed fees are counted once a month below, not per batch of work
+        let rated = project
+            .filter(|p| p.billing_mode != BillingMode::Fixed)
+            .and_then(|p| p.rate.map(|rate| (p.billing_mode, rate)));
+        if let Some((mode, rate)) = rated {
+            r.unbilled += billing::amount(mode, rate, entries);
+        }
+    }
+
+    for project in projects {
+        if let (BillingMode::Fixed, Some(rate)) = (project.billing_mode, project.rate) {
+            let currency = project.currency.as_deref().unwrap_or("$");
+            forecast_row(&mut rows, clients, project.client_id, currency).recurring += rate;
+        }
+    }
+
+    // The history window ends with last month
+    let this_month = month_index(today.year(), today.month());
+    let window = (this_month - history as i32)..this_month;
+    let mut invoiced: BTreeMap<(Option<i64>, String), i64> = BTreeMap::new();
+    for invoice in invoices.iter().filter(|i| !i.is_void()) {
+        let snapshot = invoice
+            .snapshot
+            .as_deref()
+            .and_then(|s| InvoiceSnapshot::from_json(s).ok());
+        let (period, currency) = match &snapshot {
+            Some(s) => (
+                Some(month_index(s.year, s.month)),
+                invoice_currency(s.rates.values()),
+            ),
+            None => (
+                NaiveDate::parse_from_str(&invoice.date_issued, "%Y-%m-%d")
+                    .ok()
+                    .map(|d| month_index(d.year(), d.month())),
+                "$".to_string(),
+            ),
+        };
+        if period.is_some_and(|p| window.contains(&p)) {
+            *invoiced.entry((invoice.client_id, currency)).or_default() += invoice.subtotal.cents();
+        }
+    }
+    for ((client_id, currency), cents) in invoiced {
+        forecast_row(&mut rows, clients, client_id, &currency).monthly_average =
+            Money::from_cents(cents / history.max(1) as i64);
+    }
+
+    let mut rows: Vec<ForecastRow> = rows
+        .into_values()
+        .map(|mut r| {
+            let monthly = r.monthly_average.max(r.recurring);
+            r.forecast = r.unbilled + monthly.times(months as f64);
+            r
+        })
+        .collect();
+    rows.sort_by(|a, b| {
+        (a.client.is_none(), &a.client, &a.currency).cmp(&(
+            b.client.is_none(),
+            &b.client,
+            &b.currency,
+        ))
+    });
+    rows
+}
+
+/// Hours tracked in each hour of the day (0-23) on each weekday (Monday first),
+/// in local time. A span is split where it crosses the hour, so 9:40-10:20
+/// adds 20 minutes to 9:00 and 20 to 10:00. Day entries and running entries
+/// have no span and are left out.
+pub fn hour_heatmap(entries: &[&Entry]) -> [[f64; 24]; 7] {
+    let mut grid = [[0.0; 24]; 7];
+    for entry in entries.iter().filter(|e| e.days.is_none()) {
+        let Some(end) = entry.end else { continue };
+        let end = end.with_timezone(&Local);
+        let mut t = entry.start.with_timezone(&Local);
+        while t < end {
+            let hour_start = t
+                .with_minute(0)
+                .and_then(|t| t.with_second(0))
+                .and_then(|t| t.with_nanosecond(0))
+                .unwrap_or(t);
+            let next = (hour_start + Duration::hours(1)).min(end);
+            let weekday = t.weekday().num_days_from_monday() as usize;
+            grid[weekday][t.hour() as usize] += (next - t).num_seconds() as f64 / 3600.0;
+            t = next;
+        }
+    }
+    grid
+}
+
+/// A shade for `hours` out of the busiest cell's `max`, from blank to solid
+pub fn heat_shade(hours: f64, max: f64) -> char {
+    const SHADES: [char; 5] = [' ', '░', '▒', '▓', '█'];
+    if hours <= 0.0 || max <= 0.0 {
+        return SHADES[0];
+    }
+    let level = ((hours / max) * 4.0).ceil() as usize;
+    SHADES[level.clamp(1, 4)]
+}
+
+/// A calendar event and the tracked time that overlaps it
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReconcileRow {
+    pub event: CalendarEvent,
+    /// Seconds of timed entries within the event
+    pub tracked_secs: i64,
+    /// Projects of the entries that overlap it, alphabetically
+    pub projects: Vec<String>,
+}
+
+impl ReconcileRow {
+    pub fn event_secs(&self) -> i64 {
+        (self.event.end - self.event.start).num_seconds()
+    }
+
+    /// Whether less than `min_coverage` (0-1) of the event was tracked
+    pub fn is_untracked(&self, min_coverage: f64) -> bool {
+        (self.tracked_secs as f64) < self.event_secs() as f64 * min_coverage
+    }
+}
+
+/// How much of each meeting in `events` tracked time covers. All-day and
+/// zero-length events aren't meetings and are left out. Day entries have no
+/// span, so they don't count; a running entry counts up to `now`.
+pub fn reconcile(
+    events: &[CalendarEvent],
+    entries: &[Entry],
+    now: DateTime<Utc>,
+) -> Vec<ReconcileRow> {
+    events
+        .iter()
+        .filter(|event| !event.all_day && event.end > event.start)
+        .map(|event| {
+            let mut tracked_secs = 0;
+            let mut projects: Vec<String> = Vec::new();
+            for entry in entries.iter().filter(|e| e.days.is_none()) {
+                let start = entry.start.max(event.start);
+                let end = entry.end.unwrap_or(now).min(event.end);
+                if end > start {
+                    tracked_secs += (end - start).num_seconds();
+                    if !projects.contains(&entry.project) {
+                        projects.push(entry.project.clone());
+                    }
+                }
+            }
+            projects.sort();
+            let event_secs = (event.end - event.start).num_seconds();
+            ReconcileRow {
+                event: event.clone(),
+                tracked_secs: tracked_secs.min(event_secs),
+                projects,
+            }
+        })
+        .collect()
+}
+
+/// Hours tracked in one area and its projects
+#[derive(Debug, Clone, PartialEq)]
+pub struct AreaHours {
+    /// `None` for projects that aren't in an area
+    pub area: Option<String>,
+    pub hours: f64,
+    /// Each project's hours, most first
+    pub projects: Vec<(String, f64)>,
+}
+
+/// Hours per project in `entries`, counting a running entry up to `now`. Day
+/// entries have no hours and are left out.
+pub fn project_hours(entries: &[&Entry], now: DateTime<Utc>) -> Vec<(String, f64)> {
+    let mut hours: BTreeMap<&str, f64> = BTreeMap::new();
+    for entry in entries.iter().filter(|e| e.days.is_none()) {
+        let secs = (entry.end.unwrap_or(now) - entry.start).num_seconds();
+        *hours.entry(&entry.project).or_default() += secs as f64 / 3600.0;
+    }
+    hours
+        .into_iter()
+        .map(|(project, hours)| (project.to_string(), hours))
+        .collect()
+}
+
+/// `project_hours` rolled up by each project's area, most hours first, with
+/// projects outside any area last. Projects without hours are left out.
+pub fn area_hours(project_hours: &[(String, f64)], projects: &[Project]) -> Vec<AreaHours> {
+    let mut areas: Vec<AreaHours> = Vec::new();
+    for (project, hours) in project_hours.iter().filter(|(_, h)| *h > 0.0) {
+        let area = projects
+            .iter()
+            .find(|p| &p.name == project)
+            .and_then(|p| p.area.clone());
+        let idx = match areas.iter().position(|a| a.area == area) {
+            Some(idx) => idx,
+            None => {
+                areas.push(AreaHours {
+                    area,
+                    hours: 0.0,
+                    projects: Vec::new(),
+                });
+                areas.len() - 1
+            }
+        };
+        areas[idx].hours += hours;
+        areas[idx].projects.push((project.clone(), *hours));
+    }
+    for area in &mut areas {
+        area.projects
+            .sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    }
+    areas.sort_by(|a, b| {
+        a.area
+            .is_none()
+            .cmp(&b.area.is_none())
+            .then_with(|| b.hours.total_cmp(&a.hours))
+            .then_with(|| a.area.cmp(&b.area))
+    });
+    areas
+}
+
+/// Forecast rows keyed by client ID and currency
+type ForecastRows = BTreeMap<(Option<i64>, String), ForecastRow>;
+
+/// The row for `client_id` in `currency`, added with zero amounts if it's new
+fn forecast_row<'a>(
+    rows: &'a mut ForecastRows,
+    clients: &[Client],
+    client_id: Option<i64>,
+    currency: &str,
+) -> &'a mut ForecastRow {
+    rows.entry((client_id, currency.to_string()))
+        .or_insert_with(|| ForecastRow {
+            client: client_id
+                .and_then(|id| clients.iter().find(|c| c.id == id))
+                .map(|c| c.name.clone()),
+            currency: currency.to_string(),
+            unbilled_hours: 0.0,
+            unbilled: Money::ZERO,
+            recurring: Money::ZERO,
+            monthly_average: Money::ZERO,
+            forecast: Money::ZERO,
+        })
+}
+
+/// Months since year 0, so month ranges can be compared across years
+fn month_index(year: i32, month: u32) -> i32 {
+    year * 12 + month as i32 - 1
+}
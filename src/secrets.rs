@@ -0,0 +1,155 @@
+//! Storage for secrets such as SMTP passwords, API tokens, webhook signing keys
+//! and sync credentials. They are kept in the OS keychain (macOS Keychain,
+//! Secret Service, Windows Credential Manager). Where no keychain is available
+//! they go in `secrets.enc` in the config folder instead (see `paths`), encrypted with a key derived from
+//! the `METER_SECRETS_PASSPHRASE` environment variable.
+
+use argon2::Argon2;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, Write};
+
+use crate::paths::secrets_file;
+
+const KEYCHAIN_SERVICE: &str = "meter";
+const PASSPHRASE_VAR: &str = "METER_SECRETS_PASSPHRASE";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+/// Look up a secret by name
+pub fn get(name: &str) -> Result<Option<String>, String> {
+    match keychain_entry(name).and_then(|e| e.get_password()) {
+        Ok(value) => Ok(Some(value)),
+        Err(e) if matches!(e, keyring::Error::NoEntry) || keychain_unavailable(&e) => {
+            Ok(read_file()?.remove(name))
+        }
+        Err(e) => Err(format!("Failed to read '{}' from keychain: {}", name, e)),
+    }
+}
+
+/// Store a secret, replacing any previous value
+pub fn set(name: &str, value: &str) -> Result<(), String> {
+    match keychain_entry(name).and_then(|e| e.set_password(value)) {
+        Ok(()) => Ok(()),
+        Err(e) if keychain_unavailable(&e) => {
+            let mut secrets = read_file()?;
+            secrets.insert(name.to_string(), value.to_string());
+            write_file(&secrets)
+        }
+        Err(e) => Err(format!("Failed to store '{}' in keychain: {}", name, e)),
+    }
+}
+
+/// Remove a secret from the keychain and the fallback file. Removing one that
+/// isn't stored is not an error.
+pub fn delete(name: &str) -> Result<(), String> {
+    match keychain_entry(name).and_then(|e| e.delete_credential()) {
+        Ok(()) | Err(keyring::Error::NoEntry) => {}
+        Err(e) if keychain_unavailable(&e) => {}
+        Err(e) => return Err(format!("Failed to remove '{}' from keychain: {}", name, e)),
+    }
+    if secrets_file().exists() {
+        let mut secrets = read_file()?;
+        if secrets.remove(name).is_some() {
+            write_file(&secrets)?;
+        }
+    }
+    Ok(())
+}
+
+fn keychain_entry(name: &str) -> keyring::Result<keyring::Entry> {
+    keyring::Entry::new(KEYCHAIN_SERVICE, name)
+}
+
+/// Whether the error means there is no usable keychain, as opposed to a
+/// problem with this particular secret
+fn keychain_unavailable(e: &keyring::Error) -> bool {
+    matches!(
+        e,
+        keyring::Error::PlatformFailure(_) | keyring::Error::NoStorageAccess(_)
+    )
+}
+
+fn passphrase() -> Result<String, String> {
+    std::env::var(PASSPHRASE_VAR)
+        .ok()
+        .filter(|p| !p.is_empty())
+        .ok_or_else(|| {
+            format!(
+                "No OS keychain available: set {} to keep secrets in an encrypted file instead",
+                PASSPHRASE_VAR
+            )
+        })
+}
+
+fn derive_key(salt: &[u8]) -> Result<Key, String> {
+    let mut key = Key::default();
+    Argon2::default()
+        .hash_password_into(passphrase()?.as_bytes(), salt, &mut key)
+        .map_err(|e| format!("Failed to derive secrets key: {}", e))?;
+    Ok(key)
+}
+
+/// Read the fallback file: a random salt, a nonce, then the encrypted JSON map
+/// of name to secret. A missing file holds no secrets.
+fn read_file() -> Result<BTreeMap<String, String>, String> {
+    let path = secrets_file();
+    let data = match fs::read(&path) {
+        Ok(data) => data,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(BTreeMap::new()),
+        Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e)),
+    };
+    if data.len() < SALT_LEN + NONCE_LEN {
+        return Err(format!("{} is corrupt", path.display()));
+    }
+    let (salt, rest) = data.split_at(SALT_LEN);
+    let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+    let cipher = ChaCha20Poly1305::new(&derive_key(salt)?);
+    let plaintext = cipher
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| {
+            format!(
+                "Failed to decrypt {}: is {} correct?",
+                path.display(),
+                PASSPHRASE_VAR
+            )
+        })?;
+    serde_json::from_slice(&plaintext).map_err(|e| format!("{} is corrupt: {}", path.display(), e))
+}
+
+/// Write the fallback file with a fresh salt and nonce, readable only by the owner
+fn write_file(secrets: &BTreeMap<String, String>) -> Result<(), String> {
+    let path = secrets_file();
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    let cipher = ChaCha20Poly1305::new(&derive_key(&salt)?);
+    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let plaintext = serde_json::to_vec(secrets).map_err(|e| e.to_string())?;
+    let ciphertext = cipher
+        .encrypt(&nonce, plaintext.as_slice())
+        .map_err(|_| "Failed to encrypt secrets".to_string())?;
+
+    let mut data = Vec::with_capacity(SALT_LEN + NONCE_LEN + ciphertext.len());
+    data.extend_from_slice(&salt);
+    data.extend_from_slice(&nonce);
+    data.extend_from_slice(&ciphertext);
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options
+        .open(&path)
+        .and_then(|mut file| file.write_all(&data))
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
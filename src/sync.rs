@@ -0,0 +1,113 @@
+//! Pushing finished entries to Harvest or Toggl, for agencies that want time
+//! logged there. Each meter project is mapped to a project (and task) in the
+//! service; the API token is kept with the other secrets (see `secrets`), keyed
+//! by the account it belongs to.
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use chrono::{Local, SecondsFormat};
+use serde_json::{Value, json};
+
+use crate::models::{Entry, SyncMapping, SyncService, SyncSettings};
+use crate::secrets;
+
+const HARVEST_URL: &str = "https://api.harvestapp.com/v2/time_entries";
+const TOGGL_URL: &str = "https://api.track.toggl.com/api/v9/workspaces";
+const USER_AGENT: &str = "meter (https://github.com/bpeterson92/meter)";
+
+fn token_secret(settings: &SyncSettings) -> String {
+    format!("{}:{}", settings.service, settings.account_id)
+}
+
+/// The stored API token, if there is one
+pub fn token(settings: &SyncSettings) -> Result<Option<String>, String> {
+    secrets::get(&token_secret(settings))
+}
+
+/// Store the API token, or remove it when `token` is empty
+pub fn set_token(settings: &SyncSettings, token: &str) -> Result<(), String> {
+    if token.is_empty() {
+        secrets::delete(&token_secret(settings))
+    } else {
+        secrets::set(&token_secret(settings), token)
+    }
+}
+
+/// Create a time entry for `entry` in the service. Returns the service's ID
+/// for it.
+pub fn push(
+    settings: &SyncSettings,
+    token: &str,
+    entry: &Entry,
+    mapping: &SyncMapping,
+) -> Result<i64, String> {
+    let Some(end) = entry.end else {
+        return Err(format!("Entry {} is still running", entry.id));
+    };
+    let request = match settings.service {
+        SyncService::Harvest => {
+            let Some(task) = mapping.remote_task else {
+                return Err(format!(
+                    "Harvest needs a task for '{}': meter sync map --project \"{}\" --remote-project {} --task ID",
+                    mapping.project, mapping.project, mapping.remote_project
+                ));
+            };
+            let body = json!({
+                "project_id": mapping.remote_project,
+                "task_id": task,
+                "spent_date": entry.start.with_timezone(&Local).format("%Y-%m-%d").to_string(),
+                "hours": (end - entry.start).num_seconds() as f64 / 3600.0,
+                "notes": entry.description,
+            });
+            ureq::post(HARVEST_URL)
+                .set("Authorization", &format!("Bearer {}", token))
+                .set("Harvest-Account-Id", &settings.account_id)
+                .set("User-Agent", USER_AGENT)
+                .send_json(body)
+        }
+        SyncService::Toggl => {
+            let workspace: i64 = settings
+                .account_id
+                .parse()
+                .map_err(|_| format!("Invalid Toggl workspace ID '{}'", settings.account_id))?;
+            let body = json!({
+                "created_with": "meter",
+                "workspace_id": workspace,
+                "project_id": mapping.remote_project,
+                "task_id": mapping.remote_task,
+                "description": entry.description,
+                "start": entry.start.to_rfc3339_opts(SecondsFormat::Secs, true),
+                "stop": end.to_rfc3339_opts(SecondsFormat::Secs, true),
+                "duration": (end - entry.start).num_seconds(),
+            });
+            let auth = BASE64.encode(format!("{}:api_token", token));
+            ureq::post(&format!("{}/{}/time_entries", TOGGL_URL, workspace))
+                .set("Authorization", &format!("Basic {}", auth))
+                .set("User-Agent", USER_AGENT)
+                .send_json(body)
+        }
+    };
+
+    let response: Value = match request {
+        Ok(response) => response
+            .into_json()
+            .map_err(|e| format!("Unexpected response from {}: {}", settings.service, e))?,
+        Err(ureq::Error::Status(code, response)) => {
+            let body = response.into_string().unwrap_or_default();
+            return Err(format!(
+                "{} rejected entry {} ({}): {}",
+                settings.service,
+                entry.id,
+                code,
+                body.trim()
+            ));
+        }
+        Err(e) => return Err(format!("Failed to reach {}: {}", settings.service, e)),
+    };
+    response["id"].as_i64().ok_or_else(|| {
+        format!(
+            "{} didn't return an ID for entry {}",
+            settings.service, entry.id
+        )
+    })
+}
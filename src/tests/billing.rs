@@ -0,0 +1,302 @@
+use chrono::{Duration, Utc};
+use proptest::prelude::*;
+
+use super::{at, day, finished, insert, test_db};
+use crate::billing::{self, ProjectRate, Totals};
+use crate::models::{
+    BillingMode, Entry, EntryStatus, Money, NumberLocale, OvertimeKind, OvertimeRates,
+    format_money, format_money_in,
+};
+
+#[test]
+fn statuses_move_only_forward_once_invoiced() {
+    let db = test_db();
+    let entry = insert(&db, finished("acme", at(day(2025, 3, 3), 9), 1.0));
+
+    // Open entries have to be billed before they can be invoiced
+    assert!(
+        !db.set_entry_status(entry.id, EntryStatus::Invoiced)
+            .unwrap()
+    );
+    assert!(db.set_entry_status(entry.id, EntryStatus::Billed).unwrap());
+    assert!(
+        db.set_entry_status(entry.id, EntryStatus::Invoiced)
+            .unwrap()
+    );
+    assert!(!db.set_entry_status(entry.id, EntryStatus::Open).unwrap());
+    assert!(db.set_entry_status(entry.id, EntryStatus::Paid).unwrap());
+    assert!(!db.set_entry_status(entry.id, EntryStatus::Billed).unwrap());
+
+    let entry = db.get_entry_by_id(entry.id).unwrap().unwrap();
+    assert_eq!(entry.status, EntryStatus::Paid);
+}
+
+#[test]
+fn bulk_status_changes_skip_entries_that_cannot_move() {
+    let db = test_db();
+    let open = insert(&db, finished("acme", at(day(2025, 3, 3), 9), 1.0));
+    let billed = insert(&db, finished("acme", at(day(2025, 3, 4), 9), 1.0));
+    db.set_entry_status(billed.id, EntryStatus::Billed).unwrap();
+
+    let changed = db
+        .set_entries_status(&[open.id, billed.id], EntryStatus::Invoiced)
+        .unwrap();
+    assert_eq!(changed, vec![billed.id]);
+}
+
+#[test]
+fn amounts_follow_the_billing_mode() {
+    let monday = day(2025, 3, 3);
+    let entries = [
+        finished("acme", at(monday, 9), 1.5),
+        finished("acme", at(monday, 13), 0.5),
+        finished("acme", at(day(2025, 3, 4), 9), 2.0),
+        Entry {
+            days: Some(0.5),
+            ..finished("acme", at(day(2025, 3, 5), 9), 0.0)
+        },
+    ];
+    let entries: Vec<&Entry> = entries.iter().collect();
+    let rate = |billing_mode| ProjectRate {
+        rate: Money::from_cents(10_000),
+        currency: "USD".to_string(),
+        billing_mode,
+    };
+
+    assert_eq!(
+        rate(BillingMode::Hourly).amount(&entries),
+        Money::from_cents(40_000)
+    );
+    // Two days with time logged plus half a day entered by the day
+    assert_eq!(
+        rate(BillingMode::Daily).amount(&entries),
+        Money::from_cents(25_000)
+    );
+    assert_eq!(
+        rate(BillingMode::Fixed).amount(&entries),
+        Money::from_cents(10_000)
+    );
+}
+
+#[test]
+fn project_totals_leave_invoiced_time_out_of_unbilled() {
+    let db = test_db();
+    insert(&db, finished("acme", at(day(2025, 3, 3), 9), 2.0));
+    let invoiced = insert(&db, finished("acme", at(day(2025, 3, 4), 9), 3.0));
+    db.set_entry_status(invoiced.id, EntryStatus::Billed)
+        .unwrap();
+    db.set_entry_status(invoiced.id, EntryStatus::Invoiced)
+        .unwrap();
+    db.sync_projects_from_entries().unwrap();
+
+    let totals = db.project_totals(Utc::now()).unwrap();
+    let acme = &totals["acme"];
+    assert!((acme.total_hours - 5.0).abs() < 1e-6);
+    assert!((acme.unbilled_hours - 2.0).abs() < 1e-6);
+}
+
+#[test]
+fn monthly_target_paces_billable_hours_by_the_day() {
+    let db = test_db();
+    let now = at(day(2025, 4, 15), 18);
+    assert!(db.target_pace(now).unwrap().is_none());
+
+    let mut settings = db.get_notification_settings().unwrap();
+    settings.monthly_target_hours = 120.0;
+    db.set_notification_settings(&settings).unwrap();
+    db.set_project_rate("acme", Some(Money::from_cents(10_000)), None)
+        .unwrap();
+    for d in 7..=11 {
+        insert(&db, finished("acme", at(day(2025, 4, d), 9), 8.0));
+    }
+    // Last month, and a project without a rate, don't count
+    insert(&db, finished("acme", at(day(2025, 3, 31), 9), 8.0));
+    insert(&db, finished("internal", at(day(2025, 4, 14), 9), 8.0));
+    db.sync_projects_from_entries().unwrap();
+
+    let pace = db.target_pace(now).unwrap().unwrap();
+    assert!((pace.billable - 40.0).abs() < 1e-6);
+    assert!((pace.expected() - 60.0).abs() < 1e-6);
+    assert!(pace.is_behind(8.0));
+    assert_eq!(
+        pace.summary(8.0),
+        "behind: 40/120h by the 15th, 60h expected"
+    );
+    assert!(!pace.is_behind(20.0));
+    assert_eq!(pace.summary(20.0), "on track: 40/120h by the 15th");
+}
+
+/// Up to 20 entries over four weeks: timed entries of up to a day, and day
+/// entries of up to four days in halves
+fn entries() -> impl Strategy<Value = Vec<Entry>> {
+    let entry = (
+        0i64..28 * 24 * 3600,
+        0i64..24 * 3600,
+        prop::option::of(1u32..=8),
+    );
+    prop::collection::vec(entry, 0..20).prop_map(|specs| {
+        let base = at(day(2025, 3, 3), 0);
+        specs
+            .into_iter()
+            .map(|(offset, secs, halves)| {
+                let start = base + Duration::seconds(offset);
+                match halves {
+                    Some(halves) => Entry {
+                        days: Some(halves as f64 / 2.0),
+                        ..finished("acme", start, 0.0)
+                    },
+                    None => finished("acme", start, secs as f64 / 3600.0),
+                }
+            })
+            .collect()
+    })
+}
+
+proptest! {
+    #[test]
+    fn total_hours_adds_up_entry_hours(entries in entries()) {
+        let refs: Vec<&Entry> = entries.iter().collect();
+        let sum: f64 = entries.iter().filter_map(billing::entry_hours).sum();
+        prop_assert!((billing::total_hours(&refs) - sum).abs() < 1e-9);
+        prop_assert!(billing::total_hours(&refs) >= 0.0);
+    }
+
+    #[test]
+    fn worked_days_ignores_order_and_stays_in_bounds(entries in entries()) {
+        let refs: Vec<&Entry> = entries.iter().collect();
+        let reversed: Vec<&Entry> = entries.iter().rev().collect();
+        let logged: f64 = entries.iter().filter_map(|e| e.days).sum();
+        let timed = entries.iter().filter(|e| e.days.is_none()).count() as f64;
+
+        let days = billing::worked_days(&refs);
+        prop_assert_eq!(days, billing::worked_days(&reversed));
+        prop_assert!(days >= logged);
+        prop_assert!(days <= logged + timed);
+    }
+
+    #[test]
+    fn hourly_amount_is_within_half_a_cent(entries in entries(), rate in 0i64..10_000_000) {
+        let refs: Vec<&Entry> = entries.iter().collect();
+        let amount = billing::amount(BillingMode::Hourly, Money::from_cents(rate), &refs);
+        let exact = rate as f64 * billing::total_hours(&refs);
+        prop_assert!((amount.cents() as f64 - exact).abs() <= 0.5 + 1e-6);
+    }
+
+    #[test]
+    fn fixed_amount_is_the_rate(entries in entries(), rate in 0i64..10_000_000) {
+        let refs: Vec<&Entry> = entries.iter().collect();
+        let amount = billing::amount(BillingMode::Fixed, Money::from_cents(rate), &refs);
+        prop_assert_eq!(amount, Money::from_cents(rate));
+    }
+
+    #[test]
+    fn total_is_subtotal_plus_rounded_tax(subtotal in 0i64..100_000_000, tax_rate in 0.0f64..30.0) {
+        let totals = Totals::new(Money::from_cents(subtotal), tax_rate, 2);
+        let exact_tax = subtotal as f64 * tax_rate / 100.0;
+        prop_assert!((totals.tax_amount.cents() as f64 - exact_tax).abs() <= 0.5 + 1e-6);
+        prop_assert_eq!(totals.total, totals.subtotal + totals.tax_amount);
+        prop_assert_eq!(Totals::new(Money::from_cents(subtotal), 0.0, 2).tax_amount, Money::ZERO);
+    }
+
+    #[test]
+    fn totals_from_amounts_match_their_sum(
+        amounts in prop::collection::vec(0i64..10_000_000, 0..10),
+        tax_rate in 0.0f64..30.0,
+    ) {
+        let sum = Money::from_cents(amounts.iter().sum());
+        let totals = Totals::from_amounts(amounts.into_iter().map(Money::from_cents), tax_rate, 2);
+        prop_assert_eq!(totals, Totals::new(sum, tax_rate, 2));
+    }
+
+    #[test]
+    fn inclusive_totals_back_the_tax_out_of_the_total(total in 0i64..100_000_000, tax_rate in 0.0f64..30.0) {
+        let totals = Totals::tax_inclusive(Money::from_cents(total), tax_rate, 2);
+        let exact_tax = total as f64 * tax_rate / (100.0 + tax_rate);
+        prop_assert!((totals.tax_amount.cents() as f64 - exact_tax).abs() <= 0.5 + 1e-6);
+        prop_assert_eq!(totals.total, Money::from_cents(total));
+        prop_assert_eq!(totals.total, totals.subtotal + totals.tax_amount);
+    }
+}
+
+#[test]
+fn totals_are_rounded_as_the_currency_is_written() {
+    // 10% of ¥1,234 is ¥123.40, but yen have no cents
+    let totals = Totals::new(Money::from_cents(123_400), 10.0, 0);
+    assert_eq!(totals.tax_amount, Money::from_cents(12_300));
+    assert_eq!(totals.total, Money::from_cents(135_700));
+    assert_eq!(format_money(totals.total, "JPY"), "¥1,357");
+    assert_eq!(
+        format_money_in(Money::from_cents(123_456_789), "USD", NumberLocale::De),
+        "$1.234.567,89"
+    );
+    assert_eq!(
+        format_money_in(Money::from_cents(123_450), "EUR", NumberLocale::Fr),
+        "1 234,50 €"
+    );
+}
+
+#[test]
+fn negative_amounts_that_round_to_zero_have_no_sign() {
+    // Less than half a yen is nothing at all
+    assert_eq!(format_money(Money::from_cents(-30), "JPY"), "¥0");
+    assert_eq!(format_money(Money::from_cents(-60), "JPY"), "¥-1");
+    assert_eq!(format_money(Money::from_cents(-30), "EUR"), "-0,30 €");
+    assert_eq!(format_money(Money::ZERO, "EUR"), "0,00 €");
+}
+
+#[test]
+fn rounding_moves_timed_entries_ends_up_to_the_increment() {
+    let start = at(day(2025, 3, 3), 9);
+    let timed = finished("acme", start, 1.1); // 66 minutes
+    let exact = finished("acme", start, 0.5);
+    let by_day = Entry {
+        days: Some(1.0),
+        ..finished("acme", start, 8.0)
+    };
+
+    let rounded = billing::rounded_entries(&[timed, exact, by_day], 15);
+    assert_eq!(rounded[0].end, Some(start + Duration::minutes(75)));
+    assert_eq!(rounded[1].end, Some(start + Duration::minutes(30)));
+    assert_eq!(rounded[2].end, Some(start + Duration::hours(8)));
+
+    let unrounded = billing::rounded_entries(&rounded[..1], 0);
+    assert_eq!(unrounded[0].end, rounded[0].end);
+}
+
+#[test]
+fn overtime_goes_to_the_highest_multiplier_that_applies() {
+    let rates = OvertimeRates {
+        evening: Some(1.5),
+        weekend: Some(2.0),
+        holiday: Some(2.5),
+        ..Default::default()
+    };
+    let holiday = day(2025, 6, 3);
+    // Monday 17:00-19:00: one evening hour
+    let monday = finished("acme", at(day(2025, 6, 2), 17), 2.0);
+    // A holiday Tuesday, 10:00-12:00
+    let tuesday = finished("acme", at(holiday, 10), 2.0);
+    // Saturday 17:00-20:00: all weekend, which pays more than evenings
+    let saturday = finished("acme", at(day(2025, 6, 7), 17), 3.0);
+    let entries = [&monday, &tuesday, &saturday];
+
+    let overtime = billing::overtime_hours(&entries, &rates, |d| d == holiday);
+    let hours = |kind| {
+        overtime
+            .iter()
+            .find(|o| o.kind == kind)
+            .map(|o| o.hours)
+            .unwrap_or_default()
+    };
+    assert!((hours(OvertimeKind::Evening) - 1.0).abs() < 1e-9);
+    assert!((hours(OvertimeKind::Holiday) - 2.0).abs() < 1e-9);
+    assert!((hours(OvertimeKind::Weekend) - 3.0).abs() < 1e-9);
+
+    // 1 regular hour at 100, then 150 + 2 × 250 + 3 × 200
+    let amount = billing::amount_with_overtime(
+        Money::from_cents(10_000),
+        billing::total_hours(&entries),
+        &overtime,
+    );
+    assert_eq!(amount, Money::from_cents(135_000));
+}
@@ -0,0 +1,68 @@
+//! Two connections to one database file, as when the menu bar app and the TUI
+//! run side by side and one of them is in the middle of a write.
+
+use std::fs;
+use std::thread;
+use std::time::Duration;
+
+use super::finished;
+use crate::db::{self, Db};
+use crate::models;
+
+/// A fresh database file in the temp directory, opened twice
+fn two_connections(name: &str) -> (Db, Db) {
+    let path = std::env::temp_dir().join(format!(
+        "meter-test-busy-{}-{}.sqlite",
+        name,
+        std::process::id()
+    ));
+    for suffix in ["", "-wal", "-shm"] {
+        let _ = fs::remove_file(format!("{}{}", path.display(), suffix));
+    }
+    let path = path.to_string_lossy().into_owned();
+    let first = Db::new(&path).unwrap();
+    models::init_all(first.conn()).unwrap();
+    let second = Db::new(&path).unwrap();
+    (first, second)
+}
+
+/// Hold the write lock on `db` with an uncommitted entry for `hold`, then commit
+fn write_slowly(db: Db, hold: Duration) -> thread::JoinHandle<()> {
+    db.conn().execute_batch("BEGIN IMMEDIATE").unwrap();
+    db.insert(&finished("acme", chrono::Utc::now(), 1.0))
+        .unwrap();
+    thread::spawn(move || {
+        thread::sleep(hold);
+        db.conn().execute_batch("COMMIT").unwrap();
+    })
+}
+
+#[test]
+fn a_writer_waits_for_another_to_commit() {
+    let (first, second) = two_connections("insert");
+    let writer = write_slowly(first, Duration::from_millis(300));
+
+    second
+        .insert(&finished("globex", chrono::Utc::now(), 1.0))
+        .unwrap();
+    writer.join().unwrap();
+
+    assert_eq!(second.list(None).unwrap().len(), 2);
+    assert!(db::take_busy_wait());
+}
+
+#[test]
+fn a_transaction_that_reads_first_still_waits_its_turn() {
+    let (first, second) = two_connections("switch");
+    second.start_timer("acme", "Design").unwrap();
+    let writer = write_slowly(first, Duration::from_millis(300));
+
+    // Reads the running timer, then writes: would fail at once if it took a
+    // snapshot before the other connection committed
+    let (stopped, started) = second.switch_timer("globex", "Review").unwrap();
+    writer.join().unwrap();
+
+    assert_eq!(stopped.unwrap().project, "acme");
+    assert_eq!(started.project, "globex");
+    assert_eq!(second.list(None).unwrap().len(), 3);
+}
@@ -0,0 +1,126 @@
+use super::{at, day, finished, insert, test_db};
+use crate::invoice::expenses_for_invoice;
+use crate::models::{DistanceUnit, Expense, ExpenseKind, MileageSettings, Money};
+
+fn expense(date: chrono::NaiveDate, amount: i64) -> Expense {
+    Expense {
+        id: 0,
+        date,
+        kind: ExpenseKind::Expense,
+        description: "Parking".to_string(),
+        amount: Money::from_cents(amount),
+        distance: None,
+        unit: None,
+        entry_id: None,
+        client_id: None,
+        invoice_number: None,
+    }
+}
+
+#[test]
+fn expenses_are_stored_with_mileage_details() {
+    let db = test_db();
+    db.set_mileage_settings(&MileageSettings {
+        rate: Money::from_cents(70),
+        unit: DistanceUnit::Mi,
+    })
+    .unwrap();
+    let mileage = db.get_mileage_settings().unwrap();
+    assert_eq!(mileage.unit, DistanceUnit::Mi);
+
+    let id = db
+        .add_expense(&Expense {
+            kind: ExpenseKind::Mileage,
+            description: "Client visit".to_string(),
+            amount: mileage.rate.times(42.0),
+            distance: Some(42.0),
+            unit: Some(mileage.unit),
+            ..expense(day(2025, 3, 3), 0)
+        })
+        .unwrap();
+    db.set_expense_invoiced(id, 7).unwrap();
+
+    let stored = db.list_expenses().unwrap();
+    assert_eq!(stored.len(), 1);
+    assert_eq!(stored[0].amount, Money::from_cents(2940));
+    assert_eq!(stored[0].invoice_number, Some(7));
+    assert_eq!(stored[0].label(), "Mileage: Client visit (42 mi)");
+    assert!(db.delete_expense(id).unwrap());
+}
+
+#[test]
+fn invoices_pick_up_their_entries_and_clients_expenses() {
+    let db = test_db();
+    let entry = insert(&db, finished("acme", at(day(2025, 3, 3), 9), 2.0));
+    let entries = vec![entry.clone()];
+
+    let for_entry = Expense {
+        id: 1,
+        entry_id: Some(entry.id),
+        ..expense(day(2025, 2, 28), 500)
+    };
+    let for_client = Expense {
+        id: 2,
+        client_id: Some(9),
+        ..expense(day(2025, 3, 20), 1200)
+    };
+    let other_month = Expense {
+        id: 3,
+        client_id: Some(9),
+        ..expense(day(2025, 4, 1), 300)
+    };
+    let other_client = Expense {
+        id: 4,
+        client_id: Some(8),
+        ..expense(day(2025, 3, 5), 300)
+    };
+    let invoiced = Expense {
+        id: 5,
+        client_id: Some(9),
+        invoice_number: Some(1),
+        ..expense(day(2025, 3, 5), 300)
+    };
+    let all = [for_entry, for_client, other_month, other_client, invoiced];
+
+    let ids: Vec<i64> = expenses_for_invoice(&all, &entries, Some(9), 2025, 3)
+        .iter()
+        .map(|x| x.id)
+        .collect();
+    assert_eq!(ids, vec![1, 2]);
+
+    let ids: Vec<i64> = expenses_for_invoice(&all, &entries, None, 2025, 3)
+        .iter()
+        .map(|x| x.id)
+        .collect();
+    assert_eq!(ids, vec![1]);
+}
+
+#[test]
+fn invoice_attachments_include_billed_expenses_receipts() {
+    let db = test_db();
+    let billed = db.add_expense(&expense(day(2025, 3, 3), 500)).unwrap();
+    let unbilled = db.add_expense(&expense(day(2025, 3, 4), 700)).unwrap();
+    db.set_expense_invoiced(billed, 12).unwrap();
+
+    let receipt = db
+        .add_attachment(Some(billed), None, "parking.pdf", "/tmp/a-parking.pdf")
+        .unwrap();
+    db.add_attachment(Some(unbilled), None, "train.pdf", "/tmp/b-train.pdf")
+        .unwrap();
+    let direct = db
+        .add_attachment(None, Some(12), "timesheet.png", "/tmp/c-timesheet.png")
+        .unwrap();
+
+    let ids: Vec<i64> = db
+        .invoice_attachments(12)
+        .unwrap()
+        .iter()
+        .map(|a| a.id)
+        .collect();
+    assert_eq!(ids, vec![receipt, direct]);
+
+    // Deleting an expense takes its attachments with it
+    assert!(db.delete_expense(billed).unwrap());
+    assert!(db.get_attachment(receipt).unwrap().is_none());
+    assert_eq!(db.list_attachments().unwrap().len(), 2);
+}
@@ -0,0 +1,365 @@
+use chrono::{TimeZone, Utc};
+
+use super::{at, day, finished, insert, invoice, test_db};
+use crate::export::{parse_json, to_json};
+use crate::import::{
+    UNTAGGED_PROJECT, parse_column_map, parse_csv, parse_timewarrior, parse_watson,
+};
+use crate::models::{
+    ApprovalSheet, Client, DEFAULT_PROFILE_ID, DatabaseExport, DistanceUnit, DunningSettings,
+    EmailSettings, EntryStatus, Expense, ExpenseKind, InboxSettings, Invoice, InvoiceDraft,
+    MailAction, MailMatch, MileageSettings, Money, OvertimeRates, Payment, SCHEMA_VERSION,
+    SmtpSecurity, SyncService, SyncSettings,
+};
+
+#[test]
+fn timewarrior_first_tag_is_the_project() {
+    let data = "inc 20240603T090000Z - 20240603T103000Z # acme dev \"code review\" # \"fix \\\"login\\\" bug\"\n\
+                inc 20240603T110000Z - 20240603T113000Z\n\
+                inc 20240603T120000Z # acme\n";
+    let entries = parse_timewarrior(data).unwrap();
+
+    // The running interval is left out
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].project, "acme");
+    assert_eq!(
+        entries[0].description,
+        "fix \"login\" bug #dev #code-review"
+    );
+    assert_eq!(
+        entries[0].start,
+        Utc.with_ymd_and_hms(2024, 6, 3, 9, 0, 0).unwrap()
+    );
+    assert_eq!(
+        entries[0].end,
+        Some(Utc.with_ymd_and_hms(2024, 6, 3, 10, 30, 0).unwrap())
+    );
+    assert_eq!(entries[1].project, UNTAGGED_PROJECT);
+    assert_eq!(entries[1].description, "");
+}
+
+#[test]
+fn timewarrior_rejects_other_lines() {
+    assert!(parse_timewarrior("exc 20240603T090000Z").is_err());
+    assert!(parse_timewarrior("inc yesterday - today # acme").is_err());
+}
+
+#[test]
+fn watson_frames_keep_project_and_tags() {
+    let data = r#"[[1717405200, 1717410600, "acme", "0a1b2c", ["dev", "ops"], 1717410600]]"#;
+    let entries = parse_watson(data).unwrap();
+
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].project, "acme");
+    assert_eq!(entries[0].description, "#dev #ops");
+    assert_eq!(
+        entries[0].end.unwrap() - entries[0].start,
+        chrono::Duration::minutes(90)
+    );
+    assert!(parse_watson(r#"[["soon", 1717410600, "acme"]]"#).is_err());
+}
+
+#[test]
+fn csv_reads_toggl_columns() {
+    let data = "User,Email,Client,Project,Task,Description,Billable,Start date,Start time,End date,End time,Duration,Tags\r\n\
+                Me,me@example.com,Acme,acme,,\"fix \"\"login\"\", then deploy\",Yes,2024-06-03,09:00:00,2024-06-03,10:30:00,01:30:00,\"dev, ops\"\r\n\
+                Me,me@example.com,,,,,No,2024-06-03,23:30:00,2024-06-04,00:15:00,00:45:00,\r\n";
+    let entries = parse_csv(data, &[]).unwrap();
+
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].project, "acme");
+    assert_eq!(
+        entries[0].description,
+        "fix \"login\", then deploy #dev #ops"
+    );
+    assert_eq!(
+        entries[0].end.unwrap() - entries[0].start,
+        chrono::Duration::minutes(90)
+    );
+    assert_eq!(entries[1].project, UNTAGGED_PROJECT);
+    assert_eq!(
+        entries[1].end.unwrap() - entries[1].start,
+        chrono::Duration::minutes(45)
+    );
+}
+
+#[test]
+fn csv_columns_can_be_mapped() {
+    let data = "Client,Date,From,Hours\n\
+                Acme,06/03/2024,1:00 PM,1.5\n";
+    assert!(parse_csv(data, &[]).is_err());
+
+    let columns = parse_column_map(&[
+        "project=Client".to_string(),
+        "start_date=date".to_string(),
+        "start_time=From".to_string(),
+        "duration=Hours".to_string(),
+    ])
+    .unwrap();
+    let entries = parse_csv(data, &columns).unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].project, "Acme");
+    assert_eq!(
+        entries[0].end.unwrap() - entries[0].start,
+        chrono::Duration::minutes(90)
+    );
+
+    assert!(parse_column_map(&["client=Client".to_string()]).is_err());
+    assert!(parse_column_map(&["project".to_string()]).is_err());
+}
+
+#[test]
+fn already_recorded_entries_are_skipped() {
+    let db = test_db();
+    let start = at(day(2025, 3, 3), 9);
+    insert(&db, finished("acme", start, 1.0));
+
+    // Same project and start as a recorded entry, but a different end
+    let longer = finished("acme", start, 2.0);
+    let other = finished("globex", start, 1.0);
+    let kept = crate::unrecorded_entries(&db, vec![longer.clone(), other.clone()], true);
+    assert_eq!(kept.len(), 2);
+
+    // Without matching ends, the recorded one and repeats in the file are dropped
+    let kept = crate::unrecorded_entries(&db, vec![longer, other.clone(), other], false);
+    assert_eq!(kept.len(), 1);
+    assert_eq!(kept[0].project, "globex");
+}
+
+#[test]
+fn json_export_restores_into_an_empty_database() {
+    let db = test_db();
+    let client_id = db
+        .add_client(&Client {
+            name: "Acme Corp".to_string(),
+            ..Default::default()
+        })
+        .unwrap();
+    let entry = insert(&db, finished("acme", at(day(2025, 3, 3), 9), 1.5));
+    db.add_entry_tags(entry.id, &["support".to_string()])
+        .unwrap();
+    db.get_or_create_project("acme").unwrap();
+    db.set_project_client("acme", Some(client_id)).unwrap();
+    db.set_project_rate("acme", Some(Money::from_cents(12_000)), Some("EUR"))
+        .unwrap();
+    db.set_entry_status(entry.id, EntryStatus::Billed).unwrap();
+    db.record_invoice(&Invoice {
+        client_id: Some(client_id),
+        ..invoice(7, 18_000)
+    })
+    .unwrap();
+    let mut settings = db.get_timer_settings().unwrap();
+    settings.min_duration_secs = 90;
+    db.set_timer_settings(&settings).unwrap();
+
+    let data = parse_json(&to_json(&db).unwrap()).unwrap();
+    let restored = test_db();
+    restored.import_all(&data).unwrap();
+
+    let entries = restored.list(None).unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].id, entry.id);
+    assert_eq!(entries[0].start, entry.start);
+    assert_eq!(entries[0].status, EntryStatus::Billed);
+    assert_eq!(restored.entry_tags(entry.id).unwrap(), vec!["support"]);
+    let project = restored.get_project_by_name("acme").unwrap().unwrap();
+    assert_eq!(project.client_id, Some(client_id));
+    assert_eq!(project.rate, Some(Money::from_cents(12_000)));
+    assert_eq!(
+        restored.get_client(client_id).unwrap().unwrap().name,
+        "Acme Corp"
+    );
+    assert_eq!(restored.list_invoices().unwrap()[0].invoice_number, 7);
+    assert_eq!(restored.get_timer_settings().unwrap().min_duration_secs, 90);
+
+    // Restoring twice would clash on IDs, so nothing is added the second time
+    assert!(restored.import_all(&data).is_err());
+    assert_eq!(restored.list(None).unwrap().len(), 1);
+}
+
+#[test]
+fn json_export_round_trips_every_table() {
+    let db = test_db();
+    let client_id = db
+        .add_client(&Client {
+            name: "Acme Corp".to_string(),
+            ..Default::default()
+        })
+        .unwrap();
+    db.get_or_create_project("acme").unwrap();
+    db.set_project_client("acme", Some(client_id)).unwrap();
+    let entry = insert(&db, finished("acme", at(day(2025, 3, 3), 9), 2.0));
+    let other = insert(&db, finished("acme", at(day(2025, 3, 4), 9), 1.0));
+
+    db.set_timer_target(entry.id, 3600).unwrap();
+    db.set_tag_color("support", Some("red")).unwrap();
+    db.save_network_rule("AcmeGuest", "acme", Some("client-site"))
+        .unwrap();
+    db.add_time_off(day(2025, 4, 18), day(2025, 4, 21), "Easter", true)
+        .unwrap();
+    db.set_overtime_rates(
+        client_id,
+        &OvertimeRates {
+            weekend: Some(1.5),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    let expense_id = db
+        .add_expense(&Expense {
+            id: 0,
+            date: day(2025, 3, 3),
+            kind: ExpenseKind::Expense,
+            description: "Train".to_string(),
+            amount: Money::from_cents(4250),
+            distance: None,
+            unit: None,
+            entry_id: Some(entry.id),
+            client_id: Some(client_id),
+            invoice_number: None,
+        })
+        .unwrap();
+    db.set_mileage_settings(&MileageSettings {
+        rate: Money::from_cents(30),
+        unit: DistanceUnit::Mi,
+    })
+    .unwrap();
+    db.add_attachment(Some(expense_id), None, "ticket.pdf", "/tmp/ticket.pdf")
+        .unwrap();
+    let draft_id = db
+        .create_invoice_draft(&InvoiceDraft {
+            id: 0,
+            client_id: Some(client_id),
+            year: 2025,
+            month: 3,
+            tax_rate: 19.0,
+            rounding_minutes: 15,
+            entry_ids: vec![entry.id],
+            items: Vec::new(),
+            supersedes: None,
+            profile_id: DEFAULT_PROFILE_ID,
+            created_at: Utc::now(),
+        })
+        .unwrap();
+    db.add_draft_item(draft_id, "Discount", Money::from_cents(-1000))
+        .unwrap();
+    let sheet_id = db
+        .create_approval_sheet(&ApprovalSheet {
+            id: 0,
+            client_id: Some(client_id),
+            year: 2025,
+            month: 3,
+            file_path: "/tmp/timesheet.pdf".to_string(),
+            entry_ids: vec![other.id],
+            created_at: Utc::now(),
+            approved_at: None,
+        })
+        .unwrap();
+    db.approve_sheet(sheet_id, &[]).unwrap();
+    db.record_payment(&Payment {
+        id: 0,
+        provider: "stripe".to_string(),
+        external_id: "pi_123".to_string(),
+        invoice_number: Some(7),
+        amount: Money::from_cents(10_000),
+        fee: Money::from_cents(320),
+        currency: "EUR".to_string(),
+        paid_at: Utc.with_ymd_and_hms(2025, 4, 2, 10, 0, 0).unwrap(),
+        expense_id: Some(expense_id),
+    })
+    .unwrap();
+    db.set_email_settings(&EmailSettings {
+        smtp_host: "smtp.example.com".to_string(),
+        from_address: "me@example.com".to_string(),
+        security: SmtpSecurity::Tls,
+        ..Default::default()
+    })
+    .unwrap();
+    db.set_inbox_settings(&InboxSettings {
+        imap_host: "imap.example.com".to_string(),
+        enabled: true,
+        last_uid: 42,
+        ..Default::default()
+    })
+    .unwrap();
+    db.add_mail_rule("payment received", MailAction::Paid)
+        .unwrap();
+    db.record_mail_match(&MailMatch {
+        id: 0,
+        invoice_number: 7,
+        action: MailAction::Paid,
+        subject: "Invoice 7: payment received".to_string(),
+        sender: "billing@acme.com".to_string(),
+        matched_at: Utc.with_ymd_and_hms(2025, 4, 2, 11, 0, 0).unwrap(),
+    })
+    .unwrap();
+    db.set_sync_settings(&SyncSettings {
+        service: SyncService::Toggl,
+        account_id: "7654321".to_string(),
+    })
+    .unwrap();
+    db.map_sync_project(SyncService::Toggl, "acme", 99, None)
+        .unwrap();
+    db.record_push(SyncService::Toggl, entry.id, 555).unwrap();
+    db.set_dunning_settings(&DunningSettings {
+        schedule: vec![3, 10],
+        email: true,
+    })
+    .unwrap();
+    db.claim_dunning_reminder(7, 3, Utc.with_ymd_and_hms(2025, 4, 5, 9, 0, 0).unwrap())
+        .unwrap();
+    let mut notifications = db.get_notification_settings().unwrap();
+    notifications.monthly_target_hours = 120.0;
+    db.set_notification_settings(&notifications).unwrap();
+
+    let data = parse_json(&to_json(&db).unwrap()).unwrap();
+    assert_eq!(data.timer_targets.len(), 1);
+    assert_eq!(data.tag_colors.len(), 1);
+    assert_eq!(data.network_rules.len(), 1);
+    assert_eq!(data.time_off.len(), 1);
+    assert_eq!(data.overtime_rates.len(), 1);
+    assert_eq!(data.expenses.len(), 1);
+    assert_eq!(data.attachments.len(), 1);
+    assert_eq!(data.invoice_drafts.len(), 1);
+    assert_eq!(data.approval_sheets.len(), 1);
+    assert_eq!(data.payments.len(), 1);
+    assert_eq!(data.mail_rules.len(), 1);
+    assert_eq!(data.mail_matches.len(), 1);
+    assert_eq!(data.sync_mappings.len(), 1);
+    assert_eq!(data.sync_pushes.len(), 1);
+    assert_eq!(data.dunning_log.len(), 1);
+
+    let restored = test_db();
+    restored.import_all(&data).unwrap();
+
+    // Exporting the restored database gives back every table as it was
+    let table = |export: &DatabaseExport| {
+        let mut value = serde_json::to_value(export).unwrap();
+        value.as_object_mut().unwrap().remove("exported_at");
+        value
+    };
+    let again = restored.export_all().unwrap();
+    let (before, after) = (table(&data), table(&again));
+    for (name, value) in before.as_object().unwrap() {
+        assert_eq!(Some(value), after.get(name), "{} differs", name);
+    }
+    assert_eq!(
+        restored
+            .get_notification_settings()
+            .unwrap()
+            .monthly_target_hours,
+        120.0
+    );
+    assert_eq!(restored.drafted_entry_ids().unwrap(), vec![entry.id]);
+}
+
+#[test]
+fn json_export_from_a_newer_schema_is_refused() {
+    let db = test_db();
+    let json = to_json(&db).unwrap().replacen(
+        &format!("\"schema_version\": {}", SCHEMA_VERSION),
+        &format!("\"schema_version\": {}", SCHEMA_VERSION + 1),
+        1,
+    );
+    assert!(parse_json(&json).unwrap_err().contains("newer version"));
+}
@@ -0,0 +1,31 @@
+use crate::inbox::{invoice_number_in_subject, parse_headers};
+use crate::models::{MailAction, MailRule};
+
+#[test]
+fn invoice_numbers_are_found_in_subjects() {
+    assert_eq!(invoice_number_in_subject("Re: Invoice #0012"), Some(12));
+    assert_eq!(
+        invoice_number_in_subject("Payment for invoice 34"),
+        Some(34)
+    );
+    assert_eq!(invoice_number_in_subject("INVOICE NO. 7 received"), Some(7));
+    assert_eq!(invoice_number_in_subject("Invoice number: 8"), Some(8));
+    assert_eq!(invoice_number_in_subject("Your invoice"), None);
+    assert_eq!(invoice_number_in_subject("Lunch on the 12th?"), None);
+}
+
+#[test]
+fn headers_are_unfolded_and_rules_ignore_case() {
+    let header = "Received: from mx\r\nSubject: Re: Invoice #0012 -\r\n payment received\r\nFrom: Acme Billing <billing@acme.com>\r\n\r\n";
+    let (subject, from) = parse_headers(header);
+    assert_eq!(subject, "Re: Invoice #0012 - payment received");
+    assert_eq!(from, "Acme Billing <billing@acme.com>");
+
+    let rule = MailRule {
+        id: 1,
+        pattern: "Payment Received".to_string(),
+        action: MailAction::Paid,
+    };
+    assert!(rule.matches(&subject, &from));
+    assert!(!rule.matches("Re: Invoice #0012", &from));
+}
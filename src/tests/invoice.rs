@@ -0,0 +1,424 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+
+use super::{at, day, finished, insert, invoice, test_db};
+use crate::approval::sheet_csv;
+use crate::billing::ProjectRate;
+use crate::dunning;
+use crate::invoice::{InvoiceParams, InvoiceSnapshot, orphan_invoice_files_in, write_invoice_in};
+use crate::models::{
+    ApprovalSheet, BillingMode, DEFAULT_PROFILE_ID, DunningSettings, EntryStatus, Invoice,
+    InvoiceDraft, InvoiceSettings, Money, Payment, parse_dunning_schedule,
+};
+
+#[test]
+fn invoice_pdf_and_json_are_written_with_totals() {
+    let db = test_db();
+    let monday = day(2025, 3, 3);
+    let first = insert(&db, finished("acme", at(monday, 9), 1.5));
+    let second = insert(&db, finished("acme", at(monday, 13), 0.5));
+    db.set_entries_status(&[first.id, second.id], EntryStatus::Billed)
+        .unwrap();
+    let entries = db.list(Some(EntryStatus::Billed)).unwrap();
+
+    let rates = HashMap::from([(
+        "acme".to_string(),
+        ProjectRate {
+            rate: Money::from_cents(10_000),
+            currency: "USD".to_string(),
+            billing_mode: BillingMode::Hourly,
+        },
+    )]);
+    let settings = InvoiceSettings {
+        business_name: "Test Consulting".to_string(),
+        json_snapshot: true,
+        ..Default::default()
+    };
+    let params = InvoiceParams {
+        entries: &entries,
+        rounding_minutes: 0,
+        expenses: &[],
+        items: &[],
+        project_rates: &rates,
+        year: 2025,
+        month: 3,
+        invoice_number: db.get_next_invoice_number(DEFAULT_PROFILE_ID).unwrap(),
+        draft: false,
+        supersedes: None,
+        settings: &settings,
+        client: None,
+        overtime: None,
+        tax_rate: 10.0,
+        holidays: &[],
+        reissue_dates: None,
+    };
+
+    let dir = std::env::temp_dir().join(format!("meter-test-invoice-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let result = match write_invoice_in(&params, dir.to_str().unwrap()) {
+        Ok(result) => result,
+        // Rendering needs Arial or Liberation Sans installed
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            eprintln!("skipping invoice PDF test: {}", e);
+            return;
+        }
+        Err(e) => panic!("Failed to write invoice: {}", e),
+    };
+
+    assert_eq!(result.subtotal, Money::from_cents(20_000));
+    assert_eq!(result.tax_amount, Money::from_cents(2000));
+    assert_eq!(result.total, Money::from_cents(22_000));
+    assert_eq!(result.snapshot.line_items.len(), 2);
+
+    let bytes = fs::read(&result.file_path).unwrap();
+    assert!(bytes.starts_with(b"%PDF"));
+
+    let json = fs::read_to_string(result.json_path.expect("JSON copy is on")).unwrap();
+    let snapshot = InvoiceSnapshot::from_json(&json).unwrap();
+    assert_eq!(snapshot.total, result.total);
+    assert_eq!(snapshot.settings.business_name, "Test Consulting");
+
+    let table = snapshot.markdown_table();
+    assert_eq!(table.lines().count(), 2 + 2 + 3);
+    assert!(table.contains("| acme |"));
+    assert!(table.ends_with("|  |  | Total due |  | $220.00 |\n"));
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn drafts_hold_their_entries_until_discarded() {
+    let db = test_db();
+    let entry = insert(&db, finished("acme", at(day(2025, 3, 3), 9), 1.0));
+    let draft = InvoiceDraft {
+        id: 0,
+        client_id: None,
+        year: 2025,
+        month: 3,
+        tax_rate: 0.0,
+        rounding_minutes: 15,
+        entry_ids: vec![entry.id],
+        items: Vec::new(),
+        supersedes: None,
+        profile_id: DEFAULT_PROFILE_ID,
+        created_at: chrono::Utc::now(),
+    };
+    let id = db.create_invoice_draft(&draft).unwrap();
+    db.add_draft_item(id, "Discount", Money::from_cents(-5000))
+        .unwrap();
+
+    let stored = db.get_invoice_draft(id).unwrap().unwrap();
+    assert_eq!(stored.entry_ids, vec![entry.id]);
+    assert_eq!(stored.rounding_minutes, 15);
+    assert_eq!(stored.items[0].amount, Money::from_cents(-5000));
+    assert_eq!(db.drafted_entry_ids().unwrap(), vec![entry.id]);
+
+    assert!(db.delete_invoice_draft(id).unwrap());
+    assert!(db.drafted_entry_ids().unwrap().is_empty());
+}
+
+#[test]
+fn approval_sheets_hold_entries_until_approved() {
+    let db = test_db();
+    let mut first = finished("acme", at(day(2025, 3, 3), 9), 1.0);
+    first.description = "Review, \"phase 2\"".to_string();
+    let first = insert(&db, first);
+    let second = insert(&db, finished("acme", at(day(2025, 3, 4), 9), 2.0));
+    let sheet = ApprovalSheet {
+        id: 0,
+        client_id: None,
+        year: 2025,
+        month: 3,
+        file_path: String::new(),
+        entry_ids: vec![first.id, second.id],
+        created_at: chrono::Utc::now(),
+        approved_at: None,
+    };
+    let id = db.create_approval_sheet(&sheet).unwrap();
+    assert_eq!(
+        db.awaiting_approval_entry_ids().unwrap(),
+        vec![first.id, second.id]
+    );
+
+    let csv = sheet_csv(&[&first, &second]);
+    assert!(csv.starts_with("entry_id,date,start,end,project,description,hours\n"));
+    assert!(csv.contains(",acme,\"Review, \"\"phase 2\"\"\",1.00\n"));
+
+    // A rejected entry comes off the sheet; approval releases the rest
+    db.approve_sheet(id, &[second.id]).unwrap();
+    let stored = db.get_approval_sheet(id).unwrap().unwrap();
+    assert!(stored.is_approved());
+    assert_eq!(stored.entry_ids, vec![first.id]);
+    assert!(db.awaiting_approval_entry_ids().unwrap().is_empty());
+}
+
+#[test]
+fn voiding_releases_entries_and_keeps_the_number() {
+    let db = test_db();
+    let entry = insert(&db, finished("acme", at(day(2025, 3, 3), 9), 1.0));
+    db.set_entry_status(entry.id, EntryStatus::Billed).unwrap();
+    db.set_entry_status(entry.id, EntryStatus::Invoiced)
+        .unwrap();
+    db.record_invoice(&invoice(1, 10_000)).unwrap();
+
+    let released = db.void_invoice(1, "disputed", &[entry.id]).unwrap();
+    assert_eq!(released, vec![entry.id]);
+    let entry = db.get_entry_by_id(entry.id).unwrap().unwrap();
+    assert_eq!(entry.status, EntryStatus::Billed);
+
+    let invoices = db.list_invoices().unwrap();
+    assert!(invoices[0].is_void());
+    assert_eq!(invoices[0].void_reason.as_deref(), Some("disputed"));
+    // The number stays taken
+    assert_eq!(db.get_next_invoice_number(DEFAULT_PROFILE_ID).unwrap(), 2);
+}
+
+#[test]
+fn profiles_number_invoices_from_their_own_range() {
+    let db = test_db();
+    let llc = db.add_profile("LLC", 5001).unwrap();
+    assert_eq!(db.get_next_invoice_number(DEFAULT_PROFILE_ID).unwrap(), 1);
+    assert_eq!(db.get_next_invoice_number(llc).unwrap(), 5001);
+
+    db.record_invoice(&Invoice {
+        profile_id: llc,
+        ..invoice(5001, 10_000)
+    })
+    .unwrap();
+    assert_eq!(db.get_next_invoice_number(llc).unwrap(), 5002);
+    assert_eq!(db.get_next_invoice_number(DEFAULT_PROFILE_ID).unwrap(), 1);
+    assert!(db.invoice_number_taken(5001).unwrap());
+
+    // Profiles with invoices, and the default profile, are kept
+    assert!(!db.delete_profile(llc).unwrap());
+    assert!(!db.delete_profile(DEFAULT_PROFILE_ID).unwrap());
+    let spare = db.add_profile("Spare", 9001).unwrap();
+    assert!(db.delete_profile(spare).unwrap());
+}
+
+#[test]
+fn profiles_counting_from_the_same_number_skip_each_others_invoices() {
+    let db = test_db();
+    let side = db.add_profile("Side", 1).unwrap();
+    let record = |number: i64, profile_id: i64| {
+        db.record_invoice(&Invoice {
+            profile_id,
+            ..invoice(number, 10_000)
+        })
+        .unwrap();
+    };
+
+    record(
+        db.get_next_invoice_number(DEFAULT_PROFILE_ID).unwrap(),
+        DEFAULT_PROFILE_ID,
+    );
+    assert_eq!(db.get_next_invoice_number(side).unwrap(), 2);
+    record(2, side);
+    assert_eq!(db.get_next_invoice_number(DEFAULT_PROFILE_ID).unwrap(), 3);
+    assert_eq!(db.get_next_invoice_number(side).unwrap(), 3);
+}
+
+#[test]
+fn invoices_are_overdue_until_their_entries_are_paid() {
+    let db = test_db();
+    let entry = insert(&db, finished("acme", at(day(2025, 3, 3), 9), 2.0));
+    insert(&db, finished("acme", at(day(2025, 3, 4), 9), 1.5));
+    assert!((db.unbilled_hours().unwrap() - 3.5).abs() < 1e-6);
+
+    db.set_entry_status(entry.id, EntryStatus::Billed).unwrap();
+    db.set_entry_status(entry.id, EntryStatus::Invoiced)
+        .unwrap();
+    db.record_invoice(&Invoice {
+        snapshot: Some(format!(
+            r#"{{"line_items": [{{"entry_id": {}}}]}}"#,
+            entry.id
+        )),
+        ..invoice(1, 20_000)
+    })
+    .unwrap();
+    assert!((db.unbilled_hours().unwrap() - 1.5).abs() < 1e-6);
+
+    assert_eq!(db.overdue_invoice_count(day(2025, 4, 30)).unwrap(), 0);
+    assert_eq!(db.overdue_invoice_count(day(2025, 5, 1)).unwrap(), 1);
+
+    db.set_entry_status(entry.id, EntryStatus::Paid).unwrap();
+    assert_eq!(db.overdue_invoice_count(day(2025, 5, 1)).unwrap(), 0);
+}
+
+#[test]
+fn invoices_without_entries_are_overdue_until_paid_in_full() {
+    let db = test_db();
+    // Only an expense on it, so no entry status can say it was paid
+    db.record_invoice(&Invoice {
+        snapshot: Some(r#"{"line_items": []}"#.to_string()),
+        ..invoice(3, 8_000)
+    })
+    .unwrap();
+    db.set_dunning_settings(&DunningSettings {
+        schedule: vec![3],
+        email: false,
+    })
+    .unwrap();
+
+    assert_eq!(db.overdue_invoice_count(day(2025, 5, 1)).unwrap(), 1);
+    let reminders = db.due_dunning_reminders(day(2025, 5, 3), &[3]).unwrap();
+    assert_eq!(
+        reminders
+            .iter()
+            .map(|r| r.invoice_number)
+            .collect::<Vec<_>>(),
+        vec![3]
+    );
+
+    let pay = |external_id: &str, cents: i64| {
+        db.record_payment(&Payment {
+            id: 0,
+            provider: "stripe".to_string(),
+            external_id: external_id.to_string(),
+            invoice_number: Some(3),
+            amount: Money::from_cents(cents),
+            fee: Money::ZERO,
+            currency: "USD".to_string(),
+            paid_at: at(day(2025, 5, 2), 12),
+            expense_id: None,
+        })
+        .unwrap();
+    };
+    // A partial payment leaves it overdue
+    pay("txn_1", 3_000);
+    assert_eq!(db.overdue_invoice_count(day(2025, 5, 3)).unwrap(), 1);
+    assert_eq!(db.invoice_paid_amount(3).unwrap(), Money::from_cents(3_000));
+
+    pay("txn_2", 5_000);
+    assert_eq!(db.overdue_invoice_count(day(2025, 5, 3)).unwrap(), 0);
+    assert!(dunning::send_due(&db, day(2025, 5, 3)).unwrap().is_empty());
+}
+
+#[test]
+fn dunning_sends_each_step_once_and_stops_when_paid() {
+    let db = test_db();
+    let entry = insert(&db, finished("acme", at(day(2025, 3, 3), 9), 2.0));
+    db.set_entry_status(entry.id, EntryStatus::Billed).unwrap();
+    db.set_entry_status(entry.id, EntryStatus::Invoiced)
+        .unwrap();
+    db.record_invoice(&Invoice {
+        snapshot: Some(format!(
+            r#"{{"rates": {{"acme": {{"rate": 100.0, "currency": "EUR"}}}}, "line_items": [{{"entry_id": {}}}]}}"#,
+            entry.id
+        )),
+        ..invoice(7, 20_000)
+    })
+    .unwrap();
+
+    // No schedule, no reminders
+    assert!(dunning::send_due(&db, day(2025, 5, 20)).unwrap().is_empty());
+
+    db.set_dunning_settings(&DunningSettings {
+        schedule: parse_dunning_schedule("+10, 3, 20").unwrap(),
+        email: false,
+    })
+    .unwrap();
+    assert_eq!(db.get_dunning_settings().unwrap().schedule, vec![3, 10, 20]);
+    assert!(dunning::send_due(&db, day(2025, 5, 2)).unwrap().is_empty());
+
+    let sent = dunning::send_due(&db, day(2025, 5, 3)).unwrap();
+    assert_eq!(sent.len(), 1);
+    assert_eq!(sent[0].reminder.step, 3);
+    assert_eq!(sent[0].reminder.currency, "EUR");
+    assert_eq!(
+        sent[0].reminder.message(),
+        "Invoice #0007 is 3 days past due"
+    );
+    assert!(sent[0].email.is_none());
+    assert!(dunning::send_due(&db, day(2025, 5, 4)).unwrap().is_empty());
+
+    // A step missed while nothing ran is skipped for the latest one reached
+    let sent = dunning::send_due(&db, day(2025, 5, 25)).unwrap();
+    assert_eq!(
+        sent.iter().map(|s| s.reminder.step).collect::<Vec<_>>(),
+        vec![20]
+    );
+    let log = db.dunning_log(Some(7)).unwrap();
+    assert_eq!(log.iter().map(|l| l.step).collect::<Vec<_>>(), vec![3, 20]);
+
+    db.set_entry_status(entry.id, EntryStatus::Paid).unwrap();
+    db.set_dunning_settings(&DunningSettings {
+        schedule: vec![30],
+        email: false,
+    })
+    .unwrap();
+    assert!(dunning::send_due(&db, day(2025, 6, 15)).unwrap().is_empty());
+    assert!(parse_dunning_schedule("3,-1").is_err());
+    assert!(parse_dunning_schedule("off").unwrap().is_empty());
+}
+
+#[test]
+fn relocating_rewrites_only_paths_inside_the_moved_folder() {
+    let db = test_db();
+    for (number, file_path) in [
+        (1, "/home/me/meter/invoices/invoice_0001.pdf"),
+        (2, "/home/me/meter/invoices-old/invoice_0002.pdf"),
+    ] {
+        db.record_invoice(&Invoice {
+            file_path: file_path.to_string(),
+            ..invoice(number, 10_000)
+        })
+        .unwrap();
+    }
+
+    let changed = db
+        .relocate_files(
+            "/home/me/meter/invoices",
+            "/home/me/Documents/meter/invoices",
+        )
+        .unwrap();
+    assert_eq!(changed, 1);
+    let paths: Vec<String> = db
+        .list_invoices()
+        .unwrap()
+        .into_iter()
+        .map(|i| i.file_path)
+        .collect();
+    assert!(paths.contains(&"/home/me/Documents/meter/invoices/invoice_0001.pdf".to_string()));
+    assert!(paths.contains(&"/home/me/meter/invoices-old/invoice_0002.pdf".to_string()));
+}
+
+#[test]
+fn invoice_pdfs_are_kept_however_their_stored_path_is_spelled() {
+    let dir = std::env::temp_dir().join(format!("meter-test-orphans-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    for name in [
+        "invoice_0002.pdf",
+        "invoice_0009.pdf",
+        "renamed.pdf",
+        "stray.pdf",
+        "draft_0001.pdf",
+    ] {
+        fs::write(dir.join(name), b"%PDF").unwrap();
+    }
+    let invoices = [
+        // Stored under another spelling of the same folder
+        Invoice {
+            file_path: dir
+                .join("..")
+                .join(dir.file_name().unwrap())
+                .join("renamed.pdf")
+                .to_string_lossy()
+                .into_owned(),
+            ..invoice(1, 10_000)
+        },
+        // Stored under a folder that has since moved
+        Invoice {
+            file_path: "/home/me/meter/invoices/invoice_0002.pdf".to_string(),
+            ..invoice(2, 10_000)
+        },
+    ];
+
+    let orphans = orphan_invoice_files_in(&invoices, dir.to_str().unwrap()).unwrap();
+    let _ = fs::remove_dir_all(&dir);
+    let names: Vec<_> = orphans
+        .iter()
+        .map(|p| p.file_name().unwrap().to_string_lossy().into_owned())
+        .collect();
+    assert_eq!(names, vec!["invoice_0009.pdf", "stray.pdf"]);
+}
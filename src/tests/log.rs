@@ -0,0 +1,50 @@
+use chrono::{Duration, Local};
+
+use super::{at, day, test_db};
+use crate::models::parse_log_line;
+
+#[test]
+fn log_lines_become_entries() {
+    let entry = parse_log_line("2024-06-03 09:00-10:30 acme fix login bug #dev").unwrap();
+    assert_eq!(entry.project, "acme");
+    assert_eq!(entry.description, "fix login bug #dev");
+    assert_eq!(entry.start, at(day(2024, 6, 3), 9));
+    assert_eq!(entry.end, Some(entry.start + Duration::minutes(90)));
+}
+
+#[test]
+fn log_lines_may_end_after_midnight() {
+    let entry = parse_log_line("2024-06-03 23:00-01:00 acme").unwrap();
+    assert_eq!(entry.description, "");
+    let end = entry.end.unwrap().with_timezone(&Local);
+    assert_eq!(end.date_naive(), day(2024, 6, 4));
+}
+
+#[test]
+fn malformed_log_lines_are_rejected() {
+    for line in [
+        "2024-06-03 09:00-10:30",
+        "2024-13-03 09:00-10:30 acme",
+        "2024-06-03 09:00 acme",
+        "2024-06-03 9am-10am acme",
+        "2024-06-03 09:00-09:00 acme",
+    ] {
+        assert!(parse_log_line(line).is_err(), "{}", line);
+    }
+}
+
+#[test]
+fn logged_entries_are_inserted_together() {
+    let db = test_db();
+    let entries = [
+        parse_log_line("2024-06-03 09:00-10:30 acme fix login bug").unwrap(),
+        parse_log_line("2024-06-03 11:00-12:00 globex review").unwrap(),
+    ];
+
+    let ids = db.insert_all(&entries).unwrap();
+    assert_eq!(ids.len(), 2);
+    assert_eq!(
+        db.get_entry_by_id(ids[1]).unwrap().unwrap().project,
+        "globex"
+    );
+}
@@ -0,0 +1,83 @@
+//! Behavioral tests run against an in-memory database, so they never touch
+//! the real database or each other's data.
+
+mod billing;
+mod busy;
+mod expenses;
+mod import;
+mod inbox;
+mod invoice;
+mod log;
+mod payments;
+mod query;
+mod report;
+mod timer;
+mod tui;
+
+use chrono::{DateTime, Duration, Local, NaiveDate, TimeZone, Utc};
+use rusqlite::Connection;
+
+use crate::db::Db;
+use crate::models::{self, DEFAULT_PROFILE_ID, Entry, EntryStatus, Invoice, Money};
+
+/// A fresh database with every table created
+fn test_db() -> Db {
+    let conn = Connection::open_in_memory().expect("Failed to open in-memory DB");
+    let db = Db::from_connection(conn).expect("Failed to set up DB");
+    models::init_all(db.conn()).expect("Failed to init DB");
+    db
+}
+
+/// `hour`:00 local time on `day`
+fn at(day: NaiveDate, hour: u32) -> DateTime<Utc> {
+    Local
+        .from_local_datetime(&day.and_hms_opt(hour, 0, 0).unwrap())
+        .earliest()
+        .unwrap()
+        .with_timezone(&Utc)
+}
+
+fn day(y: i32, m: u32, d: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(y, m, d).unwrap()
+}
+
+/// A finished, open entry of `hours` starting at `start`
+fn finished(project: &str, start: DateTime<Utc>, hours: f64) -> Entry {
+    Entry {
+        id: 0,
+        project: project.to_string(),
+        description: "Work session".to_string(),
+        start,
+        end: Some(start + Duration::seconds((hours * 3600.0) as i64)),
+        status: EntryStatus::Open,
+        days: None,
+        issue: None,
+        location: None,
+    }
+}
+
+/// Insert `entry` and return it with its new ID
+fn insert(db: &Db, entry: Entry) -> Entry {
+    let id = db.insert(&entry).expect("Failed to insert entry");
+    Entry { id, ..entry }
+}
+
+/// An invoice for `total_cents`, issued on 31 March 2025 and due a month later
+fn invoice(number: i64, total_cents: i64) -> Invoice {
+    Invoice {
+        id: number,
+        invoice_number: number,
+        client_id: None,
+        date_issued: "2025-03-31".to_string(),
+        due_date: "2025-04-30".to_string(),
+        subtotal: Money::from_cents(total_cents),
+        tax_rate: 0.0,
+        tax_amount: Money::ZERO,
+        total: Money::from_cents(total_cents),
+        file_path: format!("/tmp/invoice_{:04}.pdf", number),
+        snapshot: None,
+        void_reason: None,
+        supersedes: None,
+        profile_id: DEFAULT_PROFILE_ID,
+    }
+}
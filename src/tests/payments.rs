@@ -0,0 +1,47 @@
+use serde_json::json;
+
+use super::invoice;
+use crate::models::Money;
+use crate::payments::{match_invoice, parse_balance_transactions};
+
+#[test]
+fn stripe_charges_match_by_metadata_then_amount() {
+    let page = json!({
+        "data": [
+            {
+                "id": "txn_1", "amount": 120000, "fee": 3510, "currency": "usd",
+                "created": 1743465600,
+                "source": { "description": "Consulting", "metadata": { "invoice_number": "#12" } }
+            },
+            {
+                "id": "txn_2", "amount": 50000, "fee": 1480, "currency": "usd",
+                "created": 1743552000,
+                "source": { "description": "Payment", "metadata": {} }
+            },
+            {
+                "id": "txn_3", "amount": 5000, "fee": 0, "currency": "jpy",
+                "created": 1743552000,
+                "source": { "description": "Payment for invoice 99" }
+            }
+        ],
+        "has_more": false
+    });
+    let charges = parse_balance_transactions(&page);
+    assert_eq!(charges.len(), 3);
+    assert_eq!(charges[0].amount, Money::from_cents(120_000));
+    assert_eq!(charges[0].fee, Money::from_cents(3510));
+    assert_eq!(charges[0].currency, "USD");
+    // Yen have no minor unit
+    assert_eq!(charges[2].amount, Money::from_cents(500_000));
+
+    let invoices = vec![
+        invoice(12, 120_000),
+        invoice(13, 50_000),
+        invoice(14, 80_000),
+    ];
+    assert_eq!(match_invoice(&charges[0], &invoices, &[]), Some(12));
+    assert_eq!(match_invoice(&charges[1], &invoices, &[]), Some(13));
+    // Already paid, and invoice 99 doesn't exist
+    assert_eq!(match_invoice(&charges[1], &invoices, &[13]), None);
+    assert_eq!(match_invoice(&charges[2], &invoices, &[]), None);
+}
@@ -0,0 +1,141 @@
+use std::collections::HashMap;
+
+use super::{at, day, finished, test_db};
+use crate::models::{Entry, EntryFilter, EntryStatus};
+use crate::query::{QueryResult, parse};
+
+fn entries() -> (Vec<Entry>, HashMap<i64, Vec<String>>) {
+    let mut acme = finished("acme", at(day(2024, 6, 3), 9), 2.0);
+    acme.id = 1;
+    acme.description = "Fix login bug".to_string();
+    let mut billed = finished("acme", at(day(2024, 6, 11), 9), 1.5);
+    billed.id = 2;
+    billed.status = EntryStatus::Invoiced;
+    let mut earlier = finished("acme", at(day(2024, 5, 30), 9), 1.0);
+    earlier.id = 3;
+    let mut globex = finished("globex", at(day(2024, 6, 4), 9), 3.0);
+    globex.id = 4;
+    let tags = HashMap::from([
+        (1, vec!["support".to_string()]),
+        (4, vec!["dev".to_string()]),
+    ]);
+    (vec![acme, billed, earlier, globex], tags)
+}
+
+fn ids(result: &QueryResult) -> Vec<i64> {
+    match result {
+        QueryResult::Entries(rows) => rows.iter().map(|r| r.id).collect(),
+        QueryResult::Groups(_) => panic!("expected entries"),
+    }
+}
+
+#[test]
+fn conditions_are_joined_by_and() {
+    let (entries, tags) = entries();
+    let run = |q: &str| ids(&parse(q).unwrap().run(&entries, &tags));
+
+    assert_eq!(run(""), vec![3, 1, 4, 2]);
+    assert_eq!(
+        run("project=ACME and billed=false AND start>=2024-06-01"),
+        vec![1]
+    );
+    assert_eq!(run("billed = true"), vec![2]);
+    assert_eq!(run("description~LOGIN"), vec![1]);
+    assert_eq!(run("tag=support"), vec![1]);
+    assert_eq!(run("tag!=support AND project!=acme"), vec![4]);
+    assert_eq!(run("hours>=2 AND start<\"2024-06-04 12:00\""), vec![1, 4]);
+}
+
+#[test]
+fn group_by_totals_hours() {
+    let (entries, tags) = entries();
+    let result = parse("project=acme GROUP BY week")
+        .unwrap()
+        .run(&entries, &tags);
+    let QueryResult::Groups(groups) = &result else {
+        panic!("expected groups");
+    };
+    let totals: Vec<(&str, usize, f64)> = groups
+        .iter()
+        .map(|g| (g.group.as_str(), g.entries, g.hours))
+        .collect();
+    assert_eq!(
+        totals,
+        vec![
+            ("2024-W22", 1, 1.0),
+            ("2024-W23", 1, 2.0),
+            ("2024-W24", 1, 1.5)
+        ]
+    );
+    assert_eq!(
+        result.to_csv(),
+        "group,entries,hours\n2024-W22,1,1.00\n2024-W23,1,2.00\n2024-W24,1,1.50\n"
+    );
+}
+
+#[test]
+fn malformed_queries_are_refused() {
+    for query in [
+        "client=acme",
+        "project acme",
+        "project=acme billed=false",
+        "status>open",
+        "start>=June",
+        "project=\"acme",
+        "GROUP BY year",
+        "GROUP BY week AND project=acme",
+    ] {
+        assert!(parse(query).is_err(), "{}", query);
+    }
+}
+
+#[test]
+fn listing_filters_by_project_start_day_and_status_with_a_limit() {
+    let db = test_db();
+    let mut billed = finished("acme", at(day(2024, 6, 4), 9), 1.0);
+    billed.status = EntryStatus::Billed;
+    let ids = db
+        .insert_all(&[
+            finished("acme", at(day(2024, 6, 2), 23), 2.0),
+            finished("acme", at(day(2024, 6, 3), 9), 1.0),
+            billed,
+            finished("globex", at(day(2024, 6, 4), 13), 1.0),
+            finished("acme", at(day(2024, 6, 5), 0), 1.0),
+        ])
+        .unwrap();
+    let listed = |filter: EntryFilter| -> Vec<i64> {
+        let entries = db.list_filtered(&filter).unwrap();
+        entries.iter().map(|e| e.id).collect()
+    };
+
+    // Entries are matched by the local day they started, most recent first
+    let june_3_to_4 = EntryFilter {
+        project: Some("acme".to_string()),
+        start_from: Some(at(day(2024, 6, 3), 0)),
+        start_before: Some(at(day(2024, 6, 5), 0)),
+        ..EntryFilter::default()
+    };
+    assert_eq!(listed(june_3_to_4.clone()), [ids[2], ids[1]]);
+    assert_eq!(
+        listed(EntryFilter {
+            status: Some(EntryStatus::Open),
+            ..june_3_to_4
+        }),
+        [ids[1]]
+    );
+    assert_eq!(
+        listed(EntryFilter {
+            limit: Some(2),
+            ..EntryFilter::default()
+        }),
+        [ids[4], ids[3]]
+    );
+    assert_eq!(listed(EntryFilter::default()).len(), 5);
+    assert!(
+        listed(EntryFilter {
+            project: Some("initech".to_string()),
+            ..EntryFilter::default()
+        })
+        .is_empty()
+    );
+}
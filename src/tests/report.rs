@@ -0,0 +1,341 @@
+use chrono::{Datelike, Duration};
+
+use super::{at, day, finished};
+use crate::ical;
+use crate::models::{BillingMode, CapPeriod, Client, Entry, Invoice, Money, Project};
+use crate::report;
+
+fn project(name: &str, cents: i64, mode: BillingMode, client_id: Option<i64>) -> Project {
+    Project {
+        id: 0,
+        name: name.to_string(),
+        rate: Some(Money::from_cents(cents)),
+        currency: None,
+        client_id,
+        hour_cap: None,
+        cap_period: CapPeriod::default(),
+        billing_mode: mode,
+        idle_stop_minutes: None,
+        area: None,
+    }
+}
+
+fn invoice(number: i64, client_id: Option<i64>, issued: &str, cents: i64) -> Invoice {
+    Invoice {
+        id: 0,
+        invoice_number: number,
+        client_id,
+        date_issued: issued.to_string(),
+        due_date: issued.to_string(),
+        subtotal: Money::from_cents(cents),
+        tax_rate: 0.0,
+        tax_amount: Money::ZERO,
+        total: Money::from_cents(cents),
+        file_path: String::new(),
+        snapshot: None,
+        void_reason: None,
+        supersedes: None,
+        profile_id: 1,
+    }
+}
+
+#[test]
+fn forecast_adds_unbilled_work_to_the_larger_of_average_and_fixed_fees() {
+    let clients = [Client {
+        id: 1,
+        name: "Acme".to_string(),
+        ..Default::default()
+    }];
+    let projects = [
+        project("acme", 10_000, BillingMode::Hourly, Some(1)),
+        project("retainer", 50_000, BillingMode::Fixed, None),
+    ];
+    let entries = [
+        finished("acme", at(day(2025, 6, 2), 9), 2.0),
+        // Fixed fees are counted per month, not per entry
+        finished("retainer", at(day(2025, 6, 2), 13), 1.0),
+    ];
+    let mut voided = invoice(3, Some(1), "2025-05-31", 900_000);
+    voided.void_reason = Some("Wrong client".to_string());
+    let invoices = [
+        invoice(1, Some(1), "2025-04-30", 120_000),
+        invoice(2, Some(1), "2025-05-31", 60_000),
+        voided,
+        // This month's invoice isn't in the history window yet
+        invoice(4, Some(1), "2025-06-01", 500_000),
+    ];
+
+    let rows = report::forecast(
+        &entries,
+        &projects,
+        &clients,
+        &invoices,
+        day(2025, 6, 15),
+        3,
+        6,
+    );
+
+    assert_eq!(rows.len(), 2);
+    let acme = &rows[0];
+    assert_eq!(acme.client.as_deref(), Some("Acme"));
+    assert_eq!(acme.unbilled, Money::from_cents(20_000));
+    assert_eq!(acme.monthly_average, Money::from_cents(30_000));
+    assert_eq!(acme.forecast, Money::from_cents(110_000));
+
+    let no_client = &rows[1];
+    assert_eq!(no_client.client, None);
+    assert_eq!(no_client.unbilled, Money::ZERO);
+    assert!((no_client.unbilled_hours - 1.0).abs() < 1e-9);
+    assert_eq!(no_client.recurring, Money::from_cents(50_000));
+    assert_eq!(no_client.forecast, Money::from_cents(150_000));
+}
+
+#[test]
+fn heatmap_splits_spans_at_the_hour() {
+    let monday = day(2025, 6, 2);
+    assert_eq!(monday.weekday().num_days_from_monday(), 0);
+    let late = finished("acme", at(monday, 9) + Duration::minutes(40), 1.0);
+    // Runs past midnight into Tuesday
+    let overnight = finished("acme", at(monday, 23), 2.0);
+    let mut day_entry = finished("acme", at(monday, 9), 8.0);
+    day_entry.days = Some(1.0);
+
+    let grid = report::hour_heatmap(&[&late, &overnight, &day_entry]);
+
+    let close = |a: f64, b: f64| (a - b).abs() < 1e-9;
+    assert!(close(grid[0][9], 20.0 / 60.0));
+    assert!(close(grid[0][10], 40.0 / 60.0));
+    assert!(close(grid[0][23], 1.0));
+    assert!(close(grid[1][0], 1.0));
+    assert!(close(grid.iter().flatten().sum::<f64>(), 3.0));
+}
+
+#[test]
+fn area_hours_roll_projects_up_with_projects_outside_areas_last() {
+    let in_area = |name: &str, area: &str| Project {
+        area: Some(area.to_string()),
+        ..project(name, 10_000, BillingMode::Hourly, None)
+    };
+    let projects = [
+        in_area("acme", "Client work"),
+        in_area("globex", "Client work"),
+        in_area("meter", "Open source"),
+        project("admin", 0, BillingMode::Hourly, None),
+    ];
+    let monday = at(day(2025, 6, 2), 9);
+    let mut day_entry = finished("globex", monday, 8.0);
+    day_entry.days = Some(1.0);
+    let entries = [
+        finished("acme", monday, 2.0),
+        finished("globex", monday, 3.0),
+        finished("acme", monday + Duration::days(1), 1.5),
+        finished("meter", monday, 4.0),
+        finished("admin", monday, 10.0),
+        // Not a known project, so in no area either
+        finished("side", monday, 0.5),
+        day_entry,
+    ];
+    let entries: Vec<&Entry> = entries.iter().collect();
+
+    let hours = report::project_hours(&entries, monday + Duration::days(7));
+    let areas = report::area_hours(&hours, &projects);
+
+    let summary: Vec<(Option<&str>, f64, Vec<&str>)> = areas
+        .iter()
+        .map(|a| {
+            let names = a.projects.iter().map(|(p, _)| p.as_str()).collect();
+            (a.area.as_deref(), a.hours, names)
+        })
+        .collect();
+    assert_eq!(
+        summary,
+        [
+            (Some("Client work"), 6.5, vec!["acme", "globex"]),
+            (Some("Open source"), 4.0, vec!["meter"]),
+            (None, 10.5, vec!["admin", "side"]),
+        ]
+    );
+}
+
+#[test]
+fn calendar_events_are_read_with_folding_durations_and_alarms() {
+    // The second summary is folded onto a continuation line
+    let data = [
+        "BEGIN:VCALENDAR",
+        "BEGIN:VEVENT",
+        "SUMMARY:Acme sync\\, weekly",
+        "DTSTART:20250602T070000Z",
+        "DTEND:20250602T080000Z",
+        "END:VEVENT",
+        "BEGIN:VEVENT",
+        "SUMMARY:Globex design",
+        "  review",
+        "DTSTART;TZID=Europe/Berlin:20250603T140000",
+        "DURATION:PT1H30M",
+        "BEGIN:VALARM",
+        "DURATION:PT15M",
+        "END:VALARM",
+        "END:VEVENT",
+        "BEGIN:VEVENT",
+        "SUMMARY:Holiday",
+        "DTSTART;VALUE=DATE:20250604",
+        "END:VEVENT",
+        "BEGIN:VEVENT",
+        "SUMMARY:Moved",
+        "DTSTART:20250605T090000Z",
+        "STATUS:CANCELLED",
+        "END:VEVENT",
+        "END:VCALENDAR",
+    ]
+    .join("\r\n");
+
+    let events = ical::parse(&data, at(day(2025, 7, 1), 0)).unwrap();
+    let summaries: Vec<&str> = events.iter().map(|e| e.summary.as_str()).collect();
+    assert_eq!(
+        summaries,
+        ["Acme sync, weekly", "Globex design review", "Holiday"]
+    );
+    assert_eq!(events[0].end - events[0].start, Duration::hours(1));
+    // A time with a TZID is read as local time
+    assert_eq!(events[1].start, at(day(2025, 6, 3), 14));
+    assert_eq!(events[1].end - events[1].start, Duration::minutes(90));
+    assert!(events[2].all_day);
+    assert_eq!(events[2].end - events[2].start, Duration::days(1));
+}
+
+#[test]
+fn recurring_calendar_events_are_expanded_less_exceptions() {
+    let data = [
+        "BEGIN:VCALENDAR",
+        "BEGIN:VEVENT",
+        "UID:standup",
+        "SUMMARY:Standup",
+        "DTSTART:20250602T090000",
+        "DTEND:20250602T093000",
+        "RRULE:FREQ=WEEKLY;BYDAY=MO,WE;UNTIL=20250616",
+        "EXDATE:20250604T090000",
+        "END:VEVENT",
+        "BEGIN:VEVENT",
+        "UID:standup",
+        "SUMMARY:Standup",
+        "RECURRENCE-ID:20250609T090000",
+        "DTSTART:20250609T110000",
+        "DTEND:20250609T113000",
+        "END:VEVENT",
+        "BEGIN:VEVENT",
+        "UID:standup",
+        "SUMMARY:Standup",
+        "RECURRENCE-ID:20250611T090000",
+        "DTSTART:20250611T090000",
+        "STATUS:CANCELLED",
+        "END:VEVENT",
+        "BEGIN:VEVENT",
+        "UID:review",
+        "SUMMARY:Review",
+        "DTSTART:20250131T150000",
+        "DURATION:PT1H",
+        "RRULE:FREQ=MONTHLY;BYDAY=-1FR;COUNT=3",
+        "END:VEVENT",
+        "BEGIN:VEVENT",
+        "UID:gym",
+        "SUMMARY:Gym",
+        "DTSTART:20250620T080000",
+        "DTEND:20250620T090000",
+        "RRULE:FREQ=DAILY;INTERVAL=2",
+        "END:VEVENT",
+        "END:VCALENDAR",
+    ]
+    .join("\r\n");
+
+    let events = ical::parse(&data, at(day(2025, 6, 27), 0)).unwrap();
+    let starts = |summary: &str| -> Vec<_> {
+        events
+            .iter()
+            .filter(|e| e.summary == summary)
+            .map(|e| (e.start, e.end - e.start))
+            .collect()
+    };
+    let half_hour = Duration::minutes(30);
+    // The 4th is excluded, the 9th moved to 11:00 and the 11th cancelled
+    assert_eq!(
+        starts("Standup"),
+        [
+            (at(day(2025, 6, 2), 9), half_hour),
+            (at(day(2025, 6, 9), 11), half_hour),
+            (at(day(2025, 6, 16), 9), half_hour),
+        ]
+    );
+    let hour = Duration::hours(1);
+    assert_eq!(
+        starts("Review"),
+        [
+            (at(day(2025, 1, 31), 15), hour),
+            (at(day(2025, 2, 28), 15), hour),
+            (at(day(2025, 3, 28), 15), hour),
+        ]
+    );
+    // An endless rule stops at the end of the window
+    assert_eq!(
+        starts("Gym"),
+        [20, 22, 24, 26].map(|d| (at(day(2025, 6, d), 8), hour))
+    );
+}
+
+#[test]
+fn calendar_rules_that_cant_be_expanded_are_errors() {
+    let calendar = |lines: &[&str]| {
+        let mut data = vec!["BEGIN:VCALENDAR", "BEGIN:VEVENT", "SUMMARY:Sync"];
+        data.extend(lines);
+        data.extend(["END:VEVENT", "END:VCALENDAR"]);
+        ical::parse(&data.join("\r\n"), at(day(2025, 7, 1), 0))
+    };
+
+    for rule in [
+        "FREQ=HOURLY",
+        "FREQ=MONTHLY;BYSETPOS=-1;BYDAY=MO",
+        "COUNT=3",
+    ] {
+        let rrule = format!("RRULE:{}", rule);
+        let result = calendar(&["DTSTART:20250602T090000", rrule.as_str()]);
+        assert!(result.is_err(), "{} was accepted", rule);
+    }
+
+    // A length too long to represent drops the event rather than panicking
+    let events = calendar(&["DTSTART:20250602T090000", "DURATION:P99999999999999W"]).unwrap();
+    assert!(events.is_empty());
+    let events = calendar(&["DTSTART:20250602T090000", "DURATION:PT45M"]).unwrap();
+    assert_eq!(events[0].end - events[0].start, Duration::minutes(45));
+}
+
+#[test]
+fn reconcile_measures_tracked_time_within_each_meeting() {
+    let monday = day(2025, 6, 2);
+    let event = |summary: &str, hour: u32, minutes: i64| ical::CalendarEvent {
+        summary: summary.to_string(),
+        start: at(monday, hour),
+        end: at(monday, hour) + Duration::minutes(minutes),
+        all_day: false,
+    };
+    let events = [
+        event("Acme sync", 9, 60),
+        event("Globex review", 14, 60),
+        event("Lunch", 12, 0),
+        ical::CalendarEvent {
+            all_day: true,
+            ..event("Offsite", 0, 24 * 60)
+        },
+    ];
+    let entries = [
+        finished("acme", at(monday, 9) + Duration::minutes(15), 2.0),
+        finished("globex", at(monday, 14) + Duration::minutes(45), 1.0),
+    ];
+
+    let rows = report::reconcile(&events, &entries, at(monday, 18));
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[0].tracked_secs, 45 * 60);
+    assert_eq!(rows[0].projects, ["acme"]);
+    assert!(!rows[0].is_untracked(0.5));
+    assert_eq!(rows[1].tracked_secs, 15 * 60);
+    assert!(rows[1].is_untracked(0.5));
+    assert!(!rows[1].is_untracked(0.25));
+}
@@ -0,0 +1,286 @@
+use chrono::{Duration, Utc};
+
+use super::test_db;
+use crate::models::{ReviewReason, issue_from_branch, normalize_location, normalize_tag};
+
+#[test]
+fn start_then_stop_records_a_finished_entry() {
+    let db = test_db();
+
+    let started = db.start_timer("acme", "Design review").unwrap();
+    assert_eq!(started.project, "acme");
+    assert!(started.end.is_none());
+    assert_eq!(
+        db.get_active_entry().unwrap().map(|e| e.id),
+        Some(started.id)
+    );
+
+    let stopped = db.stop_active_timer().unwrap().expect("timer was running");
+    assert_eq!(stopped.id, started.id);
+    assert!(stopped.end.is_some_and(|end| end >= stopped.start));
+    assert!(db.get_active_entry().unwrap().is_none());
+    assert_eq!(db.list(None).unwrap().len(), 1);
+}
+
+#[test]
+fn stopping_without_a_running_timer_does_nothing() {
+    let db = test_db();
+
+    assert!(db.stop_active_timer().unwrap().is_none());
+    assert!(db.list(None).unwrap().is_empty());
+}
+
+#[test]
+fn stop_time_is_never_before_the_start() {
+    let db = test_db();
+    let started = db.start_timer("acme", "Standup").unwrap();
+
+    let stopped = db
+        .stop_active_timer_at(Utc::now() - Duration::hours(2))
+        .unwrap()
+        .unwrap();
+    assert_eq!(stopped.end, Some(started.start));
+}
+
+#[test]
+fn time_offsets_too_large_to_represent_are_rejected() {
+    let ago = crate::parse_time_arg("-90m").unwrap();
+    assert!(
+        (Utc::now() - ago - Duration::minutes(90))
+            .num_seconds()
+            .abs()
+            <= 1
+    );
+
+    assert!(crate::parse_time_arg("-99999999999h").is_none());
+    assert!(crate::parse_time_arg("-9223372036854775807s").is_none());
+    assert!(crate::parse_duration_arg("9223372036854775807m").is_none());
+}
+
+#[test]
+fn switching_stops_the_running_timer_where_the_next_starts() {
+    let db = test_db();
+    let first = db.start_timer("acme", "Support").unwrap();
+
+    let (stopped, started) = db.switch_timer("globex", "Support").unwrap();
+    let stopped = stopped.expect("timer was running");
+    assert_eq!(stopped.id, first.id);
+    assert_eq!(stopped.end, Some(started.start));
+    assert_eq!(
+        db.get_active_entry().unwrap().map(|e| (e.id, e.project)),
+        Some((started.id, "globex".to_string()))
+    );
+
+    db.stop_active_timer().unwrap();
+    let (stopped, _) = db.switch_timer("acme", "Support").unwrap();
+    assert!(stopped.is_none());
+    assert_eq!(db.list(None).unwrap().len(), 3);
+}
+
+#[test]
+fn a_backdated_timer_must_start_after_the_previous_entry_ends() {
+    let db = test_db();
+    let previous = db.start_timer("acme", "Standup").unwrap();
+    let previous = db
+        .stop_active_timer_at(previous.start + Duration::minutes(15))
+        .unwrap()
+        .unwrap();
+    let previous_end = previous.end.unwrap();
+
+    let overlapping = db
+        .entry_ending_after(previous_end - Duration::minutes(5))
+        .unwrap();
+    assert_eq!(overlapping.map(|e| e.id), Some(previous.id));
+    assert!(db.entry_ending_after(previous_end).unwrap().is_none());
+
+    let started = db.start_timer_at("globex", "Design", previous_end).unwrap();
+    assert_eq!(started.start, previous_end);
+    assert_eq!(
+        db.get_active_entry().unwrap().map(|e| (e.id, e.start)),
+        Some((started.id, previous_end))
+    );
+}
+
+#[test]
+fn resume_picks_the_entry_that_stopped_last() {
+    let db = test_db();
+    assert!(db.last_stopped_entry().unwrap().is_none());
+
+    let now = Utc::now();
+    let support = db
+        .start_timer_at("acme", "Support", now - Duration::hours(3))
+        .unwrap();
+    db.stop_active_timer_at(now - Duration::hours(1)).unwrap();
+    db.start_timer_at("globex", "Design", now - Duration::hours(2))
+        .unwrap();
+    db.stop_active_timer_at(now - Duration::minutes(90))
+        .unwrap();
+    db.start_timer("initech", "Running").unwrap();
+
+    let last = db.last_stopped_entry().unwrap().unwrap();
+    assert_eq!(last.id, support.id);
+    assert_eq!(last.description, "Support");
+}
+
+#[test]
+fn time_tracked_since_counts_the_running_timer_and_clips_earlier_entries() {
+    let db = test_db();
+    let now = Utc::now();
+    let since = now - Duration::hours(3);
+    let overnight = db
+        .start_timer_at("acme", "Deploy", since - Duration::hours(1))
+        .unwrap();
+    db.stop_active_timer_at(overnight.start + Duration::hours(2))
+        .unwrap();
+    db.start_timer_at("globex", "Design", now - Duration::minutes(30))
+        .unwrap();
+
+    // One hour after `since` plus the 30 running minutes
+    assert_eq!(db.seconds_tracked_since(since, now).unwrap(), 90 * 60);
+    assert_eq!(db.seconds_tracked_since(now, now).unwrap(), 0);
+}
+
+#[test]
+fn countdown_notification_is_claimed_once() {
+    let db = test_db();
+    let started = db.start_timer("acme", "Focus").unwrap();
+    db.set_timer_target(started.id, 25 * 60).unwrap();
+
+    assert_eq!(db.timer_target(started.id).unwrap(), Some(25 * 60));
+    assert!(db.claim_target_notification(started.id).unwrap());
+    assert!(!db.claim_target_notification(started.id).unwrap());
+}
+
+#[test]
+fn issue_references_survive_stopping_and_midnight_splits() {
+    let db = test_db();
+
+    let mut entry = db.start_timer("acme", "Fix login").unwrap();
+    entry.issue = Some("ACME-123".to_string());
+    entry.start = Utc::now() - Duration::days(2);
+    db.update_entry(&entry).unwrap();
+    let stopped = db.stop_active_timer().unwrap().unwrap();
+    assert_eq!(stopped.issue.as_deref(), Some("ACME-123"));
+
+    assert!(db.split_entry_at_midnight(stopped.id).unwrap() > 0);
+    let entries = db.list(None).unwrap();
+    assert!(entries.len() > 1);
+    assert!(
+        entries
+            .iter()
+            .all(|e| e.issue.as_deref() == Some("ACME-123"))
+    );
+}
+
+#[test]
+fn issues_are_found_in_branch_names() {
+    assert_eq!(
+        issue_from_branch("feature/ACME-123-fix-login").as_deref(),
+        Some("ACME-123")
+    );
+    assert_eq!(issue_from_branch("PROJ2-7").as_deref(), Some("PROJ2-7"));
+    assert_eq!(issue_from_branch("fix/42-login").as_deref(), Some("#42"));
+    assert_eq!(issue_from_branch("main"), None);
+    assert_eq!(issue_from_branch("release/v2-1"), None);
+}
+
+#[test]
+fn locations_are_normalized_and_stored() {
+    assert_eq!(
+        normalize_location(" On-Site ").as_deref(),
+        Some("client-site")
+    );
+    assert_eq!(normalize_location("WFH").as_deref(), Some("home"));
+    assert_eq!(
+        normalize_location("Berlin office").as_deref(),
+        Some("Berlin office")
+    );
+    assert_eq!(normalize_location("  "), None);
+
+    let db = test_db();
+    let mut entry = db.start_timer("acme", "Workshop").unwrap();
+    entry.location = normalize_location("client");
+    db.update_entry(&entry).unwrap();
+    let stopped = db.stop_active_timer().unwrap().unwrap();
+    assert_eq!(stopped.location.as_deref(), Some("client-site"));
+}
+
+#[test]
+fn network_rules_are_replaced_per_network() {
+    let db = test_db();
+    db.save_network_rule("ClientCorp", "clientcorp", None)
+        .unwrap();
+    db.save_network_rule("ClientCorp", "clientcorp-support", Some("client-site"))
+        .unwrap();
+    db.save_network_rule("Home", "internal", None).unwrap();
+
+    let rule = db.network_rule_for("ClientCorp").unwrap().unwrap();
+    assert_eq!(rule.project, "clientcorp-support");
+    assert_eq!(rule.location.as_deref(), Some("client-site"));
+    assert!(db.network_rule_for("clientcorp").unwrap().is_none());
+    assert_eq!(db.list_network_rules().unwrap().len(), 2);
+
+    assert!(db.delete_network_rule("Home").unwrap());
+    assert!(!db.delete_network_rule("Home").unwrap());
+    assert!(db.network_rule_for("Home").unwrap().is_none());
+}
+
+#[test]
+fn tags_are_normalized_and_follow_midnight_splits() {
+    assert_eq!(normalize_tag(" #Support ").as_deref(), Some("support"));
+    assert_eq!(
+        normalize_tag("Code  Review").as_deref(),
+        Some("code-review")
+    );
+    assert_eq!(normalize_tag("#"), None);
+
+    let db = test_db();
+    let mut entry = db.start_timer("acme", "Incident").unwrap();
+    entry.start = Utc::now() - Duration::days(2);
+    db.update_entry(&entry).unwrap();
+    let tags = ["support".to_string(), "on-call".to_string()];
+    db.add_entry_tags(entry.id, &tags).unwrap();
+    db.add_entry_tags(entry.id, &tags[..1]).unwrap();
+    let stopped = db.stop_active_timer().unwrap().unwrap();
+    assert_eq!(db.entry_tags(stopped.id).unwrap(), ["on-call", "support"]);
+
+    let added = db.split_entry_at_midnight(stopped.id).unwrap();
+    assert!(added > 0);
+    assert_eq!(
+        db.tag_counts().unwrap(),
+        [
+            ("on-call".to_string(), added as i64 + 1),
+            ("support".to_string(), added as i64 + 1)
+        ]
+    );
+
+    assert_eq!(db.remove_entry_tags(stopped.id, &tags).unwrap(), 2);
+    assert!(db.entry_tags(stopped.id).unwrap().is_empty());
+}
+
+#[test]
+fn midnight_splits_flag_every_part_for_review() {
+    let db = test_db();
+    let mut entry = db.start_timer("acme", "Migration").unwrap();
+    entry.start = Utc::now() - Duration::days(2);
+    db.update_entry(&entry).unwrap();
+    let stopped = db.stop_active_timer().unwrap().unwrap();
+    db.flag_for_review(&[stopped.id], ReviewReason::Imported)
+        .unwrap();
+
+    let added = db.split_entry_at_midnight(stopped.id).unwrap();
+    assert!(added > 0);
+    let flags = db.review_flags().unwrap();
+    assert_eq!(flags.len(), added + 1);
+    assert!(
+        flags
+            .values()
+            .all(|reasons| reasons == &[ReviewReason::Imported, ReviewReason::Split])
+    );
+
+    // Marking one part reviewed leaves the others flagged
+    assert_eq!(db.clear_review_flags(stopped.id).unwrap(), 2);
+    let flags = db.review_flags().unwrap();
+    assert!(!flags.contains_key(&stopped.id));
+    assert_eq!(flags.len(), added);
+}
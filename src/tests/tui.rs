@@ -0,0 +1,484 @@
+use chrono::{Datelike, Duration, Local};
+use std::{fs, io};
+
+use super::{at, day, finished, insert, test_db};
+use crate::db::Db;
+use crate::invoice::{InvoiceParams, expenses_for_invoice, write_invoice_in};
+use crate::models::{
+    Client, DEFAULT_PROFILE_ID, EntryStatus, EntryTemplate, Expense, ExpenseKind, InvoiceSettings,
+    Money, OvertimeRates, ReviewReason,
+};
+use crate::tui::app::{App, Message, Screen, review_anomaly};
+
+/// Send `msg` and every follow-up message it returns, as the event loop does
+fn send(app: &mut App, db: &Db, msg: Message) {
+    let mut next = Some(msg);
+    while let Some(msg) = next {
+        next = app.update(msg, db);
+    }
+}
+
+/// An app past the first-run wizard
+fn app(db: &Db) -> App {
+    let mut app = App::new(db);
+    if app.onboarding.is_some() {
+        send(&mut app, db, Message::OnboardingSkip);
+    }
+    app
+}
+
+#[test]
+fn fresh_database_opens_the_setup_wizard() {
+    let db = test_db();
+    let mut app = App::new(&db);
+    assert!(app.onboarding.is_some());
+
+    // Skipping the welcome step skips the whole wizard
+    send(&mut app, &db, Message::OnboardingSkip);
+    assert!(app.onboarding.is_none());
+}
+
+#[test]
+fn timer_starts_and_stops_from_the_timer_screen() {
+    let db = test_db();
+    let mut app = app(&db);
+
+    for c in "acme".chars() {
+        send(&mut app, &db, Message::UpdateProjectInput(c));
+    }
+    send(&mut app, &db, Message::StartTimer);
+    let running = app.active_entry.clone().expect("timer should be running");
+    assert_eq!(running.project, "acme");
+    assert!(app.project_input.is_empty());
+
+    send(&mut app, &db, Message::StopTimer);
+    assert!(app.active_entry.is_none());
+    let stopped = db.get_entry_by_id(running.id).unwrap().unwrap();
+    assert!(stopped.end.is_some());
+}
+
+#[test]
+fn cancelling_the_timer_throws_its_entry_away() {
+    let db = test_db();
+    let mut app = app(&db);
+    let kept = insert(&db, finished("acme", at(day(2025, 3, 3), 9), 1.0));
+
+    for c in "wrong".chars() {
+        send(&mut app, &db, Message::UpdateProjectInput(c));
+    }
+    send(&mut app, &db, Message::StartTimer);
+    let running = app.active_entry.clone().expect("timer should be running");
+
+    // Saying no leaves it running
+    send(&mut app, &db, Message::CancelTimer);
+    assert!(app.confirm_cancel_timer);
+    send(&mut app, &db, Message::KeepTimer);
+    assert!(!app.confirm_cancel_timer);
+    assert!(db.get_active_entry().unwrap().is_some());
+
+    send(&mut app, &db, Message::CancelTimer);
+    send(&mut app, &db, Message::ConfirmCancelTimer);
+    assert!(app.active_entry.is_none());
+    assert!(db.get_entry_by_id(running.id).unwrap().is_none());
+    assert!(db.get_entry_by_id(kept.id).unwrap().is_some());
+}
+
+#[test]
+fn timer_needs_a_project() {
+    let db = test_db();
+    let mut app = app(&db);
+
+    send(&mut app, &db, Message::StartTimer);
+    assert!(app.active_entry.is_none());
+    assert!(db.list(None).unwrap().is_empty());
+}
+
+#[test]
+fn entry_status_and_delete_go_through_the_database() {
+    let db = test_db();
+    let entry = insert(&db, finished("acme", at(day(2025, 3, 3), 9), 1.0));
+    let mut app = app(&db);
+
+    send(
+        &mut app,
+        &db,
+        Message::SetEntryStatus(entry.id, EntryStatus::Billed),
+    );
+    let billed = db.get_entry_by_id(entry.id).unwrap().unwrap();
+    assert_eq!(billed.status, EntryStatus::Billed);
+
+    // Billed entries have to be invoiced before they're paid; the status line says why
+    send(
+        &mut app,
+        &db,
+        Message::SetEntryStatus(entry.id, EntryStatus::Paid),
+    );
+    assert!(
+        app.status_message
+            .as_deref()
+            .is_some_and(|m| m.contains("can't be marked paid"))
+    );
+    assert_eq!(
+        db.get_entry_by_id(entry.id).unwrap().unwrap().status,
+        EntryStatus::Billed
+    );
+
+    send(&mut app, &db, Message::DeleteEntry(entry.id));
+    assert_eq!(app.confirm_delete, Some(entry.id));
+    send(&mut app, &db, Message::ConfirmDelete);
+    assert!(db.get_entry_by_id(entry.id).unwrap().is_none());
+}
+
+#[test]
+fn invoice_screen_lists_billed_entries() {
+    let db = test_db();
+    let billed = insert(&db, finished("acme", at(day(2025, 3, 3), 9), 1.0));
+    insert(&db, finished("acme", at(day(2025, 3, 4), 9), 1.0));
+    db.set_entry_status(billed.id, EntryStatus::Billed).unwrap();
+    let mut app = app(&db);
+
+    send(&mut app, &db, Message::SwitchScreen(Screen::Invoice));
+    assert_eq!(app.current_screen, Screen::Invoice);
+    let ids: Vec<i64> = app.invoice_entries.iter().map(|e| e.id).collect();
+    assert_eq!(ids, vec![billed.id]);
+}
+
+#[test]
+fn invoice_preview_bills_the_same_total_as_the_pdf() {
+    let db = test_db();
+    let client_id = db
+        .add_client(&Client {
+            name: "Acme Corp".to_string(),
+            ..Default::default()
+        })
+        .unwrap();
+    db.get_or_create_project("acme").unwrap();
+    db.set_project_client("acme", Some(client_id)).unwrap();
+    db.set_project_rate("acme", Some(Money::from_cents(10_000)), Some("$"))
+        .unwrap();
+    db.set_overtime_rates(
+        client_id,
+        &OvertimeRates {
+            weekend: Some(1.5),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    db.set_invoice_settings(&InvoiceSettings {
+        default_tax_rate: 10.0,
+        ..Default::default()
+    })
+    .unwrap();
+    // Two hours on a Friday and one on a Saturday, and parking that month
+    let friday = insert(&db, finished("acme", at(day(2025, 3, 7), 9), 2.0));
+    let saturday = insert(&db, finished("acme", at(day(2025, 3, 8), 10), 1.0));
+    db.set_entries_status(&[friday.id, saturday.id], EntryStatus::Billed)
+        .unwrap();
+    db.add_expense(&Expense {
+        id: 0,
+        date: day(2025, 3, 10),
+        kind: ExpenseKind::Expense,
+        description: "Parking".to_string(),
+        amount: Money::from_cents(1234),
+        distance: None,
+        unit: None,
+        entry_id: None,
+        client_id: Some(client_id),
+        invoice_number: None,
+    })
+    .unwrap();
+    let mut app = app(&db);
+    send(&mut app, &db, Message::SwitchScreen(Screen::Invoice));
+
+    // 2 h × $100 + 1 h × $150 + $12.34, plus 10% tax
+    let preview = app.preview_bill(&app.invoice_entries, 2025, 3);
+    assert_eq!(
+        preview.line("acme").unwrap().amount,
+        Money::from_cents(35_000)
+    );
+    assert_eq!(preview.expenses, Money::from_cents(1234));
+    assert_eq!(preview.totals.tax_amount, Money::from_cents(3623));
+    assert_eq!(preview.totals.total, Money::from_cents(39_857));
+
+    // The PDF generate_invoice writes for the same entries
+    let entries = db.list(Some(EntryStatus::Billed)).unwrap();
+    let settings = db.get_profile_settings(DEFAULT_PROFILE_ID).unwrap();
+    let overtime = db.get_overtime_rates(client_id).unwrap();
+    let expenses = expenses_for_invoice(
+        &db.list_expenses().unwrap(),
+        &entries,
+        Some(client_id),
+        2025,
+        3,
+    );
+    let params = InvoiceParams {
+        entries: &entries,
+        rounding_minutes: 0,
+        expenses: &expenses,
+        items: &[],
+        project_rates: &app.project_rates,
+        year: 2025,
+        month: 3,
+        invoice_number: 1,
+        draft: false,
+        supersedes: None,
+        settings: &settings,
+        client: None,
+        overtime: overtime.as_ref(),
+        tax_rate: settings.default_tax_rate,
+        holidays: &[],
+        reissue_dates: None,
+    };
+    let dir = std::env::temp_dir().join(format!("meter-test-preview-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let result = match write_invoice_in(&params, dir.to_str().unwrap()) {
+        Ok(result) => result,
+        // Rendering needs Arial or Liberation Sans installed
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            eprintln!("skipping invoice PDF test: {}", e);
+            return;
+        }
+        Err(e) => panic!("Failed to write invoice: {}", e),
+    };
+    assert_eq!(result.total, preview.totals.total);
+    assert_eq!(result.tax_amount, preview.totals.tax_amount);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn renaming_a_tag_to_one_in_use_merges_them() {
+    let db = test_db();
+    let first = insert(&db, finished("acme", at(day(2025, 3, 3), 9), 1.0));
+    let second = insert(&db, finished("acme", at(day(2025, 3, 4), 9), 1.0));
+    db.add_entry_tags(first.id, &["support".to_string(), "ops".to_string()])
+        .unwrap();
+    db.add_entry_tags(second.id, &["ops".to_string()]).unwrap();
+    let mut app = app(&db);
+    send(&mut app, &db, Message::SwitchScreen(Screen::Tags));
+    assert_eq!(app.tags.len(), 2);
+
+    send(&mut app, &db, Message::CycleTagColor("ops".to_string()));
+    assert_eq!(app.tag_colors.get("ops").map(String::as_str), Some("red"));
+
+    send(&mut app, &db, Message::RenameTag("ops".to_string()));
+    for _ in 0.."ops".len() {
+        send(&mut app, &db, Message::TagRenameBackspace);
+    }
+    for c in "Support".chars() {
+        send(&mut app, &db, Message::TagRenameInput(c));
+    }
+    send(&mut app, &db, Message::SaveTagRename);
+    assert_eq!(app.tags, [("support".to_string(), 2)]);
+    assert_eq!(
+        app.tag_colors.get("support").map(String::as_str),
+        Some("red")
+    );
+
+    send(&mut app, &db, Message::DeleteTag("support".to_string()));
+    send(&mut app, &db, Message::ConfirmDeleteTag);
+    assert!(app.tags.is_empty());
+    assert!(db.entry_tags(first.id).unwrap().is_empty());
+}
+
+#[test]
+fn settings_screen_adds_duplicates_and_deletes_profiles() {
+    let db = test_db();
+    let mut settings = db.get_invoice_settings().unwrap();
+    settings.business_name = "Acme Consulting".to_string();
+    db.set_invoice_settings(&settings).unwrap();
+    let mut app = app(&db);
+    send(&mut app, &db, Message::SwitchScreen(Screen::Settings));
+    assert_eq!(app.profiles.len(), 1);
+
+    send(&mut app, &db, Message::DuplicateProfile(DEFAULT_PROFILE_ID));
+    assert_eq!(app.profile_first_number_input, "1001");
+    send(&mut app, &db, Message::SaveProfile);
+    assert_eq!(app.profiles.len(), 2);
+    assert_eq!(app.profiles[1].name, "default (copy)");
+    assert_eq!(app.profiles[1].first_number, 1001);
+    assert_eq!(app.shown_settings().business_name, "Acme Consulting");
+
+    send(&mut app, &db, Message::DeleteProfile(DEFAULT_PROFILE_ID));
+    assert!(app.confirm_delete_profile.is_none());
+
+    let copy = app.shown_profile_id();
+    send(&mut app, &db, Message::DeleteProfile(copy));
+    send(&mut app, &db, Message::ConfirmDeleteProfile);
+    assert_eq!(app.profiles.len(), 1);
+    assert_eq!(app.shown_profile_id(), DEFAULT_PROFILE_ID);
+}
+
+#[test]
+fn weekly_review_walks_last_weeks_entries_and_flags_odd_ones() {
+    let db = test_db();
+    let today = Local::now().date_naive();
+    let monday = today - Duration::days(today.weekday().num_days_from_monday() as i64 + 7);
+    let normal = insert(&db, finished("acme", at(monday, 9), 2.0));
+    let forgotten = insert(
+        &db,
+        finished("acme", at(monday + Duration::days(1), 8), 12.0),
+    );
+    insert(
+        &db,
+        finished("acme", at(monday + Duration::days(7), 9), 1.0),
+    );
+    let mut app = app(&db);
+    send(&mut app, &db, Message::SwitchScreen(Screen::Review));
+    assert_eq!(app.review_entries.len(), 2);
+    assert_eq!(app.review_entries[0].id, normal.id);
+    assert_eq!(review_anomaly(&app.review_entries[0]), None);
+
+    send(&mut app, &db, Message::ReviewEditDescription);
+    for _ in 0.."Work session".len() {
+        send(&mut app, &db, Message::ReviewBackspace);
+    }
+    for c in "Planning".chars() {
+        send(&mut app, &db, Message::ReviewInput(c));
+    }
+    send(&mut app, &db, Message::SaveReviewInput);
+    send(&mut app, &db, Message::ReviewAddTag);
+    for c in "Meetings".chars() {
+        send(&mut app, &db, Message::ReviewInput(c));
+    }
+    send(&mut app, &db, Message::SaveReviewInput);
+    send(&mut app, &db, Message::ReviewCycleStatus);
+    assert_eq!(db.entry_tags(normal.id).unwrap(), ["meetings"]);
+    let saved = db.get_entry_by_id(normal.id).unwrap().unwrap();
+    assert_eq!(saved.description, "Planning");
+    assert_eq!(saved.status, EntryStatus::Submitted);
+
+    send(&mut app, &db, Message::ReviewNext);
+    assert_eq!(app.review_entries[1].id, forgotten.id);
+    assert!(review_anomaly(&app.review_entries[1]).is_some());
+    send(&mut app, &db, Message::ReviewNext);
+    send(&mut app, &db, Message::ReviewNext);
+    assert_eq!(app.review_index, 2);
+    assert_eq!(app.review_edited.len(), 1);
+}
+
+#[test]
+fn trimming_an_entry_moves_only_its_end() {
+    let db = test_db();
+    let entry = insert(&db, finished("acme", at(day(2025, 3, 3), 9), 1.0));
+    let mut app = app(&db);
+    send(&mut app, &db, Message::SwitchScreen(Screen::Entries));
+
+    send(&mut app, &db, Message::AdjustEntryEnd(entry.id, -15));
+    send(&mut app, &db, Message::AdjustEntryEnd(entry.id, 5));
+    let saved = db.get_entry_by_id(entry.id).unwrap().unwrap();
+    assert_eq!(saved.start, entry.start);
+    assert_eq!(saved.end, Some(entry.start + Duration::minutes(50)));
+
+    // Never past the start
+    send(&mut app, &db, Message::AdjustEntryEnd(entry.id, -60));
+    let saved = db.get_entry_by_id(entry.id).unwrap().unwrap();
+    assert_eq!(saved.end, Some(entry.start + Duration::minutes(50)));
+}
+
+#[test]
+fn project_picker_limits_entries_to_one_project() {
+    let db = test_db();
+    let acme = insert(&db, finished("acme", at(day(2025, 3, 3), 9), 1.0));
+    insert(&db, finished("globex", at(day(2025, 3, 3), 11), 1.0));
+    db.get_or_create_project("acme").unwrap();
+    db.get_or_create_project("globex").unwrap();
+    let mut app = app(&db);
+    send(&mut app, &db, Message::SwitchScreen(Screen::Entries));
+    send(&mut app, &db, Message::RefreshEntries);
+    assert_eq!(app.entries.len(), 2);
+
+    send(&mut app, &db, Message::OpenProjectPicker);
+    assert!(app.show_project_picker);
+    assert_eq!(app.project_picker_index, 0);
+    let row = app.projects.iter().position(|p| p.name == "acme").unwrap() + 1;
+    for _ in 0..row {
+        send(&mut app, &db, Message::ProjectPickerNext);
+    }
+    send(&mut app, &db, Message::ApplyProjectPicker);
+    assert!(!app.show_project_picker);
+    assert_eq!(app.entries_project_filter.as_deref(), Some("acme"));
+    assert_eq!(
+        app.entries.iter().map(|e| e.id).collect::<Vec<_>>(),
+        vec![acme.id]
+    );
+
+    // The status filter still applies on top
+    send(&mut app, &db, Message::CycleStatusFilter);
+    assert_eq!(app.entries.len(), 1);
+    send(&mut app, &db, Message::CycleStatusFilter);
+    assert!(app.entries.is_empty());
+
+    // "All projects" is the first row
+    send(&mut app, &db, Message::OpenProjectPicker);
+    for _ in 0..row {
+        send(&mut app, &db, Message::ProjectPickerPrevious);
+    }
+    send(&mut app, &db, Message::ApplyProjectPicker);
+    assert!(app.entries_project_filter.is_none());
+}
+
+#[test]
+fn template_picker_fills_in_a_new_entry() {
+    let db = test_db();
+    for (name, duration) in [("standup", 0.25), ("weekly", 1.0)] {
+        db.save_template(&EntryTemplate {
+            id: 0,
+            name: name.to_string(),
+            project: "acme".to_string(),
+            description: format!("{} meeting", name),
+            tags: vec!["meetings".to_string()],
+            duration,
+        })
+        .unwrap();
+    }
+    let mut app = app(&db);
+    send(&mut app, &db, Message::SwitchScreen(Screen::Entries));
+
+    send(&mut app, &db, Message::OpenTemplatePicker);
+    assert!(app.show_template_picker);
+    assert_eq!(app.templates.len(), 2);
+    send(&mut app, &db, Message::TemplatePickerNext);
+    send(&mut app, &db, Message::TemplatePickerPrevious);
+    send(&mut app, &db, Message::ApplyTemplatePicker);
+    assert!(!app.show_template_picker);
+    assert_eq!(app.edit_project_input, "acme");
+    assert_eq!(app.edit_description_input, "standup meeting");
+
+    send(&mut app, &db, Message::SaveEditEntry);
+    let entries = db.list(None).unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(
+        entries[0].end.unwrap() - entries[0].start,
+        Duration::minutes(15)
+    );
+    assert_eq!(db.entry_tags(entries[0].id).unwrap(), vec!["meetings"]);
+    assert!(app.new_entry_tags.is_empty());
+}
+
+#[test]
+fn entries_can_be_flagged_for_review_and_listed_alone() {
+    let db = test_db();
+    let monday = day(2024, 6, 3);
+    let imported = insert(&db, finished("acme", at(monday, 9), 1.0));
+    let typed = insert(&db, finished("acme", at(monday, 11), 1.0));
+    db.flag_for_review(&[imported.id], ReviewReason::Imported)
+        .unwrap();
+    let mut app = app(&db);
+    send(&mut app, &db, Message::SwitchScreen(Screen::Entries));
+    send(&mut app, &db, Message::ToggleFlaggedOnly);
+    let ids: Vec<i64> = app.entries.iter().map(|e| e.id).collect();
+    assert_eq!(ids, [imported.id]);
+
+    send(&mut app, &db, Message::ToggleReviewFlag(typed.id));
+    assert_eq!(
+        db.review_flags().unwrap().get(&typed.id),
+        Some(&vec![ReviewReason::Manual])
+    );
+    assert_eq!(app.entries.len(), 2);
+
+    // Toggling again marks it reviewed
+    send(&mut app, &db, Message::ToggleReviewFlag(imported.id));
+    send(&mut app, &db, Message::ToggleReviewFlag(typed.id));
+    assert!(db.review_flags().unwrap().is_empty());
+    assert!(app.entries.is_empty());
+}
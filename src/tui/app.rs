@@ -1,9 +1,22 @@
-use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, TimeZone, Timelike, Utc};
-use std::collections::HashMap;
+use chrono::{DateTime, Datelike, Duration, Local, Months, NaiveDate, TimeZone, Timelike, Utc};
+use std::collections::{HashMap, HashSet};
+use std::time::Instant;
 
+use crate::attachments;
+use crate::billing::{Bill, ProjectRate};
 use crate::db::Db;
-use crate::invoice::{InvoiceParams, ProjectRate, write_invoice};
-use crate::models::{Client, Entry, InvoiceSettings, PomodoroConfig, Project};
+use crate::email;
+use crate::invoice::{
+    self, ClientMatch, InvoiceParams, InvoiceSnapshot, detect_client, expenses_for_invoice,
+    regenerate_invoice, write_invoice,
+};
+use crate::models::{
+    Attachment, BusinessProfile, CapUsage, Client, DEFAULT_DESCRIPTION, DEFAULT_PROFILE_ID,
+    EmailSettings, Entry, EntryStatus, EntryTemplate, Expense, Invoice, InvoiceReminder,
+    InvoiceSettings, Money, OvertimeRates, PomodoroConfig, Project, ProjectTotals, ReviewReason,
+    TAG_COLORS, TargetPace, TimeOff, TimerSettings, format_short_duration, normalize_currency,
+    normalize_location, normalize_tag, validate_rate,
+};
 use crate::notification;
 
 /// The active screen/view in the TUI
@@ -17,6 +30,59 @@ pub enum Screen {
     Pomodoro,
     Clients,
     Settings,
+    Tags,
+    Review,
+}
+
+impl Screen {
+    pub const ALL: [Screen; 9] = [
+        Screen::Timer,
+        Screen::Entries,
+        Screen::Invoice,
+        Screen::Projects,
+        Screen::Pomodoro,
+        Screen::Clients,
+        Screen::Settings,
+        Screen::Tags,
+        Screen::Review,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Screen::Timer => "timer",
+            Screen::Entries => "entries",
+            Screen::Invoice => "invoice",
+            Screen::Projects => "projects",
+            Screen::Pomodoro => "pomodoro",
+            Screen::Clients => "clients",
+            Screen::Settings => "settings",
+            Screen::Tags => "tags",
+            Screen::Review => "review",
+        }
+    }
+}
+
+impl std::fmt::Display for Screen {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for Screen {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().to_ascii_lowercase();
+        Screen::ALL
+            .into_iter()
+            .find(|screen| screen.as_str() == s)
+            .ok_or_else(|| {
+                format!(
+                    "Unknown screen '{}' (expected timer, entries, invoice, projects, pomodoro, clients, settings, tags or review)",
+                    s
+                )
+            })
+    }
 }
 
 /// Running state of the application
@@ -37,6 +103,65 @@ pub enum InvoiceMode {
     SelectEntries,
 }
 
+/// Date filter for picking entries in SelectEntries mode (by end date)
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum InvoiceDateFilter {
+    #[default]
+    All,
+    ThisWeek,
+    ThisMonth,
+    LastMonth,
+}
+
+impl InvoiceDateFilter {
+    pub fn label(&self) -> &'static str {
+        match self {
+            InvoiceDateFilter::All => "All dates",
+            InvoiceDateFilter::ThisWeek => "This week",
+            InvoiceDateFilter::ThisMonth => "This month",
+            InvoiceDateFilter::LastMonth => "Last month",
+        }
+    }
+
+    fn next(&self) -> Self {
+        match self {
+            InvoiceDateFilter::All => InvoiceDateFilter::ThisWeek,
+            InvoiceDateFilter::ThisWeek => InvoiceDateFilter::ThisMonth,
+            InvoiceDateFilter::ThisMonth => InvoiceDateFilter::LastMonth,
+            InvoiceDateFilter::LastMonth => InvoiceDateFilter::All,
+        }
+    }
+
+    /// Days covered (start inclusive, end exclusive), or `None` for all dates
+    fn range(&self, today: NaiveDate) -> Option<(NaiveDate, NaiveDate)> {
+        let first_of_month = today.with_day(1)?;
+        match self {
+            InvoiceDateFilter::All => None,
+            InvoiceDateFilter::ThisWeek => {
+                let monday = today - Duration::days(today.weekday().num_days_from_monday() as i64);
+                Some((monday, monday + Duration::days(7)))
+            }
+            InvoiceDateFilter::ThisMonth => Some((
+                first_of_month,
+                first_of_month.checked_add_months(Months::new(1))?,
+            )),
+            InvoiceDateFilter::LastMonth => Some((
+                first_of_month.checked_sub_months(Months::new(1))?,
+                first_of_month,
+            )),
+        }
+    }
+}
+
+/// How much of the entry history the entries screen shows at once
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum EntriesPeriod {
+    #[default]
+    All,
+    Day,
+    Week,
+}
+
 /// Input mode for text entry
 #[derive(Debug, Clone, PartialEq, Default)]
 pub enum InputMode {
@@ -44,11 +169,13 @@ pub enum InputMode {
     Normal,
     EditingProject,
     EditingDescription,
+    EditingStartTime,
     // Entry editing modes
     EditEntryProject,
     EditEntryDescription,
     EditEntryStart,
     EditEntryEnd,
+    EditEntryLocation,
     // Project rate editing modes
     EditingRate,
     EditingCurrency,
@@ -61,6 +188,14 @@ pub enum InputMode {
     EditingClient,
     // Invoice settings editing modes
     EditingSettings,
+    // Email settings editing mode
+    EditingEmailSettings,
+    // Tag renaming mode
+    RenamingTag,
+    // Business profile name/numbering dialog
+    EditingProfile,
+    // Weekly review description or tag prompt
+    EditingReview,
 }
 
 /// Which field is selected in the edit entry dialog
@@ -71,6 +206,7 @@ pub enum EditField {
     Description,
     Start,
     End,
+    Location,
 }
 
 /// Pomodoro timer state
@@ -127,6 +263,115 @@ pub enum SettingsField {
     PaymentInstructions,
 }
 
+/// What the weekly review prompt is asking for
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ReviewField {
+    #[default]
+    Description,
+    Tag,
+}
+
+/// Entries longer than this are flagged in the weekly review
+const REVIEW_LONG_SECS: i64 = 10 * 3600;
+
+/// Entries shorter than this are flagged in the weekly review
+const REVIEW_SHORT_SECS: i64 = 5 * 60;
+
+/// How often the pace against the monthly target is worked out again
+const PACE_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// Why the weekly review flags `entry`, if it looks like a forgotten timer or
+/// a stray start/stop. Entries logged by the day are never flagged.
+pub fn review_anomaly(entry: &Entry) -> Option<&'static str> {
+    if entry.days.is_some() {
+        return None;
+    }
+    let secs = (entry.end? - entry.start).num_seconds();
+    if secs > REVIEW_LONG_SECS {
+        Some("very long (over 10h)")
+    } else if secs < REVIEW_SHORT_SECS {
+        Some("very short (under 5 min)")
+    } else {
+        None
+    }
+}
+
+/// Which field is selected in the business profile dialog
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum ProfileField {
+    #[default]
+    Name,
+    FirstNumber,
+}
+
+/// What saving the business profile dialog does
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ProfileDialog {
+    Add,
+    /// Add a profile with a copy of this profile's settings
+    Duplicate(i64),
+    /// Change this profile's name and first invoice number
+    Rename(i64),
+}
+
+/// Which field is selected in the email settings dialog
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum EmailField {
+    #[default]
+    Host,
+    Port,
+    Security,
+    Username,
+    Password,
+    From,
+}
+
+/// Steps of the first-run setup wizard
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OnboardingStep {
+    Welcome,
+    Business,
+    Client,
+    Project,
+    Pomodoro,
+    Done,
+}
+
+impl OnboardingStep {
+    pub fn title(&self) -> &'static str {
+        match self {
+            OnboardingStep::Welcome => "Welcome to Meter",
+            OnboardingStep::Business => "Step 1/4: Your business",
+            OnboardingStep::Client => "Step 2/4: Your first client",
+            OnboardingStep::Project => "Step 3/4: Your first project",
+            OnboardingStep::Pomodoro => "Step 4/4: Pomodoro",
+            OnboardingStep::Done => "All set",
+        }
+    }
+
+    /// Labels of the input fields on this step
+    pub fn fields(&self) -> &'static [&'static str] {
+        match self {
+            OnboardingStep::Business => &["Business name", "Email", "Payment terms"],
+            OnboardingStep::Client => &["Client name", "Contact", "Email"],
+            OnboardingStep::Project => &["Project name", "Hourly rate", "Currency"],
+            OnboardingStep::Pomodoro => &["Enable (y/n)", "Work minutes", "Short break minutes"],
+            OnboardingStep::Welcome | OnboardingStep::Done => &[],
+        }
+    }
+
+    pub fn next(&self) -> Option<OnboardingStep> {
+        match self {
+            OnboardingStep::Welcome => Some(OnboardingStep::Business),
+            OnboardingStep::Business => Some(OnboardingStep::Client),
+            OnboardingStep::Client => Some(OnboardingStep::Project),
+            OnboardingStep::Project => Some(OnboardingStep::Pomodoro),
+            OnboardingStep::Pomodoro => Some(OnboardingStep::Done),
+            OnboardingStep::Done => None,
+        }
+    }
+}
+
 /// Main application state
 #[derive(Debug, Default)]
 pub struct App {
@@ -136,14 +381,45 @@ pub struct App {
 
     // Timer state
     pub active_entry: Option<Entry>,
+    /// Target duration in seconds when the running timer is a countdown
+    pub active_target: Option<i64>,
+    /// Running entry whose project has already been flagged as over its hour cap
+    pub cap_warned_entry: Option<i64>,
+    /// Billable hours this month against the monthly target, if one is set
+    pub target_pace: Option<TargetPace>,
+    /// Hours the pace may trail the target before it counts as behind
+    pub target_margin_hours: f64,
+    /// When the pace was last worked out
+    pace_checked: Option<Instant>,
+    /// Day the falling-behind warning was last shown, so it comes once a day
+    pace_warned_day: Option<NaiveDate>,
     pub project_input: String,
     pub description_input: String,
+    /// When a forgotten timer really started, HH:MM today; empty for now
+    pub start_time_input: String,
     pub input_mode: InputMode,
 
     // Entries list state
     pub entries: Vec<Entry>,
     pub selected_entry_index: usize,
-    pub show_only_unbilled: bool,
+    pub status_filter: Option<EntryStatus>,
+    /// Entries are limited to this project when set
+    pub entries_project_filter: Option<String>,
+    /// Only entries flagged for review are listed
+    pub flagged_only: bool,
+    /// Why entries are flagged for review, by entry ID
+    pub review_flags: HashMap<i64, Vec<ReviewReason>>,
+    pub show_project_picker: bool,
+    /// Row of the project picker; 0 is "All projects"
+    pub project_picker_index: usize,
+    pub templates: Vec<EntryTemplate>,
+    pub show_template_picker: bool,
+    pub template_picker_index: usize,
+    pub entries_period: EntriesPeriod,
+    /// Day the entries page is anchored on (defaults to today)
+    pub entries_anchor: Option<NaiveDate>,
+    /// Time off overlapping the day/week shown on the entries screen
+    pub time_off: Vec<TimeOff>,
     pub confirm_delete: Option<i64>,
 
     // Edit entry state
@@ -153,6 +429,9 @@ pub struct App {
     pub edit_description_input: String,
     pub edit_start_input: String,
     pub edit_end_input: String,
+    pub edit_location_input: String,
+    /// Tags from the template a new entry was started from, added when it is saved
+    pub new_entry_tags: Vec<String>,
 
     // Invoice state
     pub invoice_mode: InvoiceMode,
@@ -162,13 +441,32 @@ pub struct App {
     pub selected_entry_ids: Vec<i64>,
     pub invoice_entries: Vec<Entry>,
     pub invoice_select_index: usize,
+    /// SelectEntries filters: only this project / only this date range / only this tag
+    pub invoice_project_filter: Option<String>,
+    pub invoice_date_filter: InvoiceDateFilter,
+    pub invoice_tag_filter: Option<String>,
+    /// Tags of the billed entries, by entry ID
+    pub invoice_entry_tags: HashMap<i64, Vec<String>>,
 
     // UI state
+    /// Opened with `--read-only`: every action that changes data is refused
+    pub read_only: bool,
     pub show_help: bool,
     pub status_message: Option<String>,
 
+    // First-run wizard state
+    pub onboarding: Option<OnboardingStep>,
+    pub onboarding_inputs: Vec<String>,
+    pub onboarding_field: usize,
+    /// Client added during the wizard, linked to the wizard's project
+    pub onboarding_client_id: Option<i64>,
+
     // Projects state
     pub projects: Vec<Project>,
+    /// Hour cap usage for capped projects, keyed by project name
+    pub cap_usage: HashMap<String, CapUsage>,
+    /// Total hours, unbilled time and last activity, keyed by project name
+    pub project_totals: HashMap<String, ProjectTotals>,
     pub selected_project_index: usize,
     pub editing_project_rate: Option<i64>,
     pub rate_input: String,
@@ -176,6 +474,19 @@ pub struct App {
 
     // Project rates cache for invoice
     pub project_rates: HashMap<String, ProjectRate>,
+    /// Profile settings and overtime rates of each client (and of no client),
+    /// for the invoice preview to bill with
+    pub invoice_client_billing: HashMap<Option<i64>, (InvoiceSettings, Option<OvertimeRates>)>,
+    /// Expenses and public holidays, for the invoice preview to bill with
+    pub invoice_expenses: Vec<Expense>,
+    pub invoice_holidays: Vec<TimeOff>,
+
+    // Timer behavior settings
+    pub timer_settings: TimerSettings,
+    /// A just-stopped entry shorter than the minimum duration, awaiting keep/discard
+    pub confirm_discard: Option<Entry>,
+    /// Asking whether to throw away the running timer without saving it
+    pub confirm_cancel_timer: bool,
 
     // Pomodoro state
     pub pomodoro_config: PomodoroConfig,
@@ -214,6 +525,14 @@ pub struct App {
 
     // Invoice settings state
     pub invoice_settings: InvoiceSettings,
+    /// Finished months with uninvoiced hours, shown as a banner
+    pub invoice_reminders: Vec<InvoiceReminder>,
+    /// Issued invoices whose PDF has been moved or deleted
+    pub missing_invoices: Vec<Invoice>,
+    /// Receipts and other stored files, listed in the attachments panel
+    pub attachments: Vec<Attachment>,
+    pub show_attachments: bool,
+    pub attachment_index: usize,
 
     // Settings editing state
     pub editing_settings: bool,
@@ -230,6 +549,50 @@ pub struct App {
     pub settings_payment_terms_input: String,
     pub settings_default_tax_rate_input: String,
     pub settings_payment_instructions_input: String,
+
+    // Weekly review state
+    /// Finished entries from last week, oldest first
+    pub review_entries: Vec<Entry>,
+    pub review_tags: HashMap<i64, Vec<String>>,
+    /// Monday of the week under review
+    pub review_week: Option<NaiveDate>,
+    /// Entry being reviewed; one past the last shows the summary
+    pub review_index: usize,
+    /// Entries changed during the review
+    pub review_edited: HashSet<i64>,
+    pub review_field: ReviewField,
+    pub review_input: String,
+
+    // Business profiles state
+    pub profiles: Vec<BusinessProfile>,
+    /// Profile shown and edited on the settings screen
+    pub selected_profile_index: usize,
+    pub profile_dialog: Option<ProfileDialog>,
+    pub profile_field: ProfileField,
+    pub profile_name_input: String,
+    pub profile_first_number_input: String,
+    pub confirm_delete_profile: Option<i64>,
+
+    // Email settings state
+    pub email_settings: EmailSettings,
+    pub email_field: EmailField,
+    pub email_host_input: String,
+    pub email_port_input: String,
+    pub email_security_input: String,
+    pub email_username_input: String,
+    /// New SMTP password; left empty to keep the one in the keychain
+    pub email_password_input: String,
+    pub email_from_input: String,
+
+    // Tags state
+    /// Tags in use with how many entries have each, alphabetically
+    pub tags: Vec<(String, i64)>,
+    /// Color names from `TAG_COLORS`, by tag
+    pub tag_colors: HashMap<String, String>,
+    pub selected_tag_index: usize,
+    pub renaming_tag: Option<String>,
+    pub tag_rename_input: String,
+    pub confirm_delete_tag: Option<String>,
 }
 
 /// All possible application messages/events
@@ -239,26 +602,60 @@ pub enum Message {
     SwitchScreen(Screen),
     Quit,
 
+    // First-run wizard
+    OnboardingSubmit,
+    OnboardingSkip,
+    OnboardingNextField,
+    OnboardingPrevField,
+    OnboardingInput(char),
+    OnboardingBackspace,
+
     // Timer actions
     StartTimer,
     StopTimer,
+    ConfirmDiscard,
+    CancelDiscard,
+    CancelTimer,
+    ConfirmCancelTimer,
+    KeepTimer,
     UpdateProjectInput(char),
     UpdateDescriptionInput(char),
+    UpdateStartTimeInput(char),
     DeleteProjectChar,
     DeleteDescriptionChar,
+    DeleteStartTimeChar,
 
     // Entry list actions
     SelectNextEntry,
     SelectPreviousEntry,
-    ToggleBilledFilter,
+    CycleStatusFilter,
+    ToggleFlaggedOnly,
+    ToggleReviewFlag(i64),
+    OpenProjectPicker,
+    ProjectPickerNext,
+    ProjectPickerPrevious,
+    ApplyProjectPicker,
+    CloseProjectPicker,
+    OpenTemplatePicker,
+    TemplatePickerNext,
+    TemplatePickerPrevious,
+    ApplyTemplatePicker,
+    CloseTemplatePicker,
+    CycleEntriesPeriod,
+    PrevEntriesPage,
+    NextEntriesPage,
     DeleteEntry(i64),
     ConfirmDelete,
     CancelDelete,
-    MarkEntryBilled(i64),
-    UnbillEntry(i64),
+    SetEntryStatus(i64, EntryStatus),
+    /// Move a finished entry's end by this many minutes
+    AdjustEntryEnd(i64, i64),
 
     // Edit entry actions
     EditEntry(i64),
+    /// Edit the running entry without stopping it
+    AmendActiveEntry,
+    NewEntry,
     EditNextField,
     EditPrevField,
     EditFieldInput(char),
@@ -273,7 +670,17 @@ pub enum Message {
     ToggleEntrySelection(i64),
     NextInvoiceEntry,
     PrevInvoiceEntry,
+    CycleInvoiceProjectFilter,
+    CycleInvoiceDateFilter,
+    CycleInvoiceTagFilter,
+    SelectAllInvoiceEntries,
+    SelectNoInvoiceEntries,
     GenerateInvoice,
+    RegenerateMissingInvoices,
+    ToggleAttachments,
+    NextAttachment,
+    PrevAttachment,
+    OpenAttachment,
 
     // Input mode
     EnterInputMode(InputMode),
@@ -300,6 +707,7 @@ pub enum Message {
     SaveProjectRate,
     CancelEditRate,
     ClearProjectRate(i64),
+    CycleProjectClient(i64),
 
     // Pomodoro actions
     TogglePomodoroMode,
@@ -320,6 +728,7 @@ pub enum Message {
     SelectPreviousClient,
     SelectInvoiceClient(Option<i64>),
     CycleInvoiceClient,
+    ToggleClientArchived(i64),
 
     // Client editing actions
     AddClient,
@@ -342,20 +751,150 @@ pub enum Message {
     SettingsFieldBackspace,
     SaveSettings,
     CancelEditSettings,
+
+    // Weekly review actions
+    RefreshReview,
+    ReviewNext,
+    ReviewPrevious,
+    ReviewEditDescription,
+    ReviewAddTag,
+    ReviewInput(char),
+    ReviewBackspace,
+    SaveReviewInput,
+    CancelReviewInput,
+    ReviewCycleClient,
+    ReviewCycleStatus,
+    ReviewMarkChecked,
+
+    // Business profile actions
+    SelectNextProfile,
+    SelectPreviousProfile,
+    AddProfile,
+    DuplicateProfile(i64),
+    RenameProfile(i64),
+    ProfileNextField,
+    ProfileFieldInput(char),
+    ProfileFieldBackspace,
+    SaveProfile,
+    CancelEditProfile,
+    DeleteProfile(i64),
+    ConfirmDeleteProfile,
+    CancelDeleteProfile,
+
+    // Email settings actions
+    EditEmailSettings,
+    EmailNextField,
+    EmailPrevField,
+    EmailFieldInput(char),
+    EmailFieldBackspace,
+    SaveEmailSettings,
+    CancelEditEmailSettings,
+    SendTestEmail,
+
+    // Tag actions
+    RefreshTags,
+    SelectNextTag,
+    SelectPreviousTag,
+    RenameTag(String),
+    TagRenameInput(char),
+    TagRenameBackspace,
+    SaveTagRename,
+    CancelTagRename,
+    CycleTagColor(String),
+    DeleteTag(String),
+    ConfirmDeleteTag,
+    CancelDeleteTag,
+}
+
+impl Message {
+    /// Whether handling this message writes to the database, or opens a dialog
+    /// or input field whose only purpose is to do so
+    pub fn changes_data(&self) -> bool {
+        matches!(
+            self,
+            Message::OnboardingSubmit
+                | Message::StartTimer
+                | Message::StopTimer
+                | Message::ConfirmDiscard
+                | Message::CancelDiscard
+                | Message::CancelTimer
+                | Message::ConfirmCancelTimer
+                | Message::DeleteEntry(_)
+                | Message::ConfirmDelete
+                | Message::SetEntryStatus(..)
+                | Message::ToggleReviewFlag(_)
+                | Message::AdjustEntryEnd(..)
+                | Message::EditEntry(_)
+                | Message::AmendActiveEntry
+                | Message::NewEntry
+                | Message::OpenTemplatePicker
+                | Message::ApplyTemplatePicker
+                | Message::SaveEditEntry
+                | Message::GenerateInvoice
+                | Message::RegenerateMissingInvoices
+                | Message::EnterInputMode(_)
+                | Message::EditProjectRate(_)
+                | Message::SaveProjectRate
+                | Message::ClearProjectRate(_)
+                | Message::CycleProjectClient(_)
+                | Message::TogglePomodoroMode
+                | Message::AcknowledgePomodoro
+                | Message::SavePomodoroConfig
+                | Message::ToggleClientArchived(_)
+                | Message::AddClient
+                | Message::EditClient(_)
+                | Message::DeleteClient(_)
+                | Message::ConfirmDeleteClient
+                | Message::SaveClient
+                | Message::EditSettings
+                | Message::SaveSettings
+                | Message::ReviewEditDescription
+                | Message::ReviewAddTag
+                | Message::SaveReviewInput
+                | Message::ReviewCycleClient
+                | Message::ReviewCycleStatus
+                | Message::ReviewMarkChecked
+                | Message::AddProfile
+                | Message::DuplicateProfile(_)
+                | Message::RenameProfile(_)
+                | Message::SaveProfile
+                | Message::DeleteProfile(_)
+                | Message::ConfirmDeleteProfile
+                | Message::EditEmailSettings
+                | Message::SaveEmailSettings
+                | Message::RenameTag(_)
+                | Message::SaveTagRename
+                | Message::CycleTagColor(_)
+                | Message::DeleteTag(_)
+                | Message::ConfirmDeleteTag
+        )
+    }
 }
 
 impl App {
     pub fn new(db: &Db) -> Self {
         let mut app = App::default();
-        app.description_input = "Work session".to_string();
+        app.read_only = db.is_read_only();
+        app.timer_settings = db.get_timer_settings().unwrap_or_default();
+        app.description_input = app.blank_description_input();
         app.refresh_entries(db);
         app.refresh_active_timer(db);
         app.refresh_pomodoro_config(db);
         app.refresh_clients(db);
         app.refresh_invoice_settings(db);
+        app.refresh_profiles(db);
+        app.refresh_email_settings(db);
+        app.refresh_invoice_reminders(db);
+        app.check_target_pace(db);
+
+        // Walk a brand-new user through setup instead of leaving every setting blank
+        if !app.read_only && db.is_fresh().unwrap_or(false) {
+            app.set_onboarding_step(OnboardingStep::Welcome);
+        }
 
-        // If there's an active timer and Pomodoro is enabled, set state to Working
-        if app.active_entry.is_some() && app.pomodoro_config.enabled {
+        // If there's an active timer and Pomodoro is enabled, set state to Working.
+        // Not when read-only, since the end of a work period stops the timer.
+        if !app.read_only && app.active_entry.is_some() && app.pomodoro_config.enabled {
             app.pomodoro_state = PomodoroState::Working;
             app.pomodoro_interval_start = app.active_entry.as_ref().map(|e| e.start);
         }
@@ -365,22 +904,40 @@ impl App {
 
     /// Core update function
     pub fn update(&mut self, msg: Message, db: &Db) -> Option<Message> {
+        if self.read_only && msg.changes_data() {
+            self.status_message = Some("Read-only mode: changes are disabled".to_string());
+            return None;
+        }
+
         match msg {
             // Navigation
             Message::SwitchScreen(screen) => {
                 self.current_screen = screen.clone();
                 self.input_mode = InputMode::Normal;
                 self.confirm_delete = None;
+                self.show_project_picker = false;
+                self.show_template_picker = false;
+                self.refresh_invoice_reminders(db);
                 if screen == Screen::Invoice {
                     self.refresh_invoice_entries(db);
                 }
                 if screen == Screen::Projects {
-                    self.projects = db.list_projects().unwrap_or_default();
+                    self.refresh_projects(db);
+                    self.refresh_clients(db);
                 }
                 if screen == Screen::Pomodoro {
                     self.refresh_pomodoro_config(db);
                     self.load_pomodoro_inputs();
                 }
+                if screen == Screen::Tags {
+                    self.refresh_tags(db);
+                }
+                if screen == Screen::Settings {
+                    self.refresh_profiles(db);
+                }
+                if screen == Screen::Review {
+                    self.refresh_review(db);
+                }
                 None
             }
             Message::Quit => {
@@ -391,23 +948,68 @@ impl App {
             // Timer actions
             Message::StartTimer => {
                 if !self.project_input.is_empty() && self.active_entry.is_none() {
-                    if db
-                        .start_timer(&self.project_input, &self.description_input)
-                        .is_ok()
-                    {
+                    let start = if self.start_time_input.trim().is_empty() {
+                        Utc::now()
+                    } else {
+                        match parse_start_time(&self.start_time_input) {
+                            Some(start) if start <= Utc::now() => start,
+                            Some(_) => {
+                                self.status_message =
+                                    Some("A timer can't start in the future".to_string());
+                                return None;
+                            }
+                            None => {
+                                self.status_message = Some(
+                                    "Invalid start time (use HH:MM or YYYY-MM-DD HH:MM)"
+                                        .to_string(),
+                                );
+                                return None;
+                            }
+                        }
+                    };
+                    if let Ok(Some(previous)) = db.entry_ending_after(start) {
+                        self.status_message = Some(format!(
+                            "That overlaps the previous entry for '{}', which ended at {}",
+                            previous.project,
+                            previous
+                                .end
+                                .unwrap_or(previous.start)
+                                .with_timezone(&Local)
+                                .format("%H:%M")
+                        ));
+                        return None;
+                    }
+                    let description = if self.description_input.trim().is_empty() {
+                        db.default_description(&self.project_input)
+                            .unwrap_or_else(|_| DEFAULT_DESCRIPTION.to_string())
+                    } else {
+                        self.description_input.clone()
+                    };
+                    if let Ok(entry) = db.start_timer_at(&self.project_input, &description, start) {
                         // Store project info for Pomodoro resume
                         self.pomodoro_last_project = Some(self.project_input.clone());
-                        self.pomodoro_last_description = Some(self.description_input.clone());
+                        self.pomodoro_last_description = Some(description);
 
                         self.project_input.clear();
-                        self.description_input = "Work session".to_string();
+                        self.description_input = self.blank_description_input();
+                        self.start_time_input.clear();
                         self.status_message = Some("Timer started".to_string());
+                        if let Ok(Some(usage)) = db.project_cap_usage(&entry.project, Utc::now())
+                            && usage.is_reached()
+                        {
+                            self.cap_warned_entry = Some(entry.id);
+                            self.status_message = Some(format!(
+                                "Timer started - '{}' is already at its hour cap ({})",
+                                entry.project,
+                                usage.summary()
+                            ));
+                        }
                         self.input_mode = InputMode::Normal;
 
                         // If Pomodoro enabled, set state to Working
                         if self.pomodoro_config.enabled {
                             self.pomodoro_state = PomodoroState::Working;
-                            self.pomodoro_interval_start = Some(Utc::now());
+                            self.pomodoro_interval_start = Some(start);
                         }
 
                         return Some(Message::RefreshActiveTimer);
@@ -417,10 +1019,24 @@ impl App {
             }
             Message::StopTimer => {
                 if self.active_entry.is_some() {
-                    if db.stop_active_timer().is_ok() {
+                    if let Ok(stopped) = db.stop_active_timer() {
                         self.active_entry = None;
                         self.status_message = Some("Timer stopped".to_string());
 
+                        // Short entries are usually noise: drop them or ask first
+                        self.timer_settings = db.get_timer_settings().unwrap_or_default();
+                        if let Some(entry) = stopped {
+                            if !self.timer_settings.is_too_short(&entry) {
+                                self.split_if_enabled(entry.id, db);
+                            } else if self.timer_settings.discard_short_entries {
+                                let _ = db.delete(entry.id);
+                                self.status_message =
+                                    Some(format!("Discarded {}s entry", entry_seconds(&entry)));
+                            } else {
+                                self.confirm_discard = Some(entry);
+                            }
+                        }
+
                         // Reset Pomodoro state
                         self.pomodoro_state = PomodoroState::Idle;
                         self.pomodoro_interval_start = None;
@@ -431,6 +1047,34 @@ impl App {
                 }
                 None
             }
+            Message::CancelTimer => {
+                if self.active_entry.is_some() {
+                    self.confirm_cancel_timer = true;
+                }
+                None
+            }
+            Message::ConfirmCancelTimer => {
+                self.confirm_cancel_timer = false;
+                if let Ok(Some(entry)) = db.cancel_active_timer() {
+                    self.active_entry = None;
+                    self.status_message = Some(format!(
+                        "Discarded the running timer for '{}'",
+                        entry.project
+                    ));
+
+                    // Reset Pomodoro state
+                    self.pomodoro_state = PomodoroState::Idle;
+                    self.pomodoro_interval_start = None;
+                    self.pomodoro_cycles_completed = 0;
+
+                    return Some(Message::RefreshEntries);
+                }
+                None
+            }
+            Message::KeepTimer => {
+                self.confirm_cancel_timer = false;
+                None
+            }
             Message::UpdateProjectInput(c) => {
                 self.project_input.push(c);
                 None
@@ -443,10 +1087,18 @@ impl App {
                 self.project_input.pop();
                 None
             }
+            Message::UpdateStartTimeInput(c) => {
+                self.start_time_input.push(c);
+                None
+            }
             Message::DeleteDescriptionChar => {
                 self.description_input.pop();
                 None
             }
+            Message::DeleteStartTimeChar => {
+                self.start_time_input.pop();
+                None
+            }
 
             // Entry navigation
             Message::SelectNextEntry => {
@@ -460,11 +1112,206 @@ impl App {
                 self.selected_entry_index = self.selected_entry_index.saturating_sub(1);
                 None
             }
-            Message::ToggleBilledFilter => {
-                self.show_only_unbilled = !self.show_only_unbilled;
+            Message::CycleStatusFilter => {
+                // All -> open -> submitted -> billed -> invoiced -> paid -> All
+                self.status_filter = match self.status_filter {
+                    None => Some(EntryStatus::ALL[0]),
+                    Some(current) => EntryStatus::ALL
+                        .iter()
+                        .position(|s| *s == current)
+                        .and_then(|i| EntryStatus::ALL.get(i + 1).copied()),
+                };
+                self.selected_entry_index = 0;
+                Some(Message::RefreshEntries)
+            }
+            Message::ToggleFlaggedOnly => {
+                self.flagged_only = !self.flagged_only;
+                self.selected_entry_index = 0;
+                Some(Message::RefreshEntries)
+            }
+            Message::ToggleReviewFlag(id) => {
+                let result = if self.review_flags.contains_key(&id) {
+                    db.clear_review_flags(id)
+                        .map(|_| format!("Entry {} marked reviewed", id))
+                } else {
+                    db.flag_for_review(&[id], ReviewReason::Manual)
+                        .map(|_| format!("Entry {} flagged for review", id))
+                };
+                self.status_message = Some(match result {
+                    Ok(message) => message,
+                    Err(e) => format!("Failed to update entry: {}", e),
+                });
+                Some(Message::RefreshEntries)
+            }
+            Message::OpenProjectPicker => {
+                self.refresh_projects(db);
+                self.project_picker_index = self
+                    .entries_project_filter
+                    .as_ref()
+                    .and_then(|name| self.projects.iter().position(|p| &p.name == name))
+                    .map_or(0, |i| i + 1);
+                self.show_project_picker = true;
+                None
+            }
+            Message::ProjectPickerNext => {
+                // "All projects" plus one row per project
+                self.project_picker_index =
+                    (self.project_picker_index + 1).min(self.projects.len());
+                None
+            }
+            Message::ProjectPickerPrevious => {
+                self.project_picker_index = self.project_picker_index.saturating_sub(1);
+                None
+            }
+            Message::ApplyProjectPicker => {
+                self.entries_project_filter = self
+                    .project_picker_index
+                    .checked_sub(1)
+                    .and_then(|i| self.projects.get(i))
+                    .map(|p| p.name.clone());
+                self.show_project_picker = false;
                 self.selected_entry_index = 0;
                 Some(Message::RefreshEntries)
             }
+            Message::CloseProjectPicker => {
+                self.show_project_picker = false;
+                None
+            }
+            Message::OpenTemplatePicker => {
+                self.templates = db.list_templates().unwrap_or_default();
+                if self.templates.is_empty() {
+                    self.status_message =
+                        Some("No templates yet: add one with `meter template add`".to_string());
+                    return None;
+                }
+                self.template_picker_index = 0;
+                self.show_template_picker = true;
+                None
+            }
+            Message::TemplatePickerNext => {
+                if self.template_picker_index + 1 < self.templates.len() {
+                    self.template_picker_index += 1;
+                }
+                None
+            }
+            Message::TemplatePickerPrevious => {
+                self.template_picker_index = self.template_picker_index.saturating_sub(1);
+                None
+            }
+            Message::ApplyTemplatePicker => {
+                self.show_template_picker = false;
+                // The new entry dialog, filled in and ending now, to check before saving
+                let template = self.templates.get(self.template_picker_index)?.clone();
+                let now = Utc::now();
+                let start = now - Duration::seconds((template.duration * 3600.0).round() as i64);
+                self.open_new_entry(template.project, template.description, start, now);
+                self.new_entry_tags = template.tags;
+                None
+            }
+            Message::CloseTemplatePicker => {
+                self.show_template_picker = false;
+                None
+            }
+
+            // Day/week paging
+            Message::CycleEntriesPeriod => {
+                self.entries_period = match self.entries_period {
+                    EntriesPeriod::All => EntriesPeriod::Day,
+                    EntriesPeriod::Day => EntriesPeriod::Week,
+                    EntriesPeriod::Week => EntriesPeriod::All,
+                };
+                self.selected_entry_index = 0;
+                Some(Message::RefreshEntries)
+            }
+            Message::PrevEntriesPage | Message::NextEntriesPage => {
+                if self.entries_period == EntriesPeriod::All {
+                    // Paging starts from today's page
+                    self.entries_period = EntriesPeriod::Day;
+                    self.entries_anchor = None;
+                } else {
+                    let step = match self.entries_period {
+                        EntriesPeriod::Week => 7,
+                        _ => 1,
+                    };
+                    let step = if matches!(msg, Message::PrevEntriesPage) {
+                        -step
+                    } else {
+                        step
+                    };
+                    let anchor = self
+                        .entries_anchor
+                        .unwrap_or_else(|| Local::now().date_naive());
+                    self.entries_anchor = Some(anchor + Duration::days(step));
+                }
+                self.selected_entry_index = 0;
+                Some(Message::RefreshEntries)
+            }
+
+            // First-run wizard
+            Message::OnboardingSubmit => {
+                let step = self.onboarding?;
+                if let Err(e) = self.save_onboarding_step(step, db) {
+                    // Stay on the step so the input can be corrected
+                    self.status_message = Some(e);
+                    return None;
+                }
+                self.advance_onboarding(step);
+                None
+            }
+            Message::OnboardingSkip => {
+                match self.onboarding {
+                    // Skipping the welcome screen skips the whole wizard
+                    Some(OnboardingStep::Welcome) => self.onboarding = None,
+                    Some(step) => self.advance_onboarding(step),
+                    None => {}
+                }
+                None
+            }
+            Message::OnboardingNextField => {
+                if !self.onboarding_inputs.is_empty() {
+                    self.onboarding_field =
+                        (self.onboarding_field + 1) % self.onboarding_inputs.len();
+                }
+                None
+            }
+            Message::OnboardingPrevField => {
+                if !self.onboarding_inputs.is_empty() {
+                    let len = self.onboarding_inputs.len();
+                    self.onboarding_field = (self.onboarding_field + len - 1) % len;
+                }
+                None
+            }
+            Message::OnboardingInput(c) => {
+                if let Some(input) = self.onboarding_inputs.get_mut(self.onboarding_field) {
+                    input.push(c);
+                }
+                None
+            }
+            Message::OnboardingBackspace => {
+                if let Some(input) = self.onboarding_inputs.get_mut(self.onboarding_field) {
+                    input.pop();
+                }
+                None
+            }
+
+            // Short entry discard prompt
+            Message::ConfirmDiscard => {
+                if let Some(entry) = self.confirm_discard.take()
+                    && db.delete(entry.id).is_ok()
+                {
+                    self.status_message =
+                        Some(format!("Discarded {}s entry", entry_seconds(&entry)));
+                    return Some(Message::RefreshEntries);
+                }
+                None
+            }
+            Message::CancelDiscard => {
+                if let Some(entry) = self.confirm_discard.take() {
+                    self.split_if_enabled(entry.id, db);
+                    return Some(Message::RefreshEntries);
+                }
+                None
+            }
 
             // Delete flow
             Message::DeleteEntry(id) => {
@@ -490,54 +1337,56 @@ impl App {
 
             // Edit entry
             Message::EditEntry(id) => {
-                if let Some(entry) = self.entries.iter().find(|e| e.id == id) {
-                    self.editing_entry = Some(entry.clone());
-                    self.edit_field = EditField::Project;
-                    self.edit_project_input = entry.project.clone();
-                    self.edit_description_input = entry.description.clone();
-
-                    let start_local = Local.from_utc_datetime(&entry.start.naive_utc());
-                    self.edit_start_input = start_local.format("%Y-%m-%d %H:%M").to_string();
-
-                    self.edit_end_input = match entry.end {
-                        Some(end) => {
-                            let end_local = Local.from_utc_datetime(&end.naive_utc());
-                            end_local.format("%Y-%m-%d %H:%M").to_string()
-                        }
-                        None => String::new(),
-                    };
-
-                    self.input_mode = InputMode::EditEntryProject;
+                if let Some(entry) = self.entries.iter().find(|e| e.id == id).cloned() {
+                    self.open_edit_entry(entry);
+                }
+                None
+            }
+            Message::AmendActiveEntry => {
+                self.refresh_active_timer(db);
+                if let Some(entry) = self.active_entry.clone() {
+                    self.open_edit_entry(entry);
+                } else {
+                    self.status_message = Some("No running timer to amend".to_string());
                 }
                 None
             }
+            Message::NewEntry => {
+                let now = Utc::now();
+                self.open_new_entry(String::new(), String::new(), now - Duration::hours(1), now);
+                None
+            }
             Message::EditNextField => {
                 self.edit_field = match self.edit_field {
                     EditField::Project => EditField::Description,
                     EditField::Description => EditField::Start,
                     EditField::Start => EditField::End,
-                    EditField::End => EditField::Project,
+                    EditField::End => EditField::Location,
+                    EditField::Location => EditField::Project,
                 };
                 self.input_mode = match self.edit_field {
                     EditField::Project => InputMode::EditEntryProject,
                     EditField::Description => InputMode::EditEntryDescription,
                     EditField::Start => InputMode::EditEntryStart,
                     EditField::End => InputMode::EditEntryEnd,
+                    EditField::Location => InputMode::EditEntryLocation,
                 };
                 None
             }
             Message::EditPrevField => {
                 self.edit_field = match self.edit_field {
-                    EditField::Project => EditField::End,
+                    EditField::Project => EditField::Location,
                     EditField::Description => EditField::Project,
                     EditField::Start => EditField::Description,
                     EditField::End => EditField::Start,
+                    EditField::Location => EditField::End,
                 };
                 self.input_mode = match self.edit_field {
                     EditField::Project => InputMode::EditEntryProject,
                     EditField::Description => InputMode::EditEntryDescription,
                     EditField::Start => InputMode::EditEntryStart,
                     EditField::End => InputMode::EditEntryEnd,
+                    EditField::Location => InputMode::EditEntryLocation,
                 };
                 None
             }
@@ -547,6 +1396,7 @@ impl App {
                     EditField::Description => self.edit_description_input.push(c),
                     EditField::Start => self.edit_start_input.push(c),
                     EditField::End => self.edit_end_input.push(c),
+                    EditField::Location => self.edit_location_input.push(c),
                 }
                 None
             }
@@ -564,6 +1414,9 @@ impl App {
                     EditField::End => {
                         self.edit_end_input.pop();
                     }
+                    EditField::Location => {
+                        self.edit_location_input.pop();
+                    }
                 }
                 None
             }
@@ -571,61 +1424,138 @@ impl App {
                 if let Some(mut entry) = self.editing_entry.take() {
                     entry.project = self.edit_project_input.clone();
                     entry.description = self.edit_description_input.clone();
+                    entry.location = normalize_location(&self.edit_location_input);
 
                     // Parse start time
-                    if let Ok(parsed) = chrono::NaiveDateTime::parse_from_str(
-                        &self.edit_start_input,
-                        "%Y-%m-%d %H:%M",
-                    ) {
-                        entry.start = Local
-                            .from_local_datetime(&parsed)
-                            .single()
-                            .map(|dt| dt.with_timezone(&Utc))
-                            .unwrap_or(entry.start);
+                    if let Some(start) = parse_edit_time(&self.edit_start_input) {
+                        entry.start = start;
                     }
 
                     // Parse end time
                     if self.edit_end_input.is_empty() {
                         entry.end = None;
-                    } else if let Ok(parsed) = chrono::NaiveDateTime::parse_from_str(
-                        &self.edit_end_input,
-                        "%Y-%m-%d %H:%M",
-                    ) {
-                        entry.end = Local
-                            .from_local_datetime(&parsed)
-                            .single()
-                            .map(|dt| dt.with_timezone(&Utc));
-                    }
-
-                    if db.update_entry(&entry).is_ok() {
-                        self.status_message = Some(format!("Entry {} updated", entry.id));
+                    } else if let Some(end) = parse_edit_time(&self.edit_end_input) {
+                        entry.end = Some(end);
+                    }
+
+                    // A running entry can't have started in the future
+                    if entry.id != 0 && entry.end.is_none() && entry.start > Utc::now() {
+                        self.status_message =
+                            Some("A running entry can't start in the future".to_string());
+                        self.editing_entry = Some(entry);
+                        return None;
+                    }
+
+                    if entry.id == 0 {
+                        // Quick-add: keep the dialog open until the entry is valid
+                        let valid = !entry.project.trim().is_empty()
+                            && entry.end.is_some_and(|end| end > entry.start);
+                        if !valid {
+                            self.status_message = Some(
+                                "New entries need a project and an end after the start".to_string(),
+                            );
+                            self.editing_entry = Some(entry);
+                            return None;
+                        }
+                        entry.project = entry.project.trim().to_string();
+                        if entry.description.trim().is_empty() {
+                            entry.description = DEFAULT_DESCRIPTION.to_string();
+                        }
+                        match db.insert(&entry) {
+                            Ok(id) => {
+                                let _ = db.get_or_create_project(&entry.project);
+                                if !self.new_entry_tags.is_empty() {
+                                    let _ = db.add_entry_tags(id, &self.new_entry_tags);
+                                }
+                                self.status_message = Some(format!("Entry {} added", id));
+                                self.timer_settings = db.get_timer_settings().unwrap_or_default();
+                                self.split_if_enabled(id, db);
+                            }
+                            Err(_) => {
+                                self.status_message = Some("Failed to add entry".to_string());
+                            }
+                        }
+                    } else if db.update_entry(&entry).is_ok() {
+                        let _ = db.get_or_create_project(&entry.project);
+                        self.status_message = Some(if entry.end.is_none() {
+                            format!("Running entry {} amended", entry.id)
+                        } else {
+                            format!("Entry {} updated", entry.id)
+                        });
+                        self.refresh_active_timer(db);
                     } else {
                         self.status_message = Some("Failed to update entry".to_string());
                     }
                 }
                 self.input_mode = InputMode::Normal;
                 self.edit_field = EditField::Project;
+                self.new_entry_tags.clear();
                 Some(Message::RefreshEntries)
             }
             Message::CancelEditEntry => {
                 self.editing_entry = None;
+                self.new_entry_tags.clear();
                 self.input_mode = InputMode::Normal;
                 self.edit_field = EditField::Project;
                 None
             }
 
-            // Bill entry
-            Message::MarkEntryBilled(id) => {
-                if db.mark_billed(id).is_ok() {
-                    self.status_message = Some(format!("Entry {} marked as billed", id));
+            // Billing status
+            Message::AdjustEntryEnd(id, minutes) => {
+                let entry = self.entries.iter().find(|e| e.id == id)?;
+                let (Some(end), None) = (entry.end, entry.days) else {
+                    self.status_message = Some(format!(
+                        "Entry {} has no end time to adjust; use [e] to edit it",
+                        id
+                    ));
+                    return None;
+                };
+                let end = end + Duration::minutes(minutes);
+                if end <= entry.start {
+                    self.status_message = Some(format!("Entry {} would end before it starts", id));
+                    return None;
+                }
+                if end > Utc::now() {
+                    self.status_message = Some(format!("Entry {} can't end in the future", id));
+                    return None;
+                }
+                let updated = Entry {
+                    end: Some(end),
+                    ..entry.clone()
+                };
+                if db.update_entry(&updated).is_ok() {
+                    self.status_message = Some(format!(
+                        "Entry {} now ends at {} ({})",
+                        id,
+                        end.with_timezone(&Local).format("%H:%M"),
+                        format_short_duration((end - updated.start).num_seconds())
+                    ));
                     return Some(Message::RefreshEntries);
                 }
+                self.status_message = Some("Failed to update entry".to_string());
                 None
             }
-            Message::UnbillEntry(id) => {
-                if db.unmark_billed(id).is_ok() {
-                    self.status_message = Some(format!("Entry {} unbilled", id));
-                    return Some(Message::RefreshEntries);
+            Message::SetEntryStatus(id, status) => {
+                match db.set_entry_status(id, status) {
+                    Ok(true) => {
+                        self.status_message = Some(format!("Entry {} marked as {}", id, status));
+                        return Some(Message::RefreshEntries);
+                    }
+                    Ok(false) => {
+                        let current = self.entries.iter().find(|e| e.id == id).map(|e| e.status);
+                        self.status_message = Some(match current {
+                            Some(current) => {
+                                format!(
+                                    "Entry {} is {} and can't be marked {}",
+                                    id, current, status
+                                )
+                            }
+                            None => format!("Entry {} not found", id),
+                        });
+                    }
+                    Err(e) => {
+                        self.status_message = Some(format!("Failed to update entry: {}", e));
+                    }
                 }
                 None
             }
@@ -642,9 +1572,10 @@ impl App {
                     };
                 } else {
                     // In select entries mode, navigate entries
-                    if !self.invoice_entries.is_empty() {
+                    let visible = self.visible_invoice_entries().len();
+                    if visible > 0 {
                         self.invoice_select_index =
-                            (self.invoice_select_index + 1).min(self.invoice_entries.len() - 1);
+                            (self.invoice_select_index + 1).min(visible - 1);
                     }
                 }
                 None
@@ -678,9 +1609,9 @@ impl App {
                 None
             }
             Message::NextInvoiceEntry => {
-                if !self.invoice_entries.is_empty() {
-                    self.invoice_select_index =
-                        (self.invoice_select_index + 1).min(self.invoice_entries.len() - 1);
+                let visible = self.visible_invoice_entries().len();
+                if visible > 0 {
+                    self.invoice_select_index = (self.invoice_select_index + 1).min(visible - 1);
                 }
                 None
             }
@@ -696,10 +1627,103 @@ impl App {
                 }
                 None
             }
+            Message::CycleInvoiceProjectFilter => {
+                // All projects -> each project with billed entries -> All projects
+                let mut projects: Vec<&String> =
+                    self.invoice_entries.iter().map(|e| &e.project).collect();
+                projects.sort();
+                projects.dedup();
+                self.invoice_project_filter = match &self.invoice_project_filter {
+                    None => projects.first().map(|p| p.to_string()),
+                    Some(current) => projects
+                        .iter()
+                        .position(|p| *p == current)
+                        .and_then(|i| projects.get(i + 1))
+                        .map(|p| p.to_string()),
+                };
+                self.invoice_select_index = 0;
+                None
+            }
+            Message::CycleInvoiceDateFilter => {
+                self.invoice_date_filter = self.invoice_date_filter.next();
+                self.invoice_select_index = 0;
+                None
+            }
+            Message::CycleInvoiceTagFilter => {
+                // All tags -> each tag on a billed entry -> All tags
+                let mut tags: Vec<&String> = self.invoice_entry_tags.values().flatten().collect();
+                tags.sort();
+                tags.dedup();
+                self.invoice_tag_filter = match &self.invoice_tag_filter {
+                    None => tags.first().map(|t| t.to_string()),
+                    Some(current) => tags
+                        .iter()
+                        .position(|t| *t == current)
+                        .and_then(|i| tags.get(i + 1))
+                        .map(|t| t.to_string()),
+                };
+                self.invoice_select_index = 0;
+                None
+            }
+            Message::SelectAllInvoiceEntries => {
+                let visible: Vec<i64> = self
+                    .visible_invoice_entries()
+                    .iter()
+                    .map(|e| e.id)
+                    .collect();
+                for id in visible {
+                    if !self.selected_entry_ids.contains(&id) {
+                        self.selected_entry_ids.push(id);
+                    }
+                }
+                None
+            }
+            Message::SelectNoInvoiceEntries => {
+                self.selected_entry_ids.clear();
+                None
+            }
             Message::GenerateInvoice => {
                 self.generate_invoice(db);
                 None
             }
+            Message::RegenerateMissingInvoices => {
+                self.regenerate_missing_invoices(db);
+                None
+            }
+            Message::ToggleAttachments => {
+                self.show_attachments = !self.show_attachments;
+                self.attachment_index = 0;
+                None
+            }
+            Message::NextAttachment => {
+                if !self.attachments.is_empty() {
+                    self.attachment_index = (self.attachment_index + 1) % self.attachments.len();
+                }
+                None
+            }
+            Message::PrevAttachment => {
+                if !self.attachments.is_empty() {
+                    self.attachment_index = if self.attachment_index == 0 {
+                        self.attachments.len() - 1
+                    } else {
+                        self.attachment_index - 1
+                    };
+                }
+                None
+            }
+            Message::OpenAttachment => {
+                if let Some(attachment) = self.attachments.get(self.attachment_index) {
+                    self.status_message = Some(if attachment.file_missing() {
+                        format!("Stored file for {} is missing", attachment.name)
+                    } else {
+                        match attachments::open(std::path::Path::new(&attachment.path)) {
+                            Ok(()) => format!("Opened {}", attachment.name),
+                            Err(e) => format!("Failed to open {}: {}", attachment.name, e),
+                        }
+                    });
+                }
+                None
+            }
 
             // Input mode
             Message::EnterInputMode(mode) => {
@@ -728,6 +1752,9 @@ impl App {
                 // Refresh active timer from database to detect external changes
                 // (e.g., timer started/stopped from menu bar)
                 self.refresh_active_timer(db);
+                self.check_hour_cap(db);
+                self.check_timer_target(db);
+                self.check_target_pace(db);
 
                 // Check Pomodoro state transitions
                 if self.pomodoro_config.enabled {
@@ -744,11 +1771,15 @@ impl App {
                                         self.pomodoro_last_description =
                                             Some(entry.description.clone());
                                     }
-                                    let _ = db.stop_active_timer();
+                                    if let Ok(Some(stopped)) = db.stop_active_timer() {
+                                        self.split_if_enabled(stopped.id, db);
+                                    }
                                     self.active_entry = None;
                                     self.pomodoro_state = PomodoroState::WorkComplete;
                                     self.pomodoro_interval_start = None;
-                                    notification::notify_work_complete();
+                                    notification::notify_work_complete(
+                                        &db.get_notification_settings().unwrap_or_default(),
+                                    );
                                     self.status_message = Some(
                                         "Work period complete! Press [Space] to start break"
                                             .to_string(),
@@ -761,7 +1792,9 @@ impl App {
                                     // Break complete
                                     self.pomodoro_state = PomodoroState::BreakComplete;
                                     self.pomodoro_interval_start = None;
-                                    notification::notify_break_complete();
+                                    notification::notify_break_complete(
+                                        &db.get_notification_settings().unwrap_or_default(),
+                                    );
                                     self.status_message = Some(
                                         "Break complete! Press [s] to resume work".to_string(),
                                     );
@@ -787,7 +1820,7 @@ impl App {
 
             // Project rate actions
             Message::RefreshProjects => {
-                self.projects = db.list_projects().unwrap_or_default();
+                self.refresh_projects(db);
                 None
             }
             Message::SelectNextProject => {
@@ -804,10 +1837,7 @@ impl App {
             Message::EditProjectRate(id) => {
                 if let Some(project) = self.projects.iter().find(|p| p.id == id) {
                     self.editing_project_rate = Some(id);
-                    self.rate_input = project
-                        .rate
-                        .map(|r| format!("{:.2}", r))
-                        .unwrap_or_default();
+                    self.rate_input = project.rate.map(|r| r.to_string()).unwrap_or_default();
                     self.currency_input =
                         project.currency.clone().unwrap_or_else(|| "$".to_string());
                     self.input_mode = InputMode::EditingRate;
@@ -835,16 +1865,24 @@ impl App {
             Message::SaveProjectRate => {
                 if let Some(project_id) = self.editing_project_rate.take() {
                     if let Some(project) = self.projects.iter().find(|p| p.id == project_id) {
-                        let rate = self.rate_input.parse::<f64>().ok();
+                        let rate = self.rate_input.parse::<f64>().ok().map(Money::from_f64);
                         let currency = if self.currency_input.is_empty() {
-                            None
+                            Ok(None)
                         } else {
-                            Some(self.currency_input.as_str())
+                            normalize_currency(&self.currency_input).map(Some)
                         };
 
-                        if db.set_project_rate(&project.name, rate, currency).is_ok() {
-                            self.status_message =
-                                Some(format!("Rate updated for '{}'", project.name));
+                        match currency {
+                            Ok(currency) => {
+                                if db
+                                    .set_project_rate(&project.name, rate, currency.as_deref())
+                                    .is_ok()
+                                {
+                                    self.status_message =
+                                        Some(format!("Rate updated for '{}'", project.name));
+                                }
+                            }
+                            Err(e) => self.status_message = Some(e),
                         }
                     }
                 }
@@ -870,6 +1908,29 @@ impl App {
                 None
             }
 
+            Message::CycleProjectClient(id) => {
+                // Cycle through: None -> Client 1 -> Client 2 -> ... -> None
+                if let Some(project) = self.projects.iter().find(|p| p.id == id) {
+                    let active = self.active_clients();
+                    let next_client = match project.client_id {
+                        None => active.first().copied(),
+                        Some(current_id) => active
+                            .iter()
+                            .position(|c| c.id == current_id)
+                            .and_then(|idx| active.get(idx + 1).copied()),
+                    };
+                    let next_id = next_client.map(|c| c.id);
+                    if db.set_project_client(&project.name, next_id).is_ok() {
+                        self.status_message = Some(match next_client {
+                            Some(c) => format!("Linked '{}' to client '{}'", project.name, c.name),
+                            None => format!("Unlinked '{}' from its client", project.name),
+                        });
+                        return Some(Message::RefreshProjects);
+                    }
+                }
+                None
+            }
+
             // Pomodoro actions
             Message::TogglePomodoroMode => {
                 self.pomodoro_config.enabled = !self.pomodoro_config.enabled;
@@ -1071,26 +2132,32 @@ impl App {
             }
             Message::CycleInvoiceClient => {
                 // Cycle through: None -> Client 1 -> Client 2 -> ... -> None
-                if self.clients.is_empty() {
-                    self.selected_invoice_client = None;
-                } else {
-                    match self.selected_invoice_client {
-                        None => {
-                            self.selected_invoice_client = Some(self.clients[0].id);
-                        }
-                        Some(current_id) => {
-                            // Find current index and move to next
-                            if let Some(idx) = self.clients.iter().position(|c| c.id == current_id)
-                            {
-                                if idx + 1 < self.clients.len() {
-                                    self.selected_invoice_client = Some(self.clients[idx + 1].id);
-                                } else {
-                                    self.selected_invoice_client = None;
-                                }
-                            } else {
-                                self.selected_invoice_client = None;
-                            }
+                // Archived clients are skipped
+                let active = self.active_clients();
+                let next = match self.selected_invoice_client {
+                    None => active.first().map(|c| c.id),
+                    Some(current_id) => active
+                        .iter()
+                        .position(|c| c.id == current_id)
+                        .and_then(|idx| active.get(idx + 1))
+                        .map(|c| c.id),
+                };
+                self.selected_invoice_client = next;
+                None
+            }
+            Message::ToggleClientArchived(id) => {
+                if let Some(client) = self.clients.iter().find(|c| c.id == id) {
+                    let archived = !client.archived;
+                    if db.set_client_archived(id, archived).is_ok() {
+                        self.status_message = Some(if archived {
+                            format!("Client '{}' archived", client.name)
+                        } else {
+                            format!("Client '{}' restored", client.name)
+                        });
+                        if archived && self.selected_invoice_client == Some(id) {
+                            self.selected_invoice_client = None;
                         }
+                        return Some(Message::RefreshClients);
                     }
                 }
                 None
@@ -1116,7 +2183,16 @@ impl App {
                 None
             }
             Message::DeleteClient(id) => {
-                self.confirm_delete_client = Some(id);
+                // Clients with invoice history can't be deleted
+                let invoice_count = db.count_client_invoices(id).unwrap_or(0);
+                if invoice_count > 0 {
+                    self.status_message = Some(format!(
+                        "Client has {} invoice(s); archive it instead of deleting",
+                        invoice_count
+                    ));
+                } else {
+                    self.confirm_delete_client = Some(id);
+                }
                 None
             }
             Message::ConfirmDeleteClient => {
@@ -1127,6 +2203,8 @@ impl App {
                             self.selected_client_index -= 1;
                         }
                         return Some(Message::RefreshClients);
+                    } else {
+                        self.status_message = Some(format!("Failed to delete client {}", id));
                     }
                 }
                 None
@@ -1206,7 +2284,7 @@ impl App {
             Message::SaveClient => {
                 let client = Client {
                     id: self.editing_client.as_ref().map(|c| c.id).unwrap_or(0),
-                    name: self.client_name_input.clone(),
+                    name: self.client_name_input.trim().to_string(),
                     contact_person: self.client_contact_input.clone(),
                     address_street: self.client_street_input.clone(),
                     address_city: self.client_city_input.clone(),
@@ -1214,8 +2292,24 @@ impl App {
                     address_postal: self.client_postal_input.clone(),
                     address_country: self.client_country_input.clone(),
                     email: self.client_email_input.clone(),
+                    archived: self.editing_client.as_ref().is_some_and(|c| c.archived),
+                    reminder_day: self.editing_client.as_ref().and_then(|c| c.reminder_day),
+                    profile_id: self.editing_client.as_ref().and_then(|c| c.profile_id),
                 };
 
+                // Keep the dialog open so the user can correct the input
+                if let Err(e) = client.validate() {
+                    self.status_message = Some(e);
+                    return None;
+                }
+                if let Ok(Some(existing)) = db.find_client_by_name(&client.name)
+                    && existing.id != client.id
+                {
+                    self.status_message =
+                        Some(format!("Client '{}' already exists", existing.name));
+                    return None;
+                }
+
                 if self.adding_new_client {
                     if db.add_client(&client).is_ok() {
                         self.status_message = Some(format!("Client '{}' added", client.name));
@@ -1351,6 +2445,7 @@ impl App {
                 None
             }
             Message::SaveSettings => {
+                let shown = self.shown_settings().clone();
                 let settings = InvoiceSettings {
                     business_name: self.settings_business_name_input.clone(),
                     address_street: self.settings_street_input.clone(),
@@ -1364,10 +2459,15 @@ impl App {
                     payment_instructions: self.settings_payment_instructions_input.clone(),
                     default_payment_terms: self.settings_payment_terms_input.clone(),
                     default_tax_rate: self.settings_default_tax_rate_input.parse().unwrap_or(0.0),
+                    ..shown
                 };
 
-                if db.set_invoice_settings(&settings).is_ok() {
-                    self.invoice_settings = settings;
+                if db
+                    .set_profile_settings(self.shown_profile_id(), &settings)
+                    .is_ok()
+                {
+                    self.refresh_invoice_settings(db);
+                    self.refresh_profiles(db);
                     self.status_message = Some("Settings saved".to_string());
                 } else {
                     self.status_message = Some("Failed to save settings".to_string());
@@ -1383,16 +2483,535 @@ impl App {
                 self.load_settings_inputs();
                 None
             }
+
+            // Weekly review actions
+            Message::RefreshReview => {
+                self.refresh_review(db);
+                None
+            }
+            Message::ReviewNext => {
+                if self.review_index < self.review_entries.len() {
+                    self.review_index += 1;
+                }
+                None
+            }
+            Message::ReviewPrevious => {
+                self.review_index = self.review_index.saturating_sub(1);
+                None
+            }
+            Message::ReviewEditDescription => {
+                if let Some(entry) = self.review_entries.get(self.review_index) {
+                    self.review_input = entry.description.clone();
+                    self.review_field = ReviewField::Description;
+                    self.input_mode = InputMode::EditingReview;
+                }
+                None
+            }
+            Message::ReviewAddTag => {
+                if self.review_index < self.review_entries.len() {
+                    self.review_input.clear();
+                    self.review_field = ReviewField::Tag;
+                    self.input_mode = InputMode::EditingReview;
+                }
+                None
+            }
+            Message::ReviewInput(c) => {
+                self.review_input.push(c);
+                None
+            }
+            Message::ReviewBackspace => {
+                self.review_input.pop();
+                None
+            }
+            Message::SaveReviewInput => {
+                let Some(entry) = self.review_entries.get_mut(self.review_index) else {
+                    self.input_mode = InputMode::Normal;
+                    return None;
+                };
+                let input = self.review_input.trim().to_string();
+                match self.review_field {
+                    ReviewField::Description => {
+                        if input.is_empty() {
+                            self.status_message = Some("A description can't be empty".to_string());
+                            return None;
+                        }
+                        let updated = Entry {
+                            description: input,
+                            ..entry.clone()
+                        };
+                        match db.update_entry(&updated) {
+                            Ok(_) => {
+                                self.status_message = Some(format!("Entry {} updated", updated.id));
+                                self.review_edited.insert(updated.id);
+                                *entry = updated;
+                            }
+                            Err(e) => {
+                                self.status_message =
+                                    Some(format!("Failed to update entry: {}", e));
+                            }
+                        }
+                    }
+                    ReviewField::Tag => {
+                        let Some(tag) = normalize_tag(&input) else {
+                            self.input_mode = InputMode::Normal;
+                            return None;
+                        };
+                        match db.add_entry_tags(entry.id, std::slice::from_ref(&tag)) {
+                            Ok(()) => {
+                                let tags = self.review_tags.entry(entry.id).or_default();
+                                if !tags.contains(&tag) {
+                                    tags.push(tag.clone());
+                                    tags.sort();
+                                }
+                                self.status_message =
+                                    Some(format!("Tagged entry {} '{}'", entry.id, tag));
+                                self.review_edited.insert(entry.id);
+                            }
+                            Err(e) => {
+                                self.status_message = Some(format!("Failed to tag entry: {}", e));
+                            }
+                        }
+                    }
+                }
+                self.input_mode = InputMode::Normal;
+                None
+            }
+            Message::CancelReviewInput => {
+                self.input_mode = InputMode::Normal;
+                None
+            }
+            Message::ReviewCycleClient => {
+                let entry = self.review_entries.get(self.review_index)?;
+                match self.projects.iter().find(|p| p.name == entry.project) {
+                    Some(project) => {
+                        self.review_edited.insert(entry.id);
+                        return Some(Message::CycleProjectClient(project.id));
+                    }
+                    None => {
+                        self.status_message =
+                            Some(format!("Project '{}' not found", entry.project));
+                    }
+                }
+                None
+            }
+            Message::ReviewCycleStatus => {
+                let entry = self.review_entries.get_mut(self.review_index)?;
+                // Only the statuses before invoicing can be changed freely
+                let next = match entry.status {
+                    EntryStatus::Open => EntryStatus::Submitted,
+                    EntryStatus::Submitted => EntryStatus::Billed,
+                    EntryStatus::Billed => EntryStatus::Open,
+                    status => {
+                        self.status_message =
+                            Some(format!("Entry {} is already {}", entry.id, status));
+                        return None;
+                    }
+                };
+                match db.set_entry_status(entry.id, next) {
+                    Ok(true) => {
+                        entry.status = next;
+                        self.review_edited.insert(entry.id);
+                        self.status_message =
+                            Some(format!("Entry {} marked as {}", entry.id, next));
+                    }
+                    Ok(false) => {
+                        self.status_message =
+                            Some(format!("Entry {} can't be marked {}", entry.id, next));
+                    }
+                    Err(e) => {
+                        self.status_message = Some(format!("Failed to update entry: {}", e));
+                    }
+                }
+                None
+            }
+            Message::ReviewMarkChecked => {
+                let id = self.review_entries.get(self.review_index)?.id;
+                if !self.review_flags.contains_key(&id) {
+                    self.status_message = Some(format!("Entry {} isn't flagged", id));
+                    return None;
+                }
+                match db.clear_review_flags(id) {
+                    Ok(_) => {
+                        self.review_flags.remove(&id);
+                        self.review_edited.insert(id);
+                        self.status_message = Some(format!("Entry {} marked reviewed", id));
+                    }
+                    Err(e) => {
+                        self.status_message = Some(format!("Failed to update entry: {}", e));
+                    }
+                }
+                None
+            }
+
+            // Business profile actions
+            Message::SelectNextProfile => {
+                if !self.profiles.is_empty() {
+                    self.selected_profile_index =
+                        (self.selected_profile_index + 1).min(self.profiles.len() - 1);
+                }
+                None
+            }
+            Message::SelectPreviousProfile => {
+                self.selected_profile_index = self.selected_profile_index.saturating_sub(1);
+                None
+            }
+            Message::AddProfile => {
+                self.open_profile_dialog(ProfileDialog::Add, String::new(), db);
+                None
+            }
+            Message::DuplicateProfile(id) => {
+                if let Some(profile) = self.profiles.iter().find(|p| p.id == id) {
+                    let name = format!("{} (copy)", profile.name);
+                    self.open_profile_dialog(ProfileDialog::Duplicate(id), name, db);
+                }
+                None
+            }
+            Message::RenameProfile(id) => {
+                if let Some(profile) = self.profiles.iter().find(|p| p.id == id) {
+                    let (name, first_number) = (profile.name.clone(), profile.first_number);
+                    self.open_profile_dialog(ProfileDialog::Rename(id), name, db);
+                    self.profile_first_number_input = first_number.to_string();
+                }
+                None
+            }
+            Message::ProfileNextField => {
+                self.profile_field = match self.profile_field {
+                    ProfileField::Name => ProfileField::FirstNumber,
+                    ProfileField::FirstNumber => ProfileField::Name,
+                };
+                None
+            }
+            Message::ProfileFieldInput(c) => {
+                match self.profile_field {
+                    ProfileField::Name => self.profile_name_input.push(c),
+                    ProfileField::FirstNumber => {
+                        if c.is_ascii_digit() {
+                            self.profile_first_number_input.push(c);
+                        }
+                    }
+                }
+                None
+            }
+            Message::ProfileFieldBackspace => {
+                match self.profile_field {
+                    ProfileField::Name => {
+                        self.profile_name_input.pop();
+                    }
+                    ProfileField::FirstNumber => {
+                        self.profile_first_number_input.pop();
+                    }
+                }
+                None
+            }
+            Message::SaveProfile => {
+                let dialog = self.profile_dialog?;
+                let name = self.profile_name_input.trim().to_string();
+                let first_number = self.profile_first_number_input.parse::<i64>().unwrap_or(0);
+                if name.is_empty() {
+                    self.status_message = Some("A profile needs a name".to_string());
+                    return None;
+                }
+                if first_number < 1 {
+                    self.status_message =
+                        Some("The first invoice number must be at least 1".to_string());
+                    return None;
+                }
+                let saved = match dialog {
+                    ProfileDialog::Add => db.add_profile(&name, first_number).map(|_| ()),
+                    ProfileDialog::Duplicate(source) => {
+                        db.get_profile_settings(source).and_then(|settings| {
+                            let id = db.add_profile(&name, first_number)?;
+                            db.set_profile_settings(id, &settings)
+                        })
+                    }
+                    ProfileDialog::Rename(id) => db.update_profile(id, &name, first_number),
+                };
+                match saved {
+                    Ok(()) => {
+                        self.status_message = Some(match dialog {
+                            ProfileDialog::Rename(_) => format!("Profile '{}' saved", name),
+                            _ => format!("Profile '{}' added", name),
+                        });
+                        self.profile_dialog = None;
+                        self.input_mode = InputMode::Normal;
+                        self.refresh_profiles(db);
+                        if dialog != ProfileDialog::Rename(self.shown_profile_id()) {
+                            self.selected_profile_index = self.profiles.len().saturating_sub(1);
+                        }
+                    }
+                    Err(e) => self.status_message = Some(format!("Failed to save profile: {}", e)),
+                }
+                None
+            }
+            Message::CancelEditProfile => {
+                self.profile_dialog = None;
+                self.input_mode = InputMode::Normal;
+                None
+            }
+            Message::DeleteProfile(id) => {
+                if id == DEFAULT_PROFILE_ID {
+                    self.status_message = Some("The default profile can't be deleted".to_string());
+                } else {
+                    self.confirm_delete_profile = Some(id);
+                }
+                None
+            }
+            Message::ConfirmDeleteProfile => {
+                if let Some(id) = self.confirm_delete_profile.take() {
+                    match db.delete_profile(id) {
+                        Ok(true) => {
+                            self.status_message = Some(format!("Profile {} deleted", id));
+                            self.selected_profile_index =
+                                self.selected_profile_index.saturating_sub(1);
+                            self.refresh_profiles(db);
+                        }
+                        Ok(false) => {
+                            self.status_message =
+                                Some("Profile has invoices; it can't be deleted".to_string());
+                        }
+                        Err(e) => {
+                            self.status_message = Some(format!("Failed to delete profile: {}", e));
+                        }
+                    }
+                }
+                None
+            }
+            Message::CancelDeleteProfile => {
+                self.confirm_delete_profile = None;
+                None
+            }
+
+            // Email settings actions
+            Message::EditEmailSettings => {
+                self.email_field = EmailField::Host;
+                self.load_email_inputs();
+                self.input_mode = InputMode::EditingEmailSettings;
+                None
+            }
+            Message::EmailNextField => {
+                self.email_field = match self.email_field {
+                    EmailField::Host => EmailField::Port,
+                    EmailField::Port => EmailField::Security,
+                    EmailField::Security => EmailField::Username,
+                    EmailField::Username => EmailField::Password,
+                    EmailField::Password => EmailField::From,
+                    EmailField::From => EmailField::Host,
+                };
+                None
+            }
+            Message::EmailPrevField => {
+                self.email_field = match self.email_field {
+                    EmailField::Host => EmailField::From,
+                    EmailField::Port => EmailField::Host,
+                    EmailField::Security => EmailField::Port,
+                    EmailField::Username => EmailField::Security,
+                    EmailField::Password => EmailField::Username,
+                    EmailField::From => EmailField::Password,
+                };
+                None
+            }
+            Message::EmailFieldInput(c) => {
+                match self.email_field {
+                    EmailField::Host => self.email_host_input.push(c),
+                    EmailField::Port => {
+                        if c.is_ascii_digit() {
+                            self.email_port_input.push(c);
+                        }
+                    }
+                    EmailField::Security => self.email_security_input.push(c),
+                    EmailField::Username => self.email_username_input.push(c),
+                    EmailField::Password => self.email_password_input.push(c),
+                    EmailField::From => self.email_from_input.push(c),
+                }
+                None
+            }
+            Message::EmailFieldBackspace => {
+                match self.email_field {
+                    EmailField::Host => self.email_host_input.pop(),
+                    EmailField::Port => self.email_port_input.pop(),
+                    EmailField::Security => self.email_security_input.pop(),
+                    EmailField::Username => self.email_username_input.pop(),
+                    EmailField::Password => self.email_password_input.pop(),
+                    EmailField::From => self.email_from_input.pop(),
+                };
+                None
+            }
+            Message::SaveEmailSettings => {
+                let security = match self.email_security_input.parse() {
+                    Ok(security) => security,
+                    Err(e) => {
+                        self.status_message = Some(e);
+                        return None;
+                    }
+                };
+                let Ok(smtp_port) = self.email_port_input.parse() else {
+                    self.status_message = Some("Invalid SMTP port".to_string());
+                    return None;
+                };
+                let settings = EmailSettings {
+                    smtp_host: self.email_host_input.trim().to_string(),
+                    smtp_port,
+                    smtp_username: self.email_username_input.trim().to_string(),
+                    from_address: self.email_from_input.trim().to_string(),
+                    security,
+                };
+
+                if db.set_email_settings(&settings).is_err() {
+                    self.status_message = Some("Failed to save email settings".to_string());
+                    return None;
+                }
+                self.status_message = Some("Email settings saved".to_string());
+                if !self.email_password_input.is_empty()
+                    && let Err(e) = email::set_smtp_password(&settings, &self.email_password_input)
+                {
+                    self.status_message = Some(e);
+                }
+                self.email_settings = settings;
+                self.email_password_input.clear();
+                self.input_mode = InputMode::Normal;
+                None
+            }
+            Message::CancelEditEmailSettings => {
+                self.input_mode = InputMode::Normal;
+                self.load_email_inputs();
+                None
+            }
+            // Tag actions
+            Message::RefreshTags => {
+                self.refresh_tags(db);
+                None
+            }
+            Message::SelectNextTag => {
+                if !self.tags.is_empty() {
+                    self.selected_tag_index =
+                        (self.selected_tag_index + 1).min(self.tags.len() - 1);
+                }
+                None
+            }
+            Message::SelectPreviousTag => {
+                self.selected_tag_index = self.selected_tag_index.saturating_sub(1);
+                None
+            }
+            Message::RenameTag(tag) => {
+                self.tag_rename_input = tag.clone();
+                self.renaming_tag = Some(tag);
+                self.input_mode = InputMode::RenamingTag;
+                None
+            }
+            Message::TagRenameInput(c) => {
+                self.tag_rename_input.push(c);
+                None
+            }
+            Message::TagRenameBackspace => {
+                self.tag_rename_input.pop();
+                None
+            }
+            Message::SaveTagRename => {
+                let tag = self.renaming_tag.take()?;
+                self.input_mode = InputMode::Normal;
+                let input = std::mem::take(&mut self.tag_rename_input);
+                let Some(new_tag) = normalize_tag(&input) else {
+                    self.status_message = Some("A tag needs a name".to_string());
+                    return None;
+                };
+                if new_tag == tag {
+                    return None;
+                }
+                let merging = self.tags.iter().any(|(t, _)| *t == new_tag);
+                match db.rename_tag(&tag, &new_tag) {
+                    Ok(_) if merging => {
+                        self.status_message = Some(format!("Merged '{}' into '{}'", tag, new_tag));
+                    }
+                    Ok(_) => {
+                        self.status_message = Some(format!("Renamed '{}' to '{}'", tag, new_tag));
+                    }
+                    Err(e) => self.status_message = Some(format!("Failed to rename tag: {}", e)),
+                }
+                Some(Message::RefreshTags)
+            }
+            Message::CancelTagRename => {
+                self.renaming_tag = None;
+                self.tag_rename_input.clear();
+                self.input_mode = InputMode::Normal;
+                None
+            }
+            Message::CycleTagColor(tag) => {
+                // Cycle through: none -> each of TAG_COLORS -> none
+                let next = match self.tag_colors.get(&tag) {
+                    None => TAG_COLORS.first().copied(),
+                    Some(current) => TAG_COLORS
+                        .iter()
+                        .position(|c| c == current)
+                        .and_then(|idx| TAG_COLORS.get(idx + 1).copied()),
+                };
+                if db.set_tag_color(&tag, next).is_ok() {
+                    self.status_message = Some(match next {
+                        Some(color) => format!("'{}' is now {}", tag, color),
+                        None => format!("'{}' has no color", tag),
+                    });
+                    return Some(Message::RefreshTags);
+                }
+                None
+            }
+            Message::DeleteTag(tag) => {
+                self.confirm_delete_tag = Some(tag);
+                None
+            }
+            Message::ConfirmDeleteTag => {
+                if let Some(tag) = self.confirm_delete_tag.take() {
+                    match db.delete_tag(&tag) {
+                        Ok(count) => {
+                            self.status_message =
+                                Some(format!("Removed '{}' from {} entries", tag, count));
+                            if self.selected_tag_index > 0 {
+                                self.selected_tag_index -= 1;
+                            }
+                            return Some(Message::RefreshTags);
+                        }
+                        Err(e) => {
+                            self.status_message = Some(format!("Failed to delete tag: {}", e));
+                        }
+                    }
+                }
+                None
+            }
+            Message::CancelDeleteTag => {
+                self.confirm_delete_tag = None;
+                None
+            }
+
+            Message::SendTestEmail => {
+                self.status_message = Some(match email::send_test_email(&self.email_settings) {
+                    Ok(()) => format!("Test email sent to {}", self.email_settings.from_address),
+                    Err(e) => e,
+                });
+                None
+            }
         }
     }
 
     fn refresh_entries(&mut self, db: &Db) {
-        let filter = if self.show_only_unbilled {
-            Some(false)
+        self.entries = match &self.entries_project_filter {
+            Some(project) => db.list_for_project(project, self.status_filter),
+            None => db.list(self.status_filter),
+        }
+        .unwrap_or_default();
+        self.review_flags = db.review_flags().unwrap_or_default();
+        if self.flagged_only {
+            let flags = &self.review_flags;
+            self.entries.retain(|e| flags.contains_key(&e.id));
+        }
+        if let Some((from, to)) = self.entries_range() {
+            self.entries.retain(|e| {
+                let day = e.start.with_timezone(&Local).date_naive();
+                day >= from && day < to
+            });
+            self.time_off = db
+                .time_off_between(from, to - Duration::days(1))
+                .unwrap_or_default();
         } else {
-            None
-        };
-        self.entries = db.list(filter).unwrap_or_default();
+            self.time_off.clear();
+        }
         if self.selected_entry_index >= self.entries.len() && !self.entries.is_empty() {
             self.selected_entry_index = self.entries.len() - 1;
         }
@@ -1400,23 +3019,184 @@ impl App {
 
     fn refresh_active_timer(&mut self, db: &Db) {
         self.active_entry = db.get_active_entry().unwrap_or(None);
+        self.active_target = self
+            .active_entry
+            .as_ref()
+            .and_then(|e| db.timer_target(e.id).ok().flatten());
     }
 
-    fn refresh_invoice_entries(&mut self, db: &Db) {
-        // Get billed entries for invoice selection
-        self.invoice_entries = db.list(Some(true)).unwrap_or_default();
+    /// Notify once when a countdown timer reaches its target
+    fn check_timer_target(&mut self, db: &Db) {
+        let (Some(entry), Some(target)) = (&self.active_entry, self.active_target) else {
+            return;
+        };
+        if (Utc::now() - entry.start).num_seconds() < target
+            || !db.claim_target_notification(entry.id).unwrap_or(false)
+        {
+            return;
+        }
+        let label = format_short_duration(target);
+        notification::notify_target_reached(
+            &db.get_notification_settings().unwrap_or_default(),
+            &entry.project,
+            &label,
+        );
+        self.status_message = Some(format!(
+            "{} on '{}' is up. Press [s] to stop",
+            label, entry.project
+        ));
+    }
+
+    /// Seconds left on a countdown timer; negative once it has run over
+    pub fn get_target_remaining_secs(&self) -> Option<i64> {
+        let entry = self.active_entry.as_ref()?;
+        Some(self.active_target? - (Utc::now() - entry.start).num_seconds())
+    }
 
-        // Fetch project rates for invoice preview
+    fn refresh_invoice_entries(&mut self, db: &Db) {
+        // Get billed entries for invoice selection, leaving out those held by a
+        // draft or waiting for the client's approval
+        let mut drafted = db.drafted_entry_ids().unwrap_or_default();
+        drafted.extend(db.awaiting_approval_entry_ids().unwrap_or_default());
+        self.invoice_entries = db
+            .list(Some(EntryStatus::Billed))
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|e| !drafted.contains(&e.id))
+            .collect();
+        let tags = db.all_entry_tags().unwrap_or_default();
+        self.invoice_entry_tags = self
+            .invoice_entries
+            .iter()
+            .filter_map(|e| Some((e.id, tags.get(&e.id)?.clone())))
+            .collect();
+
+        // Fetch projects, for the preview's client, and their rates
+        self.projects = db.list_projects().unwrap_or_default();
         self.project_rates.clear();
-        if let Ok(projects) = db.list_projects() {
-            for proj in projects {
-                if let Some(rate) = proj.rate {
-                    let currency = proj.currency.unwrap_or_else(|| "$".to_string());
-                    self.project_rates
-                        .insert(proj.name, ProjectRate { rate, currency });
+        for proj in &self.projects {
+            if let Some(rate) = proj.rate {
+                let currency = proj.currency.clone().unwrap_or_else(|| "$".to_string());
+                self.project_rates.insert(
+                    proj.name.clone(),
+                    ProjectRate {
+                        rate,
+                        currency,
+                        billing_mode: proj.billing_mode,
+                    },
+                );
+            }
+        }
+
+        // The rest of what generate_invoice bills with, for the preview
+        self.invoice_client_billing = db
+            .list_clients()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|c| Some(c.id))
+            .chain([None])
+            .map(|client_id| {
+                let profile_id = db
+                    .profile_for_client(client_id)
+                    .unwrap_or(DEFAULT_PROFILE_ID);
+                let settings = db.get_profile_settings(profile_id).unwrap_or_default();
+                let overtime = client_id.and_then(|id| db.get_overtime_rates(id).ok().flatten());
+                (client_id, (settings, overtime))
+            })
+            .collect();
+        self.invoice_expenses = db.list_expenses().unwrap_or_default();
+        self.invoice_holidays = db.holidays().unwrap_or_default();
+
+        self.missing_invoices = db
+            .list_invoices()
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|i| i.file_missing())
+            .collect();
+        self.attachments = db.list_attachments().unwrap_or_default();
+        self.attachment_index = self
+            .attachment_index
+            .min(self.attachments.len().saturating_sub(1));
+    }
+
+    /// Rewrite every missing invoice PDF from its stored snapshot
+    fn regenerate_missing_invoices(&mut self, db: &Db) {
+        if self.missing_invoices.is_empty() {
+            self.status_message = Some("No missing invoice PDFs".to_string());
+            return;
+        }
+        let mut restored = 0;
+        let mut failed = Vec::new();
+        for invoice in &self.missing_invoices {
+            let result = invoice
+                .snapshot
+                .as_deref()
+                .and_then(|json| InvoiceSnapshot::from_json(json).ok())
+                .and_then(|snapshot| regenerate_invoice(&snapshot).ok());
+            match result {
+                Some(result) => {
+                    let _ = db.set_invoice_file_path(invoice.id, &result.file_path);
+                    restored += 1;
                 }
+                None => failed.push(format!("#{}", invoice.invoice_number)),
             }
         }
+        self.refresh_invoice_entries(db);
+        self.status_message = Some(if failed.is_empty() {
+            format!("Regenerated {} invoice PDF(s)", restored)
+        } else {
+            format!(
+                "Regenerated {} invoice PDF(s); could not regenerate {} (no snapshot)",
+                restored,
+                failed.join(", ")
+            )
+        });
+    }
+
+    /// The client an invoice of `entries` is for: the one picked, or else the
+    /// one their projects are all linked to
+    fn invoice_client(
+        &self,
+        entries: &[Entry],
+        projects: &[Project],
+    ) -> (Option<i64>, ClientMatch) {
+        let client_match = detect_client(entries, projects);
+        let client_id = match (self.selected_invoice_client, &client_match) {
+            (Some(id), _) => Some(id),
+            (None, ClientMatch::Single(id)) => Some(*id),
+            _ => None,
+        };
+        (client_id, client_match)
+    }
+
+    /// What generating an invoice of `entries` for `year`-`month` would bill,
+    /// worked out from what `refresh_invoice_entries` loaded
+    pub fn preview_bill(&self, entries: &[Entry], year: i32, month: u32) -> Bill {
+        let (client_id, _) = self.invoice_client(entries, &self.projects);
+        let (settings, overtime) = match self.invoice_client_billing.get(&client_id) {
+            Some((settings, overtime)) => (settings, overtime.as_ref()),
+            None => (&self.invoice_settings, None),
+        };
+        let expenses =
+            expenses_for_invoice(&self.invoice_expenses, entries, client_id, year, month);
+        invoice::bill(&InvoiceParams {
+            entries,
+            rounding_minutes: 0,
+            expenses: &expenses,
+            items: &[],
+            project_rates: &self.project_rates,
+            year,
+            month,
+            invoice_number: 0,
+            draft: true,
+            supersedes: None,
+            settings,
+            client: None,
+            overtime,
+            tax_rate: settings.default_tax_rate,
+            holidays: &self.invoice_holidays,
+            reissue_dates: None,
+        })
     }
 
     fn generate_invoice(&mut self, db: &Db) {
@@ -1433,7 +3213,7 @@ impl App {
                     .with_second(0)
                     .unwrap();
                 let end = now;
-                db.list_by_date_range(start, end, Some(true))
+                db.list_by_date_range(start, end, Some(EntryStatus::Billed))
                     .unwrap_or_default()
             }
             InvoiceMode::PriorMonth => {
@@ -1449,14 +3229,14 @@ impl App {
                     .with_second(0)
                     .unwrap();
                 let end = first_of_current;
-                db.list_by_date_range(start, end, Some(true))
+                db.list_by_date_range(start, end, Some(EntryStatus::Billed))
                     .unwrap_or_default()
             }
             InvoiceMode::CustomRange => {
                 if let (Some(start), Some(end)) = (self.custom_start_date, self.custom_end_date) {
                     let start_dt = start.and_hms_opt(0, 0, 0).unwrap().and_utc();
                     let end_dt = end.and_hms_opt(23, 59, 59).unwrap().and_utc();
-                    db.list_by_date_range(start_dt, end_dt, Some(true))
+                    db.list_by_date_range(start_dt, end_dt, Some(EntryStatus::Billed))
                         .unwrap_or_default()
                 } else {
                     Vec::new()
@@ -1469,6 +3249,14 @@ impl App {
                 .cloned()
                 .collect(),
         };
+        // Entries held by a draft are invoiced when the draft is finalized, and
+        // those on an approval sheet once the client approves it
+        let mut drafted = db.drafted_entry_ids().unwrap_or_default();
+        drafted.extend(db.awaiting_approval_entry_ids().unwrap_or_default());
+        let entries: Vec<Entry> = entries
+            .into_iter()
+            .filter(|e| !drafted.contains(&e.id))
+            .collect();
 
         // Determine year/month for invoice filename
         let (year, month) = match &self.invoice_mode {
@@ -1488,19 +3276,43 @@ impl App {
             InvoiceMode::SelectEntries => (now.year(), now.month()),
         };
 
-        // Get invoice settings and next invoice number
-        let settings = db.get_invoice_settings().unwrap_or_default();
-        let invoice_number = db.get_next_invoice_number().unwrap_or(1);
+        let projects = db.list_projects().unwrap_or_default();
+        let (client_id, client_match) = self.invoice_client(&entries, &projects);
+        let client = client_id.and_then(|id| self.clients.iter().find(|c| c.id == id));
+
+        // Get the client's profile settings and its next invoice number
+        let profile_id = db
+            .profile_for_client(client_id)
+            .unwrap_or(DEFAULT_PROFILE_ID);
+        let settings = db.get_profile_settings(profile_id).unwrap_or_default();
+        let invoice_number = db.get_next_invoice_number(profile_id).unwrap_or(1);
+        let holidays = db.holidays().unwrap_or_default();
+        let overtime = client_id.and_then(|id| db.get_overtime_rates(id).ok().flatten());
+        let expenses = expenses_for_invoice(
+            &db.list_expenses().unwrap_or_default(),
+            &entries,
+            client_id,
+            year,
+            month,
+        );
 
         let params = InvoiceParams {
             entries: &entries,
+            rounding_minutes: 0,
+            expenses: &expenses,
+            items: &[],
             project_rates: &self.project_rates,
             year,
             month,
             invoice_number,
+            draft: false,
+            supersedes: None,
             settings: &settings,
-            client: self.get_selected_invoice_client(),
+            client,
+            overtime: overtime.as_ref(),
             tax_rate: settings.default_tax_rate,
+            holidays: &holidays,
+            reissue_dates: None,
         };
 
         // Use shared invoice generation
@@ -1510,7 +3322,7 @@ impl App {
                 let invoice_record = crate::models::Invoice {
                     id: 0,
                     invoice_number,
-                    client_id: self.selected_invoice_client,
+                    client_id,
                     date_issued: result.date_issued.clone(),
                     due_date: result.due_date.clone(),
                     subtotal: result.subtotal,
@@ -1518,12 +3330,32 @@ impl App {
                     tax_amount: result.tax_amount,
                     total: result.total,
                     file_path: result.file_path.clone(),
+                    snapshot: Some(result.snapshot.to_json()),
+                    void_reason: None,
+                    supersedes: None,
+                    profile_id,
                 };
                 let _ = db.record_invoice(&invoice_record);
+                for entry in &entries {
+                    let _ = db.set_entry_status(entry.id, EntryStatus::Invoiced);
+                }
+                for expense in &expenses {
+                    let _ = db.set_expense_invoiced(expense.id, invoice_number);
+                }
+                self.refresh_entries(db);
+                self.refresh_invoice_entries(db);
+                self.refresh_invoice_reminders(db);
+                self.selected_entry_ids.clear();
+                self.invoice_select_index = 0;
 
+                let warning = if matches!(client_match, ClientMatch::Mixed(_)) {
+                    " (warning: entries span multiple clients)"
+                } else {
+                    ""
+                };
                 self.status_message = Some(format!(
-                    "Invoice #{} written to {}",
-                    invoice_number, result.file_path
+                    "Invoice #{} written to {}{}",
+                    invoice_number, result.file_path, warning
                 ));
             }
             Err(e) => {
@@ -1532,6 +3364,269 @@ impl App {
         }
     }
 
+    /// Split a stopped entry at midnight when that setting is on
+    fn split_if_enabled(&mut self, id: i64, db: &Db) {
+        if !self.timer_settings.split_at_midnight {
+            return;
+        }
+        if let Ok(added) = db.split_entry_at_midnight(id)
+            && added > 0
+        {
+            self.status_message = Some(format!("Timer stopped (split across {} days)", added + 1));
+        }
+    }
+
+    /// Billed entries shown in SelectEntries mode after the project and date filters
+    pub fn visible_invoice_entries(&self) -> Vec<&Entry> {
+        let range = self.invoice_date_filter.range(Local::now().date_naive());
+        self.invoice_entries
+            .iter()
+            .filter(|e| {
+                self.invoice_project_filter
+                    .as_ref()
+                    .is_none_or(|p| &e.project == p)
+            })
+            .filter(|e| {
+                self.invoice_tag_filter.as_ref().is_none_or(|tag| {
+                    self.invoice_entry_tags
+                        .get(&e.id)
+                        .is_some_and(|tags| tags.contains(tag))
+                })
+            })
+            .filter(|e| match (range, e.end) {
+                (None, _) => true,
+                (Some((from, to)), Some(end)) => {
+                    let day = end.with_timezone(&Local).date_naive();
+                    day >= from && day < to
+                }
+                (Some(_), None) => false,
+            })
+            .collect()
+    }
+
+    /// Amount for the checked entries, per currency, using the cached project rates.
+    /// Entries for projects without a rate are left out.
+    pub fn selected_invoice_amounts(&self) -> Vec<(String, Money)> {
+        let mut by_project: Vec<(&str, Vec<&Entry>)> = Vec::new();
+        for entry in self
+            .invoice_entries
+            .iter()
+            .filter(|e| self.selected_entry_ids.contains(&e.id))
+        {
+            match by_project.iter_mut().find(|(p, _)| *p == entry.project) {
+                Some((_, entries)) => entries.push(entry),
+                None => by_project.push((&entry.project, vec![entry])),
+            }
+        }
+
+        let mut amounts: Vec<(String, Money)> = Vec::new();
+        for (project, entries) in by_project {
+            let Some(rate) = self.project_rates.get(project) else {
+                continue;
+            };
+            let amount = rate.amount(&entries);
+            match amounts.iter_mut().find(|(c, _)| *c == rate.currency) {
+                Some((_, total)) => *total += amount,
+                None => amounts.push((rate.currency.clone(), amount)),
+            }
+        }
+        amounts
+    }
+
+    /// The days shown on the entries screen (start inclusive, end exclusive),
+    /// or `None` when showing every entry.
+    pub fn entries_range(&self) -> Option<(NaiveDate, NaiveDate)> {
+        let anchor = self
+            .entries_anchor
+            .unwrap_or_else(|| Local::now().date_naive());
+        match self.entries_period {
+            EntriesPeriod::All => None,
+            EntriesPeriod::Day => Some((anchor, anchor + Duration::days(1))),
+            EntriesPeriod::Week => {
+                let monday =
+                    anchor - Duration::days(anchor.weekday().num_days_from_monday() as i64);
+                Some((monday, monday + Duration::days(7)))
+            }
+        }
+    }
+
+    /// The time off covering `day`, if it is a day off
+    pub fn day_off(&self, day: NaiveDate) -> Option<&TimeOff> {
+        self.time_off.iter().find(|t| t.contains(day))
+    }
+
+    /// Show a wizard step with its fields prefilled with sensible defaults
+    fn set_onboarding_step(&mut self, step: OnboardingStep) {
+        self.onboarding = Some(step);
+        self.onboarding_field = 0;
+        self.onboarding_inputs = match step {
+            OnboardingStep::Business => vec![
+                String::new(),
+                String::new(),
+                self.invoice_settings.default_payment_terms.clone(),
+            ],
+            OnboardingStep::Project => vec![String::new(), String::new(), "$".to_string()],
+            OnboardingStep::Pomodoro => vec![
+                if self.pomodoro_config.enabled {
+                    "y"
+                } else {
+                    "n"
+                }
+                .to_string(),
+                self.pomodoro_config.work_duration.to_string(),
+                self.pomodoro_config.short_break.to_string(),
+            ],
+            _ => vec![String::new(); step.fields().len()],
+        };
+    }
+
+    fn advance_onboarding(&mut self, step: OnboardingStep) {
+        match step.next() {
+            Some(next) => self.set_onboarding_step(next),
+            None => {
+                self.onboarding = None;
+                self.onboarding_inputs.clear();
+            }
+        }
+    }
+
+    /// Save what was entered on a wizard step. Blank steps save nothing.
+    fn save_onboarding_step(
+        &mut self,
+        step: OnboardingStep,
+        db: &Db,
+    ) -> std::result::Result<(), String> {
+        let input = |i: usize| {
+            self.onboarding_inputs
+                .get(i)
+                .map(|s| s.trim().to_string())
+                .unwrap_or_default()
+        };
+        match step {
+            OnboardingStep::Business => {
+                let mut settings = db.get_invoice_settings().unwrap_or_default();
+                settings.business_name = input(0);
+                settings.email = input(1);
+                if !input(2).is_empty() {
+                    settings.default_payment_terms = input(2);
+                }
+                db.set_invoice_settings(&settings)
+                    .map_err(|_| "Failed to save settings".to_string())?;
+                self.refresh_invoice_settings(db);
+            }
+            OnboardingStep::Client => {
+                if input(0).is_empty() {
+                    return Ok(());
+                }
+                let client = Client {
+                    name: input(0),
+                    contact_person: input(1),
+                    email: input(2),
+                    ..Default::default()
+                };
+                client.validate()?;
+                let id = db
+                    .add_client(&client)
+                    .map_err(|_| "Failed to add client".to_string())?;
+                self.onboarding_client_id = Some(id);
+                self.refresh_clients(db);
+            }
+            OnboardingStep::Project => {
+                let name = input(0);
+                if name.is_empty() {
+                    return Ok(());
+                }
+                let rate = if input(1).is_empty() {
+                    None
+                } else {
+                    Some(
+                        input(1)
+                            .parse::<f64>()
+                            .map_err(|_| format!("Invalid rate '{}'", input(1)))?,
+                    )
+                };
+                if let Some(rate) = rate {
+                    validate_rate(rate)?;
+                }
+                let rate = rate.map(Money::from_f64);
+                let currency = if input(2).is_empty() {
+                    "$".to_string()
+                } else {
+                    normalize_currency(&input(2))?
+                };
+                db.set_project_rate(&name, rate, Some(&currency))
+                    .map_err(|_| "Failed to save project".to_string())?;
+                if let Some(client_id) = self.onboarding_client_id {
+                    let _ = db.set_project_client(&name, Some(client_id));
+                }
+                self.project_input = name;
+            }
+            OnboardingStep::Pomodoro => {
+                let mut config = db.get_pomodoro_config().unwrap_or_default();
+                config.enabled = input(0).eq_ignore_ascii_case("y");
+                if let Ok(work) = input(1).parse::<i32>()
+                    && work > 0
+                {
+                    config.work_duration = work;
+                }
+                if let Ok(short_break) = input(2).parse::<i32>()
+                    && short_break > 0
+                {
+                    config.short_break = short_break;
+                }
+                db.set_pomodoro_config(&config)
+                    .map_err(|_| "Failed to save Pomodoro settings".to_string())?;
+                self.refresh_pomodoro_config(db);
+            }
+            OnboardingStep::Welcome | OnboardingStep::Done => {}
+        }
+        Ok(())
+    }
+
+    /// Fill the edit dialog with an existing entry
+    fn open_edit_entry(&mut self, entry: Entry) {
+        self.edit_field = EditField::Project;
+        self.edit_project_input = entry.project.clone();
+        self.edit_description_input = entry.description.clone();
+        self.edit_start_input = format_edit_time(entry.start);
+        self.edit_end_input = entry.end.map(format_edit_time).unwrap_or_default();
+        self.edit_location_input = entry.location.clone().unwrap_or_default();
+        self.editing_entry = Some(entry);
+        self.input_mode = InputMode::EditEntryProject;
+    }
+
+    /// Same dialog as editing, for an entry that doesn't exist yet (id 0)
+    fn open_new_entry(
+        &mut self,
+        project: String,
+        description: String,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) {
+        self.new_entry_tags.clear();
+        self.open_edit_entry(Entry {
+            id: 0,
+            project,
+            description,
+            start,
+            end: Some(end),
+            status: EntryStatus::Open,
+            days: None,
+            issue: None,
+            location: None,
+        });
+    }
+
+    /// What the description field starts as: empty when the project's last description
+    /// will be filled in on start, otherwise the global default.
+    fn blank_description_input(&self) -> String {
+        if self.timer_settings.reuse_last_description {
+            String::new()
+        } else {
+            DEFAULT_DESCRIPTION.to_string()
+        }
+    }
+
     pub fn get_selected_entry(&self) -> Option<&Entry> {
         self.entries.get(self.selected_entry_index)
     }
@@ -1551,6 +3646,164 @@ impl App {
         self.invoice_settings = db.get_invoice_settings().unwrap_or_default();
     }
 
+    /// Load last week's finished entries and start the review from the first
+    fn refresh_review(&mut self, db: &Db) {
+        let today = Local::now().date_naive();
+        let monday = today - Duration::days(today.weekday().num_days_from_monday() as i64 + 7);
+        let next_monday = monday + Duration::days(7);
+        // Listed newest first
+        let mut entries: Vec<Entry> = db
+            .list(None)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|e| {
+                let day = e.start.with_timezone(&Local).date_naive();
+                e.end.is_some() && day >= monday && day < next_monday
+            })
+            .collect();
+        entries.reverse();
+
+        let tags = db.all_entry_tags().unwrap_or_default();
+        self.review_tags = entries
+            .iter()
+            .filter_map(|e| Some((e.id, tags.get(&e.id)?.clone())))
+            .collect();
+        self.review_entries = entries;
+        self.review_flags = db.review_flags().unwrap_or_default();
+        self.review_week = Some(monday);
+        self.review_index = 0;
+        self.review_edited.clear();
+        self.refresh_projects(db);
+        self.refresh_clients(db);
+    }
+
+    fn refresh_profiles(&mut self, db: &Db) {
+        self.profiles = db.list_profiles().unwrap_or_default();
+        if self.selected_profile_index >= self.profiles.len() {
+            self.selected_profile_index = self.profiles.len().saturating_sub(1);
+        }
+    }
+
+    /// ID of the business profile shown on the settings screen
+    pub fn shown_profile_id(&self) -> i64 {
+        self.profiles
+            .get(self.selected_profile_index)
+            .map(|p| p.id)
+            .unwrap_or(DEFAULT_PROFILE_ID)
+    }
+
+    /// Invoice settings of the business profile shown on the settings screen
+    pub fn shown_settings(&self) -> &InvoiceSettings {
+        self.profiles
+            .get(self.selected_profile_index)
+            .map(|p| &p.settings)
+            .unwrap_or(&self.invoice_settings)
+    }
+
+    /// Open the profile dialog with `name`, suggesting a first invoice number
+    /// past every profile's next one, rounded up to the next thousand
+    fn open_profile_dialog(&mut self, dialog: ProfileDialog, name: String, db: &Db) {
+        let highest = self
+            .profiles
+            .iter()
+            .filter_map(|p| db.get_next_invoice_number(p.id).ok())
+            .max()
+            .unwrap_or(1);
+        self.profile_first_number_input = ((highest / 1000 + 1) * 1000 + 1).to_string();
+        self.profile_name_input = name;
+        self.profile_field = ProfileField::Name;
+        self.profile_dialog = Some(dialog);
+        self.input_mode = InputMode::EditingProfile;
+    }
+
+    fn refresh_email_settings(&mut self, db: &Db) {
+        self.email_settings = db.get_email_settings().unwrap_or_default();
+    }
+
+    fn refresh_tags(&mut self, db: &Db) {
+        self.tags = db.tag_counts().unwrap_or_default();
+        self.tag_colors = db.tag_colors().unwrap_or_default();
+        if self.selected_tag_index >= self.tags.len() {
+            self.selected_tag_index = self.tags.len().saturating_sub(1);
+        }
+    }
+
+    fn refresh_projects(&mut self, db: &Db) {
+        self.projects = db.list_projects().unwrap_or_default();
+        let now = Utc::now();
+        self.cap_usage = self
+            .projects
+            .iter()
+            .filter_map(|p| {
+                db.project_cap_usage(&p.name, now)
+                    .ok()
+                    .flatten()
+                    .map(|usage| (p.name.clone(), usage))
+            })
+            .collect();
+        self.project_totals = db.project_totals(now).unwrap_or_default();
+    }
+
+    /// Notify once when the running timer takes its project past its hour cap
+    fn check_hour_cap(&mut self, db: &Db) {
+        let Some(entry) = &self.active_entry else {
+            return;
+        };
+        if self.cap_warned_entry == Some(entry.id) {
+            return;
+        }
+        if let Ok(Some(usage)) = db.project_cap_usage(&entry.project, Utc::now())
+            && usage.is_reached()
+        {
+            self.cap_warned_entry = Some(entry.id);
+            notification::notify_cap_reached(
+                &db.get_notification_settings().unwrap_or_default(),
+                &entry.project,
+                &usage.summary(),
+            );
+            self.status_message = Some(format!(
+                "'{}' has reached its hour cap ({})",
+                entry.project,
+                usage.summary()
+            ));
+        }
+    }
+
+    /// Work out the pace against the monthly target every so often, and notify
+    /// once a day while billable hours are behind it
+    fn check_target_pace(&mut self, db: &Db) {
+        if self
+            .pace_checked
+            .is_some_and(|t| t.elapsed() < PACE_CHECK_INTERVAL)
+        {
+            return;
+        }
+        self.pace_checked = Some(Instant::now());
+        let settings = db.get_notification_settings().unwrap_or_default();
+        self.target_margin_hours = settings.target_margin_hours;
+        self.target_pace = db.target_pace(Utc::now()).ok().flatten();
+        let Some(pace) = &self.target_pace else {
+            return;
+        };
+        if pace.is_behind(self.target_margin_hours) && self.pace_warned_day != Some(pace.today) {
+            self.pace_warned_day = Some(pace.today);
+            let summary = pace.summary(self.target_margin_hours);
+            notification::notify_behind_target(&settings, &summary);
+            self.status_message = Some(format!("Billable hours are {}", summary));
+        }
+    }
+
+    fn refresh_invoice_reminders(&mut self, db: &Db) {
+        self.invoice_reminders = db
+            .invoice_reminders(Local::now().date_naive())
+            .unwrap_or_default();
+    }
+
+    /// Clients that can be picked for new work (archived ones are hidden)
+    pub fn active_clients(&self) -> Vec<&Client> {
+        self.clients.iter().filter(|c| !c.archived).collect()
+    }
+
     /// Get the selected client for invoicing
     pub fn get_selected_invoice_client(&self) -> Option<&Client> {
         self.selected_invoice_client
@@ -1619,19 +3872,29 @@ impl App {
 
     /// Load invoice settings values into input fields
     fn load_settings_inputs(&mut self) {
-        self.settings_business_name_input = self.invoice_settings.business_name.clone();
-        self.settings_street_input = self.invoice_settings.address_street.clone();
-        self.settings_city_input = self.invoice_settings.address_city.clone();
-        self.settings_state_input = self.invoice_settings.address_state.clone();
-        self.settings_postal_input = self.invoice_settings.address_postal.clone();
-        self.settings_country_input = self.invoice_settings.address_country.clone();
-        self.settings_email_input = self.invoice_settings.email.clone();
-        self.settings_phone_input = self.invoice_settings.phone.clone();
-        self.settings_tax_id_input = self.invoice_settings.tax_id.clone();
-        self.settings_payment_terms_input = self.invoice_settings.default_payment_terms.clone();
-        self.settings_default_tax_rate_input = self.invoice_settings.default_tax_rate.to_string();
-        self.settings_payment_instructions_input =
-            self.invoice_settings.payment_instructions.clone();
+        let settings = self.shown_settings().clone();
+        self.settings_business_name_input = settings.business_name;
+        self.settings_street_input = settings.address_street;
+        self.settings_city_input = settings.address_city;
+        self.settings_state_input = settings.address_state;
+        self.settings_postal_input = settings.address_postal;
+        self.settings_country_input = settings.address_country;
+        self.settings_email_input = settings.email;
+        self.settings_phone_input = settings.phone;
+        self.settings_tax_id_input = settings.tax_id;
+        self.settings_payment_terms_input = settings.default_payment_terms;
+        self.settings_default_tax_rate_input = settings.default_tax_rate.to_string();
+        self.settings_payment_instructions_input = settings.payment_instructions;
+    }
+
+    /// Load the email settings into the input fields
+    fn load_email_inputs(&mut self) {
+        self.email_host_input = self.email_settings.smtp_host.clone();
+        self.email_port_input = self.email_settings.smtp_port.to_string();
+        self.email_security_input = self.email_settings.security.to_string();
+        self.email_username_input = self.email_settings.smtp_username.clone();
+        self.email_password_input.clear();
+        self.email_from_input = self.email_settings.from_address.clone();
     }
 
     /// Get the selected client for editing
@@ -1639,3 +3902,44 @@ impl App {
         self.clients.get(self.selected_client_index)
     }
 }
+
+/// Times in the edit dialog are local and keep their seconds
+fn format_edit_time(time: DateTime<Utc>) -> String {
+    time.with_timezone(&Local)
+        .format("%Y-%m-%d %H:%M:%S")
+        .to_string()
+}
+
+/// Parse an edit dialog time, with or without seconds
+fn parse_edit_time(input: &str) -> Option<DateTime<Utc>> {
+    let input = input.trim();
+    let parsed = chrono::NaiveDateTime::parse_from_str(input, "%Y-%m-%d %H:%M:%S")
+        .or_else(|_| chrono::NaiveDateTime::parse_from_str(input, "%Y-%m-%d %H:%M"))
+        .ok()?;
+    Local
+        .from_local_datetime(&parsed)
+        .single()
+        .map(|dt| dt.with_timezone(&Utc))
+}
+
+/// Parse the start time of a forgotten timer: HH:MM[:SS] today, or a full
+/// date and time as in the edit dialog
+fn parse_start_time(input: &str) -> Option<DateTime<Utc>> {
+    let time = chrono::NaiveTime::parse_from_str(input.trim(), "%H:%M:%S")
+        .or_else(|_| chrono::NaiveTime::parse_from_str(input.trim(), "%H:%M"));
+    let Ok(time) = time else {
+        return parse_edit_time(input);
+    };
+    Local
+        .from_local_datetime(&Local::now().date_naive().and_time(time))
+        .single()
+        .map(|dt| dt.with_timezone(&Utc))
+}
+
+/// Length of a stopped entry in whole seconds
+fn entry_seconds(entry: &Entry) -> i64 {
+    entry
+        .end
+        .map(|end| (end - entry.start).num_seconds())
+        .unwrap_or(0)
+}
@@ -1,9 +1,24 @@
 use crossterm::event::{KeyCode, KeyEvent};
 
+use crate::models::EntryStatus;
+
 use super::app::{App, InputMode, InvoiceMode, Message, PomodoroField, PomodoroState, Screen};
 
 /// Map key events to messages based on current app state
 pub fn handle_key(key: KeyEvent, app: &App) -> Option<Message> {
+    // The first-run wizard takes every key until it is finished or skipped
+    if app.onboarding.is_some() {
+        return match key.code {
+            KeyCode::Enter => Some(Message::OnboardingSubmit),
+            KeyCode::Esc => Some(Message::OnboardingSkip),
+            KeyCode::Tab | KeyCode::Down => Some(Message::OnboardingNextField),
+            KeyCode::BackTab | KeyCode::Up => Some(Message::OnboardingPrevField),
+            KeyCode::Backspace => Some(Message::OnboardingBackspace),
+            KeyCode::Char(c) => Some(Message::OnboardingInput(c)),
+            _ => None,
+        };
+    }
+
     // Handle help toggle globally
     if key.code == KeyCode::Char('?') && app.input_mode == InputMode::Normal {
         return Some(Message::ToggleHelp);
@@ -23,6 +38,24 @@ pub fn handle_key(key: KeyEvent, app: &App) -> Option<Message> {
         };
     }
 
+    // Handle short entry discard prompt
+    if app.confirm_discard.is_some() {
+        return match key.code {
+            KeyCode::Char('y') | KeyCode::Char('Y') => Some(Message::ConfirmDiscard),
+            KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => Some(Message::CancelDiscard),
+            _ => None,
+        };
+    }
+
+    // Handle the prompt to throw away the running timer
+    if app.confirm_cancel_timer {
+        return match key.code {
+            KeyCode::Char('y') | KeyCode::Char('Y') => Some(Message::ConfirmCancelTimer),
+            KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => Some(Message::KeepTimer),
+            _ => None,
+        };
+    }
+
     // Handle confirm delete client dialog
     if app.confirm_delete_client.is_some() {
         return match key.code {
@@ -34,6 +67,28 @@ pub fn handle_key(key: KeyEvent, app: &App) -> Option<Message> {
         };
     }
 
+    // Handle confirm delete tag dialog
+    if app.confirm_delete_tag.is_some() {
+        return match key.code {
+            KeyCode::Char('y') | KeyCode::Char('Y') => Some(Message::ConfirmDeleteTag),
+            KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
+                Some(Message::CancelDeleteTag)
+            }
+            _ => None,
+        };
+    }
+
+    // Handle confirm delete profile dialog
+    if app.confirm_delete_profile.is_some() {
+        return match key.code {
+            KeyCode::Char('y') | KeyCode::Char('Y') => Some(Message::ConfirmDeleteProfile),
+            KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
+                Some(Message::CancelDeleteProfile)
+            }
+            _ => None,
+        };
+    }
+
     // Handle input modes
     match app.input_mode {
         InputMode::EditingProject => {
@@ -50,16 +105,27 @@ pub fn handle_key(key: KeyEvent, app: &App) -> Option<Message> {
             return match key.code {
                 KeyCode::Enter => Some(Message::StartTimer),
                 KeyCode::Esc => Some(Message::ExitInputMode),
-                KeyCode::Tab => Some(Message::EnterInputMode(InputMode::EditingProject)),
+                KeyCode::Tab => Some(Message::EnterInputMode(InputMode::EditingStartTime)),
                 KeyCode::Backspace => Some(Message::DeleteDescriptionChar),
                 KeyCode::Char(c) => Some(Message::UpdateDescriptionInput(c)),
                 _ => None,
             };
         }
+        InputMode::EditingStartTime => {
+            return match key.code {
+                KeyCode::Enter => Some(Message::StartTimer),
+                KeyCode::Esc => Some(Message::ExitInputMode),
+                KeyCode::Tab => Some(Message::EnterInputMode(InputMode::EditingProject)),
+                KeyCode::Backspace => Some(Message::DeleteStartTimeChar),
+                KeyCode::Char(c) => Some(Message::UpdateStartTimeInput(c)),
+                _ => None,
+            };
+        }
         InputMode::EditEntryProject
         | InputMode::EditEntryDescription
         | InputMode::EditEntryStart
-        | InputMode::EditEntryEnd => {
+        | InputMode::EditEntryEnd
+        | InputMode::EditEntryLocation => {
             return match key.code {
                 KeyCode::Enter => Some(Message::SaveEditEntry),
                 KeyCode::Esc => Some(Message::CancelEditEntry),
@@ -126,6 +192,45 @@ pub fn handle_key(key: KeyEvent, app: &App) -> Option<Message> {
                 _ => None,
             };
         }
+        InputMode::EditingEmailSettings => {
+            return match key.code {
+                KeyCode::Enter => Some(Message::SaveEmailSettings),
+                KeyCode::Esc => Some(Message::CancelEditEmailSettings),
+                KeyCode::Tab => Some(Message::EmailNextField),
+                KeyCode::BackTab => Some(Message::EmailPrevField),
+                KeyCode::Backspace => Some(Message::EmailFieldBackspace),
+                KeyCode::Char(c) => Some(Message::EmailFieldInput(c)),
+                _ => None,
+            };
+        }
+        InputMode::RenamingTag => {
+            return match key.code {
+                KeyCode::Enter => Some(Message::SaveTagRename),
+                KeyCode::Esc => Some(Message::CancelTagRename),
+                KeyCode::Backspace => Some(Message::TagRenameBackspace),
+                KeyCode::Char(c) => Some(Message::TagRenameInput(c)),
+                _ => None,
+            };
+        }
+        InputMode::EditingReview => {
+            return match key.code {
+                KeyCode::Enter => Some(Message::SaveReviewInput),
+                KeyCode::Esc => Some(Message::CancelReviewInput),
+                KeyCode::Backspace => Some(Message::ReviewBackspace),
+                KeyCode::Char(c) => Some(Message::ReviewInput(c)),
+                _ => None,
+            };
+        }
+        InputMode::EditingProfile => {
+            return match key.code {
+                KeyCode::Enter => Some(Message::SaveProfile),
+                KeyCode::Esc => Some(Message::CancelEditProfile),
+                KeyCode::Tab | KeyCode::BackTab => Some(Message::ProfileNextField),
+                KeyCode::Backspace => Some(Message::ProfileFieldBackspace),
+                KeyCode::Char(c) => Some(Message::ProfileFieldInput(c)),
+                _ => None,
+            };
+        }
         InputMode::Normal => {}
     }
 
@@ -139,6 +244,8 @@ pub fn handle_key(key: KeyEvent, app: &App) -> Option<Message> {
         KeyCode::Char('5') => return Some(Message::SwitchScreen(Screen::Pomodoro)),
         KeyCode::Char('6') => return Some(Message::SwitchScreen(Screen::Clients)),
         KeyCode::Char('7') => return Some(Message::SwitchScreen(Screen::Settings)),
+        KeyCode::Char('8') => return Some(Message::SwitchScreen(Screen::Tags)),
+        KeyCode::Char('9') => return Some(Message::SwitchScreen(Screen::Review)),
         _ => {}
     }
 
@@ -151,6 +258,8 @@ pub fn handle_key(key: KeyEvent, app: &App) -> Option<Message> {
         Screen::Pomodoro => handle_pomodoro_keys(key, app),
         Screen::Clients => handle_clients_keys(key, app),
         Screen::Settings => handle_settings_keys(key, app),
+        Screen::Tags => handle_tags_keys(key, app),
+        Screen::Review => handle_review_keys(key),
     }
 }
 
@@ -193,6 +302,12 @@ fn handle_timer_keys(key: KeyEvent, app: &App) -> Option<Message> {
             // Toggle Pomodoro mode
             Some(Message::TogglePomodoroMode)
         }
+        KeyCode::Char('e') | KeyCode::Char('E') if app.active_entry.is_some() => {
+            Some(Message::AmendActiveEntry)
+        }
+        KeyCode::Char('x') | KeyCode::Char('X') if app.active_entry.is_some() => {
+            Some(Message::CancelTimer)
+        }
         KeyCode::Enter => {
             if app.active_entry.is_none() {
                 Some(Message::EnterInputMode(InputMode::EditingProject))
@@ -212,9 +327,31 @@ fn handle_timer_keys(key: KeyEvent, app: &App) -> Option<Message> {
 }
 
 fn handle_entries_keys(key: KeyEvent, app: &App) -> Option<Message> {
+    // The project picker takes the keys while it is open
+    if app.show_project_picker {
+        return match key.code {
+            KeyCode::Char('j') | KeyCode::Down => Some(Message::ProjectPickerNext),
+            KeyCode::Char('k') | KeyCode::Up => Some(Message::ProjectPickerPrevious),
+            KeyCode::Enter => Some(Message::ApplyProjectPicker),
+            KeyCode::Char('p') | KeyCode::Esc => Some(Message::CloseProjectPicker),
+            _ => None,
+        };
+    }
+    if app.show_template_picker {
+        return match key.code {
+            KeyCode::Char('j') | KeyCode::Down => Some(Message::TemplatePickerNext),
+            KeyCode::Char('k') | KeyCode::Up => Some(Message::TemplatePickerPrevious),
+            KeyCode::Enter => Some(Message::ApplyTemplatePicker),
+            KeyCode::Char('t') | KeyCode::Esc => Some(Message::CloseTemplatePicker),
+            _ => None,
+        };
+    }
+
     match key.code {
         KeyCode::Char('j') | KeyCode::Down => Some(Message::SelectNextEntry),
         KeyCode::Char('k') | KeyCode::Up => Some(Message::SelectPreviousEntry),
+        KeyCode::Char('a') | KeyCode::Char('A') => Some(Message::NewEntry),
+        KeyCode::Char('t') | KeyCode::Char('T') => Some(Message::OpenTemplatePicker),
         KeyCode::Char('e') | KeyCode::Char('E') => {
             if let Some(entry) = app.get_selected_entry() {
                 Some(Message::EditEntry(entry.id))
@@ -229,29 +366,40 @@ fn handle_entries_keys(key: KeyEvent, app: &App) -> Option<Message> {
                 None
             }
         }
-        KeyCode::Char('b') | KeyCode::Char('B') => {
-            if let Some(entry) = app.get_selected_entry() {
-                if !entry.billed {
-                    Some(Message::MarkEntryBilled(entry.id))
-                } else {
-                    None
-                }
-            } else {
-                None
-            }
-        }
-        KeyCode::Char('u') => {
-            if let Some(entry) = app.get_selected_entry() {
-                if entry.billed {
-                    Some(Message::UnbillEntry(entry.id))
-                } else {
-                    None
-                }
-            } else {
-                None
-            }
-        }
-        KeyCode::Char('f') | KeyCode::Char('F') => Some(Message::ToggleBilledFilter),
+        KeyCode::Char('b') | KeyCode::Char('B') => app
+            .get_selected_entry()
+            .map(|entry| Message::SetEntryStatus(entry.id, EntryStatus::Billed)),
+        KeyCode::Char('u') => app
+            .get_selected_entry()
+            .map(|entry| Message::SetEntryStatus(entry.id, EntryStatus::Open)),
+        KeyCode::Char('s') | KeyCode::Char('S') => app
+            .get_selected_entry()
+            .map(|entry| Message::SetEntryStatus(entry.id, EntryStatus::Submitted)),
+        KeyCode::Char('i') | KeyCode::Char('I') => app
+            .get_selected_entry()
+            .map(|entry| Message::SetEntryStatus(entry.id, EntryStatus::Invoiced)),
+        KeyCode::Char('P') => app
+            .get_selected_entry()
+            .map(|entry| Message::SetEntryStatus(entry.id, EntryStatus::Paid)),
+        KeyCode::Char('p') => Some(Message::OpenProjectPicker),
+        // Quick fixes for a timer stopped late or early
+        KeyCode::Char('-') => app
+            .get_selected_entry()
+            .map(|entry| Message::AdjustEntryEnd(entry.id, -5)),
+        KeyCode::Char('_') => app
+            .get_selected_entry()
+            .map(|entry| Message::AdjustEntryEnd(entry.id, -15)),
+        KeyCode::Char('+') | KeyCode::Char('=') => app
+            .get_selected_entry()
+            .map(|entry| Message::AdjustEntryEnd(entry.id, 5)),
+        KeyCode::Char('f') | KeyCode::Char('F') => Some(Message::CycleStatusFilter),
+        KeyCode::Char('v') | KeyCode::Char('V') => Some(Message::ToggleFlaggedOnly),
+        KeyCode::Char('r') | KeyCode::Char('R') => app
+            .get_selected_entry()
+            .map(|entry| Message::ToggleReviewFlag(entry.id)),
+        KeyCode::Char('w') | KeyCode::Char('W') => Some(Message::CycleEntriesPeriod),
+        KeyCode::Char('[') => Some(Message::PrevEntriesPage),
+        KeyCode::Char(']') => Some(Message::NextEntriesPage),
         KeyCode::Char('g') => Some(Message::SelectPreviousEntry), // go to top (simplified)
         KeyCode::Char('G') => Some(Message::SelectNextEntry),     // go to bottom (simplified)
         _ => None,
@@ -259,6 +407,19 @@ fn handle_entries_keys(key: KeyEvent, app: &App) -> Option<Message> {
 }
 
 fn handle_invoice_keys(key: KeyEvent, app: &App) -> Option<Message> {
+    // The attachments panel takes the keys while it is open
+    if app.show_attachments {
+        return match key.code {
+            KeyCode::Char('j') | KeyCode::Down => Some(Message::NextAttachment),
+            KeyCode::Char('k') | KeyCode::Up => Some(Message::PrevAttachment),
+            KeyCode::Enter => Some(Message::OpenAttachment),
+            KeyCode::Char('f') | KeyCode::Char('F') | KeyCode::Esc => {
+                Some(Message::ToggleAttachments)
+            }
+            _ => None,
+        };
+    }
+
     // 'c' cycles through clients regardless of mode
     if key.code == KeyCode::Char('c') || key.code == KeyCode::Char('C') {
         return Some(Message::CycleInvoiceClient);
@@ -269,13 +430,15 @@ fn handle_invoice_keys(key: KeyEvent, app: &App) -> Option<Message> {
         match key.code {
             KeyCode::Char('j') | KeyCode::Down => Some(Message::NextInvoiceEntry),
             KeyCode::Char('k') | KeyCode::Up => Some(Message::PrevInvoiceEntry),
-            KeyCode::Char(' ') => {
-                if let Some(entry) = app.invoice_entries.get(app.invoice_select_index) {
-                    Some(Message::ToggleEntrySelection(entry.id))
-                } else {
-                    None
-                }
-            }
+            KeyCode::Char(' ') => app
+                .visible_invoice_entries()
+                .get(app.invoice_select_index)
+                .map(|entry| Message::ToggleEntrySelection(entry.id)),
+            KeyCode::Char('p') | KeyCode::Char('P') => Some(Message::CycleInvoiceProjectFilter),
+            KeyCode::Char('m') | KeyCode::Char('M') => Some(Message::CycleInvoiceDateFilter),
+            KeyCode::Char('t') | KeyCode::Char('T') => Some(Message::CycleInvoiceTagFilter),
+            KeyCode::Char('a') | KeyCode::Char('A') => Some(Message::SelectAllInvoiceEntries),
+            KeyCode::Char('n') | KeyCode::Char('N') => Some(Message::SelectNoInvoiceEntries),
             KeyCode::Enter => Some(Message::GenerateInvoice),
             KeyCode::Esc => Some(Message::ExitInputMode),
             _ => None,
@@ -292,6 +455,8 @@ fn handle_invoice_keys(key: KeyEvent, app: &App) -> Option<Message> {
                     Some(Message::GenerateInvoice)
                 }
             }
+            KeyCode::Char('r') | KeyCode::Char('R') => Some(Message::RegenerateMissingInvoices),
+            KeyCode::Char('f') | KeyCode::Char('F') => Some(Message::ToggleAttachments),
             _ => None,
         }
     }
@@ -315,6 +480,10 @@ fn handle_projects_keys(key: KeyEvent, app: &App) -> Option<Message> {
                 None
             }
         }
+        KeyCode::Char('l') | KeyCode::Char('L') => app
+            .projects
+            .get(app.selected_project_index)
+            .map(|project| Message::CycleProjectClient(project.id)),
         _ => None,
     }
 }
@@ -358,13 +527,58 @@ fn handle_clients_keys(key: KeyEvent, app: &App) -> Option<Message> {
                 None
             }
         }
+        KeyCode::Char('x') | KeyCode::Char('X') => app
+            .get_selected_client()
+            .map(|client| Message::ToggleClientArchived(client.id)),
         _ => None,
     }
 }
 
-fn handle_settings_keys(key: KeyEvent, _app: &App) -> Option<Message> {
+fn handle_settings_keys(key: KeyEvent, app: &App) -> Option<Message> {
+    let selected = app.shown_profile_id();
     match key.code {
         KeyCode::Char('e') | KeyCode::Char('E') | KeyCode::Enter => Some(Message::EditSettings),
+        KeyCode::Char('m') => Some(Message::EditEmailSettings),
+        KeyCode::Char('t') => Some(Message::SendTestEmail),
+        KeyCode::Char('j') | KeyCode::Down => Some(Message::SelectNextProfile),
+        KeyCode::Char('k') | KeyCode::Up => Some(Message::SelectPreviousProfile),
+        KeyCode::Char('a') => Some(Message::AddProfile),
+        KeyCode::Char('c') => Some(Message::DuplicateProfile(selected)),
+        KeyCode::Char('r') => Some(Message::RenameProfile(selected)),
+        KeyCode::Char('d') => Some(Message::DeleteProfile(selected)),
+        _ => None,
+    }
+}
+
+fn handle_tags_keys(key: KeyEvent, app: &App) -> Option<Message> {
+    let selected = app
+        .tags
+        .get(app.selected_tag_index)
+        .map(|(tag, _)| tag.clone());
+    match key.code {
+        KeyCode::Char('j') | KeyCode::Down => Some(Message::SelectNextTag),
+        KeyCode::Char('k') | KeyCode::Up => Some(Message::SelectPreviousTag),
+        KeyCode::Char('r') | KeyCode::Char('R') | KeyCode::Enter => {
+            selected.map(Message::RenameTag)
+        }
+        KeyCode::Char('c') | KeyCode::Char('C') => selected.map(Message::CycleTagColor),
+        KeyCode::Char('d') | KeyCode::Char('D') => selected.map(Message::DeleteTag),
+        _ => None,
+    }
+}
+
+fn handle_review_keys(key: KeyEvent) -> Option<Message> {
+    match key.code {
+        KeyCode::Char('n') | KeyCode::Char(' ') | KeyCode::Right | KeyCode::Enter => {
+            Some(Message::ReviewNext)
+        }
+        KeyCode::Char('p') | KeyCode::Left => Some(Message::ReviewPrevious),
+        KeyCode::Char('e') | KeyCode::Char('E') => Some(Message::ReviewEditDescription),
+        KeyCode::Char('t') | KeyCode::Char('T') => Some(Message::ReviewAddTag),
+        KeyCode::Char('l') | KeyCode::Char('L') => Some(Message::ReviewCycleClient),
+        KeyCode::Char('b') | KeyCode::Char('B') => Some(Message::ReviewCycleStatus),
+        KeyCode::Char('c') | KeyCode::Char('C') => Some(Message::ReviewMarkChecked),
+        KeyCode::Char('r') | KeyCode::Char('R') => Some(Message::RefreshReview),
         _ => None,
     }
 }
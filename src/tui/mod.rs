@@ -13,11 +13,14 @@ use crossterm::{
 };
 use ratatui::{Terminal, backend::CrosstermBackend};
 
-use crate::db::Db;
-use app::{App, Message, RunningState};
+use crate::db::{self, Db};
+use app::{App, Message, RunningState, Screen};
+
+/// Main entry point for TUI mode, opening on `screen`
+pub fn run_tui(db: Db, screen: Screen) -> io::Result<()> {
+    // Waiting on a busy database is reported in the status bar instead
+    db::set_busy_notices(false);
 
-/// Main entry point for TUI mode
-pub fn run_tui(db: Db) -> io::Result<()> {
     // Setup terminal
     enable_raw_mode()?;
     let mut stdout = io::stdout();
@@ -27,6 +30,9 @@ pub fn run_tui(db: Db) -> io::Result<()> {
 
     // Create app state
     let mut app = App::new(&db);
+    if screen != Screen::Timer {
+        app.update(Message::SwitchScreen(screen), &db);
+    }
 
     // Main loop
     let result = run_app(&mut terminal, &mut app, &db);
@@ -68,6 +74,14 @@ fn run_app(
             app.update(Message::Tick, db);
         }
 
+        // The screen froze while another process held the database
+        if db::take_busy_wait() {
+            app.status_message = Some(match app.status_message.take() {
+                Some(message) => format!("{} (database was busy, retried)", message),
+                None => "Database was busy, retried".to_string(),
+            });
+        }
+
         // Check if we should quit
         if app.running_state == RunningState::Done {
             return Ok(());
@@ -1,3 +1,4 @@
+use chrono::Utc;
 use ratatui::{
     Frame,
     layout::{Constraint, Direction, Layout, Rect},
@@ -6,36 +7,48 @@ use ratatui::{
     widgets::{Block, Borders, Clear, Paragraph, Wrap},
 };
 
-use super::app::{App, EditField, InputMode, PomodoroState, Screen};
+use super::app::{App, EditField, InputMode, InvoiceMode, OnboardingStep, PomodoroState, Screen};
 use super::views::{
-    draw_clients, draw_entries, draw_invoice, draw_pomodoro, draw_projects, draw_settings,
-    draw_timer,
+    draw_clients, draw_entries, draw_invoice, draw_pomodoro, draw_projects, draw_review,
+    draw_settings, draw_tags, draw_timer,
 };
+use crate::models::format_short_duration;
 
 /// Main draw function that delegates to screen-specific views
 pub fn draw(frame: &mut Frame, app: &App) {
+    let banner_height = if app.invoice_reminders.is_empty() {
+        0
+    } else {
+        1
+    };
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
-            Constraint::Length(3), // Header
-            Constraint::Min(10),   // Main content
-            Constraint::Length(3), // Status/help bar
+            Constraint::Length(3),             // Header
+            Constraint::Length(banner_height), // Month-end invoice reminder
+            Constraint::Min(10),               // Main content
+            Constraint::Length(3),             // Status/help bar
         ])
         .split(frame.area());
 
     draw_header(frame, app, chunks[0]);
+    if banner_height > 0 {
+        draw_reminder_banner(frame, app, chunks[1]);
+    }
 
     match app.current_screen {
-        Screen::Timer => draw_timer(frame, app, chunks[1]),
-        Screen::Entries => draw_entries(frame, app, chunks[1]),
-        Screen::Invoice => draw_invoice(frame, app, chunks[1]),
-        Screen::Projects => draw_projects(frame, app, chunks[1]),
-        Screen::Pomodoro => draw_pomodoro(frame, app, chunks[1]),
-        Screen::Clients => draw_clients(frame, app, chunks[1]),
-        Screen::Settings => draw_settings(frame, app, chunks[1]),
+        Screen::Timer => draw_timer(frame, app, chunks[2]),
+        Screen::Entries => draw_entries(frame, app, chunks[2]),
+        Screen::Invoice => draw_invoice(frame, app, chunks[2]),
+        Screen::Projects => draw_projects(frame, app, chunks[2]),
+        Screen::Pomodoro => draw_pomodoro(frame, app, chunks[2]),
+        Screen::Clients => draw_clients(frame, app, chunks[2]),
+        Screen::Settings => draw_settings(frame, app, chunks[2]),
+        Screen::Tags => draw_tags(frame, app, chunks[2]),
+        Screen::Review => draw_review(frame, app, chunks[2]),
     }
 
-    draw_footer(frame, app, chunks[2]);
+    draw_footer(frame, app, chunks[3]);
 
     // Draw help overlay if active
     if app.show_help {
@@ -47,6 +60,16 @@ pub fn draw(frame: &mut Frame, app: &App) {
         draw_delete_confirm(frame, app);
     }
 
+    // Draw short entry discard prompt if active
+    if app.confirm_discard.is_some() {
+        draw_discard_confirm(frame, app);
+    }
+
+    // Draw the prompt to throw away the running timer if active
+    if app.confirm_cancel_timer {
+        draw_cancel_timer_confirm(frame, app);
+    }
+
     // Draw edit entry dialog if active
     if app.editing_entry.is_some() {
         draw_edit_entry(frame, app);
@@ -56,6 +79,96 @@ pub fn draw(frame: &mut Frame, app: &App) {
     if app.editing_project_rate.is_some() {
         draw_edit_rate(frame, app);
     }
+
+    // Draw first-run wizard over everything else
+    if let Some(step) = app.onboarding {
+        draw_onboarding(frame, app, step);
+    }
+}
+
+fn draw_onboarding(frame: &mut Frame, app: &App, step: OnboardingStep) {
+    let area = centered_rect(60, 50, frame.area());
+
+    let mut text = vec![
+        Line::from(""),
+        Line::from(Span::styled(
+            format!("  {}", step.title()),
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        )),
+        Line::from(""),
+    ];
+
+    match step {
+        OnboardingStep::Welcome => {
+            text.push(Line::from(
+                "  Let's set up your business details, a first client,",
+            ));
+            text.push(Line::from(
+                "  a first project with its rate, and Pomodoro preferences.",
+            ));
+            text.push(Line::from(""));
+            text.push(Line::from(
+                "  Everything can be changed later on the other screens.",
+            ));
+        }
+        OnboardingStep::Done => {
+            text.push(Line::from("  Setup is complete."));
+            text.push(Line::from(""));
+            text.push(Line::from(
+                "  Press [s] on the Timer screen to start tracking time.",
+            ));
+        }
+        _ => {
+            for (i, label) in step.fields().iter().enumerate() {
+                let selected = i == app.onboarding_field;
+                let style = if selected {
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default().fg(Color::White)
+                };
+                let value = app
+                    .onboarding_inputs
+                    .get(i)
+                    .map(String::as_str)
+                    .unwrap_or("");
+                text.push(Line::from(vec![
+                    Span::styled(format!("  {:<20} ", format!("{}:", label)), style),
+                    Span::styled(
+                        format!("{}{}", value, if selected { "_" } else { "" }),
+                        style,
+                    ),
+                ]));
+                text.push(Line::from(""));
+            }
+        }
+    }
+
+    let hint = match step {
+        OnboardingStep::Welcome => "  [Enter] Begin  [Esc] Skip setup",
+        OnboardingStep::Done => "  [Enter] Finish",
+        _ => "  [Tab] Next field  [Enter] Save and continue  [Esc] Skip step",
+    };
+    text.push(Line::from(""));
+    text.push(Line::from(Span::styled(
+        hint,
+        Style::default().fg(Color::DarkGray),
+    )));
+
+    let wizard = Paragraph::new(text)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(" Setup ")
+                .style(Style::default().fg(Color::Cyan)),
+        )
+        .style(Style::default().fg(Color::White));
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(wizard, area);
 }
 
 fn draw_header(frame: &mut Frame, app: &App, area: Rect) {
@@ -130,19 +243,58 @@ fn draw_header(frame: &mut Frame, app: &App, area: Rect) {
         } else {
             Span::styled(" [7] Settings ", Style::default().fg(Color::DarkGray))
         },
+        if app.current_screen == Screen::Tags {
+            Span::styled(
+                " [8] Tags ",
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            )
+        } else {
+            Span::styled(" [8] Tags ", Style::default().fg(Color::DarkGray))
+        },
+        if app.current_screen == Screen::Review {
+            Span::styled(
+                " [9] Review ",
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            )
+        } else {
+            Span::styled(" [9] Review ", Style::default().fg(Color::DarkGray))
+        },
     ];
 
+    let title = if app.read_only {
+        " METER - Time Tracking (read-only) "
+    } else {
+        " METER - Time Tracking "
+    };
     let header = Paragraph::new(Line::from(tabs))
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .title(" METER - Time Tracking "),
-        )
+        .block(Block::default().borders(Borders::ALL).title(title))
         .style(Style::default().fg(Color::White));
 
     frame.render_widget(header, area);
 }
 
+/// One-line banner for the oldest month that still has uninvoiced hours
+fn draw_reminder_banner(frame: &mut Frame, app: &App, area: Rect) {
+    let Some(reminder) = app.invoice_reminders.first() else {
+        return;
+    };
+    let mut text = format!(" {} [3] Invoice", reminder.message());
+    if app.invoice_reminders.len() > 1 {
+        text.push_str(&format!("  (+{} more)", app.invoice_reminders.len() - 1));
+    }
+    let banner = Paragraph::new(text).style(
+        Style::default()
+            .fg(Color::Black)
+            .bg(Color::Yellow)
+            .add_modifier(Modifier::BOLD),
+    );
+    frame.render_widget(banner, area);
+}
+
 fn draw_footer(frame: &mut Frame, app: &App, area: Rect) {
     let help_text = match app.current_screen {
         Screen::Timer => {
@@ -153,7 +305,7 @@ fn draw_footer(frame: &mut Frame, app: &App, area: Rect) {
                 PomodoroState::OnBreak => "[?] Help  [q] Quit",
                 _ => {
                     if app.active_entry.is_some() {
-                        "[s] Stop  [p] Pomodoro  [?] Help  [q] Quit"
+                        "[s] Stop  [e] Amend  [x] Cancel  [p] Pomodoro  [?] Help  [q] Quit"
                     } else {
                         "[s] Start  [p] Pomodoro  [?] Help  [q] Quit"
                     }
@@ -161,15 +313,35 @@ fn draw_footer(frame: &mut Frame, app: &App, area: Rect) {
             }
         }
         Screen::Entries => {
-            "[j/k] Navigate  [e] Edit  [d] Delete  [b] Bill  [u] Unbill  [f] Filter  [?] Help  [q] Quit"
+            "[j/k] Navigate  [a] Add  [t] Template  [e] Edit  [d] Delete  [-/_/+] End -5/-15/+5m  [s/b/i/P] Status  [u] Reopen  [f] Filter  [v] Flagged  [r] Review flag  [p] Project  [w] Day/Week  [[/]] Page  [?] Help  [q] Quit"
+        }
+        Screen::Invoice => {
+            if app.invoice_mode == InvoiceMode::SelectEntries {
+                "[j/k] Move  [Space] Toggle  [a/n] All/None  [p] Project  [m] Dates  [Enter] Generate  [?] Help"
+            } else if app.show_attachments {
+                "[j/k] Move  [Enter] Open attachment  [f/Esc] Close  [?] Help  [q] Quit"
+            } else {
+                "[j/k] Select  [Enter] Generate  [r] Regenerate missing PDFs  [f] Attachments  [?] Help  [q] Quit"
+            }
+        }
+        Screen::Projects => {
+            "[j/k] Navigate  [e] Edit rate  [c] Clear rate  [l] Link client  [?] Help  [q] Quit"
         }
-        Screen::Invoice => "[j/k] Select  [Enter] Generate  [?] Help  [q] Quit",
-        Screen::Projects => "[j/k] Navigate  [e] Edit rate  [c] Clear rate  [?] Help  [q] Quit",
         Screen::Pomodoro => {
             "[Tab] Next field  [Enter] Toggle/Save  [Esc] Cancel  [?] Help  [q] Quit"
         }
-        Screen::Clients => "[j/k] Navigate  [a] Add  [e] Edit  [d] Delete  [?] Help  [q] Quit",
-        Screen::Settings => "[e] Edit  [?] Help  [q] Quit",
+        Screen::Clients => {
+            "[j/k] Navigate  [a] Add  [e] Edit  [x] Archive  [d] Delete  [?] Help  [q] Quit"
+        }
+        Screen::Settings => {
+            "[j/k] Profile  [e] Edit  [a] Add  [c] Copy  [r] Rename  [d] Delete  [m] Email  [t] Test  [?] Help  [q] Quit"
+        }
+        Screen::Tags => {
+            "[j/k] Navigate  [r] Rename/merge  [c] Color  [d] Delete  [?] Help  [q] Quit"
+        }
+        Screen::Review => {
+            "[n/p] Next/Prev  [e] Description  [t] Tag  [l] Client  [b] Status  [c] Reviewed  [r] Restart  [?] Help  [q] Quit"
+        }
     };
 
     let status = if let Some(msg) = &app.status_message {
@@ -216,6 +388,8 @@ fn draw_help_overlay(frame: &mut Frame, _app: &App) {
             Style::default().add_modifier(Modifier::BOLD),
         )),
         Line::from("  s        - Start/Stop timer"),
+        Line::from("  e        - Amend the running entry"),
+        Line::from("  x        - Discard the running timer"),
         Line::from("  p        - Toggle Pomodoro mode"),
         Line::from("  Space    - Acknowledge Pomodoro transition"),
         Line::from("  Tab      - Switch input field"),
@@ -227,11 +401,23 @@ fn draw_help_overlay(frame: &mut Frame, _app: &App) {
             Style::default().add_modifier(Modifier::BOLD),
         )),
         Line::from("  j/k      - Navigate up/down"),
+        Line::from("  a        - Add entry"),
+        Line::from("  t        - Add entry from a template"),
         Line::from("  e        - Edit entry"),
         Line::from("  d        - Delete entry"),
+        Line::from("  - / _    - End 5 / 15 minutes earlier"),
+        Line::from("  +        - End 5 minutes later"),
+        Line::from("  s        - Mark as submitted"),
         Line::from("  b        - Mark as billed"),
-        Line::from("  u        - Unbill entry"),
-        Line::from("  f        - Toggle filter"),
+        Line::from("  i        - Mark as invoiced"),
+        Line::from("  P        - Mark as paid"),
+        Line::from("  u        - Reopen entry"),
+        Line::from("  f        - Cycle status filter"),
+        Line::from("  v        - Show only entries flagged for review"),
+        Line::from("  r        - Flag for review, or mark reviewed"),
+        Line::from("  p        - Pick a project to show"),
+        Line::from("  w        - Cycle all/day/week view"),
+        Line::from("  [ / ]    - Previous/next day or week"),
         Line::from(""),
         Line::from(Span::styled(
             "Invoice Screen",
@@ -240,6 +426,45 @@ fn draw_help_overlay(frame: &mut Frame, _app: &App) {
         Line::from("  j/k      - Select mode"),
         Line::from("  Enter    - Generate invoice"),
         Line::from("  Space    - Toggle entry (select mode)"),
+        Line::from("  a / n    - Select all shown / none (select mode)"),
+        Line::from("  p        - Cycle project filter (select mode)"),
+        Line::from("  m        - Cycle date filter (select mode)"),
+        Line::from("  t        - Cycle tag filter (select mode)"),
+        Line::from("  r        - Regenerate missing invoice PDFs"),
+        Line::from("  f        - Show attachments (Enter opens one)"),
+        Line::from(""),
+        Line::from(Span::styled(
+            "Settings Screen",
+            Style::default().add_modifier(Modifier::BOLD),
+        )),
+        Line::from("  j/k      - Select business profile"),
+        Line::from("  e        - Edit the selected profile's business information"),
+        Line::from("  a        - Add a business profile"),
+        Line::from("  c        - Duplicate the selected profile"),
+        Line::from("  r        - Rename/renumber the selected profile"),
+        Line::from("  d        - Delete the selected profile"),
+        Line::from("  m        - Edit email (SMTP) settings"),
+        Line::from("  t        - Send a test email"),
+        Line::from(""),
+        Line::from(Span::styled(
+            "Tags Screen",
+            Style::default().add_modifier(Modifier::BOLD),
+        )),
+        Line::from("  r        - Rename tag (an existing name merges them)"),
+        Line::from("  c        - Cycle tag color"),
+        Line::from("  d        - Remove tag from every entry"),
+        Line::from(""),
+        Line::from(Span::styled(
+            "Review Screen",
+            Style::default().add_modifier(Modifier::BOLD),
+        )),
+        Line::from("  n/p      - Next/previous entry from last week"),
+        Line::from("  e        - Fix the description"),
+        Line::from("  t        - Add a tag"),
+        Line::from("  l        - Cycle the project's client"),
+        Line::from("  b        - Cycle status: open, submitted, billed"),
+        Line::from("  c        - Mark a flagged entry reviewed"),
+        Line::from("  r        - Start the review over"),
         Line::from(""),
         Line::from(Span::styled(
             "Press any key to close",
@@ -287,10 +512,84 @@ fn draw_delete_confirm(frame: &mut Frame, app: &App) {
     frame.render_widget(confirm, area);
 }
 
+fn draw_discard_confirm(frame: &mut Frame, app: &App) {
+    let area = centered_rect(50, 20, frame.area());
+
+    let (project, secs) = app
+        .confirm_discard
+        .as_ref()
+        .map(|e| {
+            let secs = e.end.map(|end| (end - e.start).num_seconds()).unwrap_or(0);
+            (e.project.as_str(), secs)
+        })
+        .unwrap_or(("", 0));
+    let text = vec![
+        Line::from(""),
+        Line::from(Span::styled(
+            format!("Timer for '{}' ran only {}s", project, secs),
+            Style::default().add_modifier(Modifier::BOLD),
+        )),
+        Line::from(format!(
+            "Shorter than the {}s minimum. Discard it?",
+            app.timer_settings.min_duration_secs
+        )),
+        Line::from(""),
+        Line::from("Press [y] to discard, [n] to keep"),
+    ];
+
+    let confirm = Paragraph::new(text)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(" Short Entry ")
+                .style(Style::default().fg(Color::Yellow)),
+        )
+        .style(Style::default().fg(Color::White));
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(confirm, area);
+}
+
+fn draw_cancel_timer_confirm(frame: &mut Frame, app: &App) {
+    let area = centered_rect(50, 20, frame.area());
+
+    let (project, secs) = app
+        .active_entry
+        .as_ref()
+        .map(|e| (e.project.as_str(), (Utc::now() - e.start).num_seconds()))
+        .unwrap_or(("", 0));
+    let text = vec![
+        Line::from(""),
+        Line::from(Span::styled(
+            format!("Discard the running timer for '{}'?", project),
+            Style::default().add_modifier(Modifier::BOLD),
+        )),
+        Line::from(format!(
+            "Its {} will not be saved.",
+            format_short_duration(secs)
+        )),
+        Line::from(""),
+        Line::from("Press [y] to discard, [n] to keep it running"),
+    ];
+
+    let confirm = Paragraph::new(text)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(" Cancel Timer ")
+                .style(Style::default().fg(Color::Red)),
+        )
+        .style(Style::default().fg(Color::White));
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(confirm, area);
+}
+
 fn draw_edit_entry(frame: &mut Frame, app: &App) {
-    let area = centered_rect(60, 50, frame.area());
+    let area = centered_rect(60, 55, frame.area());
 
     let entry_id = app.editing_entry.as_ref().map(|e| e.id).unwrap_or(0);
+    let running = entry_id != 0 && app.editing_entry.as_ref().is_some_and(|e| e.end.is_none());
 
     let field_style = |field: EditField| -> Style {
         if app.edit_field == field {
@@ -341,7 +640,25 @@ fn draw_edit_entry(frame: &mut Frame, app: &App) {
                 field_style(EditField::End),
             ),
         ]),
-        Line::from(""),
+        Line::from(Span::styled(
+            if running {
+                "               (leave empty to keep the timer running)"
+            } else {
+                ""
+            },
+            Style::default().fg(Color::DarkGray),
+        )),
+        Line::from(vec![
+            Span::styled("  Location:    ", field_style(EditField::Location)),
+            Span::styled(
+                format!("{}{}", app.edit_location_input, cursor(EditField::Location)),
+                field_style(EditField::Location),
+            ),
+        ]),
+        Line::from(Span::styled(
+            "               (home, client-site, travel or your own)",
+            Style::default().fg(Color::DarkGray),
+        )),
         Line::from(""),
         Line::from(Span::styled(
             "  [Tab] Next field  [Enter] Save  [Esc] Cancel",
@@ -353,7 +670,20 @@ fn draw_edit_entry(frame: &mut Frame, app: &App) {
         .block(
             Block::default()
                 .borders(Borders::ALL)
-                .title(format!(" Edit Entry {} ", entry_id))
+                .title(if entry_id == 0 && !app.new_entry_tags.is_empty() {
+                    let tags: Vec<String> = app
+                        .new_entry_tags
+                        .iter()
+                        .map(|t| format!("#{}", t))
+                        .collect();
+                    format!(" New Entry {} ", tags.join(" "))
+                } else if entry_id == 0 {
+                    " New Entry ".to_string()
+                } else if running {
+                    format!(" Amend Running Entry {} ", entry_id)
+                } else {
+                    format!(" Edit Entry {} ", entry_id)
+                })
                 .style(Style::default().fg(Color::Cyan)),
         )
         .style(Style::default().fg(Color::White));
@@ -19,8 +19,9 @@ pub fn draw_clients(frame: &mut Frame, app: &App, area: Rect) {
         Line::from(""),
         Line::from(Span::styled(
             format!(
-                "  {} clients configured. Press [a] to add, [e] to edit, [d] to delete.",
-                app.clients.len()
+                "  {} clients configured ({} archived). Press [a] to add, [e] to edit, [x] to archive, [d] to delete.",
+                app.clients.len(),
+                app.clients.iter().filter(|c| c.archived).count()
             ),
             Style::default().fg(Color::DarkGray),
         )),
@@ -53,26 +54,40 @@ pub fn draw_clients(frame: &mut Frame, app: &App, area: Rect) {
             .block(Block::default().borders(Borders::ALL).title(" Clients "));
         frame.render_widget(empty, chunks[1]);
     } else {
-        let header_cells = ["ID", "Name", "Contact", "Email", "City"].iter().map(|h| {
-            Cell::from(*h).style(
-                Style::default()
-                    .fg(Color::Yellow)
-                    .add_modifier(Modifier::BOLD),
-            )
-        });
+        let header_cells = ["ID", "Name", "Contact", "Email", "City", "Status"]
+            .iter()
+            .map(|h| {
+                Cell::from(*h).style(
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD),
+                )
+            });
 
         let header = Row::new(header_cells).height(1).bottom_margin(1);
 
         let rows = app.clients.iter().enumerate().map(|(i, client)| {
+            let status = if client.archived {
+                Span::styled("archived", Style::default().fg(Color::DarkGray))
+            } else {
+                Span::styled("active", Style::default().fg(Color::Green))
+            };
+
             let cells = vec![
                 Cell::from(client.id.to_string()),
                 Cell::from(client.name.clone()),
                 Cell::from(client.contact_person.clone()),
                 Cell::from(client.email.clone()),
                 Cell::from(client.address_city.clone()),
+                Cell::from(status),
             ];
 
             let row = Row::new(cells);
+            let row = if client.archived {
+                row.style(Style::default().fg(Color::DarkGray))
+            } else {
+                row
+            };
             if i == app.selected_client_index {
                 row.style(
                     Style::default()
@@ -88,8 +103,9 @@ pub fn draw_clients(frame: &mut Frame, app: &App, area: Rect) {
             Constraint::Length(5),
             Constraint::Percentage(25),
             Constraint::Percentage(20),
-            Constraint::Percentage(30),
+            Constraint::Percentage(25),
             Constraint::Percentage(15),
+            Constraint::Length(10),
         ];
 
         let table = Table::new(rows, widths)
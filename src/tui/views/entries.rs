@@ -1,19 +1,42 @@
-use chrono::{Local, TimeZone};
+use chrono::{Local, NaiveDate, TimeZone, Utc};
 use ratatui::{
     Frame,
-    layout::{Constraint, Rect},
+    layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
-    text::Span,
-    widgets::{Block, Borders, Cell, Row, Table},
+    text::{Line, Span},
+    widgets::{Block, Borders, Cell, Clear, Paragraph, Row, Table},
 };
 
-use crate::tui::app::App;
+use crate::models::{Entry, EntryStatus, format_days};
+use crate::tui::app::{App, EntriesPeriod};
 
 pub fn draw_entries(frame: &mut Frame, app: &App, area: Rect) {
-    let filter_text = if app.show_only_unbilled {
-        "Filter: Unbilled"
-    } else {
-        "Filter: All"
+    let mut filter_text = match app.status_filter {
+        Some(status) => format!("Filter: {}", status),
+        None => "Filter: All".to_string(),
+    };
+    if let Some(project) = &app.entries_project_filter {
+        filter_text.push_str(&format!(" | Project: {}", project));
+    }
+    if app.flagged_only {
+        filter_text.push_str(" | Needs review");
+    }
+    let period_text = match app.entries_range() {
+        Some((from, _)) => {
+            let label = if app.entries_period == EntriesPeriod::Week {
+                format!("Week of {}", from.format("%a %m/%d/%Y"))
+            } else {
+                from.format("%a %m/%d/%Y").to_string()
+            };
+            let total: f64 = app.entries.iter().map(entry_hours).sum();
+            let off = days_off(app).len();
+            match off {
+                0 => format!(" | {} | {:.2}h", label, total),
+                1 => format!(" | {} | {:.2}h | 1 day off", label, total),
+                n => format!(" | {} | {:.2}h | {} days off", label, total, n),
+            }
+        }
+        None => String::new(),
     };
 
     let header_cells = [
@@ -36,11 +59,36 @@ pub fn draw_entries(frame: &mut Frame, app: &App, area: Rect) {
 
     let header = Row::new(header_cells).height(1).bottom_margin(1);
 
-    let rows = app.entries.iter().enumerate().map(|(i, entry)| {
+    // In day/week view, entries are grouped under a header row per day with its total.
+    // Days off get a header row even when nothing was logged on them.
+    let grouped = app.entries_period != EntriesPeriod::All;
+    let mut off_days = if grouped { days_off(app) } else { Vec::new() };
+    let mut rows = Vec::new();
+    let mut current_day = None;
+    for (i, entry) in app.entries.iter().enumerate() {
         let start_local = Local.from_utc_datetime(&entry.start.naive_utc());
+        let day = start_local.date_naive();
+        if grouped && current_day != Some(day) {
+            // Entries are newest first, so emit later days off before this day
+            while let Some(&off) = off_days.last() {
+                if off <= day {
+                    break;
+                }
+                off_days.pop();
+                rows.push(day_header_row(app, off));
+            }
+            off_days.retain(|d| *d != day);
+            current_day = Some(day);
+            rows.push(day_header_row(app, day));
+        }
+
         let start_str = start_local.format("%m/%d %H:%M").to_string();
 
         let (end_str, duration) = match entry.end {
+            Some(_) if entry.days.is_some() => (
+                "--".to_string(),
+                format!("{}d", format_days(entry.days.unwrap_or_default())),
+            ),
             Some(end) => {
                 let end_local = Local.from_utc_datetime(&end.naive_utc());
                 let hrs = (end - entry.start).num_seconds() as f64 / 3600.0;
@@ -54,24 +102,27 @@ pub fn draw_entries(frame: &mut Frame, app: &App, area: Rect) {
 
         let status = if entry.end.is_none() {
             "active"
-        } else if entry.billed {
-            "billed"
         } else {
-            "pending"
+            entry.status.as_str()
         };
 
         let status_style = if entry.end.is_none() {
             Style::default().fg(Color::Cyan)
-        } else if entry.billed {
-            Style::default().fg(Color::DarkGray)
         } else {
-            Style::default().fg(Color::Green)
+            Style::default().fg(status_color(entry.status))
+        };
+
+        // Entries flagged for review are marked next to their ID
+        let id_cell = if app.review_flags.contains_key(&entry.id) {
+            Cell::from(format!("{} !", entry.id)).style(Style::default().fg(Color::Yellow))
+        } else {
+            Cell::from(entry.id.to_string())
         };
 
         let cells = vec![
-            Cell::from(entry.id.to_string()),
+            id_cell,
             Cell::from(entry.project.clone()),
-            Cell::from(truncate_string(&entry.description, 20)),
+            Cell::from(truncate_string(&entry.description_with_issue(), 20)),
             Cell::from(start_str),
             Cell::from(end_str),
             Cell::from(duration),
@@ -79,7 +130,7 @@ pub fn draw_entries(frame: &mut Frame, app: &App, area: Rect) {
         ];
 
         let row = Row::new(cells);
-        if i == app.selected_entry_index {
+        rows.push(if i == app.selected_entry_index {
             row.style(
                 Style::default()
                     .bg(Color::DarkGray)
@@ -87,11 +138,14 @@ pub fn draw_entries(frame: &mut Frame, app: &App, area: Rect) {
             )
         } else {
             row
-        }
-    });
+        });
+    }
+    for off in off_days.into_iter().rev() {
+        rows.push(day_header_row(app, off));
+    }
 
     let widths = [
-        Constraint::Length(6),
+        Constraint::Length(8),
         Constraint::Percentage(15),
         Constraint::Percentage(25),
         Constraint::Length(12),
@@ -105,11 +159,124 @@ pub fn draw_entries(frame: &mut Frame, app: &App, area: Rect) {
         .block(
             Block::default()
                 .borders(Borders::ALL)
-                .title(format!(" Entries ({}) ", filter_text)),
+                .title(format!(" Entries ({}{}) ", filter_text, period_text)),
         )
         .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED));
 
     frame.render_widget(table, area);
+
+    if app.show_project_picker {
+        draw_project_picker(frame, app);
+    }
+    if app.show_template_picker {
+        draw_template_picker(frame, app);
+    }
+}
+
+fn draw_project_picker(frame: &mut Frame, app: &App) {
+    let area = centered_rect(40, 50, frame.area());
+
+    let names = std::iter::once("All projects").chain(app.projects.iter().map(|p| p.name.as_str()));
+    let mut lines = vec![Line::from("")];
+    for (i, name) in names.enumerate() {
+        let style = if i == app.project_picker_index {
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default()
+        };
+        let marker = if i == app.project_picker_index {
+            "> "
+        } else {
+            "  "
+        };
+        lines.push(Line::from(Span::styled(
+            format!("  {}{}", marker, name),
+            style,
+        )));
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        "  [j/k] Move  [Enter] Show  [Esc] Cancel",
+        Style::default().fg(Color::DarkGray),
+    )));
+
+    let dialog = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(" Show Project ")
+            .style(Style::default().fg(Color::Cyan)),
+    );
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(dialog, area);
+}
+
+fn draw_template_picker(frame: &mut Frame, app: &App) {
+    let area = centered_rect(50, 50, frame.area());
+
+    let mut lines = vec![Line::from("")];
+    for (i, template) in app.templates.iter().enumerate() {
+        let style = if i == app.template_picker_index {
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default()
+        };
+        let marker = if i == app.template_picker_index {
+            "> "
+        } else {
+            "  "
+        };
+        lines.push(Line::from(Span::styled(
+            format!(
+                "  {}{:<14} {:<16} {:>5.2} hrs",
+                marker,
+                truncate_string(&template.name, 14),
+                truncate_string(&template.project, 16),
+                template.duration
+            ),
+            style,
+        )));
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        "  [j/k] Move  [Enter] Use  [Esc] Cancel",
+        Style::default().fg(Color::DarkGray),
+    )));
+
+    let dialog = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(" Add From Template ")
+            .style(Style::default().fg(Color::Cyan)),
+    );
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(dialog, area);
+}
+
+/// Helper to create a centered rectangle
+fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
+    let popup_layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Percentage((100 - percent_y) / 2),
+            Constraint::Percentage(percent_y),
+            Constraint::Percentage((100 - percent_y) / 2),
+        ])
+        .split(r);
+
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage((100 - percent_x) / 2),
+            Constraint::Percentage(percent_x),
+            Constraint::Percentage((100 - percent_x) / 2),
+        ])
+        .split(popup_layout[1])[1]
 }
 
 fn truncate_string(s: &str, max_len: usize) -> String {
@@ -119,3 +286,62 @@ fn truncate_string(s: &str, max_len: usize) -> String {
         s.to_string()
     }
 }
+
+/// Color for each billing status, from green (still open) to gray (paid and done).
+fn status_color(status: EntryStatus) -> Color {
+    match status {
+        EntryStatus::Open => Color::Green,
+        EntryStatus::Submitted => Color::Yellow,
+        EntryStatus::Billed => Color::Magenta,
+        EntryStatus::Invoiced => Color::Blue,
+        EntryStatus::Paid => Color::DarkGray,
+    }
+}
+
+/// Hours logged by an entry so far (running entries count up to now)
+fn entry_hours(entry: &Entry) -> f64 {
+    let end = entry.end.unwrap_or_else(Utc::now);
+    (end - entry.start).num_seconds() as f64 / 3600.0
+}
+
+/// Total hours for the entries that started on `day`
+fn day_hours(app: &App, day: NaiveDate) -> f64 {
+    app.entries
+        .iter()
+        .filter(|e| Local.from_utc_datetime(&e.start.naive_utc()).date_naive() == day)
+        .map(entry_hours)
+        .sum()
+}
+
+/// Days off in the shown day/week, earliest first
+fn days_off(app: &App) -> Vec<NaiveDate> {
+    let Some((from, to)) = app.entries_range() else {
+        return Vec::new();
+    };
+    from.iter_days()
+        .take_while(|d| *d < to)
+        .filter(|d| app.day_off(*d).is_some())
+        .collect()
+}
+
+/// Separator row showing a day, its total and why it's a day off
+fn day_header_row(app: &App, day: NaiveDate) -> Row<'static> {
+    let style = Style::default()
+        .fg(Color::Cyan)
+        .add_modifier(Modifier::BOLD);
+    let off = match app.day_off(day) {
+        Some(t) if t.reason.is_empty() => "Off".to_string(),
+        Some(t) => format!("Off ({})", t.reason),
+        None => String::new(),
+    };
+    let hours = day_hours(app, day);
+    Row::new(vec![
+        Cell::from(""),
+        Cell::from(Span::styled(day.format("%a %m/%d").to_string(), style)),
+        Cell::from(Span::styled(off, Style::default().fg(Color::DarkGray))),
+        Cell::from(""),
+        Cell::from(""),
+        Cell::from(Span::styled(format!("{:.2}h", hours), style)),
+        Cell::from(""),
+    ])
+}
@@ -1,4 +1,4 @@
-use chrono::{Datelike, Utc};
+use chrono::{Datelike, Local, Utc};
 use ratatui::{
     Frame,
     layout::{Constraint, Direction, Layout, Rect},
@@ -7,6 +7,8 @@ use ratatui::{
     widgets::{Block, Borders, Cell, Paragraph, Row, Table},
 };
 
+use crate::billing::{self, entry_hours, worked_days};
+use crate::models::{BillingMode, Entry, Money, format_days, format_money};
 use crate::tui::app::{App, InvoiceMode};
 
 pub fn draw_invoice(frame: &mut Frame, app: &App, area: Rect) {
@@ -15,17 +17,32 @@ pub fn draw_invoice(frame: &mut Frame, app: &App, area: Rect) {
         .constraints([Constraint::Percentage(40), Constraint::Percentage(60)])
         .split(area);
 
-    // Left side: mode selection and client selection
+    // Left side: mode selection, client selection and any missing PDFs
+    let missing_height = if app.missing_invoices.is_empty() {
+        0
+    } else {
+        // Up to five invoices, an overflow line, the hint and the borders
+        app.missing_invoices.len().min(5) as u16 + 5
+    };
     let left_chunks = Layout::default()
         .direction(Direction::Vertical)
-        .constraints([Constraint::Min(12), Constraint::Length(8)])
+        .constraints([
+            Constraint::Min(12),
+            Constraint::Length(8),
+            Constraint::Length(missing_height),
+        ])
         .split(chunks[0]);
 
     draw_mode_selection(frame, app, left_chunks[0]);
     draw_client_selection(frame, app, left_chunks[1]);
+    if !app.missing_invoices.is_empty() {
+        draw_missing_invoices(frame, app, left_chunks[2]);
+    }
 
     if app.invoice_mode == InvoiceMode::SelectEntries {
         draw_entry_selection(frame, app, chunks[1]);
+    } else if app.show_attachments {
+        draw_attachments(frame, app, chunks[1]);
     } else {
         draw_preview(frame, app, chunks[1]);
     }
@@ -61,6 +78,40 @@ fn draw_client_selection(frame: &mut Frame, app: &App, area: Rect) {
     frame.render_widget(block, area);
 }
 
+fn draw_missing_invoices(frame: &mut Frame, app: &App, area: Rect) {
+    let mut lines: Vec<Line> = app
+        .missing_invoices
+        .iter()
+        .take(5)
+        .map(|inv| {
+            Line::from(Span::styled(
+                format!("  #{:04} issued {}", inv.invoice_number, inv.date_issued),
+                Style::default().fg(Color::Red),
+            ))
+        })
+        .collect();
+    if app.missing_invoices.len() > 5 {
+        lines.push(Line::from(format!(
+            "  ...and {} more",
+            app.missing_invoices.len() - 5
+        )));
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        "  Press [r] to regenerate",
+        Style::default().fg(Color::DarkGray),
+    )));
+
+    let block = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(" Missing PDFs ")
+            .border_style(Style::default().fg(Color::Red)),
+    );
+
+    frame.render_widget(block, area);
+}
+
 fn draw_mode_selection(frame: &mut Frame, app: &App, area: Rect) {
     let now = Utc::now();
     let current_month = now.format("%B %Y").to_string();
@@ -123,6 +174,10 @@ fn draw_mode_selection(frame: &mut Frame, app: &App, area: Rect) {
             "  [Enter] Generate invoice",
             Style::default().fg(Color::DarkGray),
         )));
+        lines.push(Line::from(Span::styled(
+            "  [f] Attachments",
+            Style::default().fg(Color::DarkGray),
+        )));
     }
 
     let block = Paragraph::new(lines).block(
@@ -136,58 +191,61 @@ fn draw_mode_selection(frame: &mut Frame, app: &App, area: Rect) {
 
 fn draw_preview(frame: &mut Frame, app: &App, area: Rect) {
     let now = Utc::now();
-    let (title, entries) = match app.invoice_mode {
+    let (title, entries, (year, month)) = match app.invoice_mode {
         InvoiceMode::CurrentMonth => {
             let title = format!("Preview: {}", now.format("%B %Y"));
-            // Get entries for current month from app's entries list (billed ones)
+            // Billed entries for the current month
             let entries: Vec<_> = app
-                .entries
+                .invoice_entries
                 .iter()
                 .filter(|e| {
-                    e.billed
-                        && e.end.is_some()
+                    e.end.is_some()
                         && e.end.unwrap().month() == now.month()
                         && e.end.unwrap().year() == now.year()
                 })
                 .collect();
-            (title, entries)
+            (title, entries, (now.year(), now.month()))
         }
         InvoiceMode::PriorMonth => {
             let first_of_current = now.with_day(1).unwrap();
             let last_of_prior = first_of_current - chrono::Duration::days(1);
             let title = format!("Preview: {}", last_of_prior.format("%B %Y"));
             let entries: Vec<_> = app
-                .entries
+                .invoice_entries
                 .iter()
                 .filter(|e| {
-                    e.billed
-                        && e.end.is_some()
+                    e.end.is_some()
                         && e.end.unwrap().month() == last_of_prior.month()
                         && e.end.unwrap().year() == last_of_prior.year()
                 })
                 .collect();
-            (title, entries)
+            (
+                title,
+                entries,
+                (last_of_prior.year(), last_of_prior.month()),
+            )
         }
         InvoiceMode::CustomRange => {
             let title = "Preview: Custom Range".to_string();
             // Would need date picker implementation
-            (title, Vec::new())
+            (title, Vec::new(), (now.year(), now.month()))
         }
         InvoiceMode::SelectEntries => {
             // Shouldn't reach here as we draw entry selection instead
-            ("".to_string(), Vec::new())
+            ("".to_string(), Vec::new(), (now.year(), now.month()))
         }
     };
 
-    // Calculate hours by project
-    let mut project_hours: std::collections::HashMap<String, f64> =
-        std::collections::HashMap::new();
-    for entry in &entries {
-        if let Some(end) = entry.end {
-            let hours = (end - entry.start).num_seconds() as f64 / 3600.0;
-            *project_hours.entry(entry.project.clone()).or_insert(0.0) += hours;
-        }
-    }
+    // Billed as the invoice will bill them, with overtime, expenses and tax
+    let entries: Vec<Entry> = entries
+        .into_iter()
+        .filter(|e| e.end.is_some())
+        .cloned()
+        .collect();
+    let bill = app.preview_bill(&entries, year, month);
+
+    // Group finished entries by project, in the order the invoice will use
+    let project_entries = billing::project_sections(&entries, app.invoice_settings.section_order);
 
     let mut lines = vec![
         Line::from(""),
@@ -198,25 +256,39 @@ fn draw_preview(frame: &mut Frame, app: &App, area: Rect) {
         Line::from(""),
     ];
 
-    if project_hours.is_empty() {
+    if project_entries.is_empty() {
         lines.push(Line::from(Span::styled(
             "  No billed entries found",
             Style::default().fg(Color::DarkGray),
         )));
     } else {
         let mut total_hours = 0.0;
-        let mut total_cost = 0.0;
-        let mut has_rates = false;
 
-        for (project, hours) in &project_hours {
-            if let Some(rate_info) = app.project_rates.get(project) {
-                has_rates = true;
-                let cost = hours * rate_info.rate;
+        for (project, proj_entries) in &project_entries {
+            let hours = billing::total_hours(proj_entries);
+            if let (Some(rate_info), Some(line)) =
+                (app.project_rates.get(project), bill.line(project))
+            {
+                let quantity = match rate_info.billing_mode {
+                    BillingMode::Hourly => format!("{:>6.2} hrs x ", hours),
+                    BillingMode::Daily => {
+                        format!("{:>6} days x ", format_days(worked_days(proj_entries)))
+                    }
+                    BillingMode::Fixed => format!("{:>6.2} hrs, fixed ", hours),
+                };
+                let overtime = if line.overtime.is_empty() {
+                    ""
+                } else {
+                    " incl. overtime"
+                };
                 lines.push(Line::from(format!(
-                    "  {:<15} | {:>6.2} hrs x {}{:.2} = {}{:.2}",
-                    project, hours, rate_info.currency, rate_info.rate, rate_info.currency, cost
+                    "  {:<15} | {}{} = {}{}",
+                    project,
+                    quantity,
+                    format_money(rate_info.rate, &rate_info.currency),
+                    format_money(line.amount, &rate_info.currency),
+                    overtime
                 )));
-                total_cost += cost;
             } else {
                 lines.push(Line::from(format!(
                     "  {:<15} | {:>6.2} hrs",
@@ -225,13 +297,32 @@ fn draw_preview(frame: &mut Frame, app: &App, area: Rect) {
             }
             total_hours += hours;
         }
+        if bill.expenses != Money::ZERO {
+            lines.push(Line::from(format!(
+                "  {:<15} | {}",
+                "Expenses",
+                format_money(bill.expenses, &bill.currency)
+            )));
+        }
         lines.push(Line::from(""));
         lines.push(Line::from(
             "  ==================================================",
         ));
-        if has_rates {
+        let totals = bill.totals;
+        if totals.tax_amount != Money::ZERO {
+            lines.push(Line::from(format!(
+                "  Subtotal: {}  Tax: {}",
+                format_money(totals.subtotal, &bill.currency),
+                format_money(totals.tax_amount, &bill.currency)
+            )));
+        }
+        if totals.total != Money::ZERO || !bill.lines.is_empty() {
             lines.push(Line::from(Span::styled(
-                format!("  Total: {:>6.2} hrs | ${:.2}", total_hours, total_cost),
+                format!(
+                    "  Total: {:>6.2} hrs | {}",
+                    total_hours,
+                    format_money(totals.total, &bill.currency)
+                ),
                 Style::default()
                     .fg(Color::Green)
                     .add_modifier(Modifier::BOLD),
@@ -265,47 +356,45 @@ fn draw_entry_selection(frame: &mut Frame, app: &App, area: Rect) {
 
     let header = Row::new(header_cells).height(1).bottom_margin(1);
 
-    let rows = app.invoice_entries.iter().enumerate().map(|(i, entry)| {
-        let is_selected = app.selected_entry_ids.contains(&entry.id);
-        let checkbox = if is_selected { "[x]" } else { "[ ]" };
-
-        let duration = match entry.end {
-            Some(end) => {
-                let hrs = (end - entry.start).num_seconds() as f64 / 3600.0;
-                format!("{:.2}h", hrs)
+    let rows = app
+        .visible_invoice_entries()
+        .into_iter()
+        .enumerate()
+        .map(|(i, entry)| {
+            let is_selected = app.selected_entry_ids.contains(&entry.id);
+            let checkbox = if is_selected { "[x]" } else { "[ ]" };
+
+            let duration = match entry_hours(entry) {
+                Some(hrs) => format!("{:.2}h", hrs),
+                None => "-".to_string(),
+            };
+
+            let cells = vec![
+                Cell::from(checkbox),
+                Cell::from(entry.id.to_string()),
+                Cell::from(entry.project.clone()),
+                Cell::from(truncate_string(&entry.description, 20)),
+                Cell::from(duration),
+            ];
+
+            let row = Row::new(cells);
+            if i == app.invoice_select_index {
+                row.style(
+                    Style::default()
+                        .bg(Color::DarkGray)
+                        .add_modifier(Modifier::BOLD),
+                )
+            } else {
+                row
             }
-            None => "-".to_string(),
-        };
-
-        let cells = vec![
-            Cell::from(checkbox),
-            Cell::from(entry.id.to_string()),
-            Cell::from(entry.project.clone()),
-            Cell::from(truncate_string(&entry.description, 20)),
-            Cell::from(duration),
-        ];
-
-        let row = Row::new(cells);
-        if i == app.invoice_select_index {
-            row.style(
-                Style::default()
-                    .bg(Color::DarkGray)
-                    .add_modifier(Modifier::BOLD),
-            )
-        } else {
-            row
-        }
-    });
+        });
 
     // Calculate selected total
     let selected_hours: f64 = app
         .invoice_entries
         .iter()
         .filter(|e| app.selected_entry_ids.contains(&e.id))
-        .filter_map(|e| {
-            e.end
-                .map(|end| (end - e.start).num_seconds() as f64 / 3600.0)
-        })
+        .filter_map(entry_hours)
         .sum();
 
     let widths = [
@@ -316,16 +405,101 @@ fn draw_entry_selection(frame: &mut Frame, app: &App, area: Rect) {
         Constraint::Length(10),
     ];
 
+    let amounts = app
+        .selected_invoice_amounts()
+        .iter()
+        .map(|(currency, amount)| format_money(*amount, currency))
+        .collect::<Vec<_>>()
+        .join(" + ");
+    let amount_text = if amounts.is_empty() {
+        String::new()
+    } else {
+        format!(" | {}", amounts)
+    };
+    let project_text = app
+        .invoice_project_filter
+        .as_deref()
+        .unwrap_or("All projects");
+    let tag_text = app
+        .invoice_tag_filter
+        .as_ref()
+        .map(|t| format!(" | #{}", t))
+        .unwrap_or_default();
+
     let table = Table::new(rows, widths).header(header).block(
         Block::default().borders(Borders::ALL).title(format!(
-            " Select Entries (Selected: {:.2} hrs) ",
-            selected_hours
+            " Select Entries ({} | {}{} | Selected: {} entries, {:.2} hrs{}) ",
+            project_text,
+            app.invoice_date_filter.label(),
+            tag_text,
+            app.selected_entry_ids.len(),
+            selected_hours,
+            amount_text
         )),
     );
 
     frame.render_widget(table, area);
 }
 
+fn draw_attachments(frame: &mut Frame, app: &App, area: Rect) {
+    let header_cells = ["ID", "Added", "For", "Name"].iter().map(|h| {
+        Cell::from(*h).style(
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        )
+    });
+    let header = Row::new(header_cells).height(1).bottom_margin(1);
+
+    let rows = app.attachments.iter().enumerate().map(|(i, attachment)| {
+        let name = if attachment.file_missing() {
+            format!("{} (missing)", attachment.name)
+        } else {
+            attachment.name.clone()
+        };
+        let row = Row::new(vec![
+            Cell::from(attachment.id.to_string()),
+            Cell::from(
+                attachment
+                    .added_at
+                    .with_timezone(&Local)
+                    .format("%Y-%m-%d")
+                    .to_string(),
+            ),
+            Cell::from(attachment.owner()),
+            Cell::from(name),
+        ]);
+        if i == app.attachment_index {
+            row.style(
+                Style::default()
+                    .bg(Color::DarkGray)
+                    .add_modifier(Modifier::BOLD),
+            )
+        } else if attachment.file_missing() {
+            row.style(Style::default().fg(Color::Red))
+        } else {
+            row
+        }
+    });
+
+    let widths = [
+        Constraint::Length(5),
+        Constraint::Length(11),
+        Constraint::Length(14),
+        Constraint::Min(10),
+    ];
+    let title = if app.attachments.is_empty() {
+        " Attachments (none - add with `meter attachments add`) ".to_string()
+    } else {
+        " Attachments ([Enter] open, [f] close) ".to_string()
+    };
+    let table = Table::new(rows, widths)
+        .header(header)
+        .block(Block::default().borders(Borders::ALL).title(title));
+
+    frame.render_widget(table, area);
+}
+
 fn truncate_string(s: &str, max_len: usize) -> String {
     if s.len() > max_len {
         format!("{}...", &s[..max_len - 3])
@@ -3,7 +3,9 @@ mod entries;
 mod invoice;
 mod pomodoro;
 mod projects;
+mod review;
 mod settings;
+mod tags;
 mod timer;
 
 pub use clients::draw_clients;
@@ -11,5 +13,7 @@ pub use entries::draw_entries;
 pub use invoice::draw_invoice;
 pub use pomodoro::draw_pomodoro;
 pub use projects::draw_projects;
+pub use review::draw_review;
 pub use settings::draw_settings;
+pub use tags::draw_tags;
 pub use timer::draw_timer;
@@ -1,14 +1,58 @@
+use chrono::Local;
 use ratatui::{
     Frame,
-    layout::{Constraint, Rect},
+    layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     widgets::{Block, Borders, Cell, Row, Table},
 };
 
+use crate::models::format_money;
+use crate::report;
 use crate::tui::app::App;
 
 pub fn draw_projects(frame: &mut Frame, app: &App, area: Rect) {
-    let header_cells = ["ID", "Project Name", "Rate", "Currency"].iter().map(|h| {
+    // Hours roll up by area below the projects once any project has one
+    let areas = if app.projects.iter().any(|p| p.area.is_some()) {
+        let hours: Vec<(String, f64)> = app
+            .projects
+            .iter()
+            .map(|p| {
+                let totals = app.project_totals.get(&p.name);
+                (p.name.clone(), totals.map_or(0.0, |t| t.total_hours))
+            })
+            .collect();
+        report::area_hours(&hours, &app.projects)
+    } else {
+        Vec::new()
+    };
+    let (area, areas_area) = if areas.is_empty() {
+        (area, None)
+    } else {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Min(6),
+                Constraint::Length(areas.len() as u16 + 4),
+            ])
+            .split(area);
+        (chunks[0], Some(chunks[1]))
+    };
+
+    let header_cells = [
+        "ID",
+        "Project Name",
+        "Rate",
+        "Currency",
+        "Cap",
+        "Client",
+        "Area",
+        "Total",
+        "Unbilled",
+        "Amount",
+        "Last Entry",
+    ]
+    .iter()
+    .map(|h| {
         Cell::from(*h).style(
             Style::default()
                 .fg(Color::Yellow)
@@ -19,18 +63,57 @@ pub fn draw_projects(frame: &mut Frame, app: &App, area: Rect) {
     let header = Row::new(header_cells).height(1).bottom_margin(1);
 
     let rows = app.projects.iter().enumerate().map(|(i, project)| {
+        let currency = project.currency.as_deref().unwrap_or("$");
         let rate_str = project
             .rate
-            .map(|r| format!("{:.2}", r))
+            .map(|r| format_money(r, currency))
             .unwrap_or_else(|| "-".to_string());
 
         let currency_str = project.currency.clone().unwrap_or_else(|| "-".to_string());
 
+        let client_str = project
+            .client_id
+            .and_then(|id| app.clients.iter().find(|c| c.id == id))
+            .map(|c| c.name.clone())
+            .unwrap_or_else(|| "-".to_string());
+
+        let cap_cell = match app.cap_usage.get(&project.name) {
+            Some(usage) if usage.is_reached() => {
+                Cell::from(usage.summary()).style(Style::default().fg(Color::Red))
+            }
+            Some(usage) if usage.used >= usage.cap * 0.8 => {
+                Cell::from(usage.summary()).style(Style::default().fg(Color::Yellow))
+            }
+            Some(usage) => Cell::from(usage.summary()),
+            None => Cell::from("-"),
+        };
+
+        let totals = app
+            .project_totals
+            .get(&project.name)
+            .cloned()
+            .unwrap_or_default();
+        let amount_str = totals
+            .unbilled_amount(project)
+            .map(|a| format_money(a, currency))
+            .unwrap_or_else(|| "-".to_string());
+        let last_str = totals
+            .last_entry
+            .map(|d| d.with_timezone(&Local).format("%Y-%m-%d").to_string())
+            .unwrap_or_else(|| "-".to_string());
+
         let cells = vec![
             Cell::from(project.id.to_string()),
             Cell::from(project.name.clone()),
             Cell::from(rate_str),
             Cell::from(currency_str),
+            cap_cell,
+            Cell::from(client_str),
+            Cell::from(project.area.clone().unwrap_or_else(|| "-".to_string())),
+            Cell::from(format!("{:.1}h", totals.total_hours)),
+            Cell::from(format!("{:.1}h", totals.unbilled_hours)),
+            Cell::from(amount_str),
+            Cell::from(last_str),
         ];
 
         let row = Row::new(cells);
@@ -47,16 +130,66 @@ pub fn draw_projects(frame: &mut Frame, app: &App, area: Rect) {
 
     let widths = [
         Constraint::Length(6),
-        Constraint::Percentage(50),
+        Constraint::Percentage(20),
         Constraint::Length(12),
         Constraint::Length(10),
+        Constraint::Length(22),
+        Constraint::Percentage(15),
+        Constraint::Percentage(12),
+        Constraint::Length(9),
+        Constraint::Length(9),
+        Constraint::Length(12),
+        Constraint::Length(11),
+    ];
+
+    let table = Table::new(rows, widths)
+        .header(header)
+        .block(Block::default().borders(Borders::ALL).title(" Projects "));
+
+    frame.render_widget(table, area);
+
+    if let Some(areas_area) = areas_area {
+        draw_areas(frame, &areas, areas_area);
+    }
+}
+
+/// Lifetime hours per area, with each area's projects
+fn draw_areas(frame: &mut Frame, areas: &[report::AreaHours], area: Rect) {
+    let total: f64 = areas.iter().map(|a| a.hours).sum();
+    let header = Row::new(["Area", "Total", "Share", "Projects"].map(|h| {
+        Cell::from(h).style(
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        )
+    }))
+    .height(1)
+    .bottom_margin(1);
+
+    let rows = areas.iter().map(|a| {
+        let projects: Vec<&str> = a.projects.iter().map(|(p, _)| p.as_str()).collect();
+        let name = match &a.area {
+            Some(name) => Cell::from(name.clone()),
+            None => Cell::from("No area").style(Style::default().fg(Color::DarkGray)),
+        };
+        Row::new(vec![
+            name,
+            Cell::from(format!("{:.1}h", a.hours)),
+            Cell::from(format!("{:.0}%", a.hours / total * 100.0)),
+            Cell::from(projects.join(", ")),
+        ])
+    });
+
+    let widths = [
+        Constraint::Percentage(20),
+        Constraint::Length(9),
+        Constraint::Length(7),
+        Constraint::Min(20),
     ];
 
-    let table = Table::new(rows, widths).header(header).block(
-        Block::default()
-            .borders(Borders::ALL)
-            .title(" Projects & Rates "),
-    );
+    let table = Table::new(rows, widths)
+        .header(header)
+        .block(Block::default().borders(Borders::ALL).title(" Areas "));
 
     frame.render_widget(table, area);
 }
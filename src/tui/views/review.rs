@@ -0,0 +1,293 @@
+use chrono::{Duration, Local};
+use ratatui::{
+    Frame,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+};
+
+use crate::models::format_short_duration;
+use crate::tui::app::{App, InputMode, ReviewField, review_anomaly};
+
+pub fn draw_review(frame: &mut Frame, app: &App, area: Rect) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(3), Constraint::Min(10)])
+        .split(area);
+
+    // Header info
+    let week = match app.review_week {
+        Some(monday) => format!(
+            "{} to {}",
+            monday.format("%b %d"),
+            (monday + Duration::days(6)).format("%b %d")
+        ),
+        None => "last week".to_string(),
+    };
+    let progress = if app.review_index < app.review_entries.len() {
+        format!(
+            "  Week of {}: entry {} of {}",
+            week,
+            app.review_index + 1,
+            app.review_entries.len()
+        )
+    } else {
+        format!("  Week of {}: summary", week)
+    };
+
+    let header = Paragraph::new(vec![Line::from(Span::styled(
+        progress,
+        Style::default().fg(Color::DarkGray),
+    ))])
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(" Weekly Review "),
+    );
+    frame.render_widget(header, chunks[0]);
+
+    if app.review_entries.is_empty() {
+        let empty_lines = vec![
+            Line::from(""),
+            Line::from(""),
+            Line::from(Span::styled(
+                "  No entries last week",
+                Style::default().fg(Color::DarkGray),
+            )),
+        ];
+        let empty = Paragraph::new(empty_lines)
+            .block(Block::default().borders(Borders::ALL).title(" Review "));
+        frame.render_widget(empty, chunks[1]);
+    } else if app.review_index < app.review_entries.len() {
+        draw_review_entry(frame, app, chunks[1]);
+    } else {
+        draw_review_summary(frame, app, chunks[1]);
+    }
+
+    // Draw the description/tag prompt if active
+    if app.input_mode == InputMode::EditingReview {
+        draw_review_input(frame, app);
+    }
+}
+
+fn draw_review_entry(frame: &mut Frame, app: &App, area: Rect) {
+    let entry = &app.review_entries[app.review_index];
+    let label = |text: &'static str| {
+        Span::styled(
+            text,
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        )
+    };
+
+    let start = entry.start.with_timezone(&Local);
+    let duration = entry
+        .end
+        .map(|end| format_short_duration((end - entry.start).num_seconds()))
+        .unwrap_or_default();
+    let when = match entry.days {
+        Some(days) => format!("{} ({} days)", start.format("%a %b %d"), days),
+        None => format!(
+            "{} {} - {} ({})",
+            start.format("%a %b %d"),
+            start.format("%H:%M"),
+            entry
+                .end
+                .map(|end| end.with_timezone(&Local).format("%H:%M").to_string())
+                .unwrap_or_default(),
+            duration
+        ),
+    };
+    let tags = app
+        .review_tags
+        .get(&entry.id)
+        .filter(|tags| !tags.is_empty())
+        .map(|tags| tags.join(", "))
+        .unwrap_or_else(|| "(none)".to_string());
+    let client = app
+        .projects
+        .iter()
+        .find(|p| p.name == entry.project)
+        .and_then(|p| p.client_id)
+        .and_then(|id| app.clients.iter().find(|c| c.id == id))
+        .map(|c| c.name.clone())
+        .unwrap_or_else(|| "(none)".to_string());
+
+    let mut lines = vec![
+        Line::from(""),
+        Line::from(vec![label("  When:         "), Span::raw(when)]),
+        Line::from(vec![
+            label("  Project:      "),
+            Span::raw(entry.project.clone()),
+        ]),
+        Line::from(vec![
+            label("  Description:  "),
+            Span::raw(entry.description_with_issue()),
+        ]),
+        Line::from(vec![label("  Tags:         "), Span::raw(tags)]),
+        Line::from(vec![label("  Client:       "), Span::raw(client)]),
+        Line::from(vec![
+            label("  Status:       "),
+            Span::raw(entry.status.to_string()),
+        ]),
+        Line::from(""),
+    ];
+    if let Some(anomaly) = review_anomaly(entry) {
+        lines.push(Line::from(Span::styled(
+            format!("  Check this entry: {}", anomaly),
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+        )));
+    } else if app.review_edited.contains(&entry.id) {
+        lines.push(Line::from(Span::styled(
+            "  Updated",
+            Style::default().fg(Color::Green),
+        )));
+    }
+    if let Some(reasons) = app.review_flags.get(&entry.id) {
+        let reasons: Vec<&str> = reasons.iter().map(|r| r.describe()).collect();
+        lines.push(Line::from(Span::styled(
+            format!("  Flagged for review: {}", reasons.join(", ")),
+            Style::default().fg(Color::Yellow),
+        )));
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        "  [e] Description  [t] Add tag  [l] Client  [b] Status  [c] Reviewed  [n] Next  [p] Previous",
+        Style::default().fg(Color::DarkGray),
+    )));
+
+    let paragraph = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(format!(" Entry {} ", entry.id)),
+    );
+    frame.render_widget(paragraph, area);
+}
+
+fn draw_review_summary(frame: &mut Frame, app: &App, area: Rect) {
+    let total_secs: i64 = app
+        .review_entries
+        .iter()
+        .filter_map(|e| Some((e.end? - e.start).num_seconds()))
+        .sum();
+    let untagged = app
+        .review_entries
+        .iter()
+        .filter(|e| {
+            app.review_tags
+                .get(&e.id)
+                .is_none_or(|tags| tags.is_empty())
+        })
+        .count();
+    let flagged: Vec<_> = app
+        .review_entries
+        .iter()
+        .filter_map(|e| Some((e, review_anomaly(e)?)))
+        .collect();
+
+    let mut lines = vec![
+        Line::from(""),
+        Line::from(format!(
+            "  Reviewed {} entries, {} tracked",
+            app.review_entries.len(),
+            format_short_duration(total_secs)
+        )),
+        Line::from(format!("  Updated {} entries", app.review_edited.len())),
+        Line::from(format!("  {} entries without tags", untagged)),
+        Line::from(format!(
+            "  {} entries still flagged for review",
+            app.review_entries
+                .iter()
+                .filter(|e| app.review_flags.contains_key(&e.id))
+                .count()
+        )),
+        Line::from(""),
+    ];
+    if flagged.is_empty() {
+        lines.push(Line::from(Span::styled(
+            "  Nothing looks off",
+            Style::default().fg(Color::Green),
+        )));
+    } else {
+        lines.push(Line::from(Span::styled(
+            format!("  {} entries to check:", flagged.len()),
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+        )));
+        for (entry, anomaly) in flagged {
+            lines.push(Line::from(format!(
+                "    {} {} '{}': {}",
+                entry.start.with_timezone(&Local).format("%a %b %d"),
+                entry.project,
+                entry.description,
+                anomaly
+            )));
+        }
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        "  [p] Back to the last entry  [r] Start over",
+        Style::default().fg(Color::DarkGray),
+    )));
+
+    let paragraph =
+        Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(" Summary "));
+    frame.render_widget(paragraph, area);
+}
+
+fn draw_review_input(frame: &mut Frame, app: &App) {
+    let area = centered_rect(50, 25, frame.area());
+
+    let (title, prompt) = match app.review_field {
+        ReviewField::Description => (" Edit Description ", "  Description: "),
+        ReviewField::Tag => (" Add Tag ", "  Tag: "),
+    };
+    let style = Style::default()
+        .fg(Color::Yellow)
+        .add_modifier(Modifier::BOLD);
+
+    let text = vec![
+        Line::from(""),
+        Line::from(vec![
+            Span::styled(prompt, style),
+            Span::styled(format!("[{}_]", app.review_input), style),
+        ]),
+        Line::from(""),
+        Line::from(Span::styled(
+            "  [Enter] Save  [Esc] Cancel",
+            Style::default().fg(Color::DarkGray),
+        )),
+    ];
+
+    let dialog = Paragraph::new(text).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(title)
+            .style(Style::default().fg(Color::Cyan)),
+    );
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(dialog, area);
+}
+
+/// Helper to create a centered rectangle
+fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
+    let popup_layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Percentage((100 - percent_y) / 2),
+            Constraint::Percentage(percent_y),
+            Constraint::Percentage((100 - percent_y) / 2),
+        ])
+        .split(r);
+
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage((100 - percent_x) / 2),
+            Constraint::Percentage(percent_x),
+            Constraint::Percentage((100 - percent_x) / 2),
+        ])
+        .split(popup_layout[1])[1]
+}
@@ -6,19 +6,44 @@ use ratatui::{
     widgets::{Block, Borders, Clear, Paragraph},
 };
 
-use crate::tui::app::{App, InputMode, SettingsField};
+use crate::models::DEFAULT_PROFILE_ID;
+use crate::tui::app::{App, EmailField, InputMode, ProfileDialog, ProfileField, SettingsField};
 
 pub fn draw_settings(frame: &mut Frame, app: &App, area: Rect) {
     let chunks = Layout::default()
         .direction(Direction::Vertical)
-        .constraints([Constraint::Length(3), Constraint::Min(10)])
+        .constraints([
+            Constraint::Length(4),
+            Constraint::Min(10),
+            Constraint::Length(8),
+        ])
         .split(area);
 
-    // Header info
+    // Header info: one tab per business profile
+    let mut profile_spans = vec![Span::raw("  ")];
+    for (i, profile) in app.profiles.iter().enumerate() {
+        let label = if profile.id == DEFAULT_PROFILE_ID {
+            format!(" {} (default) ", profile.name)
+        } else {
+            format!(" {} ", profile.name)
+        };
+        profile_spans.push(if i == app.selected_profile_index {
+            Span::styled(
+                label,
+                Style::default()
+                    .bg(Color::DarkGray)
+                    .add_modifier(Modifier::BOLD),
+            )
+        } else {
+            Span::raw(label)
+        });
+        profile_spans.push(Span::raw(" "));
+    }
+
     let header_lines = vec![
-        Line::from(""),
+        Line::from(profile_spans),
         Line::from(Span::styled(
-            "  Invoice settings for your business. Press [e] to edit.",
+            "  [j/k] Profile  [e] Edit  [a] Add  [c] Duplicate  [r] Rename  [d] Delete",
             Style::default().fg(Color::DarkGray),
         )),
     ];
@@ -31,7 +56,12 @@ pub fn draw_settings(frame: &mut Frame, app: &App, area: Rect) {
     frame.render_widget(header, chunks[0]);
 
     // Settings display
-    let settings = &app.invoice_settings;
+    let settings = app.shown_settings();
+    let profile_name = app
+        .profiles
+        .get(app.selected_profile_index)
+        .map(|p| p.name.as_str())
+        .unwrap_or("default");
 
     let settings_lines = vec![
         Line::from(""),
@@ -130,7 +160,123 @@ pub fn draw_settings(frame: &mut Frame, app: &App, area: Rect) {
                     .fg(Color::Yellow)
                     .add_modifier(Modifier::BOLD),
             ),
-            Span::raw(format!("{}%", settings.default_tax_rate)),
+            Span::raw(if settings.tax_inclusive {
+                format!("{}% (included in rates)", settings.default_tax_rate)
+            } else {
+                format!("{}%", settings.default_tax_rate)
+            }),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "  Reminder Day:   ",
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(if settings.reminder_day > 0 {
+                format!(
+                    "{} (meter invoice-settings --reminder-day)",
+                    settings.reminder_day
+                )
+            } else {
+                "off (meter invoice-settings --reminder-day)".to_string()
+            }),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "  Entry Notes:    ",
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(if settings.include_entry_notes {
+                "shown (meter invoice-settings --include-notes)"
+            } else {
+                "hidden (meter invoice-settings --include-notes)"
+            }),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "  Due Dates:      ",
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(if settings.business_day_due_dates {
+                "next business day (meter invoice-settings --business-days)"
+            } else {
+                "any day (meter invoice-settings --business-days)"
+            }),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "  JSON Copy:      ",
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(if settings.json_snapshot {
+                "written next to each PDF (meter invoice-settings --json)"
+            } else {
+                "off (meter invoice-settings --json)"
+            }),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "  Issue Refs:     ",
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(if settings.issue_refs {
+                "shown (meter invoice-settings --issue-refs)"
+            } else {
+                "hidden (meter invoice-settings --issue-refs)"
+            }),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "  Page Layout:    ",
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(format!(
+                "{}, {} mm margins, {} pt text",
+                settings.paper_size, settings.margin_mm, settings.font_size
+            )),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "  Section Order:  ",
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(format!(
+                "{} (meter invoice-settings --section-order)",
+                settings.section_order
+            )),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "  Bank Details:   ",
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::raw({
+                let details = settings.payment_details();
+                if details.is_empty() {
+                    "(not set) (meter invoice-settings --iban ...)".to_string()
+                } else {
+                    details
+                        .iter()
+                        .map(|(label, value)| format!("{} {}", label, value))
+                        .collect::<Vec<_>>()
+                        .join("  ")
+                }
+            }),
         ]),
         Line::from(""),
         Line::from(vec![Span::styled(
@@ -152,14 +298,254 @@ pub fn draw_settings(frame: &mut Frame, app: &App, area: Rect) {
     let settings_paragraph = Paragraph::new(settings_lines).block(
         Block::default()
             .borders(Borders::ALL)
-            .title(" Your Business Information "),
+            .title(format!(" Your Business Information: {} ", profile_name)),
     );
     frame.render_widget(settings_paragraph, chunks[1]);
 
+    draw_email_settings(frame, app, chunks[2]);
+
     // Draw edit dialog if editing
     if app.input_mode == InputMode::EditingSettings {
         draw_settings_edit_dialog(frame, app);
     }
+    if app.input_mode == InputMode::EditingEmailSettings {
+        draw_email_edit_dialog(frame, app);
+    }
+    if app.input_mode == InputMode::EditingProfile {
+        draw_profile_dialog(frame, app);
+    }
+
+    // Draw delete confirmation if active
+    if app.confirm_delete_profile.is_some() {
+        draw_delete_profile_confirm(frame, app);
+    }
+}
+
+fn draw_profile_dialog(frame: &mut Frame, app: &App) {
+    let area = centered_rect(60, 35, frame.area());
+
+    let field_style = |field: ProfileField| -> Style {
+        if app.profile_field == field {
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(Color::White)
+        }
+    };
+    let row = |label: &'static str, value: String, field: ProfileField| -> Line<'static> {
+        let cursor = if app.profile_field == field { "_" } else { "" };
+        Line::from(vec![
+            Span::styled(label, field_style(field.clone())),
+            Span::styled(format!("{}{}", value, cursor), field_style(field)),
+        ])
+    };
+
+    let title = match app.profile_dialog {
+        Some(ProfileDialog::Duplicate(_)) => " Duplicate Profile ",
+        Some(ProfileDialog::Rename(_)) => " Rename Profile ",
+        _ => " Add Profile ",
+    };
+
+    let text = vec![
+        Line::from(""),
+        row(
+            "  Name:                  ",
+            app.profile_name_input.clone(),
+            ProfileField::Name,
+        ),
+        row(
+            "  First invoice number:  ",
+            app.profile_first_number_input.clone(),
+            ProfileField::FirstNumber,
+        ),
+        Line::from(""),
+        Line::from(Span::styled(
+            "  Each profile numbers its invoices from its own first number.",
+            Style::default().fg(Color::DarkGray),
+        )),
+        Line::from(""),
+        Line::from(Span::styled(
+            "  [Tab] Next field  [Enter] Save  [Esc] Cancel",
+            Style::default().fg(Color::DarkGray),
+        )),
+    ];
+
+    let dialog = Paragraph::new(text).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(title)
+            .style(Style::default().fg(Color::Cyan)),
+    );
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(dialog, area);
+}
+
+fn draw_delete_profile_confirm(frame: &mut Frame, app: &App) {
+    let area = centered_rect(40, 20, frame.area());
+
+    let name = app
+        .profiles
+        .iter()
+        .find(|p| Some(p.id) == app.confirm_delete_profile)
+        .map(|p| p.name.as_str())
+        .unwrap_or("");
+
+    let text = vec![
+        Line::from(""),
+        Line::from(Span::styled(
+            format!("Delete business profile '{}'?", name),
+            Style::default().add_modifier(Modifier::BOLD),
+        )),
+        Line::from(""),
+        Line::from("Press [y] to confirm, [n] to cancel"),
+    ];
+
+    let confirm = Paragraph::new(text)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(" Confirm Delete ")
+                .style(Style::default().fg(Color::Red)),
+        )
+        .style(Style::default().fg(Color::White));
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(confirm, area);
+}
+
+fn draw_email_settings(frame: &mut Frame, app: &App, area: Rect) {
+    let email = &app.email_settings;
+    let label = |text: &'static str| {
+        Span::styled(
+            text,
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        )
+    };
+    let or_not_set = |value: &str| {
+        if value.is_empty() {
+            "(not set)".to_string()
+        } else {
+            value.to_string()
+        }
+    };
+
+    let lines = vec![
+        Line::from(""),
+        Line::from(vec![
+            label("  SMTP Server:    "),
+            Span::raw(if email.smtp_host.is_empty() {
+                "(not set)".to_string()
+            } else {
+                format!(
+                    "{}:{} ({})",
+                    email.smtp_host, email.smtp_port, email.security
+                )
+            }),
+        ]),
+        Line::from(vec![
+            label("  Username:       "),
+            Span::raw(or_not_set(&email.smtp_username)),
+            Span::styled(
+                "  (password kept in the OS keychain)",
+                Style::default().fg(Color::DarkGray),
+            ),
+        ]),
+        Line::from(vec![
+            label("  From:           "),
+            Span::raw(or_not_set(&email.from_address)),
+        ]),
+        Line::from(""),
+        Line::from(Span::styled(
+            "  Press [m] to edit, [t] to send a test email to the from address.",
+            Style::default().fg(Color::DarkGray),
+        )),
+    ];
+
+    let paragraph =
+        Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(" Email "));
+    frame.render_widget(paragraph, area);
+}
+
+fn draw_email_edit_dialog(frame: &mut Frame, app: &App) {
+    let area = centered_rect(70, 50, frame.area());
+
+    let field_style = |field: EmailField| -> Style {
+        if app.email_field == field {
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(Color::White)
+        }
+    };
+    let row = |label: &'static str, value: String, field: EmailField| -> Line<'static> {
+        let cursor = if app.email_field == field { "_" } else { "" };
+        Line::from(vec![
+            Span::styled(label, field_style(field.clone())),
+            Span::styled(format!("{}{}", value, cursor), field_style(field)),
+        ])
+    };
+
+    let password = if app.email_password_input.is_empty() && app.email_field != EmailField::Password
+    {
+        "(unchanged)".to_string()
+    } else {
+        "*".repeat(app.email_password_input.chars().count())
+    };
+
+    let text = vec![
+        Line::from(""),
+        row(
+            "  SMTP Host:     ",
+            app.email_host_input.clone(),
+            EmailField::Host,
+        ),
+        row(
+            "  Port:          ",
+            app.email_port_input.clone(),
+            EmailField::Port,
+        ),
+        row(
+            "  Security:      ",
+            app.email_security_input.clone(),
+            EmailField::Security,
+        ),
+        row(
+            "  Username:      ",
+            app.email_username_input.clone(),
+            EmailField::Username,
+        ),
+        row("  Password:      ", password, EmailField::Password),
+        row(
+            "  From Address:  ",
+            app.email_from_input.clone(),
+            EmailField::From,
+        ),
+        Line::from(""),
+        Line::from(Span::styled(
+            "  Security is starttls, tls or none. The password is stored in the OS keychain.",
+            Style::default().fg(Color::DarkGray),
+        )),
+        Line::from(""),
+        Line::from(Span::styled(
+            "  [Tab] Next field  [Shift+Tab] Prev  [Enter] Save  [Esc] Cancel",
+            Style::default().fg(Color::DarkGray),
+        )),
+    ];
+
+    let dialog = Paragraph::new(text).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(" Edit Email Settings ")
+            .style(Style::default().fg(Color::Cyan)),
+    );
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(dialog, area);
 }
 
 fn draw_settings_edit_dialog(frame: &mut Frame, app: &App) {
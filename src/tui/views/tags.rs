@@ -0,0 +1,218 @@
+use ratatui::{
+    Frame,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Cell, Clear, Paragraph, Row, Table},
+};
+
+use crate::tui::app::{App, InputMode};
+
+pub fn draw_tags(frame: &mut Frame, app: &App, area: Rect) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(3), Constraint::Min(10)])
+        .split(area);
+
+    // Header info
+    let header_lines = vec![
+        Line::from(""),
+        Line::from(Span::styled(
+            format!(
+                "  {} tags in use. Press [r] to rename or merge, [c] to change color, [d] to delete.",
+                app.tags.len()
+            ),
+            Style::default().fg(Color::DarkGray),
+        )),
+    ];
+
+    let header = Paragraph::new(header_lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(" Tag Management "),
+    );
+    frame.render_widget(header, chunks[0]);
+
+    // Tags table
+    if app.tags.is_empty() {
+        let empty_lines = vec![
+            Line::from(""),
+            Line::from(""),
+            Line::from(Span::styled(
+                "  No tags yet",
+                Style::default().fg(Color::DarkGray),
+            )),
+            Line::from(""),
+            Line::from(Span::styled(
+                "  Tag entries with `meter tag ID TAG` or `meter start --tag TAG`",
+                Style::default().fg(Color::Cyan),
+            )),
+        ];
+
+        let empty = Paragraph::new(empty_lines)
+            .block(Block::default().borders(Borders::ALL).title(" Tags "));
+        frame.render_widget(empty, chunks[1]);
+    } else {
+        let header_cells = ["Tag", "Entries", "Color"].iter().map(|h| {
+            Cell::from(*h).style(
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            )
+        });
+
+        let header = Row::new(header_cells).height(1).bottom_margin(1);
+
+        let rows = app.tags.iter().enumerate().map(|(i, (tag, count))| {
+            let color = app.tag_colors.get(tag);
+            let tag_cell = match color.map(String::as_str).and_then(tag_color) {
+                Some(color) => Cell::from(tag.clone()).style(Style::default().fg(color)),
+                None => Cell::from(tag.clone()),
+            };
+
+            let cells = vec![
+                tag_cell,
+                Cell::from(count.to_string()),
+                Cell::from(color.cloned().unwrap_or_else(|| "-".to_string())),
+            ];
+
+            let row = Row::new(cells);
+            if i == app.selected_tag_index {
+                row.style(
+                    Style::default()
+                        .bg(Color::DarkGray)
+                        .add_modifier(Modifier::BOLD),
+                )
+            } else {
+                row
+            }
+        });
+
+        let widths = [
+            Constraint::Percentage(50),
+            Constraint::Length(10),
+            Constraint::Length(10),
+        ];
+
+        let table = Table::new(rows, widths)
+            .header(header)
+            .block(Block::default().borders(Borders::ALL).title(" Tags "));
+
+        frame.render_widget(table, chunks[1]);
+    }
+
+    // Draw rename dialog if renaming
+    if app.input_mode == InputMode::RenamingTag {
+        draw_rename_dialog(frame, app);
+    }
+
+    // Draw delete confirmation if active
+    if app.confirm_delete_tag.is_some() {
+        draw_delete_confirm(frame, app);
+    }
+}
+
+fn draw_rename_dialog(frame: &mut Frame, app: &App) {
+    let area = centered_rect(50, 30, frame.area());
+
+    let tag = app.renaming_tag.as_deref().unwrap_or("");
+    let style = Style::default()
+        .fg(Color::Yellow)
+        .add_modifier(Modifier::BOLD);
+
+    let text = vec![
+        Line::from(""),
+        Line::from(vec![
+            Span::styled("  New name: ", style),
+            Span::styled(format!("[{}_]", app.tag_rename_input), style),
+        ]),
+        Line::from(""),
+        Line::from(Span::styled(
+            "  (a tag already in use merges the two)",
+            Style::default().fg(Color::DarkGray),
+        )),
+        Line::from(""),
+        Line::from(Span::styled(
+            "  [Enter] Save  [Esc] Cancel",
+            Style::default().fg(Color::DarkGray),
+        )),
+    ];
+
+    let dialog = Paragraph::new(text).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(format!(" Rename Tag: {} ", tag))
+            .style(Style::default().fg(Color::Cyan)),
+    );
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(dialog, area);
+}
+
+fn draw_delete_confirm(frame: &mut Frame, app: &App) {
+    let area = centered_rect(40, 20, frame.area());
+
+    let tag = app.confirm_delete_tag.as_deref().unwrap_or("");
+    let count = app
+        .tags
+        .iter()
+        .find(|(t, _)| t == tag)
+        .map(|(_, count)| *count)
+        .unwrap_or(0);
+
+    let text = vec![
+        Line::from(""),
+        Line::from(Span::styled(
+            format!("Remove tag '{}' from {} entries?", tag, count),
+            Style::default().add_modifier(Modifier::BOLD),
+        )),
+        Line::from(""),
+        Line::from("Press [y] to confirm, [n] to cancel"),
+    ];
+
+    let confirm = Paragraph::new(text)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(" Confirm Delete ")
+                .style(Style::default().fg(Color::Red)),
+        )
+        .style(Style::default().fg(Color::White));
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(confirm, area);
+}
+
+/// The terminal color for a color name from `TAG_COLORS`
+fn tag_color(name: &str) -> Option<Color> {
+    match name {
+        "red" => Some(Color::Red),
+        "green" => Some(Color::Green),
+        "yellow" => Some(Color::Yellow),
+        "blue" => Some(Color::Blue),
+        "magenta" => Some(Color::Magenta),
+        "cyan" => Some(Color::Cyan),
+        _ => None,
+    }
+}
+
+/// Helper to create a centered rectangle
+fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
+    let popup_layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Percentage((100 - percent_y) / 2),
+            Constraint::Percentage(percent_y),
+            Constraint::Percentage((100 - percent_y) / 2),
+        ])
+        .split(r);
+
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage((100 - percent_x) / 2),
+            Constraint::Percentage(percent_x),
+            Constraint::Percentage((100 - percent_x) / 2),
+        ])
+        .split(popup_layout[1])[1]
+}
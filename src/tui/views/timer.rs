@@ -14,7 +14,7 @@ pub fn draw_timer(frame: &mut Frame, app: &App, area: Rect) {
         .direction(Direction::Vertical)
         .constraints([
             Constraint::Length(3),  // Spacer
-            Constraint::Length(14), // Timer display (increased for Pomodoro info)
+            Constraint::Length(16), // Timer display (increased for Pomodoro info)
             Constraint::Min(0),     // Rest
         ])
         .split(area);
@@ -48,6 +48,30 @@ pub fn draw_timer(frame: &mut Frame, app: &App, area: Rect) {
             }
         }
     }
+
+    if let Some(pace) = &app.target_pace {
+        let color = if pace.is_behind(app.target_margin_hours) {
+            Color::Red
+        } else {
+            Color::Green
+        };
+        let line = Paragraph::new(Line::from(vec![
+            Span::styled("  Monthly target: ", Style::default().fg(Color::DarkGray)),
+            Span::styled(
+                pace.summary(app.target_margin_hours),
+                Style::default().fg(color),
+            ),
+        ]));
+        let row = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Percentage(20),
+                Constraint::Percentage(60),
+                Constraint::Percentage(20),
+            ])
+            .split(chunks[2]);
+        frame.render_widget(line, row[1]);
+    }
 }
 
 fn format_remaining_time(secs: i64) -> String {
@@ -56,6 +80,16 @@ fn format_remaining_time(secs: i64) -> String {
     format!("{:02}:{:02}", mins, s)
 }
 
+/// Format seconds as `HH:MM:SS`
+fn format_countdown(secs: i64) -> String {
+    format!(
+        "{:02}:{:02}:{:02}",
+        secs / 3600,
+        (secs % 3600) / 60,
+        secs % 60
+    )
+}
+
 fn draw_active_timer(frame: &mut Frame, app: &App, entry: &crate::models::Entry, area: Rect) {
     let elapsed = Utc::now() - entry.start;
     let hours = elapsed.num_hours();
@@ -89,6 +123,25 @@ fn draw_active_timer(frame: &mut Frame, app: &App, entry: &crate::models::Entry,
         )]),
     ];
 
+    if let Some(remaining) = app.get_target_remaining_secs() {
+        let (text, color) = if remaining >= 0 {
+            (
+                format!("{} remaining", format_countdown(remaining)),
+                Color::Magenta,
+            )
+        } else {
+            (format!("{} over", format_countdown(-remaining)), Color::Red)
+        };
+        content.push(Line::from(""));
+        content.push(Line::from(vec![
+            Span::styled("  Countdown: ", Style::default().fg(color)),
+            Span::styled(
+                text,
+                Style::default().fg(color).add_modifier(Modifier::BOLD),
+            ),
+        ]));
+    }
+
     // Add Pomodoro info if enabled and in working state
     if app.pomodoro_config.enabled && app.pomodoro_state == PomodoroState::Working {
         if let Some(remaining) = app.get_pomodoro_remaining_secs() {
@@ -276,6 +329,12 @@ fn draw_start_form(frame: &mut Frame, app: &App, area: Rect) {
         ""
     };
 
+    let (start_style, start_cursor) = if app.input_mode == InputMode::EditingStartTime {
+        (Style::default().fg(Color::Yellow), "_")
+    } else {
+        (Style::default().fg(Color::DarkGray), "")
+    };
+
     // Pomodoro status line
     let pomodoro_status = if app.pomodoro_config.enabled {
         Span::styled("[P] Pomodoro: ON", Style::default().fg(Color::Magenta))
@@ -304,6 +363,30 @@ fn draw_start_form(frame: &mut Frame, app: &App, area: Rect) {
                 format!("[{}{}]", &app.description_input, desc_cursor),
                 desc_style,
             ),
+            if app.description_input.is_empty() && app.timer_settings.reuse_last_description {
+                Span::styled(
+                    " (last description for project)",
+                    Style::default().fg(Color::DarkGray),
+                )
+            } else {
+                Span::raw("")
+            },
+        ]),
+        Line::from(""),
+        Line::from(vec![
+            Span::raw("  Started at:  "),
+            Span::styled(
+                format!("[{}{}]", &app.start_time_input, start_cursor),
+                start_style,
+            ),
+            if app.start_time_input.is_empty() {
+                Span::styled(
+                    " (now, or HH:MM if you forgot)",
+                    Style::default().fg(Color::DarkGray),
+                )
+            } else {
+                Span::raw("")
+            },
         ]),
         Line::from(""),
         Line::from(vec![Span::raw("  "), pomodoro_status]),